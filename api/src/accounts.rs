@@ -36,11 +36,14 @@ pub fn get_account(context: Context) -> BoxedFilter<(impl Reply,)> {
         .boxed()
 }
 
-// GET /accounts/<address>
+// GET /accounts/<address>/blob
 pub fn get_account_state_blob(context: Context) -> BoxedFilter<(impl Reply,)> {
     warp::path!("accounts" / AddressParam / "blob")
         .and(warp::get())
         .and(context.filter())
+        .and(warp::query::<Version>())
+        .map(|address, ctx, version: Version| (version.version, address, ctx))
+        .untuple_one()
         .and_then(handle_get_account_state_blob)
         .with(metrics("get_account_state_blob"))
         .boxed()
@@ -81,11 +84,12 @@ async fn handle_get_account(
 }
 
 async fn handle_get_account_state_blob(
+    ledger_version: Option<LedgerVersionParam>,
     address: AddressParam,
     context: Context,
 ) -> Result<impl Reply, Rejection> {
     fail_point("endpoint_get_account")?;
-    Ok(Account::new(None, address, context)?.account_state_blob()?)
+    Ok(Account::new(ledger_version, address, context)?.account_state_blob()?)
 }
 
 async fn handle_get_account_resources(
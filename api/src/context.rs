@@ -10,7 +10,7 @@ use aptos_types::{
     account_state::AccountState,
     account_state_blob::AccountStateBlob,
     chain_id::ChainId,
-    contract_event::ContractEvent,
+    contract_event::{ContractEvent, EventWithProof},
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     transaction::{SignedTransaction, TransactionWithProof},
@@ -250,6 +250,22 @@ impl Context {
             .collect::<Vec<_>>())
     }
 
+    pub fn get_events_with_proofs(
+        &self,
+        event_key: &EventKey,
+        start: u64,
+        limit: u16,
+        ledger_version: u64,
+    ) -> Result<Vec<EventWithProof>> {
+        self.db.get_events_with_proofs(
+            event_key,
+            start,
+            Order::Ascending,
+            limit as u64,
+            Some(ledger_version),
+        )
+    }
+
     pub fn health_check_route(&self) -> BoxedFilter<(impl Reply,)> {
         super::health_check::health_check_route(self.db.clone())
     }
@@ -7,7 +7,7 @@ use crate::{
     failpoint::fail_point,
     metrics::metrics,
     page::Page,
-    param::{AddressParam, EventKeyParam, MoveIdentifierParam, MoveStructTagParam},
+    param::{accept_bcs, AddressParam, EventKeyParam, MoveIdentifierParam, MoveStructTagParam},
 };
 
 use aptos_api_types::{Error, LedgerInfo, Response};
@@ -17,10 +17,12 @@ use aptos_types::event::EventKey;
 use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
 
 // GET /events/<event_key>
+// With `Accept: application/x-bcs`, the BCS encoded events with their proofs are returned.
 pub fn get_events_by_event_key(context: Context) -> BoxedFilter<(impl Reply,)> {
     warp::path!("events" / EventKeyParam)
         .and(warp::get())
         .and(warp::query::<Page>())
+        .and(accept_bcs())
         .and(context.filter())
         .and_then(handle_get_events_by_event_key)
         .with(metrics("get_events_by_event_key"))
@@ -41,10 +43,16 @@ pub fn get_events_by_event_handle(context: Context) -> BoxedFilter<(impl Reply,)
 async fn handle_get_events_by_event_key(
     event_key: EventKeyParam,
     page: Page,
+    bcs: bool,
     context: Context,
 ) -> Result<impl Reply, Rejection> {
     fail_point("endpoint_get_events_by_event_key")?;
-    Ok(Events::new(event_key.parse("event key")?.into(), context)?.list(page)?)
+    let events = Events::new(event_key.parse("event key")?.into(), context)?;
+    if bcs {
+        Ok(events.list_with_proofs(page)?)
+    } else {
+        Ok(events.list(page)?)
+    }
 }
 
 async fn handle_get_events_by_event_handle(
@@ -76,7 +84,7 @@ impl Events {
         })
     }
 
-    pub fn list(self, page: Page) -> Result<impl Reply, Error> {
+    pub fn list(self, page: Page) -> Result<Response, Error> {
         let contract_events = self.context.get_events(
             &self.key,
             page.start(0, u64::MAX)?,
@@ -88,4 +96,14 @@ impl Events {
         let events = converter.try_into_events(&contract_events)?;
         Response::new(self.ledger_info, &events)
     }
+
+    pub fn list_with_proofs(self, page: Page) -> Result<Response, Error> {
+        let events = self.context.get_events_with_proofs(
+            &self.key,
+            page.start(0, u64::MAX)?,
+            page.limit()?,
+            self.ledger_info.version(),
+        )?;
+        Response::new_bcs(self.ledger_info, &events)
+    }
 }
@@ -1,12 +1,13 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use aptos_api_types::{Address, Error, EventKey, MoveStructTag, TransactionId};
+use aptos_api_types::{mime_types, Address, Error, EventKey, MoveStructTag, TransactionId};
 use move_core_types::identifier::Identifier;
 use percent_encoding::percent_decode_str;
 use serde::{Deserialize, Deserializer};
 
 use std::{convert::Infallible, str::FromStr};
+use warp::{Filter, Rejection};
 
 pub type AddressParam = Param<Address>;
 pub type TransactionIdParam = Param<TransactionId>;
@@ -59,6 +60,17 @@ impl<'de, T: FromStr> Deserialize<'de> for Param<T> {
     }
 }
 
+/// Extracts whether the `Accept` header of the request asks for BCS encoded on-chain data.
+pub fn accept_bcs() -> impl Filter<Extract = (bool,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("accept").map(|accept: Option<String>| {
+        accept.map_or(false, |accept| {
+            accept
+                .split(',')
+                .any(|mime_type| mime_type.trim() == mime_types::BCS)
+        })
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::MoveIdentifierParam;
@@ -5,7 +5,9 @@ use crate::{
     current_function_name,
     tests::{find_value, new_test_context},
 };
+use aptos_types::account_state::AccountState;
 use serde_json::json;
+use std::convert::TryFrom;
 
 #[tokio::test]
 async fn test_get_account_resources_returns_empty_array_for_account_has_no_resources() {
@@ -180,6 +182,46 @@ async fn test_get_account_modules_by_ledger_version() {
     assert_eq!(modules, json!([]));
 }
 
+#[tokio::test]
+async fn test_get_account_state_blob_by_ledger_version() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account);
+    context.commit_block(&vec![txn.clone()]).await;
+    let version = context.get_latest_ledger_info().version();
+    let other_account = context.gen_account();
+    let txn = context.create_user_account(&other_account);
+    context.commit_block(&vec![txn]).await;
+
+    let blob = context
+        .get(&format!(
+            "/accounts/{}/blob?version={}",
+            account.address().to_hex_literal(),
+            version
+        ))
+        .await;
+    let bytes: Vec<u8> = serde_json::from_value(blob.clone()).unwrap();
+    let account_state = AccountState::try_from(&bytes).unwrap();
+    assert!(account_state.get_account_resource().unwrap().is_some());
+    let latest_blob = context
+        .get(&format!(
+            "/accounts/{}/blob",
+            account.address().to_hex_literal()
+        ))
+        .await;
+    assert_eq!(blob, latest_blob);
+
+    // The account is created by the last transaction of the first block.
+    context
+        .expect_status_code(404)
+        .get(&format!(
+            "/accounts/{}/blob?version={}",
+            account.address().to_hex_literal(),
+            version - 1
+        ))
+        .await;
+}
+
 #[tokio::test]
 async fn test_get_core_account_data() {
     let mut context = new_test_context(current_function_name!());
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{current_function_name, tests::new_test_context};
+use aptos_types::contract_event::EventWithProof;
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 
 static EVENT_KEY: &str =
@@ -43,6 +44,21 @@ async fn test_get_events_filter_by_limit_page_size() {
     assert_eq!(resp.as_array().unwrap().len(), 2);
 }
 
+#[tokio::test]
+async fn test_get_events_with_proofs_in_bcs() {
+    let context = new_test_context(current_function_name!());
+
+    let bytes = context
+        .get_bcs(format!("/events/{}?start=1&limit=2", EVENT_KEY).as_str())
+        .await;
+    let events: Vec<EventWithProof> = bcs::from_bytes(&bytes).unwrap();
+    let sequence_numbers: Vec<_> = events
+        .iter()
+        .map(|event| event.event.sequence_number())
+        .collect();
+    assert_eq!(sequence_numbers, vec![1, 2]);
+}
+
 #[tokio::test]
 async fn test_get_events_by_invalid_key() {
     let mut context = new_test_context(current_function_name!());
@@ -44,7 +44,7 @@ use rand::SeedableRng;
 use serde_json::{json, Value};
 use std::{boxed::Box, collections::BTreeMap, sync::Arc};
 use vm_validator::vm_validator::VMValidator;
-use warp::http::header::CONTENT_TYPE;
+use warp::http::header::{ACCEPT, CONTENT_TYPE};
 
 pub fn new_test_context(test_name: &'static str) -> TestContext {
     let tmp_dir = TempPath::new();
@@ -346,6 +346,20 @@ impl TestContext {
         .await
     }
 
+    pub async fn get_bcs(&self, path: &str) -> Bytes {
+        let resp = self
+            .reply(
+                warp::test::request()
+                    .method("GET")
+                    .path(path)
+                    .header(ACCEPT, mime_types::BCS),
+            )
+            .await;
+        assert_eq!(self.expect_status_code, resp.status());
+        assert_eq!(resp.headers()[CONTENT_TYPE], mime_types::BCS);
+        resp.into_body()
+    }
+
     pub async fn reply(&self, req: warp::test::RequestBuilder) -> Response<Bytes> {
         req.reply(&index::routes(self.context.clone())).await
     }
@@ -385,7 +399,7 @@ impl TestContext {
         let id = HashValue::random_with_rng(&mut self.rng);
         self.fake_time += 1;
         let timestamp = self.fake_time;
-        BlockMetadata::new(id, round, timestamp, vec![], self.validator_owner)
+        BlockMetadata::new(id, round, timestamp, vec![], self.validator_owner, vec![])
     }
 
     fn new_ledger_info(
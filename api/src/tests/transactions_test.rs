@@ -17,7 +17,7 @@ use aptos_types::{
     account_address::AccountAddress,
     transaction::{
         authenticator::{AuthenticationKey, TransactionAuthenticator},
-        ChangeSet, Script, ScriptFunction, SignedTransaction,
+        ChangeSet, Script, ScriptFunction, SignedTransaction, Transaction,
     },
     write_set::{WriteOp, WriteSetMut},
 };
@@ -59,6 +59,24 @@ async fn test_get_transactions_returns_last_page_when_start_version_is_not_speci
     context.check_golden_output(resp);
 }
 
+#[tokio::test]
+async fn test_get_transactions_in_bcs() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account);
+    context.commit_block(&vec![txn]).await;
+
+    let bytes = context.get_bcs("/transactions?start=1&limit=2").await;
+    let txns: Vec<Transaction> = bcs::from_bytes(&bytes).unwrap();
+    let expected: Vec<_> = context
+        .get_transactions(1, 2)
+        .into_iter()
+        .map(|txn| txn.transaction)
+        .collect();
+    assert_eq!(txns, expected);
+    assert_eq!(txns.len(), 2);
+}
+
 #[tokio::test]
 async fn test_get_transactions_with_start_version_is_too_large() {
     let mut context = new_test_context(current_function_name!());
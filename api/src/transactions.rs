@@ -6,7 +6,7 @@ use crate::{
     failpoint::fail_point,
     metrics::metrics,
     page::Page,
-    param::{AddressParam, TransactionIdParam},
+    param::{accept_bcs, AddressParam, TransactionIdParam},
 };
 
 use aptos_api_types::{
@@ -36,10 +36,12 @@ pub fn get_transaction(context: Context) -> BoxedFilter<(impl Reply,)> {
 }
 
 // GET /transactions?start={u64}&limit={u16}
+// With `Accept: application/x-bcs`, the BCS encoded on-chain transactions are returned.
 pub fn get_transactions(context: Context) -> BoxedFilter<(impl Reply,)> {
     warp::path!("transactions")
         .and(warp::get())
         .and(warp::query::<Page>())
+        .and(accept_bcs())
         .and(context.filter())
         .and_then(handle_get_transactions)
         .with(metrics("get_transactions"))
@@ -118,9 +120,13 @@ async fn handle_get_transaction(
         .await?)
 }
 
-async fn handle_get_transactions(page: Page, context: Context) -> Result<impl Reply, Rejection> {
+async fn handle_get_transactions(
+    page: Page,
+    bcs: bool,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
     fail_point("endpoint_get_transactions")?;
-    Ok(Transactions::new(context)?.list(page)?)
+    Ok(Transactions::new(context)?.list(page, bcs)?)
 }
 
 async fn handle_get_account_transactions(
@@ -213,7 +219,7 @@ impl Transactions {
         }
     }
 
-    pub fn list(self, page: Page) -> Result<impl Reply, Error> {
+    pub fn list(self, page: Page, bcs: bool) -> Result<impl Reply, Error> {
         let ledger_version = self.ledger_info.version();
         let limit = page.limit()?;
         let last_page_start = if ledger_version > (limit as u64) {
@@ -227,6 +233,10 @@ impl Transactions {
             .context
             .get_transactions(start_version, limit, ledger_version)?;
 
+        if bcs {
+            let txns: Vec<_> = data.into_iter().map(|t| t.transaction).collect();
+            return Response::new_bcs(self.ledger_info, &txns);
+        }
         self.render_transactions(data)
     }
 
@@ -240,7 +250,7 @@ impl Transactions {
         self.render_transactions(data)
     }
 
-    fn render_transactions(self, data: Vec<TransactionOnChainData>) -> Result<impl Reply, Error> {
+    fn render_transactions(self, data: Vec<TransactionOnChainData>) -> Result<Response, Error> {
         if data.is_empty() {
             let txns: Vec<Transaction> = vec![];
             return Response::new(self.ledger_info, &txns);
@@ -3,3 +3,4 @@
 
 pub const BCS_SIGNED_TRANSACTION: &str = "application/x.diem.signed_transaction+bcs";
 pub const JSON: &str = "application/json";
+pub const BCS: &str = "application/x-bcs";
@@ -1,7 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{Error, LedgerInfo};
+use crate::{mime_types, Error, LedgerInfo};
 
 use anyhow::Result;
 use serde::Serialize;
@@ -15,6 +15,7 @@ pub const X_APTOS_LEDGER_TIMESTAMP: &str = "X-Aptos-Ledger-TimestampUsec";
 pub struct Response {
     pub ledger_info: LedgerInfo,
     pub body: Vec<u8>,
+    pub content_type: &'static str,
 }
 
 impl Response {
@@ -22,6 +23,17 @@ impl Response {
         Ok(Self {
             ledger_info,
             body: serde_json::to_vec(body)?,
+            content_type: mime_types::JSON,
+        })
+    }
+
+    /// Creates a response with the BCS encoded `body`, for clients requesting on-chain data in
+    /// its native format.
+    pub fn new_bcs<T: Serialize>(ledger_info: LedgerInfo, body: &T) -> Result<Self, Error> {
+        Ok(Self {
+            ledger_info,
+            body: bcs::to_bytes(body).map_err(|e| Error::internal(e.into()))?,
+            content_type: mime_types::BCS,
         })
     }
 }
@@ -31,7 +43,7 @@ impl warp::Reply for Response {
         let mut res = warp::reply::Response::new(self.body.into());
         let headers = res.headers_mut();
 
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(self.content_type));
         headers.insert(X_APTOS_CHAIN_ID, (self.ledger_info.chain_id as u16).into());
         headers.insert(
             X_APTOS_LEDGER_VERSION,
@@ -0,0 +1,304 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Parallel-friendly aggregator updates. A `DeltaOp` records a net addition or subtraction
+//! together with the history of partial results it went through, so that it can be validated
+//! against (and applied to) a base value that is only known later.
+
+use anyhow::{bail, Result};
+use aptos_state_view::StateView;
+use aptos_types::{access_path::AccessPath, write_set::WriteOp};
+
+/// The net update a delta performs on the value it is applied to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeltaUpdate {
+    Plus(u128),
+    Minus(u128),
+}
+
+/// A commutative update of an aggregator value.
+///
+/// Besides the net update, the operation tracks the largest intermediate increase and the
+/// largest intermediate decrease (relative to the base value) observed while the update was
+/// accumulated. Applying the delta succeeds only if none of the intermediate values would have
+/// overflowed `limit` or gone below zero, which makes the outcome identical to applying each
+/// individual addition and subtraction in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeltaOp {
+    /// Net update of the value.
+    update: DeltaUpdate,
+    /// Maximum positive offset from the base value reached by any intermediate result.
+    max_positive: u128,
+    /// Maximum negative offset from the base value reached by any intermediate result.
+    min_negative: u128,
+    /// Upper bound (inclusive) of the aggregator value.
+    limit: u128,
+}
+
+impl DeltaOp {
+    pub fn new(update: DeltaUpdate, limit: u128, max_positive: u128, min_negative: u128) -> Self {
+        Self {
+            update,
+            max_positive,
+            min_negative,
+            limit,
+        }
+    }
+
+    /// Creates a delta that adds `value` to an aggregator bounded by `limit`.
+    pub fn addition(value: u128, limit: u128) -> Result<Self> {
+        if value > limit {
+            bail!("Addition of {} exceeds the limit {}", value, limit);
+        }
+        Ok(Self::new(DeltaUpdate::Plus(value), limit, value, 0))
+    }
+
+    /// Creates a delta that subtracts `value` from an aggregator bounded by `limit`.
+    pub fn subtraction(value: u128, limit: u128) -> Result<Self> {
+        if value > limit {
+            bail!("Subtraction of {} exceeds the limit {}", value, limit);
+        }
+        Ok(Self::new(DeltaUpdate::Minus(value), limit, 0, value))
+    }
+
+    pub fn update(&self) -> DeltaUpdate {
+        self.update
+    }
+
+    pub fn limit(&self) -> u128 {
+        self.limit
+    }
+
+    /// Applies the delta to `base`, returning an error if any intermediate result would have
+    /// overflowed the limit or underflowed zero.
+    pub fn apply_to(&self, base: u128) -> Result<u128> {
+        // Check the history first: the value must have stayed within bounds all along.
+        if self.max_positive > self.limit || base > self.limit - self.max_positive {
+            bail!(
+                "Delta {:?} overflows the limit when applied to {}",
+                self,
+                base
+            );
+        }
+        if base < self.min_negative {
+            bail!("Delta {:?} underflows when applied to {}", self, base);
+        }
+        Ok(match self.update {
+            DeltaUpdate::Plus(value) => base + value,
+            DeltaUpdate::Minus(value) => base - value,
+        })
+    }
+
+    /// Accumulates an addition of `value` on top of this delta.
+    pub fn add(&mut self, value: u128) -> Result<()> {
+        self.merge_with(Self::addition(value, self.limit)?)
+    }
+
+    /// Accumulates a subtraction of `value` on top of this delta.
+    pub fn sub(&mut self, value: u128) -> Result<()> {
+        self.merge_with(Self::subtraction(value, self.limit)?)
+    }
+
+    /// Accumulates `next`, which must logically happen after `self`, into `self`.
+    pub fn merge_with(&mut self, next: DeltaOp) -> Result<()> {
+        let mut merged = next;
+        merged.merge_onto(*self)?;
+        *self = merged;
+        Ok(())
+    }
+
+    /// Rewrites `self` as if `previous` had been applied before it, i.e. the result is the
+    /// delta of applying `previous` followed by `self`.
+    pub fn merge_onto(&mut self, previous: DeltaOp) -> Result<()> {
+        if self.limit != previous.limit {
+            bail!(
+                "Cannot merge deltas with different limits {} and {}",
+                previous.limit,
+                self.limit
+            );
+        }
+
+        // Offsets of `self` are relative to the result of `previous`, so they have to be
+        // shifted by the net update of `previous` before being compared.
+        let (max_positive, min_negative) = match previous.update {
+            DeltaUpdate::Plus(value) => (
+                match self.max_positive.checked_add(value) {
+                    Some(shifted) => previous.max_positive.max(shifted),
+                    None => bail!("Overflow when merging {:?} onto {:?}", self, previous),
+                },
+                previous
+                    .min_negative
+                    .max(self.min_negative.saturating_sub(value)),
+            ),
+            DeltaUpdate::Minus(value) => (
+                previous
+                    .max_positive
+                    .max(self.max_positive.saturating_sub(value)),
+                match self.min_negative.checked_add(value) {
+                    Some(shifted) => previous.min_negative.max(shifted),
+                    None => bail!("Underflow when merging {:?} onto {:?}", self, previous),
+                },
+            ),
+        };
+        if max_positive > self.limit || min_negative > self.limit {
+            bail!("Merging {:?} onto {:?} exceeds the limit", self, previous);
+        }
+
+        self.update = addition(previous.update, self.update)?;
+        self.max_positive = max_positive;
+        self.min_negative = min_negative;
+        Ok(())
+    }
+}
+
+/// Computes the net update of applying `first` followed by `second`.
+fn addition(first: DeltaUpdate, second: DeltaUpdate) -> Result<DeltaUpdate> {
+    use DeltaUpdate::*;
+    Ok(match (first, second) {
+        (Plus(a), Plus(b)) => match a.checked_add(b) {
+            Some(value) => Plus(value),
+            None => bail!("Overflow when adding {} and {}", a, b),
+        },
+        (Minus(a), Minus(b)) => match a.checked_add(b) {
+            Some(value) => Minus(value),
+            None => bail!("Underflow when subtracting {} and {}", a, b),
+        },
+        (Plus(a), Minus(b)) | (Minus(b), Plus(a)) => {
+            if a >= b {
+                Plus(a - b)
+            } else {
+                Minus(b - a)
+            }
+        }
+    })
+}
+
+/// Serializes an aggregator value the way it is stored in the global state.
+pub fn serialize(value: &u128) -> Vec<u8> {
+    bcs::to_bytes(value).expect("Unexpected serialization error in aggregator")
+}
+
+/// Deserializes an aggregator value stored in the global state.
+pub fn deserialize(value_bytes: &[u8]) -> Result<u128> {
+    Ok(bcs::from_bytes(value_bytes)?)
+}
+
+/// Deltas produced by a single transaction, keyed by the access path of the aggregator value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeltaChangeSet {
+    delta_change_set: Vec<(AccessPath, DeltaOp)>,
+}
+
+impl DeltaChangeSet {
+    pub fn new(delta_change_set: Vec<(AccessPath, DeltaOp)>) -> Self {
+        DeltaChangeSet { delta_change_set }
+    }
+
+    pub fn empty() -> Self {
+        DeltaChangeSet::default()
+    }
+
+    pub fn push(&mut self, delta: (AccessPath, DeltaOp)) {
+        self.delta_change_set.push(delta);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.delta_change_set.is_empty()
+    }
+
+    pub fn iter(&self) -> ::std::slice::Iter<'_, (AccessPath, DeltaOp)> {
+        self.delta_change_set.iter()
+    }
+
+    /// Resolves every delta against the value currently stored in `state_view` and returns the
+    /// resulting writes.
+    pub fn materialize<S: StateView>(self, state_view: &S) -> Result<Vec<(AccessPath, WriteOp)>> {
+        self.delta_change_set
+            .into_iter()
+            .map(|(access_path, delta)| {
+                let base = match state_view.get_by_access_path(&access_path)? {
+                    Some(bytes) => deserialize(&bytes)?,
+                    None => bail!("Aggregator value at {:?} does not exist", access_path),
+                };
+                let value = delta.apply_to(base)?;
+                Ok((access_path, WriteOp::Value(serialize(&value))))
+            })
+            .collect()
+    }
+}
+
+impl ::std::iter::IntoIterator for DeltaChangeSet {
+    type Item = (AccessPath, DeltaOp);
+    type IntoIter = ::std::vec::IntoIter<(AccessPath, DeltaOp)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.delta_change_set.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_within_bounds() {
+        let add = DeltaOp::addition(10, 100).unwrap();
+        assert_eq!(add.apply_to(0).unwrap(), 10);
+        assert_eq!(add.apply_to(90).unwrap(), 100);
+        assert!(add.apply_to(91).is_err());
+
+        let sub = DeltaOp::subtraction(10, 100).unwrap();
+        assert_eq!(sub.apply_to(10).unwrap(), 0);
+        assert!(sub.apply_to(9).is_err());
+    }
+
+    #[test]
+    fn merged_deltas_keep_history() {
+        // +50 then -60: net -10, but must have had room for +50 first.
+        let mut delta = DeltaOp::addition(50, 100).unwrap();
+        delta.sub(60).unwrap();
+        assert_eq!(delta.update(), DeltaUpdate::Minus(10));
+        assert!(delta.apply_to(5).is_err());
+        assert_eq!(delta.apply_to(10).unwrap(), 0);
+        assert_eq!(delta.apply_to(50).unwrap(), 40);
+        assert!(delta.apply_to(51).is_err());
+
+        // -30 then +40: net +10, but the base must be at least 30.
+        let mut delta = DeltaOp::subtraction(30, 100).unwrap();
+        delta.add(40).unwrap();
+        assert_eq!(delta.update(), DeltaUpdate::Plus(10));
+        assert!(delta.apply_to(29).is_err());
+        assert_eq!(delta.apply_to(30).unwrap(), 40);
+        assert_eq!(delta.apply_to(90).unwrap(), 100);
+        assert!(delta.apply_to(91).is_err());
+    }
+
+    #[test]
+    fn merge_matches_sequential_application() {
+        let ops: Vec<DeltaOp> = vec![
+            DeltaOp::addition(7, 20).unwrap(),
+            DeltaOp::subtraction(12, 20).unwrap(),
+            DeltaOp::addition(3, 20).unwrap(),
+            DeltaOp::subtraction(1, 20).unwrap(),
+        ];
+        let mut merged = ops[0];
+        for op in &ops[1..] {
+            merged.merge_with(*op).unwrap();
+        }
+
+        for base in 0..=20 {
+            let sequential = ops
+                .iter()
+                .try_fold(base, |value, op| op.apply_to(value).ok());
+            assert_eq!(merged.apply_to(base).ok(), sequential);
+        }
+    }
+
+    #[test]
+    fn merge_rejects_mismatched_limits_and_overflows() {
+        let mut delta = DeltaOp::addition(5, 10).unwrap();
+        assert!(delta.merge_with(DeltaOp::addition(5, 20).unwrap()).is_err());
+        assert!(delta.add(6).is_err());
+        assert!(DeltaOp::addition(11, 10).is_err());
+    }
+}
@@ -0,0 +1,11 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Commutative aggregator updates ("deltas") that can be produced by transactions instead of
+//! regular writes, so that concurrent increments of the same value do not conflict during
+//! parallel execution.
+
+pub mod delta_change_set;
+pub mod transaction;
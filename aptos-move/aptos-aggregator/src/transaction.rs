@@ -0,0 +1,71 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::delta_change_set::DeltaChangeSet;
+use anyhow::Result;
+use aptos_state_view::StateView;
+use aptos_types::transaction::{TransactionOutput, TransactionStatus};
+
+/// Extension of `TransactionOutput` that also carries the aggregator deltas produced by the
+/// transaction. Deltas must be materialized into regular writes before the output can be
+/// committed.
+#[derive(Debug)]
+pub struct TransactionOutputExt {
+    delta_change_set: DeltaChangeSet,
+    output: TransactionOutput,
+}
+
+impl TransactionOutputExt {
+    pub fn new(delta_change_set: DeltaChangeSet, output: TransactionOutput) -> Self {
+        TransactionOutputExt {
+            delta_change_set,
+            output,
+        }
+    }
+
+    pub fn delta_change_set(&self) -> &DeltaChangeSet {
+        &self.delta_change_set
+    }
+
+    pub fn txn_output(&self) -> &TransactionOutput {
+        &self.output
+    }
+
+    pub fn into(self) -> (DeltaChangeSet, TransactionOutput) {
+        (self.delta_change_set, self.output)
+    }
+
+    /// Materializes the deltas against `state_view` and appends the resulting writes to the
+    /// write set of the output.
+    pub fn into_transaction_output<S: StateView>(
+        self,
+        state_view: &S,
+    ) -> Result<TransactionOutput> {
+        let (delta_change_set, output) = self.into();
+        if delta_change_set.is_empty() {
+            return Ok(output);
+        }
+
+        let materialized = delta_change_set.materialize(state_view)?;
+        let (write_set, events, gas_used, status) = output.unpack();
+        // Deltas are only ever produced by transactions that are kept.
+        debug_assert!(matches!(status, TransactionStatus::Keep(_)));
+
+        let mut write_set_mut = write_set.into_mut();
+        for write in materialized {
+            write_set_mut.push(write);
+        }
+        Ok(TransactionOutput::new(
+            write_set_mut.freeze()?,
+            events,
+            gas_used,
+            status,
+        ))
+    }
+}
+
+impl From<TransactionOutput> for TransactionOutputExt {
+    fn from(output: TransactionOutput) -> Self {
+        TransactionOutputExt::new(DeltaChangeSet::empty(), output)
+    }
+}
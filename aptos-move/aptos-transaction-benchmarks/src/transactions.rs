@@ -190,6 +190,7 @@ impl TransactionBenchState {
             1,
             vec![],
             *validator_set.payload()[0].account_address(),
+            vec![],
         );
 
         state
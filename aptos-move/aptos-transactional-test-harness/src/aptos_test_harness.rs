@@ -1194,8 +1194,14 @@ impl<'a> MoveTestAdapter<'a> for AptosTestAdapter<'a> {
         match input.command {
             AptosSubCommand::BlockCommand(block_cmd) => {
                 let proposer = self.compiled_state().resolve_address(&block_cmd.proposer);
-                let metadata =
-                    BlockMetadata::new(HashValue::zero(), 0, block_cmd.time, vec![], proposer);
+                let metadata = BlockMetadata::new(
+                    HashValue::zero(),
+                    0,
+                    block_cmd.time,
+                    vec![],
+                    proposer,
+                    vec![],
+                );
 
                 let output = self.run_transaction(Transaction::BlockMetadata(metadata))?;
 
@@ -0,0 +1,460 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::AptosValidatorInterface;
+use anyhow::{anyhow, Result};
+use aptos_infallible::Mutex;
+use aptos_rest_client::{aptos_api_types::Transaction as ApiTransaction, Client};
+use aptos_types::{
+    account_address::AccountAddress,
+    account_state::AccountState,
+    contract_event::EventWithProof,
+    event::EventKey,
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::{Transaction, Version},
+};
+use lru::LruCache;
+use std::{convert::TryFrom, future::Future};
+use tokio::runtime::Runtime;
+
+/// Maximum number of items the REST API returns per request.
+const MAX_PAGE_SIZE: u64 = 1000;
+
+/// Number of state values and of transactions cached by default.
+const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
+/// Serves the debugger from the REST API of a fullnode. State values and transactions are
+/// fetched on demand and the most recently used ones are cached, as replaying a transaction
+/// reads the same accounts many times.
+pub struct RestDebuggerInterface {
+    client: Client,
+    runtime: Runtime,
+    state_cache: Mutex<LruCache<(StateKey, Version), Option<StateValue>>>,
+    transaction_cache: Mutex<LruCache<Version, Transaction>>,
+}
+
+impl RestDebuggerInterface {
+    pub fn new(client: Client) -> Result<Self> {
+        Self::new_with_cache_capacity(client, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Creates an interface caching up to `cache_capacity` state values and as many
+    /// transactions.
+    pub fn new_with_cache_capacity(client: Client, cache_capacity: usize) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            client,
+            runtime,
+            state_cache: Mutex::new(LruCache::new(cache_capacity)),
+            transaction_cache: Mutex::new(LruCache::new(cache_capacity)),
+        })
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+}
+
+impl AptosValidatorInterface for RestDebuggerInterface {
+    fn get_account_state_by_version(
+        &self,
+        account: AccountAddress,
+        version: Version,
+    ) -> Result<Option<AccountState>> {
+        self.get_state_value_by_version(&StateKey::AccountAddressKey(account), version)?
+            .map(|s| AccountState::try_from(&s))
+            .transpose()
+    }
+
+    fn get_state_value_by_version(
+        &self,
+        state_key: &StateKey,
+        version: Version,
+    ) -> Result<Option<StateValue>> {
+        let cache_key = (state_key.clone(), version);
+        if let Some(state_value) = self.state_cache.lock().get(&cache_key) {
+            return Ok(state_value.clone());
+        }
+
+        let state_value = match state_key {
+            StateKey::AccountAddressKey(address) => self
+                .block_on(
+                    self.client
+                        .get_account_state_blob_by_version(*address, version),
+                )?
+                .map(StateValue::from),
+        };
+        self.state_cache.lock().put(cache_key, state_value.clone());
+        Ok(state_value)
+    }
+
+    fn get_events(
+        &self,
+        key: &EventKey,
+        start_seq: u64,
+        limit: u64,
+    ) -> Result<Vec<EventWithProof>> {
+        let mut events = vec![];
+        while (events.len() as u64) < limit {
+            let batch = self
+                .block_on(self.client.get_events_bcs(
+                    key,
+                    Some(start_seq + events.len() as u64),
+                    Some(std::cmp::min(limit - events.len() as u64, MAX_PAGE_SIZE)),
+                ))?
+                .into_inner();
+            if batch.is_empty() {
+                break;
+            }
+            events.extend(batch);
+        }
+        Ok(events)
+    }
+
+    fn get_committed_transactions(&self, start: Version, limit: u64) -> Result<Vec<Transaction>> {
+        let mut txns = Vec::with_capacity(limit as usize);
+        while (txns.len() as u64) < limit {
+            let version = start + txns.len() as u64;
+            if let Some(txn) = self.transaction_cache.lock().get(&version) {
+                txns.push(txn.clone());
+                continue;
+            }
+
+            let batch = self
+                .block_on(self.client.get_transactions_bcs(
+                    Some(version),
+                    Some(std::cmp::min(limit - txns.len() as u64, MAX_PAGE_SIZE)),
+                ))?
+                .into_inner();
+            if batch.is_empty() {
+                break;
+            }
+            let mut transaction_cache = self.transaction_cache.lock();
+            for (offset, txn) in batch.into_iter().enumerate() {
+                transaction_cache.put(version + offset as u64, txn.clone());
+                txns.push(txn);
+            }
+        }
+        Ok(txns)
+    }
+
+    fn get_latest_version(&self) -> Result<Version> {
+        Ok(self
+            .block_on(self.client.get_ledger_information())?
+            .inner()
+            .version)
+    }
+
+    fn get_version_by_account_sequence(
+        &self,
+        account: AccountAddress,
+        seq: u64,
+    ) -> Result<Option<Version>> {
+        let txns = self
+            .block_on(
+                self.client
+                    .get_account_transactions(account, Some(seq), Some(1)),
+            )?
+            .into_inner();
+        match txns.first() {
+            Some(ApiTransaction::UserTransaction(txn)) => {
+                if txn.request.sequence_number.0 == seq {
+                    Ok(Some(txn.info.version.0))
+                } else {
+                    Ok(None)
+                }
+            }
+            Some(txn) => Err(anyhow!("Unexpected account transaction: {:?}", txn)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::HashValue;
+    use aptos_types::{
+        block_metadata::BlockMetadata,
+        contract_event::ContractEvent,
+        proof::{
+            EventAccumulatorProof, EventProof, TransactionAccumulatorProof,
+            TransactionInfoWithProof,
+        },
+        transaction::TransactionInfo,
+        vm_status::KeptVMStatus,
+    };
+    use move_core_types::language_storage::TypeTag;
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+    use url::Url;
+    use warp::{http::StatusCode, reply::Response, Filter, Reply};
+
+    /// Number of items the mocked REST API returns per request, to exercise paging.
+    const PAGE_SIZE: u64 = 2;
+
+    fn with_state_headers(reply: impl Reply) -> Response {
+        let reply = warp::reply::with_header(reply, "X-Aptos-Chain-Id", "4");
+        let reply = warp::reply::with_header(reply, "X-Aptos-Epoch", "1");
+        let reply = warp::reply::with_header(reply, "X-Aptos-Ledger-Version", "10");
+        warp::reply::with_header(reply, "X-Aptos-Ledger-TimestampUsec", "100").into_response()
+    }
+
+    /// Returns the `start` and `limit` query parameters, capping the limit to `PAGE_SIZE`.
+    fn page(query: &HashMap<String, u64>) -> (usize, usize) {
+        let start = query.get("start").copied().unwrap_or(0);
+        let limit = query.get("limit").copied().unwrap_or(PAGE_SIZE);
+        (start as usize, std::cmp::min(limit, PAGE_SIZE) as usize)
+    }
+
+    /// A REST API serving the given account blobs, transactions and events the way a node does,
+    /// counting the requests it receives.
+    struct MockRestApi {
+        _runtime: Runtime,
+        url: Url,
+        requests: Arc<AtomicUsize>,
+    }
+
+    impl MockRestApi {
+        fn new(
+            blobs: HashMap<(AccountAddress, Version), Vec<u8>>,
+            transactions: Vec<Transaction>,
+            events: Vec<EventWithProof>,
+        ) -> Self {
+            let requests = Arc::new(AtomicUsize::new(0));
+            let counter = requests.clone();
+            let count = warp::any()
+                .map(move || {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                })
+                .untuple_one();
+
+            let blob_route = warp::path!("accounts" / String / "blob")
+                .and(warp::query::<HashMap<String, u64>>())
+                .map(move |address: String, query: HashMap<String, u64>| {
+                    let address = AccountAddress::from_hex(&address).unwrap();
+                    let version = query["version"];
+                    match blobs.get(&(address, version)) {
+                        Some(blob) => with_state_headers(warp::reply::json(blob)),
+                        None => {
+                            let message = format!(
+                                "account not found by address({}) and ledger version({})",
+                                address, version,
+                            );
+                            warp::reply::with_status(
+                                format!(r#"{{"code":404,"message":"{}"}}"#, message),
+                                StatusCode::NOT_FOUND,
+                            )
+                            .into_response()
+                        }
+                    }
+                });
+            let transactions_route = warp::path!("transactions")
+                .and(warp::query::<HashMap<String, u64>>())
+                .map(move |query: HashMap<String, u64>| {
+                    let (start, limit) = page(&query);
+                    let page: Vec<_> = transactions.iter().skip(start).take(limit).collect();
+                    with_state_headers(bcs::to_bytes(&page).unwrap())
+                });
+            let events_route = warp::path!("events" / String)
+                .and(warp::query::<HashMap<String, u64>>())
+                .map(move |_key: String, query: HashMap<String, u64>| {
+                    let (start, limit) = page(&query);
+                    let page: Vec<_> = events
+                        .iter()
+                        .filter(|event| event.event.sequence_number() >= start as u64)
+                        .take(limit)
+                        .collect();
+                    with_state_headers(bcs::to_bytes(&page).unwrap())
+                });
+            let routes = warp::get().and(count).and(
+                blob_route
+                    .or(transactions_route)
+                    .unify()
+                    .or(events_route)
+                    .unify(),
+            );
+
+            let runtime = Runtime::new().unwrap();
+            let (address, server) = {
+                let _guard = runtime.enter();
+                warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0))
+            };
+            runtime.spawn(server);
+            Self {
+                _runtime: runtime,
+                url: Url::parse(&format!("http://{}", address)).unwrap(),
+                requests,
+            }
+        }
+
+        fn interface(&self, cache_capacity: usize) -> RestDebuggerInterface {
+            RestDebuggerInterface::new_with_cache_capacity(
+                Client::new(self.url.clone()),
+                cache_capacity,
+            )
+            .unwrap()
+        }
+
+        fn requests(&self) -> usize {
+            self.requests.load(Ordering::SeqCst)
+        }
+    }
+
+    fn gen_transactions(count: u64) -> Vec<Transaction> {
+        (0..count)
+            .map(|round| {
+                Transaction::BlockMetadata(BlockMetadata::new(
+                    HashValue::zero(),
+                    round,
+                    round,
+                    vec![],
+                    AccountAddress::ZERO,
+                    vec![],
+                ))
+            })
+            .collect()
+    }
+
+    fn gen_events(key: &EventKey, count: u64) -> Vec<EventWithProof> {
+        (0..count)
+            .map(|sequence_number| {
+                EventWithProof::new(
+                    sequence_number,
+                    0,
+                    ContractEvent::new(*key, sequence_number, TypeTag::Bool, vec![]),
+                    EventProof::new(
+                        TransactionInfoWithProof::new(
+                            TransactionAccumulatorProof::new(vec![]),
+                            TransactionInfo::new(
+                                HashValue::zero(),
+                                HashValue::zero(),
+                                HashValue::zero(),
+                                0,
+                                KeptVMStatus::Executed,
+                            ),
+                        ),
+                        EventAccumulatorProof::new(vec![]),
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_get_state_value_by_version() {
+        let account = AccountAddress::random();
+        let mut account_state = AccountState::default();
+        account_state.insert(vec![1], vec![1]);
+        let blob1 = bcs::to_bytes(&account_state).unwrap();
+        account_state.insert(vec![2], vec![2]);
+        let blob2 = bcs::to_bytes(&account_state).unwrap();
+        let api = MockRestApi::new(
+            vec![((account, 1), blob1.clone()), ((account, 2), blob2.clone())]
+                .into_iter()
+                .collect(),
+            vec![],
+            vec![],
+        );
+        let interface = api.interface(10);
+        let state_key = StateKey::AccountAddressKey(account);
+
+        assert_eq!(
+            interface.get_state_value_by_version(&state_key, 1).unwrap(),
+            Some(StateValue::from(blob1)),
+        );
+        assert_eq!(
+            interface.get_account_state_by_version(account, 2).unwrap(),
+            Some(account_state),
+        );
+        // The account doesn't exist at version 0.
+        assert_eq!(
+            interface.get_state_value_by_version(&state_key, 0).unwrap(),
+            None
+        );
+        assert_eq!(api.requests(), 3);
+
+        // Values are served from the cache, absent ones included.
+        assert_eq!(
+            interface.get_state_value_by_version(&state_key, 2).unwrap(),
+            Some(StateValue::from(blob2)),
+        );
+        assert_eq!(
+            interface.get_state_value_by_version(&state_key, 0).unwrap(),
+            None
+        );
+        assert_eq!(api.requests(), 3);
+    }
+
+    #[test]
+    fn test_cache_is_bounded() {
+        let account = AccountAddress::random();
+        let blobs = (0..3)
+            .map(|version| ((account, version), vec![version as u8]))
+            .collect();
+        let api = MockRestApi::new(blobs, gen_transactions(3), vec![]);
+        let interface = api.interface(2);
+        let state_key = StateKey::AccountAddressKey(account);
+
+        for version in 0..3 {
+            interface
+                .get_state_value_by_version(&state_key, version)
+                .unwrap();
+        }
+        assert_eq!(api.requests(), 3);
+        // The least recently used value is evicted.
+        interface.get_state_value_by_version(&state_key, 2).unwrap();
+        assert_eq!(api.requests(), 3);
+        interface.get_state_value_by_version(&state_key, 0).unwrap();
+        assert_eq!(api.requests(), 4);
+
+        // Only the last two transactions remain cached.
+        interface.get_committed_transactions(0, 3).unwrap();
+        let requests = api.requests();
+        interface.get_committed_transactions(1, 2).unwrap();
+        assert_eq!(api.requests(), requests);
+        interface.get_committed_transactions(0, 1).unwrap();
+        assert_eq!(api.requests(), requests + 1);
+    }
+
+    #[test]
+    fn test_get_committed_transactions_by_pages() {
+        let transactions = gen_transactions(5);
+        let api = MockRestApi::new(HashMap::new(), transactions.clone(), vec![]);
+        let interface = api.interface(10);
+
+        assert_eq!(
+            interface.get_committed_transactions(1, 3).unwrap(),
+            transactions[1..4].to_vec(),
+        );
+        assert_eq!(api.requests(), 2);
+
+        // The cached transactions are not fetched again, and paging stops at the latest version.
+        assert_eq!(
+            interface.get_committed_transactions(2, 10).unwrap(),
+            transactions[2..].to_vec(),
+        );
+        assert_eq!(api.requests(), 4);
+    }
+
+    #[test]
+    fn test_get_events_by_pages() {
+        let key = EventKey::new_from_address(&AccountAddress::random(), 0);
+        let events = gen_events(&key, 5);
+        let api = MockRestApi::new(HashMap::new(), vec![], events.clone());
+        let interface = api.interface(10);
+
+        assert_eq!(interface.get_events(&key, 1, 3).unwrap(), events[1..4]);
+        assert_eq!(api.requests(), 2);
+
+        assert_eq!(interface.get_events(&key, 3, 10).unwrap(), events[3..]);
+        assert_eq!(api.requests(), 4);
+    }
+}
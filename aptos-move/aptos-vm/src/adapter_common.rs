@@ -3,6 +3,7 @@
 
 use crate::{counters::*, data_cache::StateViewCache};
 use anyhow::Result;
+use aptos_aggregator::transaction::TransactionOutputExt;
 use aptos_state_view::StateView;
 use aptos_types::{
     transaction::{SignatureCheckedTransaction, SignedTransaction, VMValidatorResult},
@@ -73,7 +74,7 @@ pub trait VMAdapter {
         txn: &PreprocessedTransaction,
         data_cache: &S,
         log_context: &AdapterLogSchema,
-    ) -> Result<(VMStatus, TransactionOutput, Option<String>), VMStatus>;
+    ) -> Result<(VMStatus, TransactionOutputExt, Option<String>), VMStatus>;
 }
 
 /// Validate a signed transaction by performing the following:
@@ -213,8 +214,13 @@ pub(crate) fn execute_block_impl<A: VMAdapter, S: StateView>(
             debug!(log_context, "Retry after reconfiguration");
             continue;
         };
-        let (vm_status, output, sender) =
+        let (vm_status, output_ext, sender) =
             adapter.execute_single_transaction(&txn, data_cache, &log_context)?;
+        // Aggregator deltas are applied eagerly during sequential execution, so this only
+        // unwraps the output.
+        let output = output_ext
+            .into_transaction_output(data_cache)
+            .map_err(|_| VMStatus::Error(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR))?;
         if !output.status().is_discarded() {
             data_cache.push_write_set(output.write_set());
         } else {
@@ -296,6 +302,11 @@ pub(crate) fn discard_error_vm_status(err: VMStatus) -> (VMStatus, TransactionOu
     (vm_status, discard_error_output(error_code))
 }
 
+pub(crate) fn discard_error_vm_status_ext(err: VMStatus) -> (VMStatus, TransactionOutputExt) {
+    let (vm_status, output) = discard_error_vm_status(err);
+    (vm_status, TransactionOutputExt::from(output))
+}
+
 pub(crate) fn discard_error_output(err: StatusCode) -> TransactionOutput {
     // Since this transaction will be discarded, no writeset will be included.
     TransactionOutput::new(
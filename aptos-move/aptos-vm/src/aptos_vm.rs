@@ -4,8 +4,9 @@
 use crate::{
     adapter_common,
     adapter_common::{
-        discard_error_output, discard_error_vm_status, validate_signature_checked_transaction,
-        validate_signed_transaction, PreprocessedTransaction, VMAdapter,
+        discard_error_output, discard_error_vm_status, discard_error_vm_status_ext,
+        validate_signature_checked_transaction, validate_signed_transaction,
+        PreprocessedTransaction, VMAdapter,
     },
     aptos_vm_impl::{
         charge_global_write_gas_usage, get_currency_info, get_gas_currency_code,
@@ -22,6 +23,7 @@ use crate::{
     VMExecutor, VMValidator,
 };
 use anyhow::Result;
+use aptos_aggregator::transaction::TransactionOutputExt;
 use aptos_crypto::HashValue;
 use aptos_logger::prelude::*;
 use aptos_state_view::StateView;
@@ -37,10 +39,11 @@ use aptos_types::{
         TransactionOutput, TransactionPayload, TransactionStatus, VMValidatorResult,
         WriteSetPayload,
     },
-    vm_status::{KeptVMStatus, StatusCode, VMStatus},
+    vm_status::{AbortLocation, KeptVMStatus, StatusCode, VMStatus},
     write_set::{WriteSet, WriteSetMut},
 };
 use fail::fail_point;
+use framework::natives::aggregator::{aggregator_module_id, EAGGREGATOR_DELTA_APPLICATION};
 use move_binary_format::errors::VMResult;
 use move_core_types::{
     account_address::AccountAddress,
@@ -52,11 +55,15 @@ use move_core_types::{
     value::{serialize_values, MoveValue},
 };
 use move_vm_types::gas_schedule::GasStatus;
+use once_cell::sync::OnceCell;
 use std::{
+    cmp::min,
     collections::HashSet,
     convert::{AsMut, AsRef},
 };
 
+static EXECUTION_CONCURRENCY_LEVEL: OnceCell<usize> = OnceCell::new();
+
 #[derive(Clone)]
 pub struct AptosVM(pub(crate) AptosVMImpl);
 
@@ -85,6 +92,27 @@ impl AptosVM {
             publishing_option,
         ))
     }
+
+    /// Sets the number of threads used by parallel execution, only the first call has an
+    /// effect. A level of 0 (or above the number of CPUs) uses all CPUs.
+    pub fn set_concurrency_level_once(concurrency_level: usize) {
+        let num_cpus = num_cpus::get();
+        let concurrency_level = if concurrency_level == 0 {
+            num_cpus
+        } else {
+            min(concurrency_level, num_cpus)
+        };
+        EXECUTION_CONCURRENCY_LEVEL.set(concurrency_level).ok();
+    }
+
+    /// Number of threads used by parallel execution, defaults to the number of CPUs.
+    pub fn get_concurrency_level() -> usize {
+        match EXECUTION_CONCURRENCY_LEVEL.get() {
+            Some(concurrency_level) => *concurrency_level,
+            None => num_cpus::get(),
+        }
+    }
+
     pub fn internals(&self) -> AptosVMInternals {
         AptosVMInternals::new(&self.0)
     }
@@ -104,7 +132,7 @@ impl AptosVM {
         storage: &S,
         account_currency_symbol: &IdentStr,
         log_context: &AdapterLogSchema,
-    ) -> TransactionOutput {
+    ) -> TransactionOutputExt {
         self.failed_transaction_cleanup_and_keep_vm_status(
             error_code,
             gas_status,
@@ -124,7 +152,7 @@ impl AptosVM {
         storage: &S,
         account_currency_symbol: &IdentStr,
         log_context: &AdapterLogSchema,
-    ) -> (VMStatus, TransactionOutput) {
+    ) -> (VMStatus, TransactionOutputExt) {
         gas_status.set_metering(false);
         let mut session = self.0.new_session(storage, SessionId::txn_meta(txn_data));
         match TransactionStatus::from(error_code.clone()) {
@@ -142,7 +170,7 @@ impl AptosVM {
                     account_currency_symbol,
                     log_context,
                 ) {
-                    return discard_error_vm_status(e);
+                    return discard_error_vm_status_ext(e);
                 }
                 let txn_output = get_transaction_output(
                     &mut (),
@@ -151,12 +179,13 @@ impl AptosVM {
                     txn_data,
                     status,
                 )
-                .unwrap_or_else(|e| discard_error_vm_status(e).1);
+                .unwrap_or_else(|e| discard_error_vm_status_ext(e).1);
                 (error_code, txn_output)
             }
-            TransactionStatus::Discard(status) => {
-                (VMStatus::Error(status), discard_error_output(status))
-            }
+            TransactionStatus::Discard(status) => (
+                VMStatus::Error(status),
+                TransactionOutputExt::from(discard_error_output(status)),
+            ),
             TransactionStatus::Retry => unreachable!(),
         }
     }
@@ -168,7 +197,7 @@ impl AptosVM {
         txn_data: &TransactionMetadata,
         account_currency_symbol: &IdentStr,
         log_context: &AdapterLogSchema,
-    ) -> Result<(VMStatus, TransactionOutput), VMStatus> {
+    ) -> Result<(VMStatus, TransactionOutputExt), VMStatus> {
         gas_status.set_metering(false);
         self.0.run_success_epilogue(
             &mut session,
@@ -198,7 +227,7 @@ impl AptosVM {
         payload: &TransactionPayload,
         account_currency_symbol: &IdentStr,
         log_context: &AdapterLogSchema,
-    ) -> Result<(VMStatus, TransactionOutput), VMStatus> {
+    ) -> Result<(VMStatus, TransactionOutputExt), VMStatus> {
         fail_point!("move_adapter::execute_script_or_script_function", |_| {
             Err(VMStatus::Error(
                 StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
@@ -285,7 +314,7 @@ impl AptosVM {
         modules: &ModuleBundle,
         account_currency_symbol: &IdentStr,
         log_context: &AdapterLogSchema,
-    ) -> Result<(VMStatus, TransactionOutput), VMStatus> {
+    ) -> Result<(VMStatus, TransactionOutputExt), VMStatus> {
         fail_point!("move_adapter::execute_module", |_| {
             Err(VMStatus::Error(
                 StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
@@ -318,17 +347,17 @@ impl AptosVM {
         )
     }
 
-    pub(crate) fn execute_user_transaction<S: MoveResolver>(
+    pub(crate) fn execute_user_transaction<S: MoveResolver + StateView>(
         &self,
         storage: &S,
         txn: &SignatureCheckedTransaction,
         log_context: &AdapterLogSchema,
-    ) -> (VMStatus, TransactionOutput) {
+    ) -> (VMStatus, TransactionOutputExt) {
         macro_rules! unwrap_or_discard {
             ($res: expr) => {
                 match $res {
                     Ok(s) => s,
-                    Err(e) => return discard_error_vm_status(e),
+                    Err(e) => return discard_error_vm_status_ext(e),
                 }
             };
         }
@@ -336,13 +365,13 @@ impl AptosVM {
         let account_currency_symbol = match get_gas_currency_code(txn) {
             Ok(symbol) => symbol,
             Err(err) => {
-                return discard_error_vm_status(err);
+                return discard_error_vm_status_ext(err);
             }
         };
 
         if self.0.chain_info().currency_code_required {
             if let Err(err) = get_currency_info(&account_currency_symbol, storage) {
-                return discard_error_vm_status(err);
+                return discard_error_vm_status_ext(err);
             }
         }
 
@@ -355,7 +384,7 @@ impl AptosVM {
             false,
             log_context,
         ) {
-            return discard_error_vm_status(err);
+            return discard_error_vm_status_ext(err);
         };
 
         let gas_schedule = unwrap_or_discard!(self.0.get_gas_schedule(log_context));
@@ -382,10 +411,25 @@ impl AptosVM {
                 log_context,
             ),
             TransactionPayload::WriteSet(_) => {
-                return discard_error_vm_status(VMStatus::Error(StatusCode::UNREACHABLE));
+                return discard_error_vm_status_ext(VMStatus::Error(StatusCode::UNREACHABLE));
             }
         };
 
+        // Unless the aggregator deltas are resolved later by the parallel executor, apply them
+        // right away so that out of bounds updates fail (and are charged for) the transaction.
+        let result = result.and_then(|(vm_status, output)| {
+            if self.0.defer_aggregator_deltas() {
+                return Ok((vm_status, output));
+            }
+            match output.into_transaction_output(storage) {
+                Ok(output) => Ok((vm_status, TransactionOutputExt::from(output))),
+                Err(_) => Err(VMStatus::MoveAbort(
+                    AbortLocation::Module(aggregator_module_id()),
+                    EAGGREGATOR_DELTA_APPLICATION,
+                )),
+            }
+        });
+
         let gas_usage = txn_data
             .max_gas_amount()
             .sub(gas_status.remaining_gas())
@@ -397,7 +441,7 @@ impl AptosVM {
             Err(err) => {
                 let txn_status = TransactionStatus::from(err.clone());
                 if txn_status.is_discarded() {
-                    discard_error_vm_status(err)
+                    discard_error_vm_status_ext(err)
                 } else {
                     self.failed_transaction_cleanup_and_keep_vm_status(
                         err,
@@ -512,7 +556,7 @@ impl AptosVM {
         storage: &S,
         block_metadata: BlockMetadata,
         log_context: &AdapterLogSchema,
-    ) -> Result<(VMStatus, TransactionOutput), VMStatus> {
+    ) -> Result<(VMStatus, TransactionOutputExt), VMStatus> {
         fail_point!("move_adapter::process_block_prologue", |_| {
             Err(VMStatus::Error(
                 StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
@@ -529,13 +573,20 @@ impl AptosVM {
             .0
             .new_session(storage, SessionId::block_meta(&block_metadata));
 
-        let (round, timestamp, previous_vote, proposer) = block_metadata.into_inner();
+        let (round, timestamp, previous_vote, proposer, failed_proposers) =
+            block_metadata.into_inner();
         let args = serialize_values(&vec![
             MoveValue::Signer(txn_data.sender),
             MoveValue::U64(round),
             MoveValue::U64(timestamp),
             MoveValue::Vector(previous_vote.into_iter().map(MoveValue::Address).collect()),
             MoveValue::Address(proposer),
+            MoveValue::Vector(
+                failed_proposers
+                    .into_iter()
+                    .map(MoveValue::Address)
+                    .collect(),
+            ),
         ]);
         session
             .execute_function(&BLOCK_MODULE, BLOCK_PROLOGUE, vec![], args, &mut gas_status)
@@ -878,7 +929,7 @@ impl VMAdapter for AptosVM {
         txn: &PreprocessedTransaction,
         data_cache: &S,
         log_context: &AdapterLogSchema,
-    ) -> Result<(VMStatus, TransactionOutput, Option<String>), VMStatus> {
+    ) -> Result<(VMStatus, TransactionOutputExt, Option<String>), VMStatus> {
         Ok(match txn {
             PreprocessedTransaction::BlockMetadata(block_metadata) => {
                 let (vm_status, output) =
@@ -888,7 +939,11 @@ impl VMAdapter for AptosVM {
             PreprocessedTransaction::WaypointWriteSet(write_set_payload) => {
                 let (vm_status, output) =
                     self.process_waypoint_change_set(data_cache, write_set_payload.clone())?;
-                (vm_status, output, Some("waypoint_write_set".to_string()))
+                (
+                    vm_status,
+                    TransactionOutputExt::from(output),
+                    Some("waypoint_write_set".to_string()),
+                )
             }
             PreprocessedTransaction::UserTransaction(txn) => {
                 let sender = txn.sender().to_string();
@@ -897,7 +952,7 @@ impl VMAdapter for AptosVM {
                     self.execute_user_transaction(data_cache, txn, log_context);
 
                 // Increment the counter for user transactions executed.
-                let counter_label = match output.status() {
+                let counter_label = match output.txn_output().status() {
                     TransactionStatus::Keep(_) => Some("success"),
                     TransactionStatus::Discard(_) => Some("discarded"),
                     TransactionStatus::Retry => None,
@@ -910,11 +965,15 @@ impl VMAdapter for AptosVM {
             PreprocessedTransaction::WriteSet(txn) => {
                 let (vm_status, output) =
                     self.process_writeset_transaction(data_cache, txn, log_context)?;
-                (vm_status, output, Some("write_set".to_string()))
+                (
+                    vm_status,
+                    TransactionOutputExt::from(output),
+                    Some("write_set".to_string()),
+                )
             }
             PreprocessedTransaction::InvalidSignature => {
                 let (vm_status, output) =
-                    discard_error_vm_status(VMStatus::Error(StatusCode::INVALID_SIGNATURE));
+                    discard_error_vm_status_ext(VMStatus::Error(StatusCode::INVALID_SIGNATURE));
                 (vm_status, output, None)
             }
             PreprocessedTransaction::StateCheckpoint => {
@@ -924,7 +983,11 @@ impl VMAdapter for AptosVM {
                     0,
                     TransactionStatus::Keep(KeptVMStatus::Executed),
                 );
-                (VMStatus::Executed, output, Some("state_checkpoint".into()))
+                (
+                    VMStatus::Executed,
+                    TransactionOutputExt::from(output),
+                    Some("state_checkpoint".into()),
+                )
             }
         })
     }
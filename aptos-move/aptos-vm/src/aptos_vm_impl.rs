@@ -10,6 +10,7 @@ use crate::{
     move_vm_ext::{MoveVmExt, SessionExt, SessionId},
     transaction_metadata::TransactionMetadata,
 };
+use aptos_aggregator::transaction::TransactionOutputExt;
 use aptos_crypto::HashValue;
 use aptos_logger::prelude::*;
 use aptos_state_view::StateView;
@@ -49,6 +50,9 @@ pub struct AptosVMImpl {
     version: Option<Version>,
     publishing_option: Option<VMPublishingOption>,
     chain_account_info: Option<ChainSpecificAccountInfo>,
+    /// Whether aggregator deltas are kept in the transaction output instead of being applied
+    /// eagerly, so that they can be resolved by the parallel executor.
+    defer_aggregator_deltas: bool,
 }
 
 impl AptosVMImpl {
@@ -62,6 +66,7 @@ impl AptosVMImpl {
             version: None,
             publishing_option: None,
             chain_account_info: None,
+            defer_aggregator_deltas: false,
         };
         vm.load_configs_impl(&RemoteStorage::new(state));
         vm.chain_account_info = Self::get_chain_specific_account_info(&RemoteStorage::new(state));
@@ -81,6 +86,7 @@ impl AptosVMImpl {
             version: Some(version),
             publishing_option: Some(publishing_option),
             chain_account_info: None,
+            defer_aggregator_deltas: false,
         }
     }
 
@@ -89,6 +95,14 @@ impl AptosVMImpl {
         AptosVMInternals(self)
     }
 
+    pub(crate) fn defer_aggregator_deltas(&self) -> bool {
+        self.defer_aggregator_deltas
+    }
+
+    pub(crate) fn set_defer_aggregator_deltas(&mut self, defer_aggregator_deltas: bool) {
+        self.defer_aggregator_deltas = defer_aggregator_deltas;
+    }
+
     pub(crate) fn chain_info(&self) -> &ChainSpecificAccountInfo {
         self.chain_account_info
             .as_ref()
@@ -634,18 +648,16 @@ pub(crate) fn get_transaction_output<A: AccessPathCache, S: MoveResolver>(
     gas_left: GasUnits<GasCarrier>,
     txn_data: &TransactionMetadata,
     status: KeptVMStatus,
-) -> Result<TransactionOutput, VMStatus> {
+) -> Result<TransactionOutputExt, VMStatus> {
     let gas_used: u64 = txn_data.max_gas_amount().sub(gas_left).get();
 
     let session_out = session.finish().map_err(|e| e.into_vm_status())?;
-    let (write_set, events) = session_out.into_change_set(ap_cache)?.into_inner();
-
-    Ok(TransactionOutput::new(
-        write_set,
-        events,
-        gas_used,
-        TransactionStatus::Keep(status),
-    ))
+    let (change_set, delta_change_set) = session_out.into_change_set_ext(ap_cache)?;
+    let (write_set, events) = change_set.into_inner();
+
+    let txn_output =
+        TransactionOutput::new(write_set, events, gas_used, TransactionStatus::Keep(status));
+    Ok(TransactionOutputExt::new(delta_change_set, txn_output))
 }
 
 pub(crate) fn get_gas_currency_code(txn: &SignedTransaction) -> Result<Identifier, VMStatus> {
@@ -71,3 +71,39 @@ pub static TXN_GAS_USAGE: Lazy<Histogram> = Lazy::new(|| {
 pub static CRITICAL_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!("aptos_vm_critical_errors", "Number of critical errors").unwrap()
 });
+
+pub static PARALLEL_EXECUTION_INCARNATIONS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_vm_parallel_execution_incarnations",
+        "Number of incarnations executed per transaction in parallel execution"
+    )
+    .unwrap()
+});
+
+/// Count the number of transactions aborted by a failed validation in parallel execution.
+pub static PARALLEL_EXECUTION_VALIDATION_ABORTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_vm_parallel_execution_validation_aborts",
+        "Number of validation aborts in parallel execution"
+    )
+    .unwrap()
+});
+
+/// Count the number of times a transaction waited on a dependency in parallel execution.
+pub static PARALLEL_EXECUTION_DEPENDENCY_WAITS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_vm_parallel_execution_dependency_waits",
+        "Number of dependency waits in parallel execution"
+    )
+    .unwrap()
+});
+
+/// Count the number of blocks that fell back to sequential execution, with a "reason" label.
+pub static PARALLEL_EXECUTION_FALLBACKS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_vm_parallel_execution_fallbacks",
+        "Number of blocks re-executed sequentially after parallel execution",
+        &["reason"]
+    )
+    .unwrap()
+});
@@ -7,18 +7,23 @@ use crate::{
     access_path_cache::AccessPathCache, aptos_vm_impl::convert_changeset_and_events_cached,
     natives::aptos_natives, transaction_metadata::TransactionMetadata,
 };
+use aptos_aggregator::delta_change_set::{serialize, DeltaChangeSet};
 use aptos_crypto::{hash::CryptoHash, HashValue};
 use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
 use aptos_types::{
     block_metadata::BlockMetadata,
     transaction::{ChangeSet, SignatureCheckedTransaction},
+    write_set::WriteOp,
+};
+use framework::natives::aggregator::{
+    aggregator_value_access_path, AggregatorChange, NativeAggregatorContext,
 };
 use move_binary_format::errors::VMResult;
 use move_core_types::{
     account_address::AccountAddress,
     effects::{ChangeSet as MoveChangeSet, Event as MoveEvent},
     resolver::MoveResolver,
-    vm_status::VMStatus,
+    vm_status::{StatusCode, VMStatus},
 };
 use move_vm_runtime::{
     move_vm::MoveVM, native_functions::NativeContextExtensions, session::Session,
@@ -43,10 +48,11 @@ impl MoveVmExt {
     pub fn new_session<'r, S: MoveResolver>(
         &self,
         remote: &'r S,
-        _session_id: SessionId,
+        session_id: SessionId,
     ) -> SessionExt<'r, '_, S> {
         // TODO: install table extension
-        let extensions = NativeContextExtensions::default();
+        let mut extensions = NativeContextExtensions::default();
+        extensions.add(NativeAggregatorContext::new(session_id.as_uuid()));
 
         SessionExt {
             inner: self.inner.new_session_with_extensions(remote, extensions),
@@ -157,13 +163,56 @@ pub struct SessionOutput {
 }
 
 impl SessionOutput {
+    /// Converts the session output into a change set. Fails if the session produced aggregator
+    /// deltas, use `into_change_set_ext` for sessions that may update aggregators.
     pub fn into_change_set<C: AccessPathCache>(
         self,
         ap_cache: &mut C,
     ) -> Result<ChangeSet, VMStatus> {
+        let (change_set, delta_change_set) = self.into_change_set_ext(ap_cache)?;
+        if !delta_change_set.is_empty() {
+            return Err(VMStatus::Error(
+                StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+            ));
+        }
+        Ok(change_set)
+    }
+
+    /// Converts the session output into a change set, which includes aggregators created or
+    /// destroyed by the session, and the aggregator deltas that still need to be applied.
+    pub fn into_change_set_ext<C: AccessPathCache>(
+        self,
+        ap_cache: &mut C,
+    ) -> Result<(ChangeSet, DeltaChangeSet), VMStatus> {
+        let Self {
+            change_set,
+            events,
+            mut extensions,
+        } = self;
         // TODO: consider table change set from the table extension
-        convert_changeset_and_events_cached(ap_cache, self.change_set, self.events)
-            .map(|(write_set, events)| ChangeSet::new(write_set, events))
+        let (write_set, events) =
+            convert_changeset_and_events_cached(ap_cache, change_set, events)?;
+
+        let mut write_set_mut = write_set.into_mut();
+        let mut delta_change_set = DeltaChangeSet::empty();
+        let aggregator_changes = extensions
+            .remove::<NativeAggregatorContext>()
+            .into_changes();
+        for (handle, change) in aggregator_changes {
+            let access_path = aggregator_value_access_path(handle);
+            match change {
+                AggregatorChange::Write(value) => {
+                    write_set_mut.push((access_path, WriteOp::Value(serialize(&value))))
+                }
+                AggregatorChange::Delete => write_set_mut.push((access_path, WriteOp::Deletion)),
+                AggregatorChange::Delta(delta) => delta_change_set.push((access_path, delta)),
+            }
+        }
+        let write_set = write_set_mut
+            .freeze()
+            .map_err(|_| VMStatus::Error(StatusCode::DATA_FORMAT_ERROR))?;
+
+        Ok((ChangeSet::new(write_set, events), delta_change_set))
     }
 
     pub fn unpack(self) -> (MoveChangeSet, Vec<MoveEvent>, NativeContextExtensions) {
@@ -8,11 +8,22 @@ mod vm_wrapper;
 use crate::{
     adapter_common::{preprocess_transaction, PreprocessedTransaction},
     aptos_vm::AptosVM,
+    counters::{
+        PARALLEL_EXECUTION_DEPENDENCY_WAITS, PARALLEL_EXECUTION_FALLBACKS,
+        PARALLEL_EXECUTION_INCARNATIONS, PARALLEL_EXECUTION_VALIDATION_ABORTS,
+    },
     parallel_executor::vm_wrapper::DiemVMWrapper,
 };
+use anyhow::bail;
+use aptos_aggregator::{
+    delta_change_set::{deserialize, serialize, DeltaOp},
+    transaction::TransactionOutputExt,
+};
+use aptos_logger::prelude::*;
 use aptos_parallel_executor::{
     errors::Error,
     executor::ParallelTransactionExecutor,
+    stats::ExecutionStats,
     task::{Transaction as PTransaction, TransactionOutput as PTransactionOutput},
 };
 use aptos_state_view::StateView;
@@ -22,22 +33,78 @@ use aptos_types::{
     write_set::{WriteOp, WriteSet},
 };
 use move_core_types::vm_status::{StatusCode, VMStatus};
+use once_cell::sync::Lazy;
 use rayon::prelude::*;
+use std::sync::Arc;
+
+/// Thread pool the parallel executor runs on, sized to AptosVM::get_concurrency_level.
+static RAYON_EXEC_POOL: Lazy<rayon::ThreadPool> = Lazy::new(|| {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(AptosVM::get_concurrency_level())
+        .thread_name(|index| format!("par_exec_{}", index))
+        .build()
+        .unwrap()
+});
 
 impl PTransaction for PreprocessedTransaction {
     type Key = AccessPath;
     type Value = WriteOp;
 }
 
+/// Applies an aggregated delta to the serialized value of an aggregator.
+pub(crate) fn apply_delta(base: Option<Vec<u8>>, delta: &DeltaOp) -> anyhow::Result<Vec<u8>> {
+    match base {
+        Some(bytes) => Ok(serialize(&delta.apply_to(deserialize(&bytes)?)?)),
+        None => bail!("Aggregator value to apply {:?} to does not exist", delta),
+    }
+}
+
 // Wrapper to avoid orphan rule
-pub(crate) struct DiemTransactionOutput(TransactionOutput);
+pub(crate) struct DiemTransactionOutput {
+    output: TransactionOutputExt,
+    /// Deltas aggregated up to this transaction, with the value they apply to (None if the
+    /// value is in storage). Populated once the transaction is committed.
+    delta_writes: Vec<(AccessPath, Option<Arc<WriteOp>>, DeltaOp)>,
+}
 
 impl DiemTransactionOutput {
-    pub fn new(output: TransactionOutput) -> Self {
-        Self(output)
+    pub fn new(output: TransactionOutputExt) -> Self {
+        Self {
+            output,
+            delta_writes: vec![],
+        }
     }
-    pub fn into(self) -> TransactionOutput {
-        self.0
+
+    /// Turns the aggregator deltas into regular writes, reading values that were not written
+    /// during the block from `state_view`.
+    pub fn into_transaction_output<S: StateView>(
+        self,
+        state_view: &S,
+    ) -> anyhow::Result<TransactionOutput> {
+        let (_, output) = self.output.into();
+        if self.delta_writes.is_empty() {
+            return Ok(output);
+        }
+
+        let (write_set, events, gas_used, status) = output.unpack();
+        let mut write_set_mut = write_set.into_mut();
+        for (access_path, base, delta) in self.delta_writes {
+            let base = match base {
+                Some(write_op) => match write_op.as_ref() {
+                    WriteOp::Value(bytes) => Some(bytes.clone()),
+                    WriteOp::Deletion => None,
+                },
+                None => state_view.get_by_access_path(&access_path)?,
+            };
+            let value = apply_delta(base, &delta)?;
+            write_set_mut.push((access_path, WriteOp::Value(value)));
+        }
+        Ok(TransactionOutput::new(
+            write_set_mut.freeze()?,
+            events,
+            gas_used,
+            status,
+        ))
     }
 }
 
@@ -45,17 +112,33 @@ impl PTransactionOutput for DiemTransactionOutput {
     type T = PreprocessedTransaction;
 
     fn get_writes(&self) -> Vec<(AccessPath, WriteOp)> {
-        self.0.write_set().iter().cloned().collect()
+        self.output
+            .txn_output()
+            .write_set()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    fn get_deltas(&self) -> Vec<(AccessPath, DeltaOp)> {
+        self.output.delta_change_set().iter().cloned().collect()
+    }
+
+    fn incorporate_delta_writes(
+        &mut self,
+        delta_writes: Vec<(AccessPath, Option<Arc<WriteOp>>, DeltaOp)>,
+    ) {
+        self.delta_writes = delta_writes;
     }
 
     /// Execution output for transactions that comes after SkipRest signal.
     fn skip_output() -> Self {
-        Self(TransactionOutput::new(
+        Self::new(TransactionOutputExt::from(TransactionOutput::new(
             WriteSet::default(),
             vec![],
             0,
             TransactionStatus::Retry,
-        ))
+        )))
     }
 }
 
@@ -74,30 +157,97 @@ impl ParallelAptosVM {
             .map(|txn| preprocess_transaction::<AptosVM>(txn.clone()))
             .collect();
 
-        match ParallelTransactionExecutor::<PreprocessedTransaction, DiemVMWrapper<S>>::new()
-            .execute_transactions_parallel(state_view, signature_verified_block)
-        {
-            Ok(results) => Ok((
-                results
+        let executor =
+            ParallelTransactionExecutor::<PreprocessedTransaction, DiemVMWrapper<S>>::with_concurrency_level(
+                AptosVM::get_concurrency_level(),
+            );
+        let result = RAYON_EXEC_POOL.install(|| {
+            executor.execute_transactions_parallel(state_view, signature_verified_block)
+        });
+        if let Some(stats) = executor.last_execution_stats() {
+            Self::record_execution_stats(&stats);
+        }
+
+        match result {
+            Ok(results) => {
+                let outputs: anyhow::Result<Vec<TransactionOutput>> = results
                     .into_iter()
-                    .map(DiemTransactionOutput::into)
-                    .collect(),
-                None,
-            )),
-            Err(err @ Error::InferencerError) | Err(err @ Error::UnestimatedWrite) => {
-                let output = AptosVM::execute_block_and_keep_vm_status(transactions, state_view)?;
-                Ok((
-                    output
-                        .into_iter()
-                        .map(|(_vm_status, txn_output)| txn_output)
-                        .collect(),
-                    Some(err),
-                ))
+                    .map(|output| output.into_transaction_output(state_view))
+                    .collect();
+                match outputs {
+                    Ok(outputs) => Ok((outputs, None)),
+                    // Deltas of committed transactions can only be checked against the final
+                    // values, re-execute sequentially to find the failing transactions.
+                    Err(_) => Self::execute_block_sequentially(
+                        transactions,
+                        state_view,
+                        Error::DeltaApplicationFailure,
+                        "delta_application_failure",
+                    ),
+                }
+            }
+            Err(err @ Error::InferencerError) => {
+                Self::execute_block_sequentially(transactions, state_view, err, "inferencer_error")
             }
+            Err(err @ Error::UnestimatedWrite) => {
+                Self::execute_block_sequentially(transactions, state_view, err, "unestimated_write")
+            }
+            Err(err @ Error::DeltaApplicationFailure) => Self::execute_block_sequentially(
+                transactions,
+                state_view,
+                err,
+                "delta_application_failure",
+            ),
             Err(Error::InvariantViolation) => Err(VMStatus::Error(
                 StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
             )),
             Err(Error::UserError(err)) => Err(err),
         }
     }
+
+    fn record_execution_stats(stats: &ExecutionStats<AccessPath>) {
+        for incarnations in &stats.incarnations {
+            PARALLEL_EXECUTION_INCARNATIONS.observe(*incarnations as f64);
+        }
+        PARALLEL_EXECUTION_VALIDATION_ABORTS.inc_by(stats.validation_aborts as u64);
+        PARALLEL_EXECUTION_DEPENDENCY_WAITS.inc_by(stats.dependency_waits as u64);
+        if !stats.top_conflicting_keys.is_empty() {
+            debug!(
+                "Parallel execution of {} transactions: {} incarnations, {} validation aborts, \
+                 {} dependency waits, top conflicting keys: {:?}",
+                stats.incarnations.len(),
+                stats.total_incarnations(),
+                stats.validation_aborts,
+                stats.dependency_waits,
+                stats.top_conflicting_keys,
+            );
+        }
+    }
+
+    /// Fallback when the block can not be executed in parallel, `err` is reported alongside
+    /// the outputs and `reason` labels the fallback counter.
+    fn execute_block_sequentially<S: StateView>(
+        transactions: Vec<Transaction>,
+        state_view: &S,
+        err: Error<VMStatus>,
+        reason: &'static str,
+    ) -> Result<(Vec<TransactionOutput>, Option<Error<VMStatus>>), VMStatus> {
+        PARALLEL_EXECUTION_FALLBACKS
+            .with_label_values(&[reason])
+            .inc();
+        warn!(
+            "Falling back to sequential execution of {} transactions: {}",
+            transactions.len(),
+            reason
+        );
+
+        let output = AptosVM::execute_block_and_keep_vm_status(transactions, state_view)?;
+        Ok((
+            output
+                .into_iter()
+                .map(|(_vm_status, txn_output)| txn_output)
+                .collect(),
+            Some(err),
+        ))
+    }
 }
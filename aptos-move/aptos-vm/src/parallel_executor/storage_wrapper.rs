@@ -1,8 +1,8 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::data_cache::RemoteStorage;
-use aptos_parallel_executor::executor::MVHashMapView;
+use crate::{data_cache::RemoteStorage, parallel_executor::apply_delta};
+use aptos_parallel_executor::executor::{MVHashMapView, ReadResult};
 use aptos_state_view::{StateView, StateViewId};
 use aptos_types::{access_path::AccessPath, state_store::state_key::StateKey, write_set::WriteOp};
 use move_binary_format::errors::VMError;
@@ -37,11 +37,24 @@ impl<'a, S: StateView> StateView for VersionedView<'a, S> {
     // Get some data either through the cache or the `StateView` on a cache miss.
     fn get_by_access_path(&self, access_path: &AccessPath) -> anyhow::Result<Option<Vec<u8>>> {
         match self.hashmap_view.read(access_path) {
-            Ok(Some(v)) => Ok(match v.as_ref() {
+            Ok(ReadResult::Value(v)) => Ok(match v.as_ref() {
                 WriteOp::Value(w) => Some(w.clone()),
                 WriteOp::Deletion => None,
             }),
-            Ok(None) => self.base_view.get_by_access_path(access_path),
+            Ok(ReadResult::Delta(base, delta)) => {
+                let base = match base {
+                    Some(v) => match v.as_ref() {
+                        WriteOp::Value(w) => Some(w.clone()),
+                        WriteOp::Deletion => None,
+                    },
+                    None => self.base_view.get_by_access_path(access_path)?,
+                };
+                apply_delta(base, &delta).map(Some).map_err(|err| {
+                    self.hashmap_view.mark_delta_application_failure();
+                    err
+                })
+            }
+            Ok(ReadResult::None) => self.base_view.get_by_access_path(access_path),
             Err(err) => Err(err),
         }
     }
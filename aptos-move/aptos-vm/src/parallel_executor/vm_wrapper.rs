@@ -31,7 +31,9 @@ impl<'a, S: 'a + StateView> ExecutorTask for DiemVMWrapper<'a, S> {
     type Argument = &'a S;
 
     fn init(argument: &'a S) -> Self {
-        let vm = AptosVM::new(argument);
+        let mut vm = AptosVM::new(argument);
+        // Aggregator deltas are resolved by the parallel executor once transactions commit.
+        vm.0.set_defer_aggregator_deltas(true);
 
         // Loading `0x1::DiemAccount` and its transitive dependency into the code cache.
         //
@@ -62,7 +64,7 @@ impl<'a, S: 'a + StateView> ExecutorTask for DiemVMWrapper<'a, S> {
             .execute_single_transaction(txn, &versioned_view, &log_context)
         {
             Ok((vm_status, output, sender)) => {
-                if output.status().is_discarded() {
+                if output.txn_output().status().is_discarded() {
                     match sender {
                         Some(s) => trace!(
                             log_context,
@@ -75,7 +77,7 @@ impl<'a, S: 'a + StateView> ExecutorTask for DiemVMWrapper<'a, S> {
                         }
                     };
                 }
-                if AptosVM::should_restart_execution(&output) {
+                if AptosVM::should_restart_execution(output.txn_output()) {
                     ExecutionStatus::SkipRest(DiemTransactionOutput::new(output))
                 } else {
                     ExecutionStatus::Success(DiemTransactionOutput::new(output))
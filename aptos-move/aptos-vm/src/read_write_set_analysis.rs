@@ -174,7 +174,7 @@ impl<'a, R: MoveResolver> ReadWriteSetAnalysis<'a, R> {
                 self.get_keys_user_transaction_impl(tx, concretize)
             }
             PreprocessedTransaction::BlockMetadata(block_metadata) => {
-                let (round, timestamp, previous_vote, proposer) =
+                let (round, timestamp, previous_vote, proposer, failed_proposers) =
                     block_metadata.clone().into_inner();
                 let args = serialize_values(&vec![
                     MoveValue::Signer(account_config::reserved_vm_address()),
@@ -182,6 +182,12 @@ impl<'a, R: MoveResolver> ReadWriteSetAnalysis<'a, R> {
                     MoveValue::U64(timestamp),
                     MoveValue::Vector(previous_vote.into_iter().map(MoveValue::Address).collect()),
                     MoveValue::Address(proposer),
+                    MoveValue::Vector(
+                        failed_proposers
+                            .into_iter()
+                            .map(MoveValue::Address)
+                            .collect(),
+                    ),
                 ]);
                 let metadata_access = self.get_partially_concretized_summary(
                     &BLOCK_MODULE,
@@ -484,13 +484,20 @@ impl<'env> TraceReplayer<'env> {
         // args
         let signer = reserved_vm_address();
         let session_id = SessionId::block_meta(&block_metadata);
-        let (round, timestamp, previous_votes, proposer) = block_metadata.into_inner();
+        let (round, timestamp, previous_votes, proposer, failed_proposers) =
+            block_metadata.into_inner();
         let args: Vec<_> = vec![
             MoveValue::Signer(signer),
             MoveValue::U64(round),
             MoveValue::U64(timestamp),
             MoveValue::Vector(previous_votes.into_iter().map(MoveValue::Address).collect()),
             MoveValue::Address(proposer),
+            MoveValue::Vector(
+                failed_proposers
+                    .into_iter()
+                    .map(MoveValue::Address)
+                    .collect(),
+            ),
         ]
         .into_iter()
         .map(|v| v.simple_serialize().unwrap())
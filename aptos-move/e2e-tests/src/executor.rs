@@ -479,6 +479,7 @@ impl FakeExecutor {
             self.block_time,
             vec![],
             *validator_set.payload()[0].account_address(),
+            vec![],
         );
         let output = self
             .execute_transaction_block(vec![Transaction::BlockMetadata(new_block)])
@@ -0,0 +1,205 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{SignedTransaction, TransactionStatus},
+    vm_status::KeptVMStatus,
+};
+use language_e2e_tests::{
+    account::AccountData,
+    compile::{compile_module, compile_script},
+    executor::FakeExecutor,
+};
+use move_binary_format::CompiledModule;
+
+/// The upper bound of the counter aggregator.
+const COUNTER_LIMIT: u128 = 100;
+/// Abort code of `AptosFramework::Aggregator` when an addition exceeds the limit.
+const EAGGREGATOR_OVERFLOW: u64 = 1;
+
+/// Publishes a module wrapping an aggregator in a `Counter` resource, and the resource itself.
+fn publish_counter(executor: &mut FakeExecutor, owner: &AccountData) -> CompiledModule {
+    let module_code = format!(
+        "
+        module 0x{}.Counter {{
+            import 0x1.Aggregator;
+            struct Counter has key {{ aggregator: Aggregator.Aggregator }}
+
+            public publish(account: &signer) {{
+            label b0:
+                move_to<Counter>(move(account), Counter {{ aggregator: Aggregator.create({}u128) }});
+                return;
+            }}
+
+            public increment(addr: address, value: u128) acquires Counter {{
+                let counter: &mut Self.Counter;
+            label b0:
+                counter = borrow_global_mut<Counter>(move(addr));
+                Aggregator.add(&mut move(counter).Counter::aggregator, move(value));
+                return;
+            }}
+
+            public value(addr: address): u128 acquires Counter {{
+                let counter: &Self.Counter;
+            label b0:
+                counter = borrow_global<Counter>(move(addr));
+                return Aggregator.read(&move(counter).Counter::aggregator);
+            }}
+        }}
+        ",
+        owner.address(),
+        COUNTER_LIMIT,
+    );
+    let (compiled_module, module) = compile_module(&module_code);
+    let txn = owner
+        .account()
+        .transaction()
+        .module(module)
+        .sequence_number(owner.sequence_number())
+        .sign();
+    executor.execute_and_apply(txn);
+
+    let script_code = format!(
+        "
+        import 0x{}.Counter;
+
+        main(account: signer) {{
+        label b0:
+            Counter.publish(&account);
+            return;
+        }}
+        ",
+        owner.address(),
+    );
+    let txn = owner
+        .account()
+        .transaction()
+        .script(compile_script(&script_code, vec![compiled_module.clone()]))
+        .sequence_number(owner.sequence_number() + 1)
+        .sign();
+    executor.execute_and_apply(txn);
+
+    compiled_module
+}
+
+fn increment_txn(
+    sender: &AccountData,
+    sequence_number: u64,
+    owner: AccountAddress,
+    value: u128,
+    counter: &CompiledModule,
+) -> SignedTransaction {
+    let script_code = format!(
+        "
+        import 0x{}.Counter;
+
+        main(account: signer) {{
+        label b0:
+            Counter.increment(0x{}, {}u128);
+            return;
+        }}
+        ",
+        owner, owner, value,
+    );
+    sender
+        .account()
+        .transaction()
+        .script(compile_script(&script_code, vec![counter.clone()]))
+        .sequence_number(sequence_number)
+        .sign()
+}
+
+fn check_value_txn(
+    sender: &AccountData,
+    sequence_number: u64,
+    owner: AccountAddress,
+    expected_value: u128,
+    counter: &CompiledModule,
+) -> SignedTransaction {
+    let script_code = format!(
+        "
+        import 0x{}.Counter;
+
+        main(account: signer) {{
+        label b0:
+            assert(Counter.value(0x{}) == {}u128, 42);
+            return;
+        }}
+        ",
+        owner, owner, expected_value,
+    );
+    sender
+        .account()
+        .transaction()
+        .script(compile_script(&script_code, vec![counter.clone()]))
+        .sequence_number(sequence_number)
+        .sign()
+}
+
+fn assert_executed(status: &TransactionStatus) {
+    assert_eq!(status, &TransactionStatus::Keep(KeptVMStatus::Executed));
+}
+
+#[test]
+fn aggregator_concurrent_increments() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let owner = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&owner);
+    let counter = publish_counter(&mut executor, &owner);
+
+    // Many senders increment the same aggregator in a single block. The block is executed both
+    // sequentially and in parallel, and the outputs are checked to be identical.
+    let senders: Vec<_> = (0..10)
+        .map(|_| {
+            let sender = executor.create_raw_account_data(1_000_000, 0);
+            executor.add_account_data(&sender);
+            sender
+        })
+        .collect();
+    let mut block = vec![];
+    for sequence_number in 0..5 {
+        for sender in &senders {
+            block.push(increment_txn(
+                sender,
+                sequence_number,
+                *owner.address(),
+                1,
+                &counter,
+            ));
+        }
+    }
+    let outputs = executor.execute_block(block).unwrap();
+    for output in outputs {
+        assert_executed(output.status());
+        executor.apply_write_set(output.write_set());
+    }
+
+    // The committed value includes every increment.
+    let txn = check_value_txn(&senders[0], 5, *owner.address(), 50, &counter);
+    executor.execute_and_apply(txn);
+
+    // Increments and reads of the aggregator in the same block observe each other.
+    let block = vec![
+        increment_txn(&senders[0], 6, *owner.address(), 25, &counter),
+        check_value_txn(&senders[1], 5, *owner.address(), 75, &counter),
+        increment_txn(&senders[2], 5, *owner.address(), 25, &counter),
+        check_value_txn(&senders[3], 5, *owner.address(), COUNTER_LIMIT, &counter),
+    ];
+    let outputs = executor.execute_block(block).unwrap();
+    for output in outputs {
+        assert_executed(output.status());
+        executor.apply_write_set(output.write_set());
+    }
+
+    // Increments beyond the limit abort, leaving the value untouched.
+    let txn = increment_txn(&senders[4], 5, *owner.address(), 1, &counter);
+    let output = executor.execute_transaction(txn);
+    executor.apply_write_set(output.write_set());
+    assert!(matches!(
+        output.status(),
+        TransactionStatus::Keep(KeptVMStatus::MoveAbort(_, EAGGREGATOR_OVERFLOW))
+    ));
+    let txn = check_value_txn(&senders[5], 5, *owner.address(), COUNTER_LIMIT, &counter);
+    executor.execute_and_apply(txn);
+}
@@ -38,14 +38,16 @@ fn failed_transaction_cleanup_test() {
         let mut gas_status = GasStatus::new(&gas_schedule, GasUnits::new(10_000));
 
         // TYPE_MISMATCH should be kept and charged.
-        let out1 = aptos_vm.failed_transaction_cleanup(
-            VMStatus::Error(StatusCode::TYPE_MISMATCH),
-            &mut gas_status,
-            &txn_data,
-            &data_cache,
-            &account::xus_currency_code(),
-            &log_context,
-        );
+        let (_, out1) = aptos_vm
+            .failed_transaction_cleanup(
+                VMStatus::Error(StatusCode::TYPE_MISMATCH),
+                &mut gas_status,
+                &txn_data,
+                &data_cache,
+                &account::xus_currency_code(),
+                &log_context,
+            )
+            .into();
         assert!(!out1.write_set().is_empty());
         assert_eq!(out1.gas_used(), 90_000);
         assert!(!out1.status().is_discarded());
@@ -56,14 +58,16 @@ fn failed_transaction_cleanup_test() {
         );
 
         // Invariant violations should be discarded and not charged.
-        let out2 = aptos_vm.failed_transaction_cleanup(
-            VMStatus::Error(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR),
-            &mut gas_status,
-            &txn_data,
-            &data_cache,
-            &account::xus_currency_code(),
-            &log_context,
-        );
+        let (_, out2) = aptos_vm
+            .failed_transaction_cleanup(
+                VMStatus::Error(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR),
+                &mut gas_status,
+                &txn_data,
+                &data_cache,
+                &account::xus_currency_code(),
+                &log_context,
+            )
+            .into();
         assert!(out2.write_set().is_empty());
         assert!(out2.gas_used() == 0);
         assert!(out2.status().is_discarded());
@@ -0,0 +1,67 @@
+/// This module provides an integer that can be concurrently incremented and decremented by
+/// many transactions without conflicts during parallel execution. Updates are recorded as
+/// deltas, which are only applied to the stored value when the transaction is committed.
+///
+/// Additions abort if the value would exceed the limit of the aggregator, subtractions abort
+/// if the value would go below zero.
+module AptosFramework::Aggregator {
+    /// Handle to an aggregator. The value lives in an `AggregatorValue` resource stored at the
+    /// handle address, so reading it creates a dependency on all prior updates.
+    struct Aggregator has store {
+        handle: address,
+        limit: u128,
+    }
+
+    /// Value of an aggregator.
+    struct AggregatorValue has key {
+        value: u128,
+    }
+
+    /// Creates a new aggregator with the value zero and the given upper bound.
+    public fun create(limit: u128): Aggregator {
+        Aggregator {
+            handle: new_handle(),
+            limit,
+        }
+    }
+
+    /// Adds `value` to the aggregator, aborting on overflow.
+    public fun add(aggregator: &mut Aggregator, value: u128) {
+        add_delta(aggregator.handle, aggregator.limit, value)
+    }
+
+    /// Subtracts `value` from the aggregator, aborting on underflow.
+    public fun sub(aggregator: &mut Aggregator, value: u128) {
+        sub_delta(aggregator.handle, aggregator.limit, value)
+    }
+
+    /// Returns the current value of the aggregator. Unlike `add` and `sub`, reading serializes
+    /// the transaction after all prior transactions that updated the aggregator.
+    public fun read(aggregator: &Aggregator): u128 acquires AggregatorValue {
+        let handle = aggregator.handle;
+        let base = if (exists<AggregatorValue>(handle)) {
+            borrow_global<AggregatorValue>(handle).value
+        } else {
+            // Aggregators created by the current transaction are not published yet.
+            0
+        };
+        apply_pending(handle, base)
+    }
+
+    /// Returns the upper bound of the aggregator.
+    public fun limit(aggregator: &Aggregator): u128 {
+        aggregator.limit
+    }
+
+    /// Destroys the aggregator and its value.
+    public fun destroy(aggregator: Aggregator) {
+        let Aggregator { handle, limit: _ } = aggregator;
+        destroy_handle(handle)
+    }
+
+    native fun new_handle(): address;
+    native fun add_delta(handle: address, limit: u128, value: u128);
+    native fun sub_delta(handle: address, limit: u128, value: u128);
+    native fun apply_pending(handle: address, base: u128): u128;
+    native fun destroy_handle(handle: address);
+}
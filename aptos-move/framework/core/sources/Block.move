@@ -20,6 +20,9 @@ module CoreFramework::Block {
 
         /// On-chain time during  he block at the given height
         time_microseconds: u64,
+        /// The proposers elected for the rounds right before this one that failed to get their
+        /// block certified, by increasing round
+        failed_proposers: vector<address>,
     }
 
     /// The `BlockMetadata` resource is in an invalid state
@@ -56,7 +59,8 @@ module CoreFramework::Block {
         round: u64,
         timestamp: u64,
         previous_block_votes: vector<address>,
-        proposer: address
+        proposer: address,
+        failed_proposers: vector<address>
     ) acquires BlockMetadata {
         Timestamp::assert_operating();
         // Operational constraint: can only be invoked by the VM.
@@ -78,6 +82,7 @@ module CoreFramework::Block {
                 proposer,
                 previous_block_votes,
                 time_microseconds: timestamp,
+                failed_proposers,
             }
         );
     }
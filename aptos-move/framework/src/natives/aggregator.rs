@@ -0,0 +1,274 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Natives backing `AptosFramework::Aggregator`. Additions and subtractions are not applied to
+//! the stored value directly but recorded as deltas in the `NativeAggregatorContext` extension,
+//! so that transactions updating the same aggregator do not conflict during parallel execution.
+
+use aptos_aggregator::delta_change_set::DeltaOp;
+use aptos_crypto::HashValue;
+use aptos_types::{access_path::AccessPath, account_config::CORE_CODE_ADDRESS};
+use move_binary_format::errors::PartialVMResult;
+use move_core_types::{
+    account_address::AccountAddress,
+    gas_schedule::{GasAlgebra, GasCarrier, InternalGasUnits},
+    identifier::Identifier,
+    language_storage::{ModuleId, ResourceKey, StructTag},
+};
+use move_vm_runtime::native_functions::NativeContext;
+use move_vm_types::{
+    loaded_data::runtime_types::Type, natives::function::NativeResult, pop_arg, values::Value,
+};
+use smallvec::smallvec;
+use std::collections::{btree_map::Entry, BTreeMap, VecDeque};
+
+/// Abort code when an addition exceeds the limit of the aggregator.
+pub const EAGGREGATOR_OVERFLOW: u64 = 1;
+/// Abort code when a subtraction goes below zero.
+pub const EAGGREGATOR_UNDERFLOW: u64 = 2;
+/// Abort code when the deltas of a transaction can not be applied to the stored value.
+pub const EAGGREGATOR_DELTA_APPLICATION: u64 = 3;
+/// Abort code when an aggregator is used after being destroyed.
+pub const EAGGREGATOR_NOT_FOUND: u64 = 4;
+
+// The native gas schedule only has entries for the natives of the Move standard library, so the
+// aggregator natives are priced here. Creating an aggregator additionally pays for the storage slot
+// of its value, see `native_new_handle`.
+const NEW_HANDLE_COST: GasCarrier = 200;
+const UPDATE_COST: GasCarrier = 100;
+const APPLY_PENDING_COST: GasCarrier = 100;
+const DESTROY_COST: GasCarrier = 100;
+
+const AGGREGATOR_MODULE_NAME: &str = "Aggregator";
+const AGGREGATOR_VALUE_STRUCT_NAME: &str = "AggregatorValue";
+
+/// Id of the `AptosFramework::Aggregator` module.
+pub fn aggregator_module_id() -> ModuleId {
+    ModuleId::new(
+        CORE_CODE_ADDRESS,
+        Identifier::new(AGGREGATOR_MODULE_NAME).unwrap(),
+    )
+}
+
+/// Struct tag of the resource holding the value of an aggregator at its handle address.
+pub fn aggregator_value_tag() -> StructTag {
+    StructTag {
+        address: CORE_CODE_ADDRESS,
+        module: Identifier::new(AGGREGATOR_MODULE_NAME).unwrap(),
+        name: Identifier::new(AGGREGATOR_VALUE_STRUCT_NAME).unwrap(),
+        type_params: vec![],
+    }
+}
+
+/// Access path of the value of the aggregator with the given handle.
+pub fn aggregator_value_access_path(handle: AccountAddress) -> AccessPath {
+    AccessPath::resource_access_path(ResourceKey::new(handle, aggregator_value_tag()))
+}
+
+/// Change of an aggregator value made by a single transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AggregatorChange {
+    /// The aggregator was created by the transaction and holds the given value.
+    Write(u128),
+    /// The stored value is updated by the given delta.
+    Delta(DeltaOp),
+    /// The aggregator was destroyed by the transaction.
+    Delete,
+}
+
+/// Native context extension tracking aggregator changes of the current session, keyed by the
+/// aggregator handle.
+pub struct NativeAggregatorContext {
+    txn_hash: u128,
+    num_created: u64,
+    changes: BTreeMap<AccountAddress, AggregatorChange>,
+}
+
+impl NativeAggregatorContext {
+    /// Creates a new context for the session identified by `txn_hash`, which is used to derive
+    /// unique handles for new aggregators.
+    pub fn new(txn_hash: u128) -> Self {
+        Self {
+            txn_hash,
+            num_created: 0,
+            changes: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the changes recorded during the session.
+    pub fn into_changes(self) -> BTreeMap<AccountAddress, AggregatorChange> {
+        self.changes
+    }
+
+    fn new_handle(&mut self) -> AccountAddress {
+        let mut bytes = self.txn_hash.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.num_created.to_be_bytes());
+        self.num_created += 1;
+
+        let hash = HashValue::sha3_256_of(&bytes);
+        AccountAddress::from_bytes(&hash.as_ref()[..AccountAddress::LENGTH])
+            .expect("Hash must be long enough to derive an address")
+    }
+
+    fn update(
+        &mut self,
+        handle: AccountAddress,
+        limit: u128,
+        value: u128,
+        is_addition: bool,
+    ) -> Result<(), u64> {
+        let abort_code = if is_addition {
+            EAGGREGATOR_OVERFLOW
+        } else {
+            EAGGREGATOR_UNDERFLOW
+        };
+        match self.changes.entry(handle) {
+            Entry::Vacant(entry) => {
+                let delta = if is_addition {
+                    DeltaOp::addition(value, limit)
+                } else {
+                    DeltaOp::subtraction(value, limit)
+                };
+                entry.insert(AggregatorChange::Delta(delta.map_err(|_| abort_code)?));
+            }
+            Entry::Occupied(mut entry) => match entry.get_mut() {
+                AggregatorChange::Write(current) => {
+                    *current = if is_addition {
+                        current.checked_add(value).filter(|result| *result <= limit)
+                    } else {
+                        current.checked_sub(value)
+                    }
+                    .ok_or(abort_code)?;
+                }
+                AggregatorChange::Delta(delta) => {
+                    if is_addition {
+                        delta.add(value)
+                    } else {
+                        delta.sub(value)
+                    }
+                    .map_err(|_| abort_code)?;
+                }
+                AggregatorChange::Delete => return Err(EAGGREGATOR_NOT_FOUND),
+            },
+        }
+        Ok(())
+    }
+}
+
+fn aggregator_context<'a>(context: &'a mut NativeContext) -> &'a mut NativeAggregatorContext {
+    context
+        .extensions_mut()
+        .get_mut::<NativeAggregatorContext>()
+}
+
+pub fn native_new_handle(
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.is_empty());
+    debug_assert!(arguments.is_empty());
+
+    // A new aggregator value is written to storage, which is charged like a new resource.
+    let gas_constants = &context.cost_table().gas_constants;
+    let cost = InternalGasUnits::new(NEW_HANDLE_COST).add(
+        gas_constants
+            .global_memory_per_byte_write_cost
+            .mul(gas_constants.default_account_size),
+    );
+    let aggregator_context = aggregator_context(context);
+    let handle = aggregator_context.new_handle();
+    aggregator_context
+        .changes
+        .insert(handle, AggregatorChange::Write(0));
+    Ok(NativeResult::ok(cost, smallvec![Value::address(handle)]))
+}
+
+pub fn native_add(
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    native_update(context, ty_args, arguments, true)
+}
+
+pub fn native_sub(
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    native_update(context, ty_args, arguments, false)
+}
+
+fn native_update(
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+    is_addition: bool,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.is_empty());
+    debug_assert!(arguments.len() == 3);
+
+    let value = pop_arg!(arguments, u128);
+    let limit = pop_arg!(arguments, u128);
+    let handle = pop_arg!(arguments, AccountAddress);
+
+    let cost = InternalGasUnits::new(UPDATE_COST);
+    match aggregator_context(context).update(handle, limit, value, is_addition) {
+        Ok(()) => Ok(NativeResult::ok(cost, smallvec![])),
+        Err(abort_code) => Ok(NativeResult::err(cost, abort_code)),
+    }
+}
+
+pub fn native_apply_pending(
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.is_empty());
+    debug_assert!(arguments.len() == 2);
+
+    let base = pop_arg!(arguments, u128);
+    let handle = pop_arg!(arguments, AccountAddress);
+
+    let cost = InternalGasUnits::new(APPLY_PENDING_COST);
+    let value = match aggregator_context(context).changes.get(&handle) {
+        None => Ok(base),
+        Some(AggregatorChange::Write(value)) => Ok(*value),
+        Some(AggregatorChange::Delta(delta)) => delta
+            .apply_to(base)
+            .map_err(|_| EAGGREGATOR_DELTA_APPLICATION),
+        Some(AggregatorChange::Delete) => Err(EAGGREGATOR_NOT_FOUND),
+    };
+    match value {
+        Ok(value) => Ok(NativeResult::ok(cost, smallvec![Value::u128(value)])),
+        Err(abort_code) => Ok(NativeResult::err(cost, abort_code)),
+    }
+}
+
+pub fn native_destroy(
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.is_empty());
+    debug_assert!(arguments.len() == 1);
+
+    let handle = pop_arg!(arguments, AccountAddress);
+
+    let cost = InternalGasUnits::new(DESTROY_COST);
+    let changes = &mut aggregator_context(context).changes;
+    match changes.get(&handle) {
+        // Aggregators created and destroyed by the same transaction leave no trace.
+        Some(AggregatorChange::Write(_)) => {
+            changes.remove(&handle);
+        }
+        Some(AggregatorChange::Delete) => {
+            return Ok(NativeResult::err(cost, EAGGREGATOR_NOT_FOUND));
+        }
+        None | Some(AggregatorChange::Delta(_)) => {
+            changes.insert(handle, AggregatorChange::Delete);
+        }
+    }
+    Ok(NativeResult::ok(cost, smallvec![]))
+}
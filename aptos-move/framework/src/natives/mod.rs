@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod account;
+pub mod aggregator;
 pub mod signature;
 
 use move_core_types::{account_address::AccountAddress, identifier::Identifier};
@@ -31,6 +32,15 @@ pub fn all_natives(diem_framework_addr: AccountAddress) -> NativeFunctionTable {
             signature::native_ed25519_signature_verification,
         ),
         ("Account", "create_signer", account::native_create_signer),
+        ("Aggregator", "new_handle", aggregator::native_new_handle),
+        ("Aggregator", "add_delta", aggregator::native_add),
+        ("Aggregator", "sub_delta", aggregator::native_sub),
+        (
+            "Aggregator",
+            "apply_pending",
+            aggregator::native_apply_pending,
+        ),
+        ("Aggregator", "destroy_handle", aggregator::native_destroy),
     ];
     NATIVES
         .iter()
@@ -1,6 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+use aptos_aggregator::delta_change_set::DeltaOp;
 use crossbeam::utils::CachePadded;
 use dashmap::DashMap;
 use std::{
@@ -23,23 +24,38 @@ pub type Version = (TxnIndex, Incarnation);
 const FLAG_DONE: usize = 0;
 const FLAG_ESTIMATE: usize = 1;
 
-/// Type of entry, recorded in the shared multi-version data-structure for each write.
-struct WriteCell<V> {
+/// Contents of an entry: either a regular write or a commutative aggregator delta.
+enum EntryCell<V> {
+    /// Actual data stored in a shared pointer (to ensure ownership and avoid clones).
+    Write(Arc<V>),
+    /// Delta that is applied on top of the closest preceding write (or storage).
+    Delta(DeltaOp),
+}
+
+/// Type of entry, recorded in the shared multi-version data-structure for each write or delta.
+struct Entry<V> {
     /// Used to mark the entry as a "write estimate".
     flag: AtomicUsize,
     /// Incarnation number of the transaction that wrote the entry. Note that
     /// TxnIndex is part of the key and not recorded here.
     incarnation: Incarnation,
-    /// Actual data stored in a shared pointer (to ensure ownership and avoid clones).
-    data: Arc<V>,
+    cell: EntryCell<V>,
 }
 
-impl<V> WriteCell<V> {
-    pub fn new_from(flag: usize, incarnation: Incarnation, data: V) -> WriteCell<V> {
-        WriteCell {
+impl<V> Entry<V> {
+    pub fn new_write_from(flag: usize, incarnation: Incarnation, data: V) -> Entry<V> {
+        Entry {
             flag: AtomicUsize::new(flag),
             incarnation,
-            data: Arc::new(data),
+            cell: EntryCell::Write(Arc::new(data)),
+        }
+    }
+
+    pub fn new_delta_from(flag: usize, incarnation: Incarnation, delta: DeltaOp) -> Entry<V> {
+        Entry {
+            flag: AtomicUsize::new(flag),
+            incarnation,
+            cell: EntryCell::Delta(delta),
         }
     }
 
@@ -52,16 +68,40 @@ impl<V> WriteCell<V> {
     }
 }
 
+/// Successful outcome of a read from the multi-version data-structure.
+#[derive(Debug, PartialEq)]
+pub enum MVHashMapOutput<V> {
+    /// The closest preceding entry is a write with the given version.
+    Version(Version, Arc<V>),
+    /// The closest preceding write (with the given version) is followed by deltas, which
+    /// aggregate to the given delta that still needs to be applied to the written value.
+    Delta(Version, Arc<V>, DeltaOp),
+}
+
+/// Reads that can not be (fully) served from the multi-version data-structure.
+#[derive(Debug, PartialEq)]
+pub enum MVHashMapError {
+    /// No prior entry is found, the value should be read from storage.
+    NotFound,
+    /// Read resulted in a dependency on the given transaction, whose write is an estimate.
+    Dependency(TxnIndex),
+    /// Only deltas precede the read, the aggregated delta must be applied to the value
+    /// from storage.
+    Unresolved(DeltaOp),
+    /// Preceding deltas could not be aggregated because the bounds were violated.
+    DeltaApplicationFailure,
+}
+
 /// Main multi-version data-structure used by threads to read/write during parallel
 /// execution. Maps each access path to an interal BTreeMap that contains the indices
 /// of transactions that write at the given access path alongside the corresponding
-/// entries of WriteCell type.
+/// entries of Entry type.
 ///
 /// Concurrency is managed by DashMap, i.e. when a method accesses a BTreeMap at a
 /// given key, it holds exclusive access and doesn't need to explicitly synchronize
 /// with other reader/writers.
 pub struct MVHashMap<K, V> {
-    data: DashMap<K, BTreeMap<TxnIndex, CachePadded<WriteCell<V>>>>,
+    data: DashMap<K, BTreeMap<TxnIndex, CachePadded<Entry<V>>>>,
 }
 
 impl<K: Hash + Clone + Eq, V> MVHashMap<K, V> {
@@ -71,20 +111,36 @@ impl<K: Hash + Clone + Eq, V> MVHashMap<K, V> {
         }
     }
 
-    /// Write a versioned data at a specified key. If the WriteCell entry is overwritten,
+    /// Write a versioned data at a specified key. If the entry is overwritten,
     /// asserts that the new incarnation is strictly higher.
     pub fn write(&self, key: &K, version: Version, data: V) {
         let (txn_idx, incarnation) = version;
+        self.insert(
+            key,
+            txn_idx,
+            Entry::new_write_from(FLAG_DONE, incarnation, data),
+        );
+    }
 
-        let mut map = self.data.entry(key.clone()).or_insert(BTreeMap::new());
-        let prev_cell = map.insert(
+    /// Record a delta by a specified version at a specified key. If the entry is
+    /// overwritten, asserts that the new incarnation is strictly higher.
+    pub fn add_delta(&self, key: &K, version: Version, delta: DeltaOp) {
+        let (txn_idx, incarnation) = version;
+        self.insert(
+            key,
             txn_idx,
-            CachePadded::new(WriteCell::new_from(FLAG_DONE, incarnation, data)),
+            Entry::new_delta_from(FLAG_DONE, incarnation, delta),
         );
+    }
+
+    fn insert(&self, key: &K, txn_idx: TxnIndex, entry: Entry<V>) {
+        let incarnation = entry.incarnation;
+        let mut map = self.data.entry(key.clone()).or_insert(BTreeMap::new());
+        let prev_entry = map.insert(txn_idx, CachePadded::new(entry));
 
         // Assert that the previous entry for txn_idx, if present, had lower incarnation.
-        assert!(prev_cell
-            .map(|cell| cell.incarnation < incarnation)
+        assert!(prev_entry
+            .map(|entry| entry.incarnation < incarnation)
             .unwrap_or(true));
     }
 
@@ -105,30 +161,48 @@ impl<K: Hash + Clone + Eq, V> MVHashMap<K, V> {
         map.remove(&txn_idx);
     }
 
-    /// read may return Ok((Arc<V>, txn_idx, incarnation)), Err(dep_txn_idx) for
-    /// a dependency of transaction dep_txn_idx or Err(None) when no prior entry is found.
-    pub fn read(&self, key: &K, txn_idx: TxnIndex) -> Result<(Version, Arc<V>), Option<TxnIndex>> {
-        match self.data.get(key) {
-            Some(tree) => {
-                // Find the dependency
-                let mut iter = tree.range(0..txn_idx);
-                if let Some((idx, write_cell)) = iter.next_back() {
-                    let flag = write_cell.flag();
-
-                    if flag == FLAG_ESTIMATE {
-                        // Found a dependency.
-                        Err(Some(*idx))
-                    } else {
-                        debug_assert!(flag == FLAG_DONE);
-                        // The entry is populated, return its contents.
-                        let write_version = (*idx, write_cell.incarnation);
-                        Ok((write_version, write_cell.data.clone()))
-                    }
-                } else {
-                    Err(None)
+    /// Read the entry at access path 'key' as observed by transaction 'txn_idx'. Deltas of
+    /// preceding transactions are aggregated until the closest preceding write is found, see
+    /// MVHashMapOutput and MVHashMapError for the possible outcomes.
+    pub fn read(&self, key: &K, txn_idx: TxnIndex) -> Result<MVHashMapOutput<V>, MVHashMapError> {
+        let tree = match self.data.get(key) {
+            Some(tree) => tree,
+            None => return Err(MVHashMapError::NotFound),
+        };
+
+        // Deltas aggregated so far, going from the latest to the earliest entry.
+        let mut aggregated: Option<DeltaOp> = None;
+        for (idx, entry) in tree.range(0..txn_idx).rev() {
+            let flag = entry.flag();
+            if flag == FLAG_ESTIMATE {
+                // Found a dependency.
+                return Err(MVHashMapError::Dependency(*idx));
+            }
+            debug_assert!(flag == FLAG_DONE);
+
+            // The entry is populated, return its contents or keep aggregating.
+            let write_version = (*idx, entry.incarnation);
+            match &entry.cell {
+                EntryCell::Write(data) => {
+                    return Ok(match aggregated {
+                        None => MVHashMapOutput::Version(write_version, data.clone()),
+                        Some(delta) => MVHashMapOutput::Delta(write_version, data.clone(), delta),
+                    });
                 }
+                EntryCell::Delta(delta) => match aggregated.as_mut() {
+                    None => aggregated = Some(*delta),
+                    Some(later) => {
+                        if later.merge_onto(*delta).is_err() {
+                            return Err(MVHashMapError::DeltaApplicationFailure);
+                        }
+                    }
+                },
             }
-            None => Err(None),
+        }
+
+        match aggregated {
+            Some(delta) => Err(MVHashMapError::Unresolved(delta)),
+            None => Err(MVHashMapError::NotFound),
         }
     }
 }
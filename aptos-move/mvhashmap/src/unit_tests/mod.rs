@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
+use crate::{MVHashMapError::*, MVHashMapOutput::*};
+use aptos_aggregator::delta_change_set::DeltaOp;
 
 mod proptest_types;
 
@@ -26,21 +28,21 @@ fn create_write_read_placeholder_struct() {
 
     // Reads that should go the the DB return Err(None)
     let r_db = mvtbl.read(&ap1, 5);
-    assert_eq!(Err(None), r_db);
+    assert_eq!(Err(NotFound), r_db);
 
     // Write by txn 10.
     mvtbl.write(&ap1, (10, 1), value_for(10, 1));
 
     // Reads that should go the the DB return Err(None)
     let r_db = mvtbl.read(&ap1, 9);
-    assert_eq!(Err(None), r_db);
+    assert_eq!(Err(NotFound), r_db);
     // Reads return entries from smaller txns, not txn 10.
     let r_db = mvtbl.read(&ap1, 10);
-    assert_eq!(Err(None), r_db);
+    assert_eq!(Err(NotFound), r_db);
 
     // Reads for a higher txn return the entry written by txn 10.
     let r_10 = mvtbl.read(&ap1, 15);
-    assert_eq!(Ok(((10, 1), arc_value_for(10, 1))), r_10);
+    assert_eq!(Ok(Version((10, 1), arc_value_for(10, 1))), r_10);
 
     // More writes.
     mvtbl.write(&ap1, (12, 0), value_for(12, 0));
@@ -48,18 +50,18 @@ fn create_write_read_placeholder_struct() {
 
     // Verify reads.
     let r_12 = mvtbl.read(&ap1, 15);
-    assert_eq!(Ok(((12, 0), arc_value_for(12, 0))), r_12);
+    assert_eq!(Ok(Version((12, 0), arc_value_for(12, 0))), r_12);
     let r_10 = mvtbl.read(&ap1, 11);
-    assert_eq!(Ok(((10, 1), arc_value_for(10, 1))), r_10);
+    assert_eq!(Ok(Version((10, 1), arc_value_for(10, 1))), r_10);
     let r_8 = mvtbl.read(&ap1, 10);
-    assert_eq!(Ok(((8, 3), arc_value_for(8, 3))), r_8);
+    assert_eq!(Ok(Version((8, 3), arc_value_for(8, 3))), r_8);
 
     // Mark the entry written by 10 as an estimate.
     mvtbl.mark_estimate(&ap1, 10);
 
     // Read for txn 11 must observe a dependency.
     let r_10 = mvtbl.read(&ap1, 11);
-    assert_eq!(Err(Some(10)), r_10);
+    assert_eq!(Err(Dependency(10)), r_10);
 
     // Delete the entry written by 10, write to a different ap.
     mvtbl.delete(&ap1, 10);
@@ -67,15 +69,15 @@ fn create_write_read_placeholder_struct() {
 
     // Read by txn 11 no longer observes entry from txn 10.
     let r_8 = mvtbl.read(&ap1, 11);
-    assert_eq!(Ok(((8, 3), arc_value_for(8, 3))), r_8);
+    assert_eq!(Ok(Version((8, 3), arc_value_for(8, 3))), r_8);
 
     // Reads, writes for ap2 and ap3.
     mvtbl.write(&ap2, (5, 0), value_for(5, 0));
     mvtbl.write(&ap3, (20, 4), value_for(20, 4));
     let r_5 = mvtbl.read(&ap2, 10);
-    assert_eq!(Ok(((5, 0), arc_value_for(5, 0))), r_5);
+    assert_eq!(Ok(Version((5, 0), arc_value_for(5, 0))), r_5);
     let r_20 = mvtbl.read(&ap3, 21);
-    assert_eq!(Ok(((20, 4), arc_value_for(20, 4))), r_20);
+    assert_eq!(Ok(Version((20, 4), arc_value_for(20, 4))), r_20);
 
     // Clear ap1 and ap3.
     mvtbl.delete(&ap1, 12);
@@ -84,14 +86,61 @@ fn create_write_read_placeholder_struct() {
 
     // Reads from ap1 and ap3 go to db.
     let r_db = mvtbl.read(&ap1, 30);
-    assert_eq!(Err(None), r_db);
+    assert_eq!(Err(NotFound), r_db);
     let r_db = mvtbl.read(&ap3, 30);
-    assert_eq!(Err(None), r_db);
+    assert_eq!(Err(NotFound), r_db);
 
     // No-op delete at ap2.
     mvtbl.delete(&ap2, 11);
 
     // Read entry by txn 10 at ap2.
     let r_10 = mvtbl.read(&ap2, 15);
-    assert_eq!(Ok(((10, 2), arc_value_for(10, 2))), r_10);
+    assert_eq!(Ok(Version((10, 2), arc_value_for(10, 2))), r_10);
+}
+
+#[test]
+fn read_aggregated_deltas() {
+    let ap = b"/foo/aggregator".to_vec();
+
+    let mvtbl = MVHashMap::new();
+
+    // Deltas on top of storage can not be resolved by the data-structure.
+    mvtbl.add_delta(&ap, (3, 0), DeltaOp::addition(10, 100).unwrap());
+    mvtbl.add_delta(&ap, (5, 0), DeltaOp::subtraction(4, 100).unwrap());
+    let mut expected = DeltaOp::addition(10, 100).unwrap();
+    expected.sub(4).unwrap();
+    assert_eq!(Err(Unresolved(expected)), mvtbl.read(&ap, 6));
+    assert_eq!(
+        Err(Unresolved(DeltaOp::addition(10, 100).unwrap())),
+        mvtbl.read(&ap, 5)
+    );
+
+    // A preceding write becomes the base the deltas are applied to.
+    mvtbl.write(&ap, (1, 0), value_for(1, 0));
+    assert_eq!(
+        Ok(Delta((1, 0), arc_value_for(1, 0), expected)),
+        mvtbl.read(&ap, 6)
+    );
+    assert_eq!(Ok(Version((1, 0), arc_value_for(1, 0))), mvtbl.read(&ap, 2));
+
+    // A write in between hides the earlier deltas.
+    mvtbl.write(&ap, (4, 1), value_for(4, 1));
+    assert_eq!(
+        Ok(Delta(
+            (4, 1),
+            arc_value_for(4, 1),
+            DeltaOp::subtraction(4, 100).unwrap()
+        )),
+        mvtbl.read(&ap, 6)
+    );
+
+    // Estimates among the deltas are dependencies.
+    mvtbl.mark_estimate(&ap, 5);
+    assert_eq!(Err(Dependency(5)), mvtbl.read(&ap, 6));
+    mvtbl.delete(&ap, 5);
+    mvtbl.delete(&ap, 4);
+
+    // Deltas that can not be merged within the limit fail.
+    mvtbl.add_delta(&ap, (7, 0), DeltaOp::addition(95, 100).unwrap());
+    assert_eq!(Err(DeltaApplicationFailure), mvtbl.read(&ap, 8));
 }
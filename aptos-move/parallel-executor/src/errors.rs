@@ -11,6 +11,9 @@ pub enum Error<E> {
     /// A transaction write to a key that wasn't estimated by the inferencer, abort the execution
     /// because we don't have a good way of handling read-after-write dependency. Will relax this limitation later.
     UnestimatedWrite,
+    /// Aggregator deltas of committed transactions could not be applied within their bounds,
+    /// the block needs to be re-executed sequentially to determine which transactions fail.
+    DeltaApplicationFailure,
     /// Execution of a thread yields a non-recoverable error, such error will be propagated back to
     /// the caller.
     UserError(E),
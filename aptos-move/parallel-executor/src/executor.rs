@@ -5,12 +5,14 @@ use crate::{
     errors::*,
     outcome_array::OutcomeArray,
     scheduler::{Scheduler, SchedulerTask, TaskGuard, TxnIndex, Version},
+    stats::{ExecutionStats, StatsCollector},
     task::{ExecutionStatus, ExecutorTask, Transaction, TransactionOutput},
     txn_last_input_output::{ReadDescriptor, TxnLastInputOutput},
 };
 use anyhow::{bail, Result as AResult};
+use aptos_aggregator::delta_change_set::DeltaOp;
 use aptos_infallible::Mutex;
-use mvhashmap::MVHashMap;
+use mvhashmap::{MVHashMap, MVHashMapError, MVHashMapOutput};
 use num_cpus;
 use rayon::{prelude::*, scope};
 use std::{
@@ -24,6 +26,18 @@ use std::{
     thread::spawn,
 };
 
+/// Result of a read from the VM execution through MVHashMapView.
+#[derive(Debug)]
+pub enum ReadResult<V> {
+    /// Value written by a prior transaction.
+    Value(Arc<V>),
+    /// Aggregated delta of prior transactions that must be applied to the given value, or to
+    /// the value from storage if the base is None.
+    Delta(Option<Arc<V>>, DeltaOp),
+    /// No prior transaction wrote to the key, the value must be read from storage.
+    None,
+}
+
 /// A struct that is always used by a single thread performing an execution task. The struct is
 /// passed to the VM and acts as a proxy to resolve reads first in the shared multi-version
 /// data-structure. It also allows the caller to track the read-set and any dependencies.
@@ -36,6 +50,7 @@ pub struct MVHashMapView<'a, K, V> {
     txn_idx: TxnIndex,
     scheduler: &'a Scheduler,
     read_dependency: AtomicBool,
+    delta_application_failure: AtomicBool,
     captured_reads: Mutex<Vec<ReadDescriptor<K>>>,
 }
 
@@ -47,25 +62,48 @@ impl<'a, K: PartialOrd + Send + Clone + Hash + Eq, V: Send + Sync> MVHashMapView
     }
 
     /// Captures a read from the VM execution.
-    pub fn read(&self, key: &K) -> AResult<Option<Arc<V>>> {
+    pub fn read(&self, key: &K) -> AResult<ReadResult<V>> {
         loop {
             match self.versioned_map.read(key, self.txn_idx) {
-                Ok((version, v)) => {
+                Ok(MVHashMapOutput::Version(version, v)) => {
                     let (txn_idx, incarnation) = version;
                     self.captured_reads.lock().push(ReadDescriptor::from(
                         key.clone(),
                         txn_idx,
                         incarnation,
                     ));
-                    return Ok(Some(v));
+                    return Ok(ReadResult::Value(v));
                 }
-                Err(None) => {
+                Ok(MVHashMapOutput::Delta(version, v, delta)) => {
+                    let (txn_idx, incarnation) = version;
+                    self.captured_reads.lock().push(ReadDescriptor::from_delta(
+                        key.clone(),
+                        txn_idx,
+                        incarnation,
+                        delta,
+                    ));
+                    return Ok(ReadResult::Delta(Some(v), delta));
+                }
+                Err(MVHashMapError::NotFound) => {
                     self.captured_reads
                         .lock()
                         .push(ReadDescriptor::from_storage(key.clone()));
-                    return Ok(None);
+                    return Ok(ReadResult::None);
+                }
+                Err(MVHashMapError::Unresolved(delta)) => {
+                    self.captured_reads
+                        .lock()
+                        .push(ReadDescriptor::from_storage_delta(key.clone(), delta));
+                    return Ok(ReadResult::Delta(None, delta));
                 }
-                Err(Some(dep_idx)) => {
+                Err(MVHashMapError::DeltaApplicationFailure) => {
+                    self.captured_reads
+                        .lock()
+                        .push(ReadDescriptor::from_delta_application_failure(key.clone()));
+                    self.mark_delta_application_failure();
+                    bail!("Aggregator deltas can not be applied within bounds")
+                }
+                Err(MVHashMapError::Dependency(dep_idx)) => {
                     // Don't start execution transaction `self.txn_idx` until `dep_idx` is computed.
                     if self.scheduler.try_add_dependency(self.txn_idx, dep_idx) {
                         // dep_idx is already executed, push `self.txn_idx` to ready queue.
@@ -89,10 +127,24 @@ impl<'a, K: PartialOrd + Send + Clone + Hash + Eq, V: Send + Sync> MVHashMapView
     pub fn read_dependency(&self) -> bool {
         self.read_dependency.load(Ordering::Relaxed)
     }
+
+    /// Records that a delta returned by `read` could not be applied to its base value. As in
+    /// sequential execution deltas are applied eagerly and within bounds, the outcome of
+    /// parallel execution can not be trusted if such a read turns out to be valid.
+    pub fn mark_delta_application_failure(&self) {
+        self.delta_application_failure
+            .store(true, Ordering::Relaxed);
+    }
+
+    /// Return whether applying a delta failed during VM execution.
+    pub fn delta_application_failure(&self) -> bool {
+        self.delta_application_failure.load(Ordering::Relaxed)
+    }
 }
 
 pub struct ParallelTransactionExecutor<T: Transaction, E: ExecutorTask> {
-    num_cpus: usize,
+    concurrency_level: usize,
+    last_execution_stats: Mutex<Option<ExecutionStats<T::Key>>>,
     phantom: PhantomData<(T, E)>,
 }
 
@@ -102,12 +154,32 @@ where
     E: ExecutorTask<T = T>,
 {
     pub fn new() -> Self {
+        Self::with_concurrency_level(num_cpus::get())
+    }
+
+    /// Creates an executor that runs the given number of worker tasks. A concurrency level of
+    /// 0 defaults to the number of CPUs.
+    pub fn with_concurrency_level(concurrency_level: usize) -> Self {
         Self {
-            num_cpus: num_cpus::get(),
+            concurrency_level: if concurrency_level == 0 {
+                num_cpus::get()
+            } else {
+                concurrency_level
+            },
+            last_execution_stats: Mutex::new(None),
             phantom: PhantomData,
         }
     }
 
+    pub fn concurrency_level(&self) -> usize {
+        self.concurrency_level
+    }
+
+    /// Statistics of the last block executed by execute_transactions_parallel, if any.
+    pub fn last_execution_stats(&self) -> Option<ExecutionStats<T::Key>> {
+        self.last_execution_stats.lock().clone()
+    }
+
     pub fn execute<'a>(
         &self,
         version_to_execute: Version,
@@ -121,6 +193,7 @@ where
         versioned_data_cache: &MVHashMap<<T as Transaction>::Key, <T as Transaction>::Value>,
         scheduler: &'a Scheduler,
         executor: &E,
+        stats: &StatsCollector<<T as Transaction>::Key>,
     ) -> SchedulerTask<'a> {
         let (idx_to_execute, incarnation) = version_to_execute;
         let txn = &signature_verified_block[idx_to_execute];
@@ -132,11 +205,14 @@ where
         if let Some(read_set) = last_input_output.read_set(idx_to_execute) {
             if read_set.iter().any(
                 |r| match versioned_data_cache.read(r.path(), idx_to_execute) {
-                    Err(Some(dep_idx)) => scheduler.try_add_dependency(idx_to_execute, dep_idx),
-                    Ok(_) | Err(None) => false,
+                    Err(MVHashMapError::Dependency(dep_idx)) => {
+                        scheduler.try_add_dependency(idx_to_execute, dep_idx)
+                    }
+                    _ => false,
                 },
             ) {
                 // Transaction has a read dependency. Was not executed and thus nothing to validate.
+                stats.record_dependency_wait();
                 return SchedulerTask::NoTask;
             }
         }
@@ -146,14 +222,17 @@ where
             txn_idx: idx_to_execute,
             scheduler,
             read_dependency: AtomicBool::new(false),
+            delta_application_failure: AtomicBool::new(false),
             captured_reads: Mutex::new(Vec::new()),
         };
 
         // VM execution.
         let execute_result = executor.execute_transaction(&state_view, txn);
+        stats.record_execution(idx_to_execute, incarnation);
 
         if state_view.read_dependency() {
             // Encountered and already handled (added to Scheduler) a read dependency.
+            stats.record_dependency_wait();
             return SchedulerTask::NoTask;
         }
 
@@ -169,9 +248,21 @@ where
                 }
                 versioned_data_cache.write(&k, write_version, v);
             }
+            for (k, d) in output.get_deltas().into_iter() {
+                if !prev_write_set.remove(&k) {
+                    writes_outside = true
+                }
+                versioned_data_cache.add_delta(&k, write_version, d);
+            }
         };
 
         let result = match execute_result {
+            _ if state_view.delta_application_failure() => {
+                // The failing read may be speculative, so the execution is validated like any
+                // other. The block is re-executed sequentially only if this result is committed,
+                // i.e. the read is still valid once all prior transactions are validated.
+                ExecutionStatus::Abort(Error::DeltaApplicationFailure)
+            }
             ExecutionStatus::Success(output) => {
                 // Commit the side effects to the versioned_data_cache.
                 apply_writes(&output);
@@ -210,23 +301,34 @@ where
         >,
         versioned_data_cache: &MVHashMap<<T as Transaction>::Key, <T as Transaction>::Value>,
         scheduler: &'a Scheduler,
+        stats: &StatsCollector<<T as Transaction>::Key>,
     ) -> SchedulerTask<'a> {
         let (idx_to_validate, incarnation) = version_to_validate;
         let read_set = last_input_output
             .read_set(idx_to_validate)
             .expect("Prior read-set must be recorded");
 
-        let valid = read_set.iter().all(|r| {
-            match versioned_data_cache.read(r.path(), idx_to_validate) {
-                Ok((version, _)) => r.validate_version(version),
-                Err(Some(_)) => false, // Dependency implies a validation failure.
-                Err(None) => r.validate_storage(),
+        let invalid_read = read_set.iter().find(|r| {
+            !match versioned_data_cache.read(r.path(), idx_to_validate) {
+                Ok(MVHashMapOutput::Version(version, _)) => r.validate_version(version),
+                Ok(MVHashMapOutput::Delta(version, _, delta)) => r.validate_delta(version, delta),
+                // Dependency implies a validation failure.
+                Err(MVHashMapError::Dependency(_)) => false,
+                Err(MVHashMapError::NotFound) => r.validate_storage(),
+                Err(MVHashMapError::Unresolved(delta)) => r.validate_storage_delta(delta),
+                Err(MVHashMapError::DeltaApplicationFailure) => {
+                    r.validate_delta_application_failure()
+                }
             }
         });
 
-        let aborted = !valid && scheduler.try_abort(idx_to_validate, incarnation);
+        let aborted = invalid_read.is_some() && scheduler.try_abort(idx_to_validate, incarnation);
 
         if aborted {
+            if let Some(r) = invalid_read {
+                stats.record_validation_abort(r.path());
+            }
+
             // Not valid and successfully aborted, mark the latest write-set as estimates.
             for k in &last_input_output.write_set(idx_to_validate) {
                 versioned_data_cache.mark_estimate(k, idx_to_validate);
@@ -238,6 +340,38 @@ where
         }
     }
 
+    /// Provides the output of a committed transaction with its deltas, aggregated on top of the
+    /// closest preceding write (or storage), see TransactionOutput::incorporate_delta_writes.
+    fn resolve_deltas(
+        txn_idx: TxnIndex,
+        output: &mut <E as ExecutorTask>::Output,
+        versioned_data_cache: &MVHashMap<<T as Transaction>::Key, <T as Transaction>::Value>,
+    ) -> Result<(), E::Error> {
+        let deltas = output.get_deltas();
+        if deltas.is_empty() {
+            return Ok(());
+        }
+
+        let mut delta_writes = Vec::with_capacity(deltas.len());
+        for (k, _) in deltas {
+            // Reading as the next transaction observes the delta of txn_idx aggregated with
+            // all deltas since the closest preceding write.
+            let (base, delta) = match versioned_data_cache.read(&k, txn_idx + 1) {
+                Ok(MVHashMapOutput::Delta(_, v, delta)) => (Some(v), delta),
+                Err(MVHashMapError::Unresolved(delta)) => (None, delta),
+                Err(MVHashMapError::DeltaApplicationFailure) => {
+                    return Err(Error::DeltaApplicationFailure)
+                }
+                Ok(MVHashMapOutput::Version(..))
+                | Err(MVHashMapError::NotFound)
+                | Err(MVHashMapError::Dependency(_)) => return Err(Error::InvariantViolation),
+            };
+            delta_writes.push((k, base, delta));
+        }
+        output.incorporate_delta_writes(delta_writes);
+        Ok(())
+    }
+
     pub fn execute_transactions_parallel(
         &self,
         executor_initial_arguments: E::Argument,
@@ -250,9 +384,10 @@ where
         let num_txns = signature_verified_block.len();
         let versioned_data_cache = MVHashMap::new();
         let outcomes = OutcomeArray::new(num_txns);
-        let compute_cpus = self.num_cpus;
+        let compute_cpus = self.concurrency_level;
         let last_input_output = TxnLastInputOutput::new(num_txns);
         let scheduler = Scheduler::new(num_txns);
+        let stats = StatsCollector::new(num_txns);
 
         scope(|s| {
             println!(
@@ -276,6 +411,7 @@ where
                                     &last_input_output,
                                     &versioned_data_cache,
                                     &scheduler,
+                                    &stats,
                                 ),
                             SchedulerTask::ExecutionTask(version_to_execute, guard) => self
                                 .execute(
@@ -286,6 +422,7 @@ where
                                     &versioned_data_cache,
                                     &scheduler,
                                     &executor,
+                                    &stats,
                                 ),
                             SchedulerTask::NoTask => scheduler.next_task(),
                             SchedulerTask::Done => break,
@@ -297,13 +434,21 @@ where
 
         // Extract outputs in parallel
         let valid_results_size = scheduler.num_txn_to_execute();
+        *self.last_execution_stats.lock() = Some(stats.finish(valid_results_size));
         let chunk_size = (valid_results_size + 4 * compute_cpus - 1) / (4 * compute_cpus);
         (0..valid_results_size)
             .collect::<Vec<TxnIndex>>()
             .par_chunks(chunk_size)
             .map(|chunk| {
                 for idx in chunk.iter() {
-                    outcomes.set_result(*idx, last_input_output.take_output(*idx));
+                    let mut output = last_input_output.take_output(*idx);
+                    if let ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) = &mut output
+                    {
+                        if let Err(err) = Self::resolve_deltas(*idx, t, &versioned_data_cache) {
+                            output = ExecutionStatus::Abort(err);
+                        }
+                    }
+                    outcomes.set_result(*idx, output);
                 }
             })
             .collect::<()>();
@@ -7,6 +7,7 @@ mod outcome_array;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod proptest_types;
 mod scheduler;
+pub mod stats;
 pub mod task;
 mod txn_last_input_output;
 #[cfg(test)]
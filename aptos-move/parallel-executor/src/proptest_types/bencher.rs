@@ -4,7 +4,7 @@
 use crate::{
     executor::ParallelTransactionExecutor,
     proptest_types::types::{
-        ExpectedOutput, Task, Transaction, TransactionGen, TransactionGenParams,
+        AggregatorValue, ExpectedOutput, Task, Transaction, TransactionGen, TransactionGenParams,
     },
 };
 use criterion::{BatchSize, Bencher as CBencher};
@@ -34,7 +34,7 @@ pub(crate) struct BencherState<K, V> {
 impl<K, V> Bencher<K, V>
 where
     K: Hash + Clone + Debug + Eq + Send + Sync + PartialOrd + Ord + Arbitrary + 'static,
-    V: Clone + Eq + Send + Sync + Arbitrary + 'static,
+    V: Clone + Eq + Send + Sync + Arbitrary + AggregatorValue + 'static,
 {
     pub fn new(transaction_size: usize, universe_size: usize) -> Self {
         Self {
@@ -66,7 +66,7 @@ where
 impl<K, V> BencherState<K, V>
 where
     K: Hash + Clone + Debug + Eq + Send + Sync + PartialOrd + Ord + 'static,
-    V: Clone + Eq + Send + Sync + Arbitrary + 'static,
+    V: Clone + Eq + Send + Sync + Arbitrary + AggregatorValue + 'static,
 {
     /// Creates a new benchmark state with the given account universe strategy and number of
     /// transactions.
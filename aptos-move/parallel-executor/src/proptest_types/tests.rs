@@ -3,7 +3,7 @@
 
 use crate::{
     executor::ParallelTransactionExecutor,
-    proptest_types::types::{ExpectedOutput, Task, Transaction, TransactionGen},
+    proptest_types::types::{AggregatorValue, ExpectedOutput, Task, Transaction, TransactionGen},
 };
 use proptest::{collection::vec, prelude::*, sample::Index, strategy::Strategy};
 use std::{fmt::Debug, hash::Hash};
@@ -16,7 +16,7 @@ fn run_transactions<K, V>(
 ) -> bool
 where
     K: Hash + Clone + Debug + Eq + Send + Sync + PartialOrd + Ord + 'static,
-    V: Clone + Eq + Send + Sync + Arbitrary + 'static,
+    V: Clone + Eq + Send + Sync + Arbitrary + AggregatorValue + 'static,
 {
     let mut transactions: Vec<_> = transaction_gens
         .into_iter()
@@ -3,22 +3,25 @@
 
 use crate::{
     errors::{Error, Result},
-    executor::MVHashMapView,
+    executor::{MVHashMapView, ReadResult},
     task::{
         Accesses, ExecutionStatus, ExecutorTask, ReadWriteSetInferencer,
         Transaction as TransactionType, TransactionOutput,
     },
 };
 use anyhow::Result as AResult;
+use aptos_aggregator::delta_change_set::DeltaOp;
 use proptest::{
     arbitrary::Arbitrary, collection::vec, prelude::*, proptest, sample::Index, strategy::Strategy,
 };
 use proptest_derive::Arbitrary;
 use std::{
     collections::{BTreeSet, HashMap},
+    convert::TryFrom,
     fmt::Debug,
     hash::Hash,
     marker::PhantomData,
+    sync::Arc,
 };
 
 ///////////////////////////////////////////////////////////////////////////
@@ -34,6 +37,8 @@ pub struct TransactionGen<V: Arbitrary + Debug + 'static + Clone> {
     keys_modified: Vec<(Index, Option<V>)>,
     #[proptest(strategy = "vec(any::<Index>(), 1..params.read_size)")]
     keys_read: Vec<Index>,
+    #[proptest(strategy = "vec((any::<Index>(), any::<u32>()), 0..params.delta_size)")]
+    deltas: Vec<(Index, u32)>,
 }
 
 #[derive(Clone, Copy)]
@@ -41,6 +46,48 @@ pub struct TransactionGenParams {
     pub possible_write_size: usize,
     pub read_size: usize,
     pub write_keep_rate: f64,
+    pub delta_size: usize,
+}
+
+/// Values of the test transactions, interpreted as unsigned integers by aggregator deltas.
+pub trait AggregatorValue {
+    fn from_u128(value: u128) -> Self;
+
+    fn to_u128(&self) -> u128;
+}
+
+impl AggregatorValue for u64 {
+    fn from_u128(value: u128) -> Self {
+        u64::try_from(value).expect("Aggregator values of u64 keys must fit into u64")
+    }
+
+    fn to_u128(&self) -> u128 {
+        *self as u128
+    }
+}
+
+impl AggregatorValue for u128 {
+    fn from_u128(value: u128) -> Self {
+        value
+    }
+
+    fn to_u128(&self) -> u128 {
+        *self
+    }
+}
+
+impl AggregatorValue for [u8; 32] {
+    fn from_u128(value: u128) -> Self {
+        let mut bytes = [0; 32];
+        bytes[..16].copy_from_slice(&value.to_le_bytes());
+        bytes
+    }
+
+    fn to_u128(&self) -> u128 {
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(&self[..16]);
+        u128::from_le_bytes(bytes)
+    }
 }
 
 /// A naive transaction that could be used to test the correctness and throughput of the system.
@@ -53,6 +100,8 @@ pub enum Transaction<K, V> {
         skipped_writes: Vec<K>,
         /// Read from some keys.
         reads: Vec<K>,
+        /// Apply aggregator deltas to some keys.
+        deltas: Vec<(K, DeltaOp)>,
     },
     /// Skip the execution of trailing transactions.
     SkipRest,
@@ -66,6 +115,7 @@ impl Default for TransactionGenParams {
             possible_write_size: 10,
             write_keep_rate: 0.5,
             read_size: 10,
+            delta_size: 3,
         }
     }
 }
@@ -92,6 +142,14 @@ impl<V: Arbitrary + Debug + Clone> TransactionGen<V> {
                 };
             }
         }
+        let mut deltas = vec![];
+        for (idx, value) in self.deltas.into_iter() {
+            let key = universe[idx.index(universe.len())].clone();
+            if !keys_modified.contains(&key) {
+                keys_modified.insert(key.clone());
+                deltas.push((key, DeltaOp::addition(value as u128, u128::MAX).unwrap()));
+            }
+        }
         Transaction::Write {
             actual_writes,
             skipped_writes,
@@ -100,6 +158,7 @@ impl<V: Arbitrary + Debug + Clone> TransactionGen<V> {
                 .into_iter()
                 .map(|k| universe[k.index(universe.len())].clone())
                 .collect(),
+            deltas,
         }
     }
 }
@@ -138,10 +197,12 @@ where
                 actual_writes,
                 skipped_writes,
                 reads,
+                deltas,
             } => {
                 let mut writes = actual_writes
                     .iter()
                     .map(|(k, _)| k.clone())
+                    .chain(deltas.iter().map(|(k, _)| k.clone()))
                     .collect::<Vec<_>>();
                 writes.append(&mut skipped_writes.clone());
                 Ok(Accesses {
@@ -178,10 +239,12 @@ where
                 actual_writes,
                 skipped_writes,
                 reads,
+                deltas,
             } => {
                 let mut writes = actual_writes
                     .iter()
                     .map(|(k, _)| k.clone())
+                    .chain(deltas.iter().map(|(k, _)| k.clone()))
                     .collect::<Vec<_>>();
                 writes.append(&mut skipped_writes.clone());
 
@@ -217,7 +280,7 @@ impl<K, V> Task<K, V> {
 impl<K, V> ExecutorTask for Task<K, V>
 where
     K: PartialOrd + Send + Sync + Clone + Hash + Eq + 'static,
-    V: Send + Sync + Debug + Clone + 'static,
+    V: Send + Sync + Debug + Clone + AggregatorValue + 'static,
 {
     type T = Transaction<K, V>;
     type Output = Output<K, V>;
@@ -238,30 +301,53 @@ where
                 reads,
                 actual_writes,
                 skipped_writes: _,
+                deltas,
             } => {
                 // Reads
                 let mut reads_result = vec![];
                 for k in reads.iter() {
                     reads_result.push(match view.read(k) {
-                        Ok(Some(v)) => Some((*v).clone()),
-                        Ok(None) => None,
+                        Ok(ReadResult::Value(v)) => Some((*v).clone()),
+                        Ok(ReadResult::None) => None,
+                        Ok(ReadResult::Delta(base, delta)) => {
+                            // Missing values in storage are treated as zero.
+                            match delta.apply_to(base.map_or(0, |v| v.to_u128())) {
+                                Ok(value) => Some(V::from_u128(value)),
+                                Err(_) => {
+                                    view.mark_delta_application_failure();
+                                    return ExecutionStatus::Abort(0);
+                                }
+                            }
+                        }
                         Err(_) => return ExecutionStatus::Abort(0),
                     })
                 }
-                ExecutionStatus::Success(Output(actual_writes.clone(), reads_result))
+                ExecutionStatus::Success(Output::new(
+                    actual_writes.clone(),
+                    deltas.clone(),
+                    reads_result,
+                ))
             }
-            Transaction::SkipRest => ExecutionStatus::SkipRest(Output(vec![], vec![])),
+            Transaction::SkipRest => ExecutionStatus::SkipRest(Output::skip_output()),
             Transaction::Abort => ExecutionStatus::Abort(view.txn_idx()),
         }
     }
 }
 
-pub struct Output<K, V>(Vec<(K, V)>, Vec<Option<V>>);
+/// Output of a test transaction: its writes, its deltas, the values it read and the values of
+/// the delta keys once its deltas are applied (populated when the transaction is committed).
+pub struct Output<K, V>(Vec<(K, V)>, Vec<(K, DeltaOp)>, Vec<Option<V>>, Vec<V>);
+
+impl<K, V> Output<K, V> {
+    fn new(writes: Vec<(K, V)>, deltas: Vec<(K, DeltaOp)>, reads: Vec<Option<V>>) -> Self {
+        Self(writes, deltas, reads, vec![])
+    }
+}
 
 impl<K, V> TransactionOutput for Output<K, V>
 where
     K: PartialOrd + Send + Sync + Clone + Hash + Eq + 'static,
-    V: Send + Sync + Debug + Clone + 'static,
+    V: Send + Sync + Debug + Clone + AggregatorValue + 'static,
 {
     type T = Transaction<K, V>;
 
@@ -269,8 +355,24 @@ where
         self.0.clone()
     }
 
+    fn get_deltas(&self) -> Vec<(K, DeltaOp)> {
+        self.1.clone()
+    }
+
+    fn incorporate_delta_writes(&mut self, delta_writes: Vec<(K, Option<Arc<V>>, DeltaOp)>) {
+        self.3 = delta_writes
+            .into_iter()
+            .map(|(_, base, delta)| {
+                let value = delta
+                    .apply_to(base.map_or(0, |v| v.to_u128()))
+                    .expect("Committed deltas must be applicable");
+                V::from_u128(value)
+            })
+            .collect();
+    }
+
     fn skip_output() -> Self {
-        Self(vec![], vec![])
+        Self(vec![], vec![], vec![], vec![])
     }
 }
 
@@ -278,14 +380,17 @@ where
 // Sequential Baseline implementation.
 ///////////////////////////////////////////////////////////////////////////
 
+/// The values read by a transaction and the values of its delta keys once the deltas are applied.
+type ExpectedResult<V> = (Vec<Option<V>>, Vec<V>);
+
 /// Sequential baseline of execution result for dummy transaction.
 pub enum ExpectedOutput<V> {
     Aborted(usize),
-    SkipRest(usize, Vec<Vec<Option<V>>>),
-    Success(Vec<Vec<Option<V>>>),
+    SkipRest(usize, Vec<ExpectedResult<V>>),
+    Success(Vec<ExpectedResult<V>>),
 }
 
-impl<V: Clone + Eq> ExpectedOutput<V> {
+impl<V: Clone + Eq + AggregatorValue> ExpectedOutput<V> {
     pub fn generate_baseline<K: Hash + Clone + Eq>(txns: &[Transaction<K, V>]) -> Self {
         let mut current_world = HashMap::new();
         let mut result_vec = vec![];
@@ -296,6 +401,7 @@ impl<V: Clone + Eq> ExpectedOutput<V> {
                     reads,
                     actual_writes,
                     skipped_writes: _,
+                    deltas,
                 } => {
                     let mut result = vec![];
                     for k in reads.iter() {
@@ -304,7 +410,18 @@ impl<V: Clone + Eq> ExpectedOutput<V> {
                     for (k, v) in actual_writes.iter() {
                         current_world.insert(k.clone(), v.clone());
                     }
-                    result_vec.push(result)
+                    let mut delta_writes = vec![];
+                    for (k, delta) in deltas.iter() {
+                        let base = current_world.get(k).map_or(0, |v: &V| v.to_u128());
+                        let value = V::from_u128(
+                            delta
+                                .apply_to(base)
+                                .expect("Test deltas are applied within bounds"),
+                        );
+                        current_world.insert(k.clone(), value.clone());
+                        delta_writes.push(value);
+                    }
+                    result_vec.push((result, delta_writes))
                 }
                 Transaction::SkipRest => return Self::SkipRest(idx, result_vec),
             }
@@ -320,16 +437,25 @@ impl<V: Clone + Eq> ExpectedOutput<V> {
                     .iter()
                     .take(*skip_at)
                     .zip(expected_results.iter())
-                    .all(|(Output(_, result), expected_results)| expected_results == result)
+                    .all(
+                        |(Output(_, _, reads, delta_writes), (expected_reads, expected_writes))| {
+                            expected_reads == reads && expected_writes == delta_writes
+                        },
+                    )
                     && results
                         .iter()
                         .skip(*skip_at)
-                        .all(|Output(_, result)| result.is_empty())
+                        .all(|Output(_, _, reads, delta_writes)| {
+                            reads.is_empty() && delta_writes.is_empty()
+                        })
+            }
+            (Self::Success(expected_results), Ok(results)) => {
+                expected_results.iter().zip(results.iter()).all(
+                    |((expected_reads, expected_writes), Output(_, _, reads, delta_writes))| {
+                        expected_reads == reads && expected_writes == delta_writes
+                    },
+                )
             }
-            (Self::Success(expected_results), Ok(results)) => expected_results
-                .iter()
-                .zip(results.iter())
-                .all(|(expected_result, Output(_, result))| expected_result == result),
             _ => false,
         }
     }
@@ -0,0 +1,89 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::scheduler::{Incarnation, TxnIndex};
+use aptos_infallible::Mutex;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Number of most conflicting keys reported in ExecutionStats.
+const NUM_TOP_CONFLICTING_KEYS: usize = 10;
+
+/// Statistics of the parallel execution of a single block.
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionStats<K> {
+    /// Number of incarnations executed for each transaction, indexed by transaction index.
+    pub incarnations: Vec<usize>,
+    /// Number of times a validation failure aborted a transaction.
+    pub validation_aborts: usize,
+    /// Number of times an execution had to wait for a transaction it read from.
+    pub dependency_waits: usize,
+    /// Keys whose reads failed validation most often, with the number of failures, in
+    /// decreasing order.
+    pub top_conflicting_keys: Vec<(K, usize)>,
+}
+
+impl<K> ExecutionStats<K> {
+    /// Total number of incarnations executed for the block.
+    pub fn total_incarnations(&self) -> usize {
+        self.incarnations.iter().sum()
+    }
+}
+
+/// Collects ExecutionStats concurrently from the worker threads.
+pub struct StatsCollector<K> {
+    incarnations: Vec<AtomicUsize>,
+    validation_aborts: AtomicUsize,
+    dependency_waits: AtomicUsize,
+    // Validation aborts are rare compared to reads, so a lock is good enough here.
+    conflicts: Mutex<HashMap<K, usize>>,
+}
+
+impl<K: Hash + Clone + Eq> StatsCollector<K> {
+    pub fn new(num_txns: usize) -> Self {
+        Self {
+            incarnations: (0..num_txns).map(|_| AtomicUsize::new(0)).collect(),
+            validation_aborts: AtomicUsize::new(0),
+            dependency_waits: AtomicUsize::new(0),
+            conflicts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn record_execution(&self, txn_idx: TxnIndex, incarnation: Incarnation) {
+        self.incarnations[txn_idx].fetch_max(incarnation + 1, Ordering::Relaxed);
+    }
+
+    pub fn record_dependency_wait(&self) {
+        self.dependency_waits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a validation abort caused by a read of `key`.
+    pub fn record_validation_abort(&self, key: &K) {
+        self.validation_aborts.fetch_add(1, Ordering::Relaxed);
+        *self.conflicts.lock().entry(key.clone()).or_insert(0) += 1;
+    }
+
+    /// Produces the statistics for the first `num_txns` transactions.
+    pub fn finish(self, num_txns: usize) -> ExecutionStats<K> {
+        let mut top_conflicting_keys: Vec<(K, usize)> = std::mem::take(&mut *self.conflicts.lock())
+            .into_iter()
+            .collect();
+        top_conflicting_keys.sort_by(|(_, a), (_, b)| b.cmp(a));
+        top_conflicting_keys.truncate(NUM_TOP_CONFLICTING_KEYS);
+
+        ExecutionStats {
+            incarnations: self
+                .incarnations
+                .into_iter()
+                .take(num_txns)
+                .map(AtomicUsize::into_inner)
+                .collect(),
+            validation_aborts: self.validation_aborts.into_inner(),
+            dependency_waits: self.dependency_waits.into_inner(),
+            top_conflicting_keys,
+        }
+    }
+}
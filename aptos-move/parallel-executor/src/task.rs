@@ -3,7 +3,8 @@
 
 use crate::executor::MVHashMapView;
 use anyhow::Result;
-use std::{fmt::Debug, hash::Hash};
+use aptos_aggregator::delta_change_set::DeltaOp;
+use std::{fmt::Debug, hash::Hash, sync::Arc};
 
 /// The execution result of a transaction
 #[derive(Debug)]
@@ -87,6 +88,25 @@ pub trait TransactionOutput: Send + Sync {
         <Self::T as Transaction>::Value,
     )>;
 
+    /// Get the aggregator deltas of a transaction from its output. Unlike writes, deltas of
+    /// different transactions to the same key commute and do not conflict with each other.
+    fn get_deltas(&self) -> Vec<(<Self::T as Transaction>::Key, DeltaOp)> {
+        Vec::new()
+    }
+
+    /// Called once the transaction is committed, with an entry for every key returned by
+    /// `get_deltas`: the delta aggregated up to and including this transaction, and the value
+    /// it needs to be applied to (`None` if the base value needs to be read from storage).
+    fn incorporate_delta_writes(
+        &mut self,
+        _delta_writes: Vec<(
+            <Self::T as Transaction>::Key,
+            Option<Arc<<Self::T as Transaction>::Value>>,
+            DeltaOp,
+        )>,
+    ) {
+    }
+
     /// Execution output for transactions that comes after SkipRest signal.
     fn skip_output() -> Self;
 }
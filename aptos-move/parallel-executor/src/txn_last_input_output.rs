@@ -6,6 +6,7 @@ use crate::{
     scheduler::{Incarnation, TxnIndex, Version},
     task::{ExecutionStatus, Transaction, TransactionOutput},
 };
+use aptos_aggregator::delta_change_set::DeltaOp;
 use arc_swap::ArcSwapOption;
 use crossbeam::utils::CachePadded;
 use std::{collections::HashSet, sync::Arc};
@@ -16,11 +17,16 @@ type TxnOutput<T, E> = ExecutionStatus<T, Error<E>>;
 // If an entry was read from the multi-version data-structure, then kind is
 // MVHashMap(txn_idx, incarnation), with transaction index and incarnation number
 // of the execution associated with the write of the entry. Otherwise, if the read
-// occured from storage, and kind is set to Storage.
+// occured from storage, and kind is set to Storage. If aggregator deltas were
+// applied on top of the write (or storage), the aggregated delta is recorded as well,
+// and if the deltas could not be aggregated, kind is DeltaApplicationFailure.
 #[derive(Clone, PartialEq)]
 enum ReadKind {
     MVHashMap(TxnIndex, Incarnation),
+    MVHashMapDelta(TxnIndex, Incarnation, DeltaOp),
     Storage,
+    StorageDelta(DeltaOp),
+    DeltaApplicationFailure,
 }
 
 #[derive(Clone)]
@@ -38,6 +44,18 @@ impl<K> ReadDescriptor<K> {
         }
     }
 
+    pub fn from_delta(
+        access_path: K,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        delta: DeltaOp,
+    ) -> Self {
+        Self {
+            access_path,
+            kind: ReadKind::MVHashMapDelta(txn_idx, incarnation, delta),
+        }
+    }
+
     pub fn from_storage(access_path: K) -> Self {
         Self {
             access_path,
@@ -45,6 +63,20 @@ impl<K> ReadDescriptor<K> {
         }
     }
 
+    pub fn from_storage_delta(access_path: K, delta: DeltaOp) -> Self {
+        Self {
+            access_path,
+            kind: ReadKind::StorageDelta(delta),
+        }
+    }
+
+    pub fn from_delta_application_failure(access_path: K) -> Self {
+        Self {
+            access_path,
+            kind: ReadKind::DeltaApplicationFailure,
+        }
+    }
+
     pub fn path(&self) -> &K {
         &self.access_path
     }
@@ -55,10 +87,27 @@ impl<K> ReadDescriptor<K> {
         self.kind == ReadKind::MVHashMap(txn_idx, incarnation)
     }
 
+    // Does the read descriptor describe a read from MVHashMap w. a specified version,
+    // with a specified delta applied on top.
+    pub fn validate_delta(&self, version: Version, delta: DeltaOp) -> bool {
+        let (txn_idx, incarnation) = version;
+        self.kind == ReadKind::MVHashMapDelta(txn_idx, incarnation, delta)
+    }
+
     // Does the read descriptor describe a read from storage.
     pub fn validate_storage(&self) -> bool {
         self.kind == ReadKind::Storage
     }
+
+    // Does the read descriptor describe a read from storage with a specified delta applied.
+    pub fn validate_storage_delta(&self, delta: DeltaOp) -> bool {
+        self.kind == ReadKind::StorageDelta(delta)
+    }
+
+    // Does the read descriptor describe a read where deltas could not be aggregated.
+    pub fn validate_delta_application_failure(&self) -> bool {
+        self.kind == ReadKind::DeltaApplicationFailure
+    }
 }
 
 pub struct TxnLastInputOutput<K, T, E> {
@@ -93,7 +142,8 @@ impl<K, T: TransactionOutput, E: Send + Clone> TxnLastInputOutput<K, T, E> {
         self.inputs[txn_idx].load_full()
     }
 
-    // Extracts a set of paths written during execution from transaction output.
+    // Extracts a set of paths written (or updated by deltas) during execution from
+    // transaction output.
     pub fn write_set(
         &self,
         txn_idx: TxnIndex,
@@ -101,9 +151,12 @@ impl<K, T: TransactionOutput, E: Send + Clone> TxnLastInputOutput<K, T, E> {
         match &self.outputs[txn_idx].load_full() {
             None => HashSet::new(),
             Some(txn_output) => match txn_output.as_ref() {
-                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => {
-                    t.get_writes().into_iter().map(|(k, _)| k).collect()
-                }
+                ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => t
+                    .get_writes()
+                    .into_iter()
+                    .map(|(k, _)| k)
+                    .chain(t.get_deltas().into_iter().map(|(k, _)| k))
+                    .collect(),
                 ExecutionStatus::Abort(_) => HashSet::new(),
             },
         }
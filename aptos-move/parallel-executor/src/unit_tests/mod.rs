@@ -3,16 +3,17 @@
 
 use crate::{
     executor::ParallelTransactionExecutor,
-    proptest_types::types::{ExpectedOutput, Task, Transaction},
+    proptest_types::types::{AggregatorValue, ExpectedOutput, Task, Transaction},
     scheduler::{Scheduler, SchedulerTask, TaskGuard},
 };
+use aptos_aggregator::delta_change_set::DeltaOp;
 use rand::random;
 use std::{fmt::Debug, hash::Hash, sync::atomic::AtomicUsize};
 
 fn run_and_assert<K, V>(transactions: Vec<Transaction<K, V>>)
 where
     K: PartialOrd + Send + Sync + Clone + Hash + Eq + 'static,
-    V: Send + Sync + Debug + Clone + Eq + 'static,
+    V: Send + Sync + Debug + Clone + Eq + AggregatorValue + 'static,
 {
     let baseline = ExpectedOutput::generate_baseline(&transactions);
 
@@ -37,6 +38,7 @@ fn cycle_transactions() {
                 reads: vec![key],
                 actual_writes: vec![(key, random::<u64>())],
                 skipped_writes: vec![],
+                deltas: vec![],
             })
         }
     }
@@ -56,6 +58,7 @@ fn one_reads_all_barrier() {
                 reads: vec![*key],
                 actual_writes: vec![(*key, random::<u64>())],
                 skipped_writes: vec![],
+                deltas: vec![],
             })
         }
         // One transaction reading the write results of every prior transactions in the block.
@@ -63,6 +66,7 @@ fn one_reads_all_barrier() {
             reads: keys.clone(),
             actual_writes: vec![],
             skipped_writes: vec![],
+            deltas: vec![],
         })
     }
     run_and_assert(transactions)
@@ -78,6 +82,7 @@ fn one_writes_all_barrier() {
                 reads: vec![*key],
                 actual_writes: vec![(*key, random::<u64>())],
                 skipped_writes: vec![],
+                deltas: vec![],
             })
         }
         // One transaction writing to the write results of every prior transactions in the block.
@@ -88,6 +93,7 @@ fn one_writes_all_barrier() {
                 .map(|key| (*key, random::<u64>()))
                 .collect::<Vec<_>>(),
             skipped_writes: vec![],
+            deltas: vec![],
         })
     }
     run_and_assert(transactions)
@@ -104,6 +110,7 @@ fn early_aborts() {
                 reads: vec![*key],
                 actual_writes: vec![(*key, random::<u64>())],
                 skipped_writes: vec![],
+                deltas: vec![],
             })
         }
         // One transaction that triggers an abort
@@ -123,6 +130,7 @@ fn early_skips() {
                 reads: vec![*key],
                 actual_writes: vec![(*key, random::<u64>())],
                 skipped_writes: vec![],
+                deltas: vec![],
             })
         }
         // One transaction that triggers an abort
@@ -131,6 +139,119 @@ fn early_skips() {
     run_and_assert(transactions)
 }
 
+#[test]
+fn aggregator_deltas() {
+    let mut transactions = vec![];
+    let keys: Vec<_> = (0..TXN_PER_BLOCK).map(|_| random::<[u8; 32]>()).collect();
+
+    for i in 0..NUM_BLOCKS {
+        for key in &keys {
+            // Deltas on top of storage, then on top of the writes of the previous block.
+            transactions.push(Transaction::Write {
+                reads: vec![*key],
+                actual_writes: vec![],
+                skipped_writes: vec![],
+                deltas: vec![(
+                    *key,
+                    DeltaOp::addition(random::<u32>() as u128, u128::MAX).unwrap(),
+                )],
+            })
+        }
+        // One transaction reading the aggregated deltas of every key, and overwriting half of them.
+        transactions.push(Transaction::Write {
+            reads: keys.clone(),
+            actual_writes: keys
+                .iter()
+                .skip(i as usize % 2)
+                .step_by(2)
+                .map(|key| (*key, random::<u64>() as u128))
+                .collect::<Vec<_>>(),
+            skipped_writes: vec![],
+            deltas: vec![],
+        })
+    }
+    run_and_assert(transactions)
+}
+
+#[test]
+fn execution_stats() {
+    let mut transactions = vec![];
+    let keys: Vec<_> = (0..TXN_PER_BLOCK).map(|_| random::<[u8; 32]>()).collect();
+    for key in &keys {
+        transactions.push(Transaction::Write {
+            reads: vec![*key],
+            actual_writes: vec![(*key, random::<u64>())],
+            skipped_writes: vec![],
+            deltas: vec![],
+        })
+    }
+    // One transaction that triggers an abort, the rest of the block is not executed.
+    transactions.push(Transaction::Abort);
+    transactions.push(Transaction::Write {
+        reads: keys.clone(),
+        actual_writes: vec![],
+        skipped_writes: vec![],
+        deltas: vec![],
+    });
+
+    let executor =
+        ParallelTransactionExecutor::<Transaction<[u8; 32], u64>, Task<[u8; 32], u64>>::with_concurrency_level(2);
+    assert_eq!(executor.concurrency_level(), 2);
+    assert!(executor.last_execution_stats().is_none());
+
+    let _ = executor.execute_transactions_parallel((), transactions);
+
+    let stats = executor.last_execution_stats().unwrap();
+    assert_eq!(stats.incarnations.len(), TXN_PER_BLOCK as usize + 1);
+    // Every transaction up to the abort is executed at least once.
+    assert!(stats.incarnations.iter().all(|n| *n >= 1));
+    assert!(stats.total_incarnations() >= stats.incarnations.len());
+    assert!(stats.top_conflicting_keys.len() <= 10);
+    assert!(stats
+        .top_conflicting_keys
+        .windows(2)
+        .all(|w| w[0].1 >= w[1].1));
+
+    // A conflicting workload, where every transaction reads and writes the same key.
+    let key = random::<[u8; 32]>();
+    let transactions: Vec<_> = (0..NUM_BLOCKS * TXN_PER_BLOCK)
+        .map(|_| Transaction::Write {
+            reads: vec![key],
+            actual_writes: vec![(key, random::<u64>())],
+            skipped_writes: vec![],
+            deltas: vec![],
+        })
+        .collect();
+    let num_txns = transactions.len();
+
+    let executor =
+        ParallelTransactionExecutor::<Transaction<[u8; 32], u64>, Task<[u8; 32], u64>>::with_concurrency_level(4);
+    assert!(executor
+        .execute_transactions_parallel((), transactions)
+        .is_ok());
+
+    let stats = executor.last_execution_stats().unwrap();
+    assert_eq!(stats.incarnations.len(), num_txns);
+    // Every incarnation after the first one is caused by a validation abort or a dependency.
+    assert_eq!(
+        stats.total_incarnations(),
+        num_txns + stats.validation_aborts + stats.dependency_waits
+    );
+    // All validation aborts are caused by reads of the only key.
+    if stats.validation_aborts > 0 {
+        assert_eq!(
+            stats.top_conflicting_keys,
+            vec![(key, stats.validation_aborts)]
+        );
+    } else {
+        assert!(stats.top_conflicting_keys.is_empty());
+    }
+    // Transactions executed concurrently conflict, unless the threads happen to be serialized.
+    if num_cpus::get() > 1 {
+        assert!(stats.validation_aborts + stats.dependency_waits > 0);
+    }
+}
+
 #[test]
 fn scheduler_tasks() {
     let s = Scheduler::new(6);
@@ -3,6 +3,7 @@
 
 use anyhow::{anyhow, bail, format_err, Result};
 use aptos_resource_viewer::{AnnotatedAccountStateBlob, AnnotatedMoveStruct, AptosValueAnnotator};
+use aptos_rest_client::Client;
 use aptos_state_view::StateView;
 use aptos_types::{
     access_path,
@@ -14,7 +15,9 @@ use aptos_types::{
     transaction::{ChangeSet, Transaction, TransactionOutput, Version, WriteSetPayload},
     write_set::WriteOp,
 };
-use aptos_validator_interface::{AptosValidatorInterface, DBDebuggerInterface, DebuggerStateView};
+use aptos_validator_interface::{
+    AptosValidatorInterface, DBDebuggerInterface, DebuggerStateView, RestDebuggerInterface,
+};
 use aptos_vm::{
     data_cache::RemoteStorage,
     logging::AdapterLogSchema,
@@ -54,6 +57,12 @@ impl AptosDebugger {
         )?)))
     }
 
+    pub fn rest_client(rest_client: Client) -> Result<Self> {
+        Ok(Self::new(Box::new(RestDebuggerInterface::new(
+            rest_client,
+        )?)))
+    }
+
     pub fn execute_transactions_at_version(
         &self,
         version: Version,
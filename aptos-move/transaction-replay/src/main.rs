@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{bail, Result};
+use aptos_rest_client::Client;
 use aptos_transaction_replay::AptosDebugger;
 use aptos_types::{
     account_address::AccountAddress,
@@ -12,13 +13,15 @@ use difference::Changeset;
 use move_core_types::effects::ChangeSet;
 use std::{fs, path::PathBuf};
 use structopt::StructOpt;
+use url::Url;
 
 #[derive(Debug, StructOpt)]
 struct Opt {
     /// Path to the local AptosDB file
     #[structopt(long, parse(from_os_str))]
     db: Option<PathBuf>,
-    /// Full URL address to connect to - should include port number, if applicable
+    /// Full URL of the REST API of a fullnode to replay from - should include port number, if
+    /// applicable
     #[structopt(short = "u", long)]
     url: Option<String>,
     /// If true, persist the effects of replaying transactions via `cmd` to disk in a format understood by the Move CLI
@@ -92,6 +95,8 @@ fn main() -> Result<()> {
     let opt = Opt::from_args();
     let debugger = if let Some(p) = opt.db {
         AptosDebugger::db(p)?
+    } else if let Some(url) = opt.url {
+        AptosDebugger::rest_client(Client::new(Url::parse(&url)?))?
     } else {
         panic!("No debugger attached")
     };
@@ -4,8 +4,8 @@
 use aptos_api::runtime::bootstrap as bootstrap_api;
 use aptos_config::{
     config::{
-        AptosDataClientConfig, DataStreamingServiceConfig, NetworkConfig, NodeConfig,
-        PersistableConfig, StorageServiceConfig,
+        AptosDataClientConfig, BootstrappingMode, DataStreamingServiceConfig, NetworkConfig,
+        NodeConfig, PersistableConfig, StorageServiceConfig,
     },
     network_id::NetworkId,
     utils::get_genesis_txn,
@@ -23,11 +23,16 @@ use aptos_types::{
     on_chain_config::{VMPublishingOption, ON_CHAIN_CONFIG_REGISTRY},
     state_store::state_key::StateKey,
     waypoint::Waypoint,
+    PeerId,
 };
 use aptos_vm::AptosVM;
 use aptosdb::AptosDB;
+#[cfg(feature = "restore-from-backup")]
+use aptosdb::GetRestoreHandler;
+#[cfg(feature = "restore-from-backup")]
+use backup_cli::coordinators::bootstrap::BootstrapCoordinator;
 use backup_service::start_backup_service;
-use consensus::consensus_provider::start_consensus;
+use consensus::{consensus_provider::start_consensus, ConsensusIntrospection};
 use consensus_notifications::ConsensusNotificationListener;
 use data_streaming_service::{
     streaming_client::{new_streaming_service_client_listener_pair, StreamingServiceClient},
@@ -35,7 +40,11 @@ use data_streaming_service::{
 };
 use debug_interface::node_debug_service::NodeDebugService;
 use event_notifications::EventSubscriptionService;
-use executor::{chunk_executor::ChunkExecutor, db_bootstrapper::maybe_bootstrap};
+use executor::{
+    chunk_executor::ChunkExecutor, components::speculation_cache::SpeculationCache,
+    db_bootstrapper::maybe_bootstrap, pre_executor::PreExecutor,
+};
+use executor_types::TransactionPreExecutor;
 use futures::channel::mpsc::channel;
 use mempool_notifications::MempoolNotificationSender;
 use network::application::storage::PeerMetadataStorage;
@@ -58,9 +67,12 @@ use std::{
     thread,
     time::Instant,
 };
+#[cfg(feature = "restore-from-backup")]
+use std::{cmp::min, time::Duration};
 use storage_interface::DbReaderWriter;
 use storage_service::start_storage_service_with_db;
 use storage_service_client::{StorageServiceClient, StorageServiceMultiSender};
+use storage_service_notifications::StorageServiceNotificationListener;
 use storage_service_server::{
     network::StorageServiceNetworkEvents, StorageReader, StorageServiceServer,
 };
@@ -71,6 +83,13 @@ const AC_SMP_CHANNEL_BUFFER_SIZE: usize = 1_024;
 const INTRA_NODE_CHANNEL_BUFFER_SIZE: usize = 1;
 const MEMPOOL_NETWORK_CHANNEL_BUFFER_SIZE: usize = 1_024;
 
+#[cfg(feature = "restore-from-backup")]
+const BACKUP_RESTORE_PROGRESS_FILE_NAME: &str = "backup_restore_progress.json";
+#[cfg(feature = "restore-from-backup")]
+const MAX_BACKUP_RESTORE_ATTEMPTS: u32 = 10;
+#[cfg(feature = "restore-from-backup")]
+const MAX_BACKUP_RESTORE_RETRY_DELAY_SECS: u64 = 60;
+
 pub struct AptosHandle {
     _api: Runtime,
     _backup: Runtime,
@@ -243,7 +262,7 @@ fn setup_debug_interface(config: &NodeConfig, logger: Option<Arc<Logger>>) -> No
 
 fn create_state_sync_runtimes<M: MempoolNotificationSender + 'static>(
     node_config: &NodeConfig,
-    storage_service_server_network_handles: Vec<StorageServiceNetworkEvents>,
+    storage_service_server_network_handles: Vec<(NetworkId, StorageServiceNetworkEvents)>,
     storage_service_client_network_handles: HashMap<
         NetworkId,
         storage_service_client::StorageServiceNetworkSender,
@@ -256,19 +275,38 @@ fn create_state_sync_runtimes<M: MempoolNotificationSender + 'static>(
     event_subscription_service: EventSubscriptionService,
     db_rw: DbReaderWriter,
 ) -> StateSyncRuntimes {
-    // Start the state sync storage service
+    // Start the state sync storage service (notified by state sync of new commits)
+    let (storage_service_notifier, storage_service_listener) =
+        storage_service_notifications::new_storage_service_notifier_listener_pair();
     let storage_service_runtime = setup_state_sync_storage_service(
         node_config.state_sync.storage_service,
         storage_service_server_network_handles,
+        storage_service_listener,
         &db_rw,
     );
 
+    // Identify the trusted peers to sync from (if any)
+    let trusted_peers = if node_config
+        .state_sync
+        .state_sync_driver
+        .syncs_from_trusted_source()
+    {
+        let trusted_peers = node_config.state_sync.trusted_source.trusted_peers.clone();
+        if trusted_peers.is_empty() {
+            panic!("The node is configured to sync from a trusted source, but no trusted peers were specified!");
+        }
+        trusted_peers
+    } else {
+        vec![]
+    };
+
     // Start the data client
     let (aptos_data_client, aptos_data_client_runtime) = setup_aptos_data_client(
         node_config.state_sync.storage_service,
         node_config.state_sync.aptos_data_client,
         storage_service_client_network_handles,
         peer_metadata_storage,
+        trusted_peers,
     );
 
     // Start the data streaming service
@@ -286,6 +324,7 @@ fn create_state_sync_runtimes<M: MempoolNotificationSender + 'static>(
     let state_sync_multiplexer = StateSyncMultiplexer::new(
         state_sync_network_handles,
         mempool_notifier,
+        storage_service_notifier,
         consensus_listener,
         db_rw,
         chunk_executor,
@@ -331,6 +370,7 @@ fn setup_aptos_data_client(
     aptos_data_client_config: AptosDataClientConfig,
     network_handles: HashMap<NetworkId, storage_service_client::StorageServiceNetworkSender>,
     peer_metadata_storage: Arc<PeerMetadataStorage>,
+    trusted_peers: Vec<PeerId>,
 ) -> (AptosNetDataClient, Runtime) {
     // Combine all storage service client handles
     let network_client = StorageServiceClient::new(
@@ -344,6 +384,7 @@ fn setup_aptos_data_client(
         storage_service_config,
         TimeService::real(),
         network_client,
+        trusted_peers,
     );
 
     // Create a new runtime for the data client and spawn the data poller
@@ -359,7 +400,8 @@ fn setup_aptos_data_client(
 
 fn setup_state_sync_storage_service(
     config: StorageServiceConfig,
-    network_handles: Vec<StorageServiceNetworkEvents>,
+    network_handles: Vec<(NetworkId, StorageServiceNetworkEvents)>,
+    storage_service_listener: StorageServiceNotificationListener,
     db_rw: &DbReaderWriter,
 ) -> Runtime {
     // Create a new state sync storage service runtime
@@ -369,18 +411,17 @@ fn setup_state_sync_storage_service(
         .build()
         .expect("Failed to start the AptosNet storage-service runtime.");
 
-    // Spawn all state sync storage service servers on the same runtime
+    // Spawn the state sync storage service server (for all networks)
     let storage_reader = StorageReader::new(config, Arc::clone(&db_rw.reader));
-    for events in network_handles {
-        let service = StorageServiceServer::new(
-            config,
-            storage_service_runtime.handle().clone(),
-            storage_reader.clone(),
-            TimeService::real(),
-            events,
-        );
-        storage_service_runtime.spawn(service.start());
-    }
+    let service = StorageServiceServer::new(
+        config,
+        storage_service_runtime.handle().clone(),
+        storage_reader,
+        TimeService::real(),
+        network_handles,
+        storage_service_listener,
+    );
+    storage_service_runtime.spawn(service.start());
 
     storage_service_runtime
 }
@@ -429,6 +470,84 @@ async fn periodic_state_dump(node_config: NodeConfig, db: DbReaderWriter) {
     }
 }
 
+/// Bootstraps the DB from the configured backup storage (if the node is set to
+/// bootstrap from backup). The restore progress is persisted in the storage dir, so
+/// failed attempts (and restarts) resume where the last one stopped. Once done, state
+/// sync catches up from peers as usual.
+#[cfg(feature = "restore-from-backup")]
+fn maybe_bootstrap_from_backup(
+    node_config: &NodeConfig,
+    aptos_db: &Arc<AptosDB>,
+) -> anyhow::Result<()> {
+    if node_config.state_sync.state_sync_driver.bootstrapping_mode
+        != BootstrappingMode::RestoreFromBackupStorage
+    {
+        return Ok(());
+    }
+
+    let instant = Instant::now();
+    let restore_runtime = Builder::new_multi_thread()
+        .thread_name("backup-restore")
+        .enable_all()
+        .build()?;
+    let progress_file = node_config
+        .storage
+        .dir()
+        .join(BACKUP_RESTORE_PROGRESS_FILE_NAME);
+    let mut num_attempts = 0;
+    loop {
+        num_attempts += 1;
+        let result = restore_runtime.block_on(
+            BootstrapCoordinator::new(
+                node_config.state_sync.backup_restore.clone(),
+                Arc::clone(aptos_db).get_restore_handler(),
+                node_config.base.waypoint.waypoint(),
+                progress_file.clone(),
+            )
+            .run(),
+        );
+        match result {
+            Ok(()) => break,
+            Err(error) if num_attempts < MAX_BACKUP_RESTORE_ATTEMPTS => {
+                let retry_delay = Duration::from_secs(min(
+                    1 << num_attempts,
+                    MAX_BACKUP_RESTORE_RETRY_DELAY_SECS,
+                ));
+                warn!(
+                    "Failed to bootstrap from backup storage (attempt {}), retrying in {:?}. Error: {:?}",
+                    num_attempts, retry_delay, error
+                );
+                thread::sleep(retry_delay);
+            }
+            Err(error) => {
+                return Err(error.context(format!(
+                    "Failed to bootstrap from backup storage after {} attempts",
+                    num_attempts
+                )))
+            }
+        }
+    }
+    info!(
+        "Bootstrapped from backup storage in {} ms",
+        instant.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "restore-from-backup"))]
+fn maybe_bootstrap_from_backup(
+    node_config: &NodeConfig,
+    _aptos_db: &Arc<AptosDB>,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        node_config.state_sync.state_sync_driver.bootstrapping_mode
+            != BootstrappingMode::RestoreFromBackupStorage,
+        "The node is set to bootstrap from backup storage, but isn't built with the \
+        `restore-from-backup` feature!"
+    );
+    Ok(())
+}
+
 pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>) -> AptosHandle {
     let debug_if = setup_debug_interface(node_config, logger);
 
@@ -441,6 +560,9 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         metric_server::start_server(public_metric_host, public_metrics_port, true)
     });
 
+    // Set the number of threads used for parallel execution before any block is executed.
+    AptosVM::set_concurrency_level_once(node_config.execution.concurrency_level);
+
     let mut instant = Instant::now();
     let (aptos_db, db_rw) = DbReaderWriter::wrap(
         AptosDB::open(
@@ -457,6 +579,10 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         Arc::clone(&aptos_db),
     );
 
+    // if bootstrapping from backup storage, restore the DB before anything else is committed.
+    maybe_bootstrap_from_backup(node_config, &aptos_db)
+        .expect("Failed to bootstrap from backup storage!");
+
     let genesis_waypoint = node_config.base.waypoint.genesis_waypoint();
     // if there's genesis txn and waypoint, commit it if the result matches.
     if let Some(genesis) = get_genesis_txn(node_config) {
@@ -520,7 +646,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     });
     let network_ids: Vec<_> = network_ids.into_iter().collect();
 
-    let peer_metadata_storage = PeerMetadataStorage::new(&network_ids);
+    let peer_metadata_storage = PeerMetadataStorage::new(&network_ids, TimeService::real());
     for network_config in network_configs.into_iter() {
         debug!("Creating runtime for {}", network_config.network_id);
         let runtime = Builder::new_multi_thread()
@@ -540,9 +666,20 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             TimeService::real(),
             Some(&mut event_subscription_service),
             peer_metadata_storage.clone(),
-        );
+        )
+        .unwrap_or_else(|error| {
+            panic!(
+                "Invalid {} network config: {}",
+                network_config.network_id, error
+            )
+        });
         let network_id = network_config.network_id;
 
+        // Report the latest synced version to the peers monitoring this node.
+        let db_reader = db_rw.reader.clone();
+        network_builder
+            .set_synced_version_reader(Arc::new(move || db_reader.get_latest_version().ok()));
+
         // Create the endpoints to connect the Network to State Sync.
         let (state_sync_sender, state_sync_events) =
             network_builder.add_p2p_service(&state_sync_v1_network_config());
@@ -557,7 +694,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             network_builder.add_service(&storage_service_server::network::network_endpoint_config(
                 node_config.state_sync.storage_service,
             ));
-        storage_service_server_network_handles.push(storage_service_events);
+        storage_service_server_network_handles.push((network_id, storage_service_events));
 
         // Register the storage-service clients with Network
         let storage_service_sender =
@@ -623,6 +760,25 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     let mut consensus_runtime = None;
     let (consensus_to_mempool_sender, consensus_requests) = channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
 
+    // Pre-executed transaction outputs are shared between mempool, which fills the cache, and
+    // the block executor of consensus.
+    let pre_execution_config = &node_config.execution.pre_execution;
+    let speculation_cache = if pre_execution_config.enabled && node_config.base.role.is_validator()
+    {
+        Some(Arc::new(SpeculationCache::new(
+            pre_execution_config.cache_capacity,
+        )))
+    } else {
+        None
+    };
+    let pre_executor = speculation_cache.as_ref().map(|speculation_cache| {
+        Arc::new(PreExecutor::<AptosVM>::new(
+            Arc::clone(&db_rw.reader),
+            speculation_cache.clone(),
+            pre_execution_config.num_threads,
+        )) as Arc<dyn TransactionPreExecutor>
+    });
+
     instant = Instant::now();
     let mempool = aptos_mempool::bootstrap(
         node_config,
@@ -633,6 +789,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         mempool_listener,
         mempool_reconfig_subscription,
         peer_metadata_storage.clone(),
+        pre_executor,
     );
     debug!("Mempool started in {} ms", instant.elapsed().as_millis());
 
@@ -650,6 +807,9 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
 
         // Initialize and start consensus.
         instant = Instant::now();
+        let consensus_introspection = ConsensusIntrospection::default();
+        let introspection = consensus_introspection.clone();
+        debug_if.register_snapshot("consensus", Arc::new(move || introspection.snapshot_json()));
         consensus_runtime = Some(start_consensus(
             node_config,
             consensus_network_sender,
@@ -660,6 +820,8 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             consensus_reconfig_subscription
                 .expect("Consensus requires a reconfiguration subscription!"),
             peer_metadata_storage,
+            consensus_introspection,
+            speculation_cache,
         ));
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
     }
@@ -26,6 +26,11 @@ pub struct ConsensusConfig {
     // the period = (poll_count - 1) * 30ms
     pub mempool_poll_count: u64,
     pub channel_size: usize,
+    // Batch dissemination settings, only used when the quorum store is enabled on-chain
+    pub quorum_store: QuorumStoreConfig,
+    // Appends every input of consensus and every SafetyRules response to this file so that the
+    // run can be replayed offline, disabled if not set
+    pub recorder_path: Option<PathBuf>,
 }
 
 impl Default for ConsensusConfig {
@@ -40,11 +45,15 @@ impl Default for ConsensusConfig {
             proposer_type: ConsensusProposerType::LeaderReputation(LeaderReputationConfig {
                 active_weights: 99,
                 inactive_weights: 1,
+                failed_weights: default_failed_weights(),
+                failure_threshold_percent: default_failure_threshold_percent(),
             }),
             safety_rules: SafetyRulesConfig::default(),
             sync_only: false,
             mempool_poll_count: 20,
             channel_size: 30, // hard-coded
+            quorum_store: QuorumStoreConfig::default(),
+            recorder_path: None,
         }
     }
 }
@@ -75,4 +84,61 @@ pub enum ConsensusProposerType {
 pub struct LeaderReputationConfig {
     pub active_weights: u64,
     pub inactive_weights: u64,
+    // Weight of the validators that failed too many of the rounds they were elected for,
+    // zero excludes them from the election
+    #[serde(default = "default_failed_weights")]
+    pub failed_weights: u64,
+    // Percentage of failed rounds out of the elected ones at which the failed weight applies
+    #[serde(default = "default_failure_threshold_percent")]
+    pub failure_threshold_percent: u64,
+}
+
+fn default_failed_weights() -> u64 {
+    1
+}
+
+fn default_failure_threshold_percent() -> u64 {
+    10
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuorumStoreConfig {
+    // How often a validator pulls transactions from mempool to form a new batch (in milliseconds)
+    pub batch_generation_interval_ms: u64,
+    // Max number of transactions in a batch
+    pub max_batch_size: u64,
+    // Max number of bytes of the transactions in a batch
+    pub max_batch_bytes: usize,
+    // Number of rounds a batch is guaranteed to be stored by the signers of its proof
+    pub batch_expiry_rounds: u64,
+    // Max number of rounds past the last committed round a received batch may expire at
+    pub max_batch_expiry_rounds: u64,
+    // Max number of batches that are broadcast but not yet committed or expired
+    pub max_pending_batches: usize,
+    // Timeout for fetching a missing batch from one of the signers (in milliseconds)
+    pub batch_request_timeout_ms: u64,
+    // Max number of batches kept in memory, across all sources
+    pub batch_store_capacity: usize,
+    // Max number of batches kept in memory for a single source
+    pub batch_store_per_author_quota: usize,
+    // Max number of bytes of the batches kept in memory for a single source
+    pub batch_store_per_author_bytes_quota: usize,
+}
+
+impl Default for QuorumStoreConfig {
+    fn default() -> QuorumStoreConfig {
+        QuorumStoreConfig {
+            batch_generation_interval_ms: 100,
+            max_batch_size: 500,
+            max_batch_bytes: 1024 * 1024, // 1MB
+            batch_expiry_rounds: 20,
+            max_batch_expiry_rounds: 40,
+            max_pending_batches: 20,
+            batch_request_timeout_ms: 1000,
+            batch_store_capacity: 10_000,
+            batch_store_per_author_quota: 500,
+            batch_store_per_author_bytes_quota: 64 * 1024 * 1024, // 64MB
+        }
+    }
 }
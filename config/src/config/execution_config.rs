@@ -23,6 +23,10 @@ pub struct ExecutionConfig {
     pub service: ExecutionCorrectnessService,
     pub backend: SecureBackend,
     pub network_timeout_ms: u64,
+    /// Number of threads used by the parallel executor, 0 means the number of CPUs.
+    pub concurrency_level: usize,
+    pub pre_execution: PreExecutionConfig,
+    pub observer: ExecutionObserverConfig,
 }
 
 impl std::fmt::Debug for ExecutionConfig {
@@ -40,8 +44,14 @@ impl std::fmt::Debug for ExecutionConfig {
         )?;
         write!(
             f,
-            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?} }}",
-            self.sign_vote_proposal, self.service, self.backend
+            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?}, concurrency_level: {:?}, \
+             pre_execution: {:?}, observer: {:?} }}",
+            self.sign_vote_proposal,
+            self.service,
+            self.backend,
+            self.concurrency_level,
+            self.pre_execution,
+            self.observer
         )?;
         self.service.fmt(f)
     }
@@ -57,6 +67,10 @@ impl Default for ExecutionConfig {
             sign_vote_proposal: true,
             // Default value of 30 seconds for the network timeout.
             network_timeout_ms: 30_000,
+            // Use all available CPUs by default.
+            concurrency_level: 0,
+            pre_execution: PreExecutionConfig::default(),
+            observer: ExecutionObserverConfig::default(),
         }
     }
 }
@@ -97,6 +111,57 @@ impl ExecutionConfig {
     }
 }
 
+/// Speculative execution of the transactions at the top of mempool, ahead of block proposals.
+/// The outputs are reused by the block executor when the state they read is unchanged.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PreExecutionConfig {
+    pub enabled: bool,
+    /// Maximum number of mempool transactions pre-executed per round.
+    pub batch_size: u64,
+    /// Interval between pre-execution rounds.
+    pub interval_ms: u64,
+    /// Number of threads dedicated to pre-execution.
+    pub num_threads: usize,
+    /// Maximum number of pre-executed outputs kept in memory.
+    pub cache_capacity: usize,
+}
+
+impl Default for PreExecutionConfig {
+    fn default() -> PreExecutionConfig {
+        PreExecutionConfig {
+            enabled: false,
+            batch_size: 500,
+            interval_ms: 100,
+            num_threads: 2,
+            cache_capacity: 10_000,
+        }
+    }
+}
+
+/// Records the TransactionInfo of every executed transaction, to locate the first transaction
+/// whose execution differs from the certified ledger when the local state root diverges.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecutionObserverConfig {
+    pub enabled: bool,
+    /// Maximum number of uncommitted blocks whose execution results are kept.
+    pub max_recorded_blocks: usize,
+    /// Directory divergences are persisted to until diagnosed, and their reports written to. If
+    /// not set, divergences are only logged and lost on restart.
+    pub dump_dir: Option<PathBuf>,
+}
+
+impl Default for ExecutionObserverConfig {
+    fn default() -> ExecutionObserverConfig {
+        ExecutionObserverConfig {
+            enabled: false,
+            max_recorded_blocks: 100,
+            dump_dir: None,
+        }
+    }
+}
+
 /// Defines how execution correctness should be run
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
@@ -234,6 +234,7 @@ impl NodeConfig {
         config.execution.load(&input_dir)?;
 
         let mut config = config.validate_network_configs()?;
+        config.state_sync.state_sync_driver.verify()?;
         config.set_data_dir(config.data_dir().to_path_buf());
         Ok(config)
     }
@@ -461,6 +462,47 @@ mod test {
         }
     }
 
+    #[test]
+    fn verify_state_sync_driver_trusted_source_modes() {
+        let mut driver_config = StateSyncDriverConfig::default();
+        driver_config.verify().unwrap();
+
+        // Mixing trusted source and non-trusted source modes is rejected
+        driver_config.bootstrapping_mode = BootstrappingMode::SyncFromTrustedSource;
+        assert!(matches!(
+            driver_config.verify(),
+            Err(Error::InvariantViolation(_))
+        ));
+        driver_config.bootstrapping_mode = BootstrappingMode::ExecuteTransactionsFromGenesis;
+        driver_config.continuous_syncing_mode = ContinuousSyncingMode::SyncFromTrustedSource;
+        assert!(matches!(
+            driver_config.verify(),
+            Err(Error::InvariantViolation(_))
+        ));
+
+        // Syncing from a trusted source in both modes is accepted
+        driver_config.bootstrapping_mode = BootstrappingMode::SyncFromTrustedSource;
+        driver_config.verify().unwrap();
+    }
+
+    #[test]
+    fn verify_fault_injection_rejected_on_quic() {
+        let mut network_config = NetworkConfig::network_with_id(NetworkId::Public);
+        network_config.fault_injection = Some(FaultInjectionConfig {
+            faults_path: PathBuf::from("faults.yaml"),
+            reload_interval_ms: 1000,
+        });
+
+        network_config.listen_address = "/ip4/0.0.0.0/tcp/6180".parse().unwrap();
+        network_config.clone().load_fullnode_network().unwrap();
+
+        network_config.listen_address = "/ip4/0.0.0.0/quic/6180".parse().unwrap();
+        assert!(matches!(
+            network_config.load_fullnode_network(),
+            Err(Error::InvariantViolation(_))
+        ));
+    }
+
     #[test]
     fn verify_configs() {
         NodeConfig::default_for_public_full_node();
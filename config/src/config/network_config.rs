@@ -10,7 +10,9 @@ use crate::{
 use aptos_crypto::{x25519, Uniform};
 use aptos_secure_storage::{CryptoStorage, KVStorage, Storage};
 use aptos_types::{
-    network_address::NetworkAddress, transaction::authenticator::AuthenticationKey, PeerId,
+    network_address::{NetworkAddress, Protocol},
+    transaction::authenticator::AuthenticationKey,
+    PeerId,
 };
 use rand::{
     rngs::{OsRng, StdRng},
@@ -25,6 +27,7 @@ use std::{
     string::ToString,
     time::Duration,
 };
+use url::Url;
 
 // TODO: We could possibly move these constants somewhere else, but since they are defaults for the
 //   configurations of the system, we'll leave it here for now.
@@ -35,6 +38,7 @@ pub const NETWORK_CHANNEL_SIZE: usize = 1024;
 pub const PING_INTERVAL_MS: u64 = 1000;
 pub const PING_TIMEOUT_MS: u64 = 10_000;
 pub const PING_FAILURES_TOLERATED: u64 = 5;
+pub const PEER_MONITORING_INTERVAL_MS: u64 = 5000;
 pub const CONNECTIVITY_CHECK_INTERVAL_MS: u64 = 5000;
 pub const MAX_CONCURRENT_NETWORK_REQS: usize = 100;
 pub const MAX_CONNECTION_DELAY_MS: u64 = 60_000; /* 1 minute */
@@ -42,9 +46,14 @@ pub const MAX_CONNECTION_DELAY_MS: u64 = 60_000; /* 1 minute */
 pub const MAX_FULLNODE_OUTBOUND_CONNECTIONS: usize = 1;
 pub const MAX_INBOUND_CONNECTIONS: usize = 100;
 pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024; /* 16 MiB */
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024; /* 64 MiB */
 pub const CONNECTION_BACKOFF_BASE: u64 = 2;
 pub const IP_BYTE_BUCKET_RATE: usize = 102400 /* 100 KiB */;
 pub const IP_BYTE_BUCKET_SIZE: usize = IP_BYTE_BUCKET_RATE;
+pub const PEER_BAN_THRESHOLD: u64 = 100;
+pub const PEER_SCORE_HALF_LIFE_SECS: u64 = 600; /* 10 minutes */
+pub const PEER_BAN_DURATION_SECS: u64 = 600; /* 10 minutes */
+pub const MAX_PEER_BAN_DURATION_SECS: u64 = 86_400; /* 1 day */
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -82,6 +91,9 @@ pub struct NetworkConfig {
     pub seeds: PeerSet,
     // The maximum size of an inbound or outbound request frame
     pub max_frame_size: usize,
+    // The maximum size of an inbound or outbound message, larger than the frame size
+    // for the messages sent as fragments over the connections that support it
+    pub max_message_size: usize,
     // Enables proxy protocol on incoming connections to get original source addresses
     pub enable_proxy_protocol: bool,
     // Interval to send healthcheck pings to peers
@@ -90,6 +102,8 @@ pub struct NetworkConfig {
     pub ping_timeout_ms: u64,
     // Number of failed healthcheck pings until a peer is marked unhealthy
     pub ping_failures_tolerated: u64,
+    // Interval to measure the latency, distance from the validators and synced version of peers
+    pub peer_monitoring_interval_ms: u64,
     // Maximum number of outbound connections, limited by ConnectivityManager
     pub max_outbound_connections: usize,
     // Maximum number of outbound connections, limited by PeerManager
@@ -98,6 +112,14 @@ pub struct NetworkConfig {
     pub inbound_rate_limit_config: Option<RateLimitConfig>,
     // Outbound rate limiting configuration, if not specified, no rate limiting
     pub outbound_rate_limit_config: Option<RateLimitConfig>,
+    // Per-protocol rate limits of the messages received from peers, by peer class
+    pub inbound_protocol_rate_limits: Vec<ProtocolRateLimitConfig>,
+    // Per-protocol rate limits of the messages sent to peers, by peer class
+    pub outbound_protocol_rate_limits: Vec<ProtocolRateLimitConfig>,
+    // Scoring and temporary bans of the peers reported for misbehaving
+    pub peer_reputation: PeerReputationConfig,
+    // Network faults injected in the outbound connections, only for testing builds
+    pub fault_injection: Option<FaultInjectionConfig>,
 }
 
 impl Default for NetworkConfig {
@@ -119,6 +141,7 @@ impl NetworkConfig {
             seed_addrs: HashMap::new(),
             seeds: PeerSet::default(),
             max_frame_size: MAX_FRAME_SIZE,
+            max_message_size: MAX_MESSAGE_SIZE,
             enable_proxy_protocol: false,
             max_connection_delay_ms: MAX_CONNECTION_DELAY_MS,
             connectivity_check_interval_ms: CONNECTIVITY_CHECK_INTERVAL_MS,
@@ -128,10 +151,15 @@ impl NetworkConfig {
             ping_interval_ms: PING_INTERVAL_MS,
             ping_timeout_ms: PING_TIMEOUT_MS,
             ping_failures_tolerated: PING_FAILURES_TOLERATED,
+            peer_monitoring_interval_ms: PEER_MONITORING_INTERVAL_MS,
             max_outbound_connections: MAX_FULLNODE_OUTBOUND_CONNECTIONS,
             max_inbound_connections: MAX_INBOUND_CONNECTIONS,
             inbound_rate_limit_config: None,
             outbound_rate_limit_config: None,
+            inbound_protocol_rate_limits: Vec::new(),
+            outbound_protocol_rate_limits: Vec::new(),
+            peer_reputation: PeerReputationConfig::default(),
+            fault_injection: None,
         };
         config.prepare_identity();
         config
@@ -181,6 +209,17 @@ impl NetworkConfig {
     /// Per convenience, so that NetworkId isn't needed to be specified for `validator_networks`
     pub fn load_validator_network(&mut self) -> Result<(), Error> {
         self.network_id = NetworkId::Validator;
+        // The validator set served by a REST endpoint isn't verified, only the on-chain
+        // validator set can decide who the validators connect to
+        if self
+            .discovery_methods()
+            .iter()
+            .any(|method| matches!(method, DiscoveryMethod::Rest(_)))
+        {
+            return Err(Error::InvariantViolation(
+                "REST discovery is not allowed on the validator network".to_string(),
+            ));
+        }
         self.load()
     }
 
@@ -200,6 +239,20 @@ impl NetworkConfig {
                 .ok_or_else(|| Error::InvariantViolation("No local IP".to_string()))?;
         }
 
+        // Faults are only injected in the TCP and memory transports
+        if self.fault_injection.is_some()
+            && self
+                .listen_address
+                .as_slice()
+                .iter()
+                .any(|protocol| matches!(protocol, Protocol::Quic(_)))
+        {
+            return Err(Error::InvariantViolation(format!(
+                "Fault injection isn't supported on the QUIC listen address {}",
+                self.listen_address
+            )));
+        }
+
         self.prepare_identity();
         Ok(())
     }
@@ -295,9 +348,19 @@ impl NetworkConfig {
 pub enum DiscoveryMethod {
     Onchain,
     File(PathBuf, Duration),
+    Rest(RestDiscovery),
     None,
 }
 
+/// Discovers the peers from the validator set, periodically fetched from the REST
+/// endpoint of a node that is already synced.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestDiscovery {
+    pub url: Url,
+    pub interval_secs: u64,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum Identity {
@@ -363,6 +426,115 @@ impl Default for RateLimitConfig {
     }
 }
 
+/// Rate limit of the messages of a protocol exchanged with each peer of a class
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProtocolRateLimitConfig {
+    /// Name of the limited protocol, e.g. `StorageServiceRpc`. The compressed variant of the
+    /// protocol shares its limit.
+    pub protocol: String,
+    /// Class of the peers the limit applies to, each peer having its own bucket
+    pub peer_class: PeerClass,
+    /// Maximum number of bytes/s for a peer
+    pub byte_bucket_rate: usize,
+    /// Maximum burst of bytes for a peer, at least the fill rate. A larger message is allowed
+    /// while the bucket isn't empty, and its excess is repaid by the next refills.
+    pub byte_bucket_size: usize,
+    /// Initial amount of tokens initially in the bucket
+    pub initial_bucket_fill_percentage: u8,
+    /// Allow for disabling the throttle, messages are still accounted in metrics
+    pub enabled: bool,
+}
+
+impl Default for ProtocolRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            protocol: String::new(),
+            peer_class: PeerClass::Public,
+            byte_bucket_rate: IP_BYTE_BUCKET_RATE,
+            byte_bucket_size: IP_BYTE_BUCKET_SIZE,
+            initial_bucket_fill_percentage: 100,
+            enabled: true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerReputationConfig {
+    /// Misbehavior score at which a peer is banned
+    pub ban_threshold: u64,
+    /// Time for the misbehavior score of a peer to decay by half
+    pub score_half_life_secs: u64,
+    /// Duration of the first ban of a peer, doubled on each following ban
+    pub ban_duration_secs: u64,
+    /// Maximum duration of a ban
+    pub max_ban_duration_secs: u64,
+    /// Allow for disabling the bans, misbehavior is still scored and reported in metrics
+    pub enabled: bool,
+}
+
+impl Default for PeerReputationConfig {
+    fn default() -> Self {
+        Self {
+            ban_threshold: PEER_BAN_THRESHOLD,
+            score_half_life_secs: PEER_SCORE_HALF_LIFE_SECS,
+            ban_duration_secs: PEER_BAN_DURATION_SECS,
+            max_ban_duration_secs: MAX_PEER_BAN_DURATION_SECS,
+            enabled: true,
+        }
+    }
+}
+
+/// Injection of network faults, to reproduce WAN conditions in tests. It requires a node built
+/// with the `fault-injection` feature.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FaultInjectionConfig {
+    /// Path of the [`NetworkFaults`] to inject, no faults are injected while it is missing
+    pub faults_path: PathBuf,
+    /// Interval to reload the faults, to change them at runtime
+    pub reload_interval_ms: u64,
+}
+
+/// The network faults injected in each direction of a connection
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LinkFaults {
+    /// Delay added to the delivery of all the data
+    pub latency_ms: u64,
+    /// Maximum random delay added to the latency
+    pub jitter_ms: u64,
+    /// Maximum throughput, unlimited if not specified
+    pub bandwidth_bytes_per_sec: Option<u64>,
+    /// Probability for each chunk of data to stall the delivery for `stall_duration_ms`
+    pub stall_probability: f64,
+    /// Duration of the stalls
+    pub stall_duration_ms: u64,
+    /// Probability for each chunk of data to reset the connection
+    pub reset_probability: f64,
+}
+
+/// The faults of a specific link, in both directions
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PeerLinkFaults {
+    pub peer_a: PeerId,
+    pub peer_b: PeerId,
+    pub faults: LinkFaults,
+}
+
+/// The network faults injected by the nodes, written by the test harness in the
+/// `faults_path` of the [`FaultInjectionConfig`] of the nodes
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkFaults {
+    /// The faults of the links without specific faults
+    pub default_faults: LinkFaults,
+    /// The faults of specific links
+    pub link_faults: Vec<PeerLinkFaults>,
+}
+
 pub type PeerSet = HashMap<PeerId, Peer>;
 
 // TODO: Combine with RoleType?
@@ -396,6 +568,36 @@ impl Default for PeerRole {
     }
 }
 
+/// Coarse trust level of a peer, used to configure per-protocol rate limits.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum PeerClass {
+    Validator,
+    ValidatorFullNode,
+    Public,
+}
+
+impl PeerClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PeerClass::Validator => "validator",
+            PeerClass::ValidatorFullNode => "vfn",
+            PeerClass::Public => "public",
+        }
+    }
+}
+
+impl From<PeerRole> for PeerClass {
+    fn from(role: PeerRole) -> Self {
+        match role {
+            PeerRole::Validator => PeerClass::Validator,
+            PeerRole::ValidatorFullNode | PeerRole::PreferredUpstream | PeerRole::Upstream => {
+                PeerClass::ValidatorFullNode
+            }
+            PeerRole::Downstream | PeerRole::Known | PeerRole::Unknown => PeerClass::Public,
+        }
+    }
+}
+
 /// Represents a single seed configuration for a seed peer
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(default)]
@@ -1,7 +1,10 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::config::{invariant, Error};
+use aptos_types::PeerId;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -31,8 +34,10 @@ pub struct StateSyncConfig {
     // Everything above belongs to state sync v1 and will be removed in the future.
     pub data_streaming_service: DataStreamingServiceConfig,
     pub aptos_data_client: AptosDataClientConfig,
+    pub backup_restore: BackupRestoreConfig,
     pub state_sync_driver: StateSyncDriverConfig,
     pub storage_service: StorageServiceConfig,
+    pub trusted_source: TrustedSourceConfig,
 }
 
 impl Default for StateSyncConfig {
@@ -49,8 +54,10 @@ impl Default for StateSyncConfig {
             tick_interval_ms: 100,
             data_streaming_service: DataStreamingServiceConfig::default(),
             aptos_data_client: AptosDataClientConfig::default(),
+            backup_restore: BackupRestoreConfig::default(),
             state_sync_driver: StateSyncDriverConfig::default(),
             storage_service: StorageServiceConfig::default(),
+            trusted_source: TrustedSourceConfig::default(),
         }
     }
 }
@@ -62,6 +69,8 @@ pub enum BootstrappingMode {
     ApplyTransactionOutputsFromGenesis, // Applies transaction outputs (starting at genesis)
     DownloadLatestAccountStates,        // Downloads the account states (at the latest version)
     ExecuteTransactionsFromGenesis,     // Executes transactions (starting at genesis)
+    RestoreFromBackupStorage, // Restores the latest state snapshot and transactions from backup storage
+    SyncFromTrustedSource, // Stores all transactions (starting at genesis) from trusted peers, without executing them
 }
 
 /// The continuous syncing mode determines how the node will stay up-to-date
@@ -71,6 +80,7 @@ pub enum BootstrappingMode {
 pub enum ContinuousSyncingMode {
     ExecuteTransactions,     // Executes transactions to stay up-to-date
     ApplyTransactionOutputs, // Applies transaction outputs to stay up-to-date
+    SyncFromTrustedSource, // Stores transactions from trusted peers (without executing them) to stay up-to-date
 }
 
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -101,15 +111,83 @@ impl Default for StateSyncDriverConfig {
     }
 }
 
+impl StateSyncDriverConfig {
+    /// Returns true iff the node bootstraps or continuously syncs from a
+    /// trusted source (i.e., the trusted peers in the `TrustedSourceConfig`).
+    pub fn syncs_from_trusted_source(&self) -> bool {
+        self.bootstrapping_mode == BootstrappingMode::SyncFromTrustedSource
+            || self.continuous_syncing_mode == ContinuousSyncingMode::SyncFromTrustedSource
+    }
+
+    /// Verifies that the node either bootstraps and continuously syncs from a
+    /// trusted source, or does neither. Transactions synced from a trusted
+    /// source aren't executed, so the node can't execute (or apply outputs)
+    /// on top of them, and vice versa.
+    pub fn verify(&self) -> Result<(), Error> {
+        let bootstraps_from_trusted_source =
+            self.bootstrapping_mode == BootstrappingMode::SyncFromTrustedSource;
+        let continuously_syncs_from_trusted_source =
+            self.continuous_syncing_mode == ContinuousSyncingMode::SyncFromTrustedSource;
+        invariant(
+            bootstraps_from_trusted_source == continuously_syncs_from_trusted_source,
+            format!(
+                "The bootstrapping mode ({:?}) and continuous syncing mode ({:?}) must both sync from a trusted source, or neither!",
+                self.bootstrapping_mode, self.continuous_syncing_mode
+            ),
+        )
+    }
+}
+
+/// The location of the backup storage to restore from (when bootstrapping
+/// using `BootstrappingMode::RestoreFromBackupStorage`, which requires the
+/// node to be built with the `restore-from-backup` feature).
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupStorageLocation {
+    CommandAdapter(PathBuf), // The config file of a command adapter backup storage (e.g., a cloud bucket)
+    LocalFs(PathBuf),        // The directory of a local file system backup storage
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupRestoreConfig {
+    pub backup_storage: Option<BackupStorageLocation>, // The backup storage to restore from
+    pub concurrent_downloads: usize, // The max num of concurrent downloads from the backup storage
+    pub metadata_cache_dir: Option<PathBuf>, // The backup metadata cache dir (defaults to a temp dir)
+}
+
+impl Default for BackupRestoreConfig {
+    fn default() -> Self {
+        Self {
+            backup_storage: None,
+            concurrent_downloads: 8,
+            metadata_cache_dir: None,
+        }
+    }
+}
+
+/// The config for syncing from a trusted source (e.g., for archival nodes that
+/// want to store the full transaction history as quickly as possible). In this
+/// mode, transactions are only verified against the transaction accumulator and
+/// the ledger info chain (i.e., no waypoint is required and nothing is executed).
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrustedSourceConfig {
+    pub trusted_peers: Vec<PeerId>, // The peers to sync from (all other peers are ignored)
+}
+
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StorageServiceConfig {
     pub max_account_states_chunk_sizes: u64, // Max num of accounts per chunk
     pub max_concurrent_requests: u64,        // Max num of concurrent storage server tasks
     pub max_epoch_chunk_size: u64,           // Max num of epoch ending ledger infos per chunk
+    pub max_lru_cache_size_bytes: u64,       // Max total size (bytes) of the lru response cache
     pub max_network_channel_size: u64,       // Max num of pending network messages
+    pub max_optimistic_fetch_period_ms: u64, // Max period (ms) an optimistic fetch is held by the server
     pub max_transaction_chunk_size: u64,     // Max num of transactions per chunk
     pub max_transaction_output_chunk_size: u64, // Max num of transaction outputs per chunk
+    pub num_precomputed_account_states_chunks: u64, // Num of subsequent account states chunks to precompute (0 disables precomputation, capped at 10)
     pub storage_summary_refresh_interval_ms: u64, // The interval (ms) to refresh the storage summary
 }
 
@@ -119,9 +197,12 @@ impl Default for StorageServiceConfig {
             max_account_states_chunk_sizes: 3000,
             max_concurrent_requests: 1000,
             max_epoch_chunk_size: 100,
+            max_lru_cache_size_bytes: 256 * 1024 * 1024, // 256 MiB
             max_network_channel_size: 1000,
+            max_optimistic_fetch_period_ms: 5000,
             max_transaction_chunk_size: 3000,
             max_transaction_output_chunk_size: 3000,
+            num_precomputed_account_states_chunks: 0,
             storage_summary_refresh_interval_ms: 1000,
         }
     }
@@ -169,13 +250,19 @@ impl Default for DataStreamingServiceConfig {
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct AptosDataClientConfig {
-    pub response_timeout_ms: u64, // Timeout (in milliseconds) when waiting for a response
-    pub summary_poll_interval_ms: u64, // Interval (in milliseconds) between data summary polls
+    pub enable_request_hedging: bool, // Whether to also send slow requests to a second peer
+    pub hedged_request_delay_ms: u64, // Delay (in milliseconds) before a slow request is sent to a second peer
+    pub optimistic_fetch_timeout_ms: u64, // Timeout (in milliseconds) when waiting for an optimistic fetch response
+    pub response_timeout_ms: u64,         // Timeout (in milliseconds) when waiting for a response
+    pub summary_poll_interval_ms: u64,    // Interval (in milliseconds) between data summary polls
 }
 
 impl Default for AptosDataClientConfig {
     fn default() -> Self {
         Self {
+            enable_request_hedging: true,
+            hedged_request_delay_ms: 2_000,
+            optimistic_fetch_timeout_ms: 10_000,
             response_timeout_ms: 10_000,
             summary_poll_interval_ms: 1_000,
         }
@@ -15,7 +15,7 @@ use aptos_types::{
     block_metadata::BlockMetadata,
     epoch_state::EpochState,
     ledger_info::LedgerInfo,
-    transaction::{Transaction, Version},
+    transaction::{SignedTransaction, Transaction, Version},
     validator_signer::ValidatorSigner,
     validator_verifier::ValidatorVerifier,
 };
@@ -94,6 +94,10 @@ impl Block {
         self.block_data.payload()
     }
 
+    pub fn failed_authors(&self) -> Option<&Vec<(Round, Author)>> {
+        self.block_data.failed_authors()
+    }
+
     pub fn quorum_cert(&self) -> &QuorumCert {
         self.block_data.quorum_cert()
     }
@@ -171,8 +175,12 @@ impl Block {
 
     /// The NIL blocks are special: they're not carrying any real payload and are generated
     /// independently by different validators just to fill in the round with some QC.
-    pub fn new_nil(round: Round, quorum_cert: QuorumCert) -> Self {
-        let block_data = BlockData::new_nil(round, quorum_cert);
+    pub fn new_nil(
+        round: Round,
+        quorum_cert: QuorumCert,
+        failed_authors: Vec<(Round, Author)>,
+    ) -> Self {
+        let block_data = BlockData::new_nil(round, quorum_cert, failed_authors);
 
         Block {
             id: block_data.hash(),
@@ -223,7 +231,7 @@ impl Block {
     pub fn validate_signature(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
         match self.block_data.block_type() {
             BlockType::Genesis => bail!("We should not accept genesis from others"),
-            BlockType::NilBlock => self.quorum_cert().verify(validator),
+            BlockType::NilBlock { .. } => self.quorum_cert().verify(validator),
             BlockType::Proposal { author, .. } => {
                 let signature = self
                     .signature
@@ -251,6 +259,22 @@ impl Block {
             parent.epoch() == self.epoch(),
             "block's parent should be in the same epoch"
         );
+        if let Some(failed_authors) = self.failed_authors() {
+            // The NIL block itself is for a failed round
+            let last_failed_round = if self.is_nil_block() {
+                self.round()
+            } else {
+                self.round() - 1
+            };
+            let mut previous_round = parent.round();
+            for (failed_round, _) in failed_authors {
+                ensure!(
+                    previous_round < *failed_round && *failed_round <= last_failed_round,
+                    "Failed rounds must be increasing and between the parent's round and the block's round"
+                );
+                previous_round = *failed_round;
+            }
+        }
         if parent.has_reconfiguration() {
             ensure!(
                 self.payload().map_or(true, |p| p.is_empty()),
@@ -289,15 +313,24 @@ impl Block {
         Ok(())
     }
 
+    /// The transactions of a block whose payload is carried inline. Blocks referencing quorum
+    /// store batches need the batch contents, see `transactions_to_execute_with_payload`.
     pub fn transactions_to_execute(&self) -> Vec<Transaction> {
+        let user_txns = match self.payload() {
+            Some(Payload::DirectMempool(txns)) => txns.clone(),
+            _ => Vec::new(),
+        };
+        self.transactions_to_execute_with_payload(user_txns)
+    }
+
+    /// The block metadata transaction followed by the given user transactions, which are the
+    /// resolved payload of this block.
+    pub fn transactions_to_execute_with_payload(
+        &self,
+        user_txns: Vec<SignedTransaction>,
+    ) -> Vec<Transaction> {
         std::iter::once(Transaction::BlockMetadata(self.into()))
-            .chain(
-                self.payload()
-                    .unwrap_or(&Vec::new())
-                    .iter()
-                    .cloned()
-                    .map(Transaction::UserTransaction),
-            )
+            .chain(user_txns.into_iter().map(Transaction::UserTransaction))
             .collect()
     }
 }
@@ -343,6 +376,9 @@ impl From<&Block> for BlockMetadata {
                 .collect(),
             // For nil block, we use 0x0 which is convention for nil address in move.
             block.author().unwrap_or(AccountAddress::ZERO),
+            block.failed_authors().map_or(vec![], |failed_authors| {
+                failed_authors.iter().map(|(_, author)| *author).collect()
+            }),
         )
     }
 }
@@ -3,6 +3,7 @@
 
 use crate::{
     common::{Author, Payload, Round},
+    proof_of_store::ProofOfStore,
     quorum_cert::QuorumCert,
     vote_data::VoteData,
 };
@@ -11,22 +12,33 @@ use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
 use aptos_types::{
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    transaction::SignedTransaction,
 };
 use mirai_annotations::*;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(from = "BlockTypeWire", into = "BlockTypeWire")]
 pub enum BlockType {
     Proposal {
         /// T of the block (e.g. one or more transaction(s)
         payload: Payload,
         /// Author of the block that can be validated by the author's public key and the signature
         author: Author,
+        /// Failed authors from the parent's block to this block.
+        /// I.e. the list of consecutive proposers from the
+        /// immediately preceeding rounds that didn't produce a successful block.
+        failed_authors: Vec<(Round, Author)>,
     },
     /// NIL blocks don't have authors or signatures: they're generated upon timeouts to fill in the
     /// gaps in the rounds.
-    NilBlock,
+    NilBlock {
+        /// Failed authors from the parent's block to this block (including this block)
+        /// I.e. the list of consecutive proposers from the
+        /// immediately preceeding rounds that didn't produce a successful block.
+        failed_authors: Vec<(Round, Author)>,
+    },
     /// A genesis block is the first committed block in any epoch that is identically constructed on
     /// all validators by any (potentially different) LedgerInfo that justifies the epoch change
     /// from the previous epoch.  The genesis block is used as the the first root block of the
@@ -34,6 +46,125 @@ pub enum BlockType {
     Genesis,
 }
 
+/// The serialized form of `BlockType`. Proposals carrying their transactions inline keep the
+/// encoding, and therefore the block id, they had before the quorum store; proposals referencing
+/// quorum store batches are encoded as a new variant. Blocks following failed rounds are encoded
+/// as new variants too, so that the blocks without failed authors keep their encoding.
+#[derive(Deserialize, Serialize)]
+#[serde(rename = "BlockType")]
+enum BlockTypeWire {
+    Proposal {
+        payload: Vec<SignedTransaction>,
+        author: Author,
+    },
+    NilBlock,
+    Genesis,
+    QuorumStoreProposal {
+        proofs: Vec<ProofOfStore>,
+        author: Author,
+    },
+    ProposalWithFailedAuthors {
+        payload: Vec<SignedTransaction>,
+        author: Author,
+        failed_authors: Vec<(Round, Author)>,
+    },
+    QuorumStoreProposalWithFailedAuthors {
+        proofs: Vec<ProofOfStore>,
+        author: Author,
+        failed_authors: Vec<(Round, Author)>,
+    },
+    NilBlockWithFailedAuthors {
+        failed_authors: Vec<(Round, Author)>,
+    },
+}
+
+impl From<BlockTypeWire> for BlockType {
+    fn from(wire: BlockTypeWire) -> Self {
+        match wire {
+            BlockTypeWire::Proposal { payload, author } => BlockType::Proposal {
+                payload: Payload::DirectMempool(payload),
+                author,
+                failed_authors: vec![],
+            },
+            BlockTypeWire::NilBlock => BlockType::NilBlock {
+                failed_authors: vec![],
+            },
+            BlockTypeWire::Genesis => BlockType::Genesis,
+            BlockTypeWire::QuorumStoreProposal { proofs, author } => BlockType::Proposal {
+                payload: Payload::InQuorumStore(proofs),
+                author,
+                failed_authors: vec![],
+            },
+            BlockTypeWire::ProposalWithFailedAuthors {
+                payload,
+                author,
+                failed_authors,
+            } => BlockType::Proposal {
+                payload: Payload::DirectMempool(payload),
+                author,
+                failed_authors,
+            },
+            BlockTypeWire::QuorumStoreProposalWithFailedAuthors {
+                proofs,
+                author,
+                failed_authors,
+            } => BlockType::Proposal {
+                payload: Payload::InQuorumStore(proofs),
+                author,
+                failed_authors,
+            },
+            BlockTypeWire::NilBlockWithFailedAuthors { failed_authors } => {
+                BlockType::NilBlock { failed_authors }
+            }
+        }
+    }
+}
+
+impl From<BlockType> for BlockTypeWire {
+    fn from(block_type: BlockType) -> Self {
+        match block_type {
+            BlockType::Proposal {
+                payload: Payload::DirectMempool(payload),
+                author,
+                failed_authors,
+            } => {
+                if failed_authors.is_empty() {
+                    BlockTypeWire::Proposal { payload, author }
+                } else {
+                    BlockTypeWire::ProposalWithFailedAuthors {
+                        payload,
+                        author,
+                        failed_authors,
+                    }
+                }
+            }
+            BlockType::Proposal {
+                payload: Payload::InQuorumStore(proofs),
+                author,
+                failed_authors,
+            } => {
+                if failed_authors.is_empty() {
+                    BlockTypeWire::QuorumStoreProposal { proofs, author }
+                } else {
+                    BlockTypeWire::QuorumStoreProposalWithFailedAuthors {
+                        proofs,
+                        author,
+                        failed_authors,
+                    }
+                }
+            }
+            BlockType::NilBlock { failed_authors } => {
+                if failed_authors.is_empty() {
+                    BlockTypeWire::NilBlock
+                } else {
+                    BlockTypeWire::NilBlockWithFailedAuthors { failed_authors }
+                }
+            }
+            BlockType::Genesis => BlockTypeWire::Genesis,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, CryptoHasher, BCSCryptoHash)]
 /// Block has the core data of a consensus block that should be persistent when necessary.
 /// Each block must know the id of its parent and keep the QuorurmCertificate to that parent.
@@ -88,6 +219,17 @@ impl BlockData {
         self.quorum_cert.certified_block().id()
     }
 
+    /// The proposers elected for the rounds between the parent block and this block that failed
+    /// to get a block certified, by increasing round. Empty for the genesis block.
+    pub fn failed_authors(&self) -> Option<&Vec<(Round, Author)>> {
+        match &self.block_type {
+            BlockType::Proposal { failed_authors, .. } | BlockType::NilBlock { failed_authors } => {
+                Some(failed_authors)
+            }
+            BlockType::Genesis => None,
+        }
+    }
+
     pub fn payload(&self) -> Option<&Payload> {
         if let BlockType::Proposal { payload, .. } = &self.block_type {
             Some(payload)
@@ -113,7 +255,7 @@ impl BlockData {
     }
 
     pub fn is_nil_block(&self) -> bool {
-        matches!(self.block_type, BlockType::NilBlock)
+        matches!(self.block_type, BlockType::NilBlock { .. })
     }
 
     pub fn new_genesis_from_ledger_info(ledger_info: &LedgerInfo) -> Self {
@@ -170,7 +312,11 @@ impl BlockData {
         }
     }
 
-    pub fn new_nil(round: Round, quorum_cert: QuorumCert) -> Self {
+    pub fn new_nil(
+        round: Round,
+        quorum_cert: QuorumCert,
+        failed_authors: Vec<(Round, Author)>,
+    ) -> Self {
         // We want all the NIL blocks to agree on the timestamps even though they're generated
         // independently by different validators, hence we're using the timestamp of a parent + 1.
         assume!(quorum_cert.certified_block().timestamp_usecs() < u64::max_value()); // unlikely to be false in this universe
@@ -181,7 +327,7 @@ impl BlockData {
             round,
             timestamp_usecs,
             quorum_cert,
-            block_type: BlockType::NilBlock,
+            block_type: BlockType::NilBlock { failed_authors },
         }
     }
 
@@ -191,13 +337,28 @@ impl BlockData {
         round: Round,
         timestamp_usecs: u64,
         quorum_cert: QuorumCert,
+    ) -> Self {
+        Self::new_proposal_ext(payload, author, vec![], round, timestamp_usecs, quorum_cert)
+    }
+
+    pub fn new_proposal_ext(
+        payload: Payload,
+        author: Author,
+        failed_authors: Vec<(Round, Author)>,
+        round: Round,
+        timestamp_usecs: u64,
+        quorum_cert: QuorumCert,
     ) -> Self {
         Self {
             epoch: quorum_cert.certified_block().epoch(),
             round,
             timestamp_usecs,
             quorum_cert,
-            block_type: BlockType::Proposal { payload, author },
+            block_type: BlockType::Proposal {
+                payload,
+                author,
+                failed_authors,
+            },
         }
     }
 
@@ -232,7 +393,74 @@ fn test_reconfiguration_suffix() {
             BTreeMap::new(),
         ),
     );
-    let reconfig_suffix_block =
-        BlockData::new_proposal(vec![], AccountAddress::random(), 2, 2, quorum_cert);
+    let reconfig_suffix_block = BlockData::new_proposal(
+        Payload::empty(false),
+        AccountAddress::random(),
+        2,
+        2,
+        quorum_cert,
+    );
     assert!(reconfig_suffix_block.is_reconfiguration_suffix());
 }
+
+#[test]
+fn test_direct_mempool_proposal_encoding() {
+    use aptos_types::account_address::AccountAddress;
+
+    // The encoding of `BlockType` before the quorum store payloads were introduced.
+    #[derive(Serialize)]
+    #[serde(rename = "BlockType")]
+    enum LegacyBlockType {
+        Proposal {
+            payload: Vec<SignedTransaction>,
+            author: Author,
+        },
+    }
+
+    let author = AccountAddress::random();
+    let block_type = BlockType::Proposal {
+        payload: Payload::DirectMempool(vec![]),
+        author,
+        failed_authors: vec![],
+    };
+    let bytes = bcs::to_bytes(&block_type).unwrap();
+    assert_eq!(
+        bytes,
+        bcs::to_bytes(&LegacyBlockType::Proposal {
+            payload: vec![],
+            author
+        })
+        .unwrap()
+    );
+    assert_eq!(bcs::from_bytes::<BlockType>(&bytes).unwrap(), block_type);
+
+    let quorum_store_block_type = BlockType::Proposal {
+        payload: Payload::InQuorumStore(vec![]),
+        author,
+        failed_authors: vec![],
+    };
+    let bytes = bcs::to_bytes(&quorum_store_block_type).unwrap();
+    assert_eq!(
+        bcs::from_bytes::<BlockType>(&bytes).unwrap(),
+        quorum_store_block_type
+    );
+
+    for block_type in [
+        BlockType::Proposal {
+            payload: Payload::DirectMempool(vec![]),
+            author,
+            failed_authors: vec![(1, author)],
+        },
+        BlockType::Proposal {
+            payload: Payload::InQuorumStore(vec![]),
+            author,
+            failed_authors: vec![(1, author)],
+        },
+        BlockType::NilBlock {
+            failed_authors: vec![(1, author)],
+        },
+    ] {
+        let bytes = bcs::to_bytes(&block_type).unwrap();
+        assert_eq!(bcs::from_bytes::<BlockType>(&bytes).unwrap(), block_type);
+    }
+}
@@ -6,6 +6,8 @@ use crate::{
         block_test_utils::{certificate_for_genesis, *},
         Block,
     },
+    block_data::BlockData,
+    common::Payload,
     quorum_cert::QuorumCert,
 };
 use aptos_crypto::hash::HashValue;
@@ -26,7 +28,7 @@ fn test_nil_block() {
     let genesis_block = Block::make_genesis_block();
     let quorum_cert = certificate_for_genesis();
 
-    let nil_block = Block::new_nil(1, quorum_cert);
+    let nil_block = Block::new_nil(1, quorum_cert, vec![]);
     assert_eq!(
         nil_block.quorum_cert().certified_block().id(),
         genesis_block.id()
@@ -43,7 +45,7 @@ fn test_nil_block() {
     assert!(nil_block.verify_well_formed().is_ok());
 
     let signer = ValidatorSigner::random(None);
-    let payload = vec![];
+    let payload = Payload::empty(false);
     let parent_block_info = nil_block.quorum_cert().certified_block();
     let nil_block_qc = gen_test_certificate(
         vec![&signer],
@@ -72,13 +74,56 @@ fn test_nil_block() {
     assert_eq!(nil_block_child.parent_id(), nil_block.id());
 }
 
+#[test]
+fn test_failed_authors_well_formed() {
+    let signer = ValidatorSigner::random(None);
+    let author = signer.author();
+    let proposal = |failed_authors| {
+        Block::new_proposal_from_block_data(
+            BlockData::new_proposal_ext(
+                Payload::empty(false),
+                author,
+                failed_authors,
+                4,
+                aptos_infallible::duration_since_epoch().as_micros() as u64,
+                certificate_for_genesis(),
+            ),
+            &signer,
+        )
+    };
+
+    assert!(proposal(vec![(1, author), (3, author)])
+        .verify_well_formed()
+        .is_ok());
+    // not increasing
+    assert!(proposal(vec![(3, author), (1, author)])
+        .verify_well_formed()
+        .is_err());
+    // the parent's round didn't fail
+    assert!(proposal(vec![(0, author)]).verify_well_formed().is_err());
+    // the proposal's round didn't fail
+    assert!(proposal(vec![(4, author)]).verify_well_formed().is_err());
+
+    // the round of a NIL block failed
+    assert!(
+        Block::new_nil(2, certificate_for_genesis(), vec![(1, author), (2, author)])
+            .verify_well_formed()
+            .is_ok()
+    );
+    assert!(
+        Block::new_nil(2, certificate_for_genesis(), vec![(3, author)])
+            .verify_well_formed()
+            .is_err()
+    );
+}
+
 #[test]
 fn test_block_relation() {
     let signer = ValidatorSigner::random(None);
     // Test genesis and the next block
     let genesis_block = Block::make_genesis_block();
     let quorum_cert = certificate_for_genesis();
-    let payload = vec![];
+    let payload = Payload::empty(false);
     let next_block = Block::new_proposal(
         payload.clone(),
         1,
@@ -105,7 +150,7 @@ fn test_same_qc_different_authors() {
     let signer = ValidatorSigner::random(None);
     let genesis_qc = certificate_for_genesis();
     let round = 1;
-    let payload = vec![];
+    let payload = Payload::empty(false);
     let current_timestamp = aptos_infallible::duration_since_epoch().as_micros() as u64;
     let block_round_1 = Block::new_proposal(
         payload.clone(),
@@ -1,7 +1,11 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use aptos_types::{account_address::AccountAddress, transaction::SignedTransaction};
+use crate::proof_of_store::ProofOfStore;
+use aptos_types::{
+    account_address::AccountAddress, transaction::SignedTransaction,
+    validator_verifier::ValidatorVerifier,
+};
 
 /// The round of a block is a consensus-internal counter, which starts with 0 and increases
 /// monotonically. It is used for the protocol safety and liveness (please see the detailed
@@ -10,5 +14,63 @@ pub type Round = u64;
 /// Author refers to the author's account address
 pub type Author = AccountAddress;
 
-/// The payload in block.
-pub type Payload = Vec<SignedTransaction>;
+/// The payload in block. It is serialized as part of `BlockType`, where a `DirectMempool` payload
+/// keeps the encoding of a plain transaction list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Payload {
+    /// The transactions pulled from mempool by the proposer.
+    DirectMempool(Vec<SignedTransaction>),
+    /// References to batches disseminated through the quorum store. The transactions are fetched
+    /// from the local batch store at execution time.
+    InQuorumStore(Vec<ProofOfStore>),
+}
+
+impl Payload {
+    /// An empty payload in the format matching the quorum store setting of the epoch.
+    pub fn empty(quorum_store_enabled: bool) -> Self {
+        if quorum_store_enabled {
+            Payload::InQuorumStore(Vec::new())
+        } else {
+            Payload::DirectMempool(Vec::new())
+        }
+    }
+
+    /// The number of transactions in the payload, including those referenced by proofs.
+    pub fn len(&self) -> usize {
+        match self {
+            Payload::DirectMempool(txns) => txns.len(),
+            Payload::InQuorumStore(proofs) => {
+                proofs.iter().map(|proof| proof.num_txns() as usize).sum()
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Payload::DirectMempool(txns) => txns.is_empty(),
+            Payload::InQuorumStore(proofs) => proofs.is_empty(),
+        }
+    }
+
+    /// Checks that the payload matches the quorum store setting and that every proof of store
+    /// is signed by a quorum of the current validators.
+    pub fn verify(
+        &self,
+        validator: &ValidatorVerifier,
+        quorum_store_enabled: bool,
+    ) -> anyhow::Result<()> {
+        match (quorum_store_enabled, self) {
+            (false, Payload::DirectMempool(_)) => Ok(()),
+            (true, Payload::InQuorumStore(proofs)) => {
+                for proof in proofs {
+                    proof.verify(validator)?;
+                }
+                Ok(())
+            }
+            (_, _) => anyhow::bail!(
+                "Wrong payload type, quorum store enabled: {}",
+                quorum_store_enabled
+            ),
+        }
+    }
+}
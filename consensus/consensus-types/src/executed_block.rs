@@ -11,7 +11,7 @@ use aptos_crypto::hash::HashValue;
 use aptos_types::{
     block_info::BlockInfo,
     contract_event::ContractEvent,
-    transaction::{Transaction, TransactionStatus},
+    transaction::{SignedTransaction, Transaction, TransactionStatus},
 };
 use executor_types::StateComputeResult;
 use std::fmt::{Debug, Display, Formatter};
@@ -106,19 +106,29 @@ impl ExecutedBlock {
     }
 
     pub fn transactions_to_commit(&self) -> Vec<Transaction> {
+        self.filter_committed(self.block.transactions_to_execute())
+    }
+
+    /// Same as `transactions_to_commit` for a block whose payload had to be resolved, e.g. from
+    /// the quorum store, before execution.
+    pub fn transactions_to_commit_with_payload(
+        &self,
+        user_txns: Vec<SignedTransaction>,
+    ) -> Vec<Transaction> {
+        self.filter_committed(self.block.transactions_to_execute_with_payload(user_txns))
+    }
+
+    fn filter_committed(&self, executed_txns: Vec<Transaction>) -> Vec<Transaction> {
         // reconfiguration suffix don't execute
         if self.is_reconfiguration_suffix() {
             return vec![];
         }
-        itertools::zip_eq(
-            self.block.transactions_to_execute(),
-            self.state_compute_result.compute_status(),
-        )
-        .filter_map(|(txn, status)| match status {
-            TransactionStatus::Keep(_) => Some(txn),
-            _ => None,
-        })
-        .collect()
+        itertools::zip_eq(executed_txns, self.state_compute_result.compute_status())
+            .filter_map(|(txn, status)| match status {
+                TransactionStatus::Keep(_) => Some(txn),
+                _ => None,
+            })
+            .collect()
     }
 
     pub fn reconfig_event(&self) -> Vec<ContractEvent> {
@@ -0,0 +1,343 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::Round;
+use anyhow::{ensure, Context};
+use aptos_crypto::{ed25519::Ed25519Signature, hash::CryptoHash, HashValue};
+use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use aptos_types::{transaction::SignedTransaction, validator_verifier::ValidatorVerifier, PeerId};
+use serde::{Deserialize, Serialize};
+use short_hex_str::AsShortHexStr;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+/// A logical point in time at which a batch stops being available. Batches are garbage collected
+/// once the ordering rounds of the epoch have passed their expiration.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LogicalTime {
+    epoch: u64,
+    round: Round,
+}
+
+impl LogicalTime {
+    pub fn new(epoch: u64, round: Round) -> Self {
+        Self { epoch, round }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn round(&self) -> Round {
+        self.round
+    }
+}
+
+/// The batch content hashed for the digest. The digest covers the source so that two validators
+/// broadcasting the same transactions do not produce colliding digests.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, CryptoHasher, BCSCryptoHash)]
+pub struct BatchPayload {
+    source: PeerId,
+    txns: Vec<SignedTransaction>,
+}
+
+impl BatchPayload {
+    pub fn new(source: PeerId, txns: Vec<SignedTransaction>) -> Self {
+        Self { source, txns }
+    }
+
+    pub fn source(&self) -> PeerId {
+        self.source
+    }
+
+    pub fn txns(&self) -> &[SignedTransaction] {
+        &self.txns
+    }
+
+    pub fn into_txns(self) -> Vec<SignedTransaction> {
+        self.txns
+    }
+}
+
+/// A batch of transactions broadcast by its source ahead of the proposal that will reference it.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Batch {
+    epoch: u64,
+    expiration: LogicalTime,
+    digest: HashValue,
+    payload: BatchPayload,
+}
+
+impl Display for Batch {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Batch: [source: {}, digest: {}, txns: {}, expiration: {:?}]",
+            self.payload.source.short_str(),
+            self.digest,
+            self.payload.txns.len(),
+            self.expiration,
+        )
+    }
+}
+
+impl Batch {
+    pub fn new(epoch: u64, expiration: LogicalTime, payload: BatchPayload) -> Self {
+        Self {
+            epoch,
+            expiration,
+            digest: payload.hash(),
+            payload,
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn source(&self) -> PeerId {
+        self.payload.source
+    }
+
+    pub fn expiration(&self) -> LogicalTime {
+        self.expiration
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.digest
+    }
+
+    pub fn num_txns(&self) -> u64 {
+        self.payload.txns.len() as u64
+    }
+
+    pub fn payload(&self) -> &BatchPayload {
+        &self.payload
+    }
+
+    pub fn into_payload(self) -> BatchPayload {
+        self.payload
+    }
+
+    /// Size of the transactions of the batch, in bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.payload
+            .txns
+            .iter()
+            .map(|txn| txn.raw_txn_bytes_len())
+            .sum()
+    }
+
+    /// Batches are not signed, checks that the digest matches the content. This is enough for a
+    /// batch fetched for a proof of store, whose signers already accepted it.
+    pub fn verify_digest(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.expiration.epoch() == self.epoch,
+            "Batch expiration is in a different epoch"
+        );
+        ensure!(self.payload.hash() == self.digest, "Batch digest mismatch");
+        Ok(())
+    }
+
+    /// Checks a batch broadcast by `sender`: only the source of a batch broadcasts it, and the
+    /// signers would not promise to store it past `max_expiration`.
+    pub fn verify(&self, sender: PeerId, max_expiration: LogicalTime) -> anyhow::Result<()> {
+        ensure!(
+            self.source() == sender,
+            "Batch source {} is not the sender {}",
+            self.source(),
+            sender
+        );
+        ensure!(
+            self.expiration <= max_expiration,
+            "Batch expiration {:?} is past {:?}",
+            self.expiration,
+            max_expiration
+        );
+        self.verify_digest()
+    }
+}
+
+/// The information a validator signs to promise that it stores the batch until `expiration`.
+#[derive(
+    Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash, CryptoHasher, BCSCryptoHash,
+)]
+pub struct SignedDigestInfo {
+    digest: HashValue,
+    expiration: LogicalTime,
+    num_txns: u64,
+}
+
+impl SignedDigestInfo {
+    pub fn new(digest: HashValue, expiration: LogicalTime, num_txns: u64) -> Self {
+        Self {
+            digest,
+            expiration,
+            num_txns,
+        }
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.digest
+    }
+
+    pub fn expiration(&self) -> LogicalTime {
+        self.expiration
+    }
+
+    pub fn num_txns(&self) -> u64 {
+        self.num_txns
+    }
+}
+
+/// A single validator's signature on a `SignedDigestInfo`, sent back to the batch source.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SignedDigest {
+    epoch: u64,
+    signer: PeerId,
+    info: SignedDigestInfo,
+    signature: Ed25519Signature,
+}
+
+impl Display for SignedDigest {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "SignedDigest: [signer: {}, digest: {}]",
+            self.signer.short_str(),
+            self.info.digest
+        )
+    }
+}
+
+impl SignedDigest {
+    pub fn new(
+        epoch: u64,
+        signer: PeerId,
+        info: SignedDigestInfo,
+        signature: Ed25519Signature,
+    ) -> Self {
+        Self {
+            epoch,
+            signer,
+            info,
+            signature,
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn signer(&self) -> PeerId {
+        self.signer
+    }
+
+    pub fn info(&self) -> &SignedDigestInfo {
+        &self.info
+    }
+
+    pub fn signature(&self) -> &Ed25519Signature {
+        &self.signature
+    }
+
+    pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
+        ensure!(
+            self.info.expiration.epoch() == self.epoch,
+            "SignedDigest expiration is in a different epoch"
+        );
+        validator
+            .verify(self.signer, &self.info, &self.signature)
+            .context("Failed to verify SignedDigest")
+    }
+}
+
+/// A proof of availability: a quorum of validators promised to store the batch with the given
+/// digest, so at least one honest validator can serve it to anybody executing the block.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ProofOfStore {
+    info: SignedDigestInfo,
+    signatures: BTreeMap<PeerId, Ed25519Signature>,
+}
+
+impl Display for ProofOfStore {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "ProofOfStore: [digest: {}, signers: {}, expiration: {:?}]",
+            self.info.digest,
+            self.signatures.len(),
+            self.info.expiration,
+        )
+    }
+}
+
+impl ProofOfStore {
+    pub fn new(info: SignedDigestInfo, signatures: BTreeMap<PeerId, Ed25519Signature>) -> Self {
+        Self { info, signatures }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.info.expiration.epoch()
+    }
+
+    pub fn info(&self) -> &SignedDigestInfo {
+        &self.info
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.info.digest
+    }
+
+    pub fn expiration(&self) -> LogicalTime {
+        self.info.expiration
+    }
+
+    pub fn num_txns(&self) -> u64 {
+        self.info.num_txns
+    }
+
+    pub fn signers(&self) -> impl Iterator<Item = &PeerId> {
+        self.signatures.keys()
+    }
+
+    pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
+        validator
+            .batch_verify_aggregated_signatures(&self.info, &self.signatures)
+            .context("Failed to verify ProofOfStore")
+    }
+}
+
+/// RPC request for the content of a batch, sent to the signers of its proof by a validator that
+/// has to execute a block referencing a batch it has not received.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BatchRequest {
+    epoch: u64,
+    digest: HashValue,
+}
+
+impl Display for BatchRequest {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "[BatchRequest epoch: {}, digest: {}]",
+            self.epoch, self.digest
+        )
+    }
+}
+
+impl BatchRequest {
+    pub fn new(epoch: u64, digest: HashValue) -> Self {
+        Self { epoch, digest }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.digest
+    }
+}
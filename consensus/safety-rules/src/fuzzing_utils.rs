@@ -22,6 +22,7 @@ use aptos_types::{
 use consensus_types::block::Block;
 use consensus_types::{
     block_data::{BlockData, BlockType},
+    common::{Payload, Round},
     quorum_cert::QuorumCert,
     timeout::Timeout,
     vote_data::VoteData,
@@ -94,10 +95,12 @@ prop_compose! {
     )(
         author in any::<AccountAddress>(),
         payload in prop::collection::vec(any::<SignedTransaction>(), 0..MAX_PROPOSAL_TRANSACTIONS),
+        failed_authors in prop::collection::vec((any::<Round>(), any::<AccountAddress>()), 0..3),
     ) -> BlockType {
         BlockType::Proposal{
-            payload,
-            author
+            payload: Payload::DirectMempool(payload),
+            author,
+            failed_authors,
         }
     }
 }
@@ -227,7 +230,8 @@ prop_compose! {
 fn arb_block_type() -> impl Strategy<Value = BlockType> {
     prop_oneof![
         arb_block_type_proposal(),
-        Just(BlockType::NilBlock),
+        prop::collection::vec((any::<Round>(), any::<AccountAddress>()), 0..3)
+            .prop_map(|failed_authors| BlockType::NilBlock { failed_authors }),
         Just(BlockType::Genesis),
     ]
 }
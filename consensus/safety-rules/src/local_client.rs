@@ -10,6 +10,7 @@ use aptos_types::{
 };
 use consensus_types::{
     block_data::BlockData,
+    proof_of_store::SignedDigestInfo,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
     vote::Vote,
@@ -83,4 +84,8 @@ impl TSafetyRules for LocalClient {
             .write()
             .sign_commit_vote(ledger_info, new_ledger_info)
     }
+
+    fn sign_signed_digest(&mut self, info: &SignedDigestInfo) -> Result<Ed25519Signature, Error> {
+        self.internal.write().sign_signed_digest(info)
+    }
 }
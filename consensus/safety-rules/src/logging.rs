@@ -55,6 +55,7 @@ pub enum LogEntry {
     State,
     Waypoint,
     SignCommitVote,
+    SignSignedDigest,
 }
 
 impl LogEntry {
@@ -75,6 +76,7 @@ impl LogEntry {
             LogEntry::State => "state",
             LogEntry::Waypoint => "waypoint",
             LogEntry::SignCommitVote => "sign_commit_vote",
+            LogEntry::SignSignedDigest => "sign_signed_digest",
         }
     }
 }
@@ -27,6 +27,7 @@ use consensus_types::{
     block::Block,
     block_data::BlockData,
     common::{Author, Round},
+    proof_of_store::SignedDigestInfo,
     quorum_cert::QuorumCert,
     safety_data::SafetyData,
     timeout::Timeout,
@@ -493,6 +494,20 @@ impl SafetyRules {
 
         Ok(signature)
     }
+
+    fn guarded_sign_signed_digest(
+        &mut self,
+        info: &SignedDigestInfo,
+    ) -> Result<Ed25519Signature, Error> {
+        self.signer()?;
+
+        // Only promise to store batches of the current epoch, the batch store is garbage
+        // collected against the rounds of that epoch.
+        let safety_data = self.persistent_storage.safety_data()?;
+        self.verify_epoch(info.expiration().epoch(), &safety_data)?;
+
+        self.sign(info)
+    }
 }
 
 impl TSafetyRules for SafetyRules {
@@ -563,6 +578,11 @@ impl TSafetyRules for SafetyRules {
         let cb = || self.guarded_sign_commit_vote(ledger_info, new_ledger_info);
         run_and_log(cb, |log| log, LogEntry::SignCommitVote)
     }
+
+    fn sign_signed_digest(&mut self, info: &SignedDigestInfo) -> Result<Ed25519Signature, Error> {
+        let cb = || self.guarded_sign_signed_digest(info);
+        run_and_log(cb, |log| log, LogEntry::SignSignedDigest)
+    }
 }
 
 fn run_and_log<F, L, R>(callback: F, log_cb: L, log_entry: LogEntry) -> Result<R, Error>
@@ -10,6 +10,7 @@ use aptos_types::{
 };
 use consensus_types::{
     block_data::BlockData,
+    proof_of_store::SignedDigestInfo,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
     vote::Vote,
@@ -34,6 +35,7 @@ pub enum SafetyRulesInput {
         Box<Option<TwoChainTimeoutCertificate>>,
     ),
     SignCommitVote(Box<LedgerInfoWithSignatures>, Box<LedgerInfo>),
+    SignSignedDigest(Box<SignedDigestInfo>),
 }
 
 pub struct SerializerService {
@@ -80,6 +82,9 @@ impl SerializerService {
                     .internal
                     .sign_commit_vote(*ledger_info, *new_ledger_info),
             ),
+            SafetyRulesInput::SignSignedDigest(info) => {
+                serde_json::to_vec(&self.internal.sign_signed_digest(&info))
+            }
         };
 
         Ok(output?)
@@ -181,6 +186,12 @@ impl TSafetyRules for SerializerClient {
         ))?;
         serde_json::from_slice(&response)?
     }
+
+    fn sign_signed_digest(&mut self, info: &SignedDigestInfo) -> Result<Ed25519Signature, Error> {
+        let _timer = counters::start_timer("external", LogEntry::SignSignedDigest.as_str());
+        let response = self.request(SafetyRulesInput::SignSignedDigest(Box::new(info.clone())))?;
+        serde_json::from_slice(&response)?
+    }
 }
 
 pub trait TSerializerClient: Send + Sync {
@@ -9,6 +9,7 @@ use aptos_types::{
 };
 use consensus_types::{
     block_data::BlockData,
+    proof_of_store::SignedDigestInfo,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
     vote::Vote,
@@ -62,4 +63,8 @@ pub trait TSafetyRules {
         ledger_info: LedgerInfoWithSignatures,
         new_ledger_info: LedgerInfo,
     ) -> Result<Ed25519Signature, Error>;
+
+    /// As the holder of the private key, SafetyRules also signs the quorum store promise to
+    /// keep a batch available until its expiration.
+    fn sign_signed_digest(&mut self, info: &SignedDigestInfo) -> Result<Ed25519Signature, Error>;
 }
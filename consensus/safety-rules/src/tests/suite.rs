@@ -18,7 +18,8 @@ use aptos_types::{
 };
 use consensus_types::{
     block::block_test_utils::random_payload,
-    common::Round,
+    common::{Payload, Round},
+    proof_of_store::{LogicalTime, SignedDigestInfo},
     quorum_cert::QuorumCert,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
@@ -35,7 +36,14 @@ fn make_proposal_with_qc_and_proof(
     signer: &ValidatorSigner,
     exec_key: Option<&Ed25519PrivateKey>,
 ) -> MaybeSignedVoteProposal {
-    test_utils::make_proposal_with_qc_and_proof(vec![], round, proof, qc, signer, exec_key)
+    test_utils::make_proposal_with_qc_and_proof(
+        Payload::empty(false),
+        round,
+        proof,
+        qc,
+        signer,
+        exec_key,
+    )
 }
 
 fn make_proposal_with_parent(
@@ -45,7 +53,14 @@ fn make_proposal_with_parent(
     signer: &ValidatorSigner,
     exec_key: Option<&Ed25519PrivateKey>,
 ) -> MaybeSignedVoteProposal {
-    test_utils::make_proposal_with_parent(vec![], round, parent, committed, signer, exec_key)
+    test_utils::make_proposal_with_parent(
+        Payload::empty(false),
+        round,
+        parent,
+        committed,
+        signer,
+        exec_key,
+    )
 }
 
 pub type Callback = Box<
@@ -77,6 +92,7 @@ pub fn run_test_suite(safety_rules: &Callback) {
     test_2chain_timeout(safety_rules);
     test_sign_commit_vote(safety_rules);
     test_bad_execution_output(safety_rules);
+    test_sign_signed_digest(safety_rules);
 }
 
 fn test_bad_execution_output(safety_rules: &Callback) {
@@ -449,7 +465,7 @@ fn test_voting_bad_epoch(safety_rules: &Callback) {
 
     let a1 = test_utils::make_proposal_with_qc(round + 1, genesis_qc, &signer, key.as_ref());
     let a2 = test_utils::make_proposal_with_parent_and_overrides(
-        vec![],
+        Payload::empty(false),
         round + 3,
         &a1,
         None,
@@ -661,7 +677,7 @@ fn test_validator_not_in_set(safety_rules: &Callback) {
     next_epoch_state.verifier =
         ValidatorVerifier::new_single(rand_signer.author(), rand_signer.public_key());
     let a2 = test_utils::make_proposal_with_parent_and_overrides(
-        vec![],
+        Payload::empty(false),
         round + 2,
         &a1,
         Some(&a1),
@@ -708,7 +724,7 @@ fn test_reconcile_key(_safety_rules: &Callback) {
     next_epoch_state.epoch = 2;
     next_epoch_state.verifier = ValidatorVerifier::new_single(signer.author(), new_pub_key);
     let a2 = test_utils::make_proposal_with_parent_and_overrides(
-        vec![],
+        Payload::empty(false),
         round + 2,
         &a1,
         Some(&a1),
@@ -725,7 +741,7 @@ fn test_reconcile_key(_safety_rules: &Callback) {
     // Verification fails for proposal signed by the outdated key
     let outdated_signer = &signer;
     let a3 = test_utils::make_proposal_with_parent_and_overrides(
-        vec![],
+        Payload::empty(false),
         round + 3,
         &a2,
         Some(&a2),
@@ -759,7 +775,7 @@ fn test_key_not_in_store(safety_rules: &Callback) {
     next_epoch_state.verifier =
         ValidatorVerifier::new_single(signer.author(), rand_signer.public_key());
     let a2 = test_utils::make_proposal_with_parent_and_overrides(
-        vec![],
+        Payload::empty(false),
         round + 2,
         &a1,
         Some(&a1),
@@ -1064,3 +1080,29 @@ fn test_sign_commit_vote(constructor: &Callback) {
         Error::InconsistentExecutionResult(_, _)
     ));
 }
+
+fn test_sign_signed_digest(constructor: &Callback) {
+    let (mut safety_rules, signer, _key) = constructor();
+    let (proof, genesis_qc) = test_utils::make_genesis(&signer);
+    let epoch = genesis_qc.certified_block().epoch();
+    let info = SignedDigestInfo::new(HashValue::random(), LogicalTime::new(epoch, 10), 1);
+
+    // the signer is only available once initialized
+    assert!(matches!(
+        safety_rules.sign_signed_digest(&info).unwrap_err(),
+        Error::NotInitialized(_)
+    ));
+
+    safety_rules.initialize(&proof).unwrap();
+    assert_eq!(
+        safety_rules.sign_signed_digest(&info).unwrap(),
+        signer.sign(&info)
+    );
+
+    // batches of other epochs are not signed
+    let info = SignedDigestInfo::new(HashValue::random(), LogicalTime::new(epoch + 1, 10), 1);
+    assert_eq!(
+        safety_rules.sign_signed_digest(&info).unwrap_err(),
+        Error::IncorrectEpoch(epoch + 1, epoch)
+    );
+}
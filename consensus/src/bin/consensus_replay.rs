@@ -0,0 +1,65 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{Context, Result};
+use aptos_config::config::{ConsensusConfig, NodeConfig};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, ValidCryptoMaterialStringExt};
+use aptos_types::{account_address::AccountAddress, validator_signer::ValidatorSigner};
+use consensus::{read_recording, replay};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "consensus-replay",
+    about = "Replay a consensus recording and report where the signed votes, proposals and \
+             timeouts diverge from the recorded ones."
+)]
+struct Opt {
+    /// Recording written by a validator with `consensus.recorder_path` set.
+    #[structopt(parse(from_os_str))]
+    recording: PathBuf,
+
+    /// Account address of the recorded validator.
+    #[structopt(long)]
+    author: AccountAddress,
+
+    /// File holding the hex encoded consensus private key of the recorded validator.
+    #[structopt(long, parse(from_os_str))]
+    consensus_key_file: PathBuf,
+
+    /// Node config of the recorded validator, the default consensus config is used otherwise.
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let opt = Opt::from_args();
+
+    let encoded_key = std::fs::read_to_string(&opt.consensus_key_file)
+        .with_context(|| format!("Unable to read {:?}", opt.consensus_key_file))?;
+    let consensus_key = Ed25519PrivateKey::from_encoded_string(encoded_key.trim())
+        .context("Invalid consensus key")?;
+    let signer = ValidatorSigner::new(opt.author, consensus_key);
+    let config = match &opt.config {
+        Some(path) => NodeConfig::load(path)?.consensus,
+        None => ConsensusConfig::default(),
+    };
+
+    let records = read_recording(&opt.recording)?;
+    println!(
+        "Replaying {} records from {:?}",
+        records.len(),
+        opt.recording
+    );
+    let runtime = tokio::runtime::Runtime::new()?;
+    let divergences = runtime.block_on(replay(records, &signer, &config))?;
+    for divergence in &divergences {
+        println!(
+            "Divergence at record {}: {:?}\n  recorded: {:?}\n  replayed: {:?}",
+            divergence.index, divergence.input, divergence.recorded, divergence.replayed
+        );
+    }
+    println!("{} divergences", divergences.len());
+    Ok(())
+}
@@ -458,6 +458,11 @@ impl BlockStore {
         Ok(())
     }
 
+    /// All the blocks in the tree, in no particular order.
+    pub fn get_all_blocks(&self) -> Vec<Arc<ExecutedBlock>> {
+        self.inner.read().get_all_blocks()
+    }
+
     /// Prune the tree up to next_root_id (keep next_root_id's block).  Any branches not part of
     /// the next_root_id's tree should be removed as well.
     ///
@@ -3,19 +3,26 @@
 
 use crate::{
     block_storage::{block_store::BlockStore, BlockReader},
+    network_interface::ConsensusNetworkSender,
     persistent_liveness_storage::{LedgerRecoveryData, RecoveryData, RootMetadata},
+    quorum_store::{batch_store::BatchStore, payload_manager::PayloadManager},
     state_computer::ExecutionProxy,
     test_utils::{EmptyStorage, MockTransactionManager, TreeInserter},
     util::mock_time_service::SimulatedTimeService,
 };
-use aptos_config::config::NodeConfig;
+use aptos_config::config::{NodeConfig, QuorumStoreConfig};
 use aptos_crypto::{ed25519::Ed25519PrivateKey, Uniform};
 use aptos_types::validator_signer::ValidatorSigner;
+use channel::{aptos_channel, message_queues::QueueStyle};
 use consensus_types::{block::Block, quorum_cert::QuorumCert};
 use execution_correctness::{ExecutionCorrectness, ExecutionCorrectnessManager};
 use executor::components::apply_chunk_output::IntoLedgerView;
 use executor_test_helpers::start_storage_service;
-use std::sync::Arc;
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::network::NewNetworkSender,
+};
+use std::{sync::Arc, time::Duration};
 use storage_interface::DbReader;
 
 fn get_initial_data_and_qc(db: &Arc<dyn DbReader>) -> (RecoveryData, QuorumCert) {
@@ -68,11 +75,23 @@ fn build_inserter(
     let client_commit_timeout_ms = config.state_sync.client_commit_timeout_ms;
     let (consensus_notifier, _consensus_listener) =
         consensus_notifications::new_consensus_notifier_listener_pair(client_commit_timeout_ms);
+    let (network_reqs_tx, _network_reqs_rx) = aptos_channel::new(QueueStyle::FIFO, 8, None);
+    let (connection_reqs_tx, _) = aptos_channel::new(QueueStyle::FIFO, 8, None);
+    let payload_manager = Arc::new(PayloadManager::new(
+        config.validator_network.as_ref().unwrap().peer_id(),
+        Arc::new(BatchStore::new(QuorumStoreConfig::default())),
+        ConsensusNetworkSender::new(
+            PeerManagerRequestSender::new(network_reqs_tx),
+            ConnectionRequestSender::new(connection_reqs_tx),
+        ),
+        Duration::from_secs(1),
+    ));
 
     let state_computer = Arc::new(ExecutionProxy::new(
         lec_client,
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(consensus_notifier),
+        payload_manager,
         &tokio::runtime::Handle::current(),
     ));
 
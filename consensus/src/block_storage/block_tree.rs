@@ -431,6 +431,13 @@ impl BlockTree {
         self.id_to_block.keys().cloned().collect()
     }
 
+    pub(super) fn get_all_blocks(&self) -> Vec<Arc<ExecutedBlock>> {
+        self.id_to_block
+            .values()
+            .map(|block| block.executed_block().clone())
+            .collect()
+    }
+
     /// Update the counters for committed blocks and prune them from the in-memory and persisted store.
     pub fn commit_callback(
         &mut self,
@@ -4,9 +4,11 @@
 use crate::{
     counters,
     epoch_manager::EpochManager,
+    introspection::ConsensusIntrospection,
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
     persistent_liveness_storage::StorageWriteProxy,
+    quorum_store::{batch_store::BatchStore, payload_manager::PayloadManager},
     state_computer::ExecutionProxy,
     txn_manager::MempoolProxy,
     util::time_service::ClockTimeService,
@@ -17,9 +19,10 @@ use aptos_mempool::ConsensusRequest;
 use consensus_notifications::ConsensusNotificationSender;
 use event_notifications::ReconfigNotificationListener;
 use execution_correctness::ExecutionCorrectnessManager;
+use executor::components::speculation_cache::SpeculationCache;
 use futures::channel::mpsc;
 use network::application::storage::PeerMetadataStorage;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use storage_interface::DbReaderWriter;
 use tokio::runtime::{self, Runtime};
 
@@ -33,6 +36,8 @@ pub fn start_consensus(
     aptos_db: DbReaderWriter,
     reconfig_events: ReconfigNotificationListener,
     peer_metadata_storage: Arc<PeerMetadataStorage>,
+    introspection: ConsensusIntrospection,
+    speculation_cache: Option<Arc<SpeculationCache>>,
 ) -> Runtime {
     let runtime = runtime::Builder::new_multi_thread()
         .thread_name("consensus")
@@ -46,12 +51,26 @@ pub fn start_consensus(
         node_config.consensus.mempool_txn_pull_timeout_ms,
         node_config.consensus.mempool_executed_txn_timeout_ms,
     ));
-    let execution_correctness_manager = ExecutionCorrectnessManager::new(node_config, aptos_db);
+    let execution_correctness_manager = ExecutionCorrectnessManager::new_with_speculation_cache(
+        node_config,
+        aptos_db,
+        speculation_cache,
+    );
+
+    network_sender.initialize(peer_metadata_storage);
+    let batch_store = Arc::new(BatchStore::new(node_config.consensus.quorum_store));
+    let payload_manager = Arc::new(PayloadManager::new(
+        node_config.validator_network.as_ref().unwrap().peer_id(),
+        batch_store.clone(),
+        network_sender.clone(),
+        Duration::from_millis(node_config.consensus.quorum_store.batch_request_timeout_ms),
+    ));
 
     let state_computer = Arc::new(ExecutionProxy::new(
         execution_correctness_manager.client(),
         txn_manager.clone(),
         state_sync_notifier,
+        payload_manager,
         runtime.handle(),
     ));
 
@@ -59,7 +78,6 @@ pub fn start_consensus(
 
     let (timeout_sender, timeout_receiver) = channel::new(1_024, &counters::PENDING_ROUND_TIMEOUTS);
     let (self_sender, self_receiver) = channel::new(1_024, &counters::PENDING_SELF_MESSAGES);
-    network_sender.initialize(peer_metadata_storage);
 
     let epoch_mgr = EpochManager::new(
         node_config,
@@ -71,6 +89,8 @@ pub fn start_consensus(
         state_computer,
         storage,
         reconfig_events,
+        batch_store,
+        introspection,
     );
 
     let (network_task, network_receiver) = NetworkTask::new(network_events, self_receiver);
@@ -98,6 +98,15 @@ pub static COMMITTED_VOTES_IN_WINDOW: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Failed rounds this validator was elected for when using LeaderReputation as the ProposerElection
+pub static FAILED_PROPOSALS_IN_WINDOW: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_failed_proposals_in_window",
+        "Total number of rounds without a committed block this validator was elected for in the current reputation window"
+    )
+    .unwrap()
+});
+
 //////////////////////
 // RoundState COUNTERS
 //////////////////////
@@ -271,6 +280,15 @@ pub static BUFFER_MANAGER_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static QUORUM_STORE_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_quorum_store_msgs_count",
+        "Counters(queued,dequeued,dropped) related to pending quorum store messages",
+        &["state"]
+    )
+    .unwrap()
+});
+
 /// Counters(queued,dequeued,dropped) related to consensus channel
 pub static CONSENSUS_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -300,3 +318,82 @@ pub static BLOCK_RETRIEVAL_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+/// Counters(queued,dequeued,dropped) related to quorum store channel
+pub static QUORUM_STORE_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_quorum_store_channel_msgs_count",
+        "Counters(queued,dequeued,dropped) related to quorum store channel",
+        &["state"]
+    )
+    .unwrap()
+});
+
+/// Counters(queued,dequeued,dropped) related to batch retrieval channel
+pub static BATCH_RETRIEVAL_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_batch_retrieval_channel_msgs_count",
+        "Counters(queued,dequeued,dropped) related to batch retrieval channel",
+        &["state"]
+    )
+    .unwrap()
+});
+
+//////////////////////
+// QUORUM STORE
+//////////////////////
+
+/// Count of the batches created by this validator since last restart.
+pub static QUORUM_STORE_CREATED_BATCHES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_consensus_quorum_store_created_batches",
+        "Count of the batches created by this validator since last restart."
+    )
+    .unwrap()
+});
+
+/// Count of the proofs of store aggregated by this validator since last restart.
+pub static QUORUM_STORE_AGGREGATED_PROOFS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_consensus_quorum_store_aggregated_proofs",
+        "Count of the proofs of store aggregated by this validator since last restart."
+    )
+    .unwrap()
+});
+
+/// Number of batches currently held in the local batch store.
+pub static QUORUM_STORE_BATCH_STORE_SIZE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_consensus_quorum_store_batch_store_size",
+        "Number of batches currently held in the local batch store."
+    )
+    .unwrap()
+});
+
+/// Number of proofs of store waiting to be proposed.
+pub static QUORUM_STORE_PROOF_QUEUE_SIZE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_consensus_quorum_store_proof_queue_size",
+        "Number of proofs of store waiting to be proposed."
+    )
+    .unwrap()
+});
+
+/// Count of the batches fetched from peers at execution time, result is success or failed.
+pub static QUORUM_STORE_BATCH_FETCHES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_quorum_store_batch_fetches",
+        "Count of the batches fetched from peers at execution time, result is success or failed",
+        &["result"]
+    )
+    .unwrap()
+});
+
+/// Count of the consensus records dropped because the recorder could not keep up.
+pub static CONSENSUS_RECORDER_DROPPED_RECORDS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_consensus_recorder_dropped_records",
+        "Count of the consensus records dropped because the recorder could not keep up."
+    )
+    .unwrap()
+});
@@ -10,6 +10,7 @@ use crate::{
         decoupled_execution_utils::prepare_phases_and_buffer_manager,
         ordering_state_computer::OrderingStateComputer,
     },
+    introspection::ConsensusIntrospection,
     liveness::{
         leader_reputation::{ActiveInactiveHeuristic, AptosDBBackend, LeaderReputation},
         proposal_generator::ProposalGenerator,
@@ -20,14 +21,22 @@ use crate::{
     },
     logging::{LogEvent, LogSchema},
     metrics_safety_rules::MetricsSafetyRules,
-    network::{IncomingBlockRetrievalRequest, NetworkReceivers, NetworkSender},
+    network::{
+        IncomingBatchRetrievalRequest, IncomingBlockRetrievalRequest, NetworkReceivers,
+        NetworkSender,
+    },
     network_interface::{ConsensusMsg, ConsensusNetworkSender},
     persistent_liveness_storage::{LedgerRecoveryData, PersistentLivenessStorage, RecoveryData},
+    quorum_store::{
+        batch_store::BatchStore, proof_queue::ProofQueue, quorum_store_client::QuorumStoreClient,
+        quorum_store_task::QuorumStore,
+    },
+    recorder::{ConsensusRecorder, RecordedEvent},
     round_manager::{RoundManager, UnverifiedEvent, VerifiedEvent},
     state_replication::{StateComputer, TxnManager},
     util::time_service::TimeService,
 };
-use anyhow::{bail, ensure, Context};
+use anyhow::{anyhow, bail, ensure, Context};
 use aptos_config::config::{ConsensusConfig, ConsensusProposerType, NodeConfig};
 use aptos_infallible::{duration_since_epoch, Mutex};
 use aptos_logger::prelude::*;
@@ -43,6 +52,7 @@ use channel::{aptos_channel, message_queues::QueueStyle};
 use consensus_types::{
     common::{Author, Round},
     epoch_retrieval::EpochRetrievalRequest,
+    proof_of_store::LogicalTime,
 };
 use event_notifications::ReconfigNotificationListener;
 use futures::{
@@ -97,6 +107,14 @@ pub struct EpochManager {
     round_manager_tx: Option<
         aptos_channel::Sender<(Author, Discriminant<VerifiedEvent>), (Author, VerifiedEvent)>,
     >,
+    // batches are kept across epochs, blocks of the previous epoch may still reference them
+    batch_store: Arc<BatchStore>,
+    // channel to quorum store, only set in epochs with quorum store enabled
+    quorum_store_msg_tx: Option<aptos_channel::Sender<AccountAddress, VerifiedEvent>>,
+    // records the inputs and SafetyRules responses of the round managers, if enabled in config
+    recorder: Option<Arc<ConsensusRecorder>>,
+    // the state of the current epoch exposed to the debug interface
+    introspection: ConsensusIntrospection,
     epoch_state: Option<EpochState>,
 }
 
@@ -111,11 +129,23 @@ impl EpochManager {
         commit_state_computer: Arc<dyn StateComputer>,
         storage: Arc<dyn PersistentLivenessStorage>,
         reconfig_events: ReconfigNotificationListener,
+        batch_store: Arc<BatchStore>,
+        introspection: ConsensusIntrospection,
     ) -> Self {
         let author = node_config.validator_network.as_ref().unwrap().peer_id();
         let config = node_config.consensus.clone();
         let sr_config = &node_config.consensus.safety_rules;
         let safety_rules_manager = SafetyRulesManager::new(sr_config);
+        let recorder =
+            config.recorder_path.as_ref().and_then(|path| {
+                match ConsensusRecorder::new(path, time_service.clone()) {
+                    Ok(recorder) => Some(Arc::new(recorder)),
+                    Err(e) => {
+                        error!(error = ?e, "Unable to start the consensus recorder");
+                        None
+                    }
+                }
+            });
         Self {
             author,
             config,
@@ -131,6 +161,10 @@ impl EpochManager {
             buffer_manager_msg_tx: None,
             buffer_manager_reset_tx: None,
             round_manager_tx: None,
+            batch_store,
+            quorum_store_msg_tx: None,
+            recorder,
+            introspection,
             epoch_state: None,
         }
     }
@@ -188,11 +222,23 @@ impl EpochManager {
                     proposers.len(),
                     self.storage.aptos_db(),
                 ));
-                let heuristic = Box::new(ActiveInactiveHeuristic::new(
-                    self.author,
-                    heuristic_config.active_weights,
-                    heuristic_config.inactive_weights,
-                ));
+                // the weights in the on-chain config take precedence over the local ones
+                let heuristic = Box::new(match onchain_config.leader_reputation_weights() {
+                    Some(weights) => ActiveInactiveHeuristic::new(
+                        self.author,
+                        weights.active_weight,
+                        weights.inactive_weight,
+                        weights.failed_weight,
+                        weights.failure_threshold_percent,
+                    ),
+                    None => ActiveInactiveHeuristic::new(
+                        self.author,
+                        heuristic_config.active_weights,
+                        heuristic_config.inactive_weights,
+                        heuristic_config.failed_weights,
+                        heuristic_config.failure_threshold_percent,
+                    ),
+                });
                 Box::new(LeaderReputation::new(
                     proposers,
                     backend,
@@ -335,6 +381,7 @@ impl EpochManager {
                 block_rx,
                 reset_rx,
                 verifier,
+                Some(self.introspection.clone()),
             );
 
         tokio::spawn(execution_phase.start());
@@ -345,6 +392,63 @@ impl EpochManager {
         OrderingStateComputer::new(block_tx, self.commit_state_computer.clone(), reset_tx)
     }
 
+    /// this function spawns the quorum store of the epoch
+    /// it sets `self.quorum_store_msg_tx` and returns the client the proposal generator pulls from
+    fn spawn_quorum_store(
+        &mut self,
+        epoch_state: &EpochState,
+        safety_rules_container: Arc<Mutex<MetricsSafetyRules>>,
+    ) -> Arc<dyn TxnManager> {
+        let proof_queue = Arc::new(ProofQueue::new());
+        let client = Arc::new(QuorumStoreClient::new(
+            proof_queue.clone(),
+            self.batch_store.clone(),
+            self.config.mempool_poll_count,
+        ));
+        let network_sender = NetworkSender::new(
+            self.author,
+            self.network_sender.clone(),
+            self.self_sender.clone(),
+            epoch_state.verifier.clone(),
+        );
+        let (quorum_store_msg_tx, quorum_store_msg_rx) =
+            aptos_channel::new::<AccountAddress, VerifiedEvent>(
+                QueueStyle::FIFO,
+                self.config.channel_size,
+                Some(&counters::QUORUM_STORE_MSGS),
+            );
+        self.quorum_store_msg_tx = Some(quorum_store_msg_tx);
+
+        let quorum_store = QuorumStore::new(
+            epoch_state.epoch,
+            self.author,
+            self.config.quorum_store,
+            safety_rules_container,
+            epoch_state.verifier.clone(),
+            network_sender,
+            self.txn_manager.clone(),
+            self.batch_store.clone(),
+            proof_queue,
+        );
+        tokio::spawn(quorum_store.start(quorum_store_msg_rx));
+        client
+    }
+
+    /// The latest expiration at which the batches received in this epoch are stored, relative to
+    /// the last committed round of the epoch.
+    fn max_batch_expiration(&self) -> LogicalTime {
+        let certified_time = self.batch_store.certified_time();
+        let round = if certified_time.epoch() == self.epoch() {
+            certified_time.round()
+        } else {
+            0
+        };
+        LogicalTime::new(
+            self.epoch(),
+            round + self.config.quorum_store.max_batch_expiry_rounds,
+        )
+    }
+
     async fn shutdown_current_processor(&mut self) {
         if self.round_manager_tx.is_some() {
             // Release the previous RoundManager, especially the SafetyRule client
@@ -357,6 +461,9 @@ impl EpochManager {
         }
         self.round_manager_tx = None;
 
+        // Dropping the sender stops the previous quorum store
+        self.quorum_store_msg_tx = None;
+
         // Shutdown the previous buffer manager, to release the SafetyRule client
         self.buffer_manager_msg_tx = None;
         if let Some(mut tx) = self.buffer_manager_reset_tx.take() {
@@ -389,6 +496,13 @@ impl EpochManager {
             "Starting new epoch",
         );
         let last_vote = recovery_data.last_vote();
+        if let Some(recorder) = &self.recorder {
+            recorder.record(RecordedEvent::EpochStart {
+                epoch_state: epoch_state.clone(),
+                onchain_config: onchain_config.clone(),
+                ledger_info: recovery_data.storage_ledger().clone(),
+            });
+        }
 
         info!(epoch = epoch, "Update SafetyRules");
 
@@ -435,6 +549,15 @@ impl EpochManager {
             Arc::clone(&self.time_service),
             onchain_config.back_pressure_limit(),
         ));
+        self.introspection.start_epoch(epoch, block_store.clone());
+
+        let quorum_store_enabled = onchain_config.quorum_store_enabled();
+        let payload_source = if quorum_store_enabled {
+            info!(epoch = epoch, "Create QuorumStore");
+            self.spawn_quorum_store(&epoch_state, safety_rules_container.clone())
+        } else {
+            self.txn_manager.clone()
+        };
 
         info!(epoch = epoch, "Create ProposalGenerator");
         // txn manager is required both by proposal generator (to pull the proposers)
@@ -442,9 +565,11 @@ impl EpochManager {
         let proposal_generator = ProposalGenerator::new(
             self.author,
             block_store.clone(),
-            self.txn_manager.clone(),
+            payload_source,
             self.time_service.clone(),
             self.config.max_block_size,
+            quorum_store_enabled,
+            onchain_config.max_failed_authors_to_store(),
         );
 
         let mut round_manager = RoundManager::new(
@@ -458,6 +583,8 @@ impl EpochManager {
             self.storage.clone(),
             self.config.sync_only,
             onchain_config,
+            self.recorder.clone(),
+            Some(self.introspection.clone()),
         );
 
         round_manager.init(last_vote).await;
@@ -503,7 +630,11 @@ impl EpochManager {
             // same epoch -> run well-formedness + signature check
             let verified_event = unverified_event
                 .clone()
-                .verify(&self.epoch_state().verifier)
+                .verify(
+                    peer_id,
+                    &self.epoch_state().verifier,
+                    self.max_batch_expiration(),
+                )
                 .context("[EpochManager] Verify event")
                 .map_err(|err| {
                     error!(
@@ -531,7 +662,10 @@ impl EpochManager {
             | ConsensusMsg::SyncInfo(_)
             | ConsensusMsg::VoteMsg(_)
             | ConsensusMsg::CommitVoteMsg(_)
-            | ConsensusMsg::CommitDecisionMsg(_) => {
+            | ConsensusMsg::CommitDecisionMsg(_)
+            | ConsensusMsg::BatchMsg(_)
+            | ConsensusMsg::SignedDigestMsg(_)
+            | ConsensusMsg::ProofOfStoreMsg(_) => {
                 let event: UnverifiedEvent = msg.into();
                 if event.epoch() == self.epoch() {
                     return Ok(Some(event));
@@ -595,6 +729,17 @@ impl EpochManager {
                     bail!("Commit Phase not started but received Commit Message (CommitVote/CommitDecision)");
                 }
             }
+            quorum_store_event
+            @
+            (VerifiedEvent::Batch(_)
+            | VerifiedEvent::SignedDigest(_)
+            | VerifiedEvent::ProofOfStore(_)) => {
+                if let Some(sender) = &mut self.quorum_store_msg_tx {
+                    sender.push(peer_id, quorum_store_event)?;
+                } else {
+                    bail!("Quorum store not started but received quorum store message (Batch/SignedDigest/ProofOfStore)");
+                }
+            }
             round_manager_event => {
                 self.forward_to_round_manager(peer_id, round_manager_event);
             }
@@ -619,6 +764,24 @@ impl EpochManager {
         );
     }
 
+    fn process_batch_retrieval(
+        &mut self,
+        request: IncomingBatchRetrievalRequest,
+    ) -> anyhow::Result<()> {
+        let digest = request.req.digest();
+        let batch = self
+            .batch_store
+            .get(&digest)
+            .ok_or_else(|| anyhow!("[EpochManager] Batch {} not found", digest))?;
+        let response_bytes = request
+            .protocol
+            .to_bytes(&ConsensusMsg::BatchMsg(Box::new(batch)))?;
+        request
+            .response_sender
+            .send(Ok(response_bytes.into()))
+            .map_err(|_| anyhow!("[EpochManager] Failed to respond to batch request"))
+    }
+
     fn process_local_timeout(&mut self, round: u64) {
         self.forward_to_round_manager(self.author, VerifiedEvent::LocalTimeout(round));
     }
@@ -647,9 +810,19 @@ impl EpochManager {
                         error!(epoch = self.epoch(), error = ?e, kind = error_kind(&e));
                     }
                 }
+                Some((peer, msg)) = network_receivers.quorum_store_messages.next() => {
+                    if let Err(e) = self.process_message(peer, msg).await {
+                        error!(epoch = self.epoch(), error = ?e, kind = error_kind(&e));
+                    }
+                }
                 Some(request) = network_receivers.block_retrieval.next() => {
                     self.process_block_retrieval(request);
                 }
+                Some(request) = network_receivers.batch_retrieval.next() => {
+                    if let Err(e) = self.process_batch_retrieval(request) {
+                        warn!(epoch = self.epoch(), error = ?e, kind = error_kind(&e));
+                    }
+                }
                 Some(round) = round_timeout_sender_rx.next() => {
                     self.process_local_timeout(round);
                 }
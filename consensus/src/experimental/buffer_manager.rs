@@ -12,9 +12,10 @@ use futures::{
 };
 use tokio::time::Duration;
 
+use aptos_infallible::duration_since_epoch;
 use aptos_logger::prelude::*;
 use aptos_types::{
-    account_address::AccountAddress, ledger_info::LedgerInfoWithSignatures,
+    account_address::AccountAddress, block_info::BlockInfo, ledger_info::LedgerInfoWithSignatures,
     validator_verifier::ValidatorVerifier,
 };
 use consensus_types::{common::Author, executed_block::ExecutedBlock};
@@ -27,6 +28,7 @@ use crate::{
         persisting_phase::PersistingRequest,
         signing_phase::{SigningRequest, SigningResponse},
     },
+    introspection::{BufferItemSnapshot, BufferSnapshot, ConsensusIntrospection},
     network::NetworkSender,
     network_interface::ConsensusMsg,
     round_manager::VerifiedEvent,
@@ -90,6 +92,10 @@ pub struct BufferManager {
     stop: bool,
 
     verifier: ValidatorVerifier,
+
+    // the last block sent to the persisting phase
+    last_committed_block: Option<BlockInfo>,
+    introspection: Option<ConsensusIntrospection>,
 }
 
 impl BufferManager {
@@ -105,6 +111,7 @@ impl BufferManager {
         block_rx: UnboundedReceiver<OrderedBlocks>,
         reset_rx: UnboundedReceiver<ResetRequest>,
         verifier: ValidatorVerifier,
+        introspection: Option<ConsensusIntrospection>,
     ) -> Self {
         let buffer = Buffer::<BufferItem>::new();
 
@@ -131,6 +138,9 @@ impl BufferManager {
             stop: false,
 
             verifier,
+
+            last_committed_block: None,
+            introspection,
         }
     }
 
@@ -218,6 +228,8 @@ impl BufferManager {
                         ))
                         .await;
                 }
+                self.last_committed_block =
+                    Some(aggregated_item.commit_proof.commit_info().clone());
                 self.persisting_phase_tx
                     .send(PersistingRequest {
                         blocks: blocks_to_persist,
@@ -404,6 +416,38 @@ impl BufferManager {
         }
     }
 
+    /// Publishes the stage of every item in the buffer for the debug interface. It walks the
+    /// whole buffer, so it only runs on the retry interval rather than after every event.
+    fn publish_introspection(&self) {
+        if let Some(introspection) = &self.introspection {
+            let mut items = vec![];
+            let mut cursor = *self.buffer.head_cursor();
+            while cursor.is_some() {
+                let item = self.buffer.get(&cursor);
+                let stage = if item.is_ordered() {
+                    "ordered"
+                } else if item.is_executed() {
+                    "executed"
+                } else if item.is_signed() {
+                    "signed"
+                } else {
+                    "aggregated"
+                };
+                items.push(BufferItemSnapshot {
+                    block_id: item.block_id(),
+                    round: item.get_blocks().last().unwrap().round(),
+                    stage,
+                });
+                cursor = self.buffer.get_next(&cursor);
+            }
+            introspection.publish_buffer(BufferSnapshot {
+                items,
+                committed_block: self.last_committed_block.as_ref().map(Into::into),
+                updated_at_ms: duration_since_epoch().as_millis() as u64,
+            });
+        }
+    }
+
     pub async fn start(mut self) {
         info!("Buffer manager starts.");
         let mut interval =
@@ -444,6 +488,7 @@ impl BufferManager {
                 }
                 _ = interval.tick() => {
                     self.retry_broadcasting_commit_votes().await;
+                    self.publish_introspection();
                 }
                 // no else branch here because interval.tick will always be available
             }
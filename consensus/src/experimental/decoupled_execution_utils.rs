@@ -9,6 +9,7 @@ use crate::{
         pipeline_phase::PipelinePhase,
         signing_phase::{SigningPhase, SigningRequest, SigningResponse},
     },
+    introspection::ConsensusIntrospection,
     metrics_safety_rules::MetricsSafetyRules,
     network::NetworkSender,
     round_manager::VerifiedEvent,
@@ -32,6 +33,7 @@ pub fn prepare_phases_and_buffer_manager(
     block_rx: UnboundedReceiver<OrderedBlocks>,
     sync_rx: UnboundedReceiver<ResetRequest>,
     verifier: ValidatorVerifier,
+    introspection: Option<ConsensusIntrospection>,
 ) -> (
     PipelinePhase<ExecutionPhase>,
     PipelinePhase<SigningPhase>,
@@ -90,6 +92,7 @@ pub fn prepare_phases_and_buffer_manager(
             block_rx,
             sync_rx,
             verifier,
+            introspection,
         ),
     )
 }
@@ -38,7 +38,7 @@ use aptos_types::{
 use channel::{aptos_channel, message_queues::QueueStyle};
 use consensus_types::{
     block::block_test_utils::certificate_for_genesis, executed_block::ExecutedBlock,
-    vote_proposal::MaybeSignedVoteProposal,
+    proof_of_store::LogicalTime, vote_proposal::MaybeSignedVoteProposal,
 };
 use futures::{channel::oneshot, FutureExt, SinkExt, StreamExt};
 use itertools::enumerate;
@@ -134,6 +134,7 @@ pub fn prepare_buffer_manager() -> (
         block_rx,
         buffer_reset_rx,
         validators.clone(),
+        None,
     );
 
     (
@@ -207,7 +208,14 @@ async fn loopback_commit_vote(
         Some(Event::Message(author, msg)) => {
             let event: UnverifiedEvent = msg.into();
             // verify the message and send the message into self loop
-            msg_tx.push(author, event.verify(verifier).unwrap()).ok();
+            msg_tx
+                .push(
+                    author,
+                    event
+                        .verify(author, verifier, LogicalTime::new(0, 0))
+                        .unwrap(),
+                )
+                .ok();
         }
         _ => {
             panic!("We are expecting a commit vote message.");
@@ -7,6 +7,7 @@ use aptos_crypto::HashValue;
 use aptos_types::{ledger_info::LedgerInfo, validator_verifier::random_validator_verifier};
 use consensus_types::{
     block::{block_test_utils::certificate_for_genesis, Block},
+    common::Payload,
     executed_block::ExecutedBlock,
     quorum_cert::QuorumCert,
 };
@@ -35,7 +36,7 @@ fn add_execution_phase_test_cases(
 ) {
     let genesis_qc = certificate_for_genesis();
     let (signers, _validators) = random_validator_verifier(1, None, false);
-    let block = Block::new_proposal(vec![], 1, 1, genesis_qc, &signers[0]);
+    let block = Block::new_proposal(Payload::empty(false), 1, 1, genesis_qc, &signers[0]);
 
     // happy path
     phase_tester.add_test_case(
@@ -63,7 +64,7 @@ fn add_execution_phase_test_cases(
         &LedgerInfo::mock_genesis(None),
         random_hash_value,
     );
-    let bad_block = Block::new_proposal(vec![], 1, 1, bad_qc, &signers[0]);
+    let bad_block = Block::new_proposal(Payload::empty(false), 1, 1, bad_qc, &signers[0]);
     phase_tester.add_test_case(
         ExecutionRequest {
             ordered_blocks: vec![ExecutedBlock::new(
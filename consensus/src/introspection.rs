@@ -0,0 +1,212 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Snapshots of the live state of consensus, to inspect a validator without going through its
+//! logs (e.g., during a stall). The RoundManager publishes its state when it changes, the
+//! BufferManager on its retry interval, the block tree is read from the BlockStore when the
+//! snapshot is taken.
+
+use crate::block_storage::{BlockReader, BlockStore};
+use aptos_crypto::HashValue;
+use aptos_infallible::{duration_since_epoch, Mutex};
+use aptos_types::block_info::BlockInfo;
+use consensus_types::{
+    common::{Author, Round},
+    executed_block::ExecutedBlock,
+};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Collects the state published by the consensus components of the current epoch.
+#[derive(Clone, Default)]
+pub struct ConsensusIntrospection {
+    inner: Arc<Mutex<IntrospectionState>>,
+}
+
+#[derive(Default)]
+struct IntrospectionState {
+    epoch: Option<u64>,
+    block_store: Option<Arc<BlockStore>>,
+    round_state: Option<RoundStateSnapshot>,
+    buffer: Option<BufferSnapshot>,
+}
+
+impl ConsensusIntrospection {
+    /// Forgets the state of the previous epoch.
+    pub(crate) fn start_epoch(&self, epoch: u64, block_store: Arc<BlockStore>) {
+        *self.inner.lock() = IntrospectionState {
+            epoch: Some(epoch),
+            block_store: Some(block_store),
+            round_state: None,
+            buffer: None,
+        };
+    }
+
+    pub(crate) fn publish_round_state(&self, round_state: RoundStateSnapshot) {
+        self.inner.lock().round_state = Some(round_state);
+    }
+
+    pub(crate) fn publish_buffer(&self, buffer: BufferSnapshot) {
+        self.inner.lock().buffer = Some(buffer);
+    }
+
+    pub(crate) fn snapshot(&self) -> ConsensusSnapshot {
+        let inner = self.inner.lock();
+        ConsensusSnapshot {
+            epoch: inner.epoch,
+            round_state: inner.round_state.clone(),
+            block_tree: inner
+                .block_store
+                .as_ref()
+                .map(|block_store| BlockTreeSnapshot::new(block_store)),
+            buffer: inner.buffer.clone(),
+        }
+    }
+
+    /// Returns the snapshot of the current state of consensus as JSON.
+    pub fn snapshot_json(&self) -> serde_json::Value {
+        serde_json::to_value(self.snapshot()).unwrap_or_else(|e| {
+            serde_json::json!({ "error": format!("Unable to serialize snapshot: {}", e) })
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConsensusSnapshot {
+    pub epoch: Option<u64>,
+    pub round_state: Option<RoundStateSnapshot>,
+    pub block_tree: Option<BlockTreeSnapshot>,
+    pub buffer: Option<BufferSnapshot>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RoundStateSnapshot {
+    pub current_round: Round,
+    pub current_round_deadline_ms: u64,
+    /// The leader of the current round, unknown until the round manager starts a round.
+    pub leader: Option<Author>,
+    /// The block the validator voted for in the current round, if it voted for one.
+    pub vote_sent: Option<HashValue>,
+    /// The votes received in the current round.
+    pub votes: Vec<VoteTally>,
+    pub timeout_voters: Vec<Author>,
+    pub updated_at_ms: u64,
+}
+
+impl RoundStateSnapshot {
+    pub fn new(
+        current_round: Round,
+        current_round_deadline_ms: u64,
+        leader: Option<Author>,
+        vote_sent: Option<HashValue>,
+        votes: Vec<VoteTally>,
+        timeout_voters: Vec<Author>,
+    ) -> Self {
+        Self {
+            current_round,
+            current_round_deadline_ms,
+            leader,
+            vote_sent,
+            votes,
+            timeout_voters,
+            updated_at_ms: duration_since_epoch().as_millis() as u64,
+        }
+    }
+}
+
+/// The votes for one ledger info.
+#[derive(Clone, Debug, Serialize)]
+pub struct VoteTally {
+    pub block_id: HashValue,
+    pub block_round: Round,
+    pub ledger_info_digest: HashValue,
+    pub voters: Vec<Author>,
+    pub voting_power: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BlockSnapshot {
+    pub id: HashValue,
+    pub round: Round,
+    pub parent_id: HashValue,
+    pub author: Option<Author>,
+}
+
+impl From<&ExecutedBlock> for BlockSnapshot {
+    fn from(block: &ExecutedBlock) -> Self {
+        Self {
+            id: block.id(),
+            round: block.round(),
+            parent_id: block.parent_id(),
+            author: block.block().author(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CertSnapshot {
+    pub block_id: HashValue,
+    pub round: Round,
+}
+
+impl From<&BlockInfo> for CertSnapshot {
+    fn from(block_info: &BlockInfo) -> Self {
+        Self {
+            block_id: block_info.id(),
+            round: block_info.round(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BlockTreeSnapshot {
+    pub ordered_root: BlockSnapshot,
+    pub commit_root: BlockSnapshot,
+    pub highest_quorum_cert: CertSnapshot,
+    pub highest_ordered_cert: CertSnapshot,
+    pub highest_ledger_info: CertSnapshot,
+    pub highest_timeout_cert_round: Option<Round>,
+    /// All the blocks of the tree, by round.
+    pub blocks: Vec<BlockSnapshot>,
+}
+
+impl BlockTreeSnapshot {
+    fn new(block_store: &BlockStore) -> Self {
+        let mut blocks: Vec<BlockSnapshot> = block_store
+            .get_all_blocks()
+            .iter()
+            .map(|block| block.as_ref().into())
+            .collect();
+        blocks.sort_by_key(|block| block.round);
+        let highest_timeout_cert_round = block_store
+            .highest_2chain_timeout_cert()
+            .map(|tc| tc.round())
+            .or_else(|| block_store.highest_timeout_cert().map(|tc| tc.round()));
+        Self {
+            ordered_root: block_store.ordered_root().as_ref().into(),
+            commit_root: block_store.commit_root().as_ref().into(),
+            highest_quorum_cert: block_store.highest_quorum_cert().certified_block().into(),
+            highest_ordered_cert: block_store.highest_ordered_cert().certified_block().into(),
+            highest_ledger_info: block_store.highest_ledger_info().commit_info().into(),
+            highest_timeout_cert_round,
+            blocks,
+        }
+    }
+}
+
+/// The state of the decoupled execution pipeline.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BufferSnapshot {
+    /// The blocks in the buffer from the oldest, with the stage they reached.
+    pub items: Vec<BufferItemSnapshot>,
+    /// The last block sent to be persisted.
+    pub committed_block: Option<CertSnapshot>,
+    pub updated_at_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BufferItemSnapshot {
+    pub block_id: HashValue,
+    pub round: Round,
+    pub stage: &'static str,
+}
@@ -18,6 +18,7 @@ mod counters;
 mod epoch_manager;
 mod error;
 mod experimental;
+mod introspection;
 mod liveness;
 mod logging;
 mod metrics_safety_rules;
@@ -26,6 +27,10 @@ mod network;
 mod network_tests;
 mod pending_votes;
 mod persistent_liveness_storage;
+mod quorum_store;
+mod recorder;
+#[cfg(any(test, feature = "fuzzing"))]
+mod replay;
 mod round_manager;
 mod state_computer;
 mod state_replication;
@@ -41,5 +46,8 @@ pub mod consensus_provider;
 /// AptosNet interface.
 pub mod network_interface;
 
+pub use introspection::ConsensusIntrospection;
+#[cfg(feature = "fuzzing")]
+pub use replay::{read_recording, replay};
 #[cfg(feature = "fuzzing")]
 pub use round_manager::round_manager_fuzzing;
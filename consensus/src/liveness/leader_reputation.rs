@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    counters::{COMMITTED_PROPOSALS_IN_WINDOW, COMMITTED_VOTES_IN_WINDOW},
+    counters::{
+        COMMITTED_PROPOSALS_IN_WINDOW, COMMITTED_VOTES_IN_WINDOW, FAILED_PROPOSALS_IN_WINDOW,
+    },
     liveness::proposer_election::{next, ProposerElection},
 };
 use aptos_crypto::HashValue;
@@ -98,18 +100,31 @@ pub trait ReputationHeuristic: Send + Sync {
 }
 
 /// If candidate appear in the history, it's assigned active_weight otherwise inactive weight.
+/// A candidate that failed at least failure_threshold_percent of the rounds it was elected for
+/// in the history is assigned failed_weight instead, the failed rounds being the ones recorded
+/// by the committed blocks.
 pub struct ActiveInactiveHeuristic {
     author: Author,
     active_weight: u64,
     inactive_weight: u64,
+    failed_weight: u64,
+    failure_threshold_percent: u64,
 }
 
 impl ActiveInactiveHeuristic {
-    pub fn new(author: Author, active_weight: u64, inactive_weight: u64) -> Self {
+    pub fn new(
+        author: Author,
+        active_weight: u64,
+        inactive_weight: u64,
+        failed_weight: u64,
+        failure_threshold_percent: u64,
+    ) -> Self {
         Self {
             author,
             active_weight,
             inactive_weight,
+            failed_weight,
+            failure_threshold_percent,
         }
     }
 }
@@ -118,9 +133,15 @@ impl ReputationHeuristic for ActiveInactiveHeuristic {
     fn get_weights(&self, candidates: &[Author], history: &[NewBlockEvent]) -> Vec<u64> {
         let mut committed_proposals: usize = 0;
         let mut committed_votes: usize = 0;
+        let mut proposals = HashMap::new();
+        let mut failures = HashMap::new();
 
         let set = history.iter().fold(HashSet::new(), |mut set, meta| {
             set.insert(meta.proposer());
+            *proposals.entry(meta.proposer()).or_insert(0u64) += 1;
+            for failed_proposer in meta.failed_proposers() {
+                *failures.entry(*failed_proposer).or_insert(0u64) += 1;
+            }
             for vote in meta.votes() {
                 set.insert(vote);
                 if vote == self.author {
@@ -139,11 +160,16 @@ impl ReputationHeuristic for ActiveInactiveHeuristic {
 
         COMMITTED_PROPOSALS_IN_WINDOW.set(committed_proposals as i64);
         COMMITTED_VOTES_IN_WINDOW.set(committed_votes as i64);
+        FAILED_PROPOSALS_IN_WINDOW.set(*failures.get(&self.author).unwrap_or(&0) as i64);
 
         candidates
             .iter()
             .map(|author| {
-                if set.contains(author) {
+                let failed = *failures.get(author).unwrap_or(&0);
+                let elected = failed + *proposals.get(author).unwrap_or(&0);
+                if failed > 0 && failed * 100 >= self.failure_threshold_percent * elected {
+                    self.failed_weight
+                } else if set.contains(author) {
                     self.active_weight
                 } else {
                     self.inactive_weight
@@ -186,6 +212,10 @@ impl ProposerElection for LeaderReputation {
         let sliding_window = self.backend.get_block_metadata(target_round);
         let mut weights = self.heuristic.get_weights(&self.proposers, &sliding_window);
         assert_eq!(weights.len(), self.proposers.len());
+        // everyone is excluded, fall back to equal weights
+        if weights.iter().all(|w| *w == 0) {
+            weights.iter_mut().for_each(|w| *w = 1);
+        }
         let mut total_weight = 0;
         for w in &mut weights {
             total_weight += *w;
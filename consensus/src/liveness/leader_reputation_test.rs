@@ -10,7 +10,7 @@ use crate::liveness::{
 use aptos_types::{block_metadata::NewBlockEvent, validator_signer::ValidatorSigner};
 use consensus_types::{
     block::{block_test_utils::certificate_for_genesis, Block},
-    common::{Author, Round},
+    common::{Author, Payload, Round},
 };
 
 struct MockHistory {
@@ -36,7 +36,17 @@ impl MetadataBackend for MockHistory {
 }
 
 fn create_block(proposer: Author, voters: Vec<&ValidatorSigner>) -> NewBlockEvent {
-    NewBlockEvent::new(0, proposer, voters.iter().map(|v| v.author()).collect(), 0)
+    NewBlockEvent::new(
+        0,
+        proposer,
+        voters.iter().map(|v| v.author()).collect(),
+        0,
+        vec![],
+    )
+}
+
+fn create_block_after_failures(proposer: Author, failed_proposers: Vec<Author>) -> NewBlockEvent {
+    NewBlockEvent::new(0, proposer, vec![], 0, failed_proposers)
 }
 
 #[test]
@@ -50,7 +60,8 @@ fn test_simple_heuristic() {
         proposers.push(signer.author());
         signers.push(signer);
     }
-    let heuristic = ActiveInactiveHeuristic::new(proposers[0], active_weight, inactive_weight);
+    let heuristic =
+        ActiveInactiveHeuristic::new(proposers[0], active_weight, inactive_weight, 0, 10);
     // 1. Window size not enough
     let weights = heuristic.get_weights(&proposers, &[]);
     assert_eq!(weights.len(), proposers.len());
@@ -98,6 +109,8 @@ fn test_api() {
             proposers[0],
             active_weight,
             inactive_weight,
+            0,
+            10,
         )),
         4,
     );
@@ -131,7 +144,7 @@ fn test_api() {
     assert!(proposer_election.is_valid_proposer(proposers[expected_index], 42));
     assert!(!proposer_election.is_valid_proposer(proposers[unexpected_index], 42));
     let good_proposal = Block::new_proposal(
-        vec![],
+        Payload::empty(false),
         round,
         1,
         certificate_for_genesis(),
@@ -139,7 +152,7 @@ fn test_api() {
     );
     assert!(proposer_election.is_valid_proposal(&good_proposal));
     let bad_proposal = Block::new_proposal(
-        vec![],
+        Payload::empty(false),
         round,
         1,
         certificate_for_genesis(),
@@ -147,7 +160,7 @@ fn test_api() {
     );
     assert!(!proposer_election.is_valid_proposal(&bad_proposal));
     let bad_proposal_2 = Block::new_proposal(
-        vec![],
+        Payload::empty(false),
         round,
         2,
         certificate_for_genesis(),
@@ -159,3 +172,64 @@ fn test_api() {
     // good proposal still passes
     assert!(proposer_election.is_valid_proposal(&good_proposal));
 }
+
+#[test]
+fn test_failed_proposers_heuristic() {
+    let (active_weight, inactive_weight, failed_weight) = (9, 1, 0);
+    let signers: Vec<_> = (0..4).map(|i| ValidatorSigner::random([i; 32])).collect();
+    let proposers: Vec<_> = signers.iter().map(|s| s.author()).collect();
+    let heuristic = ActiveInactiveHeuristic::new(
+        proposers[0],
+        active_weight,
+        inactive_weight,
+        failed_weight,
+        50,
+    );
+    // proposer 0 committed 1 of 2 rounds, proposer 1 committed 3 of 4, proposer 3 failed its only
+    // round, proposer 2 is active as a voter
+    let history = vec![
+        create_block(proposers[0], vec![&signers[2]]),
+        create_block_after_failures(proposers[1], vec![proposers[0], proposers[1]]),
+        create_block(proposers[1], vec![&signers[2]]),
+        create_block_after_failures(proposers[1], vec![proposers[3]]),
+    ];
+    let weights = heuristic.get_weights(&proposers, &history);
+    assert_eq!(
+        weights,
+        vec![failed_weight, active_weight, active_weight, failed_weight]
+    );
+}
+
+#[test]
+fn test_exclude_failed_proposer() {
+    let signers: Vec<_> = (0..2).map(|i| ValidatorSigner::random([i; 32])).collect();
+    let proposers: Vec<_> = signers.iter().map(|s| s.author()).collect();
+    // the committed block records that proposer 0 failed the round before it
+    let history = vec![create_block_after_failures(
+        proposers[1],
+        vec![proposers[0]],
+    )];
+    let leader_reputation = LeaderReputation::new(
+        proposers.clone(),
+        Box::new(MockHistory::new(10, history)),
+        Box::new(ActiveInactiveHeuristic::new(proposers[0], 1, 1, 0, 10)),
+        0,
+    );
+    for round in 1..20 {
+        assert_eq!(leader_reputation.get_valid_proposer(round), proposers[1]);
+    }
+}
+
+#[test]
+fn test_all_weights_zero() {
+    let signers: Vec<_> = (0..3).map(|i| ValidatorSigner::random([i; 32])).collect();
+    let proposers: Vec<_> = signers.iter().map(|s| s.author()).collect();
+    let leader_reputation = LeaderReputation::new(
+        proposers.clone(),
+        Box::new(MockHistory::new(10, vec![])),
+        Box::new(ActiveInactiveHeuristic::new(proposers[0], 0, 0, 0, 10)),
+        0,
+    );
+    // falls back to equal weights instead of failing the election
+    assert!(proposers.contains(&leader_reputation.get_valid_proposer(1)));
+}
@@ -2,19 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    block_storage::BlockReader, state_replication::TxnManager, util::time_service::TimeService,
+    block_storage::BlockReader, liveness::proposer_election::ProposerElection,
+    state_replication::TxnManager, util::time_service::TimeService,
 };
 use anyhow::{bail, ensure, format_err, Context};
 use consensus_types::{
     block::Block,
     block_data::BlockData,
-    common::{Author, Round},
+    common::{Author, Payload, Round},
     quorum_cert::QuorumCert,
 };
 
 use aptos_infallible::Mutex;
 use futures::future::BoxFuture;
-use std::sync::Arc;
+use std::{cmp::max, sync::Arc};
 
 #[cfg(test)]
 #[path = "proposal_generator_test.rs"]
@@ -41,6 +42,10 @@ pub struct ProposalGenerator {
     time_service: Arc<dyn TimeService>,
     // Max number of transactions to be added to a proposed block.
     max_block_size: u64,
+    // Whether the payload references quorum store batches instead of carrying the transactions.
+    quorum_store_enabled: bool,
+    // Max number of failed authors to be added to a proposed block.
+    max_failed_authors_to_store: usize,
     // Last round that a proposal was generated
     last_round_generated: Mutex<Round>,
 }
@@ -52,6 +57,8 @@ impl ProposalGenerator {
         txn_manager: Arc<dyn TxnManager>,
         time_service: Arc<dyn TimeService>,
         max_block_size: u64,
+        quorum_store_enabled: bool,
+        max_failed_authors_to_store: usize,
     ) -> Self {
         Self {
             author,
@@ -59,6 +66,8 @@ impl ProposalGenerator {
             txn_manager,
             time_service,
             max_block_size,
+            quorum_store_enabled,
+            max_failed_authors_to_store,
             last_round_generated: Mutex::new(0),
         }
     }
@@ -68,9 +77,20 @@ impl ProposalGenerator {
     }
 
     /// Creates a NIL block proposal extending the highest certified block from the block store.
-    pub fn generate_nil_block(&self, round: Round) -> anyhow::Result<Block> {
+    pub fn generate_nil_block(
+        &self,
+        round: Round,
+        proposer_election: &(dyn ProposerElection + Send + Sync),
+    ) -> anyhow::Result<Block> {
         let hqc = self.ensure_highest_quorum_cert(round)?;
-        Ok(Block::new_nil(round, hqc.as_ref().clone()))
+        let quorum_cert = hqc.as_ref().clone();
+        let failed_authors = self.compute_failed_authors(
+            round, // to include current round, as that is what failed
+            quorum_cert.certified_block().round(),
+            true,
+            proposer_election,
+        );
+        Ok(Block::new_nil(round, quorum_cert, failed_authors))
     }
 
     /// The function generates a new proposal block: the returned future is fulfilled when the
@@ -86,6 +106,7 @@ impl ProposalGenerator {
     pub async fn generate_proposal(
         &mut self,
         round: Round,
+        proposer_election: &(dyn ProposerElection + Send + Sync),
         wait_callback: BoxFuture<'static, ()>,
     ) -> anyhow::Result<BlockData> {
         {
@@ -102,7 +123,10 @@ impl ProposalGenerator {
         let (payload, timestamp) = if hqc.certified_block().has_reconfiguration() {
             // Reconfiguration rule - we propose empty blocks with parents' timestamp
             // after reconfiguration until it's committed
-            (vec![], hqc.certified_block().timestamp_usecs())
+            (
+                Payload::empty(self.quorum_store_enabled),
+                hqc.certified_block().timestamp_usecs(),
+            )
         } else {
             // One needs to hold the blocks with the references to the payloads while get_block is
             // being executed: pending blocks vector keeps all the pending ancestors of the extended branch.
@@ -116,7 +140,7 @@ impl ProposalGenerator {
 
             // Exclude all the pending transactions: these are all the ancestors of
             // parent (including) up to the root (including).
-            let exclude_payload: Vec<&Vec<_>> = pending_blocks
+            let exclude_payload: Vec<&Payload> = pending_blocks
                 .iter()
                 .flat_map(|block| block.payload())
                 .collect();
@@ -126,7 +150,7 @@ impl ProposalGenerator {
                 .path_from_ordered_root(hqc.certified_block().id())
                 .ok_or_else(|| format_err!("HQC {} already pruned", hqc.certified_block().id()))?
                 .iter()
-                .any(|block| !block.payload().map_or(true, |txns| txns.is_empty()));
+                .any(|block| !block.payload().map_or(true, |payload| payload.is_empty()));
 
             // All proposed blocks in a branch are guaranteed to have increasing timestamps
             // since their predecessor block will not be added to the BlockStore until
@@ -147,16 +171,50 @@ impl ProposalGenerator {
             (payload, timestamp.as_micros() as u64)
         };
 
+        let quorum_cert = hqc.as_ref().clone();
+        let failed_authors = self.compute_failed_authors(
+            round,
+            quorum_cert.certified_block().round(),
+            false,
+            proposer_election,
+        );
+
         // create block proposal
-        Ok(BlockData::new_proposal(
+        Ok(BlockData::new_proposal_ext(
             payload,
             self.author,
+            failed_authors,
             round,
             timestamp,
-            hqc.as_ref().clone(),
+            quorum_cert,
         ))
     }
 
+    /// The proposers elected for the rounds between the previous round and the given round,
+    /// which failed since the block of the previous round is the parent of the block of the given
+    /// round. Only the latest max_failed_authors_to_store of them are kept.
+    pub fn compute_failed_authors(
+        &self,
+        round: Round,
+        previous_round: Round,
+        include_cur_round: bool,
+        proposer_election: &(dyn ProposerElection + Send + Sync),
+    ) -> Vec<(Round, Author)> {
+        let end_round = round + u64::from(include_cur_round);
+        let start_round = max(
+            previous_round + 1,
+            end_round.saturating_sub(self.max_failed_authors_to_store as u64),
+        );
+        (start_round..end_round)
+            .map(|failed_round| {
+                (
+                    failed_round,
+                    proposer_election.get_valid_proposer(failed_round),
+                )
+            })
+            .collect()
+    }
+
     fn ensure_highest_quorum_cert(&self, round: Round) -> anyhow::Result<Arc<QuorumCert>> {
         let hqc = self.block_store.highest_quorum_cert();
         ensure!(
@@ -3,7 +3,9 @@
 
 use crate::{
     block_storage::BlockReader,
-    liveness::proposal_generator::ProposalGenerator,
+    liveness::{
+        proposal_generator::ProposalGenerator, rotating_proposer_election::RotatingProposer,
+    },
     test_utils::{build_empty_tree, MockTransactionManager, TreeInserter},
     util::mock_time_service::SimulatedTimeService,
 };
@@ -19,6 +21,7 @@ fn empty_callback() -> BoxFuture<'static, ()> {
 #[tokio::test]
 async fn test_proposal_generation_empty_tree() {
     let signer = ValidatorSigner::random(None);
+    let proposer_election = RotatingProposer::new(vec![signer.author()], 1);
     let block_store = build_empty_tree();
     let mut proposal_generator = ProposalGenerator::new(
         signer.author(),
@@ -26,22 +29,25 @@ async fn test_proposal_generation_empty_tree() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        false,
+        10,
     );
     let genesis = block_store.ordered_root();
 
     // Generate proposals for an empty tree.
     let proposal_data = proposal_generator
-        .generate_proposal(1, empty_callback())
+        .generate_proposal(1, &proposer_election, empty_callback())
         .await
         .unwrap();
     let proposal = Block::new_proposal_from_block_data(proposal_data, &signer);
     assert_eq!(proposal.parent_id(), genesis.id());
     assert_eq!(proposal.round(), 1);
     assert_eq!(proposal.quorum_cert().certified_block().id(), genesis.id());
+    assert_eq!(proposal.failed_authors(), Some(&vec![]));
 
     // Duplicate proposals on the same round are not allowed
     let proposal_err = proposal_generator
-        .generate_proposal(1, empty_callback())
+        .generate_proposal(1, &proposer_election, empty_callback())
         .await
         .err();
     assert!(proposal_err.is_some());
@@ -50,6 +56,7 @@ async fn test_proposal_generation_empty_tree() {
 #[tokio::test]
 async fn test_proposal_generation_parent() {
     let mut inserter = TreeInserter::default();
+    let proposer_election = RotatingProposer::new(vec![inserter.signer().author()], 1);
     let block_store = inserter.block_store();
     let mut proposal_generator = ProposalGenerator::new(
         inserter.signer().author(),
@@ -57,6 +64,8 @@ async fn test_proposal_generation_parent() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        false,
+        10,
     );
     let genesis = block_store.ordered_root();
     let a1 = inserter
@@ -70,7 +79,7 @@ async fn test_proposal_generation_parent() {
     // generate proposals for an empty tree.
     assert_eq!(
         proposal_generator
-            .generate_proposal(10, empty_callback())
+            .generate_proposal(10, &proposer_election, empty_callback())
             .await
             .unwrap()
             .parent_id(),
@@ -80,17 +89,26 @@ async fn test_proposal_generation_parent() {
     // Once a1 is certified, it should be the one to choose from
     inserter.insert_qc_for_block(a1.as_ref(), None);
     let a1_child_res = proposal_generator
-        .generate_proposal(11, empty_callback())
+        .generate_proposal(11, &proposer_election, empty_callback())
         .await
         .unwrap();
     assert_eq!(a1_child_res.parent_id(), a1.id());
     assert_eq!(a1_child_res.round(), 11);
     assert_eq!(a1_child_res.quorum_cert().certified_block().id(), a1.id());
+    // the rounds 2 to 10 failed
+    assert_eq!(
+        a1_child_res.failed_authors(),
+        Some(
+            &(2..11)
+                .map(|round| (round, inserter.signer().author()))
+                .collect()
+        )
+    );
 
     // Once b1 is certified, it should be the one to choose from
     inserter.insert_qc_for_block(b1.as_ref(), None);
     let b1_child_res = proposal_generator
-        .generate_proposal(12, empty_callback())
+        .generate_proposal(12, &proposer_election, empty_callback())
         .await
         .unwrap();
     assert_eq!(b1_child_res.parent_id(), b1.id());
@@ -101,6 +119,7 @@ async fn test_proposal_generation_parent() {
 #[tokio::test]
 async fn test_old_proposal_generation() {
     let mut inserter = TreeInserter::default();
+    let proposer_election = RotatingProposer::new(vec![inserter.signer().author()], 1);
     let block_store = inserter.block_store();
     let mut proposal_generator = ProposalGenerator::new(
         inserter.signer().author(),
@@ -108,6 +127,8 @@ async fn test_old_proposal_generation() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        false,
+        10,
     );
     let genesis = block_store.ordered_root();
     let a1 = inserter
@@ -116,7 +137,7 @@ async fn test_old_proposal_generation() {
     inserter.insert_qc_for_block(a1.as_ref(), None);
 
     let proposal_err = proposal_generator
-        .generate_proposal(1, empty_callback())
+        .generate_proposal(1, &proposer_election, empty_callback())
         .await
         .err();
     assert!(proposal_err.is_some());
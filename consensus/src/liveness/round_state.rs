@@ -278,6 +278,11 @@ impl RoundState {
         self.vote_sent.clone()
     }
 
+    /// Votes received for the current round.
+    pub fn pending_votes(&self) -> &PendingVotes {
+        &self.pending_votes
+    }
+
     /// Setup the timeout task and return the duration of the current timeout
     fn setup_timeout(&mut self) -> Duration {
         let timeout_sender = self.timeout_sender.clone();
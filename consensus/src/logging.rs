@@ -16,12 +16,15 @@ pub struct LogSchema {
 
 #[derive(Serialize)]
 pub enum LogEvent {
+    BroadcastBatch,
     CommitViaBlock,
     CommitViaSync,
     HelpPeerSync,
     NewEpoch,
+    NewProofOfStore,
     NewRound,
     Propose,
+    ReceiveBatchRetrieval,
     ReceiveBlockRetrieval,
     ReceiveEpochChangeProof,
     ReceiveEpochRetrieval,
@@ -29,6 +32,7 @@ pub enum LogEvent {
     ReceiveProposal,
     ReceiveSyncInfo,
     ReceiveVote,
+    RetrieveBatch,
     RetrieveBlock,
     StateSync,
     SyncToPeer,
@@ -11,6 +11,7 @@ use aptos_types::{
 };
 use consensus_types::{
     block_data::BlockData,
+    proof_of_store::SignedDigestInfo,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
     vote::Vote,
@@ -143,6 +144,10 @@ impl TSafetyRules for MetricsSafetyRules {
             )
         })
     }
+
+    fn sign_signed_digest(&mut self, info: &SignedDigestInfo) -> Result<Ed25519Signature, Error> {
+        self.retry(|inner| monitor!("safety_rules", inner.sign_signed_digest(info)))
+    }
 }
 
 #[cfg(test)]
@@ -156,6 +161,7 @@ mod tests {
     use claim::{assert_matches, assert_ok};
     use consensus_types::{
         block_data::BlockData,
+        proof_of_store::SignedDigestInfo,
         timeout::Timeout,
         timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
         vote::Vote,
@@ -241,6 +247,10 @@ mod tests {
         ) -> Result<Ed25519Signature, Error> {
             unimplemented!()
         }
+
+        fn sign_signed_digest(&mut self, _: &SignedDigestInfo) -> Result<Ed25519Signature, Error> {
+            unimplemented!()
+        }
     }
 
     #[test]
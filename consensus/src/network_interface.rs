@@ -14,6 +14,7 @@ use consensus_types::{
     block_retrieval::{BlockRetrievalRequest, BlockRetrievalResponse},
     epoch_retrieval::EpochRetrievalRequest,
     experimental::{commit_decision::CommitDecision, commit_vote::CommitVote},
+    proof_of_store::{Batch, BatchRequest, ProofOfStore, SignedDigest},
     proposal_msg::ProposalMsg,
     sync_info::SyncInfo,
     vote_msg::VoteMsg,
@@ -62,6 +63,16 @@ pub enum ConsensusMsg {
     /// than 2f + 1 signatures on the commit proposal. This part is not on the critical path, but
     /// it can save slow machines to quickly confirm the execution result.
     CommitDecisionMsg(Box<CommitDecision>),
+    /// Quorum store: a batch of transactions broadcast by its source ahead of ordering, also
+    /// used as the response to a BatchRequest.
+    BatchMsg(Box<Batch>),
+    /// Quorum store: a validator's promise to store a batch, sent back to the batch source.
+    SignedDigestMsg(Box<SignedDigest>),
+    /// Quorum store: the aggregated promises of a quorum, broadcast so that any leader can
+    /// propose the batch.
+    ProofOfStoreMsg(Box<ProofOfStore>),
+    /// Quorum store: RPC to get the content of a batch referenced by a proof of store.
+    BatchRequest(Box<BatchRequest>),
 }
 
 /// The interface from Network to Consensus layer.
@@ -198,4 +209,16 @@ impl ApplicationNetworkSender<ConsensusMsg> for ConsensusNetworkSender {
             .send_rpc(recipient, protocol, message, timeout)
             .await
     }
+
+    async fn send_rpc_with_size(
+        &self,
+        recipient: PeerId,
+        message: ConsensusMsg,
+        timeout: Duration,
+    ) -> Result<(ConsensusMsg, usize), RpcError> {
+        let protocol = self.preferred_protocol_for_peer(recipient, RPC)?;
+        self.network_sender
+            .send_rpc_with_size(recipient, protocol, message, timeout)
+            .await
+    }
 }
@@ -7,6 +7,7 @@
 //! when enough votes (or timeout votes) have been observed.
 //! Votes are automatically dropped when the structure goes out of scope.
 
+use crate::introspection::VoteTally;
 use aptos_crypto::{hash::CryptoHash, HashValue};
 use aptos_logger::prelude::*;
 use aptos_types::{
@@ -219,6 +220,56 @@ impl PendingVotes {
 
         VoteReceptionResult::VoteAdded(voting_power)
     }
+
+    /// The voters and their voting power for every ledger info voted for.
+    pub fn vote_tallies(&self, validator_verifier: &ValidatorVerifier) -> Vec<VoteTally> {
+        let mut tallies: BTreeMap<HashValue, VoteTally> = BTreeMap::new();
+        for vote in self.author_to_vote.values() {
+            let proposed = vote.vote_data().proposed();
+            let tally = tallies
+                .entry(vote.ledger_info().hash())
+                .or_insert_with(|| VoteTally {
+                    block_id: proposed.id(),
+                    block_round: proposed.round(),
+                    ledger_info_digest: vote.ledger_info().hash(),
+                    voters: vec![],
+                    voting_power: 0,
+                });
+            tally.voters.push(vote.author());
+            tally.voting_power += validator_verifier
+                .get_voting_power(&vote.author())
+                .unwrap_or(0);
+        }
+        tallies
+            .into_iter()
+            .map(|(_, mut tally)| {
+                tally.voters.sort();
+                tally
+            })
+            .collect()
+    }
+
+    /// The number of votes and of timeout votes, cheap to compare to detect new votes.
+    pub fn num_votes(&self) -> (usize, usize) {
+        let num_timeouts = self
+            .author_to_vote
+            .values()
+            .filter(|vote| vote.is_timeout())
+            .count();
+        (self.author_to_vote.len(), num_timeouts)
+    }
+
+    /// The authors of the timeout votes.
+    pub fn timeout_voters(&self) -> Vec<Author> {
+        let mut voters: Vec<_> = self
+            .author_to_vote
+            .values()
+            .filter(|vote| vote.is_timeout())
+            .map(|vote| vote.author())
+            .collect();
+        voters.sort();
+        voters
+    }
 }
 
 //
@@ -453,4 +504,41 @@ mod tests {
             }
         };
     }
+
+    #[test]
+    /// Verify that the tallies group the votes by ledger info
+    fn test_vote_tallies() {
+        let (signers, validator) = random_validator_verifier(4, Some(2), false);
+        let mut pending_votes = PendingVotes::new();
+
+        let li1 = random_ledger_info();
+        let vote_data_1 = random_vote_data();
+        let block_id = vote_data_1.proposed().id();
+        for signer in &signers[..2] {
+            let vote = Vote::new(vote_data_1.clone(), signer.author(), li1.clone(), signer);
+            pending_votes.insert_vote(&vote, &validator);
+        }
+        let mut vote = Vote::new(
+            random_vote_data(),
+            signers[2].author(),
+            random_ledger_info(),
+            &signers[2],
+        );
+        let timeout = vote.generate_timeout();
+        vote.add_timeout_signature(timeout.sign(&signers[2]));
+        pending_votes.insert_vote(&vote, &validator);
+
+        let tallies = pending_votes.vote_tallies(&validator);
+        assert_eq!(tallies.len(), 2);
+        let tally = tallies
+            .iter()
+            .find(|tally| tally.block_id == block_id)
+            .unwrap();
+        assert_eq!(tally.voting_power, 2);
+        let mut voters = vec![signers[0].author(), signers[1].author()];
+        voters.sort();
+        assert_eq!(tally.voters, voters);
+        assert_eq!(pending_votes.timeout_voters(), vec![signers[2].author()]);
+        assert_eq!(pending_votes.num_votes(), (3, 1));
+    }
 }
@@ -175,6 +175,8 @@ impl RootMetadata {
 pub struct RecoveryData {
     // The last vote message sent by this validator.
     last_vote: Option<Vote>,
+    // The latest ledger info in storage the root is derived from.
+    storage_ledger: LedgerInfoWithSignatures,
     root: RootInfo,
     root_metadata: RootMetadata,
     // 1. the blocks guarantee the topological ordering - parent <- child.
@@ -233,6 +235,7 @@ impl RecoveryData {
                 Some(v) if v.epoch() == epoch => Some(v),
                 _ => None,
             },
+            storage_ledger: ledger_recovery_data.storage_ledger,
             root,
             root_metadata,
             blocks,
@@ -257,6 +260,10 @@ impl RecoveryData {
         self.last_vote.clone()
     }
 
+    pub fn storage_ledger(&self) -> &LedgerInfoWithSignatures {
+        &self.storage_ledger
+    }
+
     pub fn take(self) -> (RootInfo, RootMetadata, Vec<Block>, Vec<QuorumCert>) {
         (
             self.root,
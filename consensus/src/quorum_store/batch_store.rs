@@ -0,0 +1,152 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters;
+use anyhow::{bail, ensure};
+use aptos_config::config::QuorumStoreConfig;
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
+use aptos_types::PeerId;
+use consensus_types::proof_of_store::{Batch, LogicalTime, ProofOfStore};
+use std::collections::HashMap;
+
+/// The number of batches and bytes stored for a source.
+#[derive(Default)]
+struct Usage {
+    num_batches: usize,
+    num_bytes: usize,
+}
+
+struct BatchStoreInner {
+    batches: HashMap<HashValue, Batch>,
+    usage: HashMap<PeerId, Usage>,
+    // Digests of the batches that were committed, kept until they expire so that a batch is not
+    // proposed twice.
+    committed: HashMap<HashValue, LogicalTime>,
+    certified_time: LogicalTime,
+}
+
+impl BatchStoreInner {
+    fn remove_expired(&mut self, certified_time: LogicalTime) {
+        let usage = &mut self.usage;
+        self.batches.retain(|_, batch| {
+            if batch.expiration() > certified_time {
+                return true;
+            }
+            if let Some(source_usage) = usage.get_mut(&batch.source()) {
+                source_usage.num_batches -= 1;
+                source_usage.num_bytes -= batch.num_bytes();
+            }
+            false
+        });
+        usage.retain(|_, source_usage| source_usage.num_batches > 0);
+    }
+}
+
+/// Local storage for the batches received from the quorum store of other validators (and our
+/// own). The store is shared across epochs: a block of the previous epoch can still reference
+/// batches while it is executed after the reconfiguration.
+/// Every source has its own quota so that a single validator cannot fill the store.
+pub struct BatchStore {
+    config: QuorumStoreConfig,
+    inner: Mutex<BatchStoreInner>,
+}
+
+impl BatchStore {
+    pub fn new(config: QuorumStoreConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(BatchStoreInner {
+                batches: HashMap::new(),
+                usage: HashMap::new(),
+                committed: HashMap::new(),
+                certified_time: LogicalTime::new(0, 0),
+            }),
+        }
+    }
+
+    /// Stores the batch if it is within the size limits and the quota of its source, the
+    /// caller is expected to have verified it already.
+    pub fn save(&self, batch: Batch) -> anyhow::Result<()> {
+        let num_bytes = batch.num_bytes();
+        ensure!(
+            batch.num_txns() <= self.config.max_batch_size,
+            "Batch {} has too many transactions: {}",
+            batch.digest(),
+            batch.num_txns()
+        );
+        ensure!(
+            num_bytes <= self.config.max_batch_bytes,
+            "Batch {} is too large: {} bytes",
+            batch.digest(),
+            num_bytes
+        );
+
+        let mut inner = self.inner.lock();
+        ensure!(
+            batch.expiration() > inner.certified_time,
+            "Batch {} already expired",
+            batch.digest()
+        );
+        if inner.batches.contains_key(&batch.digest()) {
+            return Ok(());
+        }
+        if inner.batches.len() >= self.config.batch_store_capacity {
+            bail!("Batch store is full, dropping batch {}", batch.digest());
+        }
+        let usage = inner.usage.entry(batch.source()).or_default();
+        if usage.num_batches >= self.config.batch_store_per_author_quota
+            || usage.num_bytes + num_bytes > self.config.batch_store_per_author_bytes_quota
+        {
+            bail!(
+                "Quota of {} exceeded, dropping batch {}",
+                batch.source(),
+                batch.digest()
+            );
+        }
+        usage.num_batches += 1;
+        usage.num_bytes += num_bytes;
+        inner.batches.insert(batch.digest(), batch);
+        counters::QUORUM_STORE_BATCH_STORE_SIZE.set(inner.batches.len() as i64);
+        Ok(())
+    }
+
+    pub fn get(&self, digest: &HashValue) -> Option<Batch> {
+        self.inner.lock().batches.get(digest).cloned()
+    }
+
+    pub fn contains(&self, digest: &HashValue) -> bool {
+        self.inner.lock().batches.contains_key(digest)
+    }
+
+    pub fn is_committed(&self, digest: &HashValue) -> bool {
+        self.inner.lock().committed.contains_key(digest)
+    }
+
+    pub fn certified_time(&self) -> LogicalTime {
+        self.inner.lock().certified_time
+    }
+
+    /// Records the proofs included in committed blocks so that they are not proposed again.
+    pub fn mark_committed<'a>(&self, proofs: impl Iterator<Item = &'a ProofOfStore>) {
+        let mut inner = self.inner.lock();
+        for proof in proofs {
+            inner.committed.insert(proof.digest(), proof.expiration());
+        }
+    }
+
+    /// Advances the time of the latest committed block and garbage collects everything that
+    /// expired at or before it.
+    pub fn update_certified_time(&self, certified_time: LogicalTime) {
+        let mut inner = self.inner.lock();
+        if certified_time <= inner.certified_time {
+            return;
+        }
+        inner.certified_time = certified_time;
+        inner.remove_expired(certified_time);
+        inner
+            .committed
+            .retain(|_, expiration| *expiration > certified_time);
+        counters::QUORUM_STORE_BATCH_STORE_SIZE.set(inner.batches.len() as i64);
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::quorum_store::{batch_store::BatchStore, proof_queue::ProofQueue};
+use aptos_config::config::QuorumStoreConfig;
+use aptos_types::PeerId;
+use consensus_types::proof_of_store::{
+    Batch, BatchPayload, LogicalTime, ProofOfStore, SignedDigestInfo,
+};
+use std::collections::{BTreeMap, HashSet};
+
+fn batch(epoch: u64, expiration_round: u64) -> Batch {
+    Batch::new(
+        epoch,
+        LogicalTime::new(epoch, expiration_round),
+        BatchPayload::new(PeerId::random(), vec![]),
+    )
+}
+
+fn proof_for(batch: &Batch, num_txns: u64) -> ProofOfStore {
+    ProofOfStore::new(
+        SignedDigestInfo::new(batch.digest(), batch.expiration(), num_txns),
+        BTreeMap::new(),
+    )
+}
+
+#[test]
+fn test_save_and_expire() {
+    let store = BatchStore::new(QuorumStoreConfig::default());
+    let early = batch(1, 5);
+    let late = batch(1, 10);
+    store.save(early.clone()).unwrap();
+    store.save(late.clone()).unwrap();
+    assert_eq!(store.get(&early.digest()), Some(early.clone()));
+
+    store.update_certified_time(LogicalTime::new(1, 5));
+    assert!(!store.contains(&early.digest()));
+    assert!(store.contains(&late.digest()));
+
+    // expired batches are rejected
+    assert!(store.save(early).is_err());
+    // a new epoch expires everything from the previous one
+    store.update_certified_time(LogicalTime::new(2, 1));
+    assert!(!store.contains(&late.digest()));
+}
+
+#[test]
+fn test_capacity() {
+    let store = BatchStore::new(QuorumStoreConfig {
+        batch_store_capacity: 1,
+        ..QuorumStoreConfig::default()
+    });
+    let first = batch(1, 5);
+    store.save(first.clone()).unwrap();
+    // saving the same batch twice is a no-op
+    store.save(first).unwrap();
+    assert!(store.save(batch(1, 5)).is_err());
+}
+
+#[test]
+fn test_per_author_quota() {
+    let store = BatchStore::new(QuorumStoreConfig {
+        batch_store_per_author_quota: 2,
+        ..QuorumStoreConfig::default()
+    });
+    let source = PeerId::random();
+    let batch_from = |expiration_round| {
+        Batch::new(
+            1,
+            LogicalTime::new(1, expiration_round),
+            BatchPayload::new(source, vec![]),
+        )
+    };
+    store.save(batch_from(5)).unwrap();
+    store.save(batch_from(10)).unwrap();
+    // the source is over its quota, the others are not
+    assert!(store.save(batch_from(15)).is_err());
+    store.save(batch(1, 15)).unwrap();
+
+    // expired batches no longer count against the quota
+    store.update_certified_time(LogicalTime::new(1, 5));
+    store.save(batch_from(15)).unwrap();
+}
+
+#[test]
+fn test_batch_verify() {
+    let b = batch(1, 10);
+    let source = b.source();
+    b.verify(source, LogicalTime::new(1, 10)).unwrap();
+    // only the source broadcasts its batches
+    assert!(b.verify(PeerId::random(), LogicalTime::new(1, 10)).is_err());
+    // batches expiring too far in the future are not stored
+    assert!(b.verify(source, LogicalTime::new(1, 9)).is_err());
+}
+
+#[test]
+fn test_proof_queue_pull() {
+    let store = BatchStore::new(QuorumStoreConfig::default());
+    let queue = ProofQueue::new();
+    let batches: Vec<_> = (0..4).map(|_| batch(1, 10)).collect();
+    for b in &batches {
+        queue.push(proof_for(b, 10));
+    }
+    // duplicates are ignored
+    queue.push(proof_for(&batches[0], 10));
+
+    let digests = |proofs: Vec<ProofOfStore>| -> Vec<_> {
+        proofs.into_iter().map(|proof| proof.digest()).collect()
+    };
+    assert_eq!(
+        digests(queue.pull(25, &HashSet::new(), &store)),
+        vec![batches[0].digest(), batches[1].digest()]
+    );
+
+    let exclude = vec![batches[0].digest()].into_iter().collect();
+    assert_eq!(
+        digests(queue.pull(25, &exclude, &store)),
+        vec![batches[1].digest(), batches[2].digest()]
+    );
+
+    // committed proofs are never pulled again
+    store.mark_committed(vec![proof_for(&batches[1], 10)].iter());
+    assert_eq!(
+        digests(queue.pull(25, &exclude, &store)),
+        vec![batches[2].digest(), batches[3].digest()]
+    );
+
+    store.update_certified_time(LogicalTime::new(1, 10));
+    assert!(queue.pull(25, &HashSet::new(), &store).is_empty());
+}
@@ -0,0 +1,17 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Quorum store decouples the dissemination of transactions from ordering: validators broadcast
+//! batches ahead of time, and proposals only carry proofs that a quorum stored each batch.
+
+pub(crate) mod batch_store;
+pub(crate) mod payload_manager;
+pub(crate) mod proof_builder;
+pub(crate) mod proof_queue;
+pub(crate) mod quorum_store_client;
+pub(crate) mod quorum_store_task;
+
+#[cfg(test)]
+mod batch_store_test;
+#[cfg(test)]
+mod proof_builder_test;
@@ -0,0 +1,122 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    logging::{LogEvent, LogSchema},
+    network_interface::{ConsensusMsg, ConsensusNetworkSender},
+    quorum_store::batch_store::BatchStore,
+};
+use anyhow::bail;
+use aptos_logger::prelude::*;
+use aptos_types::transaction::SignedTransaction;
+use consensus_types::{
+    block::Block,
+    common::{Author, Payload},
+    executed_block::ExecutedBlock,
+    proof_of_store::{Batch, BatchRequest, LogicalTime, ProofOfStore},
+};
+use network::protocols::network::ApplicationNetworkSender;
+use std::{sync::Arc, time::Duration};
+
+/// Resolves block payloads into the transactions to execute. Proofs of store are resolved from
+/// the local batch store, or by fetching the batch from one of the signers of the proof.
+pub struct PayloadManager {
+    author: Author,
+    batch_store: Arc<BatchStore>,
+    network_sender: ConsensusNetworkSender,
+    request_timeout: Duration,
+}
+
+impl PayloadManager {
+    pub fn new(
+        author: Author,
+        batch_store: Arc<BatchStore>,
+        network_sender: ConsensusNetworkSender,
+        request_timeout: Duration,
+    ) -> Self {
+        Self {
+            author,
+            batch_store,
+            network_sender,
+            request_timeout,
+        }
+    }
+
+    pub async fn get_transactions(&self, block: &Block) -> anyhow::Result<Vec<SignedTransaction>> {
+        match block.payload() {
+            None => Ok(vec![]),
+            Some(Payload::DirectMempool(txns)) => Ok(txns.clone()),
+            Some(Payload::InQuorumStore(proofs)) => {
+                let mut txns = vec![];
+                for proof in proofs {
+                    let batch = match self.batch_store.get(&proof.digest()) {
+                        Some(batch) => batch,
+                        None => self.fetch_batch(proof).await?,
+                    };
+                    txns.extend(batch.into_payload().into_txns());
+                }
+                Ok(txns)
+            }
+        }
+    }
+
+    async fn fetch_batch(&self, proof: &ProofOfStore) -> anyhow::Result<Batch> {
+        let request = BatchRequest::new(proof.epoch(), proof.digest());
+        for signer in proof.signers().filter(|signer| **signer != self.author) {
+            debug!(
+                LogSchema::new(LogEvent::RetrieveBatch).remote_peer(*signer),
+                "{}", request
+            );
+            let msg = ConsensusMsg::BatchRequest(Box::new(request.clone()));
+            match self
+                .network_sender
+                .send_rpc(*signer, msg, self.request_timeout)
+                .await
+            {
+                Ok(ConsensusMsg::BatchMsg(batch))
+                    if batch.digest() == proof.digest() && batch.verify_digest().is_ok() =>
+                {
+                    counters::QUORUM_STORE_BATCH_FETCHES
+                        .with_label_values(&["success"])
+                        .inc();
+                    // The batch can already be expired locally, it is still needed for execution.
+                    if let Err(e) = self.batch_store.save(*batch.clone()) {
+                        debug!(error = ?e, "Fetched batch not stored");
+                    }
+                    return Ok(*batch);
+                }
+                Ok(response) => {
+                    warn!(
+                        remote_peer = *signer,
+                        "Invalid response to {}: {:?}", request, response
+                    );
+                }
+                Err(e) => {
+                    warn!(remote_peer = *signer, error = ?e, "Failed to fetch batch");
+                }
+            }
+            counters::QUORUM_STORE_BATCH_FETCHES
+                .with_label_values(&["failed"])
+                .inc();
+        }
+        bail!("Unable to fetch batch {} from any signer", proof.digest())
+    }
+
+    /// Marks the batches of the committed blocks and garbage collects the expired ones.
+    pub fn notify_commit(&self, blocks: &[Arc<ExecutedBlock>]) {
+        self.batch_store.mark_committed(
+            blocks
+                .iter()
+                .filter_map(|block| match block.payload() {
+                    Some(Payload::InQuorumStore(proofs)) => Some(proofs.iter()),
+                    _ => None,
+                })
+                .flatten(),
+        );
+        if let Some(last_block) = blocks.last() {
+            self.batch_store
+                .update_certified_time(LogicalTime::new(last_block.epoch(), last_block.round()));
+        }
+    }
+}
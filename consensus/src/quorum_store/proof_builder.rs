@@ -0,0 +1,79 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{bail, ensure};
+use aptos_crypto::{ed25519::Ed25519Signature, HashValue};
+use aptos_types::{validator_verifier::ValidatorVerifier, PeerId};
+use consensus_types::proof_of_store::{LogicalTime, ProofOfStore, SignedDigest, SignedDigestInfo};
+use std::collections::{BTreeMap, HashMap};
+
+struct IncompleteProof {
+    info: SignedDigestInfo,
+    signatures: BTreeMap<PeerId, Ed25519Signature>,
+}
+
+/// Aggregates the signed digests received for the batches created by this validator into proofs
+/// of store.
+#[derive(Default)]
+pub struct ProofBuilder {
+    incomplete_proofs: HashMap<HashValue, IncompleteProof>,
+}
+
+impl ProofBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts collecting signatures for a batch we just broadcast.
+    pub fn init_proof(&mut self, info: SignedDigestInfo) {
+        self.incomplete_proofs.insert(
+            info.digest(),
+            IncompleteProof {
+                info,
+                signatures: BTreeMap::new(),
+            },
+        );
+    }
+
+    /// Adds an already verified signed digest, returns the proof once a quorum has signed.
+    pub fn add_signature(
+        &mut self,
+        signed_digest: SignedDigest,
+        verifier: &ValidatorVerifier,
+    ) -> anyhow::Result<Option<ProofOfStore>> {
+        let digest = signed_digest.info().digest();
+        let incomplete_proof = match self.incomplete_proofs.get_mut(&digest) {
+            Some(incomplete_proof) => incomplete_proof,
+            None => bail!("No pending proof for digest {}", digest),
+        };
+        ensure!(
+            &incomplete_proof.info == signed_digest.info(),
+            "SignedDigest info does not match the batch {}",
+            digest
+        );
+        incomplete_proof
+            .signatures
+            .insert(signed_digest.signer(), signed_digest.signature().clone());
+        if verifier
+            .check_voting_power(incomplete_proof.signatures.keys())
+            .is_err()
+        {
+            return Ok(None);
+        }
+        let IncompleteProof { info, signatures } = self
+            .incomplete_proofs
+            .remove(&digest)
+            .expect("incomplete proof must exist");
+        Ok(Some(ProofOfStore::new(info, signatures)))
+    }
+
+    /// Drops the pending proofs that can no longer be completed in time.
+    pub fn expire(&mut self, certified_time: LogicalTime) {
+        self.incomplete_proofs
+            .retain(|_, proof| proof.info.expiration() > certified_time);
+    }
+
+    pub fn num_pending(&self) -> usize {
+        self.incomplete_proofs.len()
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::quorum_store::proof_builder::ProofBuilder;
+use aptos_crypto::HashValue;
+use aptos_types::{
+    validator_signer::ValidatorSigner, validator_verifier::random_validator_verifier,
+};
+use consensus_types::proof_of_store::{LogicalTime, SignedDigest, SignedDigestInfo};
+
+fn signed_digest(signer: &ValidatorSigner, info: SignedDigestInfo) -> SignedDigest {
+    let signature = signer.sign(&info);
+    SignedDigest::new(1, signer.author(), info, signature)
+}
+
+#[test]
+fn test_proof_aggregation() {
+    let (signers, verifier) = random_validator_verifier(4, None, false);
+    let mut builder = ProofBuilder::new();
+    let info = SignedDigestInfo::new(HashValue::random(), LogicalTime::new(1, 10), 5);
+
+    // signatures for unknown batches are rejected
+    assert!(builder
+        .add_signature(signed_digest(&signers[0], info.clone()), &verifier)
+        .is_err());
+
+    builder.init_proof(info.clone());
+    // a signature on a different expiration does not count
+    let wrong_info = SignedDigestInfo::new(info.digest(), LogicalTime::new(1, 20), 5);
+    assert!(builder
+        .add_signature(signed_digest(&signers[0], wrong_info), &verifier)
+        .is_err());
+
+    for signer in &signers[0..2] {
+        assert!(builder
+            .add_signature(signed_digest(signer, info.clone()), &verifier)
+            .unwrap()
+            .is_none());
+    }
+    let proof = builder
+        .add_signature(signed_digest(&signers[2], info.clone()), &verifier)
+        .unwrap()
+        .unwrap();
+    assert_eq!(proof.info(), &info);
+    proof.verify(&verifier).unwrap();
+    assert_eq!(builder.num_pending(), 0);
+}
+
+#[test]
+fn test_expire_pending_proofs() {
+    let mut builder = ProofBuilder::new();
+    builder.init_proof(SignedDigestInfo::new(
+        HashValue::random(),
+        LogicalTime::new(1, 10),
+        5,
+    ));
+    builder.init_proof(SignedDigestInfo::new(
+        HashValue::random(),
+        LogicalTime::new(1, 20),
+        5,
+    ));
+    builder.expire(LogicalTime::new(1, 10));
+    assert_eq!(builder.num_pending(), 1);
+}
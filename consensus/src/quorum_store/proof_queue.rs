@@ -0,0 +1,60 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{counters, quorum_store::batch_store::BatchStore};
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
+use consensus_types::proof_of_store::ProofOfStore;
+use std::collections::{HashSet, VecDeque};
+
+/// The proofs of store of the current epoch that can be included in a proposal, in the order
+/// they were received. Proofs are only removed once they are committed or expired, a proof pulled
+/// for a proposal that does not make it into the chain is proposed again later.
+#[derive(Default)]
+pub struct ProofQueue {
+    proofs: Mutex<VecDeque<ProofOfStore>>,
+}
+
+impl ProofQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, proof: ProofOfStore) {
+        let mut proofs = self.proofs.lock();
+        if proofs.iter().any(|p| p.digest() == proof.digest()) {
+            return;
+        }
+        proofs.push_back(proof);
+        counters::QUORUM_STORE_PROOF_QUEUE_SIZE.set(proofs.len() as i64);
+    }
+
+    /// Returns proofs covering at most `max_txns` transactions, skipping the excluded digests.
+    pub fn pull(
+        &self,
+        max_txns: u64,
+        exclude: &HashSet<HashValue>,
+        batch_store: &BatchStore,
+    ) -> Vec<ProofOfStore> {
+        let certified_time = batch_store.certified_time();
+        let mut proofs = self.proofs.lock();
+        proofs.retain(|proof| {
+            proof.expiration() > certified_time && !batch_store.is_committed(&proof.digest())
+        });
+        counters::QUORUM_STORE_PROOF_QUEUE_SIZE.set(proofs.len() as i64);
+
+        let mut num_txns = 0;
+        let mut result = vec![];
+        for proof in proofs.iter() {
+            if exclude.contains(&proof.digest()) {
+                continue;
+            }
+            if num_txns + proof.num_txns() > max_txns {
+                break;
+            }
+            num_txns += proof.num_txns();
+            result.push(proof.clone());
+        }
+        result
+    }
+}
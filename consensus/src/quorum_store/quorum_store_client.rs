@@ -0,0 +1,92 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::MempoolError,
+    quorum_store::{batch_store::BatchStore, proof_queue::ProofQueue},
+    state_replication::TxnManager,
+};
+use anyhow::Result;
+use aptos_logger::prelude::*;
+use consensus_types::{block::Block, common::Payload};
+use executor_types::StateComputeResult;
+use futures::future::BoxFuture;
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use tokio::time::sleep;
+
+const NO_PROOF_DELAY: u64 = 30;
+
+/// Used by the proposal generator when quorum store is enabled: proposes the proofs of store
+/// collected by the quorum store instead of pulling transactions from mempool directly.
+pub struct QuorumStoreClient {
+    proof_queue: Arc<ProofQueue>,
+    batch_store: Arc<BatchStore>,
+    poll_count: u64,
+}
+
+impl QuorumStoreClient {
+    pub fn new(
+        proof_queue: Arc<ProofQueue>,
+        batch_store: Arc<BatchStore>,
+        poll_count: u64,
+    ) -> Self {
+        assert!(
+            poll_count > 0,
+            "poll_count = 0 won't pull any proofs from quorum store"
+        );
+        Self {
+            proof_queue,
+            batch_store,
+            poll_count,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TxnManager for QuorumStoreClient {
+    async fn pull_txns(
+        &self,
+        max_size: u64,
+        exclude_payloads: Vec<&Payload>,
+        wait_callback: BoxFuture<'static, ()>,
+        pending_ordering: bool,
+    ) -> Result<Payload, MempoolError> {
+        let mut exclude_digests = HashSet::new();
+        for payload in exclude_payloads {
+            if let Payload::InQuorumStore(proofs) = payload {
+                exclude_digests.extend(proofs.iter().map(|proof| proof.digest()));
+            }
+        }
+        let mut callback_wrapper = Some(wait_callback);
+        // keep polling the queue until there's a proof available or there's still pending txns
+        let mut count = self.poll_count;
+        let proofs = loop {
+            count -= 1;
+            let proofs = self
+                .proof_queue
+                .pull(max_size, &exclude_digests, &self.batch_store);
+            if proofs.is_empty() && !pending_ordering && count > 0 {
+                if let Some(callback) = callback_wrapper.take() {
+                    callback.await;
+                }
+                sleep(Duration::from_millis(NO_PROOF_DELAY)).await;
+                continue;
+            }
+            break proofs;
+        };
+        debug!(
+            poll_count = self.poll_count - count,
+            "Pull proofs from quorum store"
+        );
+        Ok(Payload::InQuorumStore(proofs))
+    }
+
+    /// Rejected transactions of a batch are not reported, mempool drops them once they expire.
+    async fn notify_failed_txn(
+        &self,
+        _block: &Block,
+        _compute_result: &StateComputeResult,
+    ) -> Result<(), MempoolError> {
+        Ok(())
+    }
+}
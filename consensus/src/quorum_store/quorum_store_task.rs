@@ -0,0 +1,234 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    logging::{LogEvent, LogSchema},
+    metrics_safety_rules::MetricsSafetyRules,
+    network::NetworkSender,
+    network_interface::ConsensusMsg,
+    quorum_store::{batch_store::BatchStore, proof_builder::ProofBuilder, proof_queue::ProofQueue},
+    round_manager::VerifiedEvent,
+    state_replication::TxnManager,
+};
+use anyhow::bail;
+use aptos_config::config::QuorumStoreConfig;
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_types::{account_address::AccountAddress, validator_verifier::ValidatorVerifier};
+use channel::aptos_channel;
+use consensus_types::{
+    common::{Author, Payload},
+    proof_of_store::{
+        Batch, BatchPayload, LogicalTime, ProofOfStore, SignedDigest, SignedDigestInfo,
+    },
+};
+use futures::{FutureExt, StreamExt};
+use safety_rules::TSafetyRules;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// The per-epoch quorum store: periodically pulls transactions from mempool into batches,
+/// broadcasts them, stores and signs the batches of the other validators, and aggregates the
+/// signatures on its own batches into proofs of store that the proposers can include in blocks.
+pub struct QuorumStore {
+    epoch: u64,
+    author: Author,
+    config: QuorumStoreConfig,
+    // Signs the batches we promise to store, the consensus key never leaves safety rules.
+    safety_rules: Arc<Mutex<MetricsSafetyRules>>,
+    verifier: ValidatorVerifier,
+    network_sender: NetworkSender,
+    txn_manager: Arc<dyn TxnManager>,
+    batch_store: Arc<BatchStore>,
+    proof_queue: Arc<ProofQueue>,
+    proof_builder: ProofBuilder,
+    // The transactions of our batches that are not committed yet, excluded from the next pulls.
+    pending_batches: HashMap<HashValue, (LogicalTime, Payload)>,
+}
+
+impl QuorumStore {
+    pub fn new(
+        epoch: u64,
+        author: Author,
+        config: QuorumStoreConfig,
+        safety_rules: Arc<Mutex<MetricsSafetyRules>>,
+        verifier: ValidatorVerifier,
+        network_sender: NetworkSender,
+        txn_manager: Arc<dyn TxnManager>,
+        batch_store: Arc<BatchStore>,
+        proof_queue: Arc<ProofQueue>,
+    ) -> Self {
+        Self {
+            epoch,
+            author,
+            config,
+            safety_rules,
+            verifier,
+            network_sender,
+            txn_manager,
+            batch_store,
+            proof_queue,
+            proof_builder: ProofBuilder::new(),
+            pending_batches: HashMap::new(),
+        }
+    }
+
+    /// The round batches expire relative to: the last committed round if it is in this epoch.
+    fn current_time(&self) -> LogicalTime {
+        let certified_time = self.batch_store.certified_time();
+        if certified_time.epoch() == self.epoch {
+            certified_time
+        } else {
+            LogicalTime::new(self.epoch, 0)
+        }
+    }
+
+    async fn generate_batch(&mut self) -> anyhow::Result<()> {
+        let certified_time = self.batch_store.certified_time();
+        let batch_store = self.batch_store.clone();
+        self.pending_batches.retain(|digest, (expiration, _)| {
+            *expiration > certified_time && !batch_store.is_committed(digest)
+        });
+        self.proof_builder.expire(certified_time);
+        if self.proof_builder.num_pending() >= self.config.max_pending_batches {
+            return Ok(());
+        }
+
+        let exclude = self
+            .pending_batches
+            .values()
+            .map(|(_, payload)| payload)
+            .collect();
+        let mut txns = match self
+            .txn_manager
+            .pull_txns(self.config.max_batch_size, exclude, async {}.boxed(), true)
+            .await?
+        {
+            Payload::DirectMempool(txns) => txns,
+            Payload::InQuorumStore(_) => bail!("Mempool returned a quorum store payload"),
+        };
+        // Receivers reject batches above the size limit.
+        let mut num_bytes = 0;
+        if let Some(limit) = txns.iter().position(|txn| {
+            num_bytes += txn.raw_txn_bytes_len();
+            num_bytes > self.config.max_batch_bytes
+        }) {
+            txns.truncate(limit);
+        }
+        if txns.is_empty() {
+            return Ok(());
+        }
+
+        let current_time = self.current_time();
+        let expiration = LogicalTime::new(
+            self.epoch,
+            current_time.round() + self.config.batch_expiry_rounds,
+        );
+        let batch = Batch::new(
+            self.epoch,
+            expiration,
+            BatchPayload::new(self.author, txns.clone()),
+        );
+        self.proof_builder.init_proof(SignedDigestInfo::new(
+            batch.digest(),
+            expiration,
+            batch.num_txns(),
+        ));
+        self.pending_batches
+            .insert(batch.digest(), (expiration, Payload::DirectMempool(txns)));
+        counters::QUORUM_STORE_CREATED_BATCHES.inc();
+        debug!(
+            LogSchema::new(LogEvent::BroadcastBatch).epoch(self.epoch),
+            "{}", batch
+        );
+        self.network_sender
+            .broadcast(ConsensusMsg::BatchMsg(Box::new(batch)))
+            .await;
+        Ok(())
+    }
+
+    async fn process_batch(&mut self, batch: Batch) -> anyhow::Result<()> {
+        let source = batch.source();
+        let info = SignedDigestInfo::new(batch.digest(), batch.expiration(), batch.num_txns());
+        // Only promise to store the batch once the store accepted it.
+        self.batch_store.save(batch)?;
+        let signature = self.safety_rules.lock().sign_signed_digest(&info)?;
+        let signed_digest = SignedDigest::new(self.epoch, self.author, info, signature);
+        self.network_sender
+            .send(
+                ConsensusMsg::SignedDigestMsg(Box::new(signed_digest)),
+                vec![source],
+            )
+            .await;
+        Ok(())
+    }
+
+    async fn process_signed_digest(&mut self, signed_digest: SignedDigest) -> anyhow::Result<()> {
+        if let Some(proof) = self
+            .proof_builder
+            .add_signature(signed_digest, &self.verifier)?
+        {
+            counters::QUORUM_STORE_AGGREGATED_PROOFS.inc();
+            debug!(
+                LogSchema::new(LogEvent::NewProofOfStore).epoch(self.epoch),
+                "{}", proof
+            );
+            self.network_sender
+                .broadcast(ConsensusMsg::ProofOfStoreMsg(Box::new(proof)))
+                .await;
+        }
+        Ok(())
+    }
+
+    fn process_proof(&mut self, proof: ProofOfStore) {
+        if proof.expiration() > self.batch_store.certified_time() {
+            self.proof_queue.push(proof);
+        }
+    }
+
+    async fn process_event(&mut self, event: VerifiedEvent) -> anyhow::Result<()> {
+        match event {
+            VerifiedEvent::Batch(batch) => self.process_batch(*batch).await,
+            VerifiedEvent::SignedDigest(signed_digest) => {
+                self.process_signed_digest(*signed_digest).await
+            }
+            VerifiedEvent::ProofOfStore(proof) => {
+                self.process_proof(*proof);
+                Ok(())
+            }
+            unexpected_event => bail!("Unexpected event: {:?}", unexpected_event),
+        }
+    }
+
+    /// Runs until the sending side of the channel is dropped at the end of the epoch.
+    pub async fn start(
+        mut self,
+        mut msg_rx: aptos_channel::Receiver<AccountAddress, VerifiedEvent>,
+    ) {
+        info!(epoch = self.epoch, "QuorumStore started");
+        let mut interval = tokio::time::interval(Duration::from_millis(
+            self.config.batch_generation_interval_ms,
+        ));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(e) = self.generate_batch().await {
+                        error!(epoch = self.epoch, error = ?e, "Failed to generate batch");
+                    }
+                }
+                maybe_event = msg_rx.next() => {
+                    match maybe_event {
+                        Some(event) => {
+                            if let Err(e) = self.process_event(event).await {
+                                warn!(epoch = self.epoch, error = ?e, "Failed to process quorum store message");
+                            }
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+        info!(epoch = self.epoch, "QuorumStore stopped");
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Opt-in recording of everything that drives a `RoundManager`: the inbound consensus messages,
+//! the local timeouts and the responses of SafetyRules. A recording can be fed back into a
+//! `RoundManager` with the replay harness to reproduce the behavior of a validator offline.
+
+use crate::{
+    counters, network_interface::ConsensusMsg, round_manager::VerifiedEvent,
+    util::time_service::TimeService,
+};
+use anyhow::{Context, Result};
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_types::{
+    epoch_state::EpochState, ledger_info::LedgerInfoWithSignatures,
+    on_chain_config::OnChainConsensusConfig,
+};
+use consensus_types::{
+    block_data::BlockData,
+    common::{Author, Round},
+    vote::Vote,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+/// Number of records waiting to be written before new records are dropped and replaced by a gap.
+const RECORDER_CHANNEL_SIZE: usize = 1_024;
+
+/// A single recorded event together with the local time it was observed at.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Record {
+    pub timestamp_usecs: u64,
+    pub event: RecordedEvent,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum RecordedEvent {
+    /// A new RoundManager is started on top of the given ledger info.
+    EpochStart {
+        epoch_state: EpochState,
+        onchain_config: OnChainConsensusConfig,
+        ledger_info: LedgerInfoWithSignatures,
+    },
+    /// A message handed to the RoundManager, after signature verification.
+    Message(Author, ConsensusMsg),
+    LocalTimeout(Round),
+    SafetyRules(SafetyRulesResponse),
+    /// The given number of records were dropped because the writer fell behind.
+    Gap(u64),
+}
+
+/// The outputs of SafetyRules the RoundManager acts upon.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum SafetyRulesResponse {
+    Vote(Vote),
+    Proposal(BlockData),
+    Timeout(Round),
+}
+
+enum RecordSink {
+    /// Sends the records to the writer thread, `dropped` counts the records dropped since the
+    /// last gap marker was queued.
+    Writer {
+        sender: SyncSender<Record>,
+        dropped: u64,
+    },
+    #[cfg(any(test, feature = "fuzzing"))]
+    Memory(Vec<Record>),
+    Closed,
+}
+
+/// Appends the records to a file as length-prefixed BCS. The file is written by a dedicated
+/// thread so that consensus never waits on the disk: records are dropped if the writer falls
+/// behind and replaced by a gap marker once it catches up, so that a replay knows the recording is
+/// incomplete. Records are flushed as soon as the writer catches up so that a crash doesn't lose
+/// the events that led to it. Dropping the recorder waits for the pending records to be written.
+pub struct ConsensusRecorder {
+    sink: Mutex<RecordSink>,
+    writer: Option<JoinHandle<()>>,
+    time_service: Arc<dyn TimeService>,
+}
+
+impl ConsensusRecorder {
+    pub fn new(path: &Path, time_service: Arc<dyn TimeService>) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Unable to open consensus recording {:?}", path))?;
+        let (sender, receiver) = sync_channel(RECORDER_CHANNEL_SIZE);
+        let writer = std::thread::Builder::new()
+            .name("consensus-recorder".into())
+            .spawn(move || write_records(file, receiver))
+            .context("Unable to start the consensus recorder")?;
+        Ok(Self {
+            sink: Mutex::new(RecordSink::Writer { sender, dropped: 0 }),
+            writer: Some(writer),
+            time_service,
+        })
+    }
+
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub fn in_memory(time_service: Arc<dyn TimeService>) -> Self {
+        Self {
+            sink: Mutex::new(RecordSink::Memory(vec![])),
+            writer: None,
+            time_service,
+        }
+    }
+
+    pub fn record(&self, event: RecordedEvent) {
+        let timestamp_usecs = self.time_service.get_current_timestamp().as_micros() as u64;
+        let record = Record {
+            timestamp_usecs,
+            event,
+        };
+        match &mut *self.sink.lock() {
+            RecordSink::Writer { sender, dropped } => {
+                // The gap has to be written before any record following it.
+                let result = if *dropped > 0 {
+                    let gap = Record {
+                        timestamp_usecs,
+                        event: RecordedEvent::Gap(*dropped),
+                    };
+                    sender.try_send(gap).map(|_| *dropped = 0)
+                } else {
+                    Ok(())
+                };
+                match result.and_then(|_| sender.try_send(record)) {
+                    Ok(()) => (),
+                    Err(TrySendError::Full(_)) => {
+                        *dropped += 1;
+                        counters::CONSENSUS_RECORDER_DROPPED_RECORDS.inc();
+                    }
+                    Err(TrySendError::Disconnected(_)) => {
+                        warn!("Consensus recorder stopped, dropping record")
+                    }
+                }
+            }
+            #[cfg(any(test, feature = "fuzzing"))]
+            RecordSink::Memory(records) => records.push(record),
+            RecordSink::Closed => (),
+        }
+    }
+
+    /// Records the events the RoundManager reacts to, the other events are ignored.
+    pub fn record_input(&self, author: Author, event: &VerifiedEvent) {
+        let event = match event {
+            VerifiedEvent::ProposalMsg(p) => {
+                RecordedEvent::Message(author, ConsensusMsg::ProposalMsg(p.clone()))
+            }
+            VerifiedEvent::VoteMsg(v) => {
+                RecordedEvent::Message(author, ConsensusMsg::VoteMsg(v.clone()))
+            }
+            VerifiedEvent::UnverifiedSyncInfo(s) => {
+                RecordedEvent::Message(author, ConsensusMsg::SyncInfo(s.clone()))
+            }
+            VerifiedEvent::LocalTimeout(round) => RecordedEvent::LocalTimeout(*round),
+            _ => return,
+        };
+        self.record(event);
+    }
+
+    pub fn record_safety_rules(&self, response: SafetyRulesResponse) {
+        self.record(RecordedEvent::SafetyRules(response));
+    }
+
+    /// Returns the records collected so far by an in-memory recorder.
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub fn take_records(&self) -> Vec<Record> {
+        match &mut *self.sink.lock() {
+            RecordSink::Writer { .. } | RecordSink::Closed => vec![],
+            RecordSink::Memory(records) => std::mem::take(records),
+        }
+    }
+}
+
+impl Drop for ConsensusRecorder {
+    fn drop(&mut self) {
+        // Closing the channel stops the writer once the pending records are written.
+        let sink = std::mem::replace(&mut *self.sink.lock(), RecordSink::Closed);
+        if let RecordSink::Writer { sender, dropped } = sink {
+            if dropped > 0 {
+                let gap = Record {
+                    timestamp_usecs: self.time_service.get_current_timestamp().as_micros() as u64,
+                    event: RecordedEvent::Gap(dropped),
+                };
+                // Waits for the writer to make room, the recorder is going away anyway.
+                let _ = sender.send(gap);
+            }
+        }
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                warn!("Consensus recorder writer panicked");
+            }
+        }
+    }
+}
+
+/// Runs on the writer thread until the recorder is dropped.
+fn write_records(file: File, receiver: Receiver<Record>) {
+    let mut writer = BufWriter::new(file);
+    while let Ok(record) = receiver.recv() {
+        let mut result = write_record(&mut writer, &record);
+        // Write whatever is already queued before flushing.
+        while let Ok(record) = receiver.try_recv() {
+            result = result.and_then(|_| write_record(&mut writer, &record));
+        }
+        if let Err(e) = result.and_then(|_| Ok(writer.flush()?)) {
+            warn!(error = ?e, "Failed to write consensus record");
+        }
+    }
+}
+
+fn write_record(writer: &mut impl Write, record: &Record) -> Result<()> {
+    let bytes = bcs::to_bytes(record)?;
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
@@ -0,0 +1,405 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Feeds a consensus recording back into a `RoundManager` running on top of `MockStateComputer`,
+//! `MockStorage` and the simulated time service, and reports where the votes, proposals and
+//! timeouts signed during the replay diverge from the recorded ones.
+//!
+//! The replay needs the consensus key of the recorded validator, and every epoch of the recording
+//! has to start from an epoch boundary. Blocks fetched through block retrieval are not part of the
+//! recording, so a replay that needs to sync up from its peers diverges at that point.
+
+use crate::{
+    block_storage::BlockStore,
+    epoch_manager::LivenessStorageData,
+    liveness::{
+        proposal_generator::ProposalGenerator,
+        round_proposer_election::RoundProposer,
+        round_state::{ExponentialTimeInterval, RoundState},
+    },
+    metrics_safety_rules::MetricsSafetyRules,
+    network::NetworkSender,
+    network_interface::{ConsensusMsg, ConsensusNetworkSender},
+    persistent_liveness_storage::PersistentLivenessStorage,
+    recorder::{ConsensusRecorder, Record, RecordedEvent, SafetyRulesResponse},
+    round_manager::RoundManager,
+    test_utils::{MockSharedStorage, MockStateComputer, MockStorage, MockTransactionManager},
+    util::mock_time_service::SimulatedTimeService,
+};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use aptos_config::config::ConsensusConfig;
+use aptos_crypto::{ed25519::Ed25519PrivateKey, Uniform};
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_secure_storage::Storage;
+use aptos_types::{
+    epoch_state::EpochState, ledger_info::LedgerInfoWithSignatures,
+    on_chain_config::OnChainConsensusConfig, transaction::SignedTransaction,
+    validator_signer::ValidatorSigner, waypoint::Waypoint,
+};
+use channel::{aptos_channel, message_queues::QueueStyle};
+use consensus_types::common::{Author, Round};
+use futures::{channel::mpsc, FutureExt, StreamExt};
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::network::Event,
+};
+use safety_rules::{PersistentSafetyStorage, SafetyRulesManager};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, ErrorKind, Read},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+#[cfg(test)]
+#[path = "replay_test.rs"]
+mod replay_test;
+
+/// Reads back all the records of a recording file. A record truncated by a crash is dropped.
+pub fn read_recording(path: &Path) -> Result<Vec<Record>> {
+    let file = File::open(path)
+        .with_context(|| format!("Unable to open consensus recording {:?}", path))?;
+    let mut reader = BufReader::new(file);
+    let mut records = vec![];
+    loop {
+        let mut len = [0u8; 4];
+        match reader.read_exact(&mut len) {
+            Ok(()) => (),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+        match reader.read_exact(&mut bytes) {
+            Ok(()) => (),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                warn!("Dropping truncated record at the end of {:?}", path);
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        }
+        records.push(bcs::from_bytes(&bytes).context("Invalid consensus record")?);
+    }
+    Ok(records)
+}
+
+/// The SafetyRules responses that followed an input differ between the recording and the replay.
+#[derive(Debug)]
+pub struct Divergence {
+    /// Index of the input in the recording.
+    pub index: usize,
+    pub input: RecordedEvent,
+    pub recorded: Vec<SafetyRulesResponse>,
+    pub replayed: Vec<SafetyRulesResponse>,
+}
+
+/// The execution results, payloads and timestamps are produced by mocks during the replay, so
+/// only the decisions of SafetyRules are compared: which block is voted for, which parent is
+/// extended by a proposal and which round is timed out.
+fn same_decision(recorded: &SafetyRulesResponse, replayed: &SafetyRulesResponse) -> bool {
+    match (recorded, replayed) {
+        (SafetyRulesResponse::Vote(a), SafetyRulesResponse::Vote(b)) => {
+            a.vote_data().proposed().id() == b.vote_data().proposed().id()
+        }
+        (SafetyRulesResponse::Proposal(a), SafetyRulesResponse::Proposal(b)) => {
+            a.epoch() == b.epoch()
+                && a.round() == b.round()
+                && a.quorum_cert().certified_block().id() == b.quorum_cert().certified_block().id()
+        }
+        (SafetyRulesResponse::Timeout(a), SafetyRulesResponse::Timeout(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// A RoundManager wired to mocks, together with the receiving ends of its mock channels.
+pub struct ReplayNode {
+    round_manager: RoundManager,
+    recorder: Arc<ConsensusRecorder>,
+    self_receiver: channel::Receiver<Event<ConsensusMsg>>,
+    _commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    _state_sync_receiver: mpsc::UnboundedReceiver<Vec<SignedTransaction>>,
+}
+
+impl ReplayNode {
+    /// Starts a RoundManager from the epoch ending `ledger_info`, as the EpochManager does.
+    pub async fn start(
+        signer: &ValidatorSigner,
+        config: &ConsensusConfig,
+        epoch_state: EpochState,
+        onchain_config: OnChainConsensusConfig,
+        ledger_info: LedgerInfoWithSignatures,
+        proposers: HashMap<Round, Author>,
+        time_service: SimulatedTimeService,
+        recorder: Arc<ConsensusRecorder>,
+    ) -> Result<Self> {
+        ensure!(
+            ledger_info.ledger_info().ends_epoch(),
+            "Epoch {} doesn't start from an epoch boundary but from {}",
+            epoch_state.epoch,
+            ledger_info
+        );
+        let author = signer.author();
+        let time_service = Arc::new(time_service);
+
+        let shared_storage = Arc::new(MockSharedStorage::new((&epoch_state.verifier).into()));
+        let storage = Arc::new(MockStorage::new_with_ledger_info(
+            shared_storage,
+            ledger_info.ledger_info().clone(),
+        ));
+        let recovery_data = match storage.start() {
+            LivenessStorageData::RecoveryData(data) => data,
+            LivenessStorageData::LedgerRecoveryData(_) => {
+                bail!(
+                    "Unable to recover the block tree of epoch {}",
+                    epoch_state.epoch
+                )
+            }
+        };
+        let last_vote = recovery_data.last_vote();
+
+        let safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(aptos_secure_storage::InMemoryStorage::new()),
+            author,
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::new_epoch_boundary(ledger_info.ledger_info())?,
+            true,
+        );
+        let safety_rules_manager = SafetyRulesManager::new_local(safety_storage, false, false);
+        let mut safety_rules =
+            MetricsSafetyRules::new(safety_rules_manager.client(), storage.clone());
+        safety_rules
+            .perform_initialize()
+            .map_err(|e| anyhow!("Unable to initialize safety rules: {}", e))?;
+
+        // Nothing is listening on the network, sends and block retrievals fail right away.
+        let (network_reqs_tx, _) = aptos_channel::new(QueueStyle::FIFO, 8, None);
+        let (connection_reqs_tx, _) = aptos_channel::new(QueueStyle::FIFO, 8, None);
+        let network_sender = ConsensusNetworkSender::new(
+            PeerManagerRequestSender::new(network_reqs_tx),
+            ConnectionRequestSender::new(connection_reqs_tx),
+        );
+        let (self_sender, self_receiver) = channel::new_test(1_024);
+        let network = NetworkSender::new(
+            author,
+            network_sender,
+            self_sender,
+            epoch_state.verifier.clone(),
+        );
+
+        let (commit_cb_sender, _commit_cb_receiver) = mpsc::unbounded();
+        let (state_sync_client, _state_sync_receiver) = mpsc::unbounded();
+        let state_computer = Arc::new(MockStateComputer::new(
+            state_sync_client,
+            commit_cb_sender,
+            storage.clone(),
+        ));
+        let block_store = Arc::new(BlockStore::new(
+            storage.clone(),
+            recovery_data,
+            state_computer,
+            config.max_pruned_blocks_in_mem,
+            time_service.clone(),
+            onchain_config.back_pressure_limit(),
+        ));
+
+        let proposal_generator = ProposalGenerator::new(
+            author,
+            block_store.clone(),
+            Arc::new(MockTransactionManager::new(None)),
+            time_service.clone(),
+            config.max_block_size,
+            onchain_config.quorum_store_enabled(),
+            onchain_config.max_failed_authors_to_store(),
+        );
+        // Local timeouts are replayed from the recording, the scheduled ones never fire.
+        let (timeout_sender, _) = channel::new_test(1);
+        let round_state = RoundState::new(
+            Box::new(ExponentialTimeInterval::new(
+                Duration::from_millis(config.round_initial_timeout_ms),
+                1.2,
+                6,
+            )),
+            time_service,
+            timeout_sender,
+        );
+        // The recorded proposals are valid by construction, rounds without a proposal go to
+        // another validator so that the replay doesn't propose in rounds it didn't record.
+        let default_proposer = epoch_state
+            .verifier
+            .get_ordered_account_addresses_iter()
+            .find(|validator| *validator != author)
+            .unwrap_or(author);
+        let proposer_election = Box::new(RoundProposer::new(proposers, default_proposer));
+
+        let mut round_manager = RoundManager::new(
+            epoch_state,
+            block_store,
+            round_state,
+            proposer_election,
+            proposal_generator,
+            Arc::new(Mutex::new(safety_rules)),
+            network,
+            storage,
+            config.sync_only,
+            onchain_config,
+            Some(recorder.clone()),
+            None,
+        );
+        round_manager.init(last_vote).await;
+        Ok(Self {
+            round_manager,
+            recorder,
+            self_receiver,
+            _commit_cb_receiver,
+            _state_sync_receiver,
+        })
+    }
+
+    /// Processes a recorded input, errors are only logged as the RoundManager does.
+    pub async fn process(&mut self, event: RecordedEvent) {
+        let result = match event {
+            RecordedEvent::Message(_, ConsensusMsg::ProposalMsg(proposal_msg)) => {
+                self.round_manager.process_proposal_msg(*proposal_msg).await
+            }
+            RecordedEvent::Message(_, ConsensusMsg::VoteMsg(vote_msg)) => {
+                self.round_manager.process_vote_msg(*vote_msg).await
+            }
+            RecordedEvent::Message(author, ConsensusMsg::SyncInfo(sync_info)) => {
+                self.round_manager
+                    .process_sync_info_msg(*sync_info, author)
+                    .await
+            }
+            RecordedEvent::LocalTimeout(round) => {
+                self.round_manager.process_local_timeout(round).await
+            }
+            event => Err(anyhow!("Unexpected replay input: {:?}", event)),
+        };
+        if let Err(e) = result {
+            debug!(error = ?e, "Replayed input failed");
+        }
+    }
+
+    /// Drains the messages the RoundManager sent to itself.
+    pub fn take_sent_messages(&mut self) -> Vec<ConsensusMsg> {
+        let mut messages = vec![];
+        while let Some(Some(event)) = self.self_receiver.next().now_or_never() {
+            if let Event::Message(_, msg) = event {
+                messages.push(msg);
+            }
+        }
+        messages
+    }
+
+    fn take_safety_rules_responses(&self) -> Vec<SafetyRulesResponse> {
+        self.recorder
+            .take_records()
+            .into_iter()
+            .filter_map(|record| match record.event {
+                RecordedEvent::SafetyRules(response) => Some(response),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Replays the records in order and returns the inputs after which the replayed SafetyRules
+/// responses differ from the recorded ones. A recording with dropped records is rejected, as its
+/// replay would diverge wherever records are missing.
+pub async fn replay(
+    records: Vec<Record>,
+    signer: &ValidatorSigner,
+    config: &ConsensusConfig,
+) -> Result<Vec<Divergence>> {
+    // The proposers of each epoch, taken from the recorded proposals and their failed authors.
+    let mut proposers: HashMap<u64, HashMap<Round, Author>> = HashMap::new();
+    // Every input grouped with the SafetyRules responses recorded right after it.
+    let mut steps: Vec<(usize, Record, Vec<SafetyRulesResponse>)> = vec![];
+    for (index, record) in records.into_iter().enumerate() {
+        match record.event {
+            RecordedEvent::SafetyRules(response) => match steps.last_mut() {
+                Some((_, _, responses)) => responses.push(response),
+                None => bail!("Recording starts with a SafetyRules response"),
+            },
+            RecordedEvent::Gap(dropped) => bail!(
+                "Recording incomplete: {} records were dropped before record {}",
+                dropped,
+                index
+            ),
+            _ => {
+                if let RecordedEvent::Message(_, ConsensusMsg::ProposalMsg(proposal_msg)) =
+                    &record.event
+                {
+                    let block = proposal_msg.proposal();
+                    let epoch_proposers = proposers.entry(block.epoch()).or_default();
+                    if let Some(author) = block.author() {
+                        epoch_proposers.insert(block.round(), author);
+                    }
+                    // Proposers of the rounds that timed out, which the recording node may not
+                    // have received a proposal for.
+                    for (round, author) in block.failed_authors().into_iter().flatten() {
+                        epoch_proposers.insert(*round, *author);
+                    }
+                }
+                steps.push((index, record, vec![]));
+            }
+        }
+    }
+
+    let time_service = SimulatedTimeService::new();
+    let mut node: Option<ReplayNode> = None;
+    let mut divergences = vec![];
+    for (index, record, recorded) in steps {
+        time_service.advance_to(Duration::from_micros(record.timestamp_usecs));
+        let input = record.event.clone();
+        let current = match record.event {
+            RecordedEvent::EpochStart {
+                epoch_state,
+                onchain_config,
+                ledger_info,
+            } => {
+                let epoch_proposers = proposers.remove(&epoch_state.epoch).unwrap_or_default();
+                let recorder =
+                    Arc::new(ConsensusRecorder::in_memory(Arc::new(time_service.clone())));
+                node.insert(
+                    ReplayNode::start(
+                        signer,
+                        config,
+                        epoch_state,
+                        onchain_config,
+                        ledger_info,
+                        epoch_proposers,
+                        time_service.clone(),
+                        recorder,
+                    )
+                    .await?,
+                )
+            }
+            event => {
+                let current = node
+                    .as_mut()
+                    .ok_or_else(|| anyhow!("Recording doesn't start with an epoch start"))?;
+                current.process(event).await;
+                current
+            }
+        };
+        current.take_sent_messages();
+        let replayed = current.take_safety_rules_responses();
+        let diverged = recorded.len() != replayed.len()
+            || recorded
+                .iter()
+                .zip(replayed.iter())
+                .any(|(recorded, replayed)| !same_decision(recorded, replayed));
+        if diverged {
+            divergences.push(Divergence {
+                index,
+                input,
+                recorded,
+                replayed,
+            });
+        }
+    }
+    Ok(divergences)
+}
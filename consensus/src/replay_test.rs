@@ -0,0 +1,415 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    network_interface::ConsensusMsg,
+    recorder::{ConsensusRecorder, Record, RecordedEvent, SafetyRulesResponse},
+    replay::{read_recording, replay, ReplayNode},
+    test_utils::{consensus_runtime, timed_block_on},
+    util::{mock_time_service::SimulatedTimeService, time_service::TimeService},
+};
+use aptos_config::config::ConsensusConfig;
+use aptos_temppath::TempPath;
+use aptos_types::{
+    epoch_state::EpochState,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    on_chain_config::OnChainConsensusConfig,
+    validator_signer::ValidatorSigner,
+    validator_verifier::random_validator_verifier,
+};
+use consensus_types::{
+    common::{Author, Round},
+    vote_msg::VoteMsg,
+};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+async fn deliver(
+    node: &mut ReplayNode,
+    recorder: &ConsensusRecorder,
+    time_service: &SimulatedTimeService,
+    event: RecordedEvent,
+) {
+    time_service.advance_to(time_service.get_current_timestamp() + Duration::from_millis(10));
+    recorder.record(event.clone());
+    node.process(event).await;
+}
+
+/// Records a single validator that proposes and votes for a few rounds and times out once.
+fn record_run(config: &ConsensusConfig) -> (ValidatorSigner, Vec<Record>) {
+    let mut runtime = consensus_runtime();
+    let (signers, verifier) = random_validator_verifier(1, None, false);
+    let signer = &signers[0];
+    let epoch_state = EpochState {
+        epoch: 1,
+        verifier: verifier.clone(),
+    };
+    let ledger_info = LedgerInfoWithSignatures::new(
+        LedgerInfo::mock_genesis(Some((&verifier).into())),
+        BTreeMap::new(),
+    );
+    let time_service = SimulatedTimeService::new();
+    time_service.advance_to(Duration::from_secs(1));
+    let recorder = Arc::new(ConsensusRecorder::in_memory(Arc::new(time_service.clone())));
+    let author = signer.author();
+
+    timed_block_on(&mut runtime, async {
+        recorder.record(RecordedEvent::EpochStart {
+            epoch_state: epoch_state.clone(),
+            onchain_config: OnChainConsensusConfig::default(),
+            ledger_info: ledger_info.clone(),
+        });
+        let mut node = ReplayNode::start(
+            signer,
+            config,
+            epoch_state,
+            OnChainConsensusConfig::default(),
+            ledger_info,
+            HashMap::new(),
+            time_service.clone(),
+            recorder.clone(),
+        )
+        .await
+        .unwrap();
+
+        // the proposals and votes are sent to the validator itself, one round every two steps
+        let mut round = 0;
+        for _ in 0..5 {
+            for msg in node.take_sent_messages() {
+                if let ConsensusMsg::ProposalMsg(proposal_msg) = &msg {
+                    round = proposal_msg.proposal().round();
+                }
+                let event = RecordedEvent::Message(author, msg);
+                deliver(&mut node, &recorder, &time_service, event).await;
+            }
+        }
+        deliver(
+            &mut node,
+            &recorder,
+            &time_service,
+            RecordedEvent::LocalTimeout(round),
+        )
+        .await;
+    });
+    (signer.clone(), recorder.take_records())
+}
+
+/// A validator of a `TestNetwork`, with everything it recorded so far.
+struct TestValidator {
+    signer: ValidatorSigner,
+    node: ReplayNode,
+    recorder: Arc<ConsensusRecorder>,
+    records: Vec<Record>,
+}
+
+/// Validators delivering their messages to each other directly, each one recording its inputs.
+struct TestNetwork {
+    time_service: SimulatedTimeService,
+    proposers: HashMap<Round, Author>,
+    /// Ordered by address.
+    validators: Vec<TestValidator>,
+    /// Messages waiting to be delivered, with the index of their recipient.
+    pending: VecDeque<(usize, RecordedEvent)>,
+}
+
+impl TestNetwork {
+    /// Starts `count` validators, round `r` being led by the validator at index `r % count`.
+    async fn new(count: usize, config: &ConsensusConfig) -> Self {
+        let (mut signers, verifier) = random_validator_verifier(count, None, false);
+        signers.sort_by_key(|signer| signer.author());
+        let epoch_state = EpochState {
+            epoch: 1,
+            verifier: verifier.clone(),
+        };
+        let ledger_info = LedgerInfoWithSignatures::new(
+            LedgerInfo::mock_genesis(Some((&verifier).into())),
+            BTreeMap::new(),
+        );
+        let proposers: HashMap<_, _> = (1..20)
+            .map(|round| (round, signers[round as usize % count].author()))
+            .collect();
+        let time_service = SimulatedTimeService::new();
+        time_service.advance_to(Duration::from_secs(1));
+
+        let mut network = Self {
+            time_service,
+            proposers,
+            validators: vec![],
+            pending: VecDeque::new(),
+        };
+        for signer in signers {
+            let recorder = Arc::new(ConsensusRecorder::in_memory(Arc::new(
+                network.time_service.clone(),
+            )));
+            recorder.record(RecordedEvent::EpochStart {
+                epoch_state: epoch_state.clone(),
+                onchain_config: OnChainConsensusConfig::default(),
+                ledger_info: ledger_info.clone(),
+            });
+            let node = ReplayNode::start(
+                &signer,
+                config,
+                epoch_state.clone(),
+                OnChainConsensusConfig::default(),
+                ledger_info.clone(),
+                network.proposers.clone(),
+                network.time_service.clone(),
+                recorder.clone(),
+            )
+            .await
+            .unwrap();
+            network.validators.push(TestValidator {
+                signer,
+                node,
+                recorder,
+                records: vec![],
+            });
+        }
+        for index in 0..count {
+            network.collect(index, None);
+        }
+        network
+    }
+
+    fn index(&self, author: Author) -> usize {
+        self.validators
+            .iter()
+            .position(|validator| validator.signer.author() == author)
+            .unwrap()
+    }
+
+    async fn deliver(&mut self, index: usize, event: RecordedEvent) {
+        self.time_service
+            .advance_to(self.time_service.get_current_timestamp() + Duration::from_millis(10));
+        let validator = &mut self.validators[index];
+        validator.recorder.record(event.clone());
+        validator.node.process(event.clone()).await;
+        self.collect(index, Some(event));
+    }
+
+    /// Queues the messages the validator sent while processing `input`.
+    fn collect(&mut self, index: usize, input: Option<RecordedEvent>) {
+        let count = self.validators.len();
+        let validator = &mut self.validators[index];
+        let author = validator.signer.author();
+        let records = validator.recorder.take_records();
+
+        // Votes for proposals are sent to the next proposer over the network, which the node
+        // doesn't expose, so they are rebuilt from the SafetyRules responses.
+        if let Some(RecordedEvent::Message(_, ConsensusMsg::ProposalMsg(proposal_msg))) = &input {
+            for record in &records {
+                if let RecordedEvent::SafetyRules(SafetyRulesResponse::Vote(vote)) = &record.event {
+                    let next_proposer = self.proposers[&(vote.vote_data().proposed().round() + 1)];
+                    if next_proposer != author {
+                        let vote_msg = VoteMsg::new(vote.clone(), proposal_msg.sync_info().clone());
+                        let event = RecordedEvent::Message(
+                            author,
+                            ConsensusMsg::VoteMsg(Box::new(vote_msg)),
+                        );
+                        let recipient = self.index(next_proposer);
+                        self.pending.push_back((recipient, event));
+                    }
+                }
+            }
+        }
+        let validator = &mut self.validators[index];
+        validator.records.extend(records);
+
+        // A message the validator sent to itself is either a broadcast, which the other validators
+        // get as well, or a vote for a round it leads.
+        for msg in validator.node.take_sent_messages() {
+            let broadcast = match &msg {
+                ConsensusMsg::ProposalMsg(_) => true,
+                ConsensusMsg::VoteMsg(vote_msg) => vote_msg.vote().is_timeout(),
+                _ => false,
+            };
+            let recipients = if broadcast {
+                0..count
+            } else {
+                index..index + 1
+            };
+            for recipient in recipients {
+                self.pending
+                    .push_back((recipient, RecordedEvent::Message(author, msg.clone())));
+            }
+        }
+    }
+
+    /// Delivers the pending messages until there is none left, `route` may replace or drop
+    /// each of them.
+    async fn run(&mut self, mut route: impl FnMut(usize, RecordedEvent) -> Option<RecordedEvent>) {
+        while let Some((recipient, event)) = self.pending.pop_front() {
+            if let Some(event) = route(recipient, event) {
+                self.deliver(recipient, event).await;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_replay_without_divergence() {
+    let config = ConsensusConfig::default();
+    let (signer, records) = record_run(&config);
+    let responses: Vec<_> = records
+        .iter()
+        .filter_map(|record| match &record.event {
+            RecordedEvent::SafetyRules(response) => Some(response),
+            _ => None,
+        })
+        .collect();
+    assert!(responses
+        .iter()
+        .any(|response| matches!(response, SafetyRulesResponse::Vote(_))));
+    assert!(responses
+        .iter()
+        .any(|response| matches!(response, SafetyRulesResponse::Timeout(_))));
+
+    let mut runtime = consensus_runtime();
+    let divergences = timed_block_on(&mut runtime, replay(records, &signer, &config)).unwrap();
+    assert!(divergences.is_empty(), "{:?}", divergences);
+}
+
+#[test]
+fn test_replay_with_failed_round() {
+    let config = ConsensusConfig::default();
+    let mut runtime = consensus_runtime();
+    let network = timed_block_on(&mut runtime, async {
+        let mut network = TestNetwork::new(4, &config).await;
+        // The proposal of round 2 only reaches its proposer and the validator at index 1, the
+        // others, including the recorded validator at index 0, only learn about the QC of round
+        // 1. Rounds after 4 are not proposed.
+        let route = |recipient: usize, event: RecordedEvent| {
+            let proposal_msg = match &event {
+                RecordedEvent::Message(_, ConsensusMsg::ProposalMsg(proposal_msg)) => {
+                    proposal_msg.clone()
+                }
+                _ => return Some(event),
+            };
+            match proposal_msg.proposal().round() {
+                2 if recipient == 0 || recipient == 3 => Some(RecordedEvent::Message(
+                    proposal_msg.proposer(),
+                    ConsensusMsg::SyncInfo(Box::new(proposal_msg.sync_info().clone())),
+                )),
+                round if round > 4 => None,
+                _ => Some(event),
+            }
+        };
+        network.run(route).await;
+        // Without a QC for round 2, every validator times out and round 3 starts from the
+        // timeout certificate.
+        for index in 0..4 {
+            network.deliver(index, RecordedEvent::LocalTimeout(2)).await;
+        }
+        network.run(route).await;
+        network
+    });
+
+    let validator = &network.validators[0];
+    let failed_proposer = network.proposers[&2];
+    assert!(validator.records.iter().any(|record| matches!(
+        record.event,
+        RecordedEvent::SafetyRules(SafetyRulesResponse::Timeout(2))
+    )));
+    assert!(validator.records.iter().any(|record| match &record.event {
+        RecordedEvent::Message(_, ConsensusMsg::ProposalMsg(proposal_msg)) => {
+            proposal_msg.proposal().round() == 3
+                && proposal_msg.proposal().failed_authors() == Some(&vec![(2, failed_proposer)])
+        }
+        _ => false,
+    }));
+    assert!(validator.records.iter().any(|record| match &record.event {
+        RecordedEvent::SafetyRules(SafetyRulesResponse::Vote(vote)) => {
+            vote.vote_data().proposed().round() == 4
+        }
+        _ => false,
+    }));
+
+    let mut runtime = consensus_runtime();
+    let divergences = timed_block_on(
+        &mut runtime,
+        replay(validator.records.clone(), &validator.signer, &config),
+    )
+    .unwrap();
+    assert!(divergences.is_empty(), "{:?}", divergences);
+}
+
+#[test]
+fn test_replay_detects_divergence() {
+    let config = ConsensusConfig::default();
+    let (signer, mut records) = record_run(&config);
+
+    // pretend the validator didn't vote for the first proposal
+    let position = records
+        .iter()
+        .position(|record| {
+            matches!(
+                record.event,
+                RecordedEvent::SafetyRules(SafetyRulesResponse::Vote(_))
+            )
+        })
+        .unwrap();
+    records.remove(position);
+
+    let mut runtime = consensus_runtime();
+    let divergences = timed_block_on(&mut runtime, replay(records, &signer, &config)).unwrap();
+    assert_eq!(divergences.len(), 1);
+    let divergence = &divergences[0];
+    assert!(matches!(
+        divergence.input,
+        RecordedEvent::Message(_, ConsensusMsg::ProposalMsg(_))
+    ));
+    assert!(divergence.recorded.is_empty());
+    assert!(matches!(
+        divergence.replayed.as_slice(),
+        [SafetyRulesResponse::Vote(_)]
+    ));
+}
+
+#[test]
+fn test_replay_rejects_incomplete_recording() {
+    let config = ConsensusConfig::default();
+    let (signer, mut records) = record_run(&config);
+
+    // the writer fell behind and dropped the records that followed the epoch start
+    let timestamp_usecs = records[1].timestamp_usecs;
+    records.insert(
+        1,
+        Record {
+            timestamp_usecs,
+            event: RecordedEvent::Gap(3),
+        },
+    );
+
+    let mut runtime = consensus_runtime();
+    let error = timed_block_on(&mut runtime, replay(records, &signer, &config)).unwrap_err();
+    assert!(
+        error.to_string().contains("Recording incomplete"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_recording_file_round_trip() {
+    let path = TempPath::new();
+    let time_service = SimulatedTimeService::new();
+    time_service.advance_to(Duration::from_secs(5));
+    let recorder = ConsensusRecorder::new(path.path(), Arc::new(time_service.clone())).unwrap();
+    recorder.record(RecordedEvent::LocalTimeout(1));
+    recorder.record_safety_rules(SafetyRulesResponse::Timeout(1));
+    drop(recorder);
+
+    // a restarted node appends to the same recording
+    let recorder = ConsensusRecorder::new(path.path(), Arc::new(time_service)).unwrap();
+    recorder.record(RecordedEvent::LocalTimeout(2));
+    // dropping the recorder waits for the writer to catch up
+    drop(recorder);
+
+    let records = read_recording(path.path()).unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0].timestamp_usecs, 5_000_000);
+    assert!(matches!(records[2].event, RecordedEvent::LocalTimeout(2)));
+}
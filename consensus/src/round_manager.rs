@@ -8,6 +8,7 @@ use crate::{
     },
     counters,
     error::{error_kind, VerifyError},
+    introspection::{ConsensusIntrospection, RoundStateSnapshot},
     liveness::{
         proposal_generator::ProposalGenerator,
         proposer_election::ProposerElection,
@@ -19,8 +20,10 @@ use crate::{
     network_interface::ConsensusMsg,
     pending_votes::VoteReceptionResult,
     persistent_liveness_storage::PersistentLivenessStorage,
+    recorder::{ConsensusRecorder, SafetyRulesResponse},
 };
 use anyhow::{bail, ensure, Context, Result};
+use aptos_crypto::HashValue;
 use aptos_infallible::{checked, Mutex};
 use aptos_logger::prelude::*;
 use aptos_metrics::monitor;
@@ -32,8 +35,9 @@ use channel::aptos_channel;
 use consensus_types::{
     block::Block,
     block_retrieval::{BlockRetrievalResponse, BlockRetrievalStatus},
-    common::{Author, Round},
+    common::{Author, Payload, Round},
     experimental::{commit_decision::CommitDecision, commit_vote::CommitVote},
+    proof_of_store::{Batch, LogicalTime, ProofOfStore, SignedDigest},
     proposal_msg::ProposalMsg,
     quorum_cert::QuorumCert,
     sync_info::SyncInfo,
@@ -58,10 +62,19 @@ pub enum UnverifiedEvent {
     SyncInfo(Box<SyncInfo>),
     CommitVote(Box<CommitVote>),
     CommitDecision(Box<CommitDecision>),
+    Batch(Box<Batch>),
+    SignedDigest(Box<SignedDigest>),
+    ProofOfStore(Box<ProofOfStore>),
 }
 
 impl UnverifiedEvent {
-    pub fn verify(self, validator: &ValidatorVerifier) -> Result<VerifiedEvent, VerifyError> {
+    /// `max_batch_expiration` bounds how long the batches sent by `peer_id` are stored.
+    pub fn verify(
+        self,
+        peer_id: Author,
+        validator: &ValidatorVerifier,
+        max_batch_expiration: LogicalTime,
+    ) -> Result<VerifiedEvent, VerifyError> {
         Ok(match self {
             UnverifiedEvent::ProposalMsg(p) => {
                 p.verify(validator)?;
@@ -81,6 +94,18 @@ impl UnverifiedEvent {
                 cd.verify(validator)?;
                 VerifiedEvent::CommitDecision(cd)
             }
+            UnverifiedEvent::Batch(b) => {
+                b.verify(peer_id, max_batch_expiration)?;
+                VerifiedEvent::Batch(b)
+            }
+            UnverifiedEvent::SignedDigest(sd) => {
+                sd.verify(validator)?;
+                VerifiedEvent::SignedDigest(sd)
+            }
+            UnverifiedEvent::ProofOfStore(p) => {
+                p.verify(validator)?;
+                VerifiedEvent::ProofOfStore(p)
+            }
         })
     }
 
@@ -91,6 +116,9 @@ impl UnverifiedEvent {
             UnverifiedEvent::SyncInfo(s) => s.epoch(),
             UnverifiedEvent::CommitVote(cv) => cv.epoch(),
             UnverifiedEvent::CommitDecision(cd) => cd.epoch(),
+            UnverifiedEvent::Batch(b) => b.epoch(),
+            UnverifiedEvent::SignedDigest(sd) => sd.epoch(),
+            UnverifiedEvent::ProofOfStore(p) => p.epoch(),
         }
     }
 }
@@ -103,6 +131,9 @@ impl From<ConsensusMsg> for UnverifiedEvent {
             ConsensusMsg::SyncInfo(m) => UnverifiedEvent::SyncInfo(m),
             ConsensusMsg::CommitVoteMsg(m) => UnverifiedEvent::CommitVote(m),
             ConsensusMsg::CommitDecisionMsg(m) => UnverifiedEvent::CommitDecision(m),
+            ConsensusMsg::BatchMsg(m) => UnverifiedEvent::Batch(m),
+            ConsensusMsg::SignedDigestMsg(m) => UnverifiedEvent::SignedDigest(m),
+            ConsensusMsg::ProofOfStoreMsg(m) => UnverifiedEvent::ProofOfStore(m),
             _ => unreachable!("Unexpected conversion"),
         }
     }
@@ -116,6 +147,9 @@ pub enum VerifiedEvent {
     UnverifiedSyncInfo(Box<SyncInfo>),
     CommitVote(Box<CommitVote>),
     CommitDecision(Box<CommitDecision>),
+    Batch(Box<Batch>),
+    SignedDigest(Box<SignedDigest>),
+    ProofOfStore(Box<ProofOfStore>),
     BlockRetrievalRequest(Box<IncomingBlockRetrievalRequest>),
     // local messages
     LocalTimeout(Round),
@@ -146,6 +180,12 @@ pub struct RoundManager {
     storage: Arc<dyn PersistentLivenessStorage>,
     sync_only: bool,
     onchain_config: OnChainConsensusConfig,
+    recorder: Option<Arc<ConsensusRecorder>>,
+    introspection: Option<ConsensusIntrospection>,
+    // the leader of the current round, recorded when the round starts
+    current_round_leader: Option<Author>,
+    // the round, vote sent and (votes, timeout votes) counts last published
+    published_round_state: Option<(Round, Option<HashValue>, (usize, usize))>,
 }
 
 impl RoundManager {
@@ -160,6 +200,8 @@ impl RoundManager {
         storage: Arc<dyn PersistentLivenessStorage>,
         sync_only: bool,
         onchain_config: OnChainConsensusConfig,
+        recorder: Option<Arc<ConsensusRecorder>>,
+        introspection: Option<ConsensusIntrospection>,
     ) -> Self {
         // when decoupled execution is false,
         // the counter is still static.
@@ -183,6 +225,10 @@ impl RoundManager {
             storage,
             sync_only,
             onchain_config,
+            recorder,
+            introspection,
+            current_round_leader: None,
+            published_round_state: None,
         }
     }
 
@@ -202,6 +248,12 @@ impl RoundManager {
         BlockRetriever::new(self.network.clone(), author)
     }
 
+    fn record_safety_rules(&self, response: SafetyRulesResponse) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record_safety_rules(response);
+        }
+    }
+
     /// Leader:
     ///
     /// This event is triggered by a new quorum certificate at the previous round or a
@@ -232,10 +284,11 @@ impl RoundManager {
             self.new_log(LogEvent::NewRound),
             reason = new_round_event.reason
         );
-        if self
+        let leader = self
             .proposer_election
-            .is_valid_proposer(self.proposal_generator.author(), new_round_event.round)
-        {
+            .get_valid_proposer(new_round_event.round);
+        self.current_round_leader = Some(leader);
+        if leader == self.proposal_generator.author() {
             let proposal_msg = Box::new(self.generate_proposal(new_round_event).await?);
             let mut network = self.network.clone();
             #[cfg(feature = "failpoints")]
@@ -266,9 +319,14 @@ impl RoundManager {
         .boxed();
         let proposal = self
             .proposal_generator
-            .generate_proposal(new_round_event.round, callback)
+            .generate_proposal(
+                new_round_event.round,
+                self.proposer_election.as_ref(),
+                callback,
+            )
             .await?;
         let signature = self.safety_rules.lock().sign_proposal(&proposal)?;
+        self.record_safety_rules(SafetyRulesResponse::Proposal(proposal.clone()));
         let signed_proposal =
             Block::new_proposal_from_block_data_and_signature(proposal, signature);
         observe_block(signed_proposal.timestamp_usecs(), BlockStage::SIGNED);
@@ -458,7 +516,9 @@ impl RoundManager {
             Some(vote) if vote.vote_data().proposed().round() == round => (true, vote),
             _ => {
                 // Didn't vote in this round yet, generate a backup vote
-                let nil_block = self.proposal_generator.generate_nil_block(round)?;
+                let nil_block = self
+                    .proposal_generator
+                    .generate_nil_block(round, self.proposer_election.as_ref())?;
                 debug!(
                     self.new_log(LogEvent::VoteNIL),
                     "Planning to vote for a NIL block {}", nil_block
@@ -492,6 +552,7 @@ impl RoundManager {
                     .context("[RoundManager] SafetyRules signs timeout")?;
                 timeout_vote.add_timeout_signature(signature);
             }
+            self.record_safety_rules(SafetyRulesResponse::Timeout(round));
         }
 
         self.round_state.record_vote(timeout_vote.clone());
@@ -542,6 +603,42 @@ impl RoundManager {
             proposal,
         );
 
+        // The failed authors are recorded on chain for leader reputation, so they must be the
+        // ones this validator elected for the skipped rounds.
+        let expected_failed_authors = self.proposal_generator.compute_failed_authors(
+            proposal.round(),
+            proposal.quorum_cert().certified_block().round(),
+            false,
+            self.proposer_election.as_ref(),
+        );
+        ensure!(
+            proposal.failed_authors() == Some(&expected_failed_authors),
+            "[RoundManager] Proposal for block {} has invalid failed_authors list {:?}, expected {:?}",
+            proposal,
+            proposal.failed_authors(),
+            expected_failed_authors,
+        );
+
+        if let Some(payload) = proposal.payload() {
+            payload
+                .verify(
+                    &self.epoch_state.verifier,
+                    self.onchain_config.quorum_store_enabled(),
+                )
+                .context("[RoundManager] Invalid proposal payload")?;
+            if let Payload::InQuorumStore(proofs) = payload {
+                let parent_time = LogicalTime::new(
+                    self.epoch_state.epoch,
+                    proposal.quorum_cert().certified_block().round(),
+                );
+                ensure!(
+                    proofs.iter().all(|proof| proof.expiration() > parent_time),
+                    "[RoundManager] Proposal {} includes an expired proof of store",
+                    proposal,
+                );
+            }
+        }
+
         let block_time_since_epoch = Duration::from_micros(proposal.timestamp_usecs());
 
         ensure!(
@@ -613,6 +710,7 @@ impl RoundManager {
             Fg(Reset),
             executed_block.block()
         ))?;
+        self.record_safety_rules(SafetyRulesResponse::Vote(vote.clone()));
         observe_block(executed_block.block().timestamp_usecs(), BlockStage::VOTED);
 
         self.storage
@@ -820,6 +918,32 @@ impl RoundManager {
         &self.round_state
     }
 
+    /// Publishes the current round, its leader and its votes for the debug interface, only when
+    /// they changed since the last time they were published.
+    fn publish_introspection(&mut self) {
+        if let Some(introspection) = &self.introspection {
+            let current_round = self.round_state.current_round();
+            let pending_votes = self.round_state.pending_votes();
+            let vote_sent = self
+                .round_state
+                .vote_sent()
+                .map(|vote| vote.vote_data().proposed().id());
+            let state = (current_round, vote_sent, pending_votes.num_votes());
+            if self.published_round_state == Some(state) {
+                return;
+            }
+            introspection.publish_round_state(RoundStateSnapshot::new(
+                current_round,
+                self.round_state.current_round_deadline().as_millis() as u64,
+                self.current_round_leader,
+                vote_sent,
+                pending_votes.vote_tallies(&self.epoch_state.verifier),
+                pending_votes.timeout_voters(),
+            ));
+            self.published_round_state = Some(state);
+        }
+    }
+
     fn new_log(&self, event: LogEvent) -> LogSchema {
         LogSchema::new(event)
             .round(self.round_state.current_round())
@@ -835,7 +959,11 @@ impl RoundManager {
         >,
     ) {
         info!(epoch = self.epoch_state().epoch, "RoundManager started");
+        self.publish_introspection();
         while let Some((peer_id, event)) = event_rx.next().await {
+            if let Some(recorder) = &self.recorder {
+                recorder.record_input(peer_id, &event);
+            }
             let result = match event {
                 VerifiedEvent::ProposalMsg(proposal_msg) => {
                     monitor!(
@@ -880,6 +1008,7 @@ impl RoundManager {
                     error!(error = ?e, kind = error_kind(&e), RoundStateLogSchema::new(round_state));
                 }
             }
+            self.publish_introspection();
         }
         info!(epoch = self.epoch_state().epoch, "RoundManager stopped");
     }
@@ -145,6 +145,8 @@ fn create_node_for_fuzzing() -> RoundManager {
         Arc::new(MockTransactionManager::new(None)),
         time_service,
         1,
+        false,
+        10,
     );
 
     //
@@ -168,6 +170,8 @@ fn create_node_for_fuzzing() -> RoundManager {
         storage,
         false,
         OnChainConsensusConfig::default(),
+        None,
+        None,
     )
 }
 
@@ -29,6 +29,7 @@ use aptos_types::{
     epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     on_chain_config::OnChainConsensusConfig,
+    transaction::SignedTransaction,
     validator_signer::ValidatorSigner,
     validator_verifier::random_validator_verifier,
     waypoint::Waypoint,
@@ -39,6 +40,7 @@ use consensus_types::{
         block_test_utils::{certificate_for_genesis, gen_test_certificate},
         Block,
     },
+    block_data::BlockData,
     block_retrieval::{BlockRetrievalRequest, BlockRetrievalStatus},
     common::{Author, Payload},
     proposal_msg::ProposalMsg,
@@ -76,7 +78,7 @@ pub struct NodeSetup {
     safety_rules_manager: SafetyRulesManager,
     all_events: Box<dyn Stream<Item = Event<ConsensusMsg>> + Send + Unpin>,
     commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
-    _state_sync_receiver: mpsc::UnboundedReceiver<Payload>,
+    _state_sync_receiver: mpsc::UnboundedReceiver<Vec<SignedTransaction>>,
     id: usize,
 }
 
@@ -204,6 +206,8 @@ impl NodeSetup {
             Arc::new(MockTransactionManager::new(None)),
             time_service.clone(),
             1,
+            false,
+            10,
         );
 
         let round_state = Self::create_round_state(time_service);
@@ -223,6 +227,8 @@ impl NodeSetup {
             storage.clone(),
             false,
             OnChainConsensusConfig::default(),
+            None,
+            None,
         );
         block_on(round_manager.init(last_vote_sent));
         Self {
@@ -343,7 +349,13 @@ fn vote_on_successful_proposal() {
         // Start round 1 and clear the message queue
         node.next_proposal().await;
 
-        let proposal = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
+        let proposal = Block::new_proposal(
+            Payload::empty(false),
+            1,
+            1,
+            genesis_qc.clone(),
+            &node.signer,
+        );
         let proposal_id = proposal.id();
         node.round_manager.process_proposal(proposal).await.unwrap();
         let vote_msg = node.next_vote().await;
@@ -368,9 +380,15 @@ fn no_vote_on_old_proposal() {
     let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 1);
     let node = &mut nodes[0];
     let genesis_qc = certificate_for_genesis();
-    let new_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
+    let new_block = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+    );
     let new_block_id = new_block.id();
-    let old_block = Block::new_proposal(vec![], 1, 2, genesis_qc, &node.signer);
+    let old_block = Block::new_proposal(Payload::empty(false), 1, 2, genesis_qc, &node.signer);
     let old_block_id = old_block.id();
     timed_block_on(&mut runtime, async {
         // clear the message queue
@@ -404,8 +422,20 @@ fn no_vote_on_mismatch_round() {
         .pop()
         .unwrap();
     let genesis_qc = certificate_for_genesis();
-    let correct_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
-    let block_skip_round = Block::new_proposal(vec![], 2, 2, genesis_qc.clone(), &node.signer);
+    let correct_block = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+    );
+    let block_skip_round = Block::new_proposal(
+        Payload::empty(false),
+        2,
+        2,
+        genesis_qc.clone(),
+        &node.signer,
+    );
     timed_block_on(&mut runtime, async {
         let bad_proposal = ProposalMsg::new(
             block_skip_round,
@@ -485,9 +515,20 @@ fn no_vote_on_invalid_proposer() {
     let incorrect_proposer = nodes.pop().unwrap();
     let mut node = nodes.pop().unwrap();
     let genesis_qc = certificate_for_genesis();
-    let correct_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
-    let block_incorrect_proposer =
-        Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &incorrect_proposer.signer);
+    let correct_block = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+    );
+    let block_incorrect_proposer = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &incorrect_proposer.signer,
+    );
     timed_block_on(&mut runtime, async {
         let bad_proposal = ProposalMsg::new(
             block_incorrect_proposer,
@@ -521,8 +562,32 @@ fn new_round_on_timeout_certificate() {
         .pop()
         .unwrap();
     let genesis_qc = certificate_for_genesis();
-    let correct_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
-    let block_skip_round = Block::new_proposal(vec![], 2, 2, genesis_qc.clone(), &node.signer);
+    let correct_block = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+    );
+    // the proposal of round 2 has to record the proposer of round 1 as failed
+    let block_skip_round_without_failed_author = Block::new_proposal(
+        Payload::empty(false),
+        2,
+        2,
+        genesis_qc.clone(),
+        &node.signer,
+    );
+    let block_skip_round = Block::new_proposal_from_block_data(
+        BlockData::new_proposal_ext(
+            Payload::empty(false),
+            node.signer.author(),
+            vec![(1, node.signer.author())],
+            2,
+            2,
+            genesis_qc.clone(),
+        ),
+        &node.signer,
+    );
     let timeout = Timeout::new(1, 1);
     let timeout_signature = timeout.sign(&node.signer);
 
@@ -530,6 +595,20 @@ fn new_round_on_timeout_certificate() {
     tc.add_signature(node.signer.author(), timeout_signature);
 
     timed_block_on(&mut runtime, async {
+        let missing_failed_author_proposal = ProposalMsg::new(
+            block_skip_round_without_failed_author,
+            SyncInfo::new(
+                genesis_qc.clone(),
+                genesis_qc.clone(),
+                Some(tc.clone()),
+                None,
+            ),
+        );
+        assert!(node
+            .round_manager
+            .process_proposal_msg(missing_failed_author_proposal)
+            .await
+            .is_err());
         let skip_round_proposal = ProposalMsg::new(
             block_skip_round,
             SyncInfo::new(genesis_qc.clone(), genesis_qc.clone(), Some(tc), None),
@@ -559,7 +638,13 @@ fn response_on_block_retrieval() {
         .unwrap();
 
     let genesis_qc = certificate_for_genesis();
-    let block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
+    let block = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+    );
     let block_id = block.id();
     let proposal = ProposalMsg::new(
         block,
@@ -663,7 +748,8 @@ fn recover_on_restart() {
     let num_proposals = 100;
     // insert a few successful proposals
     for i in 1..=num_proposals {
-        let proposal = inserter.create_block_with_qc(genesis_qc.clone(), i, i, vec![]);
+        let proposal =
+            inserter.create_block_with_qc(genesis_qc.clone(), i, i, Payload::empty(false));
         let timeout = Timeout::new(1, i - 1);
         let mut tc = TimeoutCertificate::new(timeout.clone());
         tc.add_signature(inserter.signer().author(), inserter.signer().sign(&timeout));
@@ -773,7 +859,7 @@ fn sync_info_sent_on_stale_sync_info() {
     let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 2);
     runtime.spawn(playground.start());
     let genesis_qc = certificate_for_genesis();
-    let block_0 = Block::new_proposal(vec![], 1, 1, genesis_qc, &nodes[0].signer);
+    let block_0 = Block::new_proposal(Payload::empty(false), 1, 1, genesis_qc, &nodes[0].signer);
     let parent_block_info = block_0.quorum_cert().certified_block();
     let block_0_quorum_cert = gen_test_certificate(
         vec![&nodes[0].signer, &nodes[1].signer],
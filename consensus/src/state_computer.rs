@@ -4,6 +4,7 @@
 use crate::{
     counters,
     error::StateSyncError,
+    quorum_store::payload_manager::PayloadManager,
     state_replication::{StateComputer, StateComputerCommitCallBackType, TxnManager},
 };
 use anyhow::Result;
@@ -34,6 +35,7 @@ pub struct ExecutionProxy {
     mempool_notifier: Arc<dyn TxnManager>,
     state_sync_notifier: Arc<dyn ConsensusNotificationSender>,
     async_state_sync_notifier: channel::Sender<NotificationType>,
+    payload_manager: Arc<PayloadManager>,
 }
 
 impl ExecutionProxy {
@@ -41,6 +43,7 @@ impl ExecutionProxy {
         execution_correctness_client: Box<dyn ExecutionCorrectness + Send + Sync>,
         mempool_notifier: Arc<dyn TxnManager>,
         state_sync_notifier: Arc<dyn ConsensusNotificationSender>,
+        payload_manager: Arc<PayloadManager>,
         handle: &tokio::runtime::Handle,
     ) -> Self {
         let (tx, mut rx) =
@@ -63,6 +66,7 @@ impl ExecutionProxy {
             mempool_notifier,
             state_sync_notifier,
             async_state_sync_notifier: tx,
+            payload_manager,
         }
     }
 }
@@ -87,11 +91,22 @@ impl StateComputer for ExecutionProxy {
             "Executing block",
         );
 
+        let user_txns = self
+            .payload_manager
+            .get_transactions(block)
+            .await
+            .map_err(|e| ExecutionError::InternalError {
+                error: format!("Failed to resolve payload of block {}: {}", block.id(), e),
+            })?;
+
         // TODO: figure out error handling for the prologue txn
         let compute_result = monitor!(
             "execute_block",
-            self.execution_correctness_client
-                .execute_block(block.clone(), parent_block_id)
+            self.execution_correctness_client.execute_block(
+                block.clone(),
+                user_txns,
+                parent_block_id
+            )
         )?;
 
         // notify mempool about failed transaction
@@ -120,7 +135,14 @@ impl StateComputer for ExecutionProxy {
 
         for block in blocks {
             block_ids.push(block.id());
-            txns.extend(block.transactions_to_commit());
+            let user_txns = self
+                .payload_manager
+                .get_transactions(block.block())
+                .await
+                .map_err(|e| ExecutionError::InternalError {
+                    error: format!("Failed to resolve payload of block {}: {}", block.id(), e),
+                })?;
+            txns.extend(block.transactions_to_commit_with_payload(user_txns));
             reconfig_events.extend(block.reconfig_event());
         }
 
@@ -129,6 +151,7 @@ impl StateComputer for ExecutionProxy {
             self.execution_correctness_client
                 .commit_blocks(block_ids, finality_proof.clone())?
         );
+        self.payload_manager.notify_commit(blocks);
 
         let blocks = blocks.to_vec();
         let wrapped_callback = move || {
@@ -10,33 +10,55 @@ use anyhow::{format_err, Result};
 use aptos_crypto::HashValue;
 use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
-use aptos_types::ledger_info::LedgerInfoWithSignatures;
+use aptos_types::{
+    block_info::BlockInfo, ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction,
+};
 use consensus_types::{block::Block, common::Payload, executed_block::ExecutedBlock};
 use executor_types::{Error, StateComputeResult};
 use futures::channel::mpsc;
 use std::{collections::HashMap, sync::Arc};
 use termion::color::*;
 
+/// The blocks a `MockStateComputer` moved its ledger to, in the order of the commits.
+#[derive(Clone, Debug)]
+pub enum CommittedBlocks {
+    /// Blocks committed by consensus, they extend the previously committed block.
+    Ordered(Vec<BlockInfo>),
+    /// Block the ledger was synced to, the blocks in between are unknown.
+    Synced(BlockInfo),
+}
+
 pub struct MockStateComputer {
-    state_sync_client: mpsc::UnboundedSender<Payload>,
+    state_sync_client: mpsc::UnboundedSender<Vec<SignedTransaction>>,
     commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>,
+    committed_blocks: Option<mpsc::UnboundedSender<CommittedBlocks>>,
     consensus_db: Arc<MockStorage>,
-    block_cache: Mutex<HashMap<HashValue, Payload>>,
+    block_cache: Mutex<HashMap<HashValue, Vec<SignedTransaction>>>,
 }
 
 impl MockStateComputer {
     pub fn new(
-        state_sync_client: mpsc::UnboundedSender<Payload>,
+        state_sync_client: mpsc::UnboundedSender<Vec<SignedTransaction>>,
         commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>,
         consensus_db: Arc<MockStorage>,
     ) -> Self {
         MockStateComputer {
             state_sync_client,
             commit_callback,
+            committed_blocks: None,
             consensus_db,
             block_cache: Mutex::new(HashMap::new()),
         }
     }
+
+    /// Also reports the committed blocks, not only the ledger infos, to check the chains.
+    pub fn with_committed_blocks(
+        mut self,
+        committed_blocks: mpsc::UnboundedSender<CommittedBlocks>,
+    ) -> Self {
+        self.committed_blocks = Some(committed_blocks);
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -46,9 +68,11 @@ impl StateComputer for MockStateComputer {
         block: &Block,
         _parent_block_id: HashValue,
     ) -> Result<StateComputeResult, Error> {
-        self.block_cache
-            .lock()
-            .insert(block.id(), block.payload().unwrap_or(&vec![]).clone());
+        let txns = match block.payload() {
+            Some(Payload::DirectMempool(txns)) => txns.clone(),
+            _ => vec![],
+        };
+        self.block_cache.lock().insert(block.id(), txns);
         let result = StateComputeResult::new_dummy();
         Ok(result)
     }
@@ -76,6 +100,11 @@ impl StateComputer for MockStateComputer {
         let _ = self.state_sync_client.unbounded_send(txns);
 
         let _ = self.commit_callback.unbounded_send(commit.clone());
+        if let Some(committed_blocks) = &self.committed_blocks {
+            let _ = committed_blocks.unbounded_send(CommittedBlocks::Ordered(
+                blocks.iter().map(|block| block.block_info()).collect(),
+            ));
+        }
 
         call_back(blocks, commit);
 
@@ -91,6 +120,11 @@ impl StateComputer for MockStateComputer {
         );
         self.consensus_db
             .commit_to_storage(commit.ledger_info().clone());
+        if let Some(committed_blocks) = &self.committed_blocks {
+            let _ = committed_blocks.unbounded_send(CommittedBlocks::Synced(
+                commit.ledger_info().commit_info().clone(),
+            ));
+        }
         self.commit_callback
             .unbounded_send(commit)
             .expect("Fail to notify about sync");
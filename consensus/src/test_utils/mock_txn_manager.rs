@@ -27,7 +27,7 @@ impl MockTransactionManager {
     pub fn new(consensus_to_mempool_sender: Option<mpsc::Sender<ConsensusRequest>>) -> Self {
         let mempool_proxy = consensus_to_mempool_sender.map(|s| MempoolProxy::new(s, 1, 1, 1));
         Self {
-            rejected_txns: vec![],
+            rejected_txns: Payload::empty(false),
             mempool_proxy,
         }
     }
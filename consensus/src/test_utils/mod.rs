@@ -24,7 +24,7 @@ use crate::util::mock_time_service::SimulatedTimeService;
 use aptos_types::block_info::BlockInfo;
 use consensus_types::{block::block_test_utils::gen_test_certificate, common::Payload};
 pub use mock_state_computer::{
-    EmptyStateComputer, MockStateComputer, RandomComputeResultStateComputer,
+    CommittedBlocks, EmptyStateComputer, MockStateComputer, RandomComputeResultStateComputer,
 };
 pub use mock_storage::{EmptyStorage, MockSharedStorage, MockStorage};
 pub use mock_txn_manager::MockTransactionManager;
@@ -135,7 +135,7 @@ impl TreeInserter {
                 parent_qc,
                 parent.timestamp_usecs() + 1,
                 round,
-                vec![],
+                Payload::empty(false),
             ))
             .await
             .unwrap()
@@ -0,0 +1,235 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    network_tests::NetworkPlayground,
+    test_utils::{consensus_runtime, timed_block_on, CommittedBlocks},
+    twins::{
+        scenario_generator::{
+            all_partitions, check_safety, SafetyViolation, Scenario, ScenarioConfig,
+        },
+        twins_node::SMRNode,
+    },
+    util::mock_time_service::SimulatedTimeService,
+};
+use aptos_config::config::{ConsensusConfig, ConsensusProposerType::RoundProposer};
+use aptos_crypto::HashValue;
+use aptos_types::block_info::BlockInfo;
+use consensus_types::common::Round;
+use std::{collections::HashMap, env, time::Duration};
+
+/// Seed of the first scenario unless `TWINS_FIRST_SEED` is set, so CI runs the same scenarios.
+const DEFAULT_FIRST_SEED: u64 = 0;
+/// Simulated time after which the instances are stopped, whether they made progress or not.
+const MAX_SIMULATED_TIME: Duration = Duration::from_secs(60);
+/// Real time the instances get to exchange messages before the simulated time moves forward.
+const SETTLE_DURATION: Duration = Duration::from_millis(100);
+
+/// Round of the last block an instance committed, 0 if it committed nothing.
+fn committed_round(commits: &[CommittedBlocks]) -> Round {
+    match commits.last() {
+        Some(CommittedBlocks::Ordered(blocks)) => blocks.last().map_or(0, BlockInfo::round),
+        Some(CommittedBlocks::Synced(block)) => block.round(),
+        None => 0,
+    }
+}
+
+/// Runs the scenario with `SMRNode`s sharing a simulated time and checks the committed chains of
+/// all the instances. The rounds only time out when the test advances the time, which it does
+/// until every instance committed past the scenario rounds or the time limit is reached.
+fn run_scenario(scenario: &Scenario) -> Result<(), SafetyViolation> {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut time_service = SimulatedTimeService::new();
+    let mut nodes = SMRNode::start_simulated_nodes_with_twins_of(
+        scenario.num_nodes,
+        &scenario.twins,
+        &mut playground,
+        RoundProposer(HashMap::new()),
+        Some(scenario.round_proposers()),
+        time_service.clone(),
+    );
+    let ids: Vec<_> = nodes.iter().map(|node| node.id).collect();
+    assert!(playground.split_network_round(&scenario.round_partitions(&ids)));
+    runtime.spawn(playground.start());
+
+    let last_round = scenario.rounds.keys().max().copied().unwrap_or(0);
+    let time_step = Duration::from_millis(ConsensusConfig::default().round_initial_timeout_ms);
+    let mut now = Duration::from_secs(0);
+    let mut commits: Vec<_> = nodes.iter().map(|node| (node.id.id, vec![])).collect();
+    loop {
+        timed_block_on(&mut runtime, tokio::time::sleep(SETTLE_DURATION));
+        for (node, (_, node_commits)) in nodes.iter_mut().zip(commits.iter_mut()) {
+            while let Ok(Some(committed)) = node.committed_blocks_receiver.try_next() {
+                node_commits.push(committed);
+            }
+        }
+        let done = commits
+            .iter()
+            .all(|(_, node_commits)| committed_round(node_commits) > last_round);
+        if done || now >= MAX_SIMULATED_TIME {
+            break;
+        }
+        now += time_step;
+        time_service.advance_to(now);
+        time_service.update_auto_advance_limit(time_step);
+    }
+    check_safety(&commits)
+}
+
+/// Runs the scenario and panics with the seed of the minimized scenario if it's unsafe.
+fn check_scenario(scenario: Scenario) {
+    if let Err(violation) = run_scenario(&scenario) {
+        let minimized = scenario.minimize(|candidate| run_scenario(candidate).is_err());
+        panic!(
+            "[TwinsTest] Safety violation {:?}, reproduce with {}",
+            violation, minimized
+        );
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+#[test]
+/// This test checks the safety of consensus under randomly generated Twins scenarios.
+///
+/// Setup:
+///
+/// 4 nodes and 1 twin placed on a random node, a random leader and random
+/// partitions of the 5 instances in each of the first 10 rounds.
+///
+/// Test:
+///
+/// Check that the committed chains of all the instances are prefixes of one another.
+/// A failing scenario is minimized and reported with the environment variables
+/// that reproduce it in `twins_reproduce_test`.
+///
+/// Run the test:
+/// TWINS_NUM_SCENARIOS=100 cargo xtest -p consensus random_twins_test -- --nocapture
+fn random_twins_test() {
+    let config = ScenarioConfig::default();
+    let num_scenarios: u64 = env_or("TWINS_NUM_SCENARIOS", 2);
+    let first_seed: u64 = env_or("TWINS_FIRST_SEED", DEFAULT_FIRST_SEED);
+    for seed in first_seed..first_seed.saturating_add(num_scenarios) {
+        // printed before running, so the seed is in the captured output of a failure
+        println!("[TwinsTest] Running scenario TWINS_SEED={}", seed);
+        check_scenario(Scenario::generate(&config, seed));
+    }
+}
+
+#[test]
+#[ignore]
+/// Reproduces a scenario reported by `random_twins_test`.
+///
+/// Run the test:
+/// TWINS_SEED=<seed> TWINS_CONNECTED_ROUNDS=<rounds> cargo xtest -p consensus twins_reproduce_test -- --ignored --nocapture
+fn twins_reproduce_test() {
+    let seed: u64 = env_or("TWINS_SEED", 0);
+    let connected_rounds: Vec<u64> = env::var("TWINS_CONNECTED_ROUNDS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|round| round.parse().ok())
+        .collect();
+    let scenario = Scenario::generate(&ScenarioConfig::default(), seed)
+        .with_connected_rounds(connected_rounds);
+    if let Err(violation) = run_scenario(&scenario) {
+        panic!("[TwinsTest] Safety violation {:?}", violation);
+    }
+}
+
+#[test]
+fn test_all_partitions() {
+    // Bell numbers
+    assert_eq!(all_partitions(4, 4).len(), 15);
+    assert_eq!(all_partitions(5, 5).len(), 52);
+    // Stirling numbers of the second kind S(5, 1) + S(5, 2)
+    let partitions = all_partitions(5, 2);
+    assert_eq!(partitions.len(), 16);
+    for partition in partitions {
+        let mut nodes: Vec<_> = partition.into_iter().flatten().collect();
+        nodes.sort_unstable();
+        assert_eq!(nodes, vec![0, 1, 2, 3, 4]);
+    }
+}
+
+#[test]
+fn test_generate_is_reproducible() {
+    let config = ScenarioConfig {
+        num_nodes: 7,
+        num_twins: 2,
+        num_rounds: 20,
+        max_partitions: 3,
+    };
+    let scenario = Scenario::generate(&config, 42);
+    assert_eq!(scenario, Scenario::generate(&config, 42));
+    assert_eq!(scenario.twins.len(), 2);
+    assert_eq!(scenario.rounds.len(), 20);
+    for round_config in scenario.rounds.values() {
+        assert!(round_config.leader < config.num_nodes);
+        assert!(round_config.partitions.len() <= 3);
+        let num_instances: usize = round_config.partitions.iter().map(Vec::len).sum();
+        assert_eq!(num_instances, config.num_instances());
+    }
+}
+
+#[test]
+fn test_minimize() {
+    let scenario = Scenario::generate(&ScenarioConfig::default(), 7);
+    let partitioned: Vec<_> = scenario
+        .rounds
+        .iter()
+        .filter(|(_, round_config)| round_config.partitions.len() > 1)
+        .map(|(round, _)| *round)
+        .collect();
+    let culprit = *partitioned.iter().min().unwrap();
+    // the scenario fails as long as the culprit round is partitioned
+    let minimized = scenario.minimize(|candidate| candidate.rounds[&culprit].partitions.len() > 1);
+    assert_eq!(minimized.connected_rounds.len(), partitioned.len() - 1);
+    assert!(!minimized.connected_rounds.contains(&culprit));
+    assert_eq!(
+        Scenario::generate(&ScenarioConfig::default(), 7)
+            .with_connected_rounds(minimized.connected_rounds.clone()),
+        minimized
+    );
+}
+
+#[test]
+fn test_check_safety() {
+    let block = |round, id| BlockInfo::new(1, round, id, HashValue::zero(), 0, 0, None);
+    let (a, b, c) = (
+        HashValue::random(),
+        HashValue::random(),
+        HashValue::random(),
+    );
+    let ordered = |blocks: &[(Round, HashValue)]| {
+        CommittedBlocks::Ordered(blocks.iter().map(|(r, id)| block(*r, *id)).collect())
+    };
+    assert!(check_safety(&[
+        (0, vec![ordered(&[(1, a)])]),
+        (1, vec![ordered(&[(1, a)]), ordered(&[(3, b)])]),
+        // a sync skips the unknown rounds
+        (2, vec![CommittedBlocks::Synced(block(3, b))]),
+    ])
+    .is_ok());
+
+    // different blocks at the same round
+    let violation =
+        check_safety(&[(0, vec![ordered(&[(1, a)])]), (4, vec![ordered(&[(1, b)])])]).unwrap_err();
+    assert_eq!(violation.first.0, 0);
+    assert_eq!(violation.second.0, 4);
+
+    // a fork: one chain skips the round 2 the other committed, they agree on the later round
+    let violation = check_safety(&[
+        (0, vec![ordered(&[(1, a), (3, c)])]),
+        (1, vec![ordered(&[(1, a), (2, b)]), ordered(&[(3, c)])]),
+    ])
+    .unwrap_err();
+    assert_eq!(violation.round, 2);
+    assert_eq!(violation.first, (0, None));
+    assert_eq!(violation.second, (1, Some(b)));
+}
@@ -0,0 +1,271 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generates Twins scenarios: which validators get a twin, who leads each round and how the
+//! network is partitioned in each round. A scenario is fully determined by its config and seed,
+//! so a failing scenario can be reproduced and minimized from the seed alone.
+
+use crate::test_utils::CommittedBlocks;
+use aptos_crypto::HashValue;
+use consensus_types::common::Round;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+};
+
+/// The shape of the scenarios to generate.
+#[derive(Clone, Debug)]
+pub struct ScenarioConfig {
+    pub num_nodes: usize,
+    pub num_twins: usize,
+    /// Number of rounds with a leader and partitions, later rounds are fully connected and led
+    /// by the first node.
+    pub num_rounds: Round,
+    /// Maximum number of partitions in a round.
+    pub max_partitions: usize,
+}
+
+impl ScenarioConfig {
+    /// Number of nodes in the network, counting the twins.
+    pub fn num_instances(&self) -> usize {
+        self.num_nodes + self.num_twins
+    }
+}
+
+impl Default for ScenarioConfig {
+    fn default() -> Self {
+        Self {
+            num_nodes: 4,
+            num_twins: 1,
+            num_rounds: 10,
+            max_partitions: 2,
+        }
+    }
+}
+
+/// The leader and the partitions of a round, by index of the node instances: the nodes first,
+/// then the twins in the order of `Scenario::twins`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundConfig {
+    pub leader: usize,
+    pub partitions: Vec<Vec<usize>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scenario {
+    pub num_nodes: usize,
+    pub seed: u64,
+    /// The nodes that have a twin.
+    pub twins: Vec<usize>,
+    pub rounds: HashMap<Round, RoundConfig>,
+    /// The rounds whose partitions were removed while minimizing the scenario.
+    pub connected_rounds: BTreeSet<Round>,
+}
+
+impl Scenario {
+    /// Samples a scenario: the twin placement, then a leader and a set partition of all the
+    /// node instances for every round.
+    pub fn generate(config: &ScenarioConfig, seed: u64) -> Self {
+        assert!(
+            config.num_twins <= (config.num_nodes - 1) / 3,
+            "More twins than tolerated faults"
+        );
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut nodes: Vec<usize> = (0..config.num_nodes).collect();
+        nodes.shuffle(&mut rng);
+        let mut twins = nodes[..config.num_twins].to_vec();
+        twins.sort_unstable();
+
+        let partitions = all_partitions(config.num_instances(), config.max_partitions);
+        let rounds = (1..=config.num_rounds)
+            .map(|round| {
+                let round_config = RoundConfig {
+                    leader: rng.gen_range(0..config.num_nodes),
+                    partitions: partitions.choose(&mut rng).unwrap().clone(),
+                };
+                (round, round_config)
+            })
+            .collect();
+        Self {
+            num_nodes: config.num_nodes,
+            seed,
+            twins,
+            rounds,
+            connected_rounds: BTreeSet::new(),
+        }
+    }
+
+    /// Removes the partitions of the given rounds.
+    pub fn with_connected_rounds(mut self, rounds: impl IntoIterator<Item = Round>) -> Self {
+        for round in rounds {
+            if let Some(round_config) = self.rounds.get_mut(&round) {
+                round_config.partitions = vec![(0..self.num_instances()).collect()];
+                self.connected_rounds.insert(round);
+            }
+        }
+        self
+    }
+
+    pub fn num_instances(&self) -> usize {
+        self.num_nodes + self.twins.len()
+    }
+
+    /// The leader of every round, to be used with the `RoundProposer`.
+    pub fn round_proposers(&self) -> HashMap<Round, usize> {
+        self.rounds
+            .iter()
+            .map(|(round, round_config)| (*round, round_config.leader))
+            .collect()
+    }
+
+    /// The partitions of every partitioned round, mapped to the ids of the running instances.
+    pub fn round_partitions<T: Copy>(&self, ids: &[T]) -> HashMap<Round, Vec<Vec<T>>> {
+        assert_eq!(ids.len(), self.num_instances());
+        self.rounds
+            .iter()
+            .filter(|(_, round_config)| round_config.partitions.len() > 1)
+            .map(|(round, round_config)| {
+                let partitions = round_config
+                    .partitions
+                    .iter()
+                    .map(|partition| partition.iter().map(|idx| ids[*idx]).collect())
+                    .collect();
+                (*round, partitions)
+            })
+            .collect()
+    }
+
+    /// Shrinks a failing scenario: connects the network in each round as long as the scenario
+    /// keeps failing, so that only the partitions needed for the failure remain.
+    pub fn minimize(self, mut fails: impl FnMut(&Scenario) -> bool) -> Self {
+        let mut rounds: Vec<_> = self
+            .rounds
+            .iter()
+            .filter(|(_, round_config)| round_config.partitions.len() > 1)
+            .map(|(round, _)| *round)
+            .collect();
+        rounds.sort_unstable();
+        let mut minimized = self;
+        for round in rounds {
+            let candidate = minimized.clone().with_connected_rounds(vec![round]);
+            if fails(&candidate) {
+                minimized = candidate;
+            }
+        }
+        minimized
+    }
+}
+
+impl fmt::Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "TWINS_SEED={} TWINS_CONNECTED_ROUNDS={}",
+            self.seed,
+            self.connected_rounds
+                .iter()
+                .map(|round| round.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    }
+}
+
+/// Enumerates the partitions of `0..n` into at most `max_parts` non-empty sets, in the
+/// restricted growth order so that every partition appears exactly once.
+pub fn all_partitions(n: usize, max_parts: usize) -> Vec<Vec<Vec<usize>>> {
+    fn extend(
+        next: usize,
+        n: usize,
+        max_parts: usize,
+        current: &mut Vec<Vec<usize>>,
+        result: &mut Vec<Vec<Vec<usize>>>,
+    ) {
+        if next == n {
+            result.push(current.clone());
+            return;
+        }
+        for part in 0..current.len() {
+            current[part].push(next);
+            extend(next + 1, n, max_parts, current, result);
+            current[part].pop();
+        }
+        if current.len() < max_parts {
+            current.push(vec![next]);
+            extend(next + 1, n, max_parts, current, result);
+            current.pop();
+        }
+    }
+    let mut result = vec![];
+    extend(0, n, max_parts, &mut vec![], &mut result);
+    result
+}
+
+/// Two node instances disagree on the block committed at an epoch and round, `None` when the
+/// instance committed no block at that round because its chain skips it.
+#[derive(Debug)]
+pub struct SafetyViolation {
+    pub epoch: u64,
+    pub round: Round,
+    pub first: (usize, Option<HashValue>),
+    pub second: (usize, Option<HashValue>),
+}
+
+/// The committed chain of an instance: the block committed at each known epoch and round. The
+/// rounds a commit skips between two consecutive blocks are known to have no committed block,
+/// the rounds jumped over by a sync are unknown.
+fn committed_chain(commits: &[CommittedBlocks]) -> BTreeMap<(u64, Round), Option<HashValue>> {
+    let mut chain = BTreeMap::new();
+    let mut tip: Option<(u64, Round)> = None;
+    for committed in commits {
+        match committed {
+            CommittedBlocks::Ordered(blocks) => {
+                for block in blocks {
+                    // the first commit extends the genesis at round 0
+                    let (tip_epoch, tip_round) = tip.unwrap_or((block.epoch(), 0));
+                    if tip_epoch == block.epoch() {
+                        for round in tip_round + 1..block.round() {
+                            chain.insert((block.epoch(), round), None);
+                        }
+                    }
+                    chain.insert((block.epoch(), block.round()), Some(block.id()));
+                    tip = Some((block.epoch(), block.round()));
+                }
+            }
+            CommittedBlocks::Synced(block) => {
+                chain.insert((block.epoch(), block.round()), Some(block.id()));
+                tip = Some((block.epoch(), block.round()));
+            }
+        }
+    }
+    chain
+}
+
+/// Checks that the committed chains of all the node instances are consistent: wherever two
+/// instances both know what was committed at an epoch and round, they committed the same block
+/// or both skipped the round, so one chain is a prefix of the other.
+pub fn check_safety(commits: &[(usize, Vec<CommittedBlocks>)]) -> Result<(), SafetyViolation> {
+    let chains: Vec<_> = commits
+        .iter()
+        .map(|(id, node_commits)| (*id, committed_chain(node_commits)))
+        .collect();
+    for (i, (first_id, first)) in chains.iter().enumerate() {
+        for (second_id, second) in &chains[i + 1..] {
+            for (key, block) in first {
+                match second.get(key) {
+                    Some(other) if other != block => {
+                        return Err(SafetyViolation {
+                            epoch: key.0,
+                            round: key.1,
+                            first: (*first_id, *block),
+                            second: (*second_id, *other),
+                        });
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -4,11 +4,16 @@
 use crate::{
     counters,
     epoch_manager::EpochManager,
+    introspection::ConsensusIntrospection,
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
     network_tests::{NetworkPlayground, TwinId},
-    test_utils::{MockStateComputer, MockStorage, MockTransactionManager},
-    util::time_service::ClockTimeService,
+    quorum_store::batch_store::BatchStore,
+    test_utils::{CommittedBlocks, MockStateComputer, MockStorage, MockTransactionManager},
+    util::{
+        mock_time_service::SimulatedTimeService,
+        time_service::{ClockTimeService, TimeService},
+    },
 };
 use aptos_config::{
     config::{
@@ -22,11 +27,12 @@ use aptos_mempool::mocks::MockSharedMempool;
 use aptos_types::{
     ledger_info::LedgerInfoWithSignatures,
     on_chain_config::{OnChainConfig, OnChainConfigPayload, ValidatorSet},
+    transaction::SignedTransaction,
     validator_info::ValidatorInfo,
     waypoint::Waypoint,
 };
 use channel::{self, aptos_channel, message_queues::QueueStyle};
-use consensus_types::common::{Author, Payload, Round};
+use consensus_types::common::{Author, Round};
 use event_notifications::{ReconfigNotification, ReconfigNotificationListener};
 use futures::channel::mpsc;
 use network::{
@@ -46,9 +52,10 @@ pub struct SMRNode {
     pub id: TwinId,
     pub storage: Arc<MockStorage>,
     pub commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    pub committed_blocks_receiver: mpsc::UnboundedReceiver<CommittedBlocks>,
     _runtime: Runtime,
     _shared_mempool: MockSharedMempool,
-    _state_sync: mpsc::UnboundedReceiver<Payload>,
+    _state_sync: mpsc::UnboundedReceiver<Vec<SignedTransaction>>,
 }
 
 fn author_from_config(config: &NodeConfig) -> Author {
@@ -61,6 +68,7 @@ impl SMRNode {
         config: NodeConfig,
         storage: Arc<MockStorage>,
         twin_id: TwinId,
+        simulated_time: Option<SimulatedTimeService>,
     ) -> Self {
         let (network_reqs_tx, network_reqs_rx) = aptos_channel::new(QueueStyle::FIFO, 8, None);
         let (connection_reqs_tx, _) = aptos_channel::new(QueueStyle::FIFO, 8, None);
@@ -78,13 +86,13 @@ impl SMRNode {
 
         let (state_sync_client, state_sync) = mpsc::unbounded();
         let (commit_cb_sender, commit_cb_receiver) = mpsc::unbounded::<LedgerInfoWithSignatures>();
+        let (committed_blocks_sender, committed_blocks_receiver) = mpsc::unbounded();
         let shared_mempool = MockSharedMempool::new();
         let consensus_to_mempool_sender = shared_mempool.consensus_sender.clone();
-        let state_computer = Arc::new(MockStateComputer::new(
-            state_sync_client,
-            commit_cb_sender,
-            Arc::clone(&storage),
-        ));
+        let state_computer = Arc::new(
+            MockStateComputer::new(state_sync_client, commit_cb_sender, Arc::clone(&storage))
+                .with_committed_blocks(committed_blocks_sender),
+        );
         let txn_manager = Arc::new(MockTransactionManager::new(Some(
             consensus_to_mempool_sender,
         )));
@@ -118,7 +126,10 @@ impl SMRNode {
             .build()
             .unwrap();
 
-        let time_service = Arc::new(ClockTimeService::new(runtime.handle().clone()));
+        let time_service: Arc<dyn TimeService> = match simulated_time {
+            Some(time_service) => Arc::new(time_service),
+            None => Arc::new(ClockTimeService::new(runtime.handle().clone())),
+        };
 
         let (timeout_sender, timeout_receiver) =
             channel::new(1_024, &counters::PENDING_ROUND_TIMEOUTS);
@@ -134,6 +145,8 @@ impl SMRNode {
             state_computer,
             storage.clone(),
             reconfig_listener,
+            Arc::new(BatchStore::new(config.consensus.quorum_store)),
+            ConsensusIntrospection::default(),
         );
         let (network_task, network_receiver) = NetworkTask::new(network_events, self_receiver);
 
@@ -143,6 +156,7 @@ impl SMRNode {
             id: twin_id,
             _runtime: runtime,
             commit_cb_receiver,
+            committed_blocks_receiver,
             storage,
             _shared_mempool: shared_mempool,
             _state_sync: state_sync,
@@ -158,6 +172,64 @@ impl SMRNode {
         round_proposers_idx: Option<HashMap<Round, usize>>,
     ) -> Vec<Self> {
         assert!(num_nodes >= num_twins);
+        let twins: Vec<_> = (0..num_twins).collect();
+        Self::start_nodes_with_twins_of(
+            num_nodes,
+            &twins,
+            playground,
+            proposer_type,
+            round_proposers_idx,
+        )
+    }
+
+    /// Starts a given number of nodes and a twin for each of the given node indices, the twins
+    /// come after the nodes in the returned order.
+    pub fn start_nodes_with_twins_of(
+        num_nodes: usize,
+        twins: &[usize],
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        round_proposers_idx: Option<HashMap<Round, usize>>,
+    ) -> Vec<Self> {
+        Self::start_nodes(
+            num_nodes,
+            twins,
+            playground,
+            proposer_type,
+            round_proposers_idx,
+            None,
+        )
+    }
+
+    /// Same as `start_nodes_with_twins_of`, but all the instances share the given simulated
+    /// time, so round timeouts only fire when the test advances it.
+    pub fn start_simulated_nodes_with_twins_of(
+        num_nodes: usize,
+        twins: &[usize],
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        round_proposers_idx: Option<HashMap<Round, usize>>,
+        time_service: SimulatedTimeService,
+    ) -> Vec<Self> {
+        Self::start_nodes(
+            num_nodes,
+            twins,
+            playground,
+            proposer_type,
+            round_proposers_idx,
+            Some(time_service),
+        )
+    }
+
+    fn start_nodes(
+        num_nodes: usize,
+        twins: &[usize],
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        round_proposers_idx: Option<HashMap<Round, usize>>,
+        simulated_time: Option<SimulatedTimeService>,
+    ) -> Vec<Self> {
+        assert!(twins.iter().all(|idx| *idx < num_nodes));
         let ValidatorSwarm {
             nodes: mut node_configs,
         } = generator::validator_swarm_for_testing(num_nodes);
@@ -205,8 +277,8 @@ impl SMRNode {
         // We don't add twins to ValidatorSet or round_proposers above
         // because a node with twins should be treated the same at the
         // consensus level
-        for i in 0..num_twins {
-            let twin = node_configs[i].clone();
+        for i in twins {
+            let twin = node_configs[*i].clone();
             node_configs.push(twin);
         }
 
@@ -227,14 +299,23 @@ impl SMRNode {
             config.base.waypoint = WaypointConfig::FromConfig(waypoint);
             config.consensus.proposer_type = proposer_type.clone();
             config.consensus.safety_rules.verify_vote_proposal_signature = false;
-            // Disable timeout in twins test to avoid flakiness
-            config.consensus.round_initial_timeout_ms = 2_000_000;
+            // Disable timeout in twins test to avoid flakiness, simulated time only times out
+            // rounds when the test advances it
+            if simulated_time.is_none() {
+                config.consensus.round_initial_timeout_ms = 2_000_000;
+            }
 
             let author = author_from_config(&config);
 
             let twin_id = TwinId { id: smr_id, author };
 
-            smr_nodes.push(Self::start(playground, config, storage, twin_id));
+            smr_nodes.push(Self::start(
+                playground,
+                config,
+                storage,
+                twin_id,
+                simulated_time.clone(),
+            ));
         }
         smr_nodes
     }
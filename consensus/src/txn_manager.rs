@@ -98,7 +98,12 @@ impl TxnManager for MempoolProxy {
         });
         let mut exclude_txns = vec![];
         for payload in exclude_payloads {
-            for transaction in payload {
+            // quorum store payloads are never pulled from mempool directly
+            let txns = match payload {
+                Payload::DirectMempool(txns) => txns,
+                Payload::InQuorumStore(_) => continue,
+            };
+            for transaction in txns {
                 exclude_txns.push(TransactionSummary {
                     sender: transaction.sender(),
                     sequence_number: transaction.sequence_number(),
@@ -134,8 +139,8 @@ impl TxnManager for MempoolProxy {
     ) -> Result<(), MempoolError> {
         let mut rejected_txns = vec![];
         let txns = match block.payload() {
-            Some(txns) => txns,
-            None => return Ok(()),
+            Some(Payload::DirectMempool(txns)) => txns,
+            _ => return Ok(()),
         };
         // skip the block metadata txn result
         for (txn, status) in txns
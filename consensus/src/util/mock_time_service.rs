@@ -118,6 +118,15 @@ impl SimulatedTimeService {
             futures::executor::block_on(t.run());
         }
     }
+
+    /// Moves the current time forward to the given timestamp, it never goes backwards.
+    /// Pending tasks are not run, this is used to replay recorded timestamps.
+    pub fn advance_to(&self, now: Duration) {
+        let mut inner = self.inner.lock();
+        if now > inner.now {
+            inner.now = std::cmp::min(now, inner.max);
+        }
+    }
 }
 
 impl Clone for SimulatedTimeService {
@@ -186,6 +186,9 @@ pub struct Bucket {
     key: String,
     /// The current number of available tokens to be used
     tokens: usize,
+    /// Tokens used beyond the available ones, to be repaid by the refills before any more
+    /// tokens are available
+    debt: usize,
     /// Maximum number of `tokens` in the bucket
     size: usize,
     /// The fill rate of the bucket (`tokens/s`).  Amount added to `tokens` on a `refill`
@@ -221,6 +224,7 @@ impl Bucket {
             log_info,
             key,
             tokens: initial,
+            debt: 0,
             size,
             rate,
             last_refresh_time: Instant::now(),
@@ -238,6 +242,7 @@ impl Bucket {
             log_info: String::new(),
             key: String::new(),
             tokens: std::usize::MAX,
+            debt: 0,
             size: std::usize::MAX,
             rate: std::usize::MAX,
             last_refresh_time: Instant::now(),
@@ -312,6 +317,31 @@ impl Bucket {
         }
     }
 
+    /// Acquires all the requested tokens as long as some are available, going into debt for
+    /// the missing ones.  No tokens are available until the debt is repaid by the refills,
+    /// which allows batches larger than the bucket while keeping the fill rate.  On failure,
+    /// returns the time of the next refill.
+    pub fn acquire_tokens_with_debt(&mut self, requested: usize) -> Result<(), Instant> {
+        // Skip over if we purposely have an open throttle
+        if !self.enabled || requested == 0 {
+            return Ok(());
+        }
+
+        // Refill if needed
+        self.refill();
+
+        if self.tokens > 0 {
+            self.debt = requested.saturating_sub(self.tokens);
+            self.deduct_tokens(requested);
+            self.allowed_in_period = self.allowed_in_period.saturating_add(requested);
+            Ok(())
+        } else {
+            // Keep track of the requests we've throttled
+            self.throttled_in_period = self.throttled_in_period.saturating_add(requested);
+            Err(self.time_of_next_refill())
+        }
+    }
+
     /// Returns `usize` of tokens allowed.  May be less than requested.
     /// For best effort, caller should return unused tokens with `add_tokens`
     pub fn acquire_tokens(&mut self, requested: usize) -> Result<usize, Instant> {
@@ -364,10 +394,12 @@ impl Bucket {
         }
     }
 
-    /// Add new tokens
+    /// Add new tokens, after repaying any debt
     /// Ensures bucket doesn't overfill
     fn add_tokens(&mut self, new_tokens: usize) {
-        self.tokens = min(self.size, self.tokens.saturating_add(new_tokens));
+        let repaid = min(self.debt, new_tokens);
+        self.debt -= repaid;
+        self.tokens = min(self.size, self.tokens.saturating_add(new_tokens - repaid));
     }
 
     /// Returns tokens that were unused
@@ -450,6 +482,39 @@ mod tests {
         result.expect("Should be successful");
     }
 
+    #[test]
+    fn test_rate_limiting_with_debt() {
+        let bucket_size = 5;
+        let bucket_rate = 3;
+        let key = "Key";
+        let rate_limiter = TokenBucketRateLimiter::test(bucket_size, bucket_rate);
+
+        let bucket_arc = rate_limiter.bucket(key);
+        let mut bucket = bucket_arc.lock();
+
+        // Larger than bucket, succeeds while there are tokens
+        bucket
+            .acquire_tokens_with_debt(bucket_size + 4)
+            .expect("Should be successful");
+        let next_refill = bucket
+            .acquire_tokens_with_debt(1)
+            .expect_err("Should not succeed until the debt is repaid");
+
+        // The first refill only repays the debt
+        sleep(next_refill.duration_since(Instant::now()));
+        bucket.refill();
+        assert_eq!(0, bucket.tokens);
+        assert_eq!(1, bucket.debt);
+
+        // Returned tokens repay the debt first
+        bucket.return_tokens(2);
+        assert_eq!(1, bucket.tokens);
+        assert_eq!(0, bucket.debt);
+        bucket
+            .acquire_tokens_with_debt(1)
+            .expect("Should be successful");
+    }
+
     #[test]
     fn test_refill() {
         let bucket_size = 5;
@@ -2,15 +2,24 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{anyhow, Result};
+use aptos_api_types::mime_types::BCS;
 pub use aptos_api_types::{MoveModuleBytecode, PendingTransaction, Transaction};
 use aptos_crypto::HashValue;
-use aptos_types::{account_address::AccountAddress, transaction::SignedTransaction};
+use aptos_types::{
+    account_address::AccountAddress,
+    contract_event::EventWithProof,
+    event::EventKey,
+    transaction::{SignedTransaction, Transaction as CommittedTransaction, Version},
+};
 use move_core_types::{
     ident_str,
     identifier::Identifier,
     language_storage::{StructTag, CORE_CODE_ADDRESS},
 };
-use reqwest::{header::CONTENT_TYPE, Client as ReqwestClient, StatusCode};
+use reqwest::{
+    header::{ACCEPT, CONTENT_TYPE},
+    Client as ReqwestClient, StatusCode,
+};
 use serde::{de::DeserializeOwned, Deserialize};
 use std::time::Duration;
 use url::Url;
@@ -276,6 +285,77 @@ impl Client {
         Ok(Response::new(blob, state))
     }
 
+    /// Returns the account state blob at `version`, or `None` if the account doesn't exist at
+    /// that version.
+    pub async fn get_account_state_blob_by_version(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<Option<Vec<u8>>> {
+        let url = self.base_url.join(&format!("accounts/{}/blob", address))?;
+
+        let response = self
+            .inner
+            .get(url)
+            .query(&[("version", version)])
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            let error_response = response.json::<RestError>().await?;
+            if error_response.message.starts_with("account not found") {
+                return Ok(None);
+            }
+            return Err(anyhow!("Request failed: {:?}", error_response));
+        }
+        let (response, _state) = self.check_response(response).await?;
+        Ok(Some(response.json().await?))
+    }
+
+    /// Returns the committed transactions in their on-chain format.
+    pub async fn get_transactions_bcs(
+        &self,
+        start: Option<u64>,
+        limit: Option<u64>,
+    ) -> Result<Response<Vec<CommittedTransaction>>> {
+        let url = self.base_url.join("transactions")?;
+
+        let mut request = self.inner.get(url).header(ACCEPT, BCS);
+        if let Some(start) = start {
+            request = request.query(&[("start", start)])
+        }
+
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)])
+        }
+
+        let response = request.send().await?;
+
+        self.bcs(response).await
+    }
+
+    /// Returns the events of `key` with their proofs, starting from sequence number `start`.
+    pub async fn get_events_bcs(
+        &self,
+        key: &EventKey,
+        start: Option<u64>,
+        limit: Option<u64>,
+    ) -> Result<Response<Vec<EventWithProof>>> {
+        let url = self.base_url.join(&format!("events/{:#x}", key))?;
+
+        let mut request = self.inner.get(url).header(ACCEPT, BCS);
+        if let Some(start) = start {
+            request = request.query(&[("start", start)])
+        }
+
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)])
+        }
+
+        let response = request.send().await?;
+
+        self.bcs(response).await
+    }
+
     pub async fn get_account_resources(
         &self,
         address: AccountAddress,
@@ -387,6 +467,15 @@ impl Client {
         Ok(Response::new(json, state))
     }
 
+    async fn bcs<T: serde::de::DeserializeOwned>(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<Response<T>> {
+        let (response, state) = self.check_response(response).await?;
+        let bytes = response.bytes().await?;
+        Ok(Response::new(bcs::from_bytes(&bytes)?, state))
+    }
+
     pub async fn health_check(&self, seconds: u64) -> Result<()> {
         let url = self.base_url.join("-/healthy")?;
         let response = self
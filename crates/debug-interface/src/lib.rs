@@ -78,6 +78,19 @@ impl NodeDebugClient {
             })
             .collect()
     }
+
+    /// Retrieves the snapshot of the live state of a component, e.g. "consensus".
+    pub fn get_snapshot(&self, component: &str) -> Result<serde_json::Value> {
+        let mut url = self.url.clone();
+        url.set_path(&format!("snapshot/{}", component));
+        let response = self.client.get(url).send()?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Error querying snapshot: {}", response.status());
+        }
+
+        Ok(response.json()?)
+    }
 }
 
 /// Implement default utility client for AsyncNodeDebugInterface
@@ -153,4 +166,63 @@ impl AsyncNodeDebugClient {
             })
             .collect()
     }
+
+    /// Retrieves the snapshot of the live state of a component, e.g. "consensus".
+    pub async fn get_snapshot(&self, component: &str) -> Result<serde_json::Value> {
+        let mut url = self.url.clone();
+        url.set_path(&format!("snapshot/{}", component));
+        let response = self.client.get(url).send().await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Error querying snapshot: {}", response.status());
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node_debug_service::NodeDebugService;
+    use aptos_config::{config::NodeConfig, utils};
+    use std::{
+        net::{IpAddr, Ipv4Addr, SocketAddr},
+        sync::Arc,
+        thread::sleep,
+        time::Duration,
+    };
+
+    #[test]
+    fn test_get_snapshot() {
+        let port = utils::get_available_port();
+        let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+        let service = NodeDebugService::new(address, None, &NodeConfig::default());
+        service.register_snapshot(
+            "consensus",
+            Arc::new(|| serde_json::json!({ "epoch": 2, "round_state": { "current_round": 5 } })),
+        );
+
+        // the server is bound in the background, retry until it is up
+        let client = AsyncNodeDebugClient::new(reqwest::Client::new(), "127.0.0.1", port);
+        let mut attempts = 0;
+        let snapshot = loop {
+            match service.runtime().block_on(client.get_snapshot("consensus")) {
+                Ok(snapshot) => break snapshot,
+                Err(_) if attempts < 50 => {
+                    attempts += 1;
+                    sleep(Duration::from_millis(100));
+                }
+                Err(e) => panic!("Unable to get the snapshot: {}", e),
+            }
+        };
+        assert_eq!(snapshot["epoch"], 2);
+        assert_eq!(snapshot["round_state"]["current_round"], 5);
+
+        // unknown components are not found
+        assert!(service
+            .runtime()
+            .block_on(client.get_snapshot("mempool"))
+            .is_err());
+    }
 }
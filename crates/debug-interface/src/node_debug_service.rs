@@ -4,16 +4,29 @@
 //! Debug interface to access information in a specific node.
 
 use aptos_config::config::NodeConfig;
+use aptos_infallible::RwLock;
 use aptos_logger::{info, Filter, Logger};
 use aptos_metrics::json_metrics::get_git_rev;
 use serde::{Deserialize, Serialize};
-use std::{net::SocketAddr, sync::Arc};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 use tokio::runtime::{Builder, Runtime};
-use warp::Filter as _;
+use warp::{http::StatusCode, Filter as _};
+
+/// Produces a JSON snapshot of the live state of a component of the node.
+pub type SnapshotProvider = Arc<dyn Fn() -> serde_json::Value + Send + Sync>;
 
-#[derive(Debug)]
 pub struct NodeDebugService {
     runtime: Runtime,
+    snapshots: Arc<RwLock<HashMap<String, SnapshotProvider>>>,
+}
+
+impl std::fmt::Debug for NodeDebugService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeDebugService")
+            .field("runtime", &self.runtime)
+            .field("snapshots", &self.snapshots.read().keys())
+            .finish()
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -80,16 +93,41 @@ impl NodeDebugService {
         };
         let node_info_route = warp::path("node-info").map(move || warp::reply::json(&node_info));
 
-        let routes = log.or(warp::get().and(metrics.or(node_info_route)));
+        // Get /snapshot/<component> (the live state of a component registered after startup)
+        let snapshots: Arc<RwLock<HashMap<String, SnapshotProvider>>> = Arc::default();
+        let snapshot_route = {
+            let snapshots = snapshots.clone();
+            warp::path!("snapshot" / String).map(move |component: String| {
+                let provider = snapshots.read().get(&component).cloned();
+                match provider {
+                    Some(provider) => {
+                        warp::reply::with_status(warp::reply::json(&provider()), StatusCode::OK)
+                    }
+                    None => warp::reply::with_status(
+                        warp::reply::json(&format!("Unknown component {}", component)),
+                        StatusCode::NOT_FOUND,
+                    ),
+                }
+            })
+        };
+
+        let routes = log.or(warp::get().and(metrics.or(node_info_route).or(snapshot_route)));
 
         runtime
             .handle()
             .spawn(async move { warp::serve(routes).bind(address).await });
 
-        Self { runtime }
+        Self { runtime, snapshots }
     }
 
     pub fn runtime(&self) -> &Runtime {
         &self.runtime
     }
+
+    /// Serves the snapshots of the given component at /snapshot/<component>.
+    pub fn register_snapshot(&self, component: &str, provider: SnapshotProvider) {
+        self.snapshots
+            .write()
+            .insert(component.to_string(), provider);
+    }
 }
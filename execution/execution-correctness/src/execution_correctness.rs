@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aptos_crypto::HashValue;
-use aptos_types::ledger_info::LedgerInfoWithSignatures;
+use aptos_types::{ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction};
 use consensus_types::block::Block;
 use executor_types::{Error, StateComputeResult};
 
@@ -13,10 +13,12 @@ pub trait ExecutionCorrectness: Send {
 
     fn reset(&self) -> Result<(), Error>;
 
-    /// Executes a block.
+    /// Executes a block. `user_txns` is the resolved payload of the block, which may differ from
+    /// the inline payload when the block references quorum store batches.
     fn execute_block(
         &self,
         block: Block,
+        user_txns: Vec<SignedTransaction>,
         parent_block_id: HashValue,
     ) -> Result<StateComputeResult, Error>;
 
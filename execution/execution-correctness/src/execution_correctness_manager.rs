@@ -15,7 +15,10 @@ use aptos_global_constants::EXECUTION_KEY;
 use aptos_secure_storage::{CryptoStorage, Storage};
 
 use aptos_vm::AptosVM;
-use executor::block_executor::BlockExecutor;
+use executor::{
+    block_executor::BlockExecutor,
+    components::{execution_observer::ExecutionObserver, speculation_cache::SpeculationCache},
+};
 use std::{convert::TryInto, net::SocketAddr, sync::Arc};
 use storage_client::StorageClient;
 use storage_interface::DbReaderWriter;
@@ -58,6 +61,16 @@ pub struct ExecutionCorrectnessManager {
 
 impl ExecutionCorrectnessManager {
     pub fn new(config: &NodeConfig, local_db: DbReaderWriter) -> Self {
+        Self::new_with_speculation_cache(config, local_db, None)
+    }
+
+    /// Like `new`, a local block executor additionally reuses pre-executed transaction outputs
+    /// from `speculation_cache`.
+    pub fn new_with_speculation_cache(
+        config: &NodeConfig,
+        local_db: DbReaderWriter,
+        speculation_cache: Option<Arc<SpeculationCache>>,
+    ) -> Self {
         if let ExecutionCorrectnessService::Process(remote_service) = &config.execution.service {
             return Self::new_process(
                 remote_service.server_address,
@@ -69,7 +82,26 @@ impl ExecutionCorrectnessManager {
         let storage_address = config.storage.address;
         let timeout_ms = config.storage.timeout_ms;
         match &config.execution.service {
-            ExecutionCorrectnessService::Local => Self::new_local(local_db, execution_prikey),
+            ExecutionCorrectnessService::Local => {
+                let mut block_executor = match speculation_cache {
+                    Some(speculation_cache) => {
+                        BlockExecutor::<AptosVM>::new_with_speculation_cache(
+                            local_db,
+                            speculation_cache,
+                        )
+                    }
+                    None => BlockExecutor::<AptosVM>::new(local_db),
+                };
+                let observer_config = &config.execution.observer;
+                if observer_config.enabled {
+                    block_executor =
+                        block_executor.with_execution_observer(Arc::new(ExecutionObserver::new(
+                            observer_config.max_recorded_blocks,
+                            observer_config.dump_dir.clone(),
+                        )));
+                }
+                Self::new_local_with_executor(Box::new(block_executor), execution_prikey)
+            }
             ExecutionCorrectnessService::Serializer => {
                 Self::new_serializer(storage_address, execution_prikey, timeout_ms)
             }
@@ -84,7 +116,16 @@ impl ExecutionCorrectnessManager {
     }
 
     pub fn new_local(db: DbReaderWriter, execution_prikey: Option<Ed25519PrivateKey>) -> Self {
-        let block_executor = Box::new(BlockExecutor::<AptosVM>::new(db));
+        Self::new_local_with_executor(
+            Box::new(BlockExecutor::<AptosVM>::new(db)),
+            execution_prikey,
+        )
+    }
+
+    fn new_local_with_executor(
+        block_executor: Box<BlockExecutor<AptosVM>>,
+        execution_prikey: Option<Ed25519PrivateKey>,
+    ) -> Self {
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Local(Arc::new(
                 LocalService::new(block_executor, execution_prikey),
@@ -3,7 +3,7 @@
 
 use crate::execution_correctness::ExecutionCorrectness;
 use aptos_crypto::{ed25519::Ed25519PrivateKey, traits::SigningKey, HashValue};
-use aptos_types::ledger_info::LedgerInfoWithSignatures;
+use aptos_types::{ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction};
 use consensus_types::{block::Block, vote_proposal::VoteProposal};
 use executor_types::{BlockExecutorTrait, Error, StateComputeResult};
 use std::{boxed::Box, sync::Arc};
@@ -50,11 +50,15 @@ impl ExecutionCorrectness for LocalClient {
     fn execute_block(
         &self,
         block: Block,
+        user_txns: Vec<SignedTransaction>,
         parent_block_id: HashValue,
     ) -> Result<StateComputeResult, Error> {
         let local = &self.internal;
         let mut result = local.block_executor.execute_block(
-            (block.id(), block.transactions_to_execute()),
+            (
+                block.id(),
+                block.transactions_to_execute_with_payload(user_txns),
+            ),
             parent_block_id,
         )?;
         if let Some(prikey) = local.prikey.as_ref() {
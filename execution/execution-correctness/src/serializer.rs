@@ -3,7 +3,7 @@
 
 use crate::execution_correctness::ExecutionCorrectness;
 use aptos_crypto::{ed25519::Ed25519PrivateKey, traits::SigningKey, HashValue};
-use aptos_types::ledger_info::LedgerInfoWithSignatures;
+use aptos_types::{ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction};
 use consensus_types::{block::Block, vote_proposal::VoteProposal};
 use executor_types::{BlockExecutorTrait, Error, StateComputeResult};
 use serde::{Deserialize, Serialize};
@@ -13,7 +13,7 @@ use std::sync::Arc;
 pub enum ExecutionCorrectnessInput {
     CommittedBlockId,
     Reset,
-    ExecuteBlock(Box<(Block, HashValue)>),
+    ExecuteBlock(Box<(Block, Vec<SignedTransaction>, HashValue)>),
     CommitBlocks(Box<(Vec<HashValue>, LedgerInfoWithSignatures)>),
 }
 
@@ -35,30 +35,33 @@ impl SerializerService {
                 bcs::to_bytes(&Result::<_, Error>::Ok(self.internal.committed_block_id()))
             }
             ExecutionCorrectnessInput::Reset => bcs::to_bytes(&self.internal.reset()),
-            ExecutionCorrectnessInput::ExecuteBlock(block_with_parent_id) => bcs::to_bytes(
-                &self
-                    .internal
-                    .execute_block(
-                        (
-                            block_with_parent_id.0.id(),
-                            block_with_parent_id.0.transactions_to_execute(),
-                        ),
-                        block_with_parent_id.1,
-                    )
-                    .map(|mut result| {
-                        if let Some(prikey) = self.prikey.as_ref() {
-                            let vote_proposal = VoteProposal::new(
-                                result.extension_proof(),
-                                block_with_parent_id.0.clone(),
-                                result.epoch_state().clone(),
-                                false,
-                            );
-                            let signature = prikey.sign(&vote_proposal);
-                            result.set_signature(signature);
-                        }
-                        result
-                    }),
-            ),
+            ExecutionCorrectnessInput::ExecuteBlock(block_with_parent_id) => {
+                let (block, user_txns, parent_block_id) = *block_with_parent_id;
+                bcs::to_bytes(
+                    &self
+                        .internal
+                        .execute_block(
+                            (
+                                block.id(),
+                                block.transactions_to_execute_with_payload(user_txns),
+                            ),
+                            parent_block_id,
+                        )
+                        .map(|mut result| {
+                            if let Some(prikey) = self.prikey.as_ref() {
+                                let vote_proposal = VoteProposal::new(
+                                    result.extension_proof(),
+                                    block,
+                                    result.epoch_state().clone(),
+                                    false,
+                                );
+                                let signature = prikey.sign(&vote_proposal);
+                                result.set_signature(signature);
+                            }
+                            result
+                        }),
+                )
+            }
             ExecutionCorrectnessInput::CommitBlocks(blocks_with_li) => bcs::to_bytes(
                 &self
                     .internal
@@ -102,10 +105,12 @@ impl ExecutionCorrectness for SerializerClient {
     fn execute_block(
         &self,
         block: Block,
+        user_txns: Vec<SignedTransaction>,
         parent_block_id: HashValue,
     ) -> Result<StateComputeResult, Error> {
         let response = self.request(ExecutionCorrectnessInput::ExecuteBlock(Box::new((
             block,
+            user_txns,
             parent_block_id,
         ))))?;
         bcs::from_bytes(&response)?
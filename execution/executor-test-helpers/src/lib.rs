@@ -93,6 +93,7 @@ pub fn gen_block_metadata(index: u8, proposer: AccountAddress) -> BlockMetadata
         index as u64,
         vec![],
         proposer,
+        vec![],
     )
 }
 
@@ -26,8 +26,8 @@ use aptos_types::{
     proof::{accumulator::InMemoryAccumulator, AccumulatorExtensionProof},
     state_store::{state_key::StateKey, state_value::StateValue},
     transaction::{
-        Transaction, TransactionInfo, TransactionListWithProof, TransactionOutputListWithProof,
-        TransactionStatus, Version,
+        SignedTransaction, Transaction, TransactionInfo, TransactionListWithProof,
+        TransactionOutputListWithProof, TransactionStatus, Version,
     },
     write_set::WriteSet,
 };
@@ -115,6 +115,13 @@ pub trait BlockExecutorTrait: Send + Sync {
     ) -> Result<(), Error>;
 }
 
+/// Executes pending transactions ahead of block proposals, so that their outputs can be reused
+/// by the block executor when the transactions get proposed.
+pub trait TransactionPreExecutor: Send + Sync {
+    /// Speculatively executes the transactions against the latest committed state.
+    fn pre_execute(&self, transactions: Vec<SignedTransaction>);
+}
+
 pub trait TransactionReplayer: Send {
     fn replay(
         &self,
@@ -487,6 +494,10 @@ impl TransactionData {
         self.gas_used
     }
 
+    pub fn txn_info(&self) -> &TransactionInfo {
+        &self.txn_info
+    }
+
     pub fn txn_info_hash(&self) -> HashValue {
         self.txn_info_hash
     }
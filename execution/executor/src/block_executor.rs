@@ -5,17 +5,20 @@
 
 use crate::logging::{LogEntry, LogSchema};
 use anyhow::Result;
-use aptos_crypto::HashValue;
+use aptos_crypto::{hash::CryptoHash, HashValue};
 use aptos_logger::prelude::*;
 use aptos_state_view::StateViewId;
 use aptos_types::{ledger_info::LedgerInfoWithSignatures, transaction::Transaction};
 use aptos_vm::VMExecutor;
 use executor_types::{BlockExecutorTrait, Error, StateComputeResult};
 use fail::fail_point;
-use std::marker::PhantomData;
+use std::{marker::PhantomData, sync::Arc};
 
 use crate::{
-    components::{block_tree::BlockTree, chunk_output::ChunkOutput},
+    components::{
+        block_tree::BlockTree, chunk_output::ChunkOutput, execution_observer::ExecutionObserver,
+        speculation_cache::SpeculationCache,
+    },
     metrics::{
         APTOS_EXECUTOR_COMMIT_BLOCKS_SECONDS, APTOS_EXECUTOR_EXECUTE_BLOCK_SECONDS,
         APTOS_EXECUTOR_SAVE_TRANSACTIONS_SECONDS, APTOS_EXECUTOR_TRANSACTIONS_SAVED,
@@ -27,6 +30,8 @@ use storage_interface::DbReaderWriter;
 pub struct BlockExecutor<V> {
     pub db: DbReaderWriter,
     block_tree: BlockTree,
+    speculation_cache: Option<Arc<SpeculationCache>>,
+    execution_observer: Option<Arc<ExecutionObserver>>,
     phantom: PhantomData<V>,
 }
 
@@ -39,9 +44,31 @@ where
         Self {
             db,
             block_tree,
+            speculation_cache: None,
+            execution_observer: None,
             phantom: PhantomData,
         }
     }
+
+    /// Creates a block executor reusing the outputs of pre-executed transactions from
+    /// `speculation_cache`.
+    pub fn new_with_speculation_cache(
+        db: DbReaderWriter,
+        speculation_cache: Arc<SpeculationCache>,
+    ) -> Self {
+        Self {
+            speculation_cache: Some(speculation_cache),
+            ..Self::new(db)
+        }
+    }
+
+    /// Records the executed blocks in `execution_observer` to diagnose state root divergences.
+    pub fn with_execution_observer(self, execution_observer: Arc<ExecutionObserver>) -> Self {
+        Self {
+            execution_observer: Some(execution_observer),
+            ..self
+        }
+    }
 }
 
 impl<V> BlockExecutorTrait for BlockExecutor<V>
@@ -53,7 +80,17 @@ where
     }
 
     fn reset(&self) -> Result<(), Error> {
-        Ok(self.block_tree.reset(&self.db.reader)?)
+        self.block_tree.reset(&self.db.reader)?;
+        if let Some(execution_observer) = &self.execution_observer {
+            // State sync has fetched the certified transactions by now.
+            if let Err(err) = execution_observer.diagnose(self.db.reader.as_ref()) {
+                error!(
+                    LogSchema::new(LogEntry::BlockExecutor),
+                    "Failed to diagnose divergence: {:?}", err
+                );
+            }
+        }
+        Ok(())
     }
 
     fn execute_block(
@@ -105,13 +142,23 @@ where
                         "Injected error in vm_execute_block"
                     )))
                 });
-                ChunkOutput::by_transaction_execution::<V>(transactions, state_view)?
+                match &self.speculation_cache {
+                    Some(speculation_cache) => ChunkOutput::by_speculative_execution::<V>(
+                        transactions,
+                        state_view,
+                        speculation_cache,
+                    )?,
+                    None => ChunkOutput::by_transaction_execution::<V>(transactions, state_view)?,
+                }
             };
             chunk_output.trace_log_transaction_status();
 
             let (output, _, _) = chunk_output.apply_to_ledger(parent_accumulator)?;
             output
         };
+        if let Some(execution_observer) = &self.execution_observer {
+            execution_observer.record_block(block_id, parent_accumulator.clone(), &output);
+        }
 
         let block = self
             .block_tree
@@ -163,6 +210,10 @@ where
             });
         }
 
+        if let Some(execution_observer) = &self.execution_observer {
+            execution_observer.check_commit(&block_ids, ledger_info_with_sigs.ledger_info());
+        }
+
         {
             let _timer = APTOS_EXECUTOR_SAVE_TRANSACTIONS_SECONDS.start_timer();
             APTOS_EXECUTOR_TRANSACTIONS_SAVED.observe(to_commit as f64);
@@ -180,6 +231,13 @@ where
                 .prune(ledger_info_with_sigs.ledger_info())
                 .expect("Failure pruning block tree.");
         }
+        if let Some(speculation_cache) = &self.speculation_cache {
+            let committed_hashes: Vec<_> = txns_to_commit
+                .iter()
+                .map(|txn_to_commit| txn_to_commit.transaction().hash())
+                .collect();
+            speculation_cache.remove(&committed_hashes);
+        }
         Ok(())
     }
 }
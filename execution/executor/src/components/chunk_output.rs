@@ -3,7 +3,10 @@
 
 #![forbid(unsafe_code)]
 
-use crate::components::apply_chunk_output::ApplyChunkOutput;
+use crate::components::{
+    apply_chunk_output::ApplyChunkOutput,
+    speculation_cache::{execute_block_with_speculation, SpeculationCache},
+};
 use anyhow::Result;
 use aptos_crypto::hash::TransactionAccumulatorHasher;
 use aptos_logger::trace;
@@ -43,6 +46,31 @@ impl ChunkOutput {
         })
     }
 
+    /// Like `by_transaction_execution`, but reuses the outputs of transactions pre-executed
+    /// against a state that is unchanged for the values they read.
+    pub fn by_speculative_execution<V: VMExecutor>(
+        transactions: Vec<Transaction>,
+        state_view: VerifiedStateView,
+        speculation_cache: &SpeculationCache,
+    ) -> Result<Self> {
+        let transaction_outputs =
+            execute_block_with_speculation::<V, _>(&transactions, &state_view, speculation_cache)?;
+
+        // Reused outputs were not executed on top of `state_view`, prime the state cache with
+        // all the touched accounts.
+        for output in &transaction_outputs {
+            for (access_path, _) in output.write_set() {
+                state_view.get_by_access_path(access_path)?;
+            }
+        }
+
+        Ok(Self {
+            transactions,
+            transaction_outputs,
+            state_cache: state_view.into_state_cache(),
+        })
+    }
+
     pub fn by_transaction_output(
         transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
         state_view: VerifiedStateView,
@@ -0,0 +1,345 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use crate::metrics::APTOS_EXECUTOR_OBSERVED_DIVERGENCES;
+use anyhow::{ensure, format_err, Result};
+use aptos_crypto::{hash::TransactionAccumulatorHasher, HashValue};
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_types::{
+    ledger_info::LedgerInfo,
+    proof::accumulator::InMemoryAccumulator,
+    transaction::{Transaction, TransactionInfo, Version},
+    write_set::WriteSet,
+};
+use executor_types::ExecutedChunk;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use storage_interface::DbReader;
+
+/// File of the dump directory a divergence is persisted to until it is diagnosed.
+const DIVERGENCE_FILE_NAME: &str = "divergence.bcs";
+
+/// Execution result of a single transaction of a block.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ObservedTransaction {
+    pub transaction: Transaction,
+    pub txn_info: TransactionInfo,
+    pub txn_info_hash: HashValue,
+    pub write_set: WriteSet,
+}
+
+/// Execution results of a block, on top of the transaction accumulator of its parent.
+#[derive(Clone)]
+struct ObservedBlock {
+    parent_accumulator: Arc<InMemoryAccumulator<TransactionAccumulatorHasher>>,
+    transactions: Vec<ObservedTransaction>,
+}
+
+/// Blocks whose committed accumulator root differs from the certified ledger info, waiting for
+/// the certified transactions to be synced to locate the first divergent one. The node fails to
+/// commit these blocks, so the divergence is persisted to be diagnosed after a restart.
+#[derive(Deserialize, Serialize)]
+struct Divergence {
+    ledger_info: LedgerInfo,
+    local_root_hash: HashValue,
+    /// Frozen subtree roots of the transaction accumulator of the parent of the blocks.
+    parent_frozen_subtree_roots: Vec<HashValue>,
+    first_version: Version,
+    transactions: Vec<ObservedTransaction>,
+}
+
+impl Divergence {
+    fn first_version(&self) -> Version {
+        self.first_version
+    }
+
+    /// Local accumulator root hash once the first `index + 1` transactions are appended.
+    fn local_root_hash(&self, index: usize) -> Result<HashValue> {
+        let txn_info_hashes: Vec<_> = self.transactions[..=index]
+            .iter()
+            .map(|txn| txn.txn_info_hash)
+            .collect();
+        Ok(InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            self.parent_frozen_subtree_roots.clone(),
+            self.first_version,
+        )?
+        .append(&txn_info_hashes)
+        .root_hash())
+    }
+
+    fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(bcs::from_bytes(&std::fs::read(path)?)?))
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, bcs::to_bytes(self)?)?;
+        Ok(())
+    }
+}
+
+/// Report of the first transaction whose local execution differs from the certified ledger.
+#[derive(Debug)]
+pub struct DivergenceReport {
+    pub version: Version,
+    pub local: ObservedTransaction,
+    pub expected_txn_info: TransactionInfo,
+}
+
+/// Records the per-transaction TransactionInfo of every executed block. When the root of the
+/// committed blocks diverges from the certified ledger info, the divergence is persisted in the
+/// dump directory until the certified transactions are synced, usually after a restart, then
+/// bisected to the first differing transaction whose execution results are dumped.
+pub struct ExecutionObserver {
+    max_recorded_blocks: usize,
+    dump_dir: Option<PathBuf>,
+    blocks: Mutex<(HashMap<HashValue, ObservedBlock>, VecDeque<HashValue>)>,
+    divergence: Mutex<Option<Divergence>>,
+}
+
+impl ExecutionObserver {
+    /// Creates an observer, loading the divergence persisted in `dump_dir` if any.
+    pub fn new(max_recorded_blocks: usize, dump_dir: Option<PathBuf>) -> Self {
+        let divergence = dump_dir.as_ref().and_then(|dump_dir| {
+            let path = dump_dir.join(DIVERGENCE_FILE_NAME);
+            match Divergence::load(&path) {
+                Ok(divergence) => divergence,
+                Err(err) => {
+                    error!("Failed to load divergence from {:?}: {:?}", path, err);
+                    None
+                }
+            }
+        });
+        if let Some(divergence) = &divergence {
+            info!(
+                "Loaded divergence at version {}, waiting for the certified transactions to \
+                 locate it.",
+                divergence.ledger_info.version(),
+            );
+        }
+
+        Self {
+            max_recorded_blocks,
+            dump_dir,
+            blocks: Mutex::new((HashMap::new(), VecDeque::new())),
+            divergence: Mutex::new(divergence),
+        }
+    }
+
+    /// Records the execution results of a block, evicting the oldest recorded block if needed.
+    pub fn record_block(
+        &self,
+        block_id: HashValue,
+        parent_accumulator: Arc<InMemoryAccumulator<TransactionAccumulatorHasher>>,
+        output: &ExecutedChunk,
+    ) {
+        let transactions = output
+            .to_commit
+            .iter()
+            .map(|(txn, txn_data)| ObservedTransaction {
+                transaction: txn.clone(),
+                txn_info: txn_data.txn_info().clone(),
+                txn_info_hash: txn_data.txn_info_hash(),
+                write_set: txn_data.write_set().clone(),
+            })
+            .collect();
+
+        let mut blocks = self.blocks.lock();
+        let (records, order) = &mut *blocks;
+        if records
+            .insert(
+                block_id,
+                ObservedBlock {
+                    parent_accumulator,
+                    transactions,
+                },
+            )
+            .is_none()
+        {
+            order.push_back(block_id);
+        }
+        while order.len() > self.max_recorded_blocks {
+            if let Some(evicted) = order.pop_front() {
+                records.remove(&evicted);
+            }
+        }
+    }
+
+    /// Compares the root of the blocks being committed with the certified `ledger_info`, and
+    /// persists a divergence if they differ, before the failing commit takes the node down.
+    /// Returns whether the blocks diverged.
+    pub fn check_commit(&self, block_ids: &[HashValue], ledger_info: &LedgerInfo) -> bool {
+        let mut blocks = self.blocks.lock();
+        let (records, order) = &mut *blocks;
+
+        let mut committed = Vec::with_capacity(block_ids.len());
+        for block_id in block_ids {
+            match records.remove(block_id) {
+                Some(block) => committed.push(block),
+                // Evicted before commit, there is nothing to compare with.
+                None => return false,
+            }
+        }
+        order.retain(|block_id| !block_ids.contains(block_id));
+
+        let parent_accumulator = match committed.first() {
+            Some(block) => block.parent_accumulator.clone(),
+            None => return false,
+        };
+        let transactions: Vec<_> = committed
+            .into_iter()
+            .flat_map(|block| block.transactions)
+            .collect();
+        let txn_info_hashes: Vec<_> = transactions.iter().map(|txn| txn.txn_info_hash).collect();
+        let local_root_hash = parent_accumulator.append(&txn_info_hashes).root_hash();
+        if local_root_hash == ledger_info.transaction_accumulator_hash() {
+            return false;
+        }
+
+        APTOS_EXECUTOR_OBSERVED_DIVERGENCES.inc();
+        error!(
+            "Local transaction accumulator root {} diverges from the certified root {} at version \
+             {}, waiting for the certified transactions to locate the divergence.",
+            local_root_hash,
+            ledger_info.transaction_accumulator_hash(),
+            ledger_info.version(),
+        );
+        let divergence = Divergence {
+            ledger_info: ledger_info.clone(),
+            local_root_hash,
+            parent_frozen_subtree_roots: parent_accumulator.frozen_subtree_roots().clone(),
+            first_version: parent_accumulator.num_leaves(),
+            transactions,
+        };
+        self.persist(&divergence);
+        *self.divergence.lock() = Some(divergence);
+        true
+    }
+
+    fn persist(&self, divergence: &Divergence) {
+        let dump_dir = match &self.dump_dir {
+            Some(dump_dir) => dump_dir,
+            None => {
+                // The divergence can't be diagnosed after a restart, keep the local results.
+                for (index, txn) in divergence.transactions.iter().enumerate() {
+                    error!(
+                        "Local execution at version {}: {:?}, write set {:?}",
+                        divergence.first_version() + index as u64,
+                        txn.txn_info,
+                        txn.write_set,
+                    );
+                }
+                return;
+            }
+        };
+        let path = dump_dir.join(DIVERGENCE_FILE_NAME);
+        match divergence.save(&path) {
+            Ok(()) => error!("Divergence persisted to {:?}", path),
+            Err(err) => error!("Failed to persist divergence to {:?}: {:?}", path, err),
+        }
+    }
+
+    /// Drops the divergence once diagnosed.
+    fn clear(&self, divergence: &mut Option<Divergence>) {
+        *divergence = None;
+        if let Some(dump_dir) = &self.dump_dir {
+            let path = dump_dir.join(DIVERGENCE_FILE_NAME);
+            if let Err(err) = std::fs::remove_file(&path) {
+                error!("Failed to remove divergence {:?}: {}", path, err);
+            }
+        }
+    }
+
+    /// Locates the first transaction of a recorded divergence whose TransactionInfo differs from
+    /// the certified one, once `reader` has the certified transactions. The report is dumped and
+    /// returned.
+    pub fn diagnose(&self, reader: &dyn DbReader) -> Result<Option<DivergenceReport>> {
+        let mut divergence_guard = self.divergence.lock();
+        let divergence = match divergence_guard.as_ref() {
+            Some(divergence) => divergence,
+            None => return Ok(None),
+        };
+        let ledger_version = divergence.ledger_info.version();
+        if reader.get_latest_version()? < ledger_version {
+            // Certified transactions are not synced yet.
+            return Ok(None);
+        }
+        ensure!(
+            !divergence.transactions.is_empty(),
+            "Divergent blocks at version {} have no transaction.",
+            ledger_version,
+        );
+
+        // Accumulator roots commit to all prior transactions, so once the local and certified
+        // roots differ at a version they differ at every later version.
+        let first_version = divergence.first_version();
+        let diverges = |index: usize| -> Result<bool> {
+            Ok(divergence.local_root_hash(index)?
+                != reader.get_accumulator_root_hash(first_version + index as u64)?)
+        };
+        let (mut low, mut high) = (0, divergence.transactions.len() - 1);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if diverges(mid)? {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        if !diverges(low)? {
+            info!(
+                "Local root {} at version {} matches the synced transactions.",
+                divergence.local_root_hash, ledger_version,
+            );
+            self.clear(&mut divergence_guard);
+            return Ok(None);
+        }
+
+        let version = first_version + low as u64;
+        let expected_txn_info = reader
+            .get_transactions(version, 1, ledger_version, false)?
+            .proof
+            .transaction_infos
+            .pop()
+            .ok_or_else(|| format_err!("Missing certified transaction info at {}.", version))?;
+        let report = DivergenceReport {
+            version,
+            local: divergence.transactions[low].clone(),
+            expected_txn_info,
+        };
+        self.clear(&mut divergence_guard);
+        self.dump(&report);
+        Ok(Some(report))
+    }
+
+    fn dump(&self, report: &DivergenceReport) {
+        error!(
+            "First divergent transaction at version {}: local {:?}, certified {:?}",
+            report.version, report.local.txn_info, report.expected_txn_info,
+        );
+        let dump_dir = match &self.dump_dir {
+            Some(dump_dir) => dump_dir,
+            None => {
+                error!(
+                    "Local write set at version {}: {:?}",
+                    report.version, report.local.write_set
+                );
+                return;
+            }
+        };
+        let path = dump_dir.join(format!("divergence_{}.txt", report.version));
+        match std::fs::write(&path, format!("{:#?}", report)) {
+            Ok(()) => error!("Divergence report written to {:?}", path),
+            Err(err) => error!("Failed to write divergence report to {:?}: {}", path, err),
+        }
+    }
+}
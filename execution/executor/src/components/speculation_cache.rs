@@ -0,0 +1,366 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use crate::metrics::APTOS_EXECUTOR_SPECULATIVE_OUTPUTS;
+use anyhow::Result;
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_infallible::Mutex;
+use aptos_state_view::{StateView, StateViewId};
+use aptos_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    account_state::AccountState,
+    account_state_blob::AccountStateBlob,
+    event::EventKey,
+    on_chain_config,
+    state_store::state_key::StateKey,
+    transaction::{Transaction, TransactionOutput, TransactionStatus, Version},
+    write_set::{WriteOp, WriteSet},
+};
+use aptos_vm::VMExecutor;
+use once_cell::sync::Lazy;
+use std::{collections::HashMap, convert::TryFrom, sync::Arc};
+
+static NEW_EPOCH_EVENT_KEY: Lazy<EventKey> = Lazy::new(on_chain_config::new_epoch_event_key);
+
+/// A key read by the VM through a StateView.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum StateRead {
+    AccessPath(AccessPath),
+    StateKey(StateKey),
+}
+
+impl StateRead {
+    fn read<S: StateView>(&self, state_view: &S) -> Result<Option<Vec<u8>>> {
+        match self {
+            StateRead::AccessPath(access_path) => state_view.get_by_access_path(access_path),
+            StateRead::StateKey(state_key) => state_view.get_state_value(state_key),
+        }
+    }
+}
+
+/// Output of a transaction executed ahead of the block it is included in, together with every
+/// value read during the execution. The output is valid for any state that provides the same
+/// values for the recorded reads.
+///
+/// This includes the resources updated by the BlockMetadata transaction of every block, e.g. the
+/// timestamp read by the prologue and by `Timestamp::now_microseconds`: the VM caches resources
+/// for the whole transaction, so reads of the prologue can't be told apart from reads of the
+/// payload, and the output is only reused in a block with the same timestamp.
+pub struct SpeculativeOutput {
+    /// Version of the committed state the transaction was executed against.
+    base_version: Option<Version>,
+    reads: Vec<(StateRead, Option<Vec<u8>>)>,
+    output: TransactionOutput,
+}
+
+impl SpeculativeOutput {
+    pub fn new(
+        base_version: Option<Version>,
+        reads: Vec<(StateRead, Option<Vec<u8>>)>,
+        output: TransactionOutput,
+    ) -> Self {
+        Self {
+            base_version,
+            reads,
+            output,
+        }
+    }
+
+    pub fn base_version(&self) -> Option<Version> {
+        self.base_version
+    }
+
+    pub fn output(&self) -> &TransactionOutput {
+        &self.output
+    }
+
+    /// Returns whether every recorded read yields the same value in `state_view`.
+    pub fn is_valid<S: StateView>(&self, state_view: &S) -> Result<bool> {
+        for (read, value) in &self.reads {
+            if read.read(state_view)? != *value {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Speculative outputs keyed by transaction hash, shared between the pre-executor filling it and
+/// the block executor consuming it.
+pub struct SpeculationCache {
+    capacity: usize,
+    outputs: Mutex<HashMap<HashValue, Arc<SpeculativeOutput>>>,
+}
+
+impl SpeculationCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            outputs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, txn_hash: &HashValue) -> Option<Arc<SpeculativeOutput>> {
+        self.outputs.lock().get(txn_hash).cloned()
+    }
+
+    /// Returns whether the transaction was already executed against `base_version`.
+    pub fn contains(&self, txn_hash: &HashValue, base_version: Option<Version>) -> bool {
+        self.outputs
+            .lock()
+            .get(txn_hash)
+            .map_or(false, |output| output.base_version() == base_version)
+    }
+
+    /// Inserts the output, replacing any older output of the same transaction. When the cache is
+    /// full, outputs executed against older states are evicted first; if none is left the output
+    /// is dropped.
+    pub fn insert(&self, txn_hash: HashValue, output: SpeculativeOutput) {
+        let mut outputs = self.outputs.lock();
+        if outputs.len() >= self.capacity && !outputs.contains_key(&txn_hash) {
+            let base_version = output.base_version();
+            outputs.retain(|_, o| o.base_version() >= base_version);
+            if outputs.len() >= self.capacity {
+                return;
+            }
+        }
+        outputs.insert(txn_hash, Arc::new(output));
+    }
+
+    /// Drops the outputs of the given transactions, e.g. once they are committed.
+    pub fn remove<'a>(&self, txn_hashes: impl IntoIterator<Item = &'a HashValue>) {
+        let mut outputs = self.outputs.lock();
+        for txn_hash in txn_hashes {
+            outputs.remove(txn_hash);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.outputs.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A StateView recording every value read from the underlying view.
+pub struct ReadRecordingStateView<'a, S> {
+    base_view: &'a S,
+    reads: Mutex<HashMap<StateRead, Option<Vec<u8>>>>,
+}
+
+impl<'a, S: StateView> ReadRecordingStateView<'a, S> {
+    pub fn new(base_view: &'a S) -> Self {
+        Self {
+            base_view,
+            reads: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn into_reads(self) -> Vec<(StateRead, Option<Vec<u8>>)> {
+        std::mem::take(&mut *self.reads.lock())
+            .into_iter()
+            .collect()
+    }
+
+    fn record(&self, read: StateRead) -> Result<Option<Vec<u8>>> {
+        let value = read.read(self.base_view)?;
+        self.reads.lock().insert(read, value.clone());
+        Ok(value)
+    }
+}
+
+impl<'a, S: StateView> StateView for ReadRecordingStateView<'a, S> {
+    fn id(&self) -> StateViewId {
+        self.base_view.id()
+    }
+
+    fn get_by_access_path(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        self.record(StateRead::AccessPath(access_path.clone()))
+    }
+
+    fn get_state_value(&self, state_key: &StateKey) -> Result<Option<Vec<u8>>> {
+        self.record(StateRead::StateKey(state_key.clone()))
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.base_view.is_genesis()
+    }
+}
+
+/// A StateView of the block being executed: the parent state plus the writes of the
+/// transactions already processed in the block.
+struct BlockStateView<'a, S> {
+    base_view: &'a S,
+    writes: HashMap<AccountAddress, HashMap<Vec<u8>, WriteOp>>,
+}
+
+impl<'a, S: StateView> BlockStateView<'a, S> {
+    fn new(base_view: &'a S) -> Self {
+        Self {
+            base_view,
+            writes: HashMap::new(),
+        }
+    }
+
+    fn apply_write_set(&mut self, write_set: &WriteSet) {
+        for (access_path, write_op) in write_set.iter() {
+            self.writes
+                .entry(access_path.address)
+                .or_insert_with(HashMap::new)
+                .insert(access_path.path.clone(), write_op.clone());
+        }
+    }
+}
+
+impl<'a, S: StateView> StateView for BlockStateView<'a, S> {
+    fn id(&self) -> StateViewId {
+        self.base_view.id()
+    }
+
+    fn get_by_access_path(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        match self
+            .writes
+            .get(&access_path.address)
+            .and_then(|writes| writes.get(&access_path.path))
+        {
+            Some(WriteOp::Value(value)) => Ok(Some(value.clone())),
+            Some(WriteOp::Deletion) => Ok(None),
+            None => self.base_view.get_by_access_path(access_path),
+        }
+    }
+
+    fn get_state_value(&self, state_key: &StateKey) -> Result<Option<Vec<u8>>> {
+        let base_value = self.base_view.get_state_value(state_key)?;
+        let writes = match state_key {
+            StateKey::AccountAddressKey(address) => match self.writes.get(address) {
+                Some(writes) => writes,
+                None => return Ok(base_value),
+            },
+        };
+
+        // Rebuild the account blob with the paths written in the block.
+        let mut account_state = match &base_value {
+            Some(bytes) if !bytes.is_empty() => AccountState::try_from(bytes)?,
+            _ => AccountState::default(),
+        };
+        for (path, write_op) in writes {
+            match write_op {
+                WriteOp::Value(value) => account_state.insert(path.clone(), value.clone()),
+                WriteOp::Deletion => account_state.remove(path),
+            };
+        }
+        Ok(Some(AccountStateBlob::try_from(&account_state)?.into()))
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.base_view.is_genesis()
+    }
+}
+
+/// Executes the block on top of `state_view`, reusing the speculative output of a transaction
+/// when all the values it read are unchanged at its position in the block.
+///
+/// Validating an output requires all prior transactions to be executed. To keep the VM batches
+/// large, outputs are only reused until the first user transaction without a reusable output:
+/// that transaction and all the following ones are executed by the VM in a single batch. Thus,
+/// besides the non-user transactions preceding reused outputs (e.g. the BlockMetadata
+/// transaction), the VM executes a single batch per block.
+pub fn execute_block_with_speculation<V: VMExecutor, S: StateView>(
+    transactions: &[Transaction],
+    state_view: &S,
+    speculation_cache: &SpeculationCache,
+) -> Result<Vec<TransactionOutput>> {
+    let mut block_view = BlockStateView::new(state_view);
+    let mut outputs: Vec<TransactionOutput> = Vec::with_capacity(transactions.len());
+    let mut pending = vec![];
+    let mut reusing = true;
+
+    for txn in transactions {
+        let speculative_output = match txn {
+            Transaction::UserTransaction(_) if reusing => speculation_cache.get(&txn.hash()),
+            _ => {
+                pending.push(txn.clone());
+                continue;
+            }
+        };
+        let speculative_output = match speculative_output {
+            Some(speculative_output) => speculative_output,
+            None => {
+                APTOS_EXECUTOR_SPECULATIVE_OUTPUTS
+                    .with_label_values(&["missed"])
+                    .inc();
+                reusing = false;
+                pending.push(txn.clone());
+                continue;
+            }
+        };
+
+        // Prior transactions must be executed to know the state the transaction observes. As
+        // outputs are reused only until the first pending user transaction, this only executes
+        // non-user transactions, e.g. the BlockMetadata transaction.
+        if execute_pending::<V, S>(&mut pending, &mut block_view, &mut outputs)? {
+            break;
+        }
+        if speculative_output.is_valid(&block_view)? {
+            APTOS_EXECUTOR_SPECULATIVE_OUTPUTS
+                .with_label_values(&["reused"])
+                .inc();
+            let output = speculative_output.output().clone();
+            block_view.apply_write_set(output.write_set());
+            let is_reconfig = is_reconfig(&output);
+            outputs.push(output);
+            if is_reconfig {
+                break;
+            }
+        } else {
+            APTOS_EXECUTOR_SPECULATIVE_OUTPUTS
+                .with_label_values(&["invalidated"])
+                .inc();
+            reusing = false;
+            pending.push(txn.clone());
+        }
+    }
+    execute_pending::<V, S>(&mut pending, &mut block_view, &mut outputs)?;
+
+    // Transactions after a reconfiguration are retried in the next epoch.
+    outputs.resize_with(transactions.len(), || {
+        TransactionOutput::new(WriteSet::default(), vec![], 0, TransactionStatus::Retry)
+    });
+    Ok(outputs)
+}
+
+/// Executes the pending transactions on top of the block view. Returns whether the block ended
+/// with a reconfiguration.
+fn execute_pending<V: VMExecutor, S: StateView>(
+    pending: &mut Vec<Transaction>,
+    block_view: &mut BlockStateView<S>,
+    outputs: &mut Vec<TransactionOutput>,
+) -> Result<bool> {
+    if pending.is_empty() {
+        return Ok(false);
+    }
+
+    let batch_outputs = V::execute_block(std::mem::take(pending), block_view)?;
+    let mut reconfig = false;
+    for output in batch_outputs {
+        if reconfig {
+            break;
+        }
+        block_view.apply_write_set(output.write_set());
+        reconfig = is_reconfig(&output);
+        outputs.push(output);
+    }
+    Ok(reconfig)
+}
+
+fn is_reconfig(output: &TransactionOutput) -> bool {
+    output
+        .events()
+        .iter()
+        .any(|event| *event.key() == *NEW_EPOCH_EVENT_KEY)
+}
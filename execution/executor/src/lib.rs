@@ -16,3 +16,4 @@ pub mod block_executor;
 pub mod chunk_executor;
 pub mod components;
 pub mod db_bootstrapper;
+pub mod pre_executor;
@@ -1,7 +1,10 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use aptos_metrics::{register_histogram, register_int_counter, Histogram, IntCounter};
+use aptos_metrics::{
+    register_histogram, register_int_counter, register_int_counter_vec, Histogram, IntCounter,
+    IntCounterVec,
+};
 use once_cell::sync::Lazy;
 
 pub static APTOS_EXECUTOR_EXECUTE_CHUNK_SECONDS: Lazy<Histogram> = Lazy::new(|| {
@@ -97,3 +100,26 @@ pub static APTOS_EXECUTOR_TRANSACTIONS_SAVED: Lazy<Histogram> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static APTOS_EXECUTOR_SPECULATIVE_OUTPUTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "aptos_executor_speculative_outputs",
+        // metric description
+        "Number of block transactions by whether their pre-executed output was reused, \
+         invalidated or missed",
+        // metric labels (dimensions)
+        &["result"]
+    )
+    .unwrap()
+});
+
+pub static APTOS_EXECUTOR_OBSERVED_DIVERGENCES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "aptos_executor_observed_divergences",
+        // metric description
+        "Number of commits whose locally executed accumulator root differs from the certified one"
+    )
+    .unwrap()
+});
@@ -0,0 +1,109 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use crate::components::{
+    apply_chunk_output::IntoLedgerView,
+    speculation_cache::{ReadRecordingStateView, SpeculationCache, SpeculativeOutput},
+};
+use anyhow::Result;
+use aptos_crypto::hash::CryptoHash;
+use aptos_logger::prelude::*;
+use aptos_state_view::StateViewId;
+use aptos_types::transaction::{SignedTransaction, Transaction, TransactionStatus, Version};
+use aptos_vm::VMExecutor;
+use executor_types::TransactionPreExecutor;
+use rayon::prelude::*;
+use std::{marker::PhantomData, sync::Arc};
+use storage_interface::{state_view::VerifiedStateView, DbReader};
+
+/// Executes mempool transactions against the latest committed state on a dedicated thread pool,
+/// caching their outputs and read sets for the block executor.
+pub struct PreExecutor<V> {
+    db: Arc<dyn DbReader>,
+    speculation_cache: Arc<SpeculationCache>,
+    thread_pool: rayon::ThreadPool,
+    phantom: PhantomData<V>,
+}
+
+impl<V> PreExecutor<V>
+where
+    V: VMExecutor,
+{
+    pub fn new(
+        db: Arc<dyn DbReader>,
+        speculation_cache: Arc<SpeculationCache>,
+        num_threads: usize,
+    ) -> Self {
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|index| format!("pre_exec_{}", index))
+            .build()
+            .expect("Failed to create the pre-execution thread pool.");
+        Self {
+            db,
+            speculation_cache,
+            thread_pool,
+            phantom: PhantomData,
+        }
+    }
+
+    fn latest_state_view(&self) -> Result<(VerifiedStateView, Option<Version>)> {
+        let ledger_view = self
+            .db
+            .get_latest_tree_state()?
+            .into_ledger_view(&self.db)?;
+        let state_view =
+            ledger_view.state_view(&ledger_view, StateViewId::Miscellaneous, self.db.clone());
+        Ok((state_view, ledger_view.version()))
+    }
+
+    fn pre_execute_impl(&self, transactions: Vec<SignedTransaction>) -> Result<()> {
+        let (state_view, base_version) = self.latest_state_view()?;
+
+        self.thread_pool.install(|| {
+            transactions
+                .into_par_iter()
+                .map(Transaction::UserTransaction)
+                .for_each(|txn| {
+                    let txn_hash = txn.hash();
+                    if self.speculation_cache.contains(&txn_hash, base_version) {
+                        return;
+                    }
+
+                    let recording_view = ReadRecordingStateView::new(&state_view);
+                    match V::execute_block(vec![txn], &recording_view) {
+                        Ok(mut outputs) => match outputs.pop() {
+                            Some(output) if output.status() != &TransactionStatus::Retry => {
+                                self.speculation_cache.insert(
+                                    txn_hash,
+                                    SpeculativeOutput::new(
+                                        base_version,
+                                        recording_view.into_reads(),
+                                        output,
+                                    ),
+                                );
+                            }
+                            _ => (),
+                        },
+                        Err(err) => {
+                            debug!("Failed to pre-execute {}: {:?}", txn_hash, err);
+                        }
+                    }
+                });
+        });
+        Ok(())
+    }
+}
+
+impl<V> TransactionPreExecutor for PreExecutor<V>
+where
+    V: VMExecutor,
+{
+    fn pre_execute(&self, transactions: Vec<SignedTransaction>) {
+        if let Err(err) = self.pre_execute_impl(transactions) {
+            warn!("Failed to pre-execute transactions: {:?}", err);
+        }
+    }
+}
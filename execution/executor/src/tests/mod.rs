@@ -4,12 +4,16 @@
 use crate::{
     block_executor::BlockExecutor,
     chunk_executor::ChunkExecutor,
-    components::{apply_chunk_output::IntoLedgerView, chunk_output::ChunkOutput},
+    components::{
+        apply_chunk_output::IntoLedgerView, chunk_output::ChunkOutput,
+        execution_observer::ExecutionObserver, speculation_cache::SpeculationCache,
+    },
     db_bootstrapper::{generate_waypoint, maybe_bootstrap},
     mock_vm::{
         encode_mint_transaction, encode_reconfiguration_transaction, encode_transfer_transaction,
         MockVM, DISCARD_STATUS, KEEP_STATUS,
     },
+    pre_executor::PreExecutor,
 };
 use aptos_crypto::HashValue;
 use aptos_state_view::StateViewId;
@@ -21,9 +25,12 @@ use aptos_types::{
     transaction::{Transaction, TransactionListWithProof, TransactionStatus, Version},
 };
 use aptosdb::AptosDB;
-use executor_types::{BlockExecutorTrait, ChunkExecutorTrait, ExecutedTrees, TransactionReplayer};
+use executor_types::{
+    BlockExecutorTrait, ChunkExecutorTrait, ExecutedTrees, TransactionPreExecutor,
+    TransactionReplayer,
+};
 use proptest::prelude::*;
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, sync::Arc};
 use storage_interface::DbReaderWriter;
 
 mod chunk_executor_tests;
@@ -249,6 +256,118 @@ fn test_executor_execute_same_block_multiple_times() {
     assert_eq!(responses.len(), 1);
 }
 
+#[test]
+fn test_executor_reuse_pre_executed_outputs() {
+    let executor = TestExecutor::new();
+    let speculation_cache = Arc::new(SpeculationCache::new(10));
+    let speculative_executor = BlockExecutor::<MockVM>::new_with_speculation_cache(
+        executor.db.clone(),
+        speculation_cache.clone(),
+    );
+    let parent_block_id = executor.committed_block_id();
+    let block_id = gen_block_id(1);
+
+    let txns = vec![
+        encode_mint_transaction(gen_address(0), 100),
+        encode_mint_transaction(gen_address(1), 100),
+        encode_transfer_transaction(gen_address(0), gen_address(1), 50),
+    ];
+    let signed_txns = txns
+        .iter()
+        .map(|txn| match txn {
+            Transaction::UserTransaction(signed_txn) => signed_txn.clone(),
+            _ => unreachable!(),
+        })
+        .collect();
+    PreExecutor::<MockVM>::new(executor.db.reader.clone(), speculation_cache.clone(), 2)
+        .pre_execute(signed_txns);
+    assert_eq!(speculation_cache.len(), 3);
+
+    // The mints are reused, the transfer read the balance before the mint and is re-executed.
+    let expected_output = executor
+        .execute_block((block_id, txns.clone()), parent_block_id)
+        .unwrap();
+    let output = speculative_executor
+        .execute_block((block_id, txns), parent_block_id)
+        .unwrap();
+    assert_eq!(
+        &vec![
+            KEEP_STATUS.clone(),
+            KEEP_STATUS.clone(),
+            KEEP_STATUS.clone()
+        ],
+        output.compute_status()
+    );
+    assert_eq!(output, expected_output);
+
+    let ledger_info = gen_ledger_info(3, output.root_hash(), block_id, 1);
+    speculative_executor
+        .commit_blocks(vec![block_id], ledger_info)
+        .unwrap();
+    assert!(speculation_cache.is_empty());
+}
+
+#[test]
+fn test_execution_observer_locates_divergence() {
+    let executor = TestExecutor::new();
+    let dump_dir = aptos_temppath::TempPath::new();
+    dump_dir.create_as_dir().unwrap();
+    let observed_executor =
+        BlockExecutor::<MockVM>::new(executor.db.clone()).with_execution_observer(Arc::new(
+            ExecutionObserver::new(10, Some(dump_dir.path().to_path_buf())),
+        ));
+    let parent_block_id = executor.committed_block_id();
+
+    // The observed node diverges on the last transfer.
+    let txns = vec![
+        encode_mint_transaction(gen_address(0), 100),
+        encode_mint_transaction(gen_address(1), 100),
+        encode_transfer_transaction(gen_address(0), gen_address(1), 50),
+    ];
+    let mut divergent_txns = txns.clone();
+    divergent_txns[2] = encode_transfer_transaction(gen_address(0), gen_address(1), 40);
+
+    let block_id = gen_block_id(1);
+    let divergent_block_id = gen_block_id(2);
+    let output = executor
+        .execute_block((block_id, txns), parent_block_id)
+        .unwrap();
+    observed_executor
+        .execute_block((divergent_block_id, divergent_txns), parent_block_id)
+        .unwrap();
+
+    // The divergence is persisted before the commit fails.
+    let ledger_info = gen_ledger_info(3, output.root_hash(), block_id, 1);
+    assert!(observed_executor
+        .commit_blocks(vec![divergent_block_id], ledger_info.clone())
+        .is_err());
+    let divergence_path = dump_dir.path().join("divergence.bcs");
+    assert!(divergence_path.exists());
+
+    // After a restart, the divergence is loaded and diagnosed once the certified transactions
+    // are synced.
+    let execution_observer = Arc::new(ExecutionObserver::new(
+        10,
+        Some(dump_dir.path().to_path_buf()),
+    ));
+    let restarted_executor = BlockExecutor::<MockVM>::new(executor.db.clone())
+        .with_execution_observer(execution_observer.clone());
+    restarted_executor.reset().unwrap();
+    assert!(divergence_path.exists());
+
+    executor.commit_blocks(vec![block_id], ledger_info).unwrap();
+    restarted_executor.reset().unwrap();
+    assert!(!divergence_path.exists());
+    let report = std::fs::read_to_string(dump_dir.path().join("divergence_3.txt")).unwrap();
+    assert!(report.contains("expected_txn_info"));
+    assert!(report.contains("write_set"));
+    // The divergence is only reported once.
+    assert!(execution_observer
+        .diagnose(executor.db.reader.as_ref())
+        .unwrap()
+        .is_none());
+}
+
 /// Generates a list of `TransactionListWithProof`s according to the given ranges.
 fn create_transaction_chunks(
     chunk_ranges: Vec<std::ops::Range<Version>>,
@@ -1,27 +1,34 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use aptos_crypto::PrivateKey;
+use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
 use aptos_transaction_builder::aptos_stdlib::{
-    encode_mint_script_function, encode_set_version_script_function,
+    encode_create_account_script_function, encode_mint_script_function,
+    encode_set_version_script_function, encode_transfer_script_function,
 };
 use aptos_types::{
     account_config::aptos_root_address,
     account_state::AccountState,
     block_metadata::BlockMetadata,
     state_store::state_key::StateKey,
-    transaction::{Transaction, WriteSetPayload},
+    transaction::{authenticator::AuthenticationKey, Transaction, WriteSetPayload},
     trusted_state::TrustedState,
     validator_signer::ValidatorSigner,
 };
+use aptos_vm::AptosVM;
+use executor::{
+    block_executor::BlockExecutor, components::speculation_cache::SpeculationCache,
+    metrics::APTOS_EXECUTOR_SPECULATIVE_OUTPUTS, pre_executor::PreExecutor,
+};
 use executor_test_helpers::{
-    gen_block_id, gen_ledger_info_with_sigs, get_test_signed_transaction,
+    gen_block_id, gen_block_metadata, gen_ledger_info_with_sigs, get_test_signed_transaction,
     integration_test_impl::{
         create_db_and_executor, test_execution_with_storage_impl, verify_committed_txn_status,
     },
 };
-use executor_types::BlockExecutorTrait;
-use std::convert::TryFrom;
+use executor_types::{BlockExecutorTrait, TransactionPreExecutor};
+use rand::SeedableRng;
+use std::{convert::TryFrom, sync::Arc};
 
 #[test]
 fn test_genesis() {
@@ -119,6 +126,7 @@ fn test_reconfiguration() {
         300000001,
         vec![],
         validator_account,
+        vec![],
     ));
 
     // txn3 = set the aptos version
@@ -178,3 +186,132 @@ fn test_reconfiguration() {
 fn test_execution_with_storage() {
     test_execution_with_storage_impl();
 }
+
+#[test]
+fn test_pre_executed_outputs_invalidated_by_block_timestamp() {
+    let path = aptos_temppath::TempPath::new();
+    path.create_as_dir().unwrap();
+    let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+    let genesis_key = &vm_genesis::GENESIS_KEYPAIR.0;
+    let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+    let (_, db, executor, _waypoint) = create_db_and_executor(path.path(), &genesis_txn);
+    let signer = ValidatorSigner::new(validators[0].data.address, validators[0].key.clone());
+
+    let mut rng = ::rand::rngs::StdRng::from_seed([3u8; 32]);
+    let privkey1 = Ed25519PrivateKey::generate(&mut rng);
+    let pubkey1 = privkey1.public_key();
+    let account1 = AuthenticationKey::ed25519(&pubkey1).derived_address();
+    let pubkey2 = Ed25519PrivateKey::generate(&mut rng).public_key();
+    let account2 = AuthenticationKey::ed25519(&pubkey2).derived_address();
+    let privkey3 = Ed25519PrivateKey::generate(&mut rng);
+    let pubkey3 = privkey3.public_key();
+    let account3 = AuthenticationKey::ed25519(&pubkey3).derived_address();
+    let pubkey4 = Ed25519PrivateKey::generate(&mut rng).public_key();
+    let account4 = AuthenticationKey::ed25519(&pubkey4).derived_address();
+
+    // Block 1 creates the accounts and funds the senders.
+    let payloads = vec![
+        encode_create_account_script_function(account1),
+        encode_create_account_script_function(account2),
+        encode_create_account_script_function(account3),
+        encode_create_account_script_function(account4),
+        encode_mint_script_function(account1, 1_000_000),
+        encode_mint_script_function(account3, 1_000_000),
+    ];
+    let mut block1 = vec![Transaction::BlockMetadata(gen_block_metadata(
+        1,
+        signer.author(),
+    ))];
+    block1.extend(
+        payloads
+            .into_iter()
+            .enumerate()
+            .map(|(sequence_number, payload)| {
+                get_test_signed_transaction(
+                    aptos_root_address(),
+                    sequence_number as u64,
+                    genesis_key.clone(),
+                    genesis_key.public_key(),
+                    Some(payload),
+                )
+            }),
+    );
+    let block1_id = gen_block_id(1);
+    let output1 = executor
+        .execute_block((block1_id, block1), executor.committed_block_id())
+        .unwrap();
+    let ledger_info_with_sigs = gen_ledger_info_with_sigs(1, &output1, block1_id, vec![&signer]);
+    executor
+        .commit_blocks(vec![block1_id], ledger_info_with_sigs)
+        .unwrap();
+
+    // The transfers are pre-executed against block 1, reading `Timestamp::now_microseconds` in
+    // the prologue. The BlockMetadata transaction of block 2 updates the timestamp, so they are
+    // executed again.
+    let transfer1 = get_test_signed_transaction(
+        account1,
+        /* sequence_number = */ 0,
+        privkey1,
+        pubkey1,
+        Some(encode_transfer_script_function(account2, 10_000)),
+    );
+    let transfer2 = get_test_signed_transaction(
+        account3,
+        /* sequence_number = */ 0,
+        privkey3,
+        pubkey3,
+        Some(encode_transfer_script_function(account4, 20_000)),
+    );
+    let speculation_cache = Arc::new(SpeculationCache::new(10));
+    let signed_txns = [&transfer1, &transfer2]
+        .iter()
+        .map(|txn| match txn {
+            Transaction::UserTransaction(signed_txn) => signed_txn.clone(),
+            _ => unreachable!(),
+        })
+        .collect();
+    PreExecutor::<AptosVM>::new(db.reader.clone(), speculation_cache.clone(), 2)
+        .pre_execute(signed_txns);
+    assert_eq!(speculation_cache.len(), 2);
+
+    let speculative_executor =
+        BlockExecutor::<AptosVM>::new_with_speculation_cache(db, speculation_cache.clone());
+    let block2 = vec![
+        Transaction::BlockMetadata(gen_block_metadata(2, signer.author())),
+        transfer1,
+        transfer2,
+    ];
+    let block2_id = gen_block_id(2);
+    let expected_output2 = executor
+        .execute_block((block2_id, block2.clone()), block1_id)
+        .unwrap();
+    let reused = APTOS_EXECUTOR_SPECULATIVE_OUTPUTS
+        .with_label_values(&["reused"])
+        .get();
+    let invalidated = APTOS_EXECUTOR_SPECULATIVE_OUTPUTS
+        .with_label_values(&["invalidated"])
+        .get();
+    let output2 = speculative_executor
+        .execute_block((block2_id, block2), block1_id)
+        .unwrap();
+    assert_eq!(
+        APTOS_EXECUTOR_SPECULATIVE_OUTPUTS
+            .with_label_values(&["reused"])
+            .get(),
+        reused
+    );
+    // Outputs are reused only until the first invalidated one.
+    assert_eq!(
+        APTOS_EXECUTOR_SPECULATIVE_OUTPUTS
+            .with_label_values(&["invalidated"])
+            .get(),
+        invalidated + 1
+    );
+    assert_eq!(output2, expected_output2);
+
+    let ledger_info_with_sigs = gen_ledger_info_with_sigs(1, &output2, block2_id, vec![&signer]);
+    speculative_executor
+        .commit_blocks(vec![block2_id], ledger_info_with_sigs)
+        .unwrap();
+    assert!(speculation_cache.is_empty());
+}
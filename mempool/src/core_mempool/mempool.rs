@@ -181,12 +181,61 @@ impl Mempool {
     /// `batch_size` - size of requested block.
     /// `seen_txns` - transactions that were sent to Consensus but were not committed yet,
     ///  mempool should filter out such transactions.
-    #[allow(clippy::explicit_counter_loop)]
     pub(crate) fn get_block(
         &self,
         batch_size: u64,
         mut seen: HashSet<TxnPointer>,
     ) -> Vec<SignedTransaction> {
+        let seen_size = seen.len();
+        let (result, txn_walked) = self.select_block(batch_size, &mut seen);
+        let result_size = result.len();
+        // convert transaction pointers to real values
+        let mut block_log = TxnsLog::new();
+        let block: Vec<_> = result
+            .into_iter()
+            .filter_map(|(address, tx_seq)| {
+                block_log.add(address, tx_seq);
+                self.transactions.get(&address, tx_seq)
+            })
+            .collect();
+
+        debug!(
+            LogSchema::new(LogEntry::GetBlock).txns(block_log),
+            seen_consensus = seen_size,
+            walked = txn_walked,
+            seen_after = seen.len(),
+            result_size = result_size,
+            block_size = block.len()
+        );
+        for transaction in &block {
+            self.log_latency(
+                transaction.sender(),
+                transaction.sequence_number(),
+                counters::GET_BLOCK_STAGE_LABEL,
+            );
+        }
+        block
+    }
+
+    /// Returns the transactions a block of `batch_size` would start with if proposed now,
+    /// without recording them as pulled. Used to pre-execute transactions ahead of proposals.
+    pub(crate) fn peek_block(&self, batch_size: u64) -> Vec<SignedTransaction> {
+        let (result, _) = self.select_block(batch_size, &mut HashSet::new());
+        result
+            .into_iter()
+            .filter_map(|(address, tx_seq)| self.transactions.get(&address, tx_seq))
+            .collect()
+    }
+
+    /// Selects up to `batch_size` transactions in priority order that are not in `seen` and can
+    /// be executed in order, adding them to `seen`. Also returns the number of transactions
+    /// walked in the priority queue.
+    #[allow(clippy::explicit_counter_loop)]
+    fn select_block(
+        &self,
+        batch_size: u64,
+        seen: &mut HashSet<TxnPointer>,
+    ) -> (Vec<TxnPointer>, usize) {
         let mut result = vec![];
         // Helper DS. Helps to mitigate scenarios where account submits several transactions
         // with increasing gas price (e.g. user submits transactions with sequence number 1, 2
@@ -195,7 +244,6 @@ impl Mempool {
         // but can't be executed before first txn. Once observed, such txn will be saved in
         // `skipped` DS and rechecked once it's ancestor becomes available
         let mut skipped = HashSet::new();
-        let seen_size = seen.len();
         let mut txn_walked = 0usize;
         // iterate over the queue of transactions based on gas price
         'main: for txn in self.transactions.iter_queue() {
@@ -236,33 +284,7 @@ impl Mempool {
                 skipped.insert(TxnPointer::from(txn));
             }
         }
-        let result_size = result.len();
-        // convert transaction pointers to real values
-        let mut block_log = TxnsLog::new();
-        let block: Vec<_> = result
-            .into_iter()
-            .filter_map(|(address, tx_seq)| {
-                block_log.add(address, tx_seq);
-                self.transactions.get(&address, tx_seq)
-            })
-            .collect();
-
-        debug!(
-            LogSchema::new(LogEntry::GetBlock).txns(block_log),
-            seen_consensus = seen_size,
-            walked = txn_walked,
-            seen_after = seen.len(),
-            result_size = result_size,
-            block_size = block.len()
-        );
-        for transaction in &block {
-            self.log_latency(
-                transaction.sender(),
-                transaction.sequence_number(),
-                counters::GET_BLOCK_STAGE_LABEL,
-            );
-        }
-        block
+        (result, txn_walked)
     }
 
     /// Periodic core mempool garbage collection.
@@ -147,6 +147,7 @@ pub enum LogEntry {
     DBError,
     UnexpectedNetworkMsg,
     MempoolSnapshot,
+    PreExecutionRuntime,
 }
 
 #[derive(Clone, Copy, Serialize)]
@@ -21,6 +21,7 @@ use aptos_logger::prelude::*;
 use aptos_types::on_chain_config::OnChainConfigPayload;
 use bounded_executor::BoundedExecutor;
 use event_notifications::ReconfigNotificationListener;
+use executor_types::TransactionPreExecutor;
 use futures::{
     channel::mpsc,
     stream::{select_all, FuturesUnordered},
@@ -343,6 +344,43 @@ pub(crate) async fn gc_coordinator(mempool: Arc<Mutex<CoreMempool>>, gc_interval
     ));
 }
 
+/// Periodically pre-executes the transactions at the top of core mempool, so that their outputs
+/// are available when they get proposed.
+pub(crate) async fn pre_execution_coordinator(
+    mempool: Arc<Mutex<CoreMempool>>,
+    pre_executor: Arc<dyn TransactionPreExecutor>,
+    batch_size: u64,
+    pre_execution_interval_ms: u64,
+) {
+    info!(LogSchema::event_log(
+        LogEntry::PreExecutionRuntime,
+        LogEvent::Start
+    ));
+    let mut interval =
+        IntervalStream::new(interval(Duration::from_millis(pre_execution_interval_ms)));
+    while let Some(_interval) = interval.next().await {
+        let transactions = mempool.lock().peek_block(batch_size);
+        if transactions.is_empty() {
+            continue;
+        }
+        // Execution is CPU bound, keep it off the async runtime.
+        let pre_executor = pre_executor.clone();
+        if let Err(err) =
+            tokio::task::spawn_blocking(move || pre_executor.pre_execute(transactions)).await
+        {
+            error!(
+                LogSchema::new(LogEntry::PreExecutionRuntime),
+                "Pre-execution task failed: {:?}", err
+            );
+        }
+    }
+
+    error!(LogSchema::event_log(
+        LogEntry::PreExecutionRuntime,
+        LogEvent::Terminated
+    ));
+}
+
 /// Periodically logs a snapshot of transactions in core mempool.
 /// In the future we may want an interactive way to directly query mempool's internal state.
 /// For now, we will rely on this periodic snapshot to observe the internal state.
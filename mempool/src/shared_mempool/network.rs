@@ -29,6 +29,7 @@ use network::{
     application::{
         interface::{MultiNetworkSender, NetworkInterface},
         storage::{LockingHashMap, PeerMetadataStorage},
+        types::PeerMonitoringMetadata,
     },
     error::NetworkError,
     peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
@@ -93,7 +94,10 @@ pub struct MempoolNetworkSender {
 
 pub fn network_endpoint_config(max_broadcasts_per_peer: usize) -> AppConfig {
     AppConfig::p2p(
-        [ProtocolId::MempoolDirectSend],
+        [
+            ProtocolId::MempoolDirectSend,
+            ProtocolId::MempoolDirectSendCompressed,
+        ],
         aptos_channel::Config::new(max_broadcasts_per_peer)
             .queue_style(QueueStyle::KLAST)
             .counters(&counters::PENDING_MEMPOOL_NETWORK_EVENTS),
@@ -135,6 +139,21 @@ impl ApplicationNetworkSender<MempoolSyncMsg> for MempoolNetworkSender {
             .send_rpc(recipient, protocol, req_msg, timeout)
             .await
     }
+
+    async fn send_rpc_with_size(
+        &self,
+        recipient: PeerId,
+        req_msg: MempoolSyncMsg,
+        timeout: Duration,
+    ) -> Result<(MempoolSyncMsg, usize), RpcError> {
+        fail_point!("mempool::send_to", |_| {
+            Err(anyhow::anyhow!("Injected error in mempool::send_rpc").into())
+        });
+        let protocol = ProtocolId::MempoolRpc;
+        self.inner
+            .send_rpc_with_size(recipient, protocol, req_msg, timeout)
+            .await
+    }
 }
 
 #[derive(Debug, Error)]
@@ -224,18 +243,25 @@ impl MempoolNetworkInterface {
             let peer_states = self.sync_states.read_all();
             peer_states
                 .iter()
-                .map(|(peer, state)| (*peer, state.metadata.role))
+                .map(|(peer, state)| {
+                    let monitoring_metadata = self
+                        .peer_metadata_storage
+                        .read(*peer)
+                        .map(|peer_info| peer_info.monitoring_metadata)
+                        .unwrap_or_default();
+                    (*peer, state.metadata.role, monitoring_metadata)
+                })
                 .collect()
         };
 
-        // Order peers by network and by type
+        // Order peers by network, by type and by proximity
         // Origin doesn't matter at this point, only inserted ones into peer_states are upstream
         // Validators will always have the full set
         let mut prioritized_peers = self.prioritized_peers.lock();
         let peers: Vec<_> = peers
             .iter()
             .sorted_by(|peer_a, peer_b| compare_prioritized_peers(peer_a, peer_b))
-            .map(|(peer, _)| *peer)
+            .map(|(peer, _, _)| *peer)
             .collect();
         let _ = std::mem::replace(&mut *prioritized_peers, peers);
     }
@@ -558,8 +584,8 @@ impl NetworkInterface<MempoolSyncMsg, MempoolMultiNetworkSender> for MempoolNetw
 
 /// Provides ordering for peers to send transactions to
 fn compare_prioritized_peers(
-    peer_a: &(PeerNetworkId, PeerRole),
-    peer_b: &(PeerNetworkId, PeerRole),
+    peer_a: &(PeerNetworkId, PeerRole, PeerMonitoringMetadata),
+    peer_b: &(PeerNetworkId, PeerRole, PeerMonitoringMetadata),
 ) -> Ordering {
     let peer_network_id_a = peer_a.0;
     let peer_network_id_b = peer_b.0;
@@ -574,12 +600,16 @@ fn compare_prioritized_peers(
             let role_a = peer_a.1;
             let role_b = peer_b.1;
             match role_a.cmp(&role_b) {
-                // Then tiebreak by PeerId for stability
-                Ordering::Equal => {
-                    let peer_id_a = peer_network_id_a.peer_id();
-                    let peer_id_b = peer_network_id_b.peer_id();
-                    peer_id_a.cmp(&peer_id_b)
-                }
+                // Then prefer the peers closest to the validators and with the lowest latency
+                Ordering::Equal => match peer_a.2.cmp_proximity(&peer_b.2) {
+                    // Then tiebreak by PeerId for stability
+                    Ordering::Equal => {
+                        let peer_id_a = peer_network_id_a.peer_id();
+                        let peer_id_b = peer_network_id_b.peer_id();
+                        peer_id_a.cmp(&peer_id_b)
+                    }
+                    ordering => ordering,
+                },
                 ordering => ordering,
             }
         }
@@ -600,18 +630,22 @@ mod test {
         let val_1 = (
             PeerNetworkId::new(NetworkId::Vfn, peer_id_1),
             PeerRole::Validator,
+            PeerMonitoringMetadata::default(),
         );
         let val_2 = (
             PeerNetworkId::new(NetworkId::Vfn, peer_id_2),
             PeerRole::Validator,
+            PeerMonitoringMetadata::default(),
         );
         let vfn_1 = (
             PeerNetworkId::new(NetworkId::Public, peer_id_1),
             PeerRole::ValidatorFullNode,
+            PeerMonitoringMetadata::default(),
         );
         let preferred_1 = (
             PeerNetworkId::new(NetworkId::Public, peer_id_1),
             PeerRole::PreferredUpstream,
+            PeerMonitoringMetadata::default(),
         );
 
         // NetworkId ordering
@@ -628,6 +662,22 @@ mod test {
             compare_prioritized_peers(&preferred_1, &vfn_1)
         );
 
+        // Proximity ordering, unmeasured peers last
+        let mut close_2 = val_2.clone();
+        close_2.2.distance_from_validators = Some(1);
+        close_2.2.average_ping_latency_secs = Some(0.5);
+        let mut fast_1 = val_1.clone();
+        fast_1.2.distance_from_validators = Some(1);
+        fast_1.2.average_ping_latency_secs = Some(0.1);
+        let mut far_1 = val_1.clone();
+        far_1.2.distance_from_validators = Some(2);
+        assert_eq!(Ordering::Less, compare_prioritized_peers(&close_2, &val_1));
+        assert_eq!(Ordering::Less, compare_prioritized_peers(&fast_1, &close_2));
+        assert_eq!(
+            Ordering::Greater,
+            compare_prioritized_peers(&far_1, &close_2)
+        );
+
         // Tiebreaker on peer_id
         assert_eq!(Ordering::Greater, compare_prioritized_peers(&val_2, &val_1));
         assert_eq!(Ordering::Less, compare_prioritized_peers(&val_1, &val_2));
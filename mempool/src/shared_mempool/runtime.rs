@@ -5,7 +5,7 @@ use crate::{
     core_mempool::CoreMempool,
     network::{MempoolNetworkEvents, MempoolNetworkSender},
     shared_mempool::{
-        coordinator::{coordinator, gc_coordinator, snapshot_job},
+        coordinator::{coordinator, gc_coordinator, pre_execution_coordinator, snapshot_job},
         types::{MempoolEventsReceiver, SharedMempool, SharedMempoolNotification},
     },
     ConsensusRequest,
@@ -14,6 +14,7 @@ use aptos_config::{config::NodeConfig, network_id::NetworkId};
 use aptos_infallible::{Mutex, RwLock};
 
 use event_notifications::ReconfigNotificationListener;
+use executor_types::TransactionPreExecutor;
 use futures::channel::mpsc::{self, Receiver, UnboundedSender};
 use mempool_notifications::MempoolNotificationListener;
 use network::application::storage::PeerMetadataStorage;
@@ -84,6 +85,21 @@ pub(crate) fn start_shared_mempool<V>(
     ));
 }
 
+/// Starts pre-executing the transactions at the top of `mempool` with `pre_executor`.
+pub(crate) fn start_pre_execution(
+    executor: &Handle,
+    config: &NodeConfig,
+    mempool: Arc<Mutex<CoreMempool>>,
+    pre_executor: Arc<dyn TransactionPreExecutor>,
+) {
+    executor.spawn(pre_execution_coordinator(
+        mempool,
+        pre_executor,
+        config.execution.pre_execution.batch_size,
+        config.execution.pre_execution.interval_ms,
+    ));
+}
+
 pub fn bootstrap(
     config: &NodeConfig,
     db: Arc<dyn DbReader>,
@@ -95,6 +111,7 @@ pub fn bootstrap(
     mempool_listener: MempoolNotificationListener,
     mempool_reconfig_events: ReconfigNotificationListener,
     peer_metadata_storage: Arc<PeerMetadataStorage>,
+    pre_executor: Option<Arc<dyn TransactionPreExecutor>>,
 ) -> Runtime {
     let runtime = Builder::new_multi_thread()
         .thread_name("shared-mem")
@@ -103,6 +120,9 @@ pub fn bootstrap(
         .expect("[shared mempool] failed to create runtime");
     let mempool = Arc::new(Mutex::new(CoreMempool::new(config)));
     let vm_validator = Arc::new(RwLock::new(VMValidator::new(Arc::clone(&db))));
+    if let Some(pre_executor) = pre_executor {
+        start_pre_execution(runtime.handle(), config, mempool.clone(), pre_executor);
+    }
     start_shared_mempool(
         runtime.handle(),
         config,
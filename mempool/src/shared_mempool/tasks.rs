@@ -26,7 +26,7 @@ use aptos_types::{
     vm_status::DiscardedVMStatus,
 };
 use futures::{channel::oneshot, stream::FuturesUnordered};
-use network::application::interface::NetworkInterface;
+use network::application::{interface::NetworkInterface, reputation::Severity};
 use rayon::prelude::*;
 use std::{
     cmp,
@@ -157,6 +157,20 @@ pub(crate) async fn process_transaction_broadcast<V>(
     let results = process_incoming_transactions(&smp, transactions, timeline_state);
     log_txn_process_results(&results, Some(peer));
 
+    // Honest peers validate the transactions before broadcasting them
+    if results
+        .iter()
+        .any(|(_, (_, vm_status))| *vm_status == Some(DiscardedVMStatus::INVALID_SIGNATURE))
+    {
+        smp.network_interface
+            .peer_metadata_storage()
+            .report_misbehavior(
+                peer,
+                Severity::Medium,
+                "broadcast transactions with invalid signatures",
+            );
+    }
+
     let ack_response = gen_ack_response(request_id, results, &peer);
     let network_sender = smp.network_interface.sender();
     if let Err(e) = network_sender.send_to(peer, ack_response) {
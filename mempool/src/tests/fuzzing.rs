@@ -7,6 +7,7 @@ use crate::{
 };
 use aptos_config::{config::NodeConfig, network_id::NetworkId};
 use aptos_infallible::{Mutex, RwLock};
+use aptos_time_service::TimeService;
 use aptos_types::transaction::SignedTransaction;
 use network::application::storage::PeerMetadataStorage;
 use proptest::{
@@ -44,7 +45,7 @@ pub fn test_mempool_process_incoming_transactions_impl(
         vm_validator,
         vec![],
         config.base.role,
-        PeerMetadataStorage::new(&[NetworkId::Validator]),
+        PeerMetadataStorage::new(&[NetworkId::Validator], TimeService::real()),
     );
 
     let _ = tasks::process_incoming_transactions(&smp, txns, timeline_state);
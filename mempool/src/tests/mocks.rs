@@ -13,6 +13,7 @@ use aptos_config::{
     network_id::NetworkId,
 };
 use aptos_infallible::{Mutex, RwLock};
+use aptos_time_service::TimeService;
 use aptos_types::{
     account_config::AccountSequenceInfo, mempool_status::MempoolStatusCode,
     on_chain_config::ON_CHAIN_CONFIG_REGISTRY, transaction::SignedTransaction,
@@ -120,7 +121,8 @@ impl MockSharedMempool {
         );
         let reconfig_event_subscriber = event_subscriber.subscribe_to_reconfigurations().unwrap();
         let network_handles = vec![(NetworkId::Validator, network_sender, network_events)];
-        let peer_metadata_storage = PeerMetadataStorage::new(&[NetworkId::Validator]);
+        let peer_metadata_storage =
+            PeerMetadataStorage::new(&[NetworkId::Validator], TimeService::real());
 
         start_shared_mempool(
             handle,
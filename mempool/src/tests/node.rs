@@ -15,6 +15,7 @@ use aptos_config::{
 };
 use aptos_crypto::{x25519::PrivateKey, Uniform};
 use aptos_infallible::{Mutex, MutexGuard, RwLock};
+use aptos_time_service::TimeService;
 use aptos_types::{
     account_config::AccountSequenceInfo, on_chain_config::ON_CHAIN_CONFIG_REGISTRY, PeerId,
 };
@@ -527,7 +528,7 @@ fn setup_node_network_interfaces(
         .iter()
         .map(|(network_id, _, _)| *network_id)
         .collect();
-    let peer_metadata_storage = PeerMetadataStorage::new(&network_ids);
+    let peer_metadata_storage = PeerMetadataStorage::new(&network_ids, TimeService::real());
     (network_interfaces, network_handles, peer_metadata_storage)
 }
 
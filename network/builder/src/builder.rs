@@ -9,12 +9,15 @@
 //! authentication -- a network end-point running with remote authentication enabled will
 //! connect to or accept connections from an end-point running in authenticated mode as
 //! long as the latter is in its trusted peers set.
+#[cfg(feature = "testing")]
+use crate::fault_injection::FaultInjectionReloader;
 use aptos_config::{
     config::{
-        DiscoveryMethod, NetworkConfig, Peer, PeerRole, PeerSet, RateLimitConfig, RoleType,
-        CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS, MAX_CONCURRENT_NETWORK_REQS,
-        MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE, MAX_FULLNODE_OUTBOUND_CONNECTIONS,
-        MAX_INBOUND_CONNECTIONS, NETWORK_CHANNEL_SIZE,
+        DiscoveryMethod, Error, FaultInjectionConfig, NetworkConfig, Peer, PeerRole, PeerSet,
+        RateLimitConfig, RoleType, CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS,
+        MAX_CONCURRENT_NETWORK_REQS, MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE,
+        MAX_FULLNODE_OUTBOUND_CONNECTIONS, MAX_INBOUND_CONNECTIONS, MAX_MESSAGE_SIZE,
+        NETWORK_CHANNEL_SIZE,
     },
     network_id::NetworkContext,
 };
@@ -24,8 +27,10 @@ use aptos_logger::prelude::*;
 use aptos_time_service::TimeService;
 use aptos_types::{chain_id::ChainId, network_address::NetworkAddress};
 use event_notifications::{EventSubscriptionService, ReconfigNotificationListener};
+#[cfg(feature = "testing")]
+use netcore::transport::fault_injection::FaultController;
 use network::{
-    application::storage::PeerMetadataStorage,
+    application::storage::{MisbehaviorReporter, PeerMetadataStorage},
     connectivity_manager::{builder::ConnectivityManagerBuilder, ConnectivityRequest},
     logging::NetworkSchema,
     peer_manager::{
@@ -35,6 +40,7 @@ use network::{
     protocols::{
         health_checker::{self, builder::HealthCheckerBuilder},
         network::{AppConfig, NewNetworkEvents, NewNetworkSender},
+        peer_monitoring::{self, builder::PeerMonitorBuilder, SyncedVersionReader},
     },
 };
 use network_discovery::DiscoveryChangeListener;
@@ -42,6 +48,7 @@ use std::{
     clone::Clone,
     collections::{HashMap, HashSet},
     sync::Arc,
+    time::Duration,
 };
 use tokio::runtime::Handle;
 
@@ -65,8 +72,11 @@ pub struct NetworkBuilder {
     discovery_listeners: Option<Vec<DiscoveryChangeListener>>,
     connectivity_manager_builder: Option<ConnectivityManagerBuilder>,
     health_checker_builder: Option<HealthCheckerBuilder>,
+    peer_monitor_builder: Option<PeerMonitorBuilder>,
     peer_manager_builder: PeerManagerBuilder,
     peer_metadata_storage: Arc<PeerMetadataStorage>,
+    #[cfg(feature = "testing")]
+    fault_injection_reloader: Option<FaultInjectionReloader>,
 }
 
 impl NetworkBuilder {
@@ -82,6 +92,7 @@ impl NetworkBuilder {
         listen_address: NetworkAddress,
         authentication_mode: AuthenticationMode,
         max_frame_size: usize,
+        max_message_size: usize,
         enable_proxy_protocol: bool,
         network_channel_size: usize,
         max_concurrent_network_reqs: usize,
@@ -102,6 +113,7 @@ impl NetworkBuilder {
             network_channel_size,
             max_concurrent_network_reqs,
             max_frame_size,
+            max_message_size,
             enable_proxy_protocol,
             inbound_connection_limit,
             inbound_rate_limit_config,
@@ -116,8 +128,11 @@ impl NetworkBuilder {
             discovery_listeners: None,
             connectivity_manager_builder: None,
             health_checker_builder: None,
+            peer_monitor_builder: None,
             peer_manager_builder,
             peer_metadata_storage,
+            #[cfg(feature = "testing")]
+            fault_injection_reloader: None,
         }
     }
 
@@ -142,6 +157,7 @@ impl NetworkBuilder {
             listen_address,
             authentication_mode,
             MAX_FRAME_SIZE,
+            MAX_MESSAGE_SIZE,
             false, /* Disable proxy protocol */
             NETWORK_CHANNEL_SIZE,
             MAX_CONCURRENT_NETWORK_REQS,
@@ -164,7 +180,8 @@ impl NetworkBuilder {
         builder
     }
 
-    /// Create a new NetworkBuilder based on the provided configuration.
+    /// Create a new NetworkBuilder based on the provided configuration. Fails if the
+    /// configuration is invalid.
     pub fn create(
         chain_id: ChainId,
         role: RoleType,
@@ -172,7 +189,7 @@ impl NetworkBuilder {
         time_service: TimeService,
         mut reconfig_subscription_service: Option<&mut EventSubscriptionService>,
         peer_metadata_storage: Arc<PeerMetadataStorage>,
-    ) -> NetworkBuilder {
+    ) -> Result<NetworkBuilder, Error> {
         let peer_id = config.peer_id();
         let identity_key = config.identity_key();
         let pubkey = identity_key.public_key();
@@ -187,6 +204,10 @@ impl NetworkBuilder {
 
         let trusted_peers = Arc::new(RwLock::new(HashMap::new()));
 
+        peer_metadata_storage
+            .reputation()
+            .set_config(config.network_id, config.peer_reputation);
+
         let mut network_builder = NetworkBuilder::new(
             chain_id,
             trusted_peers.clone(),
@@ -196,6 +217,7 @@ impl NetworkBuilder {
             config.listen_address.clone(),
             authentication_mode,
             config.max_frame_size,
+            config.max_message_size,
             config.enable_proxy_protocol,
             config.network_channel_size,
             config.max_concurrent_network_reqs,
@@ -203,12 +225,24 @@ impl NetworkBuilder {
             config.inbound_rate_limit_config,
             config.outbound_rate_limit_config,
         );
+        network_builder
+            .peer_manager_builder
+            .set_protocol_rate_limits(
+                &config.inbound_protocol_rate_limits,
+                &config.outbound_protocol_rate_limits,
+            )?;
+
+        if let Some(fault_injection) = &config.fault_injection {
+            network_builder.add_fault_injection(fault_injection.clone());
+        }
 
         network_builder.add_connection_monitoring(
             config.ping_interval_ms,
             config.ping_timeout_ms,
             config.ping_failures_tolerated,
         );
+        network_builder
+            .add_peer_monitoring(config.peer_monitoring_interval_ms, config.ping_timeout_ms);
 
         // Always add a connectivity manager to keep track of known peers
         let seeds = merge_seeds(config);
@@ -258,7 +292,7 @@ impl NetworkBuilder {
             network_builder.discovery_listeners.as_ref().unwrap().len()
         );
 
-        network_builder
+        Ok(network_builder)
     }
 
     /// Create the configured Networking components.
@@ -299,11 +333,28 @@ impl NetworkBuilder {
             );
         }
 
+        if let Some(peer_monitor_builder) = self.peer_monitor_builder.as_mut() {
+            peer_monitor_builder.start(executor);
+            debug!(
+                NetworkSchema::new(&self.network_context),
+                "{} Started peer monitor", self.network_context
+            );
+        }
+
         if let Some(discovery_listeners) = self.discovery_listeners.take() {
             discovery_listeners
                 .into_iter()
                 .for_each(|listener| listener.start(executor))
         }
+
+        #[cfg(feature = "testing")]
+        if let Some(fault_injection_reloader) = self.fault_injection_reloader.take() {
+            fault_injection_reloader.start(executor);
+            debug!(
+                NetworkSchema::new(&self.network_context),
+                "{} Started fault injection reloader", self.network_context
+            );
+        }
         self
     }
 
@@ -362,6 +413,7 @@ impl NetworkBuilder {
             pm_conn_mgr_notifs_rx,
             outbound_connection_limit,
             mutual_authentication,
+            self.peer_metadata_storage.clone(),
         ));
         self
     }
@@ -394,6 +446,13 @@ impl NetworkBuilder {
                 *interval_duration,
                 self.time_service.clone(),
             ),
+            DiscoveryMethod::Rest(rest_discovery) => DiscoveryChangeListener::rest(
+                self.network_context,
+                conn_mgr_reqs_tx,
+                rest_discovery.url.clone(),
+                Duration::from_secs(rest_discovery.interval_secs),
+                self.time_service.clone(),
+            ),
             DiscoveryMethod::None => return,
         };
 
@@ -403,6 +462,30 @@ impl NetworkBuilder {
             .push(listener);
     }
 
+    /// Inject the network faults reloaded from the configured file in the dialed connections.
+    #[cfg(feature = "testing")]
+    fn add_fault_injection(&mut self, config: FaultInjectionConfig) -> &mut Self {
+        let controller = FaultController::default();
+        self.peer_manager_builder.inject_faults(controller.clone());
+        self.fault_injection_reloader = Some(FaultInjectionReloader::new(
+            self.network_context,
+            self.time_service.clone(),
+            config,
+            controller,
+        ));
+        self
+    }
+
+    #[cfg(not(feature = "testing"))]
+    fn add_fault_injection(&mut self, _config: FaultInjectionConfig) -> &mut Self {
+        warn!(
+            NetworkSchema::new(&self.network_context),
+            "{} fault_injection is set in config, but the binary doesn't compile with this feature",
+            self.network_context
+        );
+        self
+    }
+
     /// Add a HealthChecker to the network.
     fn add_connection_monitoring(
         &mut self,
@@ -430,6 +513,42 @@ impl NetworkBuilder {
         self
     }
 
+    /// Add a PeerMonitor to the network.
+    fn add_peer_monitoring(
+        &mut self,
+        monitoring_interval_ms: u64,
+        request_timeout_ms: u64,
+    ) -> &mut Self {
+        let (pm_network_tx, pm_network_rx) =
+            self.add_p2p_service(&peer_monitoring::network_endpoint_config());
+        self.peer_monitor_builder = Some(PeerMonitorBuilder::new(
+            self.network_context(),
+            self.time_service.clone(),
+            monitoring_interval_ms,
+            request_timeout_ms,
+            pm_network_tx,
+            pm_network_rx,
+            self.peer_metadata_storage.clone(),
+        ));
+        debug!(
+            NetworkSchema::new(&self.network_context),
+            "{} Created peer monitor", self.network_context
+        );
+        self
+    }
+
+    /// Set how the PeerMonitor reads the highest synced version it reports to the peers.
+    pub fn set_synced_version_reader(
+        &mut self,
+        synced_version_reader: SyncedVersionReader,
+    ) -> &mut Self {
+        assert_eq!(self.state, State::CREATED);
+        if let Some(peer_monitor_builder) = self.peer_monitor_builder.as_mut() {
+            peer_monitor_builder.set_synced_version_reader(synced_version_reader);
+        }
+        self
+    }
+
     /// Register a new Peer-to-Peer (both client and service) application with
     /// network and return the specialized client and service interfaces.
     pub fn add_p2p_service<SenderT: NewNetworkSender, EventsT: NewNetworkEvents>(
@@ -453,7 +572,15 @@ impl NetworkBuilder {
     pub fn add_service<EventsT: NewNetworkEvents>(&mut self, config: &AppConfig) -> EventsT {
         let (peer_mgr_reqs_rx, connection_notifs_rx) =
             self.peer_manager_builder.add_service(config);
-        EventsT::new(peer_mgr_reqs_rx, connection_notifs_rx)
+        let misbehavior_reporter = MisbehaviorReporter::new(
+            self.network_context.network_id(),
+            self.peer_metadata_storage.clone(),
+        );
+        EventsT::new_with_reporter(
+            peer_mgr_reqs_rx,
+            connection_notifs_rx,
+            Some(misbehavior_reporter),
+        )
     }
 }
 
@@ -91,6 +91,18 @@ impl ApplicationNetworkSender<DummyMsg> for DummyNetworkSender {
             .send_rpc(recipient, protocol, message, timeout)
             .await
     }
+
+    async fn send_rpc_with_size(
+        &self,
+        recipient: PeerId,
+        message: DummyMsg,
+        timeout: Duration,
+    ) -> Result<(DummyMsg, usize), RpcError> {
+        let protocol = TEST_RPC_PROTOCOL;
+        self.inner
+            .send_rpc_with_size(recipient, protocol, message, timeout)
+            .await
+    }
 }
 
 pub struct DummyNetwork {
@@ -134,7 +146,8 @@ pub fn setup_network() -> DummyNetwork {
 
     let trusted_peers = Arc::new(RwLock::new(HashMap::new()));
     let authentication_mode = AuthenticationMode::Mutual(listener_identity_private_key);
-    let peer_metadata_storage = PeerMetadataStorage::new(&[NetworkId::Validator]);
+    let peer_metadata_storage =
+        PeerMetadataStorage::new(&[NetworkId::Validator], TimeService::real());
     // Set up the listener network
     let network_context = NetworkContext::new(role, network_id, listener_peer_id);
     let mut network_builder = NetworkBuilder::new_for_test(
@@ -165,7 +178,8 @@ pub fn setup_network() -> DummyNetwork {
     let network_context = NetworkContext::new(role, network_id, dialer_peer_id);
 
     let trusted_peers = Arc::new(RwLock::new(HashMap::new()));
-    let peer_metadata_storage = PeerMetadataStorage::new(&[NetworkId::Validator]);
+    let peer_metadata_storage =
+        PeerMetadataStorage::new(&[NetworkId::Validator], TimeService::real());
 
     let mut network_builder = NetworkBuilder::new_for_test(
         chain_id,
@@ -0,0 +1,89 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reloads the network faults injected by a node from the file written by the test harness.
+
+use aptos_config::{
+    config::{FaultInjectionConfig, LinkFaults, NetworkFaults, PersistableConfig},
+    network_id::NetworkContext,
+};
+use aptos_logger::prelude::*;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use futures::StreamExt;
+use netcore::transport::fault_injection::{FaultConfig, FaultController};
+use network::logging::NetworkSchema;
+use std::time::Duration;
+use tokio::runtime::Handle;
+
+pub struct FaultInjectionReloader {
+    network_context: NetworkContext,
+    time_service: TimeService,
+    config: FaultInjectionConfig,
+    controller: FaultController,
+}
+
+impl FaultInjectionReloader {
+    pub fn new(
+        network_context: NetworkContext,
+        time_service: TimeService,
+        config: FaultInjectionConfig,
+        controller: FaultController,
+    ) -> Self {
+        Self {
+            network_context,
+            time_service,
+            config,
+            controller,
+        }
+    }
+
+    pub fn start(self, executor: &Handle) {
+        executor.spawn(self.run());
+    }
+
+    async fn run(self) {
+        let mut interval = self
+            .time_service
+            .interval(Duration::from_millis(self.config.reload_interval_ms));
+        while interval.next().await.is_some() {
+            self.reload();
+        }
+    }
+
+    fn reload(&self) {
+        // A missing file means that no faults are injected
+        let faults = if self.config.faults_path.exists() {
+            match NetworkFaults::load_config(&self.config.faults_path) {
+                Ok(faults) => faults,
+                Err(error) => {
+                    warn!(
+                        NetworkSchema::new(&self.network_context),
+                        "{} Unable to load the network faults: {}", self.network_context, error
+                    );
+                    return;
+                }
+            }
+        } else {
+            NetworkFaults::default()
+        };
+
+        self.controller.set_faults(
+            to_fault_config(&faults.default_faults),
+            faults
+                .link_faults
+                .iter()
+                .map(|link| (link.peer_a, link.peer_b, to_fault_config(&link.faults))),
+        );
+    }
+}
+
+fn to_fault_config(faults: &LinkFaults) -> FaultConfig {
+    FaultConfig {
+        latency: Duration::from_millis(faults.latency_ms),
+        jitter: Duration::from_millis(faults.jitter_ms),
+        bandwidth_bytes_per_sec: faults.bandwidth_bytes_per_sec,
+        stall_probability: faults.stall_probability,
+        stall_duration: Duration::from_millis(faults.stall_duration_ms),
+        reset_probability: faults.reset_probability,
+    }
+}
@@ -3,6 +3,8 @@
 
 pub use network::protocols::rpc::error::RpcError;
 pub mod builder;
+#[cfg(feature = "testing")]
+mod fault_injection;
 
 // TODO:  This module should be test-only, e.g., #[cfg(any(feature = "testing", test))]
 // At present it cannot be because network_builder must be a separate crate and the current
@@ -0,0 +1,214 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{validator_set::extract_validator_set_updates, DiscoveryError};
+use aptos_config::{config::PeerSet, network_id::NetworkContext};
+use aptos_rest_client::Client;
+use aptos_time_service::{Interval, TimeService, TimeServiceTrait};
+use aptos_types::{
+    account_config::aptos_root_address, account_state::AccountState,
+    account_state_blob::AccountStateBlob, on_chain_config::ValidatorSet,
+};
+use futures::{future::BoxFuture, Future, Stream};
+use std::{
+    convert::TryFrom,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use url::Url;
+
+/// Periodically fetches the `ValidatorSet` from the REST endpoint of a node, so that a node
+/// that isn't synced yet can discover the current validators.
+///
+/// The `ValidatorSet` is taken as served, it isn't verified against a trusted ledger info. So
+/// it's only used on the networks whose peers aren't validators, the validator network only
+/// trusts the on-chain validator set.
+pub struct RestStream {
+    network_context: NetworkContext,
+    rest_client: Client,
+    interval: Pin<Box<Interval>>,
+    /// The request in flight, if any.
+    request: Option<BoxFuture<'static, Result<ValidatorSet, DiscoveryError>>>,
+}
+
+impl RestStream {
+    pub(crate) fn new(
+        network_context: NetworkContext,
+        rest_url: Url,
+        interval_duration: Duration,
+        time_service: TimeService,
+    ) -> Self {
+        assert!(
+            !network_context.network_id().is_validator_network(),
+            "REST discovery is not allowed on the validator network"
+        );
+        RestStream {
+            network_context,
+            rest_client: Client::new(rest_url),
+            interval: Box::pin(time_service.interval(interval_duration)),
+            request: None,
+        }
+    }
+}
+
+impl Stream for RestStream {
+    type Item = Result<PeerSet, DiscoveryError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(request) = self.request.as_mut() {
+                let result = futures::ready!(request.as_mut().poll(cx));
+                self.request = None;
+                let network_context = self.network_context;
+                return Poll::Ready(Some(result.map(|validator_set| {
+                    extract_validator_set_updates(network_context, validator_set)
+                })));
+            }
+
+            // Wait for delay, then start the next request
+            futures::ready!(self.interval.as_mut().poll_next(cx));
+            self.request = Some(Box::pin(fetch_validator_set(self.rest_client.clone())));
+        }
+    }
+}
+
+/// Fetches the `ValidatorSet` from the account state of the root account
+async fn fetch_validator_set(rest_client: Client) -> Result<ValidatorSet, DiscoveryError> {
+    let blob = rest_client
+        .get_account_state_blob(aptos_root_address())
+        .await
+        .map_err(DiscoveryError::Rest)?;
+    let account_state_blob: AccountStateBlob = blob.into_inner().into();
+    let account_state = AccountState::try_from(&account_state_blob)
+        .map_err(|err| DiscoveryError::Parsing(err.to_string()))?;
+    account_state
+        .get_validator_set()
+        .map_err(|err| DiscoveryError::Parsing(err.to_string()))?
+        .ok_or_else(|| DiscoveryError::Parsing("No validator set".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_config::{
+        config::{Peer, PeerRole, RoleType},
+        network_id::NetworkId,
+    };
+    use aptos_crypto::{ed25519::Ed25519PrivateKey, x25519, PrivateKey, Uniform};
+    use aptos_types::{
+        network_address::NetworkAddress,
+        on_chain_config::{access_path_for_config, OnChainConfig},
+        validator_config::ValidatorConfig,
+        validator_info::ValidatorInfo,
+        PeerId,
+    };
+    use futures::StreamExt;
+    use warp::Filter;
+
+    fn network_context() -> NetworkContext {
+        NetworkContext::new(RoleType::FullNode, NetworkId::Public, PeerId::random())
+    }
+
+    /// Serves the given account state as the blob of the root account, the way the REST API of
+    /// a node does.
+    fn serve_account_state(account_state: &AccountState) -> Url {
+        let blob = bcs::to_bytes(account_state).unwrap();
+        let route = warp::path!("accounts" / String / "blob").map(move |_address: String| {
+            let reply = warp::reply::json(&blob);
+            let reply = warp::reply::with_header(reply, "X-Aptos-Chain-Id", "4");
+            let reply = warp::reply::with_header(reply, "X-Aptos-Epoch", "1");
+            let reply = warp::reply::with_header(reply, "X-Aptos-Ledger-Version", "10");
+            warp::reply::with_header(reply, "X-Aptos-Ledger-TimestampUsec", "100")
+        });
+        let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        Url::parse(&format!("http://{}", address)).unwrap()
+    }
+
+    fn serve_validator_set(validator_set: &ValidatorSet) -> Url {
+        let mut account_state = AccountState::default();
+        account_state.insert(
+            access_path_for_config(ValidatorSet::CONFIG_ID).path,
+            bcs::to_bytes(validator_set).unwrap(),
+        );
+        serve_account_state(&account_state)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_validator_set() {
+        let peer_id = PeerId::random();
+        let pubkey = x25519::PrivateKey::generate_for_testing().public_key();
+        let addresses = vec![NetworkAddress::mock().append_prod_protos(pubkey, 0)];
+        let validator_set = ValidatorSet::new(vec![ValidatorInfo::new(
+            peer_id,
+            1,
+            ValidatorConfig::new(
+                Ed25519PrivateKey::generate_for_testing().public_key(),
+                bcs::to_bytes(&addresses).unwrap(),
+                bcs::to_bytes(&addresses).unwrap(),
+            ),
+        )]);
+        let mut stream = RestStream::new(
+            network_context(),
+            serve_validator_set(&validator_set),
+            Duration::from_millis(5),
+            TimeService::real(),
+        );
+
+        let expected: PeerSet = [(
+            peer_id,
+            Peer::from_addrs(PeerRole::ValidatorFullNode, addresses),
+        )]
+        .iter()
+        .cloned()
+        .collect();
+        for _ in 0..2 {
+            match stream.next().await {
+                Some(Ok(peer_set)) => assert_eq!(peer_set, expected),
+                update => panic!("Expected the peer set, received: {:?}", update),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_validator_set() {
+        let mut stream = RestStream::new(
+            network_context(),
+            serve_account_state(&AccountState::default()),
+            Duration::from_millis(5),
+            TimeService::real(),
+        );
+        match stream.next().await {
+            Some(Err(DiscoveryError::Parsing(_))) => {}
+            update => panic!("Expected a parsing error, received: {:?}", update),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_failure() {
+        // the server has no such endpoint
+        let url = serve_validator_set(&ValidatorSet::empty());
+        let mut stream = RestStream::new(
+            network_context(),
+            url.join("unknown/").unwrap(),
+            Duration::from_millis(5),
+            TimeService::real(),
+        );
+        match stream.next().await {
+            Some(Err(DiscoveryError::Rest(_))) => {}
+            update => panic!("Expected a REST error, received: {:?}", update),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "REST discovery is not allowed on the validator network")]
+    fn test_validator_network_rejected() {
+        RestStream::new(
+            NetworkContext::mock(),
+            Url::parse("http://127.0.0.1:8080").unwrap(),
+            Duration::from_millis(5),
+            TimeService::real(),
+        );
+    }
+}
@@ -0,0 +1,599 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! A [`Transport`] injecting network faults, to reproduce WAN conditions in tests.
+//!
+//! The [`FaultInjectingTransport`] relays the data of each outbound connection through a
+//! background task that adds latency, jitter, bandwidth caps, stalls and random resets,
+//! following the [`FaultConfig`] of the link between the local peer and the dialed peer.
+//! The faults are read from a [`FaultController`] shared by the transports of all the peers,
+//! for every chunk of data, so they can be changed at runtime on live connections.
+//!
+//! The faults of a link are injected in both directions, on the side of the dialer: it is the
+//! only side knowing the remote peer when the connection is established. Inbound connections
+//! are passed through untouched, so that each connection is only slowed down once.
+//!
+//! A reset aborts the connection: the local end fails with `ConnectionReset`, and the
+//! remote end is reset through [`ResetSocket`], e.g. with a TCP RST.
+
+use crate::transport::{boxed, quic::QuicStreams, SecureChannel, Transport};
+use aptos_infallible::RwLock;
+use aptos_types::{network_address::NetworkAddress, PeerId};
+use futures::{
+    channel::{mpsc, oneshot},
+    future::{self, Either, FutureExt, TryFutureExt},
+    io::{AsyncRead, AsyncWrite},
+    pin_mut, ready,
+    sink::SinkExt,
+    stream::{StreamExt, TryStreamExt},
+};
+use rand::Rng;
+use std::{
+    collections::HashMap,
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, DuplexStream},
+    sync::watch,
+    time::{self, Instant},
+};
+use tokio_util::compat::{Compat, FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
+
+/// The maximum size of a chunk of data relayed at once.
+const CHUNK_SIZE: usize = 64 * 1024;
+/// The number of chunks buffered in each direction before applying backpressure.
+const MAX_PENDING_CHUNKS: usize = 64;
+
+/// The faults injected in each direction of a connection.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FaultConfig {
+    /// Delay added to the delivery of all the data.
+    pub latency: Duration,
+    /// Maximum random delay added to the latency, uniformly distributed.
+    pub jitter: Duration,
+    /// Maximum throughput, unlimited if `None`.
+    pub bandwidth_bytes_per_sec: Option<u64>,
+    /// Probability for each chunk of data to stall the delivery for `stall_duration`.
+    pub stall_probability: f64,
+    /// Duration of the stalls.
+    pub stall_duration: Duration,
+    /// Probability for each chunk of data to reset the connection.
+    pub reset_probability: f64,
+}
+
+/// The faults of the links between peers, controllable at runtime.
+#[derive(Clone, Debug, Default)]
+pub struct FaultController {
+    inner: Arc<RwLock<FaultControllerInner>>,
+}
+
+#[derive(Debug, Default)]
+struct FaultControllerInner {
+    /// The faults of the links without specific faults.
+    default_faults: FaultConfig,
+    /// The faults of specific links, keyed by both orderings of the peer pair.
+    link_faults: HashMap<(PeerId, PeerId), FaultConfig>,
+}
+
+impl FaultController {
+    pub fn new(default_faults: FaultConfig) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(FaultControllerInner {
+                default_faults,
+                link_faults: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Sets the faults of all the links without specific faults.
+    pub fn set_default_faults(&self, faults: FaultConfig) {
+        self.inner.write().default_faults = faults;
+    }
+
+    /// Sets the faults of the link between two peers.
+    pub fn set_link_faults(&self, peer_a: PeerId, peer_b: PeerId, faults: FaultConfig) {
+        let mut inner = self.inner.write();
+        inner.link_faults.insert((peer_a, peer_b), faults);
+        inner.link_faults.insert((peer_b, peer_a), faults);
+    }
+
+    /// Removes the specific faults of the link between two peers.
+    pub fn clear_link_faults(&self, peer_a: PeerId, peer_b: PeerId) {
+        let mut inner = self.inner.write();
+        inner.link_faults.remove(&(peer_a, peer_b));
+        inner.link_faults.remove(&(peer_b, peer_a));
+    }
+
+    /// Replaces the faults of all the links at once.
+    pub fn set_faults(
+        &self,
+        default_faults: FaultConfig,
+        link_faults: impl IntoIterator<Item = (PeerId, PeerId, FaultConfig)>,
+    ) {
+        let mut inner = self.inner.write();
+        inner.default_faults = default_faults;
+        inner.link_faults.clear();
+        for (peer_a, peer_b, faults) in link_faults {
+            inner.link_faults.insert((peer_a, peer_b), faults);
+            inner.link_faults.insert((peer_b, peer_a), faults);
+        }
+    }
+
+    /// The faults currently injected on the link between two peers.
+    pub fn faults(&self, peer_a: PeerId, peer_b: PeerId) -> FaultConfig {
+        let inner = self.inner.read();
+        inner
+            .link_faults
+            .get(&(peer_a, peer_b))
+            .copied()
+            .unwrap_or(inner.default_faults)
+    }
+}
+
+/// A connection that can be aborted instead of gracefully closed.
+pub trait ResetSocket {
+    /// Closes the connection so that the remote peer sees a reset, e.g. with a TCP RST.
+    fn reset(self);
+}
+
+/// A transport injecting the faults of the [`FaultController`] in its outbound connections.
+pub struct FaultInjectingTransport<T> {
+    transport: T,
+    local_peer_id: PeerId,
+    controller: FaultController,
+}
+
+impl<T> FaultInjectingTransport<T> {
+    pub(crate) fn new(transport: T, local_peer_id: PeerId, controller: FaultController) -> Self {
+        Self {
+            transport,
+            local_peer_id,
+            controller,
+        }
+    }
+}
+
+impl<T> Transport for FaultInjectingTransport<T>
+where
+    T: Transport,
+    T::Output: ResetSocket + AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    T::Listener: Send + 'static,
+    T::Inbound: Send + 'static,
+    T::Outbound: Send + 'static,
+{
+    type Output = FaultInjectingSocket<T::Output>;
+    type Error = T::Error;
+    type Listener = boxed::Listener<Self::Output, Self::Error>;
+    type Inbound = boxed::Inbound<Self::Output, Self::Error>;
+    type Outbound = boxed::Outbound<Self::Output, Self::Error>;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        let (listener, addr) = self.transport.listen_on(addr)?;
+        let listener = listener.map_ok(|(inbound, addr)| {
+            let inbound = inbound.map_ok(FaultInjectingSocket::Direct).boxed();
+            (inbound as Self::Inbound, addr)
+        });
+        Ok((listener.boxed(), addr))
+    }
+
+    fn dial(&self, peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        let controller = self.controller.clone();
+        let local_peer_id = self.local_peer_id;
+        let outbound = self.transport.dial(peer_id, addr)?;
+        Ok(outbound
+            .map_ok(move |socket| {
+                let link = Link::new(controller, local_peer_id, peer_id);
+                FaultInjectingSocket::faulty(socket, link)
+            })
+            .boxed())
+    }
+}
+
+/// A connection of the [`FaultInjectingTransport`].
+#[derive(Debug)]
+pub enum FaultInjectingSocket<S> {
+    /// A connection without faults.
+    Direct(S),
+    /// The end of a pipe relaying the data of a connection with faults.
+    Faulty(FaultyPipe),
+}
+
+impl<S> FaultInjectingSocket<S>
+where
+    S: ResetSocket + AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    /// Relays the data of the socket through a task injecting the faults of the link.
+    fn faulty(socket: S, link: Link) -> Self {
+        let (pipe, relay) = tokio::io::duplex(CHUNK_SIZE);
+        let (closed_tx, closed_rx) = oneshot::channel();
+        let reset_rx = link.reset_rx.clone();
+        tokio::spawn(relay_connection(socket, relay, link, closed_rx));
+        FaultInjectingSocket::Faulty(FaultyPipe {
+            pipe: pipe.compat(),
+            reset_rx,
+            _closed_tx: closed_tx,
+        })
+    }
+}
+
+/// The local end of a connection with faults.
+#[derive(Debug)]
+pub struct FaultyPipe {
+    pipe: Compat<DuplexStream>,
+    /// Whether the connection was reset.
+    reset_rx: watch::Receiver<bool>,
+    /// Dropped with the connection, to stop relaying the data for it.
+    _closed_tx: oneshot::Sender<()>,
+}
+
+impl FaultyPipe {
+    fn check_reset(&self) -> io::Result<()> {
+        if *self.reset_rx.borrow() {
+            Err(io::Error::new(
+                io::ErrorKind::ConnectionReset,
+                "Connection reset by fault injection",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl AsyncRead for FaultyPipe {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.check_reset()?;
+        let result = ready!(Pin::new(&mut self.pipe).poll_read(context, buf));
+        // A reset closes the pipe, so it is noticed once the pipe is ready
+        self.check_reset()?;
+        Poll::Ready(result)
+    }
+}
+
+impl AsyncWrite for FaultyPipe {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.check_reset()?;
+        let result = ready!(Pin::new(&mut self.pipe).poll_write(context, buf));
+        self.check_reset()?;
+        Poll::Ready(result)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        self.check_reset()?;
+        Pin::new(&mut self.pipe).poll_flush(context)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        self.check_reset()?;
+        Pin::new(&mut self.pipe).poll_close(context)
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for FaultInjectingSocket<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            FaultInjectingSocket::Direct(socket) => Pin::new(socket).poll_read(context, buf),
+            FaultInjectingSocket::Faulty(pipe) => Pin::new(pipe).poll_read(context, buf),
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for FaultInjectingSocket<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            FaultInjectingSocket::Direct(socket) => Pin::new(socket).poll_write(context, buf),
+            FaultInjectingSocket::Faulty(pipe) => Pin::new(pipe).poll_write(context, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            FaultInjectingSocket::Direct(socket) => Pin::new(socket).poll_flush(context),
+            FaultInjectingSocket::Faulty(pipe) => Pin::new(pipe).poll_flush(context),
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            FaultInjectingSocket::Direct(socket) => Pin::new(socket).poll_close(context),
+            FaultInjectingSocket::Faulty(pipe) => Pin::new(pipe).poll_close(context),
+        }
+    }
+}
+
+/// Only sockets passed through untouched keep the channel they are carried over, the faulty
+/// pipes relay the connection outside of it.
+impl<S: SecureChannel> SecureChannel for FaultInjectingSocket<S> {
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        match self {
+            FaultInjectingSocket::Direct(socket) => socket.channel_binding(),
+            FaultInjectingSocket::Faulty(_) => None,
+        }
+    }
+
+    fn take_streams(&mut self) -> Option<QuicStreams> {
+        match self {
+            FaultInjectingSocket::Direct(socket) => socket.take_streams(),
+            FaultInjectingSocket::Faulty(_) => None,
+        }
+    }
+}
+
+/// The link of a connection with faults.
+#[derive(Clone)]
+struct Link {
+    controller: FaultController,
+    local_peer_id: PeerId,
+    remote_peer_id: PeerId,
+    /// Set once the connection is reset, to stop relaying in both directions.
+    reset_tx: Arc<watch::Sender<bool>>,
+    reset_rx: watch::Receiver<bool>,
+}
+
+impl Link {
+    fn new(controller: FaultController, local_peer_id: PeerId, remote_peer_id: PeerId) -> Self {
+        let (reset_tx, reset_rx) = watch::channel(false);
+        Self {
+            controller,
+            local_peer_id,
+            remote_peer_id,
+            reset_tx: Arc::new(reset_tx),
+            reset_rx,
+        }
+    }
+
+    fn faults(&self) -> FaultConfig {
+        self.controller
+            .faults(self.local_peer_id, self.remote_peer_id)
+    }
+
+    fn reset(&self) {
+        // The link holds a receiver, so the value is always stored
+        let _ = self.reset_tx.send(true);
+    }
+
+    fn is_reset(&self) -> bool {
+        *self.reset_rx.borrow()
+    }
+
+    /// Completes once the connection is reset.
+    async fn wait_for_reset(&self) {
+        let mut reset_rx = self.reset_rx.clone();
+        while !*reset_rx.borrow() {
+            if reset_rx.changed().await.is_err() {
+                future::pending::<()>().await;
+            }
+        }
+    }
+}
+
+/// Relays the data of a connection in both directions, until the connection is closed on both
+/// sides, the local end is dropped or the connection is reset.
+async fn relay_connection<S>(
+    socket: S,
+    relay: DuplexStream,
+    link: Link,
+    local_closed: oneshot::Receiver<()>,
+) where
+    S: ResetSocket + AsyncRead + AsyncWrite + Unpin,
+{
+    let (socket_reader, socket_writer) = tokio::io::split(socket.compat());
+    let (relay_reader, relay_writer) = tokio::io::split(relay);
+
+    // The data for the local end is useless once it is dropped, and the remote peer may never
+    // close its side of the connection, so stop reading from the socket then.
+    let inbound = async {
+        let inbound = relay_direction(socket_reader, relay_writer, link.clone());
+        pin_mut!(inbound);
+        match future::select(inbound, local_closed).await {
+            Either::Left(((socket_reader, _), _)) => Some(socket_reader),
+            Either::Right(_) => None,
+        }
+    };
+    let outbound = relay_direction(relay_reader, socket_writer, link.clone());
+    let (socket_reader, (_, socket_writer)) = future::join(inbound, outbound).await;
+
+    if link.is_reset() {
+        if let Some(socket_reader) = socket_reader {
+            socket_reader.unsplit(socket_writer).into_inner().reset();
+        }
+    }
+}
+
+/// Relays the data of one direction of a connection, returning the reader and the writer once
+/// done.
+async fn relay_direction<R, W>(reader: R, writer: W, link: Link) -> (R, W)
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    let (chunks_tx, chunks_rx) = mpsc::channel(MAX_PENDING_CHUNKS);
+    future::join(
+        schedule_chunks(reader, chunks_tx, link.clone()),
+        deliver_chunks(chunks_rx, writer, link),
+    )
+    .await
+}
+
+/// A chunk of data to deliver once due.
+struct Chunk {
+    due: Instant,
+    data: Vec<u8>,
+}
+
+/// Reads the chunks of data of one direction of a connection and schedules their delivery.
+async fn schedule_chunks<R: tokio::io::AsyncRead + Unpin>(
+    mut reader: R,
+    mut chunks_tx: mpsc::Sender<Chunk>,
+    link: Link,
+) -> R {
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut last_due = Instant::now();
+    loop {
+        let len = {
+            let read = reader.read(&mut buf);
+            let reset = link.wait_for_reset();
+            pin_mut!(read, reset);
+            match future::select(read, reset).await {
+                Either::Left((Ok(len), _)) if len > 0 => len,
+                _ => break,
+            }
+        };
+        let faults = link.faults();
+        let (reset, jitter) = {
+            let mut rng = rand::thread_rng();
+            (
+                rng.gen_bool(faults.reset_probability.max(0.0).min(1.0)),
+                faults.jitter.mul_f64(rng.gen()),
+            )
+        };
+        if reset {
+            link.reset();
+            break;
+        }
+        // Jitter doesn't reorder the data of a connection.
+        let due = last_due.max(Instant::now() + faults.latency + jitter);
+        last_due = due;
+        let chunk = Chunk {
+            due,
+            data: buf[..len].to_vec(),
+        };
+        if chunks_tx.send(chunk).await.is_err() {
+            break;
+        }
+    }
+    reader
+}
+
+/// Writes the chunks of data of one direction of a connection once due, and forwards the end
+/// of the stream once they are all delivered.
+async fn deliver_chunks<W: tokio::io::AsyncWrite + Unpin>(
+    mut chunks_rx: mpsc::Receiver<Chunk>,
+    mut writer: W,
+    link: Link,
+) -> W {
+    {
+        let delivery = async {
+            while let Some(chunk) = chunks_rx.next().await {
+                time::sleep_until(chunk.due).await;
+                let faults = link.faults();
+                if rand::thread_rng().gen_bool(faults.stall_probability.max(0.0).min(1.0)) {
+                    time::sleep(faults.stall_duration).await;
+                }
+                if let Some(bandwidth) = faults.bandwidth_bytes_per_sec {
+                    let transmission_secs = chunk.data.len() as f64 / bandwidth.max(1) as f64;
+                    time::sleep(Duration::from_secs_f64(transmission_secs)).await;
+                }
+                if writer.write_all(&chunk.data).await.is_err() || writer.flush().await.is_err() {
+                    return;
+                }
+            }
+            let _ = writer.shutdown().await;
+        };
+        let reset = link.wait_for_reset();
+        pin_mut!(delivery, reset);
+        future::select(delivery, reset).await;
+    }
+    writer
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transport::{memory::MemoryTransport, TransportExt};
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn connect(
+        faults: FaultConfig,
+    ) -> (
+        FaultInjectingSocket<memsocket::MemorySocket>,
+        FaultInjectingSocket<memsocket::MemorySocket>,
+    ) {
+        let (local_peer_id, remote_peer_id) = (PeerId::random(), PeerId::random());
+        let controller = FaultController::default();
+        controller.set_link_faults(local_peer_id, remote_peer_id, faults);
+        let transport = MemoryTransport.inject_faults(local_peer_id, controller);
+
+        let (mut listener, addr) = transport.listen_on("/memory/0".parse().unwrap()).unwrap();
+        let dialer = transport.dial(remote_peer_id, addr).unwrap().await.unwrap();
+        let (inbound, _) = listener.next().await.unwrap().unwrap();
+        (dialer, inbound.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn inject_latency() {
+        let latency = Duration::from_millis(100);
+        let (mut dialer, mut listener) = connect(FaultConfig {
+            latency,
+            ..FaultConfig::default()
+        })
+        .await;
+        assert!(matches!(dialer, FaultInjectingSocket::Faulty(_)));
+        assert!(matches!(listener, FaultInjectingSocket::Direct(_)));
+
+        // The latency is injected in both directions.
+        let start = Instant::now();
+        dialer.write_all(b"ping").await.unwrap();
+        dialer.flush().await.unwrap();
+        let mut buf = [0; 4];
+        listener.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        listener.write_all(b"pong").await.unwrap();
+        listener.flush().await.unwrap();
+        dialer.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"pong");
+        assert!(start.elapsed() >= 2 * latency);
+    }
+
+    #[tokio::test]
+    async fn reset_connection() {
+        let (mut dialer, mut listener) = connect(FaultConfig {
+            reset_probability: 1.0,
+            ..FaultConfig::default()
+        })
+        .await;
+
+        // The data is lost and the remote end is closed
+        dialer.write_all(b"lost").await.unwrap();
+        let mut buf = Vec::new();
+        listener.read_to_end(&mut buf).await.unwrap();
+        assert!(buf.is_empty());
+
+        // The local end sees the reset
+        let error = dialer.read(&mut [0; 4]).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionReset);
+    }
+
+    #[tokio::test]
+    async fn relay_stops_when_dialer_is_dropped() {
+        let (dialer, mut listener) = connect(FaultConfig::default()).await;
+
+        // The remote end is closed even though it never closes its side of the connection
+        drop(dialer);
+        let mut buf = Vec::new();
+        listener.read_to_end(&mut buf).await.unwrap();
+        assert!(buf.is_empty());
+    }
+}
@@ -98,6 +98,14 @@ impl Stream for Listener {
     }
 }
 
+impl crate::transport::SecureChannel for MemorySocket {}
+
+/// Memory sockets can't be reset, the remote end only sees the connection closed.
+#[cfg(any(test, feature = "testing"))]
+impl crate::transport::fault_injection::ResetSocket for MemorySocket {
+    fn reset(self) {}
+}
+
 #[cfg(test)]
 mod test {
     use crate::transport::{memory::MemoryTransport, Transport};
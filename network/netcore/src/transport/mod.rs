@@ -18,9 +18,12 @@ use std::fmt;
 
 pub mod and_then;
 pub mod boxed;
+#[cfg(any(test, feature = "testing"))]
+pub mod fault_injection;
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 pub mod memory;
 pub mod proxy_protocol;
+pub mod quic;
 pub mod tcp;
 
 /// Origin of how a Connection was established.
@@ -53,6 +56,26 @@ impl fmt::Display for ConnectionOrigin {
     }
 }
 
+/// The channel carrying a connection, as secured by its [`Transport`].
+///
+/// Transports securing their connections themselves, e.g. QUIC with TLS, expose a binding of
+/// the channel, which the peers authenticate along with their identity in the handshake, so
+/// that the channel can't be intercepted. The other streams multiplexed on such a channel are
+/// then as authenticated as the connection itself.
+pub trait SecureChannel {
+    /// A digest identical at both ends of the channel unless it is intercepted, or `None` if
+    /// the transport doesn't secure the channel.
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        None
+    }
+
+    /// Takes the handle to the streams multiplexed on the channel besides the connection, if
+    /// any and not already taken.
+    fn take_streams(&mut self) -> Option<quic::QuicStreams> {
+        None
+    }
+}
+
 /// A Transport is responsible for establishing connections with remote Peers.
 ///
 /// Connections are established either by [listening](Transport::listen_on)
@@ -156,4 +179,18 @@ pub trait TransportExt: Transport {
     {
         and_then::AndThen::new(self, f)
     }
+
+    /// Injects the faults of the [`FaultController`](fault_injection::FaultController) in the
+    /// connections dialed by this transport, e.g. to reproduce WAN conditions on a single host.
+    #[cfg(any(test, feature = "testing"))]
+    fn inject_faults(
+        self,
+        local_peer_id: PeerId,
+        controller: fault_injection::FaultController,
+    ) -> fault_injection::FaultInjectingTransport<Self>
+    where
+        Self: Sized,
+    {
+        fault_injection::FaultInjectingTransport::new(self, local_peer_id, controller)
+    }
 }
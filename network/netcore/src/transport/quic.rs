@@ -0,0 +1,685 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! QUIC Transport
+//!
+//! Each connection carries a control stream, opened by the dialer, which is the socket exposed
+//! as the [`Transport::Output`]. The Noise IK and handshake upgrades run on the control stream
+//! exactly as they do on a TCP socket, so peers keep authenticating with their x25519 identity
+//! keys. Both endpoints present ephemeral self-signed certificates, which TLS doesn't verify
+//! against any root of trust. Instead, the [`SecureChannel::channel_binding`] of a connection
+//! digests both certificates, and the Noise handshake authenticates it along with the identity
+//! of the peers: a man in the middle terminating TLS on either side would present another
+//! certificate, and the handshake would fail.
+//!
+//! Once the connection is authenticated, the other streams of the connection are taken with
+//! [`SecureChannel::take_streams`], opened with [`QuicStreams::open_stream`] and accepted with
+//! [`QuicStreams::accept_stream`]. Streams are flow controlled independently, so a slow class
+//! of traffic (e.g., state sync) doesn't hold back the messages of the others (e.g.,
+//! consensus) like it does on a single TCP socket. The listener endpoint serves all the inbound
+//! connections, and a single client endpoint per IP family dials all the outbound ones.
+
+use crate::transport::{tcp::resolve_with_filter, SecureChannel, Transport};
+use aptos_infallible::Mutex;
+use aptos_types::{
+    network_address::{parse_dns_quic, parse_ip_quic, NetworkAddress, Protocol},
+    PeerId,
+};
+use futures::{
+    future::{BoxFuture, FutureExt},
+    io::{AsyncRead, AsyncWrite},
+    ready,
+    stream::{Stream, StreamExt},
+};
+use quinn::{
+    ClientConfig, Connecting, Connection, Endpoint, Incoming, IncomingBiStreams, NewConnection,
+    RecvStream, SendStream, ServerConfig,
+};
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier},
+    server::{ClientCertVerified, ClientCertVerifier},
+    Certificate, DistinguishedNames, PrivateKey, ServerName,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::SystemTime,
+};
+use tokio::io::{
+    AsyncRead as TokioAsyncRead, AsyncReadExt, AsyncWrite as TokioAsyncWrite, AsyncWriteExt,
+    ReadBuf,
+};
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+/// The application-layer protocol negotiated by the QUIC endpoints.
+const ALPN_PROTOCOL: &[u8] = b"aptos";
+/// The server name of the (never verified) listener certificates.
+const SERVER_NAME: &str = "aptos";
+/// The header of the control stream. Other streams start with their stream id instead.
+const CONTROL_STREAM_HEADER: u8 = u8::MAX;
+
+/// Transport to build QUIC connections
+#[derive(Clone)]
+pub struct QuicTransport {
+    certificate: Certificate,
+    server_config: ServerConfig,
+    client_config: ClientConfig,
+    /// The endpoints dialing the IPv4 and IPv6 addresses, created on the first dial.
+    ipv4_client_endpoint: Arc<Mutex<Option<Endpoint>>>,
+    ipv6_client_endpoint: Arc<Mutex<Option<Endpoint>>>,
+}
+
+impl QuicTransport {
+    /// Builds a transport with a new ephemeral certificate.
+    pub fn new() -> io::Result<Self> {
+        let cert = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()])
+            .map_err(other_error)?;
+        let certificate = Certificate(cert.serialize_der().map_err(other_error)?);
+        let private_key = PrivateKey(cert.serialize_private_key_der());
+
+        let mut server_crypto = rustls::ServerConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .map_err(other_error)?
+            .with_client_cert_verifier(Arc::new(AcceptAnyCertificate))
+            .with_single_cert(vec![certificate.clone()], private_key.clone())
+            .map_err(other_error)?;
+        server_crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+
+        let mut client_crypto = rustls::ClientConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .map_err(other_error)?
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate))
+            .with_single_cert(vec![certificate.clone()], private_key)
+            .map_err(other_error)?;
+        client_crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+
+        Ok(Self {
+            certificate,
+            server_config: ServerConfig::with_crypto(Arc::new(server_crypto)),
+            client_config: ClientConfig::new(Arc::new(client_crypto)),
+            ipv4_client_endpoint: Arc::new(Mutex::new(None)),
+            ipv6_client_endpoint: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// The endpoint dialing the addresses of the IP family of `ip`, shared by all the dials.
+    fn client_endpoint(&self, ip: IpAddr) -> io::Result<Endpoint> {
+        let (client_endpoint, unspecified_ip) = match ip {
+            IpAddr::V4(_) => (
+                &self.ipv4_client_endpoint,
+                IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            ),
+            IpAddr::V6(_) => (
+                &self.ipv6_client_endpoint,
+                IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            ),
+        };
+        let mut client_endpoint = client_endpoint.lock();
+        if let Some(endpoint) = client_endpoint.as_ref() {
+            return Ok(endpoint.clone());
+        }
+        let mut endpoint = Endpoint::client(SocketAddr::new(unspecified_ip, 0))?;
+        endpoint.set_default_client_config(self.client_config.clone());
+        *client_endpoint = Some(endpoint.clone());
+        Ok(endpoint)
+    }
+}
+
+impl fmt::Debug for QuicTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuicTransport").finish()
+    }
+}
+
+impl Transport for QuicTransport {
+    type Output = QuicSocket;
+    type Error = io::Error;
+    type Listener = QuicListenerStream;
+    type Inbound = BoxFuture<'static, io::Result<QuicSocket>>;
+    type Outbound = BoxFuture<'static, io::Result<QuicSocket>>;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        let ((ipaddr, port), addr_suffix) =
+            parse_ip_quic(addr.as_slice()).ok_or_else(|| invalid_addr_error(&addr))?;
+        if !addr_suffix.is_empty() {
+            return Err(invalid_addr_error(&addr));
+        }
+
+        let (endpoint, incoming) =
+            Endpoint::server(self.server_config.clone(), SocketAddr::new(ipaddr, port))?;
+        let listen_addr = quic_addr(endpoint.local_addr()?);
+
+        Ok((
+            QuicListenerStream {
+                _endpoint: endpoint,
+                incoming,
+                certificate: self.certificate.clone(),
+            },
+            listen_addr,
+        ))
+    }
+
+    fn dial(&self, _peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        // ensure addr is well formed to save some work before potentially
+        // spawning a dial task that will fail anyway.
+        let protos = addr.as_slice();
+        parse_ip_quic(protos)
+            .map(|_| ())
+            .or_else(|| parse_dns_quic(protos).map(|_| ()))
+            .ok_or_else(|| invalid_addr_error(&addr))?;
+
+        Ok(dial(self.clone(), addr).boxed())
+    }
+}
+
+async fn dial(transport: QuicTransport, addr: NetworkAddress) -> io::Result<QuicSocket> {
+    let socket_addrs = resolve(&addr).await?;
+    let mut last_err = None;
+
+    // try to connect until the first succeeds
+    for socket_addr in socket_addrs {
+        let endpoint = transport.client_endpoint(socket_addr.ip())?;
+        match endpoint.connect(socket_addr, SERVER_NAME) {
+            Ok(connecting) => match connecting.await {
+                Ok(new_connection) => {
+                    return QuicSocket::outbound(new_connection, &transport.certificate).await
+                }
+                Err(err) => last_err = Some(connection_error(err)),
+            },
+            Err(err) => last_err = Some(other_error(err)),
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("could not resolve address: {}", addr),
+        )
+    }))
+}
+
+/// Resolves the socket addresses of an `/ip*/<addr>/quic/<port>` or a
+/// `/dns*/<name>/quic/<port>` address.
+async fn resolve(addr: &NetworkAddress) -> io::Result<Vec<SocketAddr>> {
+    let protos = addr.as_slice();
+
+    if let Some(((ipaddr, port), _addr_suffix)) = parse_ip_quic(protos) {
+        Ok(vec![SocketAddr::new(ipaddr, port)])
+    } else if let Some(((ip_filter, dns_name, port), _addr_suffix)) = parse_dns_quic(protos) {
+        Ok(resolve_with_filter(ip_filter, dns_name.as_ref(), port)
+            .await?
+            .collect())
+    } else {
+        Err(invalid_addr_error(addr))
+    }
+}
+
+/// The `/ip*/<addr>/quic/<port>` address of a socket address.
+fn quic_addr(socket_addr: SocketAddr) -> NetworkAddress {
+    NetworkAddress::from(Protocol::from(socket_addr.ip())).push(Protocol::Quic(socket_addr.port()))
+}
+
+fn invalid_addr_error(addr: &NetworkAddress) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid NetworkAddress: '{}'", addr),
+    )
+}
+
+fn connection_error(err: quinn::ConnectionError) -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, err)
+}
+
+fn other_error<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}
+
+/// Skips the verification of the TLS certificates against a root of trust: the certificates are
+/// ephemeral, and bound into the Noise handshake on the control stream instead. The signatures
+/// of the TLS handshake are still verified, so each end holds the key of its certificate.
+struct AcceptAnyCertificate;
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+impl ClientCertVerifier for AcceptAnyCertificate {
+    fn client_auth_root_subjects(&self) -> Option<DistinguishedNames> {
+        Some(DistinguishedNames::new())
+    }
+
+    fn verify_client_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _now: SystemTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        Ok(ClientCertVerified::assertion())
+    }
+}
+
+#[must_use = "streams do nothing unless polled"]
+pub struct QuicListenerStream {
+    // keeps the endpoint open while listening
+    _endpoint: Endpoint,
+    incoming: Incoming,
+    certificate: Certificate,
+}
+
+impl Stream for QuicListenerStream {
+    type Item = io::Result<(BoxFuture<'static, io::Result<QuicSocket>>, NetworkAddress)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        let connecting: Connecting = match ready!(self.incoming.poll_next_unpin(context)) {
+            Some(connecting) => connecting,
+            None => return Poll::Ready(None),
+        };
+        let dialer_addr = quic_addr(connecting.remote_address());
+        let certificate = self.certificate.clone();
+        let inbound = async move {
+            let new_connection = connecting.await.map_err(connection_error)?;
+            QuicSocket::inbound(new_connection, &certificate).await
+        };
+        Poll::Ready(Some(Ok((inbound.boxed(), dialer_addr))))
+    }
+}
+
+/// A bidirectional QUIC stream
+#[derive(Debug)]
+pub struct QuicStream {
+    send: SendStream,
+    recv: RecvStream,
+}
+
+impl TokioAsyncRead for QuicStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.recv).poll_read(context, buf)
+    }
+}
+
+impl TokioAsyncWrite for QuicStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write(context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(context)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_shutdown(context)
+    }
+}
+
+/// A QUIC connection, read and written through its control stream.
+pub struct QuicSocket {
+    control: Compat<QuicStream>,
+    connection: Connection,
+    streams: Option<QuicStreams>,
+    channel_binding: [u8; 32],
+}
+
+impl QuicSocket {
+    /// Opens the control stream of a dialed connection.
+    async fn outbound(
+        new_connection: NewConnection,
+        local_certificate: &Certificate,
+    ) -> io::Result<Self> {
+        let NewConnection {
+            connection,
+            bi_streams,
+            ..
+        } = new_connection;
+        let channel_binding = channel_binding(local_certificate, &peer_certificate(&connection)?);
+
+        let (send, recv) = connection.open_bi().await.map_err(connection_error)?;
+        let mut control = QuicStream { send, recv };
+        control.write_u8(CONTROL_STREAM_HEADER).await?;
+
+        Ok(Self {
+            control: control.compat(),
+            streams: Some(QuicStreams {
+                connection: connection.clone(),
+                bi_streams,
+            }),
+            connection,
+            channel_binding,
+        })
+    }
+
+    /// Accepts the control stream of an inbound connection.
+    async fn inbound(
+        new_connection: NewConnection,
+        local_certificate: &Certificate,
+    ) -> io::Result<Self> {
+        let NewConnection {
+            connection,
+            mut bi_streams,
+            ..
+        } = new_connection;
+        let channel_binding = channel_binding(&peer_certificate(&connection)?, local_certificate);
+
+        let (send, recv) = bi_streams
+            .next()
+            .await
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?
+            .map_err(connection_error)?;
+        let mut control = QuicStream { send, recv };
+        if control.read_u8().await? != CONTROL_STREAM_HEADER {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "QUIC connection didn't start with a control stream",
+            ));
+        }
+
+        Ok(Self {
+            control: control.compat(),
+            streams: Some(QuicStreams {
+                connection: connection.clone(),
+                bi_streams,
+            }),
+            connection,
+            channel_binding,
+        })
+    }
+}
+
+impl SecureChannel for QuicSocket {
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        Some(self.channel_binding)
+    }
+
+    fn take_streams(&mut self) -> Option<QuicStreams> {
+        self.streams.take()
+    }
+}
+
+/// The streams hold the connection open, so it is closed along with its control stream.
+impl Drop for QuicSocket {
+    fn drop(&mut self) {
+        self.connection.close(0u32.into(), b"");
+    }
+}
+
+impl fmt::Debug for QuicSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuicSocket")
+            .field("remote_address", &self.connection.remote_address())
+            .finish()
+    }
+}
+
+/// The streams of a QUIC connection besides its control stream, each starting with the id of
+/// the stream picked by the peer opening it.
+pub struct QuicStreams {
+    connection: Connection,
+    bi_streams: IncomingBiStreams,
+}
+
+impl QuicStreams {
+    /// Opens a new stream with the id.
+    pub async fn open_stream(&self, stream_id: u8) -> io::Result<Compat<QuicStream>> {
+        if stream_id == CONTROL_STREAM_HEADER {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Reserved stream id: {}", stream_id),
+            ));
+        }
+        let (send, recv) = self.connection.open_bi().await.map_err(connection_error)?;
+        let mut stream = QuicStream { send, recv };
+        stream.write_u8(stream_id).await?;
+        Ok(stream.compat())
+    }
+
+    /// Accepts the next stream opened by the remote peer, along with its id.
+    pub async fn accept_stream(&mut self) -> io::Result<(u8, Compat<QuicStream>)> {
+        let (send, recv) = self
+            .bi_streams
+            .next()
+            .await
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?
+            .map_err(connection_error)?;
+        let mut stream = QuicStream { send, recv };
+        match stream.read_u8().await? {
+            CONTROL_STREAM_HEADER => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected second control stream",
+            )),
+            stream_id => Ok((stream_id, stream.compat())),
+        }
+    }
+}
+
+impl fmt::Debug for QuicStreams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuicStreams")
+            .field("remote_address", &self.connection.remote_address())
+            .finish()
+    }
+}
+
+/// The certificate presented by the remote end of the connection.
+fn peer_certificate(connection: &Connection) -> io::Result<Certificate> {
+    connection
+        .peer_identity()
+        .and_then(|identity| identity.downcast::<Vec<Certificate>>().ok())
+        .and_then(|certificates| certificates.first().cloned())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "QUIC peer didn't present a certificate",
+            )
+        })
+}
+
+fn channel_binding(
+    dialer_certificate: &Certificate,
+    listener_certificate: &Certificate,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(&dialer_certificate.0);
+    hasher.update(&listener_certificate.0);
+    hasher.finalize().into()
+}
+
+impl AsyncRead for QuicSocket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.control).poll_read(context, buf)
+    }
+}
+
+impl AsyncWrite for QuicSocket {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.control).poll_write(context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.control).poll_flush(context)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.control).poll_close(context)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transport::{ConnectionOrigin, TransportExt};
+    use futures::{
+        future::join,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    async fn connect() -> (QuicSocket, QuicSocket) {
+        let listener_transport = QuicTransport::new().unwrap();
+        let dialer_transport = QuicTransport::new().unwrap();
+        let (mut listener, addr) = listener_transport
+            .listen_on("/ip4/127.0.0.1/quic/0".parse().unwrap())
+            .unwrap();
+        let dial = dialer_transport.dial(PeerId::random(), addr).unwrap();
+        let accept = async move {
+            let (inbound, _dialer_addr) = listener.next().await.unwrap().unwrap();
+            inbound.await.unwrap()
+        };
+        let (outbound, inbound) = join(dial, accept).await;
+        (outbound.unwrap(), inbound)
+    }
+
+    #[tokio::test]
+    async fn simple_listen_and_dial() -> Result<(), io::Error> {
+        let t = QuicTransport::new()?.and_then(|mut out, _addr, origin| async move {
+            match origin {
+                ConnectionOrigin::Inbound => {
+                    out.write_all(b"Earth").await?;
+                    let mut buf = [0; 3];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Air");
+                }
+                ConnectionOrigin::Outbound => {
+                    let mut buf = [0; 5];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Earth");
+                    out.write_all(b"Air").await?;
+                }
+            }
+            Ok(())
+        });
+
+        let (listener, addr) = t.listen_on("/ip4/127.0.0.1/quic/0".parse().unwrap())?;
+        assert!(matches!(addr.as_slice(), [Protocol::Ip4(_), Protocol::Quic(port)] if *port != 0));
+        let dial = t.dial(PeerId::random(), addr)?;
+        let listener = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming.map(Result::unwrap)
+        });
+
+        let (outgoing, _incoming) = join(dial, listener).await;
+        assert!(outgoing.is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn channel_binding_matches() {
+        let (outbound, inbound) = connect().await;
+        assert!(outbound.channel_binding().is_some());
+        assert_eq!(outbound.channel_binding(), inbound.channel_binding());
+
+        // another connection has another binding
+        let (other_outbound, _other_inbound) = connect().await;
+        assert_ne!(outbound.channel_binding(), other_outbound.channel_binding());
+    }
+
+    #[tokio::test]
+    async fn client_endpoint_is_reused() {
+        let transport = QuicTransport::new().unwrap();
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let endpoint = transport.client_endpoint(ip).unwrap();
+        let other_endpoint = transport.clone().client_endpoint(ip).unwrap();
+        assert_eq!(
+            endpoint.local_addr().unwrap(),
+            other_endpoint.local_addr().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn reserved_stream_id() {
+        let (mut outbound, _inbound) = connect().await;
+        let streams = outbound.take_streams().unwrap();
+        assert!(streams.open_stream(CONTROL_STREAM_HEADER).await.is_err());
+
+        // the streams are only taken once
+        assert!(outbound.take_streams().is_none());
+    }
+
+    #[tokio::test]
+    async fn streams_are_independent() {
+        let (mut outbound, mut inbound) = connect().await;
+        let mut outbound_streams = outbound.take_streams().unwrap();
+        let mut inbound_streams = inbound.take_streams().unwrap();
+
+        // fill the first stream without ever reading it
+        let mut blocked = outbound_streams.open_stream(1).await.unwrap();
+        let (stream_id, _unread) = inbound_streams.accept_stream().await.unwrap();
+        assert_eq!(stream_id, 1);
+        let flood = vec![0u8; 8 << 20];
+        let blocked_write = async move {
+            let _ = blocked.write_all(&flood).await;
+        };
+        tokio::spawn(blocked_write);
+
+        // the second stream still goes through, in both directions
+        let mut stream = inbound_streams.open_stream(2).await.unwrap();
+        stream.write_all(b"ping").await.unwrap();
+        let (stream_id, mut remote_stream) = outbound_streams.accept_stream().await.unwrap();
+        assert_eq!(stream_id, 2);
+        let mut buf = [0; 4];
+        remote_stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        remote_stream.write_all(b"pong").await.unwrap();
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"pong");
+
+        // as does the control stream
+        outbound.write_all(b"control").await.unwrap();
+        let mut buf = [0; 7];
+        inbound.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"control");
+    }
+
+    #[tokio::test]
+    async fn streams_close_with_socket() {
+        let (mut outbound, inbound) = connect().await;
+        let mut outbound_streams = outbound.take_streams().unwrap();
+        drop(inbound);
+        assert!(outbound_streams.accept_stream().await.is_err());
+    }
+
+    #[test]
+    fn unsupported_multiaddrs() {
+        let t = QuicTransport::new().unwrap();
+
+        let result = t.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap());
+        assert!(result.is_err());
+
+        let result = t.dial(PeerId::random(), "/memory/22".parse().unwrap());
+        assert!(result.is_err());
+    }
+}
@@ -134,7 +134,7 @@ impl Transport for TcpTransport {
 }
 
 /// Try to lookup the dns name, then filter addrs according to the `IpFilter`.
-async fn resolve_with_filter(
+pub(crate) async fn resolve_with_filter(
     ip_filter: IpFilter,
     dns_name: &str,
     port: u16,
@@ -318,6 +318,19 @@ impl AsyncWrite for TcpSocket {
     }
 }
 
+impl crate::transport::SecureChannel for TcpSocket {}
+
+#[cfg(any(test, feature = "testing"))]
+impl crate::transport::fault_injection::ResetSocket for TcpSocket {
+    fn reset(self) {
+        // Closing the socket without lingering sends a RST instead of a FIN
+        let _ = self
+            .inner
+            .get_ref()
+            .set_linger(Some(std::time::Duration::from_secs(0)));
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -111,4 +111,15 @@ impl<TMessage: Clone + Message + Send, Sender: ApplicationNetworkSender<TMessage
             .send_rpc(recipient.peer_id(), req_msg, timeout)
             .await
     }
+
+    pub async fn send_rpc_with_size(
+        &self,
+        recipient: PeerNetworkId,
+        req_msg: TMessage,
+        timeout: Duration,
+    ) -> Result<(TMessage, usize), RpcError> {
+        self.sender(&recipient.network_id())
+            .send_rpc_with_size(recipient.peer_id(), req_msg, timeout)
+            .await
+    }
 }
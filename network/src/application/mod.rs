@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod interface;
+pub mod reputation;
 pub mod storage;
 #[cfg(test)]
 mod tests;
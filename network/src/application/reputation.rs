@@ -0,0 +1,335 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Misbehavior scoring and temporary bans of peers, shared by all the networks.
+//!
+//! Applications report the misbehavior of a peer (e.g., an invalid proof or a malformed
+//! message) with a [`Severity`], which adds to the misbehavior score of the peer. The score
+//! decays exponentially over time, so that occasional errors are eventually forgiven.
+//!
+//! Below the ban threshold of the network, the ConnectivityManager delays redialing a peer
+//! in proportion to its score. Once the score reaches the threshold, the peer is banned: the
+//! ConnectivityManager closes the connection to the peer and stops dialing it, and the
+//! PeerManager rejects its inbound connections, until the ban expires. Each new ban of a peer
+//! lasts twice as long as the previous one, up to the maximum ban duration.
+//!
+//! Only the peers connected with an `Unknown` role on the non-validator networks can be banned.
+//! The other peers are trusted by the operator (or the on-chain configuration), so their
+//! misbehavior is scored and reported in the metrics, but never leads to a ban.
+
+use crate::counters;
+use aptos_config::{
+    config::{PeerReputationConfig, PeerRole},
+    network_id::{NetworkId, PeerNetworkId},
+};
+use aptos_infallible::RwLock;
+use aptos_logger::prelude::*;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use aptos_types::PeerId;
+use short_hex_str::AsShortHexStr;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Scores below this are considered fully decayed.
+const MIN_SCORE: f64 = 0.01;
+
+/// How bad a misbehavior is, from occasional errors to deliberate attacks.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    /// Errors that honest peers can make, e.g., a timed out or an empty response.
+    Low,
+    /// Errors that honest peers shouldn't make, e.g., an undecodable message.
+    Medium,
+    /// Invalid data, e.g., a malformed transaction batch.
+    High,
+    /// Provably malicious behavior. Bans the peer right away, if it can be banned.
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    /// The score added by a misbehavior, as a fraction of the ban threshold.
+    fn penalty(self) -> f64 {
+        match self {
+            Severity::Low => 0.01,
+            Severity::Medium => 0.1,
+            Severity::High => 0.5,
+            Severity::Critical => 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PeerScore {
+    /// The misbehavior score, as of `updated_at`.
+    score: f64,
+    updated_at: Instant,
+    /// The end of the current or last ban of the peer.
+    banned_until: Option<Instant>,
+    /// The number of times the peer was banned.
+    num_bans: u32,
+}
+
+impl PeerScore {
+    fn new(now: Instant) -> Self {
+        Self {
+            score: 0.0,
+            updated_at: now,
+            banned_until: None,
+            num_bans: 0,
+        }
+    }
+
+    fn decayed_score(&self, config: &PeerReputationConfig, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        let half_life = config.score_half_life_secs.max(1) as f64;
+        self.score * 0.5f64.powf(elapsed / half_life)
+    }
+
+    fn is_banned(&self, now: Instant) -> bool {
+        self.banned_until
+            .map_or(false, |banned_until| now < banned_until)
+    }
+
+    /// Whether the score or the bans of the peer still matter.
+    fn is_relevant(&self, config: &PeerReputationConfig, now: Instant) -> bool {
+        let max_ban_duration = Duration::from_secs(config.max_ban_duration_secs);
+        self.decayed_score(config, now) >= MIN_SCORE
+            || self
+                .banned_until
+                .map_or(false, |banned_until| now < banned_until + max_ban_duration)
+    }
+}
+
+#[derive(Debug, Default)]
+struct NetworkReputation {
+    config: PeerReputationConfig,
+    peers: HashMap<PeerId, PeerScore>,
+}
+
+/// The misbehavior scores and bans of the peers of all the networks.
+#[derive(Debug)]
+pub struct PeerReputation {
+    time_service: TimeService,
+    networks: HashMap<NetworkId, RwLock<NetworkReputation>>,
+}
+
+impl PeerReputation {
+    pub fn new(network_ids: &[NetworkId], time_service: TimeService) -> Self {
+        Self {
+            time_service,
+            networks: network_ids
+                .iter()
+                .map(|network_id| (*network_id, RwLock::new(NetworkReputation::default())))
+                .collect(),
+        }
+    }
+
+    fn get_network(&self, network_id: NetworkId) -> &RwLock<NetworkReputation> {
+        self.networks
+            .get(&network_id)
+            .unwrap_or_else(|| panic!("Unexpected network requested: {}", network_id))
+    }
+
+    /// Sets the scoring and ban configuration of a network.
+    pub fn set_config(&self, network_id: NetworkId, config: PeerReputationConfig) {
+        self.get_network(network_id).write().config = config;
+    }
+
+    /// Reports a misbehavior of the peer, connected with the given role (if still connected).
+    /// Returns true if the peer got banned.
+    pub fn report(
+        &self,
+        peer_network_id: PeerNetworkId,
+        role: Option<PeerRole>,
+        severity: Severity,
+        reason: &str,
+    ) -> bool {
+        let network_id = peer_network_id.network_id();
+        let peer_id = peer_network_id.peer_id();
+        let now = self.time_service.now();
+        counters::peer_misbehavior_reports(network_id, severity).inc();
+
+        let mut network = self.get_network(network_id).write();
+        let NetworkReputation { config, peers } = &mut *network;
+        peers.retain(|other_peer_id, peer_score| {
+            *other_peer_id == peer_id || peer_score.is_relevant(config, now)
+        });
+        let peer_score = peers.entry(peer_id).or_insert_with(|| PeerScore::new(now));
+        peer_score.score = peer_score.decayed_score(config, now)
+            + severity.penalty() * config.ban_threshold as f64;
+        peer_score.updated_at = now;
+        warn!(
+            "Peer {} misbehaved on network {} ({} severity, score {:.2}): {}",
+            peer_id.short_str(),
+            network_id,
+            severity.as_str(),
+            peer_score.score,
+            reason
+        );
+
+        if !config.enabled
+            || !can_ban(network_id, role)
+            || peer_score.is_banned(now)
+            || peer_score.score < config.ban_threshold as f64
+        {
+            return false;
+        }
+        let ban_duration = Duration::from_secs(config.ban_duration_secs)
+            .checked_mul(2u32.saturating_pow(peer_score.num_bans))
+            .unwrap_or(Duration::MAX)
+            .min(Duration::from_secs(config.max_ban_duration_secs));
+        peer_score.banned_until = Some(now + ban_duration);
+        peer_score.num_bans = peer_score.num_bans.saturating_add(1);
+        peer_score.score = 0.0;
+        counters::peer_bans(network_id).inc();
+        warn!(
+            "Peer {} is banned from network {} for {:?}",
+            peer_id.short_str(),
+            network_id,
+            ban_duration
+        );
+        true
+    }
+
+    /// Whether the peer is currently banned.
+    pub fn is_banned(&self, peer_network_id: PeerNetworkId) -> bool {
+        let network = self.get_network(peer_network_id.network_id()).read();
+        network.config.enabled
+            && network
+                .peers
+                .get(&peer_network_id.peer_id())
+                .map_or(false, |peer_score| {
+                    peer_score.is_banned(self.time_service.now())
+                })
+    }
+
+    /// The current misbehavior score of the peer.
+    pub fn score(&self, peer_network_id: PeerNetworkId) -> f64 {
+        let network = self.get_network(peer_network_id.network_id()).read();
+        network
+            .peers
+            .get(&peer_network_id.peer_id())
+            .map_or(0.0, |peer_score| {
+                peer_score.decayed_score(&network.config, self.time_service.now())
+            })
+    }
+
+    /// The extra delay before dialing the peer, a fraction of the ban duration proportional to
+    /// the score of the peer.
+    pub fn dial_delay(&self, peer_network_id: PeerNetworkId) -> Duration {
+        let score = self.score(peer_network_id);
+        let network = self.get_network(peer_network_id.network_id()).read();
+        let config = &network.config;
+        if !config.enabled || config.ban_threshold == 0 {
+            return Duration::ZERO;
+        }
+        let fraction = (score / config.ban_threshold as f64).min(1.0);
+        Duration::from_secs(config.ban_duration_secs).mul_f64(fraction)
+    }
+}
+
+/// Whether a peer connected with the given role can be banned. The peers that are no longer
+/// connected are only scored, as we can't tell whether they are trusted.
+fn can_ban(network_id: NetworkId, role: Option<PeerRole>) -> bool {
+    !network_id.is_validator_network() && role == Some(PeerRole::Unknown)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aptos_time_service::MockTimeService;
+
+    const UNKNOWN: Option<PeerRole> = Some(PeerRole::Unknown);
+
+    fn reputation() -> (PeerReputation, MockTimeService, PeerNetworkId) {
+        let time_service = TimeService::mock();
+        let reputation = PeerReputation::new(&[NetworkId::Public], time_service.clone());
+        let peer = PeerNetworkId::new(NetworkId::Public, PeerId::random());
+        (reputation, time_service.into_mock(), peer)
+    }
+
+    #[test]
+    fn score_decays() {
+        let (reputation, mock_time, peer) = reputation();
+        let half_life = Duration::from_secs(PeerReputationConfig::default().score_half_life_secs);
+
+        assert!(!reputation.report(peer, UNKNOWN, Severity::Medium, "undecodable message"));
+        assert!((reputation.score(peer) - 10.0).abs() < 1e-9);
+        assert!(reputation.dial_delay(peer) > Duration::ZERO);
+
+        mock_time.advance(half_life);
+        assert!((reputation.score(peer) - 5.0).abs() < 1e-9);
+        assert!(!reputation.is_banned(peer));
+    }
+
+    #[test]
+    fn ban_and_double_ban_duration() {
+        let (reputation, mock_time, peer) = reputation();
+        let ban_duration = Duration::from_secs(PeerReputationConfig::default().ban_duration_secs);
+
+        // Two high severity reports in a row ban the peer.
+        assert!(!reputation.report(peer, UNKNOWN, Severity::High, "malformed batch"));
+        assert!(reputation.report(peer, UNKNOWN, Severity::High, "malformed batch"));
+        assert!(reputation.is_banned(peer));
+        // Reports during the ban don't extend it.
+        assert!(!reputation.report(peer, UNKNOWN, Severity::Critical, "invalid proof"));
+
+        mock_time.advance(ban_duration);
+        assert!(!reputation.is_banned(peer));
+
+        // The second ban lasts twice as long.
+        assert!(reputation.report(peer, UNKNOWN, Severity::Critical, "invalid proof"));
+        mock_time.advance(ban_duration);
+        assert!(reputation.is_banned(peer));
+        mock_time.advance(ban_duration);
+        assert!(!reputation.is_banned(peer));
+    }
+
+    #[test]
+    fn trusted_peers_are_never_banned() {
+        let (reputation, _, peer) = reputation();
+        for role in [
+            None,
+            Some(PeerRole::Upstream),
+            Some(PeerRole::ValidatorFullNode),
+        ] {
+            assert!(!reputation.report(peer, role, Severity::Critical, "invalid proof"));
+            assert!(!reputation.is_banned(peer));
+        }
+        assert!(reputation.score(peer) > 0.0);
+
+        // Nobody is banned from the validator network
+        let reputation = PeerReputation::new(&[NetworkId::Validator], TimeService::mock());
+        let validator = PeerNetworkId::new(NetworkId::Validator, PeerId::random());
+        assert!(!reputation.report(validator, UNKNOWN, Severity::Critical, "invalid proof"));
+        assert!(!reputation.is_banned(validator));
+    }
+
+    #[test]
+    fn disabled_bans() {
+        let (reputation, _, peer) = reputation();
+        reputation.set_config(
+            NetworkId::Public,
+            PeerReputationConfig {
+                enabled: false,
+                ..PeerReputationConfig::default()
+            },
+        );
+
+        assert!(!reputation.report(peer, UNKNOWN, Severity::Critical, "invalid proof"));
+        assert!(!reputation.is_banned(peer));
+        assert_eq!(reputation.dial_delay(peer), Duration::ZERO);
+        assert!(reputation.score(peer) > 0.0);
+    }
+}
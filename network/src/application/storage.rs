@@ -2,11 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    application::types::{PeerError, PeerInfo},
+    application::{
+        reputation::{PeerReputation, Severity},
+        types::{PeerError, PeerInfo},
+    },
     transport::ConnectionMetadata,
 };
 use aptos_config::network_id::{NetworkId, PeerNetworkId};
 use aptos_infallible::{RwLock, RwLockWriteGuard};
+use aptos_time_service::TimeService;
 use aptos_types::{account_address::AccountAddress, PeerId};
 use std::{
     collections::{hash_map::Entry, HashMap},
@@ -15,23 +19,52 @@ use std::{
     sync::Arc,
 };
 
+/// Reports the misbehavior of the peers of a single network.
+#[derive(Clone, Debug)]
+pub struct MisbehaviorReporter {
+    network_id: NetworkId,
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
+}
+
+impl MisbehaviorReporter {
+    pub fn new(network_id: NetworkId, peer_metadata_storage: Arc<PeerMetadataStorage>) -> Self {
+        Self {
+            network_id,
+            peer_metadata_storage,
+        }
+    }
+
+    /// Reports a misbehavior of the peer. Returns true if the peer got banned.
+    pub fn report(&self, peer_id: PeerId, severity: Severity, reason: &str) -> bool {
+        self.peer_metadata_storage.report_misbehavior(
+            PeerNetworkId::new(self.network_id, peer_id),
+            severity,
+            reason,
+        )
+    }
+}
+
 /// Metadata storage for peers across all of networking.  Splits storage of information across
 /// networks to prevent different networks from affecting each other
 #[derive(Debug)]
 pub struct PeerMetadataStorage {
     storage: HashMap<NetworkId, LockingHashMap<PeerId, PeerInfo>>,
+    /// Misbehavior scores and bans, kept after the peers disconnect
+    reputation: PeerReputation,
 }
 
 impl PeerMetadataStorage {
     #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
     pub fn test() -> Arc<PeerMetadataStorage> {
-        PeerMetadataStorage::new(&[NetworkId::Validator])
+        PeerMetadataStorage::new(&[NetworkId::Validator], TimeService::real())
     }
 
-    /// Create a new `PeerMetadataStorage` `NetworkId`s must be known at construction time
-    pub fn new(network_ids: &[NetworkId]) -> Arc<PeerMetadataStorage> {
+    /// Create a new `PeerMetadataStorage` `NetworkId`s must be known at construction time.
+    /// The `time_service` measures the decay of the misbehavior scores and the bans.
+    pub fn new(network_ids: &[NetworkId], time_service: TimeService) -> Arc<PeerMetadataStorage> {
         let mut peer_metadata_storage = PeerMetadataStorage {
             storage: HashMap::new(),
+            reputation: PeerReputation::new(network_ids, time_service),
         };
         network_ids.iter().for_each(|network_id| {
             peer_metadata_storage
@@ -45,6 +78,27 @@ impl PeerMetadataStorage {
         self.storage.keys().copied()
     }
 
+    /// The misbehavior scores and bans of the peers
+    pub fn reputation(&self) -> &PeerReputation {
+        &self.reputation
+    }
+
+    /// Reports a misbehavior of the peer, which can only get banned if it is connected with an
+    /// untrusted role. Returns true if the peer got banned.
+    pub fn report_misbehavior(
+        &self,
+        peer_network_id: PeerNetworkId,
+        severity: Severity,
+        reason: &str,
+    ) -> bool {
+        let role = self
+            .read(peer_network_id)
+            .filter(|peer_info| peer_info.is_connected())
+            .map(|peer_info| peer_info.active_connection.role);
+        self.reputation
+            .report(peer_network_id, role, severity, reason)
+    }
+
     /// Handle common logic of getting a network
     fn get_network(&self, network_id: NetworkId) -> &LockingHashMap<AccountAddress, PeerInfo> {
         self.storage
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{protocols::wire::handshake::v1::ProtocolId, transport::ConnectionMetadata};
+use aptos_types::transaction::Version;
+use std::cmp::Ordering;
 
 /// Errors related to the peer layer in the `NetworkInterface`
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -14,6 +16,7 @@ pub enum PeerError {
 pub struct PeerInfo {
     pub status: PeerState,
     pub active_connection: ConnectionMetadata,
+    pub monitoring_metadata: PeerMonitoringMetadata,
 }
 
 impl PeerInfo {
@@ -21,6 +24,7 @@ impl PeerInfo {
         PeerInfo {
             status: PeerState::Connected,
             active_connection: connection_metadata,
+            monitoring_metadata: PeerMonitoringMetadata::default(),
         }
     }
 
@@ -43,3 +47,39 @@ pub enum PeerState {
     Disconnecting,
     Disconnected,
 }
+
+/// What the peer monitor measured of a peer, `None` until measured
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PeerMonitoringMetadata {
+    /// Moving average of the round-trip time of the monitoring requests
+    pub average_ping_latency_secs: Option<f64>,
+    /// Number of hops from the peer to the validators, as reported by the peer
+    pub distance_from_validators: Option<u64>,
+    /// Highest version synced by the peer, as reported by the peer
+    pub highest_synced_version: Option<Version>,
+}
+
+impl PeerMonitoringMetadata {
+    /// Orders the peers from the nearest to the farthest: by distance from the validators,
+    /// then by latency. Peers that weren't measured yet come last.
+    pub fn cmp_proximity(&self, other: &Self) -> Ordering {
+        fn known_first<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+        known_first(
+            self.distance_from_validators,
+            other.distance_from_validators,
+        )
+        .then_with(|| {
+            known_first(
+                self.average_ping_latency_secs,
+                other.average_ping_latency_secs,
+            )
+        })
+    }
+}
@@ -27,6 +27,7 @@
 //! using a relay protocol.
 
 use crate::{
+    application::storage::PeerMetadataStorage,
     counters,
     logging::NetworkSchema,
     peer_manager::{self, conn_notifs_channel, ConnectionRequestSender, PeerManagerError},
@@ -34,7 +35,7 @@ use crate::{
 };
 use aptos_config::{
     config::{Peer, PeerRole, PeerSet},
-    network_id::NetworkContext,
+    network_id::{NetworkContext, PeerNetworkId},
 };
 use aptos_crypto::x25519;
 use aptos_infallible::RwLock;
@@ -118,6 +119,8 @@ pub struct ConnectivityManager<TBackoff> {
     rng: SmallRng,
     /// Whether we are using mutual authentication or not
     mutual_authentication: bool,
+    /// The metadata of the connected peers, including what the peer monitor measured.
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
 }
 
 /// Different sources for peer addresses, ordered by priority (Onchain=highest,
@@ -127,6 +130,7 @@ pub struct ConnectivityManager<TBackoff> {
 pub enum DiscoverySource {
     OnChainValidatorSet,
     File,
+    Rest,
     Config,
 }
 
@@ -144,6 +148,7 @@ impl fmt::Display for DiscoverySource {
             match self {
                 DiscoverySource::OnChainValidatorSet => "OnChainValidatorSet",
                 DiscoverySource::File => "File",
+                DiscoverySource::Rest => "Rest",
                 DiscoverySource::Config => "Config",
             }
         )
@@ -205,6 +210,10 @@ struct DiscoveredPeer {
     keys: PublicKeys,
     /// The last time the node was dialed
     last_dial_time: SystemTime,
+    /// The last average ping latency measured while connected, to prefer dialing the peers with
+    /// the lowest latency. It goes away with the peer, so nothing is kept for the peers that
+    /// only connected to us.
+    observed_latency_secs: Option<f64>,
 }
 
 impl DiscoveredPeer {
@@ -214,6 +223,7 @@ impl DiscoveredPeer {
             addrs: Addresses::default(),
             keys: PublicKeys::default(),
             last_dial_time: SystemTime::UNIX_EPOCH,
+            observed_latency_secs: None,
         }
     }
     /// Peers without keys are not able to be mutually authenticated to
@@ -313,6 +323,7 @@ where
         max_delay: Duration,
         outbound_connection_limit: Option<usize>,
         mutual_authentication: bool,
+        peer_metadata_storage: Arc<PeerMetadataStorage>,
     ) -> Self {
         assert!(
             eligible.read().is_empty(),
@@ -343,6 +354,7 @@ where
             outbound_connection_limit,
             rng: SmallRng::from_entropy(),
             mutual_authentication,
+            peer_metadata_storage,
         };
 
         // set the initial config addresses and pubkeys
@@ -414,8 +426,12 @@ where
         let stale_connections: Vec<_> = self
             .connected
             .iter()
-            .filter(|(peer_id, _)| !eligible.contains_key(peer_id))
+            .filter(|(peer_id, _)| !eligible.contains_key(peer_id) || self.is_banned(peer_id))
             .filter_map(|(peer_id, metadata)| {
+                // Peers banned for misbehaving are always evicted
+                if self.is_banned(peer_id) {
+                    return Some(*peer_id);
+                }
                 // If we're using server only auth, we need to not evict unknown peers
                 // TODO: We should prevent `Unknown` from discovery sources
                 if !self.mutual_authentication
@@ -499,6 +515,7 @@ where
                     && !self.connected.contains_key(peer_id) // The node is not already connected.
                     && !self.dial_queue.contains_key(peer_id) // There is no pending dial to this node.
                     && roles_to_dial.contains(&peer.role) // We can dial this role
+                    && !self.is_banned(peer_id) // The node is not banned for misbehaving
             })
             .collect();
 
@@ -506,9 +523,21 @@ where
         // Shuffle so we don't get stuck on certain peers
         eligible.shuffle(&mut self.rng);
 
-        // Sort by peer priority
-        eligible
-            .sort_by(|(_, peer), (_, other)| peer.partial_cmp(other).unwrap_or(Ordering::Equal));
+        // Sort by peer priority, then prefer the peers with the lowest known latency
+        eligible.sort_by(|(_, peer), (_, other)| {
+            peer.partial_cmp(other)
+                .unwrap_or(Ordering::Equal)
+                .then_with(
+                    || match (peer.observed_latency_secs, other.observed_latency_secs) {
+                        (Some(latency), Some(other_latency)) => latency
+                            .partial_cmp(&other_latency)
+                            .unwrap_or(Ordering::Equal),
+                        (Some(_), None) => Ordering::Less,
+                        (None, Some(_)) => Ordering::Greater,
+                        (None, None) => Ordering::Equal,
+                    },
+                )
+        });
 
         // Limit the number of dialed connections from a Full Node
         // This does not limit the number of incoming connections
@@ -564,7 +593,14 @@ where
 
         // Using the DialState's backoff strategy, compute the delay until
         // the next dial attempt for this peer.
-        let dial_delay = dial_state.next_backoff_delay(self.max_delay);
+        let dial_delay = dial_state.next_backoff_delay(self.max_delay)
+            + self
+                .peer_metadata_storage
+                .reputation()
+                .dial_delay(PeerNetworkId::new(
+                    self.network_context.network_id(),
+                    peer_id,
+                ));
         let f_delay = self.time_service.sleep(dial_delay);
 
         let (cancel_tx, cancel_rx) = oneshot::channel();
@@ -628,6 +664,8 @@ where
             )
         });
 
+        // Remember the latencies of the connected peers, to prefer them when redialing.
+        self.update_observed_latencies();
         // Cancel dials to peers that are no longer eligible.
         self.cancel_stale_dials().await;
         // Disconnect from connected peers that are no longer eligible.
@@ -637,6 +675,32 @@ where
         self.dial_eligible_peers(pending_dials);
     }
 
+    fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.peer_metadata_storage
+            .reputation()
+            .is_banned(PeerNetworkId::new(
+                self.network_context.network_id(),
+                *peer_id,
+            ))
+    }
+
+    fn update_observed_latencies(&mut self) {
+        let connected_peers = self
+            .peer_metadata_storage
+            .read_filtered(self.network_context.network_id(), |(_, peer_info)| {
+                peer_info.is_connected()
+            });
+        for (peer_network_id, peer_info) in connected_peers {
+            let latency = peer_info.monitoring_metadata.average_ping_latency_secs;
+            if let (Some(latency), Some(peer)) = (
+                latency,
+                self.discovered_peers.get_mut(&peer_network_id.peer_id()),
+            ) {
+                peer.observed_latency_secs = Some(latency);
+            }
+        }
+    }
+
     fn reset_dial_state(&mut self, peer_id: &PeerId) {
         if let Some(dial_state) = self.dial_states.get_mut(peer_id) {
             *dial_state = DialState::new(self.backoff_strategy.clone());
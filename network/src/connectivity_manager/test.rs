@@ -99,6 +99,7 @@ impl TestHarness {
             MAX_CONNECTION_DELAY,
             Some(MAX_TEST_CONNECTIONS),
             true, /* mutual_authentication */
+            PeerMetadataStorage::test(),
         );
         let mock = Self {
             trusted_peers,
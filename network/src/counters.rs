@@ -1,8 +1,14 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::protocols::wire::handshake::v1::ProtocolId;
-use aptos_config::network_id::NetworkContext;
+use crate::{
+    application::reputation::Severity, peer::TrafficClass,
+    protocols::wire::handshake::v1::ProtocolId,
+};
+use aptos_config::{
+    config::PeerClass,
+    network_id::{NetworkContext, NetworkId},
+};
 use aptos_metrics::{
     register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
     Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
@@ -23,6 +29,9 @@ pub const RECEIVED_LABEL: &str = "received";
 pub const SENT_LABEL: &str = "sent";
 pub const SUCCEEDED_LABEL: &str = "succeeded";
 pub const FAILED_LABEL: &str = "failed";
+pub const ALLOWED_LABEL: &str = "allowed";
+pub const THROTTLED_LABEL: &str = "throttled";
+pub const DELAYED_LABEL: &str = "delayed";
 
 pub static APTOS_CONNECTIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
@@ -334,6 +343,43 @@ pub static PENDING_HEALTH_CHECKER_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::ne
     .unwrap()
 });
 
+pub static APTOS_NETWORK_PEER_MISBEHAVIOR_REPORTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_network_peer_misbehavior_reports",
+        "Number of misbehaviors reported by the applications, by severity",
+        &["network_id", "severity"]
+    )
+    .unwrap()
+});
+
+pub fn peer_misbehavior_reports(network_id: NetworkId, severity: Severity) -> IntCounter {
+    APTOS_NETWORK_PEER_MISBEHAVIOR_REPORTS
+        .with_label_values(&[network_id.as_str(), severity.as_str()])
+}
+
+pub static APTOS_NETWORK_PEER_BANS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_network_peer_bans",
+        "Number of peers banned for misbehaving",
+        &["network_id"]
+    )
+    .unwrap()
+});
+
+pub fn peer_bans(network_id: NetworkId) -> IntCounter {
+    APTOS_NETWORK_PEER_BANS.with_label_values(&[network_id.as_str()])
+}
+
+/// Counter of pending network events to the PeerMonitor.
+pub static PENDING_PEER_MONITORING_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_network_pending_peer_monitoring_events",
+        "Number of pending peer monitoring events by state",
+        &["state"]
+    )
+    .unwrap()
+});
+
 /// Counter of pending network events to Discovery.
 pub static PENDING_DISCOVERY_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -394,6 +440,48 @@ pub static PENDING_WIRE_MESSAGES: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static APTOS_NETWORK_PENDING_OUTBOUND_MESSAGES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_network_pending_outbound_messages",
+        "Number of messages waiting in the outbound queues of the peers, by traffic class",
+        &["role_type", "network_id", "peer_id", "traffic_class"]
+    )
+    .unwrap()
+});
+
+pub fn pending_outbound_messages(
+    network_context: &NetworkContext,
+    traffic_class: TrafficClass,
+) -> IntGauge {
+    APTOS_NETWORK_PENDING_OUTBOUND_MESSAGES.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        traffic_class.as_str(),
+    ])
+}
+
+pub static APTOS_NETWORK_OUTBOUND_MESSAGES_DROPPED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_network_outbound_messages_dropped",
+        "Number of messages dropped from the full outbound queues of the peers, by traffic class",
+        &["role_type", "network_id", "peer_id", "traffic_class"]
+    )
+    .unwrap()
+});
+
+pub fn outbound_messages_dropped(
+    network_context: &NetworkContext,
+    traffic_class: TrafficClass,
+) -> IntCounter {
+    APTOS_NETWORK_OUTBOUND_MESSAGES_DROPPED.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        traffic_class.as_str(),
+    ])
+}
+
 /// Counter of pending requests in Direct Send
 pub static PENDING_DIRECT_SEND_REQUESTS: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!(
@@ -474,3 +562,73 @@ pub static NETWORK_RATE_LIMIT_METRICS: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static APTOS_NETWORK_PROTOCOL_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_network_protocol_messages",
+        "Number of messages exchanged with peers, by peer class and protocol",
+        &[
+            "role_type",
+            "network_id",
+            "peer_id",
+            "peer_class",
+            "protocol_id",
+            "direction",
+            "state"
+        ]
+    )
+    .unwrap()
+});
+
+pub fn protocol_messages(
+    network_context: &NetworkContext,
+    peer_class: PeerClass,
+    protocol_id: ProtocolId,
+    direction_label: &'static str,
+    state_label: &'static str,
+) -> IntCounter {
+    APTOS_NETWORK_PROTOCOL_MESSAGES.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        peer_class.as_str(),
+        protocol_id.as_str(),
+        direction_label,
+        state_label,
+    ])
+}
+
+pub static APTOS_NETWORK_PROTOCOL_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_network_protocol_bytes",
+        "Number of message bytes exchanged with peers, by peer class and protocol",
+        &[
+            "role_type",
+            "network_id",
+            "peer_id",
+            "peer_class",
+            "protocol_id",
+            "direction",
+            "state"
+        ]
+    )
+    .unwrap()
+});
+
+pub fn protocol_bytes(
+    network_context: &NetworkContext,
+    peer_class: PeerClass,
+    protocol_id: ProtocolId,
+    direction_label: &'static str,
+    state_label: &'static str,
+) -> IntCounter {
+    APTOS_NETWORK_PROTOCOL_BYTES.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        peer_class.as_str(),
+        protocol_id.as_str(),
+        direction_label,
+        state_label,
+    ])
+}
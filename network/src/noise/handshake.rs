@@ -20,7 +20,7 @@ use aptos_infallible::{duration_since_epoch, RwLock};
 use aptos_logger::trace;
 use aptos_types::PeerId;
 use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-use netcore::transport::ConnectionOrigin;
+use netcore::transport::{ConnectionOrigin, SecureChannel};
 use short_hex_str::{AsShortHexStr, ShortHexStr};
 use std::{collections::HashMap, convert::TryFrom as _, fmt::Debug, sync::Arc};
 
@@ -168,7 +168,7 @@ impl NoiseUpgrader {
         remote_public_key: Option<x25519::PublicKey>,
     ) -> Result<(x25519::PublicKey, NoiseStream<TSocket>), NoiseHandshakeError>
     where
-        TSocket: AsyncRead + AsyncWrite + Debug + Unpin,
+        TSocket: AsyncRead + AsyncWrite + SecureChannel + Debug + Unpin,
     {
         // perform the noise handshake
         let socket = match origin {
@@ -202,6 +202,17 @@ impl NoiseUpgrader {
     /// The server's message contains no payload.
     const SERVER_MESSAGE_SIZE: usize = noise::handshake_resp_msg_len(0);
 
+    /// The prologue authenticated by the handshake: the prologue sent by the client, followed
+    /// by the binding of the channel carrying the socket, if any. The binding is never sent,
+    /// both sides observe it on their own and the handshake fails if they don't agree.
+    fn authenticated_prologue(prologue: &[u8], channel_binding: Option<[u8; 32]>) -> Vec<u8> {
+        let mut authenticated_prologue = prologue.to_vec();
+        if let Some(channel_binding) = channel_binding {
+            authenticated_prologue.extend_from_slice(&channel_binding);
+        }
+        authenticated_prologue
+    }
+
     /// Perform an outbound protocol upgrade on this connection.
     ///
     /// This runs the "client" side of the Noise IK handshake to establish a
@@ -209,6 +220,7 @@ impl NoiseUpgrader {
     /// In mutual auth scenarios, we will also include an anti replay attack counter in the
     /// Noise handshake payload. Currently this counter is always a millisecond-
     /// granularity unix epoch timestamp.
+    /// The binding of the channel carrying the socket, if any, is authenticated as well.
     pub async fn upgrade_outbound<TSocket, F>(
         &self,
         mut socket: TSocket,
@@ -216,7 +228,7 @@ impl NoiseUpgrader {
         time_provider: F,
     ) -> Result<NoiseStream<TSocket>, NoiseHandshakeError>
     where
-        TSocket: AsyncRead + AsyncWrite + Debug + Unpin,
+        TSocket: AsyncRead + AsyncWrite + SecureChannel + Debug + Unpin,
         F: Fn() -> [u8; AntiReplayTimestamps::TIMESTAMP_SIZE],
     {
         // buffer to hold prologue + first noise handshake message
@@ -228,6 +240,7 @@ impl NoiseUpgrader {
             .copy_from_slice(remote_public_key.as_slice());
 
         let (prologue_msg, mut client_noise_msg) = client_message.split_at_mut(Self::PROLOGUE_SIZE);
+        let prologue = Self::authenticated_prologue(prologue_msg, socket.channel_binding());
 
         // craft 8-byte payload as current timestamp (in milliseconds)
         let payload = time_provider();
@@ -238,7 +251,7 @@ impl NoiseUpgrader {
             .noise_config
             .initiate_connection(
                 &mut rng,
-                prologue_msg,
+                &prologue,
                 remote_public_key,
                 Some(&payload),
                 &mut client_noise_msg,
@@ -295,12 +308,13 @@ impl NoiseUpgrader {
     /// that successfully authenticate to a public key in our `trusted_peers` set.
     /// In addition, we will expect the client to include an anti replay attack
     /// counter in the Noise handshake payload in mutual auth scenarios.
+    /// The binding of the channel carrying the socket, if any, is authenticated as well.
     pub async fn upgrade_inbound<TSocket>(
         &self,
         mut socket: TSocket,
     ) -> Result<(NoiseStream<TSocket>, PeerId, PeerRole), NoiseHandshakeError>
     where
-        TSocket: AsyncRead + AsyncWrite + Debug + Unpin,
+        TSocket: AsyncRead + AsyncWrite + SecureChannel + Debug + Unpin,
     {
         // buffer to contain the client first message
         let mut client_message = [0; Self::CLIENT_MESSAGE_SIZE];
@@ -341,9 +355,10 @@ impl NoiseUpgrader {
 
         // parse it
         let (prologue, client_init_message) = client_message.split_at(Self::PROLOGUE_SIZE);
+        let prologue = Self::authenticated_prologue(prologue, socket.channel_binding());
         let (remote_public_key, handshake_state, payload) = self
             .noise_config
-            .parse_client_init_message(prologue, client_init_message)
+            .parse_client_init_message(&prologue, client_init_message)
             .map_err(|err| NoiseHandshakeError::ServerParseClient(remote_peer_short, err))?;
 
         // if mutual auth mode, verify the remote pubkey is in our set of trusted peers
@@ -720,4 +735,43 @@ mod test {
         client_session.unwrap();
         server_session.unwrap();
     }
+
+    /// helper to perform a noise handshake over channels with the given bindings
+    fn perform_handshake_with_channel_bindings(
+        dialer_channel_binding: [u8; 32],
+        listener_channel_binding: [u8; 32],
+    ) -> (
+        Result<NoiseStream<ReadWriteTestSocket<'static>>, NoiseHandshakeError>,
+        Result<(NoiseStream<ReadWriteTestSocket<'static>>, PeerId, PeerRole), NoiseHandshakeError>,
+    ) {
+        let (mut dialer_socket, mut listener_socket) = ReadWriteTestSocket::new_pair();
+        dialer_socket.set_channel_binding(dialer_channel_binding);
+        listener_socket.set_channel_binding(listener_channel_binding);
+
+        let ((client, _client_public_key), (server, server_public_key)) = build_peers(true);
+        block_on(join(
+            client.upgrade_outbound(dialer_socket, server_public_key, AntiReplayTimestamps::now),
+            server.upgrade_inbound(listener_socket),
+        ))
+    }
+
+    #[test]
+    fn test_handshake_same_channel_binding() {
+        let (client_res, server_res) = perform_handshake_with_channel_bindings([1; 32], [1; 32]);
+
+        client_res.unwrap();
+        server_res.unwrap();
+    }
+
+    #[test]
+    fn test_handshake_channel_binding_mismatch_fails() {
+        // e.g., a man in the middle terminated the channel on both sides
+        let (client_res, server_res) = perform_handshake_with_channel_bindings([1; 32], [2; 32]);
+
+        client_res.unwrap_err();
+        assert!(matches!(
+            server_res.unwrap_err(),
+            NoiseHandshakeError::ServerParseClient(_, _)
+        ));
+    }
 }
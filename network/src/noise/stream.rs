@@ -21,6 +21,7 @@ use std::{
 
 use aptos_crypto::{noise, x25519};
 use aptos_logger::prelude::*;
+use netcore::transport::{quic::QuicStreams, SecureChannel};
 
 //
 // NoiseStream
@@ -421,6 +422,19 @@ where
     }
 }
 
+impl<TSocket> SecureChannel for NoiseStream<TSocket>
+where
+    TSocket: SecureChannel,
+{
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        self.socket.channel_binding()
+    }
+
+    fn take_streams(&mut self) -> Option<QuicStreams> {
+        self.socket.take_streams()
+    }
+}
+
 //
 // NoiseBuffers
 // ------------
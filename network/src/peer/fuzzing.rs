@@ -4,6 +4,7 @@
 use crate::{
     constants,
     peer::Peer,
+    peer_manager::ProtocolLimiter,
     protocols::wire::{
         handshake::v1::{MessagingProtocolVersion, ProtocolIdSet},
         messaging::v1::{NetworkMessage, NetworkMessageSink},
@@ -106,8 +107,10 @@ pub fn fuzz(data: &[u8]) {
         constants::MAX_CONCURRENT_INBOUND_RPCS,
         constants::MAX_CONCURRENT_OUTBOUND_RPCS,
         constants::MAX_FRAME_SIZE,
+        constants::MAX_MESSAGE_SIZE,
         None,
         None,
+        ProtocolLimiter::open(network_context, PeerRole::Unknown),
     );
     executor.spawn(peer.start());
 
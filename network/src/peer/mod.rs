@@ -12,18 +12,29 @@
 //! [`Peer`] owns the actual underlying connection socket and is reponsible for
 //! the socket's shutdown, graceful or otherwise.
 //!
+//! If the connection multiplexes streams (i.e., QUIC), the messages of each application
+//! protocol negotiated with the remote peer are written on their own stream, so that the
+//! protocols don't hold back each other, and the messages of the streams opened by the remote
+//! peer are handled along with the messages of the connection socket. RPC responses are
+//! written on the stream of the protocol of their request. Messages are never fragmented on a
+//! stream.
+//!
 //! [`PeerManager`]: crate::peer_manager::PeerManager
 
 use crate::{
     counters::{self, RECEIVED_LABEL, SENT_LABEL},
     logging::NetworkSchema,
-    peer_manager::{PeerManagerError, TransportNotification},
+    peer_manager::{PeerManagerError, ProtocolLimiter, TransportNotification},
     protocols::{
         direct_send::Message,
         rpc::{InboundRpcRequest, InboundRpcs, OutboundRpcRequest, OutboundRpcs},
-        wire::messaging::v1::{
-            DirectSendMsg, ErrorCode, NetworkMessage, NetworkMessageSink, NetworkMessageStream,
-            Priority, ReadError, WriteError,
+        stream::{InboundStreamBuffer, OutboundStream, StreamError},
+        wire::{
+            compression,
+            messaging::v1::{
+                DirectSendMsg, ErrorCode, NetworkMessage, NetworkMessageSink, NetworkMessageStream,
+                ReadError, WriteError,
+            },
         },
     },
     transport::{self, Connection, ConnectionMetadata},
@@ -38,25 +49,37 @@ use bytes::Bytes;
 use channel::aptos_channel;
 use futures::{
     self,
-    channel::oneshot,
+    channel::{mpsc, oneshot},
     io::{AsyncRead, AsyncWrite},
     stream::StreamExt,
     FutureExt, SinkExt, TryFutureExt,
 };
+use netcore::transport::{quic::QuicStreams, SecureChannel};
 use serde::Serialize;
 use short_hex_str::AsShortHexStr;
-use std::{fmt, panic, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt, io, panic,
+    time::Duration,
+};
 use tokio::runtime::Handle;
 use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
 };
 
+mod outbound_queues;
+pub use outbound_queues::TrafficClass;
+use outbound_queues::{OutboundQueues, WriteRequest};
+
 #[cfg(test)]
 mod test;
 
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod fuzzing;
 
+/// The maximum number of messages waiting to be written on a stream.
+const MAX_PENDING_STREAM_MESSAGES: usize = 1024;
+
 /// Requests [`Peer`] receives from the [`PeerManager`](crate::peer_manager::PeerManager).
 #[derive(Debug)]
 pub enum PeerRequest {
@@ -97,6 +120,16 @@ impl fmt::Display for DisconnectReason {
     }
 }
 
+/// A fragmented message being written by the writer task.
+struct PendingStream {
+    /// The class of the fragmented message.
+    class: TrafficClass,
+    /// The fragments left to write, starting with the `StreamHeader`.
+    fragments: VecDeque<NetworkMessage>,
+    /// Notified once the last fragment is written.
+    ack_ch: oneshot::Sender<Result<(), PeerManagerError>>,
+}
+
 enum State {
     Connected,
     ShuttingDown(DisconnectReason),
@@ -128,18 +161,26 @@ pub struct Peer<TSocket> {
     /// Flag to indicate if the actor is being shut down.
     state: State,
     /// The maximum size of an inbound or outbound request frame
-    /// Currently, requests are only a single frame
     max_frame_size: usize,
+    /// The maximum size of an inbound or outbound message. Messages larger than a
+    /// frame are sent as fragments if the connection supports it.
+    max_message_size: usize,
+    /// The inbound message being reassembled from its fragments, if the connection
+    /// supports fragmented messages.
+    inbound_stream: Option<InboundStreamBuffer>,
     /// Optional inbound rate limiter
     inbound_rate_limiter: Option<SharedBucket>,
     /// Optional outbound rate limiter
     outbound_rate_limiter: Option<SharedBucket>,
+    /// Accounts and rate limits the messages by protocol
+    protocol_limiter: ProtocolLimiter,
 }
 
 impl<TSocket> Peer<TSocket>
 where
-    TSocket: AsyncRead + AsyncWrite + Send + 'static,
+    TSocket: AsyncRead + AsyncWrite + SecureChannel + Send + 'static,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         network_context: NetworkContext,
         executor: Handle,
@@ -152,14 +193,22 @@ where
         max_concurrent_inbound_rpcs: u32,
         max_concurrent_outbound_rpcs: u32,
         max_frame_size: usize,
+        max_message_size: usize,
         inbound_rate_limiter: Option<SharedBucket>,
         outbound_rate_limiter: Option<SharedBucket>,
+        protocol_limiter: ProtocolLimiter,
     ) -> Self {
         let Connection {
             metadata: connection_metadata,
             socket,
         } = connection;
         let remote_peer_id = connection_metadata.remote_peer_id;
+        let remote_protocols = connection_metadata.application_protocols.clone();
+        let inbound_stream = if connection_metadata.messaging_protocol.supports_streaming() {
+            Some(InboundStreamBuffer::new(max_message_size))
+        } else {
+            None
+        };
         Self {
             network_context,
             executor,
@@ -175,17 +224,25 @@ where
                 remote_peer_id,
                 inbound_rpc_timeout,
                 max_concurrent_inbound_rpcs,
+                max_message_size,
+                protocol_limiter.clone(),
             ),
             outbound_rpcs: OutboundRpcs::new(
                 network_context,
                 time_service,
                 remote_peer_id,
+                remote_protocols,
                 max_concurrent_outbound_rpcs,
+                max_message_size,
+                protocol_limiter.clone(),
             ),
             state: State::Connected,
             max_frame_size,
+            max_message_size,
+            inbound_stream,
             inbound_rate_limiter,
             outbound_rate_limiter,
+            protocol_limiter,
         }
     }
 
@@ -203,9 +260,12 @@ where
             remote_peer_id.short_str()
         );
 
+        // Take the streams of the connection, if any, before splitting it.
+        let mut socket = self.connection.take().unwrap();
+        let streams = socket.take_streams();
+
         // Split the connection into a ReadHalf and a WriteHalf.
-        let (read_socket, write_socket) =
-            tokio::io::split(self.connection.take().unwrap().compat());
+        let (read_socket, write_socket) = tokio::io::split(socket.compat());
 
         let mut reader = NetworkMessageStream::new(
             read_socket.compat(),
@@ -219,6 +279,27 @@ where
             self.outbound_rate_limiter.clone(),
         );
 
+        let outbound_stream = if self
+            .connection_metadata
+            .messaging_protocol
+            .supports_streaming()
+        {
+            Some(OutboundStream::new(
+                self.max_frame_size,
+                self.max_message_size,
+            ))
+        } else {
+            None
+        };
+
+        // Start the tasks writing and reading the streams of the connection, if any.
+        let (stream_messages_tx, mut stream_messages_rx) =
+            mpsc::channel(MAX_PENDING_STREAM_MESSAGES);
+        let mut stream_writers = match streams {
+            Some(streams) => self.start_stream_tasks(streams, stream_messages_tx).await,
+            None => HashMap::new(),
+        };
+
         // Start writer "process" as a separate task. We receive two handles to
         // communicate with the task:
         //   1. `write_reqs_tx`: Queue of pending NetworkMessages to write.
@@ -229,6 +310,8 @@ where
             self.connection_metadata.clone(),
             self.network_context,
             writer,
+            outbound_stream,
+            stream_writers.clone(),
         );
 
         // Start main Peer event loop.
@@ -268,10 +351,25 @@ where
                         None => self.shutdown(DisconnectReason::ConnectionLost),
                     }
                 },
+                // Handle a new inbound NetworkMessage read off one of the streams of the
+                // connection. The streams end along with the connection socket.
+                message = stream_messages_rx.select_next_some() => {
+                    if let Err(err) = self.handle_inbound_message(message, &mut write_reqs_tx).await {
+                        warn!(
+                            NetworkSchema::new(&self.network_context)
+                                .connection_metadata(&self.connection_metadata),
+                            error = %err,
+                            "{} Error in handling inbound stream message from peer: {}, error: {}",
+                            self.network_context,
+                            remote_peer_id.short_str(),
+                            err
+                        );
+                    }
+                },
                 // Drive the queue of pending inbound rpcs. When one is fulfilled
                 // by an upstream protocol, send the response to the remote peer.
                 maybe_response = self.inbound_rpcs.next_completed_response() => {
-                    if let Err(err) = self.inbound_rpcs.send_outbound_response(&mut write_reqs_tx, maybe_response).await {
+                    if let Err(err) = self.inbound_rpcs.send_outbound_response(&mut write_reqs_tx, &mut stream_writers, maybe_response).await {
                         warn!(
                             NetworkSchema::new(&self.network_context).connection_metadata(&self.connection_metadata),
                             error = %err,
@@ -299,12 +397,21 @@ where
     // 2. The second channel is used to instruct the task to close the connection and terminate.
     // If outbound messages are queued when the task receives a close instruction, it discards
     // them and immediately closes the connection.
+    // Outbound messages are written by priority: see `OutboundQueues`.
+    // Messages of a protocol with a `stream_writers` entry are handed to the writer of its stream
+    // as soon as they are received, instead of being queued.
+    // Messages larger than a frame are split by the `outbound_stream`, if any, and their
+    // fragments are written one at a time: the messages of a higher class that fit in a frame
+    // are written between them, and the next fragmented message is started once the previous
+    // one is complete.
     fn start_writer_task(
         executor: &Handle,
         time_service: TimeService,
         connection_metadata: ConnectionMetadata,
         network_context: NetworkContext,
         mut writer: NetworkMessageSink<impl AsyncWrite + Unpin + Send + 'static>,
+        outbound_stream: Option<OutboundStream>,
+        mut stream_writers: HashMap<ProtocolId, mpsc::Sender<WriteRequest>>,
     ) -> (
         channel::Sender<(
             NetworkMessage,
@@ -323,29 +430,96 @@ where
         let (close_tx, close_rx) = oneshot::channel();
         let writer_task = async move {
             let mut close_rx = close_rx.into_stream();
+            let mut outbound_queues = OutboundQueues::new(network_context);
+            let mut pending_stream: Option<PendingStream> = None;
             loop {
-                futures::select! {
-                    (message, ack_ch) = write_reqs_rx.select_next_some() => {
-                        if let Err(err) = writer
-                            .send(&message)
-                            .map_ok(|_| ack_ch.send(Ok(())))
-                            .await
-                        {
+                // Move the messages queued while writing to their class queue, so that the
+                // highest priority message is written next.
+                while let Some(Some(request)) = write_reqs_rx.next().now_or_never() {
+                    Self::queue_write_request(&mut outbound_queues, &mut stream_writers, request);
+                }
+                let request = match &pending_stream {
+                    // Only the messages of a higher class that don't need to be fragmented go
+                    // between the fragments, the remote peer reassembles one message at a time.
+                    Some(stream) => outbound_queues.pop_above(stream.class, |message| {
+                        !matches!(
+                            &outbound_stream,
+                            Some(outbound_stream) if outbound_stream.should_stream(message)
+                        )
+                    }),
+                    None => outbound_queues.pop(),
+                };
+                if (request.is_some() || pending_stream.is_some())
+                    && close_rx.select_next_some().now_or_never().is_some()
+                {
+                    break;
+                }
+                let (message, ack_ch) = match request {
+                    Some((message, ack_ch)) => (message, ack_ch),
+                    None => match pending_stream.as_mut() {
+                        Some(stream) => {
+                            let fragment = stream
+                                .fragments
+                                .pop_front()
+                                .expect("Pending streams have fragments left");
+                            let last_fragment = stream.fragments.is_empty();
+                            if let Err(err) = writer.send(&fragment).await {
+                                Self::log_write_error(&network_context, &connection_metadata, &err);
+                                break;
+                            }
+                            if last_fragment {
+                                if let Some(stream) = pending_stream.take() {
+                                    let _ = stream.ack_ch.send(Ok(()));
+                                }
+                            } else {
+                                // Let the messages sent meanwhile be queued before the next
+                                // fragment, even if the socket never blocks.
+                                tokio::task::yield_now().await;
+                            }
+                            continue;
+                        }
+                        None => futures::select! {
+                            request = write_reqs_rx.select_next_some() => {
+                                Self::queue_write_request(&mut outbound_queues, &mut stream_writers, request);
+                                continue;
+                            },
+                            _ = close_rx.select_next_some() => {
+                                break;
+                            }
+                        },
+                    },
+                };
+                let class = TrafficClass::of_message(&message);
+                if let Some(outbound_stream) = outbound_stream
+                    .as_ref()
+                    .filter(|outbound_stream| outbound_stream.should_stream(&message))
+                {
+                    match outbound_stream.fragment(message) {
+                        Ok(fragments) => {
+                            pending_stream = Some(PendingStream {
+                                class,
+                                fragments: fragments.into(),
+                                ack_ch,
+                            });
+                        }
+                        Err(err) => {
                             warn!(
                                 NetworkSchema::new(&network_context)
                                     .connection_metadata(&connection_metadata),
                                 error = %err,
-                                "{} Error in sending message to peer: {}, error: {}",
+                                "{} Dropping message to peer: {}, error: {}",
                                 network_context,
                                 remote_peer_id.short_str(),
                                 err
                             );
-                            break;
+                            let _ = ack_ch.send(Err(anyhow::anyhow!(err).into()));
                         }
-                    },
-                    _ = close_rx.select_next_some() => {
-                        break;
                     }
+                    continue;
+                }
+                if let Err(err) = writer.send(&message).map_ok(|_| ack_ch.send(Ok(()))).await {
+                    Self::log_write_error(&network_context, &connection_metadata, &err);
+                    break;
                 }
             }
             info!(
@@ -398,6 +572,177 @@ where
         (write_reqs_tx, close_tx)
     }
 
+    // Opens a stream for each of the application protocols negotiated with the remote peer, with
+    // a task writing the messages of the protocol on it, and starts a task accepting the streams
+    // opened by the remote peer, forwarding the messages read off them to `stream_messages_tx`.
+    // The protocols whose stream can't be opened stay on the connection socket.
+    // All the tasks end along with the connection: the writers once the returned channels are
+    // dropped by the writer task and the peer, the readers once the connection is closed.
+    async fn start_stream_tasks(
+        &mut self,
+        mut streams: QuicStreams,
+        stream_messages_tx: mpsc::Sender<Result<NetworkMessage, ReadError>>,
+    ) -> HashMap<ProtocolId, mpsc::Sender<WriteRequest>> {
+        let remote_peer_id = self.remote_peer_id();
+        let protocols = self.connection_metadata.application_protocols.clone();
+        let mut stream_writers = HashMap::new();
+        for protocol_id in protocols.iter() {
+            match streams.open_stream(protocol_id as u8).await {
+                Ok(stream) => {
+                    let writer = NetworkMessageSink::new(
+                        stream,
+                        self.max_message_size,
+                        self.outbound_rate_limiter.clone(),
+                    );
+                    let stream_writer = Self::start_stream_writer_task(
+                        &self.executor,
+                        self.connection_metadata.clone(),
+                        self.network_context,
+                        writer,
+                    );
+                    stream_writers.insert(protocol_id, stream_writer);
+                }
+                Err(err) => {
+                    warn!(
+                        NetworkSchema::new(&self.network_context)
+                            .connection_metadata(&self.connection_metadata),
+                        error = %err,
+                        "{} Unable to open the {} stream to peer: {}, error: {}",
+                        self.network_context,
+                        protocol_id,
+                        remote_peer_id.short_str(),
+                        err
+                    );
+                }
+            }
+        }
+
+        let executor = self.executor.clone();
+        let max_message_size = self.max_message_size;
+        let inbound_rate_limiter = self.inbound_rate_limiter.clone();
+        let accept_task = async move {
+            while let Ok((stream_id, stream)) = streams.accept_stream().await {
+                // Only the streams of the negotiated protocols are read
+                match bcs::from_bytes::<ProtocolId>(&[stream_id]) {
+                    Ok(protocol_id) if protocols.contains(protocol_id) => (),
+                    _ => continue,
+                }
+                let mut reader = NetworkMessageStream::new(
+                    stream,
+                    max_message_size,
+                    inbound_rate_limiter.clone(),
+                );
+                let mut stream_messages_tx = stream_messages_tx.clone();
+                executor.spawn(async move {
+                    while let Some(message) = reader.next().await {
+                        if stream_messages_tx.send(message).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        };
+        self.executor.spawn(accept_task);
+
+        stream_writers
+    }
+
+    // Starts a task writing the messages sent to the returned channel on a stream, in order,
+    // until the channel is dropped or the stream fails.
+    fn start_stream_writer_task(
+        executor: &Handle,
+        connection_metadata: ConnectionMetadata,
+        network_context: NetworkContext,
+        mut writer: NetworkMessageSink<impl AsyncWrite + Unpin + Send + 'static>,
+    ) -> mpsc::Sender<WriteRequest> {
+        let remote_peer_id = connection_metadata.remote_peer_id;
+        let (write_reqs_tx, mut write_reqs_rx) = mpsc::channel(MAX_PENDING_STREAM_MESSAGES);
+        let writer_task = async move {
+            while let Some((message, ack_ch)) = write_reqs_rx.next().await {
+                match writer.send(&message).await {
+                    Ok(()) => {
+                        let _ = ack_ch.send(Ok(()));
+                    }
+                    // The message wasn't written, e.g., it is larger than a frame
+                    Err(WriteError::SerializeError(err)) => {
+                        let _ = ack_ch.send(Err(anyhow::anyhow!(err).into()));
+                    }
+                    Err(WriteError::IoError(err)) if err.kind() == io::ErrorKind::InvalidInput => {
+                        let _ = ack_ch.send(Err(anyhow::anyhow!(err).into()));
+                    }
+                    Err(err) => {
+                        warn!(
+                            NetworkSchema::new(&network_context)
+                                .connection_metadata(&connection_metadata),
+                            error = %err,
+                            "{} Error in sending stream message to peer: {}, error: {}",
+                            network_context,
+                            remote_peer_id.short_str(),
+                            err
+                        );
+                        let _ = ack_ch.send(Err(anyhow::anyhow!(err).into()));
+                        break;
+                    }
+                }
+            }
+            let _ = writer.close().await;
+        };
+        executor.spawn(writer_task);
+        write_reqs_tx
+    }
+
+    /// Hands a message to the writer of the stream of its protocol, if any, or queues it in its
+    /// class queue, failing the message dropped if the queue is full.
+    fn queue_write_request(
+        outbound_queues: &mut OutboundQueues,
+        stream_writers: &mut HashMap<ProtocolId, mpsc::Sender<WriteRequest>>,
+        request: WriteRequest,
+    ) {
+        let stream_writer = Self::stream_protocol(&request.0)
+            .and_then(|protocol_id| stream_writers.get_mut(&protocol_id));
+        if let Some(stream_writer) = stream_writer {
+            if let Err(err) = stream_writer.try_send(request) {
+                let error = if err.is_full() {
+                    "Outbound queue is full"
+                } else {
+                    "Stream is closed"
+                };
+                let (_, ack_ch) = err.into_inner();
+                let _ = ack_ch.send(Err(anyhow::anyhow!(error).into()));
+            }
+            return;
+        }
+        if let Some((_, ack_ch)) = outbound_queues.push(request) {
+            let _ = ack_ch.send(Err(anyhow::anyhow!("Outbound queue is full").into()));
+        }
+    }
+
+    /// The protocol of the stream a message is written on, if the connection has one. The RPC
+    /// responses are handed to the stream writers by [`InboundRpcs`], which knows the protocol
+    /// of their request.
+    fn stream_protocol(message: &NetworkMessage) -> Option<ProtocolId> {
+        match message {
+            NetworkMessage::RpcRequest(request) => Some(request.protocol_id),
+            NetworkMessage::DirectSendMsg(message) => Some(message.protocol_id),
+            _ => None,
+        }
+    }
+
+    fn log_write_error(
+        network_context: &NetworkContext,
+        connection_metadata: &ConnectionMetadata,
+        err: &WriteError,
+    ) {
+        warn!(
+            NetworkSchema::new(network_context).connection_metadata(connection_metadata),
+            error = %err,
+            "{} Error in sending message to peer: {}, error: {}",
+            network_context,
+            connection_metadata.remote_peer_id.short_str(),
+            err
+        );
+    }
+
     async fn handle_inbound_message(
         &mut self,
         message: Result<NetworkMessage, ReadError>,
@@ -438,6 +783,11 @@ where
             },
         };
 
+        self.dispatch_inbound_message(message);
+        Ok(())
+    }
+
+    fn dispatch_inbound_message(&mut self, message: NetworkMessage) {
         match message {
             NetworkMessage::DirectSendMsg(message) => self.handle_inbound_direct_send(message),
             NetworkMessage::Error(error_msg) => {
@@ -469,8 +819,41 @@ where
             NetworkMessage::RpcResponse(response) => {
                 self.outbound_rpcs.handle_inbound_response(response)
             }
+            NetworkMessage::StreamHeader(header) => {
+                let result = match &mut self.inbound_stream {
+                    Some(inbound_stream) => inbound_stream.new_stream(header),
+                    None => Err(StreamError::NotSupported),
+                };
+                if let Err(err) = result {
+                    self.log_stream_error(err);
+                }
+            }
+            NetworkMessage::StreamFragment(fragment) => {
+                let result = match &mut self.inbound_stream {
+                    Some(inbound_stream) => inbound_stream.append_fragment(fragment),
+                    None => Err(StreamError::NotSupported),
+                };
+                match result {
+                    Ok(Some(message)) => self.dispatch_inbound_message(message),
+                    Ok(None) => (),
+                    Err(err) => self.log_stream_error(err),
+                }
+            }
         };
-        Ok(())
+    }
+
+    /// A broken stream only loses the message being reassembled, so we keep the
+    /// connection.
+    fn log_stream_error(&self, err: StreamError) {
+        warn!(
+            NetworkSchema::new(&self.network_context)
+                .connection_metadata(&self.connection_metadata),
+            error = %err,
+            "{} Error in reassembling message from peer: {}, error: {}",
+            self.network_context,
+            self.remote_peer_id().short_str(),
+            err
+        );
     }
 
     /// Handle an inbound DirectSendMsg from the remote peer. There's not much to
@@ -490,10 +873,42 @@ where
             protocol_id
         );
 
+        if !self.protocol_limiter.allow_inbound(protocol_id, data.len()) {
+            debug!(
+                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                "{} Dropping rate limited message from peer {} for protocol {:?}",
+                self.network_context,
+                peer_id.short_str(),
+                protocol_id
+            );
+            return;
+        }
+
         counters::direct_send_messages(&self.network_context, RECEIVED_LABEL).inc();
         counters::direct_send_bytes(&self.network_context, RECEIVED_LABEL)
             .inc_by(data.len() as u64);
 
+        // The application receives the message decompressed, with the uncompressed protocol.
+        let (protocol_id, data) = if protocol_id.is_compressed() {
+            match compression::decompress(&data, self.max_message_size) {
+                Ok(data) => (protocol_id.uncompressed(), data),
+                Err(err) => {
+                    warn!(
+                        NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                        error = %err,
+                        "{} Dropping message from peer {} for protocol {:?} that failed to decompress: {}",
+                        self.network_context,
+                        peer_id.short_str(),
+                        protocol_id,
+                        err
+                    );
+                    return;
+                }
+            }
+        } else {
+            (protocol_id, data)
+        };
+
         let notif = PeerNotification::RecvMessage(Message {
             protocol_id,
             mdata: Bytes::from(data),
@@ -527,12 +942,34 @@ where
             // To send an outbound DirectSendMsg, we just bump some counters and
             // push it onto our outbound writer queue.
             PeerRequest::SendDirectSend(message) => {
-                let message_len = message.mdata.len();
-                let protocol_id = message.protocol_id;
+                // Compress the message if the remote peer supports the compressed protocol.
+                let protocol_id = message
+                    .protocol_id
+                    .preferred_for(&self.connection_metadata.application_protocols);
+                let raw_msg = if protocol_id != message.protocol_id {
+                    compression::compress(&message.mdata)
+                } else {
+                    Vec::from(message.mdata.as_ref())
+                };
+                let message_len = raw_msg.len();
+                if !self
+                    .protocol_limiter
+                    .allow_outbound(protocol_id, message_len)
+                {
+                    debug!(
+                        NetworkSchema::new(&self.network_context)
+                            .connection_metadata(&self.connection_metadata),
+                        "{} Dropping rate limited message to peer {} for protocol {:?}",
+                        self.network_context,
+                        self.remote_peer_id().short_str(),
+                        protocol_id
+                    );
+                    return;
+                }
                 let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
                     protocol_id,
-                    priority: Priority::default(),
-                    raw_msg: Vec::from(message.mdata.as_ref()),
+                    priority: TrafficClass::from(protocol_id).priority(),
+                    raw_msg,
                 });
                 let (ack_tx, _ack_rx) = oneshot::channel();
 
@@ -0,0 +1,344 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! The outbound queues of a [`Peer`](crate::peer::Peer) connection.
+//!
+//! The messages waiting to be written to the socket are queued by [`TrafficClass`], derived
+//! from the priority of the message, and are written in strict priority order: a message is
+//! only written once no message of a higher class is waiting, so that consensus messages are
+//! not delayed behind a burst of state sync responses. Messages of the same class are written
+//! in the order they were queued.
+//!
+//! Each class queue is bounded and drops a message when full, following the [`QueueStyle`]
+//! of its class: [`QueueStyle::FIFO`] drops the newest message, [`QueueStyle::KLAST`] drops
+//! the oldest one.
+
+use crate::{
+    counters,
+    peer_manager::PeerManagerError,
+    protocols::wire::messaging::v1::{NetworkMessage, Priority},
+    ProtocolId,
+};
+use aptos_config::network_id::NetworkContext;
+use channel::message_queues::QueueStyle;
+use futures::channel::oneshot;
+use std::collections::VecDeque;
+
+/// A message waiting to be written, with the channel to notify once it is written.
+pub type WriteRequest = (
+    NetworkMessage,
+    oneshot::Sender<Result<(), PeerManagerError>>,
+);
+
+/// The classes of outbound traffic, from the lowest to the highest priority.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(u8)]
+pub enum TrafficClass {
+    /// State sync and storage service messages.
+    Bulk = 0,
+    /// Mempool broadcasts.
+    Mempool = 1,
+    /// Health checks, peer monitoring, discovery and protocol errors.
+    Control = 2,
+    /// Consensus messages.
+    Consensus = 3,
+}
+
+impl TrafficClass {
+    /// All the classes, from the highest to the lowest priority.
+    pub const ALL: [TrafficClass; 4] = [
+        TrafficClass::Consensus,
+        TrafficClass::Control,
+        TrafficClass::Mempool,
+        TrafficClass::Bulk,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TrafficClass::Bulk => "bulk",
+            TrafficClass::Mempool => "mempool",
+            TrafficClass::Control => "control",
+            TrafficClass::Consensus => "consensus",
+        }
+    }
+
+    /// The priority set on the messages of this class.
+    pub fn priority(self) -> Priority {
+        self as Priority
+    }
+
+    /// The class of the messages sent with `priority`. Priorities above the highest class
+    /// belong to the highest class.
+    pub fn from_priority(priority: Priority) -> Self {
+        match priority {
+            0 => TrafficClass::Bulk,
+            1 => TrafficClass::Mempool,
+            2 => TrafficClass::Control,
+            _ => TrafficClass::Consensus,
+        }
+    }
+
+    /// The class a message is queued in.
+    pub fn of_message(message: &NetworkMessage) -> Self {
+        match message {
+            NetworkMessage::RpcRequest(request) => Self::from_priority(request.priority),
+            NetworkMessage::RpcResponse(response) => Self::from_priority(response.priority),
+            NetworkMessage::DirectSendMsg(message) => Self::from_priority(message.priority),
+            NetworkMessage::Error(_) => TrafficClass::Control,
+            // Fragments are only created by the writer, after being scheduled.
+            NetworkMessage::StreamHeader(_) | NetworkMessage::StreamFragment(_) => {
+                TrafficClass::Bulk
+            }
+        }
+    }
+
+    /// The policy for dropping messages once the queue of this class is full.
+    fn queue_style(self) -> QueueStyle {
+        match self {
+            // Newer broadcasts supersede older ones, and unacknowledged broadcasts are retried.
+            TrafficClass::Mempool => QueueStyle::KLAST,
+            // Dropped requests and responses time out on the requester and are retried.
+            TrafficClass::Bulk | TrafficClass::Control | TrafficClass::Consensus => {
+                QueueStyle::FIFO
+            }
+        }
+    }
+
+    /// The maximum number of messages waiting in the queue of this class.
+    fn max_queue_size(self) -> usize {
+        match self {
+            TrafficClass::Control => 256,
+            TrafficClass::Bulk | TrafficClass::Mempool | TrafficClass::Consensus => 1024,
+        }
+    }
+}
+
+impl From<ProtocolId> for TrafficClass {
+    fn from(protocol_id: ProtocolId) -> Self {
+        match protocol_id {
+            ProtocolId::ConsensusRpcBcs
+            | ProtocolId::ConsensusDirectSendBcs
+            | ProtocolId::ConsensusDirectSendJson
+            | ProtocolId::ConsensusRpcJson => TrafficClass::Consensus,
+            ProtocolId::HealthCheckerRpc
+            | ProtocolId::DiscoveryDirectSend
+            | ProtocolId::PeerMonitoringServiceRpc => TrafficClass::Control,
+            ProtocolId::MempoolDirectSend
+            | ProtocolId::MempoolRpc
+            | ProtocolId::MempoolDirectSendCompressed => TrafficClass::Mempool,
+            ProtocolId::StateSyncDirectSend
+            | ProtocolId::StorageServiceRpc
+            | ProtocolId::StateSyncDirectSendCompressed
+            | ProtocolId::StorageServiceRpcCompressed => TrafficClass::Bulk,
+        }
+    }
+}
+
+/// The per-class queues of the messages waiting to be written to a connection.
+pub struct OutboundQueues {
+    network_context: NetworkContext,
+    /// The queues, indexed by `TrafficClass`.
+    queues: [VecDeque<WriteRequest>; 4],
+}
+
+impl OutboundQueues {
+    pub fn new(network_context: NetworkContext) -> Self {
+        Self {
+            network_context,
+            queues: Default::default(),
+        }
+    }
+
+    /// Queues a message in the queue of its class. Returns the message dropped from the queue,
+    /// if it was full.
+    pub fn push(&mut self, request: WriteRequest) -> Option<WriteRequest> {
+        let class = TrafficClass::of_message(&request.0);
+        let queue = &mut self.queues[class as usize];
+        let dropped = if queue.len() < class.max_queue_size() {
+            queue.push_back(request);
+            None
+        } else {
+            match class.queue_style() {
+                QueueStyle::FIFO => Some(request),
+                QueueStyle::LIFO | QueueStyle::KLAST => {
+                    let oldest = queue.pop_front();
+                    queue.push_back(request);
+                    oldest
+                }
+            }
+        };
+        match dropped {
+            Some(_) => counters::outbound_messages_dropped(&self.network_context, class).inc(),
+            None => counters::pending_outbound_messages(&self.network_context, class).inc(),
+        }
+        dropped
+    }
+
+    /// Removes the oldest message of the highest class with waiting messages.
+    pub fn pop(&mut self) -> Option<WriteRequest> {
+        let network_context = &self.network_context;
+        let queues = &mut self.queues;
+        TrafficClass::ALL.iter().find_map(|class| {
+            let request = queues[*class as usize].pop_front()?;
+            counters::pending_outbound_messages(network_context, *class).dec();
+            Some(request)
+        })
+    }
+
+    /// Removes the oldest message of the highest class above `class` whose oldest message
+    /// satisfies `predicate`.
+    pub fn pop_above(
+        &mut self,
+        class: TrafficClass,
+        predicate: impl Fn(&NetworkMessage) -> bool,
+    ) -> Option<WriteRequest> {
+        let network_context = &self.network_context;
+        let queues = &mut self.queues;
+        TrafficClass::ALL
+            .iter()
+            .take_while(|higher| **higher > class)
+            .find_map(|higher| {
+                let queue = &mut queues[*higher as usize];
+                if !predicate(&queue.front()?.0) {
+                    return None;
+                }
+                let request = queue.pop_front()?;
+                counters::pending_outbound_messages(network_context, *higher).dec();
+                Some(request)
+            })
+    }
+}
+
+impl Drop for OutboundQueues {
+    fn drop(&mut self) {
+        for class in TrafficClass::ALL.iter() {
+            let len = self.queues[*class as usize].len();
+            counters::pending_outbound_messages(&self.network_context, *class).sub(len as i64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::protocols::wire::messaging::v1::DirectSendMsg;
+
+    fn message(protocol_id: ProtocolId, id: u8) -> WriteRequest {
+        let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
+            protocol_id,
+            priority: TrafficClass::from(protocol_id).priority(),
+            raw_msg: vec![id],
+        });
+        (message, oneshot::channel().0)
+    }
+
+    fn payload(request: WriteRequest) -> (ProtocolId, u8) {
+        match request.0 {
+            NetworkMessage::DirectSendMsg(message) => (message.protocol_id, message.raw_msg[0]),
+            message => panic!("Unexpected message: {:?}", message),
+        }
+    }
+
+    #[test]
+    fn pop_in_priority_order() {
+        let mut queues = OutboundQueues::new(NetworkContext::mock());
+        queues.push(message(ProtocolId::StorageServiceRpc, 1));
+        queues.push(message(ProtocolId::MempoolDirectSend, 2));
+        queues.push(message(ProtocolId::StateSyncDirectSend, 3));
+        queues.push(message(ProtocolId::ConsensusDirectSendBcs, 4));
+        queues.push(message(ProtocolId::HealthCheckerRpc, 5));
+        queues.push(message(ProtocolId::ConsensusRpcBcs, 6));
+
+        let order: Vec<_> = std::iter::from_fn(|| queues.pop()).map(payload).collect();
+        assert_eq!(
+            order,
+            vec![
+                (ProtocolId::ConsensusDirectSendBcs, 4),
+                (ProtocolId::ConsensusRpcBcs, 6),
+                (ProtocolId::HealthCheckerRpc, 5),
+                (ProtocolId::MempoolDirectSend, 2),
+                (ProtocolId::StorageServiceRpc, 1),
+                (ProtocolId::StateSyncDirectSend, 3),
+            ]
+        );
+        assert!(queues.pop().is_none());
+    }
+
+    #[test]
+    fn pop_above_class() {
+        let mut queues = OutboundQueues::new(NetworkContext::mock());
+        queues.push(message(ProtocolId::StateSyncDirectSend, 1));
+        queues.push(message(ProtocolId::ConsensusDirectSendBcs, 2));
+        queues.push(message(ProtocolId::MempoolDirectSend, 3));
+
+        // The consensus message is skipped, the mempool one is the next above bulk.
+        let not_consensus =
+            |message: &NetworkMessage| TrafficClass::of_message(message) != TrafficClass::Consensus;
+        assert_eq!(
+            payload(queues.pop_above(TrafficClass::Bulk, not_consensus).unwrap()),
+            (ProtocolId::MempoolDirectSend, 3)
+        );
+        assert!(queues
+            .pop_above(TrafficClass::Bulk, not_consensus)
+            .is_none());
+        assert_eq!(
+            payload(queues.pop_above(TrafficClass::Bulk, |_| true).unwrap()),
+            (ProtocolId::ConsensusDirectSendBcs, 2)
+        );
+        // Messages of the class itself are never popped.
+        assert!(queues.pop_above(TrafficClass::Bulk, |_| true).is_none());
+        assert_eq!(
+            payload(queues.pop().unwrap()),
+            (ProtocolId::StateSyncDirectSend, 1)
+        );
+    }
+
+    #[test]
+    fn drop_when_full() {
+        let mut queues = OutboundQueues::new(NetworkContext::mock());
+        let max_queue_size = TrafficClass::Bulk.max_queue_size();
+        for i in 0..max_queue_size {
+            assert!(queues
+                .push(message(ProtocolId::StateSyncDirectSend, i as u8))
+                .is_none());
+            assert!(queues
+                .push(message(ProtocolId::MempoolDirectSend, i as u8))
+                .is_none());
+        }
+
+        // Bulk queue drops the newest message.
+        let dropped = queues.push(message(ProtocolId::StateSyncDirectSend, 255));
+        assert_eq!(
+            payload(dropped.unwrap()),
+            (ProtocolId::StateSyncDirectSend, 255)
+        );
+        // Mempool queue drops the oldest message.
+        let dropped = queues.push(message(ProtocolId::MempoolDirectSend, 255));
+        assert_eq!(
+            payload(dropped.unwrap()),
+            (ProtocolId::MempoolDirectSend, 0)
+        );
+
+        // Other classes are unaffected.
+        assert!(queues
+            .push(message(ProtocolId::ConsensusDirectSendBcs, 0))
+            .is_none());
+        assert_eq!(
+            payload(queues.pop().unwrap()),
+            (ProtocolId::ConsensusDirectSendBcs, 0)
+        );
+        assert_eq!(
+            payload(queues.pop().unwrap()),
+            (ProtocolId::MempoolDirectSend, 1)
+        );
+    }
+
+    #[test]
+    fn classify_messages() {
+        for protocol_id in ProtocolId::all() {
+            let class = TrafficClass::from(*protocol_id);
+            assert_eq!(TrafficClass::from_priority(class.priority()), class);
+        }
+        assert_eq!(TrafficClass::from_priority(200), TrafficClass::Consensus);
+    }
+}
@@ -4,14 +4,16 @@
 use crate::{
     constants::{
         INBOUND_RPC_TIMEOUT_MS, MAX_CONCURRENT_INBOUND_RPCS, MAX_CONCURRENT_OUTBOUND_RPCS,
-        MAX_FRAME_SIZE, NETWORK_CHANNEL_SIZE,
+        MAX_FRAME_SIZE, MAX_MESSAGE_SIZE, NETWORK_CHANNEL_SIZE,
     },
-    peer::{DisconnectReason, Peer, PeerNotification, PeerRequest},
-    peer_manager::TransportNotification,
+    peer::{DisconnectReason, Peer, PeerNotification, PeerRequest, TrafficClass},
+    peer_manager::{ProtocolLimiter, TransportNotification},
     protocols::{
         direct_send::Message,
         rpc::{error::RpcError, InboundRpcRequest, OutboundRpcRequest},
+        stream::{InboundStreamBuffer, OutboundStream},
         wire::{
+            compression,
             handshake::v1::{MessagingProtocolVersion, ProtocolIdSet},
             messaging::v1::{
                 DirectSendMsg, NetworkMessage, NetworkMessageSink, NetworkMessageStream,
@@ -35,8 +37,16 @@ use futures::{
     SinkExt,
 };
 use memsocket::MemorySocket;
-use netcore::transport::ConnectionOrigin;
-use std::{collections::HashSet, str::FromStr, time::Duration};
+use netcore::transport::{
+    quic::{QuicSocket, QuicTransport},
+    ConnectionOrigin, SecureChannel, Transport,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    iter::FromIterator,
+    str::FromStr,
+    time::Duration,
+};
 use tokio::runtime::{Handle, Runtime};
 use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
@@ -54,8 +64,58 @@ fn build_test_peer(
     MemorySocket,
     channel::Receiver<TransportNotification<MemorySocket>>,
     aptos_channel::Receiver<ProtocolId, PeerNotification>,
+) {
+    build_test_peer_with_protocols(
+        executor,
+        time_service,
+        origin,
+        MessagingProtocolVersion::V1,
+        ProtocolIdSet::empty(),
+    )
+}
+
+fn build_test_peer_with_protocols(
+    executor: Handle,
+    time_service: TimeService,
+    origin: ConnectionOrigin,
+    messaging_protocol: MessagingProtocolVersion,
+    application_protocols: ProtocolIdSet,
+) -> (
+    Peer<MemorySocket>,
+    PeerHandle,
+    MemorySocket,
+    channel::Receiver<TransportNotification<MemorySocket>>,
+    aptos_channel::Receiver<ProtocolId, PeerNotification>,
 ) {
     let (a, b) = MemorySocket::new_pair();
+    let (peer, peer_handle, connection_notifs_rx, peer_notifs_rx) = build_test_peer_with_socket(
+        executor,
+        time_service,
+        origin,
+        messaging_protocol,
+        application_protocols,
+        a,
+    );
+
+    (peer, peer_handle, b, connection_notifs_rx, peer_notifs_rx)
+}
+
+fn build_test_peer_with_socket<TSocket>(
+    executor: Handle,
+    time_service: TimeService,
+    origin: ConnectionOrigin,
+    messaging_protocol: MessagingProtocolVersion,
+    application_protocols: ProtocolIdSet,
+    socket: TSocket,
+) -> (
+    Peer<TSocket>,
+    PeerHandle,
+    channel::Receiver<TransportNotification<TSocket>>,
+    aptos_channel::Receiver<ProtocolId, PeerNotification>,
+)
+where
+    TSocket: AsyncRead + AsyncWrite + SecureChannel + Send + 'static,
+{
     let peer_id = PeerId::random();
     let connection = Connection {
         metadata: ConnectionMetadata::new(
@@ -63,11 +123,11 @@ fn build_test_peer(
             ConnectionId::default(),
             NetworkAddress::from_str("/ip4/127.0.0.1/tcp/8081").unwrap(),
             origin,
-            MessagingProtocolVersion::V1,
-            ProtocolIdSet::empty(),
+            messaging_protocol,
+            application_protocols,
             PeerRole::Unknown,
         ),
-        socket: a,
+        socket,
     };
 
     let (connection_notifs_tx, connection_notifs_rx) = channel::new_test(1);
@@ -88,12 +148,14 @@ fn build_test_peer(
         MAX_CONCURRENT_INBOUND_RPCS,
         MAX_CONCURRENT_OUTBOUND_RPCS,
         MAX_FRAME_SIZE,
+        MAX_MESSAGE_SIZE,
         None,
         None,
+        ProtocolLimiter::open(NetworkContext::mock(), PeerRole::Unknown),
     );
     let peer_handle = PeerHandle(peer_reqs_tx);
 
-    (peer, peer_handle, b, connection_notifs_rx, peer_notifs_rx)
+    (peer, peer_handle, connection_notifs_rx, peer_notifs_rx)
 }
 
 fn build_test_connected_peers(
@@ -140,6 +202,20 @@ fn build_test_connected_peers(
     )
 }
 
+async fn connect_quic() -> (QuicSocket, QuicSocket) {
+    let transport = QuicTransport::new().unwrap();
+    let (mut listener, addr) = transport
+        .listen_on("/ip4/127.0.0.1/quic/0".parse().unwrap())
+        .unwrap();
+    let dial = transport.dial(PeerId::random(), addr).unwrap();
+    let accept = async move {
+        let (inbound, _dialer_addr) = listener.next().await.unwrap().unwrap();
+        inbound.await.unwrap()
+    };
+    let (outbound, inbound) = future::join(dial, accept).await;
+    (outbound.unwrap(), inbound)
+}
+
 fn build_network_sink_stream(
     connection: &mut MemorySocket,
 ) -> (
@@ -214,7 +290,7 @@ fn peer_send_message() {
     };
     let recv_msg = NetworkMessage::DirectSendMsg(DirectSendMsg {
         protocol_id: PROTOCOL,
-        priority: 0,
+        priority: TrafficClass::from(PROTOCOL).priority(),
         raw_msg: Vec::from("hello world"),
     });
 
@@ -237,6 +313,51 @@ fn peer_send_message() {
     rt.block_on(future::join3(peer.start(), server, client));
 }
 
+// A DirectSend to a peer supporting the compressed protocol should be written
+// compressed, with the compressed protocol.
+#[test]
+fn peer_send_message_compressed() {
+    ::aptos_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let compressed_protocol = PROTOCOL.compressed().unwrap();
+    let (peer, mut peer_handle, mut connection, _connection_notifs_rx, _peer_notifs_rx) =
+        build_test_peer_with_protocols(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V1,
+            ProtocolIdSet::from_iter([PROTOCOL, compressed_protocol]),
+        );
+    let (mut client_sink, mut client_stream) = build_network_sink_stream(&mut connection);
+
+    let data = Bytes::from("hello world ".repeat(100));
+    let send_msg = Message {
+        protocol_id: PROTOCOL,
+        mdata: data.clone(),
+    };
+
+    let client = async {
+        let msg = client_stream.next().await.unwrap().unwrap();
+        match msg {
+            NetworkMessage::DirectSendMsg(msg) => {
+                assert_eq!(msg.protocol_id, compressed_protocol);
+                assert!(msg.raw_msg.len() < data.len());
+                assert_eq!(
+                    compression::decompress(&msg.raw_msg, MAX_MESSAGE_SIZE).unwrap(),
+                    data
+                );
+            }
+            _ => panic!("Expected DirectSendMsg; unexpected: {:?}", msg),
+        }
+        client_sink.close().await.unwrap();
+    };
+
+    let server = async {
+        peer_handle.send_direct_send(send_msg);
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
 // Reading an inbound DirectSendMsg off the wire should notify the PeerManager of
 // an inbound DirectSend.
 #[test]
@@ -280,6 +401,155 @@ fn peer_recv_message() {
     rt.block_on(future::join3(peer.start(), server, client));
 }
 
+// A compressed DirectSend should be handed to the application decompressed, with
+// the uncompressed protocol, unless it decompresses past the max message size.
+#[test]
+fn peer_recv_message_compressed() {
+    ::aptos_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let compressed_protocol = PROTOCOL.compressed().unwrap();
+    let (peer, _peer_handle, connection, _connection_notifs_rx, mut peer_notifs_rx) =
+        build_test_peer_with_protocols(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V1,
+            ProtocolIdSet::from_iter([PROTOCOL, compressed_protocol]),
+        );
+
+    // claims to decompress to more than the max message size
+    let mut oversized = compression::compress(b"hello world");
+    oversized[..4].copy_from_slice(&((MAX_MESSAGE_SIZE + 1) as u32).to_le_bytes());
+    let send_msgs = [oversized, compression::compress(b"hello world")]
+        .iter()
+        .map(|raw_msg| {
+            NetworkMessage::DirectSendMsg(DirectSendMsg {
+                protocol_id: compressed_protocol,
+                priority: 0,
+                raw_msg: raw_msg.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let recv_msg = PeerNotification::RecvMessage(Message {
+        protocol_id: PROTOCOL,
+        mdata: Bytes::from("hello world"),
+    });
+
+    let client = async move {
+        let mut connection = NetworkMessageSink::new(connection, MAX_FRAME_SIZE, None);
+        for send_msg in &send_msgs {
+            connection.send(send_msg).await.unwrap();
+        }
+        connection.close().await.unwrap();
+    };
+
+    let server = async move {
+        // Only the message within the max message size is received.
+        let received = peer_notifs_rx.next().await.unwrap();
+        assert_eq!(recv_msg, received);
+        assert!(peer_notifs_rx.next().await.is_none());
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
+// Over a V2 connection, a DirectSend larger than a frame should be written as
+// fragments.
+#[test]
+fn peer_send_message_streamed() {
+    ::aptos_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, mut peer_handle, mut connection, _connection_notifs_rx, _peer_notifs_rx) =
+        build_test_peer_with_protocols(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V2,
+            ProtocolIdSet::empty(),
+        );
+    let (mut client_sink, mut client_stream) = build_network_sink_stream(&mut connection);
+
+    let data = vec![7u8; 2 * MAX_FRAME_SIZE];
+    let send_msg = Message {
+        protocol_id: PROTOCOL,
+        mdata: Bytes::from(data.clone()),
+    };
+    let recv_msg = NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: PROTOCOL,
+        priority: TrafficClass::from(PROTOCOL).priority(),
+        raw_msg: data,
+    });
+
+    let client = async {
+        let mut buffer = InboundStreamBuffer::new(MAX_MESSAGE_SIZE);
+        match client_stream.next().await.unwrap().unwrap() {
+            NetworkMessage::StreamHeader(header) => buffer.new_stream(header).unwrap(),
+            msg => panic!("Expected StreamHeader; unexpected: {:?}", msg),
+        }
+        let message = loop {
+            match client_stream.next().await.unwrap().unwrap() {
+                NetworkMessage::StreamFragment(fragment) => {
+                    if let Some(message) = buffer.append_fragment(fragment).unwrap() {
+                        break message;
+                    }
+                }
+                msg => panic!("Expected StreamFragment; unexpected: {:?}", msg),
+            }
+        };
+        assert_eq!(message, recv_msg);
+        client_sink.close().await.unwrap();
+    };
+
+    let server = async {
+        peer_handle.send_direct_send(send_msg);
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
+// Over a V2 connection, the fragments of a DirectSend should be reassembled
+// before notifying the PeerManager.
+#[test]
+fn peer_recv_message_streamed() {
+    ::aptos_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, _peer_handle, connection, _connection_notifs_rx, mut peer_notifs_rx) =
+        build_test_peer_with_protocols(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V2,
+            ProtocolIdSet::empty(),
+        );
+
+    let data = vec![7u8; 2 * MAX_FRAME_SIZE];
+    let send_msg = NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: PROTOCOL,
+        priority: 0,
+        raw_msg: data.clone(),
+    });
+    let recv_msg = PeerNotification::RecvMessage(Message {
+        protocol_id: PROTOCOL,
+        mdata: Bytes::from(data),
+    });
+
+    let client = async move {
+        let mut connection = NetworkMessageSink::new(connection, MAX_FRAME_SIZE, None);
+        let frames = OutboundStream::new(MAX_FRAME_SIZE, MAX_MESSAGE_SIZE)
+            .fragment(send_msg)
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        for frame in &frames {
+            connection.send(frame).await.unwrap();
+        }
+        connection.close().await.unwrap();
+    };
+
+    let server = async move {
+        let received = peer_notifs_rx.next().await.unwrap();
+        assert_eq!(recv_msg, received);
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
 // Two connected Peer actors should be able to send/recv a DirectSend from each
 // other and then shutdown gracefully.
 #[test]
@@ -361,7 +631,7 @@ fn peer_recv_rpc() {
     });
     let resp_msg = NetworkMessage::RpcResponse(RpcResponse {
         request_id: 123,
-        priority: 0,
+        priority: TrafficClass::from(PROTOCOL).priority(),
         raw_response: Vec::from("goodbye world"),
     });
 
@@ -420,7 +690,7 @@ fn peer_recv_rpc_concurrent() {
     });
     let resp_msg = NetworkMessage::RpcResponse(RpcResponse {
         request_id: 123,
-        priority: 0,
+        priority: TrafficClass::from(PROTOCOL).priority(),
         raw_response: Vec::from("goodbye world"),
     });
 
@@ -610,7 +880,7 @@ fn peer_send_rpc() {
             };
 
             assert_eq!(received.protocol_id, PROTOCOL);
-            assert_eq!(received.priority, 0);
+            assert_eq!(received.priority, TrafficClass::from(PROTOCOL).priority());
             assert_eq!(received.raw_request, b"hello world");
 
             assert!(
@@ -633,6 +903,55 @@ fn peer_send_rpc() {
     rt.block_on(future::join3(peer.start(), server, client));
 }
 
+// An RPC to a peer supporting the compressed protocol should be sent compressed,
+// and its compressed response handed back decompressed.
+#[test]
+fn peer_send_rpc_compressed() {
+    ::aptos_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let protocol = ProtocolId::StorageServiceRpc;
+    let compressed_protocol = protocol.compressed().unwrap();
+    let (peer, mut peer_handle, mut connection, _connection_notifs_rx, _peer_notifs_rx) =
+        build_test_peer_with_protocols(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V1,
+            ProtocolIdSet::from_iter([protocol, compressed_protocol]),
+        );
+    let (mut server_sink, mut server_stream) = build_network_sink_stream(&mut connection);
+    let timeout = Duration::from_millis(10_000);
+
+    let client = async move {
+        let response = peer_handle
+            .send_rpc_request(protocol, Bytes::from(&b"hello world"[..]), timeout)
+            .await
+            .unwrap();
+        assert_eq!(response, Bytes::from(&b"goodbye world"[..]));
+    };
+    let server = async move {
+        let received = server_stream.next().await.unwrap().unwrap();
+        let received = match received {
+            NetworkMessage::RpcRequest(request) => request,
+            _ => panic!("Expected RpcRequest; unexpected: {:?}", received),
+        };
+        assert_eq!(received.protocol_id, compressed_protocol);
+        assert_eq!(
+            compression::decompress(&received.raw_request, MAX_MESSAGE_SIZE).unwrap(),
+            b"hello world"
+        );
+
+        let response = NetworkMessage::RpcResponse(RpcResponse {
+            request_id: received.request_id,
+            priority: 0,
+            raw_response: compression::compress(b"goodbye world"),
+        });
+        server_sink.send(&response).await.unwrap();
+        assert!(matches!(server_stream.next().await, None));
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
 #[test]
 fn peer_send_rpc_concurrent() {
     ::aptos_logger::Logger::init_for_testing();
@@ -679,7 +998,7 @@ fn peer_send_rpc_concurrent() {
             };
 
             assert_eq!(received.protocol_id, PROTOCOL);
-            assert_eq!(received.priority, 0);
+            assert_eq!(received.priority, TrafficClass::from(PROTOCOL).priority());
             assert_eq!(received.raw_request, b"hello world");
 
             assert!(
@@ -734,7 +1053,7 @@ fn peer_send_rpc_cancel() {
         };
 
         assert_eq!(received.protocol_id, PROTOCOL);
-        assert_eq!(received.priority, 0);
+        assert_eq!(received.priority, TrafficClass::from(PROTOCOL).priority());
         assert_eq!(received.raw_request, b"hello world");
 
         // Request should still be live. Ok(_) means the sender is not dropped.
@@ -795,7 +1114,7 @@ fn peer_send_rpc_timeout() {
         };
 
         assert_eq!(received.protocol_id, PROTOCOL);
-        assert_eq!(received.priority, 0);
+        assert_eq!(received.priority, TrafficClass::from(PROTOCOL).priority());
         assert_eq!(received.raw_request, b"hello world");
 
         // Request should still be live. Ok(_) means the sender is not dropped.
@@ -893,3 +1212,220 @@ fn peer_terminates_when_request_tx_has_dropped() {
     };
     rt.block_on(future::join(peer.start(), drop));
 }
+
+// Messages queued while the writer is busy should be written by priority, and
+// the messages of the same class in order.
+#[test]
+fn peer_writes_messages_by_priority() {
+    ::aptos_logger::Logger::init_for_testing();
+    // The writer task only runs once the runtime is blocked on, after all the
+    // messages are queued.
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let (socket, remote_socket) = MemorySocket::new_pair();
+    let (mut write_reqs_tx, _close_tx) = Peer::<MemorySocket>::start_writer_task(
+        rt.handle(),
+        TimeService::mock(),
+        ConnectionMetadata::mock(PeerId::random()),
+        NetworkContext::mock(),
+        NetworkMessageSink::new(socket, MAX_FRAME_SIZE, None),
+        None,
+        HashMap::new(),
+    );
+
+    let protocols = [
+        ProtocolId::StateSyncDirectSend,
+        ProtocolId::MempoolDirectSend,
+        ProtocolId::ConsensusDirectSendBcs,
+        ProtocolId::StateSyncDirectSend,
+        ProtocolId::DiscoveryDirectSend,
+    ];
+    let messages: Vec<_> = protocols
+        .iter()
+        .enumerate()
+        .map(|(i, protocol_id)| {
+            NetworkMessage::DirectSendMsg(DirectSendMsg {
+                protocol_id: *protocol_id,
+                priority: TrafficClass::from(*protocol_id).priority(),
+                raw_msg: vec![i as u8],
+            })
+        })
+        .collect();
+
+    rt.block_on(async move {
+        for message in &messages {
+            let (ack_tx, _) = oneshot::channel();
+            write_reqs_tx.send((message.clone(), ack_tx)).await.unwrap();
+        }
+        let mut remote_stream = NetworkMessageStream::new(remote_socket, MAX_FRAME_SIZE, None);
+        for i in [2, 4, 1, 0, 3] {
+            let received = remote_stream.next().await.unwrap().unwrap();
+            assert_eq!(received, messages[i]);
+        }
+    });
+}
+
+// A consensus message sent while a large state sync message is being fragmented should be
+// written before the last fragment of the state sync message.
+#[test]
+fn peer_writes_messages_between_fragments() {
+    ::aptos_logger::Logger::init_for_testing();
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let (socket, remote_socket) = MemorySocket::new_pair();
+    let (mut write_reqs_tx, _close_tx) = Peer::<MemorySocket>::start_writer_task(
+        rt.handle(),
+        TimeService::mock(),
+        ConnectionMetadata::mock(PeerId::random()),
+        NetworkContext::mock(),
+        NetworkMessageSink::new(socket, MAX_FRAME_SIZE, None),
+        Some(OutboundStream::new(MAX_FRAME_SIZE, MAX_MESSAGE_SIZE)),
+        HashMap::new(),
+    );
+
+    let bulk_message = NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: ProtocolId::StateSyncDirectSend,
+        priority: TrafficClass::Bulk.priority(),
+        raw_msg: vec![7u8; 4 * MAX_FRAME_SIZE],
+    });
+    let consensus_message = NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: ProtocolId::ConsensusDirectSendBcs,
+        priority: TrafficClass::Consensus.priority(),
+        raw_msg: vec![1],
+    });
+
+    rt.block_on(async move {
+        let (ack_tx, bulk_ack_rx) = oneshot::channel();
+        write_reqs_tx
+            .send((bulk_message.clone(), ack_tx))
+            .await
+            .unwrap();
+        let mut remote_stream = NetworkMessageStream::new(remote_socket, MAX_FRAME_SIZE, None);
+        let mut buffer = InboundStreamBuffer::new(MAX_MESSAGE_SIZE);
+        match remote_stream.next().await.unwrap().unwrap() {
+            NetworkMessage::StreamHeader(header) => buffer.new_stream(header).unwrap(),
+            msg => panic!("Expected StreamHeader; unexpected: {:?}", msg),
+        }
+
+        // The writer is in the middle of the fragments of the state sync message.
+        let (ack_tx, _) = oneshot::channel();
+        write_reqs_tx
+            .send((consensus_message.clone(), ack_tx))
+            .await
+            .unwrap();
+        let mut consensus_received = false;
+        let message = loop {
+            match remote_stream.next().await.unwrap().unwrap() {
+                NetworkMessage::StreamFragment(fragment) => {
+                    if let Some(message) = buffer.append_fragment(fragment).unwrap() {
+                        break message;
+                    }
+                }
+                msg => {
+                    assert_eq!(msg, consensus_message);
+                    consensus_received = true;
+                }
+            }
+        };
+        assert!(consensus_received);
+        assert_eq!(message, bulk_message);
+        bulk_ack_rx.await.unwrap().unwrap();
+    });
+}
+
+// Over a QUIC connection, the messages of the protocols written on their own streams are
+// received along with the messages written on the connection socket, and RPC responses come
+// back on the stream of their request.
+#[test]
+fn peers_send_messages_on_quic_streams() {
+    ::aptos_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (socket_a, socket_b) = rt.block_on(connect_quic());
+
+    // One protocol of each class, and an RPC protocol
+    let protocols = [
+        ProtocolId::ConsensusDirectSendBcs,
+        ProtocolId::DiscoveryDirectSend,
+        ProtocolId::MempoolDirectSend,
+        ProtocolId::StateSyncDirectSend,
+    ];
+    let rpc_protocol = ProtocolId::ConsensusRpcBcs;
+    let negotiated_protocols: ProtocolIdSet = protocols
+        .iter()
+        .copied()
+        .chain(std::iter::once(rpc_protocol))
+        .collect();
+
+    let (peer_a, mut peer_handle_a, _connection_notifs_rx_a, _peer_notifs_rx_a) =
+        build_test_peer_with_socket(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Outbound,
+            MessagingProtocolVersion::V1,
+            negotiated_protocols.clone(),
+            socket_a,
+        );
+    let (peer_b, peer_handle_b, _connection_notifs_rx_b, mut peer_notifs_rx_b) =
+        build_test_peer_with_socket(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V1,
+            negotiated_protocols,
+            socket_b,
+        );
+
+    let test = async move {
+        for protocol_id in protocols.iter().copied() {
+            peer_handle_a.send_direct_send(Message {
+                protocol_id,
+                mdata: Bytes::from("hello world"),
+            });
+        }
+
+        let mut received_protocols = HashSet::new();
+        for _ in 0..protocols.len() {
+            match peer_notifs_rx_b.next().await.unwrap() {
+                PeerNotification::RecvMessage(message) => {
+                    assert_eq!(message.mdata, Bytes::from("hello world"));
+                    received_protocols.insert(message.protocol_id);
+                }
+                notif => panic!("Expected a RecvMessage, received: {:?}", notif),
+            }
+        }
+        assert_eq!(
+            received_protocols,
+            protocols.iter().copied().collect::<HashSet<_>>()
+        );
+
+        let client = peer_handle_a.send_rpc_request(
+            rpc_protocol,
+            Bytes::from("hello world"),
+            Duration::from_secs(10),
+        );
+        let server = async {
+            match peer_notifs_rx_b.next().await.unwrap() {
+                PeerNotification::RecvRpc(request) => {
+                    assert_eq!(request.protocol_id, rpc_protocol);
+                    assert_eq!(request.data, Bytes::from("hello world"));
+                    request
+                        .res_tx
+                        .send(Ok(Bytes::from("goodbye world")))
+                        .unwrap();
+                }
+                notif => panic!("Expected a RecvRpc, received: {:?}", notif),
+            }
+        };
+        let (response, ()) = future::join(client, server).await;
+        assert_eq!(response.unwrap(), Bytes::from("goodbye world"));
+
+        // Shut down both peers
+        drop(peer_handle_a);
+        drop(peer_handle_b);
+    };
+    rt.block_on(future::join3(peer_a.start(), peer_b.start(), test));
+}
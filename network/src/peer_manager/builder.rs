@@ -9,13 +9,14 @@ use crate::{
     peer_manager::{
         conn_notifs_channel, ConnectionRequest, ConnectionRequestSender, PeerManager,
         PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
+        ProtocolRateLimiters,
     },
     protocols::{network::AppConfig, wire::handshake::v1::ProtocolIdSet},
     transport::{self, AptosNetTransport, Connection, APTOS_TCP_TRANSPORT},
     ProtocolId,
 };
 use aptos_config::{
-    config::{PeerSet, RateLimitConfig, HANDSHAKE_VERSION},
+    config::{Error, PeerSet, ProtocolRateLimitConfig, RateLimitConfig, HANDSHAKE_VERSION},
     network_id::NetworkContext,
 };
 use aptos_crypto::x25519;
@@ -27,7 +28,13 @@ use aptos_types::{chain_id::ChainId, network_address::NetworkAddress, PeerId};
 use channel::{self, aptos_channel, message_queues::QueueStyle};
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 use netcore::transport::memory::MemoryTransport;
+#[cfg(any(test, feature = "testing"))]
 use netcore::transport::{
+    fault_injection::{FaultController, FaultInjectingSocket, FaultInjectingTransport},
+    TransportExt,
+};
+use netcore::transport::{
+    quic::{QuicSocket, QuicTransport},
     tcp::{TcpSocket, TcpTransport},
     Transport,
 };
@@ -54,6 +61,9 @@ struct TransportContext {
     authentication_mode: AuthenticationMode,
     trusted_peers: Arc<RwLock<PeerSet>>,
     enable_proxy_protocol: bool,
+    /// Injects network faults in the dialed connections, to test WAN conditions.
+    #[cfg(any(test, feature = "testing"))]
+    fault_controller: Option<FaultController>,
 }
 
 impl TransportContext {
@@ -78,9 +88,12 @@ struct PeerManagerContext {
     max_concurrent_network_reqs: usize,
     channel_size: usize,
     max_frame_size: usize,
+    max_message_size: usize,
     inbound_connection_limit: usize,
     inbound_rate_limit_config: Option<RateLimitConfig>,
     outbound_rate_limit_config: Option<RateLimitConfig>,
+    inbound_protocol_rate_limiters: ProtocolRateLimiters,
+    outbound_protocol_rate_limiters: ProtocolRateLimiters,
 }
 
 impl PeerManagerContext {
@@ -102,6 +115,7 @@ impl PeerManagerContext {
         max_concurrent_network_reqs: usize,
         channel_size: usize,
         max_frame_size: usize,
+        max_message_size: usize,
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
@@ -120,9 +134,12 @@ impl PeerManagerContext {
             max_concurrent_network_reqs,
             channel_size,
             max_frame_size,
+            max_message_size,
             inbound_connection_limit,
             inbound_rate_limit_config,
             outbound_rate_limit_config,
+            inbound_protocol_rate_limiters: ProtocolRateLimiters::open(),
+            outbound_protocol_rate_limiters: ProtocolRateLimiters::open(),
         }
     }
 
@@ -146,11 +163,27 @@ impl PeerManagerContext {
 type MemoryPeerManager =
     PeerManager<AptosNetTransport<MemoryTransport>, NoiseStream<memsocket::MemorySocket>>;
 type TcpPeerManager = PeerManager<AptosNetTransport<TcpTransport>, NoiseStream<TcpSocket>>;
+type QuicPeerManager = PeerManager<AptosNetTransport<QuicTransport>, NoiseStream<QuicSocket>>;
+#[cfg(any(test, feature = "testing"))]
+type FaultyMemoryPeerManager = PeerManager<
+    AptosNetTransport<FaultInjectingTransport<MemoryTransport>>,
+    NoiseStream<FaultInjectingSocket<memsocket::MemorySocket>>,
+>;
+#[cfg(any(test, feature = "testing"))]
+type FaultyTcpPeerManager = PeerManager<
+    AptosNetTransport<FaultInjectingTransport<TcpTransport>>,
+    NoiseStream<FaultInjectingSocket<TcpSocket>>,
+>;
 
 enum TransportPeerManager {
     #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
     Memory(MemoryPeerManager),
     Tcp(TcpPeerManager),
+    Quic(QuicPeerManager),
+    #[cfg(any(test, feature = "testing"))]
+    FaultyMemory(FaultyMemoryPeerManager),
+    #[cfg(any(test, feature = "testing"))]
+    FaultyTcp(FaultyTcpPeerManager),
 }
 
 pub struct PeerManagerBuilder {
@@ -178,6 +211,7 @@ impl PeerManagerBuilder {
         channel_size: usize,
         max_concurrent_network_reqs: usize,
         max_frame_size: usize,
+        max_message_size: usize,
         enable_proxy_protocol: bool,
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
@@ -202,6 +236,8 @@ impl PeerManagerBuilder {
                 authentication_mode,
                 trusted_peers: trusted_peers.clone(),
                 enable_proxy_protocol,
+                #[cfg(any(test, feature = "testing"))]
+                fault_controller: None,
             }),
             peer_manager_context: Some(PeerManagerContext::new(
                 pm_reqs_tx,
@@ -215,6 +251,7 @@ impl PeerManagerBuilder {
                 max_concurrent_network_reqs,
                 channel_size,
                 max_frame_size,
+                max_message_size,
                 inbound_connection_limit,
                 inbound_rate_limit_config,
                 outbound_rate_limit_config,
@@ -224,6 +261,38 @@ impl PeerManagerBuilder {
         }
     }
 
+    /// Sets the per-protocol rate limits of the messages exchanged with the peers.
+    pub fn set_protocol_rate_limits(
+        &mut self,
+        inbound_protocol_rate_limits: &[ProtocolRateLimitConfig],
+        outbound_protocol_rate_limits: &[ProtocolRateLimitConfig],
+    ) -> Result<&mut Self, Error> {
+        let inbound_protocol_rate_limiters = ProtocolRateLimiters::new(
+            &self.network_context,
+            "inbound_protocol",
+            inbound_protocol_rate_limits,
+        )?;
+        let outbound_protocol_rate_limiters = ProtocolRateLimiters::new(
+            &self.network_context,
+            "outbound_protocol",
+            outbound_protocol_rate_limits,
+        )?;
+        let pm_context = self
+            .peer_manager_context
+            .as_mut()
+            .expect("Cannot set protocol rate limits once PeerManager has been built");
+        pm_context.inbound_protocol_rate_limiters = inbound_protocol_rate_limiters;
+        pm_context.outbound_protocol_rate_limiters = outbound_protocol_rate_limiters;
+        Ok(self)
+    }
+
+    /// Injects the faults of the controller in the connections dialed by this peer.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn inject_faults(&mut self, controller: FaultController) -> &mut Self {
+        self.transport_context().fault_controller = Some(controller);
+        self
+    }
+
     pub fn listen_address(&self) -> NetworkAddress {
         self.listen_address.clone()
     }
@@ -261,6 +330,8 @@ impl PeerManagerBuilder {
         let protos = transport_context.supported_protocols;
         let chain_id = transport_context.chain_id;
         let enable_proxy_protocol = transport_context.enable_proxy_protocol;
+        #[cfg(any(test, feature = "testing"))]
+        let fault_controller = transport_context.fault_controller;
 
         let (key, auth_mode) = match transport_context.authentication_mode {
             AuthenticationMode::MaybeMutual(key) => (
@@ -274,6 +345,51 @@ impl PeerManagerBuilder {
         };
 
         self.peer_manager = match self.listen_address.as_slice() {
+            #[cfg(any(test, feature = "testing"))]
+            [Ip4(_), Tcp(_)] | [Ip6(_), Tcp(_)] if fault_controller.is_some() => {
+                Some(TransportPeerManager::FaultyTcp(self.build_with_transport(
+                    AptosNetTransport::new(
+                        APTOS_TCP_TRANSPORT.clone().inject_faults(
+                            self.network_context.peer_id(),
+                            fault_controller.unwrap(),
+                        ),
+                        self.network_context,
+                        self.time_service.clone(),
+                        key,
+                        auth_mode,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        enable_proxy_protocol,
+                    ),
+                    executor,
+                )))
+            }
+            #[cfg(any(test, feature = "testing"))]
+            [Memory(_)] if fault_controller.is_some() => Some(TransportPeerManager::FaultyMemory(
+                self.build_with_transport(
+                    AptosNetTransport::new(
+                        MemoryTransport.inject_faults(
+                            self.network_context.peer_id(),
+                            fault_controller.unwrap(),
+                        ),
+                        self.network_context,
+                        self.time_service.clone(),
+                        key,
+                        auth_mode,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        enable_proxy_protocol,
+                    ),
+                    executor,
+                ),
+            )),
+            #[cfg(any(test, feature = "testing"))]
+            [Ip4(_), Quic(_)] | [Ip6(_), Quic(_)] if fault_controller.is_some() => panic!(
+                "{} Fault injection isn't supported on the QUIC transport: {}",
+                self.network_context, self.listen_address
+            ),
             [Ip4(_), Tcp(_)] | [Ip6(_), Tcp(_)] => {
                 Some(TransportPeerManager::Tcp(self.build_with_transport(
                     AptosNetTransport::new(
@@ -290,6 +406,28 @@ impl PeerManagerBuilder {
                     executor,
                 )))
             }
+            [Ip4(_), Quic(_)] | [Ip6(_), Quic(_)] => {
+                let quic_transport = QuicTransport::new().unwrap_or_else(|error| {
+                    panic!(
+                        "{} Unable to build the QUIC transport: {}",
+                        self.network_context, error
+                    )
+                });
+                Some(TransportPeerManager::Quic(self.build_with_transport(
+                    AptosNetTransport::new(
+                        quic_transport,
+                        self.network_context,
+                        self.time_service.clone(),
+                        key,
+                        auth_mode,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        enable_proxy_protocol,
+                    ),
+                    executor,
+                )))
+            }
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
             [Memory(_)] => Some(TransportPeerManager::Memory(self.build_with_transport(
                 AptosNetTransport::new(
@@ -307,7 +445,8 @@ impl PeerManagerBuilder {
             ))),
             _ => panic!(
                 "{} Unsupported listen_address: '{}', expected '/memory/<port>', \
-                 '/ip4/<addr>/tcp/<port>', or '/ip6/<addr>/tcp/<port>'.",
+                 '/ip4/<addr>/tcp/<port>', '/ip6/<addr>/tcp/<port>', \
+                 '/ip4/<addr>/quic/<port>', or '/ip6/<addr>/quic/<port>'.",
                 self.network_context, self.listen_address
             ),
         };
@@ -357,9 +496,12 @@ impl PeerManagerBuilder {
             pm_context.max_concurrent_network_reqs,
             pm_context.channel_size,
             pm_context.max_frame_size,
+            pm_context.max_message_size,
             pm_context.inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            pm_context.inbound_protocol_rate_limiters,
+            pm_context.outbound_protocol_rate_limiters,
         );
 
         // PeerManager constructor appends a public key to the listen_address.
@@ -390,6 +532,11 @@ impl PeerManagerBuilder {
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
             TransportPeerManager::Memory(pm) => self.start_peer_manager(pm, executor),
             TransportPeerManager::Tcp(pm) => self.start_peer_manager(pm, executor),
+            TransportPeerManager::Quic(pm) => self.start_peer_manager(pm, executor),
+            #[cfg(any(test, feature = "testing"))]
+            TransportPeerManager::FaultyMemory(pm) => self.start_peer_manager(pm, executor),
+            #[cfg(any(test, feature = "testing"))]
+            TransportPeerManager::FaultyTcp(pm) => self.start_peer_manager(pm, executor),
         }
     }
 
@@ -21,7 +21,7 @@ use crate::{
     },
     ProtocolId,
 };
-use aptos_config::network_id::NetworkContext;
+use aptos_config::network_id::{NetworkContext, PeerNetworkId};
 use aptos_logger::prelude::*;
 use aptos_rate_limiter::rate_limit::TokenBucketRateLimiter;
 use aptos_time_service::{TimeService, TimeServiceTrait};
@@ -47,13 +47,17 @@ use tokio::runtime::Handle;
 pub mod builder;
 pub mod conn_notifs_channel;
 mod error;
+mod protocol_rate_limit;
 mod senders;
 #[cfg(test)]
 mod tests;
 mod transport;
 mod types;
 
-pub use self::error::PeerManagerError;
+pub use self::{
+    error::PeerManagerError,
+    protocol_rate_limit::{protocol_limiter, ProtocolLimiter, ProtocolRateLimiters},
+};
 use crate::{
     application::storage::PeerMetadataStorage,
     peer_manager::transport::{TransportHandler, TransportRequest},
@@ -120,12 +124,18 @@ where
     channel_size: usize,
     /// Max network frame size
     max_frame_size: usize,
+    /// Max network message size, larger messages than frames are sent as fragments
+    max_message_size: usize,
     /// Inbound connection limit separate of outbound connections
     inbound_connection_limit: usize,
     /// Keyed storage of all inbound rate limiters
     inbound_rate_limiters: IpAddrTokenBucketLimiter,
     /// Keyed storage of all outbound rate limiters
     outbound_rate_limiters: IpAddrTokenBucketLimiter,
+    /// Per-protocol rate limiters of the messages received from each peer
+    inbound_protocol_rate_limiters: ProtocolRateLimiters,
+    /// Per-protocol rate limiters of the messages sent to each peer
+    outbound_protocol_rate_limiters: ProtocolRateLimiters,
 }
 
 impl<TTransport, TSocket> PeerManager<TTransport, TSocket>
@@ -153,9 +163,12 @@ where
         channel_size: usize,
         max_concurrent_network_reqs: usize,
         max_frame_size: usize,
+        max_message_size: usize,
         inbound_connection_limit: usize,
         inbound_rate_limiters: IpAddrTokenBucketLimiter,
         outbound_rate_limiters: IpAddrTokenBucketLimiter,
+        inbound_protocol_rate_limiters: ProtocolRateLimiters,
+        outbound_protocol_rate_limiters: ProtocolRateLimiters,
     ) -> Self {
         let (transport_notifs_tx, transport_notifs_rx) = channel::new(
             channel_size,
@@ -197,9 +210,12 @@ where
             max_concurrent_network_reqs,
             channel_size,
             max_frame_size,
+            max_message_size,
             inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            inbound_protocol_rate_limiters,
+            outbound_protocol_rate_limiters,
         }
     }
 
@@ -306,6 +322,32 @@ where
                         }
                     }
                     ConnectionOrigin::Inbound => {
+                        // Reject the connections of the peers banned for misbehaving
+                        let peer_network_id = PeerNetworkId::new(
+                            self.network_context.network_id(),
+                            conn.metadata.remote_peer_id,
+                        );
+                        if self
+                            .peer_metadata_storage
+                            .reputation()
+                            .is_banned(peer_network_id)
+                        {
+                            info!(
+                                NetworkSchema::new(&self.network_context)
+                                    .connection_metadata_with_address(&conn.metadata),
+                                "{} Connection rejected from banned peer: {}",
+                                self.network_context,
+                                conn.metadata
+                            );
+                            counters::connections_rejected(
+                                &self.network_context,
+                                conn.metadata.origin,
+                            )
+                            .inc();
+                            self.disconnect(conn);
+                            return;
+                        }
+
                         // Everything below here is meant for unknown peers only, role comes from
                         // Noise handshake and if it's not `Unknown` it is trusted
                         if conn.metadata.role == PeerRole::Unknown {
@@ -425,6 +467,10 @@ where
                 self.inbound_rate_limiters.try_garbage_collect_key(&ip_addr);
                 self.outbound_rate_limiters
                     .try_garbage_collect_key(&ip_addr);
+                self.inbound_protocol_rate_limiters
+                    .try_garbage_collect_key(&peer_id);
+                self.outbound_protocol_rate_limiters
+                    .try_garbage_collect_key(&peer_id);
             }
         }
     }
@@ -657,6 +703,13 @@ where
             .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
         let inbound_rate_limiter = self.inbound_rate_limiters.bucket(ip_addr);
         let outbound_rate_limiter = self.outbound_rate_limiters.bucket(ip_addr);
+        let protocol_limiter = protocol_limiter(
+            self.network_context,
+            peer_id,
+            connection.metadata.role,
+            &self.inbound_protocol_rate_limiters,
+            &self.outbound_protocol_rate_limiters,
+        );
 
         // TODO: Add label for peer.
         let (peer_reqs_tx, peer_reqs_rx) = aptos_channel::new(
@@ -684,8 +737,10 @@ where
             constants::MAX_CONCURRENT_INBOUND_RPCS,
             constants::MAX_CONCURRENT_OUTBOUND_RPCS,
             self.max_frame_size,
+            self.max_message_size,
             Some(inbound_rate_limiter),
             Some(outbound_rate_limiter),
+            protocol_limiter,
         );
         self.executor.spawn(peer.start());
 
@@ -0,0 +1,380 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per-protocol bandwidth accounting and rate limiting
+//!
+//! On top of the per-IP rate limiting of whole connections, the messages of a protocol can be
+//! rate limited per peer, with byte token buckets configured by protocol and [`PeerClass`].
+//! This caps, e.g., the bandwidth public peers pull through state sync without throttling
+//! mempool.
+//!
+//! The limits apply to the direct-send messages and rpc requests a peer sends us, and to the
+//! direct-send messages, rpc requests and rpc responses we send to the peer. Throttled messages
+//! and requests are dropped before any work is done for them: a throttled rpc fails with
+//! [`RpcError::RateLimited`]. The responses to the requests of the peer are delayed instead,
+//! as they were already computed. A pending response keeps its slot among the concurrent
+//! inbound rpcs of the peer, which throttles the admission of its next requests. The responses
+//! to our own rpc requests are accounted but never throttled.
+//!
+//! A message larger than the bucket of its protocol is allowed while the bucket isn't empty,
+//! and its excess is repaid by the next refills, so that the configured rate always holds.
+//!
+//! All the traffic is reported in the `aptos_network_protocol_messages` and
+//! `aptos_network_protocol_bytes` metrics, by [`PeerClass`] and protocol.
+//!
+//! [`RpcError::RateLimited`]: crate::protocols::rpc::error::RpcError::RateLimited
+
+use crate::{
+    counters::{
+        self, ALLOWED_LABEL, DELAYED_LABEL, NETWORK_RATE_LIMIT_METRICS, RECEIVED_LABEL, SENT_LABEL,
+        THROTTLED_LABEL,
+    },
+    ProtocolId,
+};
+use aptos_config::{
+    config::{Error, PeerClass, PeerRole, ProtocolRateLimitConfig},
+    network_id::NetworkContext,
+};
+use aptos_rate_limiter::rate_limit::{SharedBucket, TokenBucketRateLimiter};
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use aptos_types::PeerId;
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+type ProtocolBuckets = HashMap<ProtocolId, SharedBucket>;
+
+/// The per-protocol rate limiters of all the peers of a network, in one direction.
+pub struct ProtocolRateLimiters {
+    limiters: HashMap<(PeerClass, ProtocolId), TokenBucketRateLimiter<PeerId>>,
+}
+
+impl ProtocolRateLimiters {
+    /// Builds the rate limiters of the configured protocols.
+    pub fn new(
+        network_context: &NetworkContext,
+        label: &'static str,
+        configs: &[ProtocolRateLimitConfig],
+    ) -> Result<Self, Error> {
+        let mut limiters = HashMap::new();
+        for config in configs.iter().filter(|config| config.enabled) {
+            let protocol_id = ProtocolId::all()
+                .iter()
+                .find(|protocol_id| protocol_id.as_str() == config.protocol)
+                .ok_or_else(|| {
+                    Error::InvariantViolation(format!(
+                        "Unknown protocol in {} rate limit config: {}",
+                        label, config.protocol
+                    ))
+                })?
+                .uncompressed();
+            if config.byte_bucket_size < config.byte_bucket_rate {
+                return Err(Error::InvariantViolation(format!(
+                    "Bucket size smaller than the fill rate in {} rate limit config of {}",
+                    label, config.protocol
+                )));
+            }
+            let limiter = TokenBucketRateLimiter::new(
+                label,
+                format!(
+                    "{} {} {}",
+                    network_context,
+                    config.peer_class.as_str(),
+                    protocol_id
+                ),
+                config.initial_bucket_fill_percentage,
+                config.byte_bucket_size,
+                config.byte_bucket_rate,
+                Some(NETWORK_RATE_LIMIT_METRICS.clone()),
+            );
+            if limiters
+                .insert((config.peer_class, protocol_id), limiter)
+                .is_some()
+            {
+                return Err(Error::InvariantViolation(format!(
+                    "Duplicate {} rate limit config of {} for {} peers",
+                    label,
+                    config.protocol,
+                    config.peer_class.as_str()
+                )));
+            }
+        }
+        Ok(Self { limiters })
+    }
+
+    /// No rate limiting
+    pub fn open() -> Self {
+        Self {
+            limiters: HashMap::new(),
+        }
+    }
+
+    /// The buckets of the protocols limited for the peer.
+    fn buckets(&self, peer_id: PeerId, peer_class: PeerClass) -> ProtocolBuckets {
+        self.limiters
+            .iter()
+            .filter(|((class, _), _)| *class == peer_class)
+            .map(|((_, protocol_id), limiter)| (*protocol_id, limiter.bucket(peer_id)))
+            .collect()
+    }
+
+    /// Garbage collects the buckets of a peer, if no longer used.
+    pub fn try_garbage_collect_key(&self, peer_id: &PeerId) {
+        for limiter in self.limiters.values() {
+            limiter.try_garbage_collect_key(peer_id);
+        }
+    }
+}
+
+/// Builds the [`ProtocolLimiter`] of a new connection.
+pub fn protocol_limiter(
+    network_context: NetworkContext,
+    peer_id: PeerId,
+    peer_role: PeerRole,
+    inbound_rate_limiters: &ProtocolRateLimiters,
+    outbound_rate_limiters: &ProtocolRateLimiters,
+) -> ProtocolLimiter {
+    let peer_class = PeerClass::from(peer_role);
+    ProtocolLimiter {
+        network_context,
+        peer_class,
+        inbound: Arc::new(inbound_rate_limiters.buckets(peer_id, peer_class)),
+        outbound: Arc::new(outbound_rate_limiters.buckets(peer_id, peer_class)),
+    }
+}
+
+/// Accounts and rate limits the messages of each protocol exchanged with a peer.
+#[derive(Clone)]
+pub struct ProtocolLimiter {
+    network_context: NetworkContext,
+    /// The class of the peer, the messages are accounted by class to keep the metrics bounded.
+    peer_class: PeerClass,
+    inbound: Arc<ProtocolBuckets>,
+    outbound: Arc<ProtocolBuckets>,
+}
+
+impl ProtocolLimiter {
+    /// A limiter accounting the messages without limiting them
+    pub fn open(network_context: NetworkContext, peer_role: PeerRole) -> Self {
+        Self {
+            network_context,
+            peer_class: PeerClass::from(peer_role),
+            inbound: Arc::new(HashMap::new()),
+            outbound: Arc::new(HashMap::new()),
+        }
+    }
+
+    /// Accounts a message received from the peer. Returns false if it must be dropped.
+    pub fn allow_inbound(&self, protocol_id: ProtocolId, num_bytes: usize) -> bool {
+        self.allow(&self.inbound, RECEIVED_LABEL, protocol_id, num_bytes)
+    }
+
+    /// Accounts a message to send to the peer. Returns false if it must be dropped.
+    pub fn allow_outbound(&self, protocol_id: ProtocolId, num_bytes: usize) -> bool {
+        self.allow(&self.outbound, SENT_LABEL, protocol_id, num_bytes)
+    }
+
+    /// Accounts a message to send to the peer, waiting until its rate limit allows it.
+    pub async fn throttle_outbound(
+        &self,
+        time_service: &TimeService,
+        protocol_id: ProtocolId,
+        num_bytes: usize,
+    ) {
+        let mut state_label = ALLOWED_LABEL;
+        if let Some(bucket) = self.outbound.get(&protocol_id.uncompressed()) {
+            // The bucket isn't locked while waiting, as it is shared with the peer
+            loop {
+                let result = bucket.lock().acquire_tokens_with_debt(num_bytes);
+                match result {
+                    Ok(()) => break,
+                    Err(next_refill) => {
+                        state_label = DELAYED_LABEL;
+                        time_service
+                            .sleep(next_refill.saturating_duration_since(Instant::now()))
+                            .await;
+                    }
+                }
+            }
+        }
+        self.account(SENT_LABEL, state_label, protocol_id, num_bytes);
+    }
+
+    /// Accounts a message received from the peer that is never throttled.
+    pub fn account_inbound(&self, protocol_id: ProtocolId, num_bytes: usize) {
+        self.account(RECEIVED_LABEL, ALLOWED_LABEL, protocol_id, num_bytes);
+    }
+
+    fn allow(
+        &self,
+        buckets: &ProtocolBuckets,
+        direction_label: &'static str,
+        protocol_id: ProtocolId,
+        num_bytes: usize,
+    ) -> bool {
+        let allowed = buckets
+            .get(&protocol_id.uncompressed())
+            .map_or(true, |bucket| {
+                bucket.lock().acquire_tokens_with_debt(num_bytes).is_ok()
+            });
+        let state_label = if allowed {
+            ALLOWED_LABEL
+        } else {
+            THROTTLED_LABEL
+        };
+        self.account(direction_label, state_label, protocol_id, num_bytes);
+        allowed
+    }
+
+    fn account(
+        &self,
+        direction_label: &'static str,
+        state_label: &'static str,
+        protocol_id: ProtocolId,
+        num_bytes: usize,
+    ) {
+        counters::protocol_messages(
+            &self.network_context,
+            self.peer_class,
+            protocol_id,
+            direction_label,
+            state_label,
+        )
+        .inc();
+        counters::protocol_bytes(
+            &self.network_context,
+            self.peer_class,
+            protocol_id,
+            direction_label,
+            state_label,
+        )
+        .inc_by(num_bytes as u64);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aptos_config::{config::RoleType, network_id::NetworkId};
+    use std::time::Duration;
+
+    fn config(peer_class: PeerClass, protocol: &str) -> ProtocolRateLimitConfig {
+        ProtocolRateLimitConfig {
+            protocol: protocol.to_string(),
+            peer_class,
+            byte_bucket_rate: 100,
+            byte_bucket_size: 100,
+            initial_bucket_fill_percentage: 100,
+            enabled: true,
+        }
+    }
+
+    fn limiters(
+        peer_class: PeerClass,
+        protocol: &str,
+    ) -> (NetworkContext, ProtocolRateLimiters, ProtocolRateLimiters) {
+        let network_context =
+            NetworkContext::new(RoleType::FullNode, NetworkId::Public, PeerId::random());
+        let outbound = ProtocolRateLimiters::new(
+            &network_context,
+            "outbound",
+            &[config(peer_class, protocol)],
+        )
+        .unwrap();
+        (network_context, ProtocolRateLimiters::open(), outbound)
+    }
+
+    #[test]
+    fn throttle_protocol_of_peer_class() {
+        let (network_context, inbound, outbound) = limiters(PeerClass::Public, "StorageServiceRpc");
+        let public_peer = PeerId::random();
+        let limiter = protocol_limiter(
+            network_context,
+            public_peer,
+            PeerRole::Unknown,
+            &inbound,
+            &outbound,
+        );
+
+        // The compressed protocol shares the bucket of the protocol. A message larger than the
+        // tokens left is allowed, but the bucket is empty until the excess is repaid.
+        assert!(limiter.allow_outbound(ProtocolId::StorageServiceRpc, 60));
+        assert!(limiter.allow_outbound(ProtocolId::StorageServiceRpcCompressed, 60));
+        assert!(!limiter.allow_outbound(ProtocolId::StorageServiceRpcCompressed, 1));
+        // Other protocols and directions aren't limited.
+        assert!(limiter.allow_outbound(ProtocolId::MempoolDirectSend, 1000));
+        assert!(limiter.allow_inbound(ProtocolId::StorageServiceRpc, 1000));
+
+        // Each peer has its own bucket.
+        let other_limiter = protocol_limiter(
+            network_context,
+            PeerId::random(),
+            PeerRole::Known,
+            &inbound,
+            &outbound,
+        );
+        assert!(other_limiter.allow_outbound(ProtocolId::StorageServiceRpc, 100));
+
+        // Validators aren't limited.
+        let validator_limiter = protocol_limiter(
+            network_context,
+            PeerId::random(),
+            PeerRole::Validator,
+            &inbound,
+            &outbound,
+        );
+        assert!(validator_limiter.allow_outbound(ProtocolId::StorageServiceRpc, 1000));
+
+        // The buckets of disconnected peers are garbage collected.
+        drop(limiter);
+        outbound.try_garbage_collect_key(&public_peer);
+        let limiter = protocol_limiter(
+            network_context,
+            public_peer,
+            PeerRole::Unknown,
+            &inbound,
+            &outbound,
+        );
+        assert!(limiter.allow_outbound(ProtocolId::StorageServiceRpc, 100));
+    }
+
+    #[tokio::test]
+    async fn delay_outbound_until_refill() {
+        let (network_context, inbound, outbound) = limiters(PeerClass::Public, "StorageServiceRpc");
+        let limiter = protocol_limiter(
+            network_context,
+            PeerId::random(),
+            PeerRole::Unknown,
+            &inbound,
+            &outbound,
+        );
+        let time_service = TimeService::real();
+
+        // A response larger than the bucket is sent right away, the next one waits for the
+        // refill repaying the excess.
+        let start = Instant::now();
+        limiter
+            .throttle_outbound(&time_service, ProtocolId::StorageServiceRpc, 150)
+            .await;
+        assert!(start.elapsed() < Duration::from_millis(500));
+        limiter
+            .throttle_outbound(&time_service, ProtocolId::StorageServiceRpc, 10)
+            .await;
+        assert!(start.elapsed() >= Duration::from_millis(500));
+    }
+
+    #[test]
+    fn invalid_configs() {
+        let network_context = NetworkContext::mock();
+        let unknown_protocol = config(PeerClass::Public, "NotAProtocol");
+        assert!(
+            ProtocolRateLimiters::new(&network_context, "inbound", &[unknown_protocol]).is_err()
+        );
+
+        let mut small_bucket = config(PeerClass::Public, "StorageServiceRpc");
+        small_bucket.byte_bucket_size = small_bucket.byte_bucket_rate - 1;
+        assert!(ProtocolRateLimiters::new(&network_context, "inbound", &[small_bucket]).is_err());
+
+        let duplicate = config(PeerClass::Public, "StorageServiceRpcCompressed");
+        let configs = [config(PeerClass::Public, "StorageServiceRpc"), duplicate];
+        assert!(ProtocolRateLimiters::new(&network_context, "inbound", &configs).is_err());
+    }
+}
@@ -121,6 +121,18 @@ impl ApplicationNetworkSender<HealthCheckerMsg> for HealthCheckerNetworkSender {
             .send_rpc(recipient, protocol, req_msg, timeout)
             .await
     }
+
+    async fn send_rpc_with_size(
+        &self,
+        recipient: PeerId,
+        req_msg: HealthCheckerMsg,
+        timeout: Duration,
+    ) -> Result<(HealthCheckerMsg, usize), RpcError> {
+        let protocol = ProtocolId::HealthCheckerRpc;
+        self.inner
+            .send_rpc_with_size(recipient, protocol, req_msg, timeout)
+            .await
+    }
 }
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum HealthCheckerMsg {
@@ -7,7 +7,9 @@
 pub mod direct_send;
 pub mod network;
 pub mod rpc;
+pub mod stream;
 
 pub mod health_checker;
 pub mod identity;
+pub mod peer_monitoring;
 pub mod wire;
@@ -5,6 +5,7 @@
 
 pub use crate::protocols::rpc::error::RpcError;
 use crate::{
+    application::{reputation::Severity, storage::MisbehaviorReporter},
     error::NetworkError,
     peer_manager::{
         ConnectionNotification, ConnectionRequestSender, PeerManagerNotification,
@@ -20,18 +21,20 @@ use bytes::Bytes;
 use channel::aptos_channel;
 use futures::{
     channel::oneshot,
-    future,
-    stream::{FilterMap, FusedStream, Map, Select, Stream, StreamExt},
+    stream::{FusedStream, Map, Select, Stream, StreamExt},
     task::{Context, Poll},
 };
 use pin_project::pin_project;
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Serialize};
 use short_hex_str::AsShortHexStr;
 use std::{cmp::min, iter::FromIterator, marker::PhantomData, pin::Pin, time::Duration};
 
 use super::wire::handshake::v1::ProtocolIdSet;
 use std::fmt::Debug;
 
+#[cfg(test)]
+mod test;
+
 pub trait Message: DeserializeOwned + Serialize {}
 impl<T: DeserializeOwned + Serialize> Message for T {}
 
@@ -133,7 +136,7 @@ impl AppConfig {
 /// A `Stream` of `Event<TMessage>` from the lower network layer to an upper
 /// network application that deserializes inbound network direct-send and rpc
 /// messages into `TMessage`. Inbound messages that fail to deserialize are logged
-/// and dropped.
+/// and dropped, and their sender is reported to the `MisbehaviorReporter`, if any.
 ///
 /// `NetworkEvents` is really just a thin wrapper around a
 /// `channel::Receiver<PeerNotification>` that deserializes inbound messages.
@@ -141,16 +144,16 @@ impl AppConfig {
 pub struct NetworkEvents<TMessage> {
     #[pin]
     event_stream: Select<
-        FilterMap<
+        Map<
             aptos_channel::Receiver<(PeerId, ProtocolId), PeerManagerNotification>,
-            future::Ready<Option<Event<TMessage>>>,
-            fn(PeerManagerNotification) -> future::Ready<Option<Event<TMessage>>>,
+            fn(PeerManagerNotification) -> Result<Event<TMessage>, PeerId>,
         >,
         Map<
             aptos_channel::Receiver<PeerId, ConnectionNotification>,
-            fn(ConnectionNotification) -> Event<TMessage>,
+            fn(ConnectionNotification) -> Result<Event<TMessage>, PeerId>,
         >,
     >,
+    misbehavior_reporter: Option<MisbehaviorReporter>,
     _marker: PhantomData<TMessage>,
 }
 
@@ -159,22 +162,37 @@ pub trait NewNetworkEvents {
     fn new(
         peer_mgr_notifs_rx: aptos_channel::Receiver<(PeerId, ProtocolId), PeerManagerNotification>,
         connection_notifs_rx: aptos_channel::Receiver<PeerId, ConnectionNotification>,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new_with_reporter(peer_mgr_notifs_rx, connection_notifs_rx, None)
+    }
+
+    /// Reports the peers sending messages that fail to deserialize to `misbehavior_reporter`.
+    fn new_with_reporter(
+        peer_mgr_notifs_rx: aptos_channel::Receiver<(PeerId, ProtocolId), PeerManagerNotification>,
+        connection_notifs_rx: aptos_channel::Receiver<PeerId, ConnectionNotification>,
+        misbehavior_reporter: Option<MisbehaviorReporter>,
     ) -> Self;
 }
 
 impl<TMessage: Message> NewNetworkEvents for NetworkEvents<TMessage> {
-    fn new(
+    fn new_with_reporter(
         peer_mgr_notifs_rx: aptos_channel::Receiver<(PeerId, ProtocolId), PeerManagerNotification>,
         connection_notifs_rx: aptos_channel::Receiver<PeerId, ConnectionNotification>,
+        misbehavior_reporter: Option<MisbehaviorReporter>,
     ) -> Self {
-        let data_event_stream = peer_mgr_notifs_rx.filter_map(
+        let data_event_stream = peer_mgr_notifs_rx.map(
             peer_mgr_notif_to_event
-                as fn(PeerManagerNotification) -> future::Ready<Option<Event<TMessage>>>,
+                as fn(PeerManagerNotification) -> Result<Event<TMessage>, PeerId>,
+        );
+        let control_event_stream = connection_notifs_rx.map(
+            control_msg_to_event as fn(ConnectionNotification) -> Result<Event<TMessage>, PeerId>,
         );
-        let control_event_stream = connection_notifs_rx
-            .map(control_msg_to_event as fn(ConnectionNotification) -> Event<TMessage>);
         Self {
             event_stream: ::futures::stream::select(data_event_stream, control_event_stream),
+            misbehavior_reporter,
             _marker: PhantomData,
         }
     }
@@ -184,20 +202,36 @@ impl<TMessage> Stream for NetworkEvents<TMessage> {
     type Item = Event<TMessage>;
 
     fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
-        self.project().event_stream.poll_next(context)
+        let mut this = self.project();
+        loop {
+            match futures::ready!(this.event_stream.as_mut().poll_next(context)) {
+                Some(Ok(event)) => return Poll::Ready(Some(event)),
+                Some(Err(peer_id)) => {
+                    if let Some(misbehavior_reporter) = this.misbehavior_reporter {
+                        misbehavior_reporter.report(
+                            peer_id,
+                            Severity::Medium,
+                            "undecodable message",
+                        );
+                    }
+                }
+                None => return Poll::Ready(None),
+            }
+        }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.event_stream.size_hint()
+        // Undecodable messages are dropped.
+        (0, self.event_stream.size_hint().1)
     }
 }
 
 /// Deserialize inbound direct send and rpc messages into the application `TMessage`
-/// type, logging and dropping messages that fail to deserialize.
+/// type, logging messages that fail to deserialize and returning their sender.
 fn peer_mgr_notif_to_event<TMessage: Message>(
     notif: PeerManagerNotification,
-) -> future::Ready<Option<Event<TMessage>>> {
-    let maybe_event = match notif {
+) -> Result<Event<TMessage>, PeerId> {
+    match notif {
         PeerManagerNotification::RecvRpc(peer_id, rpc_req) => {
             request_to_network_event(peer_id, &rpc_req)
                 .map(|msg| Event::RpcRequest(peer_id, msg, rpc_req.protocol_id, rpc_req.res_tx))
@@ -205,17 +239,16 @@ fn peer_mgr_notif_to_event<TMessage: Message>(
         PeerManagerNotification::RecvMessage(peer_id, request) => {
             request_to_network_event(peer_id, &request).map(|msg| Event::Message(peer_id, msg))
         }
-    };
-    future::ready(maybe_event)
+    }
 }
 
 /// Converts a `SerializedRequest` into a network `Event` for sending to other nodes
 fn request_to_network_event<TMessage: Message, Request: SerializedRequest>(
     peer_id: PeerId,
     request: &Request,
-) -> Option<TMessage> {
+) -> Result<TMessage, PeerId> {
     match request.to_message() {
-        Ok(msg) => Some(msg),
+        Ok(msg) => Ok(msg),
         Err(err) => {
             let data = &request.data();
             warn!(
@@ -225,16 +258,18 @@ fn request_to_network_event<TMessage: Message, Request: SerializedRequest>(
                 protocol_id = request.protocol_id(),
                 data_prefix = hex::encode(&data[..min(16, data.len())]),
             );
-            None
+            Err(peer_id)
         }
     }
 }
 
-fn control_msg_to_event<TMessage>(notif: ConnectionNotification) -> Event<TMessage> {
-    match notif {
+fn control_msg_to_event<TMessage>(
+    notif: ConnectionNotification,
+) -> Result<Event<TMessage>, PeerId> {
+    Ok(match notif {
         ConnectionNotification::NewPeer(metadata, _context) => Event::NewPeer(metadata),
         ConnectionNotification::LostPeer(metadata, _context, _reason) => Event::LostPeer(metadata),
-    }
+    })
 }
 
 impl<TMessage> FusedStream for NetworkEvents<TMessage> {
@@ -340,6 +375,20 @@ impl<TMessage: Message> NetworkSender<TMessage> {
         req_msg: TMessage,
         timeout: Duration,
     ) -> Result<TMessage, RpcError> {
+        self.send_rpc_with_size(recipient, protocol, req_msg, timeout)
+            .await
+            .map(|(res_msg, _)| res_msg)
+    }
+
+    /// Same as `send_rpc`, but also returns the number of bytes received for
+    /// the response (i.e., its serialized size on the wire).
+    pub async fn send_rpc_with_size(
+        &self,
+        recipient: PeerId,
+        protocol: ProtocolId,
+        req_msg: TMessage,
+        timeout: Duration,
+    ) -> Result<(TMessage, usize), RpcError> {
         // serialize request
         let req_data = protocol.to_bytes(&req_msg)?.into();
         let res_data = self
@@ -347,7 +396,7 @@ impl<TMessage: Message> NetworkSender<TMessage> {
             .send_rpc(recipient, protocol, req_data, timeout)
             .await?;
         let res_msg: TMessage = protocol.from_bytes(&res_data)?;
-        Ok(res_msg)
+        Ok((res_msg, res_data.len()))
     }
 }
 
@@ -373,6 +422,15 @@ pub trait ApplicationNetworkSender<TMessage: Send>: Clone {
         req_msg: TMessage,
         timeout: Duration,
     ) -> Result<TMessage, RpcError>;
+
+    /// Sends an rpc request, returning the response along with the number of
+    /// bytes received for it.
+    async fn send_rpc_with_size(
+        &self,
+        recipient: PeerId,
+        req_msg: TMessage,
+        timeout: Duration,
+    ) -> Result<(TMessage, usize), RpcError>;
 }
 
 /// Generalized functionality for any request across `DirectSend` and `Rpc`.
@@ -382,7 +440,7 @@ pub trait SerializedRequest {
 
     /// Converts the `SerializedMessage` into its deserialized version of `TMessage` based on the
     /// `ProtocolId`.  See: [`ProtocolId::from_bytes`]
-    fn to_message<'a, TMessage: Deserialize<'a>>(&'a self) -> anyhow::Result<TMessage> {
+    fn to_message<TMessage: DeserializeOwned>(&self) -> anyhow::Result<TMessage> {
         self.protocol_id().from_bytes(self.data())
     }
 }
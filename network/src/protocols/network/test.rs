@@ -0,0 +1,49 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{
+    application::storage::PeerMetadataStorage, peer_manager::conn_notifs_channel,
+    protocols::direct_send::Message as DirectSendMessage,
+};
+use aptos_config::network_id::{NetworkId, PeerNetworkId};
+use channel::message_queues::QueueStyle;
+use futures::executor::block_on;
+
+// A peer sending messages that fail to deserialize should be penalized, and its next valid
+// message delivered.
+#[test]
+fn undecodable_messages_are_reported() {
+    ::aptos_logger::Logger::init_for_testing();
+    let peer_metadata_storage = PeerMetadataStorage::test();
+    let (peer_mgr_notifs_tx, peer_mgr_notifs_rx) = aptos_channel::new(QueueStyle::FIFO, 8, None);
+    let (_connection_notifs_tx, connection_notifs_rx) = conn_notifs_channel::new();
+    let mut network_events = NetworkEvents::<u64>::new_with_reporter(
+        peer_mgr_notifs_rx,
+        connection_notifs_rx,
+        Some(MisbehaviorReporter::new(
+            NetworkId::Validator,
+            peer_metadata_storage.clone(),
+        )),
+    );
+
+    let peer_id = PeerId::random();
+    let protocol_id = ProtocolId::MempoolDirectSend;
+    for mdata in [
+        Bytes::from_static(&[0xff]),
+        Bytes::from(bcs::to_bytes(&7u64).unwrap()),
+    ] {
+        let notification =
+            PeerManagerNotification::RecvMessage(peer_id, DirectSendMessage { protocol_id, mdata });
+        peer_mgr_notifs_tx
+            .push((peer_id, protocol_id), notification)
+            .unwrap();
+    }
+
+    let event = block_on(network_events.next()).unwrap();
+    assert_eq!(event, Event::Message(peer_id, 7));
+    let score = peer_metadata_storage
+        .reputation()
+        .score(PeerNetworkId::new(NetworkId::Validator, peer_id));
+    assert!(score > 0.0);
+}
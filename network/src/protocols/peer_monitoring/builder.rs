@@ -0,0 +1,55 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    application::storage::PeerMetadataStorage,
+    protocols::peer_monitoring::{
+        PeerMonitor, PeerMonitoringNetworkEvents, PeerMonitoringNetworkSender, SyncedVersionReader,
+    },
+};
+use aptos_config::network_id::NetworkContext;
+use aptos_time_service::TimeService;
+use std::{sync::Arc, time::Duration};
+use tokio::runtime::Handle;
+
+pub struct PeerMonitorBuilder {
+    service: Option<PeerMonitor>,
+}
+
+impl PeerMonitorBuilder {
+    pub fn new(
+        network_context: NetworkContext,
+        time_service: TimeService,
+        monitoring_interval_ms: u64,
+        request_timeout_ms: u64,
+        network_tx: PeerMonitoringNetworkSender,
+        network_rx: PeerMonitoringNetworkEvents,
+        peer_metadata_storage: Arc<PeerMetadataStorage>,
+    ) -> Self {
+        let service = PeerMonitor::new(
+            network_context,
+            time_service,
+            network_tx,
+            network_rx,
+            peer_metadata_storage,
+            None,
+            Duration::from_millis(monitoring_interval_ms),
+            Duration::from_millis(request_timeout_ms),
+        );
+        Self {
+            service: Some(service),
+        }
+    }
+
+    pub fn set_synced_version_reader(&mut self, synced_version_reader: SyncedVersionReader) {
+        if let Some(service) = self.service.as_mut() {
+            service.synced_version_reader = Some(synced_version_reader);
+        }
+    }
+
+    pub fn start(&mut self, executor: &Handle) {
+        if let Some(service) = self.service.take() {
+            executor.spawn(service.start());
+        }
+    }
+}
@@ -0,0 +1,381 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Protocol used to measure the quality of the connected peers
+//!
+//! The PeerMonitor periodically sends a `GetNodeInfo` request to each connected peer
+//! supporting the protocol. It measures the round-trip time of the request, and records it
+//! with the distance from the validators and the highest synced version reported by the peer
+//! in the [`PeerMonitoringMetadata`] of the peer, in the [`PeerMetadataStorage`]. Components
+//! choosing between peers can then prefer the nearby, healthy ones.
+//!
+//! A node's distance from the validators is 0 for a validator, and one more than the lowest
+//! distance reported by its connected peers otherwise. The distance a peer reports is not
+//! authenticated, so it is never trusted below what the role of the connection allows: only a
+//! validator can be at distance 0, and only a validator full node at distance 1.
+use crate::{
+    application::{
+        storage::PeerMetadataStorage,
+        types::{PeerError, PeerMonitoringMetadata},
+    },
+    constants::NETWORK_CHANNEL_SIZE,
+    counters,
+    logging::NetworkSchema,
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::{
+        network::{
+            AppConfig, ApplicationNetworkSender, Event, NetworkEvents, NetworkSender,
+            NewNetworkSender,
+        },
+        rpc::error::RpcError,
+    },
+    ProtocolId,
+};
+use aptos_config::{
+    config::PeerRole,
+    network_id::{NetworkContext, PeerNetworkId},
+};
+use aptos_logger::prelude::*;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use aptos_types::{transaction::Version, PeerId};
+use async_trait::async_trait;
+use bytes::Bytes;
+use channel::{aptos_channel, message_queues::QueueStyle};
+use futures::{
+    channel::oneshot,
+    stream::{FuturesUnordered, StreamExt},
+};
+use serde::{Deserialize, Serialize};
+use short_hex_str::AsShortHexStr;
+use std::{collections::hash_map::Entry, sync::Arc, time::Duration};
+
+pub mod builder;
+#[cfg(test)]
+mod test;
+
+/// The distance reported by a node that isn't connected to any peer closer to the validators.
+pub const MAX_DISTANCE_FROM_VALIDATORS: u64 = 100;
+
+/// Weight of the latest round-trip time in the average ping latency.
+const LATENCY_SMOOTHING_FACTOR: f64 = 0.2;
+
+/// Reads the highest version synced by the node, reported to the peers monitoring it.
+pub type SyncedVersionReader = Arc<dyn Fn() -> Option<Version> + Send + Sync>;
+
+/// The interface from Network to PeerMonitor layer.
+pub type PeerMonitoringNetworkEvents = NetworkEvents<PeerMonitoringMsg>;
+
+/// The interface from PeerMonitor to Networking layer.
+#[derive(Clone)]
+pub struct PeerMonitoringNetworkSender {
+    inner: NetworkSender<PeerMonitoringMsg>,
+}
+
+/// Configuration for the network endpoints to support the PeerMonitor.
+pub fn network_endpoint_config() -> AppConfig {
+    AppConfig::p2p(
+        [ProtocolId::PeerMonitoringServiceRpc],
+        aptos_channel::Config::new(NETWORK_CHANNEL_SIZE)
+            .queue_style(QueueStyle::LIFO)
+            .counters(&counters::PENDING_PEER_MONITORING_NETWORK_EVENTS),
+    )
+}
+
+impl NewNetworkSender for PeerMonitoringNetworkSender {
+    fn new(
+        peer_mgr_reqs_tx: PeerManagerRequestSender,
+        connection_reqs_tx: ConnectionRequestSender,
+    ) -> Self {
+        Self {
+            inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+        }
+    }
+}
+
+#[async_trait]
+impl ApplicationNetworkSender<PeerMonitoringMsg> for PeerMonitoringNetworkSender {
+    async fn send_rpc(
+        &self,
+        recipient: PeerId,
+        req_msg: PeerMonitoringMsg,
+        timeout: Duration,
+    ) -> Result<PeerMonitoringMsg, RpcError> {
+        let protocol = ProtocolId::PeerMonitoringServiceRpc;
+        self.inner
+            .send_rpc(recipient, protocol, req_msg, timeout)
+            .await
+    }
+
+    async fn send_rpc_with_size(
+        &self,
+        recipient: PeerId,
+        req_msg: PeerMonitoringMsg,
+        timeout: Duration,
+    ) -> Result<(PeerMonitoringMsg, usize), RpcError> {
+        let protocol = ProtocolId::PeerMonitoringServiceRpc;
+        self.inner
+            .send_rpc_with_size(recipient, protocol, req_msg, timeout)
+            .await
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum PeerMonitoringMsg {
+    GetNodeInfo,
+    NodeInfo(NodeInfo),
+}
+
+/// What a node reports about itself to the peers monitoring it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct NodeInfo {
+    pub distance_from_validators: u64,
+    pub highest_synced_version: Option<Version>,
+}
+
+/// The actor monitoring the peers of a network
+pub struct PeerMonitor {
+    network_context: NetworkContext,
+    /// A handle to a time service for easily mocking time-related operations.
+    time_service: TimeService,
+    network_tx: PeerMonitoringNetworkSender,
+    network_rx: PeerMonitoringNetworkEvents,
+    /// The metadata of the peers of all the networks, where the measurements are recorded.
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
+    /// Reads the highest synced version reported to the other peers, if the node has storage.
+    synced_version_reader: Option<SyncedVersionReader>,
+    /// Time we wait between each round of requests.
+    monitoring_interval: Duration,
+    /// Request timeout duration.
+    request_timeout: Duration,
+}
+
+impl PeerMonitor {
+    /// Create new instance of the [`PeerMonitor`] actor.
+    pub fn new(
+        network_context: NetworkContext,
+        time_service: TimeService,
+        network_tx: PeerMonitoringNetworkSender,
+        network_rx: PeerMonitoringNetworkEvents,
+        peer_metadata_storage: Arc<PeerMetadataStorage>,
+        synced_version_reader: Option<SyncedVersionReader>,
+        monitoring_interval: Duration,
+        request_timeout: Duration,
+    ) -> Self {
+        PeerMonitor {
+            network_context,
+            time_service,
+            network_tx,
+            network_rx,
+            peer_metadata_storage,
+            synced_version_reader,
+            monitoring_interval,
+            request_timeout,
+        }
+    }
+
+    pub async fn start(mut self) {
+        let mut pending_requests = FuturesUnordered::new();
+        info!(
+            NetworkSchema::new(&self.network_context),
+            "{} Peer monitor actor started", self.network_context
+        );
+
+        let ticker = self.time_service.interval(self.monitoring_interval);
+        tokio::pin!(ticker);
+
+        loop {
+            futures::select! {
+                maybe_event = self.network_rx.next() => {
+                    // Shutdown the PeerMonitor when this network instance shuts down.
+                    let event = match maybe_event {
+                        Some(event) => event,
+                        None => break,
+                    };
+                    match event {
+                        Event::RpcRequest(peer_id, PeerMonitoringMsg::GetNodeInfo, protocol, res_tx) => {
+                            self.handle_node_info_request(peer_id, protocol, res_tx);
+                        }
+                        Event::RpcRequest(peer_id, msg, _, _) | Event::Message(peer_id, msg) => {
+                            warn!(
+                                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                                "{} Unexpected peer monitoring message from {}: {:?}",
+                                self.network_context,
+                                peer_id.short_str(),
+                                msg
+                            );
+                        }
+                        Event::NewPeer(_) | Event::LostPeer(_) => {
+                            // The peers are read from the PeerMetadataStorage
+                        }
+                    }
+                }
+                _ = ticker.select_next_some() => {
+                    for peer_id in self.monitored_peers() {
+                        pending_requests.push(Self::request_node_info(
+                            self.time_service.clone(),
+                            self.network_tx.clone(),
+                            peer_id,
+                            self.request_timeout,
+                        ));
+                    }
+                }
+                (peer_id, result) = pending_requests.select_next_some() => {
+                    self.handle_node_info_response(peer_id, result);
+                }
+            }
+        }
+        warn!(
+            NetworkSchema::new(&self.network_context),
+            "{} Peer monitor actor terminated", self.network_context
+        );
+    }
+
+    /// The connected peers of this network that support peer monitoring
+    fn monitored_peers(&self) -> Vec<PeerId> {
+        self.peer_metadata_storage
+            .read_filtered(self.network_context.network_id(), |(_, peer_info)| {
+                peer_info.is_connected()
+                    && peer_info.supports_protocol(ProtocolId::PeerMonitoringServiceRpc)
+            })
+            .into_keys()
+            .map(|peer_network_id| peer_network_id.peer_id())
+            .collect()
+    }
+
+    /// What this node reports about itself
+    fn local_node_info(&self) -> NodeInfo {
+        let distance_from_validators = if self.network_context.role().is_validator() {
+            0
+        } else {
+            self.peer_metadata_storage
+                .networks()
+                .flat_map(|network_id| {
+                    self.peer_metadata_storage
+                        .read_filtered(network_id, |(_, peer_info)| peer_info.is_connected())
+                        .into_values()
+                        .filter_map(|peer_info| {
+                            peer_info.monitoring_metadata.distance_from_validators
+                        })
+                })
+                .min()
+                .map_or(MAX_DISTANCE_FROM_VALIDATORS, |distance| {
+                    (distance + 1).min(MAX_DISTANCE_FROM_VALIDATORS)
+                })
+        };
+        NodeInfo {
+            distance_from_validators,
+            highest_synced_version: self.synced_version_reader.as_ref().and_then(|read| read()),
+        }
+    }
+
+    fn handle_node_info_request(
+        &self,
+        peer_id: PeerId,
+        protocol: ProtocolId,
+        res_tx: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) {
+        let response = PeerMonitoringMsg::NodeInfo(self.local_node_info());
+        match protocol.to_bytes(&response) {
+            Ok(message) => {
+                let _ = res_tx.send(Ok(message.into()));
+            }
+            Err(e) => {
+                warn!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                    error = ?e,
+                    "{} Unable to serialize node info response: {}", self.network_context, e
+                );
+            }
+        }
+    }
+
+    fn handle_node_info_response(
+        &self,
+        peer_id: PeerId,
+        result: Result<(Duration, NodeInfo), RpcError>,
+    ) {
+        let (latency, node_info) = match result {
+            Ok(response) => response,
+            Err(err) => {
+                // The HealthChecker takes care of the unresponsive peers
+                trace!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                    error = ?err,
+                    "{} Node info request to peer: {} failed: {:?}",
+                    self.network_context,
+                    peer_id.short_str(),
+                    err
+                );
+                return;
+            }
+        };
+        let peer_network_id = PeerNetworkId::new(self.network_context.network_id(), peer_id);
+        let _ = self
+            .peer_metadata_storage
+            .write(peer_network_id, |entry| match entry {
+                Entry::Vacant(..) => Err(PeerError::NotFound),
+                Entry::Occupied(mut inner) => {
+                    let peer_info = inner.get_mut();
+                    let min_distance =
+                        min_distance_from_validators(peer_info.active_connection.role);
+                    update_metadata(
+                        &mut peer_info.monitoring_metadata,
+                        latency,
+                        node_info,
+                        min_distance,
+                    );
+                    Ok(())
+                }
+            });
+    }
+
+    async fn request_node_info(
+        time_service: TimeService,
+        network_tx: PeerMonitoringNetworkSender,
+        peer_id: PeerId,
+        request_timeout: Duration,
+    ) -> (PeerId, Result<(Duration, NodeInfo), RpcError>) {
+        let start = time_service.now();
+        let result = network_tx
+            .send_rpc(peer_id, PeerMonitoringMsg::GetNodeInfo, request_timeout)
+            .await
+            .and_then(|msg| match msg {
+                PeerMonitoringMsg::NodeInfo(node_info) => {
+                    Ok((time_service.now().duration_since(start), node_info))
+                }
+                _ => Err(RpcError::InvalidRpcResponse),
+            });
+        (peer_id, result)
+    }
+}
+
+/// The lowest distance from the validators a peer connected with the given role can be at
+fn min_distance_from_validators(role: PeerRole) -> u64 {
+    match role {
+        PeerRole::Validator => 0,
+        PeerRole::ValidatorFullNode => 1,
+        _ => 2,
+    }
+}
+
+/// Records a successful node info request to the peer, the reported distance is raised to
+/// `min_distance` if the peer claims to be closer to the validators than its role allows.
+fn update_metadata(
+    metadata: &mut PeerMonitoringMetadata,
+    latency: Duration,
+    node_info: NodeInfo,
+    min_distance: u64,
+) {
+    let latency_secs = latency.as_secs_f64();
+    metadata.average_ping_latency_secs = Some(match metadata.average_ping_latency_secs {
+        Some(average) => average + LATENCY_SMOOTHING_FACTOR * (latency_secs - average),
+        None => latency_secs,
+    });
+    metadata.distance_from_validators = Some(
+        node_info
+            .distance_from_validators
+            .max(min_distance)
+            .min(MAX_DISTANCE_FROM_VALIDATORS),
+    );
+    metadata.highest_synced_version = node_info.highest_synced_version;
+}
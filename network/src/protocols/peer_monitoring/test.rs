@@ -0,0 +1,259 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{
+    peer_manager::{
+        conn_notifs_channel, ConnectionRequest, PeerManagerNotification, PeerManagerRequest,
+    },
+    protocols::{
+        network::{NewNetworkEvents, NewNetworkSender},
+        rpc::InboundRpcRequest,
+        wire::handshake::v1::ProtocolIdSet,
+    },
+    transport::ConnectionMetadata,
+};
+use aptos_config::{
+    config::{PeerRole, RoleType},
+    network_id::NetworkId,
+};
+use aptos_time_service::MockTimeService;
+use futures::{executor::block_on, future};
+use std::iter::FromIterator;
+
+const MONITORING_INTERVAL: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+struct TestHarness {
+    mock_time: MockTimeService,
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
+    peer_mgr_reqs_rx: aptos_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
+    peer_mgr_notifs_tx: aptos_channel::Sender<(PeerId, ProtocolId), PeerManagerNotification>,
+    _connection_reqs_rx: aptos_channel::Receiver<PeerId, ConnectionRequest>,
+    _connection_notifs_tx: conn_notifs_channel::Sender,
+}
+
+impl TestHarness {
+    fn new(role: RoleType, synced_version: Option<Version>) -> (Self, PeerMonitor) {
+        ::aptos_logger::Logger::init_for_testing();
+        let mock_time = TimeService::mock();
+        let network_id = NetworkId::Public;
+        let peer_metadata_storage = PeerMetadataStorage::new(&[network_id], mock_time.clone());
+
+        let (peer_mgr_reqs_tx, peer_mgr_reqs_rx) = aptos_channel::new(QueueStyle::FIFO, 1, None);
+        let (connection_reqs_tx, connection_reqs_rx) =
+            aptos_channel::new(QueueStyle::FIFO, 1, None);
+        let (peer_mgr_notifs_tx, peer_mgr_notifs_rx) =
+            aptos_channel::new(QueueStyle::FIFO, 1, None);
+        let (connection_notifs_tx, connection_notifs_rx) = conn_notifs_channel::new();
+
+        let network_tx = PeerMonitoringNetworkSender::new(
+            PeerManagerRequestSender::new(peer_mgr_reqs_tx),
+            ConnectionRequestSender::new(connection_reqs_tx),
+        );
+        let network_rx = PeerMonitoringNetworkEvents::new(peer_mgr_notifs_rx, connection_notifs_rx);
+        let synced_version_reader: SyncedVersionReader = Arc::new(move || synced_version);
+        let peer_monitor = PeerMonitor::new(
+            NetworkContext::new(role, network_id, PeerId::random()),
+            mock_time.clone(),
+            network_tx,
+            network_rx,
+            peer_metadata_storage.clone(),
+            Some(synced_version_reader),
+            MONITORING_INTERVAL,
+            REQUEST_TIMEOUT,
+        );
+
+        (
+            Self {
+                mock_time: mock_time.into_mock(),
+                peer_metadata_storage,
+                peer_mgr_reqs_rx,
+                peer_mgr_notifs_tx,
+                _connection_reqs_rx: connection_reqs_rx,
+                _connection_notifs_tx: connection_notifs_tx,
+            },
+            peer_monitor,
+        )
+    }
+
+    fn connect_peer(&self, peer_id: PeerId, role: PeerRole) -> PeerNetworkId {
+        let mut connection_metadata = ConnectionMetadata::mock(peer_id);
+        connection_metadata.role = role;
+        connection_metadata.application_protocols =
+            ProtocolIdSet::from_iter([ProtocolId::PeerMonitoringServiceRpc]);
+        self.peer_metadata_storage
+            .insert_connection(NetworkId::Public, connection_metadata);
+        PeerNetworkId::new(NetworkId::Public, peer_id)
+    }
+
+    async fn expect_node_info_request(
+        &mut self,
+        expected_peer_id: PeerId,
+    ) -> oneshot::Sender<Result<Bytes, RpcError>> {
+        let req = self.peer_mgr_reqs_rx.next().await.unwrap();
+        let (peer_id, rpc_req) = match req {
+            PeerManagerRequest::SendRpc(peer_id, rpc_req) => (peer_id, rpc_req),
+            _ => panic!("Unexpected PeerManagerRequest: {:?}", req),
+        };
+        assert_eq!(peer_id, expected_peer_id);
+        assert_eq!(rpc_req.protocol_id, ProtocolId::PeerMonitoringServiceRpc);
+        match bcs::from_bytes(&rpc_req.data).unwrap() {
+            PeerMonitoringMsg::GetNodeInfo => rpc_req.res_tx,
+            msg => panic!("Unexpected PeerMonitoringMsg: {:?}", msg),
+        }
+    }
+
+    async fn send_node_info_request(&mut self, peer_id: PeerId) -> NodeInfo {
+        let protocol_id = ProtocolId::PeerMonitoringServiceRpc;
+        let data = bcs::to_bytes(&PeerMonitoringMsg::GetNodeInfo)
+            .unwrap()
+            .into();
+        let (res_tx, res_rx) = oneshot::channel();
+        let inbound_rpc_req = InboundRpcRequest {
+            protocol_id,
+            data,
+            res_tx,
+        };
+        self.peer_mgr_notifs_tx
+            .push(
+                (peer_id, protocol_id),
+                PeerManagerNotification::RecvRpc(peer_id, inbound_rpc_req),
+            )
+            .unwrap();
+        let res_data = res_rx.await.unwrap().unwrap();
+        match bcs::from_bytes(&res_data).unwrap() {
+            PeerMonitoringMsg::NodeInfo(node_info) => node_info,
+            msg => panic!("Unexpected PeerMonitoringMsg: {:?}", msg),
+        }
+    }
+}
+
+#[test]
+fn validator_reports_itself() {
+    let (mut harness, peer_monitor) = TestHarness::new(RoleType::Validator, Some(42));
+
+    let test = async move {
+        let node_info = harness.send_node_info_request(PeerId::random()).await;
+        assert_eq!(
+            node_info,
+            NodeInfo {
+                distance_from_validators: 0,
+                highest_synced_version: Some(42),
+            }
+        );
+    };
+    block_on(future::join(peer_monitor.start(), test));
+}
+
+#[test]
+fn monitor_connected_peer() {
+    let (mut harness, peer_monitor) = TestHarness::new(RoleType::FullNode, None);
+    let peer_id = PeerId::random();
+    let peer_network_id = harness.connect_peer(peer_id, PeerRole::Validator);
+
+    let test = async move {
+        // Until the peer reports its distance, the node is as far as can be from the validators.
+        let node_info = harness.send_node_info_request(peer_id).await;
+        assert_eq!(
+            node_info.distance_from_validators,
+            MAX_DISTANCE_FROM_VALIDATORS
+        );
+        assert_eq!(node_info.highest_synced_version, None);
+
+        // The connected peer is monitored on the first tick.
+        let res_tx = harness.expect_node_info_request(peer_id).await;
+
+        // The peer takes 100ms to respond, and is a validator.
+        harness
+            .mock_time
+            .advance_async(Duration::from_millis(100))
+            .await;
+        let response = PeerMonitoringMsg::NodeInfo(NodeInfo {
+            distance_from_validators: 0,
+            highest_synced_version: Some(100),
+        });
+        res_tx
+            .send(Ok(bcs::to_bytes(&response).unwrap().into()))
+            .unwrap();
+
+        // Once the response is recorded, the node is one hop away from the validators.
+        while harness
+            .send_node_info_request(peer_id)
+            .await
+            .distance_from_validators
+            != 1
+        {}
+
+        let metadata = harness
+            .peer_metadata_storage
+            .read(peer_network_id)
+            .unwrap()
+            .monitoring_metadata;
+        assert_eq!(
+            metadata,
+            PeerMonitoringMetadata {
+                average_ping_latency_secs: Some(0.1),
+                distance_from_validators: Some(0),
+                highest_synced_version: Some(100),
+            }
+        );
+    };
+    block_on(future::join(peer_monitor.start(), test));
+}
+
+#[test]
+fn untrusted_peer_distance_is_clamped() {
+    let (mut harness, peer_monitor) = TestHarness::new(RoleType::FullNode, None);
+    let peer_id = PeerId::random();
+    let peer_network_id = harness.connect_peer(peer_id, PeerRole::Unknown);
+
+    let test = async move {
+        // An unknown peer claims to be a validator.
+        let res_tx = harness.expect_node_info_request(peer_id).await;
+        let response = PeerMonitoringMsg::NodeInfo(NodeInfo {
+            distance_from_validators: 0,
+            highest_synced_version: Some(100),
+        });
+        res_tx
+            .send(Ok(bcs::to_bytes(&response).unwrap().into()))
+            .unwrap();
+
+        // It is only trusted to be as close as its role allows.
+        while harness
+            .send_node_info_request(peer_id)
+            .await
+            .distance_from_validators
+            != 3
+        {}
+        let metadata = harness
+            .peer_metadata_storage
+            .read(peer_network_id)
+            .unwrap()
+            .monitoring_metadata;
+        assert_eq!(metadata.distance_from_validators, Some(2));
+    };
+    block_on(future::join(peer_monitor.start(), test));
+}
+
+#[test]
+fn min_distance_by_role() {
+    assert_eq!(min_distance_from_validators(PeerRole::Validator), 0);
+    assert_eq!(min_distance_from_validators(PeerRole::ValidatorFullNode), 1);
+    assert_eq!(min_distance_from_validators(PeerRole::Upstream), 2);
+    assert_eq!(min_distance_from_validators(PeerRole::Unknown), 2);
+}
+
+#[test]
+fn average_ping_latency() {
+    let mut metadata = PeerMonitoringMetadata::default();
+    let node_info = NodeInfo {
+        distance_from_validators: 2,
+        highest_synced_version: Some(1),
+    };
+    update_metadata(&mut metadata, Duration::from_millis(500), node_info, 0);
+    assert_eq!(metadata.average_ping_latency_secs, Some(0.5));
+    update_metadata(&mut metadata, Duration::from_millis(5500), node_info, 0);
+    assert_eq!(metadata.average_ping_latency_secs, Some(1.5));
+    assert_eq!(metadata.distance_from_validators, Some(2));
+}
@@ -26,7 +26,8 @@
 //! [`NetworkMessage`] arrivals and polls for completed rpc requests. The queues
 //! also do not write to the wire directly; instead, they're given a reference to
 //! the [`Peer`] actor's write queue, which they can enqueue a new outbound
-//! [`NetworkMessage`] onto.
+//! [`NetworkMessage`] onto. On a connection multiplexing streams, responses are
+//! enqueued onto the writer of the stream of the protocol of their request.
 //!
 //! ## Timeouts:
 //!
@@ -38,10 +39,13 @@
 //! ## Limits:
 //!
 //! We limit the number of pending inbound and outbound RPC tasks to ensure that
-//! resource usage is bounded.
+//! resource usage is bounded. The requests and responses are also subject to the
+//! per-protocol rate limits of the [`ProtocolLimiter`] of the connection: throttled
+//! requests are dropped, while throttled responses are delayed.
 //!
 //! [AptosNet wire protocol v1]: https://github.com/aptos-labs/aptos-core/blob/main/specifications/network/messaging-v1.md
 //! [`Peer`]: crate::peer::Peer
+//! [`ProtocolLimiter`]: crate::peer_manager::ProtocolLimiter
 
 use crate::{
     counters::{
@@ -49,11 +53,15 @@ use crate::{
         RESPONSE_LABEL, SENT_LABEL,
     },
     logging::NetworkSchema,
-    peer::PeerNotification,
-    peer_manager::PeerManagerError,
+    peer::{PeerNotification, TrafficClass},
+    peer_manager::{PeerManagerError, ProtocolLimiter},
     protocols::{
         network::SerializedRequest,
-        wire::messaging::v1::{NetworkMessage, Priority, RequestId, RpcRequest, RpcResponse},
+        wire::{
+            compression,
+            handshake::v1::ProtocolIdSet,
+            messaging::v1::{NetworkMessage, RequestId, RpcRequest, RpcResponse},
+        },
     },
     ProtocolId,
 };
@@ -67,7 +75,7 @@ use bytes::Bytes;
 use channel::aptos_channel;
 use error::RpcError;
 use futures::{
-    channel::oneshot,
+    channel::{mpsc, oneshot},
     future::{BoxFuture, FusedFuture, Future, FutureExt},
     sink::SinkExt,
     stream::{FuturesUnordered, StreamExt},
@@ -174,7 +182,9 @@ pub struct InboundRpcs {
     remote_peer_id: PeerId,
     /// The core async queue of pending inbound rpc tasks. The tasks are driven
     /// to completion by the `InboundRpcs::next_completed_response()` method.
-    inbound_rpc_tasks: FuturesUnordered<BoxFuture<'static, Result<RpcResponse, RpcError>>>,
+    /// The responses are completed with the protocol of their request.
+    inbound_rpc_tasks:
+        FuturesUnordered<BoxFuture<'static, Result<(ProtocolId, RpcResponse), RpcError>>>,
     /// A blanket timeout on all inbound rpc requests. If the application handler
     /// doesn't respond to the request before this timeout, the request will be
     /// dropped.
@@ -182,6 +192,10 @@ pub struct InboundRpcs {
     /// Only allow this many concurrent inbound rpcs at one time from this remote
     /// peer.  New inbound requests exceeding this limit will be dropped.
     max_concurrent_inbound_rpcs: u32,
+    /// The maximum size of a request once decompressed.
+    max_message_size: usize,
+    /// Accounts and rate limits the requests and responses by protocol.
+    protocol_limiter: ProtocolLimiter,
 }
 
 impl InboundRpcs {
@@ -191,6 +205,8 @@ impl InboundRpcs {
         remote_peer_id: PeerId,
         inbound_rpc_timeout: Duration,
         max_concurrent_inbound_rpcs: u32,
+        max_message_size: usize,
+        protocol_limiter: ProtocolLimiter,
     ) -> Self {
         Self {
             network_context,
@@ -199,6 +215,8 @@ impl InboundRpcs {
             inbound_rpc_tasks: FuturesUnordered::new(),
             inbound_rpc_timeout,
             max_concurrent_inbound_rpcs,
+            max_message_size,
+            protocol_limiter,
         }
     }
 
@@ -219,7 +237,19 @@ impl InboundRpcs {
 
         let protocol_id = request.protocol_id;
         let request_id = request.request_id;
-        let priority = request.priority;
+
+        // Drop new inbound requests exceeding the rate limit of their protocol.
+        if !self
+            .protocol_limiter
+            .allow_inbound(protocol_id, request.raw_request.len())
+        {
+            counters::rpc_messages(network_context, RESPONSE_LABEL, DECLINED_LABEL).inc();
+            return Err(RpcError::RateLimited(protocol_id));
+        }
+
+        // The response is scheduled by the class of its protocol, whatever the priority chosen
+        // by the remote peer.
+        let priority = TrafficClass::from(protocol_id).priority();
         let req_len = request.raw_request.len() as u64;
 
         trace!(
@@ -237,42 +267,70 @@ impl InboundRpcs {
         let timer =
             counters::inbound_rpc_handler_latency(network_context, protocol_id).start_timer();
 
+        // The application receives a compressed request decompressed, with the uncompressed
+        // protocol, and its response is compressed back.
+        let compressed = protocol_id.is_compressed();
+        let (app_protocol_id, request_data) = if compressed {
+            let data = compression::decompress(&request.raw_request, self.max_message_size)
+                .map_err(RpcError::Error)?;
+            (protocol_id.uncompressed(), data)
+        } else {
+            (protocol_id, request.raw_request)
+        };
+
         // Foward request to PeerManager for handling.
         let (response_tx, response_rx) = oneshot::channel();
         let notif = PeerNotification::RecvRpc(InboundRpcRequest {
-            protocol_id,
-            data: Bytes::from(request.raw_request),
+            protocol_id: app_protocol_id,
+            data: Bytes::from(request_data),
             res_tx: response_tx,
         });
-        if let Err(err) = peer_notifs_tx.push(protocol_id, notif) {
+        if let Err(err) = peer_notifs_tx.push(app_protocol_id, notif) {
             counters::rpc_messages(network_context, RESPONSE_LABEL, FAILED_LABEL).inc();
             return Err(err.into());
         }
 
         // Create a new task that waits for a response from the upper layer with a timeout.
-        let inbound_rpc_task = self
+        let protocol_limiter = self.protocol_limiter.clone();
+        let time_service = self.time_service.clone();
+        let wait_for_response = self
             .time_service
-            .timeout(self.inbound_rpc_timeout, response_rx)
-            .map(move |result| {
-                // Flatten the errors
-                let maybe_response = match result {
-                    Ok(Ok(Ok(response_bytes))) => Ok(RpcResponse {
-                        request_id,
-                        priority,
-                        raw_response: Vec::from(response_bytes.as_ref()),
-                    }),
-                    Ok(Ok(Err(err))) => Err(err),
-                    Ok(Err(oneshot::Canceled)) => Err(RpcError::UnexpectedResponseChannelCancel),
-                    Err(timeout::Elapsed) => Err(RpcError::TimedOut),
-                };
-                // Only record latency of successful requests
-                match maybe_response {
-                    Ok(_) => timer.stop_and_record(),
-                    Err(_) => timer.stop_and_discard(),
-                };
-                maybe_response
-            })
-            .boxed();
+            .timeout(self.inbound_rpc_timeout, response_rx);
+        let inbound_rpc_task = async move {
+            // Flatten the errors
+            let maybe_response = match wait_for_response.await {
+                Ok(Ok(Ok(response_bytes))) => {
+                    let raw_response = if compressed {
+                        compression::compress(&response_bytes)
+                    } else {
+                        Vec::from(response_bytes.as_ref())
+                    };
+                    // Delay the responses exceeding the rate limit of their protocol, rather
+                    // than dropping the work already done.
+                    protocol_limiter
+                        .throttle_outbound(&time_service, protocol_id, raw_response.len())
+                        .await;
+                    Ok((
+                        protocol_id,
+                        RpcResponse {
+                            request_id,
+                            priority,
+                            raw_response,
+                        },
+                    ))
+                }
+                Ok(Ok(Err(err))) => Err(err),
+                Ok(Err(oneshot::Canceled)) => Err(RpcError::UnexpectedResponseChannelCancel),
+                Err(timeout::Elapsed) => Err(RpcError::TimedOut),
+            };
+            // Only record latency of successful requests
+            match maybe_response {
+                Ok(_) => timer.stop_and_record(),
+                Err(_) => timer.stop_and_discard(),
+            };
+            maybe_response
+        }
+        .boxed();
 
         // Add that task to the inbound completion queue. These tasks are driven
         // forward by `Peer` awaiting `self.next_completed_response()`.
@@ -286,23 +344,30 @@ impl InboundRpcs {
     /// `futures::select!`.
     pub fn next_completed_response(
         &mut self,
-    ) -> impl Future<Output = Result<RpcResponse, RpcError>> + FusedFuture + '_ {
+    ) -> impl Future<Output = Result<(ProtocolId, RpcResponse), RpcError>> + FusedFuture + '_ {
         self.inbound_rpc_tasks.select_next_some()
     }
 
     /// Handle a completed response from the application handler. If successful,
     /// we update the appropriate counters and enqueue the response message onto
-    /// the outbound write queue.
+    /// the writer of the stream of its protocol, if any, or the outbound write queue.
     pub async fn send_outbound_response(
         &mut self,
         write_reqs_tx: &mut channel::Sender<(
             NetworkMessage,
             oneshot::Sender<Result<(), PeerManagerError>>,
         )>,
-        maybe_response: Result<RpcResponse, RpcError>,
+        stream_writers: &mut HashMap<
+            ProtocolId,
+            mpsc::Sender<(
+                NetworkMessage,
+                oneshot::Sender<Result<(), PeerManagerError>>,
+            )>,
+        >,
+        maybe_response: Result<(ProtocolId, RpcResponse), RpcError>,
     ) -> Result<(), RpcError> {
         let network_context = &self.network_context;
-        let response = match maybe_response {
+        let (protocol_id, response) = match maybe_response {
             Ok(response) => response,
             Err(err) => {
                 counters::rpc_messages(network_context, RESPONSE_LABEL, FAILED_LABEL).inc();
@@ -321,7 +386,12 @@ impl InboundRpcs {
         );
         let message = NetworkMessage::RpcResponse(response);
         let (ack_tx, _) = oneshot::channel();
-        write_reqs_tx.send((message, ack_tx)).await?;
+        match stream_writers.get_mut(&protocol_id) {
+            Some(stream_writer) => stream_writer
+                .try_send((message, ack_tx))
+                .map_err(|err| err.into_send_error())?,
+            None => write_reqs_tx.send((message, ack_tx)).await?,
+        }
 
         // Collect counters for sent response.
         counters::rpc_messages(network_context, RESPONSE_LABEL, SENT_LABEL).inc();
@@ -340,6 +410,9 @@ pub struct OutboundRpcs {
     time_service: TimeService,
     /// The PeerId of this connection's remote peer. Used for logging.
     remote_peer_id: PeerId,
+    /// The protocols negotiated with the remote peer, to send requests with the
+    /// compressed version of their protocol when the remote peer supports it.
+    remote_protocols: ProtocolIdSet,
     /// Generates the next RequestId to use for the next outbound RPC. Note that
     /// request ids are local to each connection.
     request_id_gen: U32IdGenerator,
@@ -358,6 +431,10 @@ pub struct OutboundRpcs {
     /// Only allow this many concurrent outbound rpcs at one time from this remote
     /// peer. New outbound requests exceeding this limit will be dropped.
     max_concurrent_outbound_rpcs: u32,
+    /// The maximum size of a response once decompressed.
+    max_message_size: usize,
+    /// Accounts and rate limits the requests and responses by protocol.
+    protocol_limiter: ProtocolLimiter,
 }
 
 impl OutboundRpcs {
@@ -365,16 +442,22 @@ impl OutboundRpcs {
         network_context: NetworkContext,
         time_service: TimeService,
         remote_peer_id: PeerId,
+        remote_protocols: ProtocolIdSet,
         max_concurrent_outbound_rpcs: u32,
+        max_message_size: usize,
+        protocol_limiter: ProtocolLimiter,
     ) -> Self {
         Self {
             network_context,
             time_service,
             remote_peer_id,
+            remote_protocols,
             request_id_gen: U32IdGenerator::new(),
             outbound_rpc_tasks: FuturesUnordered::new(),
             pending_outbound_rpcs: HashMap::new(),
             max_concurrent_outbound_rpcs,
+            max_message_size,
+            protocol_limiter,
         }
     }
 
@@ -397,7 +480,17 @@ impl OutboundRpcs {
             timeout,
             res_tx: mut application_response_tx,
         } = request;
-        let req_len = request_data.len() as u64;
+
+        // Compress the request if the remote peer supports the compressed protocol. The
+        // response then comes back compressed and is decompressed for the application.
+        let wire_protocol_id = protocol_id.preferred_for(&self.remote_protocols);
+        let compressed = wire_protocol_id != protocol_id;
+        let raw_request = if compressed {
+            compression::compress(&request_data)
+        } else {
+            Vec::from(request_data.as_ref())
+        };
+        let req_len = raw_request.len() as u64;
 
         // Drop the outbound request if the application layer has already canceled.
         if application_response_tx.is_canceled() {
@@ -414,6 +507,16 @@ impl OutboundRpcs {
             return Err(RpcError::TooManyPending(self.max_concurrent_outbound_rpcs));
         }
 
+        // Drop new outbound requests exceeding the rate limit of their protocol.
+        if !self
+            .protocol_limiter
+            .allow_outbound(wire_protocol_id, raw_request.len())
+        {
+            counters::rpc_messages(network_context, REQUEST_LABEL, DECLINED_LABEL).inc();
+            let _ = application_response_tx.send(Err(RpcError::RateLimited(protocol_id)));
+            return Err(RpcError::RateLimited(protocol_id));
+        }
+
         let request_id = self.request_id_gen.next();
 
         trace!(
@@ -431,10 +534,10 @@ impl OutboundRpcs {
 
         // Enqueue rpc request message onto outbound write queue.
         let message = NetworkMessage::RpcRequest(RpcRequest {
-            protocol_id,
+            protocol_id: wire_protocol_id,
             request_id,
-            priority: Priority::default(),
-            raw_request: Vec::from(request_data.as_ref()),
+            priority: TrafficClass::from(protocol_id).priority(),
+            raw_request,
         });
         let (ack_tx, _) = oneshot::channel();
         write_reqs_tx.send((message, ack_tx)).await?;
@@ -453,17 +556,31 @@ impl OutboundRpcs {
         // A future that waits for the rpc response with a timeout. We create the
         // timeout out here to start the timer as soon as we push onto the queue
         // (as opposed to whenever it first gets polled on the queue).
-        let wait_for_response = self
-            .time_service
-            .timeout(timeout, response_rx)
-            .map(|result| {
-                // Flatten errors.
-                match result {
-                    Ok(Ok(response)) => Ok(Bytes::from(response.raw_response)),
-                    Ok(Err(oneshot::Canceled)) => Err(RpcError::UnexpectedResponseChannelCancel),
-                    Err(timeout::Elapsed) => Err(RpcError::TimedOut),
-                }
-            });
+        let protocol_limiter = self.protocol_limiter.clone();
+        let max_message_size = self.max_message_size;
+        let wait_for_response =
+            self.time_service
+                .timeout(timeout, response_rx)
+                .map(move |result| {
+                    // Responses to our requests are accounted, but never rate limited.
+                    if let Ok(Ok(response)) = &result {
+                        protocol_limiter
+                            .account_inbound(wire_protocol_id, response.raw_response.len());
+                    }
+                    // Flatten errors.
+                    match result {
+                        Ok(Ok(response)) if compressed => {
+                            compression::decompress(&response.raw_response, max_message_size)
+                                .map(Bytes::from)
+                                .map_err(RpcError::Error)
+                        }
+                        Ok(Ok(response)) => Ok(Bytes::from(response.raw_response)),
+                        Ok(Err(oneshot::Canceled)) => {
+                            Err(RpcError::UnexpectedResponseChannelCancel)
+                        }
+                        Err(timeout::Elapsed) => Err(RpcError::TimedOut),
+                    }
+                });
 
         // A future that waits for the response and sends it to the application.
         let notify_application = async move {
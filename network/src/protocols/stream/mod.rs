@@ -0,0 +1,289 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fragmentation of the messages that don't fit in a single frame, over connections
+//! using [`MessagingProtocolVersion::V2`].
+//!
+//! A message whose payload is larger than a frame is sent as a [`StreamHeader`],
+//! carrying the message with the beginning of its payload, followed by the
+//! [`StreamFragment`]s carrying the rest of the payload. The `Peer` writer may
+//! send messages of a higher priority between the fragments of a message, but
+//! only starts the next fragmented message once all the fragments of the previous
+//! one are sent, so the remote peer only ever reassembles a single message, of at
+//! most `max_message_size` bytes.
+//!
+//! [`MessagingProtocolVersion::V2`]: crate::protocols::wire::handshake::v1::MessagingProtocolVersion::V2
+
+use crate::protocols::wire::messaging::v1::{
+    DirectSendMsg, NetworkMessage, RpcRequest, RpcResponse,
+};
+use aptos_id_generator::{IdGenerator, U32IdGenerator};
+#[cfg(any(test, feature = "fuzzing"))]
+use proptest_derive::Arbitrary;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use thiserror::Error;
+
+#[cfg(test)]
+mod test;
+
+/// Bytes of a frame reserved for the headers of the `NetworkMessage` carrying a
+/// part of the payload.
+const FRAME_OVERHEAD: usize = 64;
+
+/// Identifies the fragments of a message, unique for each connection.
+pub type StreamId = u32;
+
+/// The first frame of a fragmented message.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct StreamHeader {
+    pub stream_id: StreamId,
+    /// The number of `StreamFragment`s following the header.
+    pub num_fragments: u8,
+    /// The message, with the beginning of its payload.
+    pub message: StreamedMessage,
+}
+
+/// A part of the payload of a fragmented message.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct StreamFragment {
+    pub stream_id: StreamId,
+    /// The index of the fragment, starting from 1.
+    pub fragment_id: u8,
+    #[serde(with = "serde_bytes")]
+    pub raw_data: Vec<u8>,
+}
+
+/// The messages that can be fragmented.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub enum StreamedMessage {
+    RpcRequest(RpcRequest),
+    RpcResponse(RpcResponse),
+    DirectSendMsg(DirectSendMsg),
+}
+
+impl StreamedMessage {
+    fn payload_len(&self) -> usize {
+        match self {
+            StreamedMessage::RpcRequest(request) => request.raw_request.len(),
+            StreamedMessage::RpcResponse(response) => response.raw_response.len(),
+            StreamedMessage::DirectSendMsg(message) => message.raw_msg.len(),
+        }
+    }
+
+    fn payload_mut(&mut self) -> &mut Vec<u8> {
+        match self {
+            StreamedMessage::RpcRequest(request) => &mut request.raw_request,
+            StreamedMessage::RpcResponse(response) => &mut response.raw_response,
+            StreamedMessage::DirectSendMsg(message) => &mut message.raw_msg,
+        }
+    }
+}
+
+impl TryFrom<NetworkMessage> for StreamedMessage {
+    type Error = NetworkMessage;
+
+    fn try_from(message: NetworkMessage) -> Result<Self, Self::Error> {
+        match message {
+            NetworkMessage::RpcRequest(request) => Ok(StreamedMessage::RpcRequest(request)),
+            NetworkMessage::RpcResponse(response) => Ok(StreamedMessage::RpcResponse(response)),
+            NetworkMessage::DirectSendMsg(message) => Ok(StreamedMessage::DirectSendMsg(message)),
+            message => Err(message),
+        }
+    }
+}
+
+impl From<StreamedMessage> for NetworkMessage {
+    fn from(message: StreamedMessage) -> Self {
+        match message {
+            StreamedMessage::RpcRequest(request) => NetworkMessage::RpcRequest(request),
+            StreamedMessage::RpcResponse(response) => NetworkMessage::RpcResponse(response),
+            StreamedMessage::DirectSendMsg(message) => NetworkMessage::DirectSendMsg(message),
+        }
+    }
+}
+
+/// The size of the payload of a message, 0 for the messages without payload.
+fn payload_len(message: &NetworkMessage) -> usize {
+    match message {
+        NetworkMessage::RpcRequest(request) => request.raw_request.len(),
+        NetworkMessage::RpcResponse(response) => response.raw_response.len(),
+        NetworkMessage::DirectSendMsg(message) => message.raw_msg.len(),
+        _ => 0,
+    }
+}
+
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum StreamError {
+    #[error("message of {0} bytes exceeds the maximum message size of {1}")]
+    MessageTooLarge(usize, usize),
+    #[error("message of {0} bytes needs more than {} fragments", u8::MAX)]
+    TooManyFragments(usize),
+    #[error("stream {0} started before the end of stream {1}")]
+    UnfinishedStream(StreamId, StreamId),
+    #[error("fragment of stream {0} received outside of a stream")]
+    NoStream(StreamId),
+    #[error("unexpected fragment {1} of stream {0}, expected fragment {3} of stream {2}")]
+    UnexpectedFragment(StreamId, u8, StreamId, u8),
+    #[error("stream {0} has no fragments")]
+    NoFragments(StreamId),
+    #[error("fragmented messages are not supported by the connection")]
+    NotSupported,
+}
+
+/// Splits the outbound messages that don't fit in a frame.
+pub struct OutboundStream {
+    fragment_size: usize,
+    max_message_size: usize,
+    stream_id_gen: U32IdGenerator,
+}
+
+impl OutboundStream {
+    pub fn new(max_frame_size: usize, max_message_size: usize) -> Self {
+        assert!(
+            max_frame_size > FRAME_OVERHEAD,
+            "Frame size {} is too small to stream messages",
+            max_frame_size
+        );
+        Self {
+            fragment_size: max_frame_size - FRAME_OVERHEAD,
+            max_message_size,
+            stream_id_gen: U32IdGenerator::new(),
+        }
+    }
+
+    /// Returns true if the message doesn't fit in a single frame.
+    pub fn should_stream(&self, message: &NetworkMessage) -> bool {
+        payload_len(message) > self.fragment_size
+    }
+
+    /// Splits the message into a `StreamHeader` and `StreamFragment`s that each
+    /// fit in a frame, to be sent in order.
+    pub fn fragment(&self, message: NetworkMessage) -> Result<Vec<NetworkMessage>, StreamError> {
+        let message_len = payload_len(&message);
+        if message_len <= self.fragment_size {
+            return Ok(vec![message]);
+        }
+        if message_len > self.max_message_size {
+            return Err(StreamError::MessageTooLarge(
+                message_len,
+                self.max_message_size,
+            ));
+        }
+        let num_fragments = u8::try_from((message_len - 1) / self.fragment_size)
+            .map_err(|_| StreamError::TooManyFragments(message_len))?;
+        let mut message = StreamedMessage::try_from(message)
+            .expect("Only messages with a payload are larger than a frame");
+
+        let stream_id = self.stream_id_gen.next();
+        let rest = message.payload_mut().split_off(self.fragment_size);
+        let mut messages = vec![NetworkMessage::StreamHeader(StreamHeader {
+            stream_id,
+            num_fragments,
+            message,
+        })];
+        messages.extend(
+            rest.chunks(self.fragment_size)
+                .zip(1..)
+                .map(|(raw_data, fragment_id)| {
+                    NetworkMessage::StreamFragment(StreamFragment {
+                        stream_id,
+                        fragment_id,
+                        raw_data: raw_data.to_vec(),
+                    })
+                }),
+        );
+        Ok(messages)
+    }
+}
+
+/// The message being reassembled.
+struct InboundStream {
+    header: StreamHeader,
+    next_fragment_id: u8,
+}
+
+/// Reassembles the inbound fragmented messages.
+pub struct InboundStreamBuffer {
+    max_message_size: usize,
+    stream: Option<InboundStream>,
+}
+
+impl InboundStreamBuffer {
+    pub fn new(max_message_size: usize) -> Self {
+        Self {
+            max_message_size,
+            stream: None,
+        }
+    }
+
+    /// Starts reassembling a new message. A stream that wasn't finished is dropped.
+    pub fn new_stream(&mut self, header: StreamHeader) -> Result<(), StreamError> {
+        let previous = self.stream.take();
+        if header.num_fragments == 0 {
+            return Err(StreamError::NoFragments(header.stream_id));
+        }
+        let message_len = header.message.payload_len();
+        if message_len > self.max_message_size {
+            return Err(StreamError::MessageTooLarge(
+                message_len,
+                self.max_message_size,
+            ));
+        }
+        let stream_id = header.stream_id;
+        self.stream = Some(InboundStream {
+            header,
+            next_fragment_id: 1,
+        });
+        match previous {
+            Some(previous) => Err(StreamError::UnfinishedStream(
+                stream_id,
+                previous.header.stream_id,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Appends a fragment to the message being reassembled and returns the message
+    /// once its last fragment is received. The stream is dropped on any error.
+    pub fn append_fragment(
+        &mut self,
+        fragment: StreamFragment,
+    ) -> Result<Option<NetworkMessage>, StreamError> {
+        let mut stream = self
+            .stream
+            .take()
+            .ok_or(StreamError::NoStream(fragment.stream_id))?;
+        if fragment.stream_id != stream.header.stream_id
+            || fragment.fragment_id != stream.next_fragment_id
+        {
+            return Err(StreamError::UnexpectedFragment(
+                fragment.stream_id,
+                fragment.fragment_id,
+                stream.header.stream_id,
+                stream.next_fragment_id,
+            ));
+        }
+
+        let payload = stream.header.message.payload_mut();
+        let message_len = payload.len() + fragment.raw_data.len();
+        if message_len > self.max_message_size {
+            return Err(StreamError::MessageTooLarge(
+                message_len,
+                self.max_message_size,
+            ));
+        }
+        payload.extend_from_slice(&fragment.raw_data);
+
+        if stream.next_fragment_id == stream.header.num_fragments {
+            Ok(Some(stream.header.message.into()))
+        } else {
+            stream.next_fragment_id += 1;
+            self.stream = Some(stream);
+            Ok(None)
+        }
+    }
+}
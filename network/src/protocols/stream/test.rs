@@ -0,0 +1,156 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{protocols::wire::messaging::v1::ErrorCode, ProtocolId};
+
+const MAX_FRAME_SIZE: usize = 1024;
+const MAX_MESSAGE_SIZE: usize = 16 * 1024;
+
+fn direct_send(len: usize) -> NetworkMessage {
+    NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: ProtocolId::MempoolDirectSend,
+        priority: 0,
+        raw_msg: (0..len).map(|i| i as u8).collect(),
+    })
+}
+
+fn rpc_response(len: usize) -> NetworkMessage {
+    NetworkMessage::RpcResponse(RpcResponse {
+        request_id: 7,
+        priority: 0,
+        raw_response: vec![1; len],
+    })
+}
+
+/// Feeds the messages to the buffer and returns the reassembled messages.
+fn reassemble(
+    buffer: &mut InboundStreamBuffer,
+    messages: Vec<NetworkMessage>,
+) -> Result<Vec<NetworkMessage>, StreamError> {
+    let mut reassembled = vec![];
+    for message in messages {
+        match message {
+            NetworkMessage::StreamHeader(header) => buffer.new_stream(header)?,
+            NetworkMessage::StreamFragment(fragment) => {
+                reassembled.extend(buffer.append_fragment(fragment)?)
+            }
+            message => reassembled.push(message),
+        }
+    }
+    Ok(reassembled)
+}
+
+#[test]
+fn small_messages_are_not_fragmented() {
+    let outbound_stream = OutboundStream::new(MAX_FRAME_SIZE, MAX_MESSAGE_SIZE);
+    for message in [
+        direct_send(0),
+        direct_send(MAX_FRAME_SIZE - FRAME_OVERHEAD),
+        NetworkMessage::Error(ErrorCode::parsing_error(0, 0)),
+    ] {
+        assert!(!outbound_stream.should_stream(&message));
+        assert_eq!(
+            outbound_stream.fragment(message.clone()).unwrap(),
+            vec![message]
+        );
+    }
+}
+
+#[test]
+fn fragment_and_reassemble() {
+    let outbound_stream = OutboundStream::new(MAX_FRAME_SIZE, MAX_MESSAGE_SIZE);
+    let mut buffer = InboundStreamBuffer::new(MAX_MESSAGE_SIZE);
+    let fragment_size = MAX_FRAME_SIZE - FRAME_OVERHEAD;
+
+    for (message, num_frames) in [
+        (direct_send(fragment_size + 1), 2),
+        (direct_send(3 * fragment_size), 3),
+        (rpc_response(MAX_MESSAGE_SIZE), 18),
+    ] {
+        assert!(outbound_stream.should_stream(&message));
+        let frames = outbound_stream.fragment(message.clone()).unwrap();
+        assert_eq!(frames.len(), num_frames);
+        for frame in &frames {
+            assert!(bcs::to_bytes(frame).unwrap().len() <= MAX_FRAME_SIZE);
+        }
+        assert_eq!(reassemble(&mut buffer, frames).unwrap(), vec![message]);
+    }
+}
+
+#[test]
+fn message_too_large() {
+    let outbound_stream = OutboundStream::new(MAX_FRAME_SIZE, MAX_MESSAGE_SIZE);
+    assert_eq!(
+        outbound_stream
+            .fragment(direct_send(MAX_MESSAGE_SIZE + 1))
+            .unwrap_err(),
+        StreamError::MessageTooLarge(MAX_MESSAGE_SIZE + 1, MAX_MESSAGE_SIZE)
+    );
+    let outbound_stream = OutboundStream::new(MAX_FRAME_SIZE, usize::MAX);
+    let len = 256 * (MAX_FRAME_SIZE - FRAME_OVERHEAD) + 1;
+    assert_eq!(
+        outbound_stream.fragment(direct_send(len)).unwrap_err(),
+        StreamError::TooManyFragments(len)
+    );
+
+    // The receiver enforces its own limit.
+    let frames = outbound_stream
+        .fragment(direct_send(2 * MAX_FRAME_SIZE))
+        .unwrap();
+    let mut buffer = InboundStreamBuffer::new(MAX_FRAME_SIZE);
+    assert!(matches!(
+        reassemble(&mut buffer, frames),
+        Err(StreamError::MessageTooLarge(_, MAX_FRAME_SIZE))
+    ));
+}
+
+#[test]
+fn broken_streams() {
+    let outbound_stream = OutboundStream::new(MAX_FRAME_SIZE, MAX_MESSAGE_SIZE);
+    let mut buffer = InboundStreamBuffer::new(MAX_MESSAGE_SIZE);
+    let first = outbound_stream
+        .fragment(direct_send(3 * MAX_FRAME_SIZE))
+        .unwrap();
+    let second = outbound_stream
+        .fragment(rpc_response(2 * MAX_FRAME_SIZE))
+        .unwrap();
+
+    // A fragment without a header.
+    assert!(matches!(
+        reassemble(&mut buffer, first[1..].to_vec()),
+        Err(StreamError::NoStream(_))
+    ));
+
+    // A stream interrupted by another one is dropped, the new one goes through.
+    let mut frames = first[..2].to_vec();
+    frames.extend(second.clone());
+    assert!(matches!(
+        reassemble(&mut buffer, frames),
+        Err(StreamError::UnfinishedStream(_, _))
+    ));
+    assert_eq!(
+        reassemble(&mut buffer, second[1..].to_vec()).unwrap(),
+        vec![rpc_response(2 * MAX_FRAME_SIZE)]
+    );
+
+    // Fragments out of order.
+    let mut frames = first.clone();
+    frames.swap(1, 2);
+    assert!(matches!(
+        reassemble(&mut buffer, frames),
+        Err(StreamError::UnexpectedFragment(_, 2, _, 1))
+    ));
+
+    // A header without fragments.
+    let mut header = match first[0].clone() {
+        NetworkMessage::StreamHeader(header) => header,
+        message => panic!("Expected StreamHeader; unexpected: {:?}", message),
+    };
+    header.num_fragments = 0;
+    let stream_id = header.stream_id;
+    assert_eq!(
+        buffer.new_stream(header).unwrap_err(),
+        StreamError::NoFragments(stream_id)
+    );
+}
@@ -0,0 +1,90 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! LZ4 compression of the application messages sent with a compressed [`ProtocolId`].
+//!
+//! A compressed message is the little-endian `u32` length of the uncompressed message followed
+//! by the LZ4 block of the message. The length is checked before decompressing against the
+//! configured `max_message_size`, so that a compressed message can't make us allocate more than
+//! the same message sent uncompressed.
+//!
+//! The [`Peer`] compresses and decompresses the messages, the applications only ever see the
+//! uncompressed messages.
+//!
+//! [`Peer`]: crate::peer::Peer
+//! [`ProtocolId`]: crate::protocols::wire::handshake::v1::ProtocolId
+
+use anyhow::{anyhow, ensure};
+use std::convert::TryInto;
+
+const SIZE_PREFIX_LEN: usize = 4;
+
+/// Compresses the message, prefixed with its uncompressed size.
+pub fn compress(raw: &[u8]) -> Vec<u8> {
+    lz4_flex::compress_prepend_size(raw)
+}
+
+/// Decompresses a message produced by [`compress`], whose uncompressed size must not exceed
+/// `max_size`.
+pub fn decompress(compressed: &[u8], max_size: usize) -> anyhow::Result<Vec<u8>> {
+    ensure!(
+        compressed.len() >= SIZE_PREFIX_LEN,
+        "Compressed message is too short: {} bytes",
+        compressed.len()
+    );
+    let (size, block) = compressed.split_at(SIZE_PREFIX_LEN);
+    let size = u32::from_le_bytes(size.try_into().expect("prefix is 4 bytes")) as usize;
+    ensure!(
+        size <= max_size,
+        "Decompressed message size {} exceeds the maximum of {}",
+        size,
+        max_size
+    );
+    let raw = lz4_flex::decompress(block, size).map_err(|e| anyhow!("{:?}", e))?;
+    ensure!(
+        raw.len() == size,
+        "Decompressed {} bytes, expected {}",
+        raw.len(),
+        size
+    );
+    Ok(raw)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAX_SIZE: usize = 64 * 1024;
+
+    #[test]
+    fn compress_roundtrip() {
+        let raw: Vec<u8> = (0..10_000u32).flat_map(|i| (i % 7).to_le_bytes()).collect();
+        let compressed = compress(&raw);
+        assert!(compressed.len() < raw.len() / 3);
+        assert_eq!(decompress(&compressed, MAX_SIZE).unwrap(), raw);
+        assert_eq!(
+            decompress(&compress(&[]), MAX_SIZE).unwrap(),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[test]
+    fn decompress_rejects_invalid_input() {
+        // too short for the size prefix
+        assert!(decompress(&[1, 2], MAX_SIZE).is_err());
+        // claims a size above the maximum
+        let mut oversized = ((MAX_SIZE + 1) as u32).to_le_bytes().to_vec();
+        oversized.extend_from_slice(&compress(b"data")[SIZE_PREFIX_LEN..]);
+        assert!(decompress(&oversized, MAX_SIZE).is_err());
+        // a message that is small compressed but too large once decompressed
+        let zeros = vec![0u8; MAX_SIZE + 1];
+        let compressed = compress(&zeros);
+        assert!(compressed.len() < MAX_SIZE / 100);
+        assert!(decompress(&compressed, MAX_SIZE).is_err());
+        assert_eq!(decompress(&compressed, MAX_SIZE + 1).unwrap(), zeros);
+        // claims more bytes than the block holds
+        let mut compressed = compress(b"some data");
+        compressed[..SIZE_PREFIX_LEN].copy_from_slice(&100u32.to_le_bytes());
+        assert!(decompress(&compressed, MAX_SIZE).is_err());
+    }
+}
@@ -15,7 +15,7 @@
 use anyhow::anyhow;
 use aptos_config::network_id::NetworkId;
 use aptos_types::chain_id::ChainId;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
     fmt,
@@ -51,11 +51,19 @@ pub enum ProtocolId {
     ConsensusRpcJson = 7,
     StorageServiceRpc = 8,
     MempoolRpc = 9,
+    // lz4 compressed bcs, the peer sends with these instead of the uncompressed
+    // protocols when both ends of the connection support them
+    MempoolDirectSendCompressed = 10,
+    StateSyncDirectSendCompressed = 11,
+    StorageServiceRpcCompressed = 12,
+    PeerMonitoringServiceRpc = 13,
 }
 
 /// The encoding types for Protocols
 enum Encoding {
     Bcs,
+    /// BCS for the applications, compressed by the peer on the wire.
+    CompressedBcs,
     Json,
 }
 
@@ -73,6 +81,10 @@ impl ProtocolId {
             ConsensusRpcJson => "ConsensusRpcJson",
             StorageServiceRpc => "StorageServiceRpc",
             MempoolRpc => "MempoolRpc",
+            MempoolDirectSendCompressed => "MempoolDirectSendCompressed",
+            StateSyncDirectSendCompressed => "StateSyncDirectSendCompressed",
+            StorageServiceRpcCompressed => "StorageServiceRpcCompressed",
+            PeerMonitoringServiceRpc => "PeerMonitoringServiceRpc",
         }
     }
 
@@ -88,6 +100,10 @@ impl ProtocolId {
             ProtocolId::ConsensusRpcJson,
             ProtocolId::StorageServiceRpc,
             ProtocolId::MempoolRpc,
+            ProtocolId::MempoolDirectSendCompressed,
+            ProtocolId::StateSyncDirectSendCompressed,
+            ProtocolId::StorageServiceRpcCompressed,
+            ProtocolId::PeerMonitoringServiceRpc,
         ]
     }
 
@@ -95,10 +111,46 @@ impl ProtocolId {
     fn encoding(self) -> Encoding {
         match self {
             ProtocolId::ConsensusDirectSendJson | ProtocolId::ConsensusRpcJson => Encoding::Json,
+            ProtocolId::MempoolDirectSendCompressed
+            | ProtocolId::StateSyncDirectSendCompressed
+            | ProtocolId::StorageServiceRpcCompressed => Encoding::CompressedBcs,
             _ => Encoding::Bcs,
         }
     }
 
+    /// The compressed protocol carrying the same messages as this protocol, if any.
+    pub fn compressed(self) -> Option<ProtocolId> {
+        match self {
+            ProtocolId::MempoolDirectSend => Some(ProtocolId::MempoolDirectSendCompressed),
+            ProtocolId::StateSyncDirectSend => Some(ProtocolId::StateSyncDirectSendCompressed),
+            ProtocolId::StorageServiceRpc => Some(ProtocolId::StorageServiceRpcCompressed),
+            _ => None,
+        }
+    }
+
+    /// The uncompressed protocol carrying the same messages as this protocol.
+    pub fn uncompressed(self) -> ProtocolId {
+        match self {
+            ProtocolId::MempoolDirectSendCompressed => ProtocolId::MempoolDirectSend,
+            ProtocolId::StateSyncDirectSendCompressed => ProtocolId::StateSyncDirectSend,
+            ProtocolId::StorageServiceRpcCompressed => ProtocolId::StorageServiceRpc,
+            _ => self,
+        }
+    }
+
+    pub fn is_compressed(self) -> bool {
+        matches!(self.encoding(), Encoding::CompressedBcs)
+    }
+
+    /// Returns the protocol to send this protocol's messages with to a peer supporting the
+    /// `remote_protocols`: the compressed protocol if the peer supports it, this protocol
+    /// otherwise.
+    pub fn preferred_for(self, remote_protocols: &ProtocolIdSet) -> ProtocolId {
+        self.compressed()
+            .filter(|compressed| remote_protocols.contains(*compressed))
+            .unwrap_or(self)
+    }
+
     #[cfg(test)]
     pub fn mock() -> Self {
         ProtocolId::DiscoveryDirectSend
@@ -107,14 +159,20 @@ impl ProtocolId {
     pub fn to_bytes<T: Serialize>(&self, value: &T) -> anyhow::Result<Vec<u8>> {
         match self.encoding() {
             Encoding::Json => serde_json::to_vec(value).map_err(|e| anyhow!("{:?}", e)),
-            Encoding::Bcs => bcs::to_bytes(value).map_err(|e| anyhow! {"{:?}", e}),
+            // The peer compresses the messages of compressed protocols on the wire
+            Encoding::Bcs | Encoding::CompressedBcs => {
+                bcs::to_bytes(value).map_err(|e| anyhow! {"{:?}", e})
+            }
         }
     }
 
-    pub fn from_bytes<'a, T: Deserialize<'a>>(&self, bytes: &'a [u8]) -> anyhow::Result<T> {
+    pub fn from_bytes<T: DeserializeOwned>(&self, bytes: &[u8]) -> anyhow::Result<T> {
         match self.encoding() {
             Encoding::Json => serde_json::from_slice(bytes).map_err(|e| anyhow!("{:?}", e)),
-            Encoding::Bcs => bcs::from_bytes(bytes).map_err(|e| anyhow! {"{:?}", e}),
+            // The peer decompresses the messages of compressed protocols off the wire
+            Encoding::Bcs | Encoding::CompressedBcs => {
+                bcs::from_bytes(bytes).map_err(|e| anyhow! {"{:?}", e})
+            }
         }
     }
 }
@@ -215,14 +273,22 @@ impl<'a> FromIterator<&'a ProtocolId> for ProtocolIdSet {
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 pub enum MessagingProtocolVersion {
     V1 = 0,
+    /// V1 with the fragmentation of the messages larger than a frame.
+    V2 = 1,
 }
 
 impl MessagingProtocolVersion {
     fn as_str(&self) -> &str {
         match self {
             Self::V1 => "V1",
+            Self::V2 => "V2",
         }
     }
+
+    /// Returns true if messages larger than a frame can be sent as fragments.
+    pub fn supports_streaming(self) -> bool {
+        self >= Self::V2
+    }
 }
 
 impl fmt::Debug for MessagingProtocolVersion {
@@ -182,3 +182,85 @@ fn ignore_unknown_protocols() {
         ProtocolIdSet::empty(),
     );
 }
+
+#[test]
+fn compressed_protocol_negotiation() {
+    let old_peer = HandshakeMsg::from_supported(ProtocolIdSet::from_iter([
+        ProtocolId::MempoolDirectSend,
+        ProtocolId::StorageServiceRpc,
+    ]));
+    let new_peer = HandshakeMsg::from_supported(ProtocolIdSet::from_iter([
+        ProtocolId::MempoolDirectSend,
+        ProtocolId::MempoolDirectSendCompressed,
+        ProtocolId::StorageServiceRpc,
+        ProtocolId::StorageServiceRpcCompressed,
+    ]));
+
+    // Both peers support the compressed protocols: use them.
+    let (_, common_protos) = new_peer.perform_handshake(&new_peer).unwrap();
+    assert_eq!(
+        ProtocolId::MempoolDirectSend.preferred_for(&common_protos),
+        ProtocolId::MempoolDirectSendCompressed,
+    );
+    assert_eq!(
+        ProtocolId::StorageServiceRpc.preferred_for(&common_protos),
+        ProtocolId::StorageServiceRpcCompressed,
+    );
+
+    // One of the peers doesn't support them: fall back to the uncompressed protocols.
+    let (_, common_protos) = new_peer.perform_handshake(&old_peer).unwrap();
+    assert_eq!(
+        ProtocolId::MempoolDirectSend.preferred_for(&common_protos),
+        ProtocolId::MempoolDirectSend,
+    );
+    assert_eq!(
+        ProtocolId::StorageServiceRpc.preferred_for(&common_protos),
+        ProtocolId::StorageServiceRpc,
+    );
+
+    // Protocols without a compressed version are left as is.
+    assert_eq!(
+        ProtocolId::ConsensusRpcBcs.preferred_for(&ProtocolIdSet::all_known()),
+        ProtocolId::ConsensusRpcBcs,
+    );
+}
+
+#[test]
+fn compressed_encoding() {
+    let value: Vec<u64> = vec![7; 1000];
+    let protocol = ProtocolId::StateSyncDirectSend;
+    let compressed_protocol = protocol.compressed().unwrap();
+    assert!(compressed_protocol.is_compressed());
+    assert!(!protocol.is_compressed());
+    assert_eq!(compressed_protocol.uncompressed(), protocol);
+    assert_eq!(protocol.uncompressed(), protocol);
+
+    // The applications encode both protocols the same way, the peer compresses on the wire.
+    let bytes = protocol.to_bytes(&value).unwrap();
+    assert_eq!(compressed_protocol.to_bytes(&value).unwrap(), bytes);
+    assert_eq!(
+        compressed_protocol.from_bytes::<Vec<u64>>(&bytes).unwrap(),
+        value
+    );
+}
+
+#[test]
+fn streaming_negotiation() {
+    let protocols = ProtocolIdSet::from_iter([ProtocolId::MempoolDirectSend]);
+    let v1_peer = HandshakeMsg::from_supported(protocols.clone());
+    let mut v2_peer = HandshakeMsg::from_supported(protocols.clone());
+    v2_peer
+        .supported_protocols
+        .insert(MessagingProtocolVersion::V2, protocols);
+
+    let (version, _) = v2_peer.perform_handshake(&v2_peer).unwrap();
+    assert_eq!(version, MessagingProtocolVersion::V2);
+    assert!(version.supports_streaming());
+
+    // Fall back to V1, without fragmentation, with an older peer.
+    let (version, _) = v2_peer.perform_handshake(&v1_peer).unwrap();
+    assert_eq!(version, MessagingProtocolVersion::V1);
+    assert!(!version.supports_streaming());
+    let (version, _) = v1_peer.perform_handshake(&v2_peer).unwrap();
+    assert_eq!(version, MessagingProtocolVersion::V1);
+}
@@ -9,7 +9,10 @@
 //! describes in greater detail how these messages are sent and received
 //! over-the-wire.
 
-use crate::protocols::wire::handshake::v1::ProtocolId;
+use crate::protocols::{
+    stream::{StreamFragment, StreamHeader},
+    wire::handshake::v1::ProtocolId,
+};
 use aptos_rate_limiter::{async_lib::AsyncRateLimiter, rate_limit::SharedBucket};
 use bytes::Bytes;
 use futures::{
@@ -43,6 +46,10 @@ pub enum NetworkMessage {
     RpcRequest(RpcRequest),
     RpcResponse(RpcResponse),
     DirectSendMsg(DirectSendMsg),
+    /// Only sent over connections using `MessagingProtocolVersion::V2`, see
+    /// [`crate::protocols::stream`].
+    StreamHeader(StreamHeader),
+    StreamFragment(StreamFragment),
 }
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
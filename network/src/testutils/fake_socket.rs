@@ -13,6 +13,7 @@ use futures::{
     task::{Context, Poll},
 };
 use memsocket::MemorySocket;
+use netcore::transport::SecureChannel;
 use std::{io, pin::Pin};
 
 //
@@ -50,6 +51,8 @@ impl<'a> ReadOnlyTestSocket<'a> {
     }
 }
 
+impl<'a> SecureChannel for ReadOnlyTestSocket<'a> {}
+
 /// Does nothing, but looks to the caller as if write worked
 impl<'a> AsyncWrite for ReadOnlyTestSocket<'a> {
     fn poll_write(
@@ -144,6 +147,8 @@ impl ReadOnlyTestSocketVec {
     }
 }
 
+impl SecureChannel for ReadOnlyTestSocketVec {}
+
 /// Does nothing, but looks to the caller as if write worked
 impl AsyncWrite for ReadOnlyTestSocketVec {
     fn poll_write(
@@ -218,6 +223,8 @@ pub struct ReadWriteTestSocket<'a> {
     fragmented_read: bool,
     /// fragment writes byte-by-byte
     fragmented_write: bool,
+    /// the binding of the channel the socket pretends to be carried over
+    channel_binding: Option<[u8; 32]>,
 }
 
 impl<'a> ReadWriteTestSocket<'a> {
@@ -227,6 +234,7 @@ impl<'a> ReadWriteTestSocket<'a> {
             written: None,
             fragmented_read: false,
             fragmented_write: false,
+            channel_binding: None,
         }
     }
 
@@ -245,6 +253,11 @@ impl<'a> ReadWriteTestSocket<'a> {
         self.fragmented_write = true;
     }
 
+    /// the socket will be carried over a channel with this binding
+    pub fn set_channel_binding(&mut self, channel_binding: [u8; 32]) {
+        self.channel_binding = Some(channel_binding);
+    }
+
     /// Creates a new pair of sockets
     pub fn new_pair() -> (Self, Self) {
         let (dialer_socket, listener_socket) = MemorySocket::new_pair();
@@ -255,6 +268,12 @@ impl<'a> ReadWriteTestSocket<'a> {
     }
 }
 
+impl<'a> SecureChannel for ReadWriteTestSocket<'a> {
+    fn channel_binding(&self) -> Option<[u8; 32]> {
+        self.channel_binding
+    }
+}
+
 impl<'a> AsyncWrite for ReadWriteTestSocket<'a> {
     fn poll_write(
         mut self: Pin<&mut Self>,
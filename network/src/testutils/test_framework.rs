@@ -14,6 +14,7 @@ use aptos_config::{
     config::NodeConfig,
     network_id::{NetworkId, PeerNetworkId},
 };
+use aptos_time_service::TimeService;
 use channel::message_queues::QueueStyle;
 use std::{collections::HashMap, hash::Hash, sync::Arc, vec::Vec};
 
@@ -46,7 +47,7 @@ pub fn setup_node_networks<NetworkSender: NewNetworkSender, NetworkEvents: NewNe
     let mut inbound_handles = HashMap::new();
     let mut outbound_handles = HashMap::new();
 
-    let peer_metadata_storage = PeerMetadataStorage::new(network_ids);
+    let peer_metadata_storage = PeerMetadataStorage::new(network_ids, TimeService::real());
 
     // Build each individual network
     for network_id in network_ids {
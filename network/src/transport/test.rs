@@ -21,7 +21,7 @@ use bytes::{Bytes, BytesMut};
 use futures::{future, io::AsyncWriteExt, stream::StreamExt};
 use netcore::{
     framing::{read_u16frame, write_u16frame},
-    transport::{memory, ConnectionOrigin, Transport},
+    transport::{memory, quic::QuicTransport, ConnectionOrigin, Transport},
 };
 use rand::{rngs::StdRng, SeedableRng};
 use std::{collections::HashMap, io, iter::FromIterator, sync::Arc};
@@ -209,6 +209,16 @@ fn expect_ip4_tcp_noise_addr(addr: &NetworkAddress) {
     );
 }
 
+/// Check that the network address matches the format
+/// `"/ip4/<ipaddr>/quic/<port>/ln-noise-ik/<pubkey>/ln-handshake/<version>"`
+fn expect_ip4_quic_noise_addr(addr: &NetworkAddress) {
+    assert!(
+        matches!(addr.as_slice(), [Ip4(_), Quic(_), NoiseIK(_), Handshake(_)]),
+        "addr: '{}'",
+        addr
+    );
+}
+
 fn test_transport_success<TTransport>(
     base_transport: TTransport,
     auth: Auth,
@@ -251,7 +261,7 @@ fn test_transport_success<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Inbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(
             conn.metadata.application_protocols,
@@ -280,7 +290,7 @@ fn test_transport_success<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Outbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(conn.metadata.application_protocols, supported_protocols);
 
@@ -388,7 +398,7 @@ fn test_transport_maybe_mutual<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Inbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(
             conn.metadata.application_protocols,
@@ -422,7 +432,7 @@ fn test_transport_maybe_mutual<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Inbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(
             conn.metadata.application_protocols,
@@ -452,7 +462,7 @@ fn test_transport_maybe_mutual<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Outbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(conn.metadata.application_protocols, supported_protocols);
 
@@ -476,7 +486,7 @@ fn test_transport_maybe_mutual<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Outbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(conn.metadata.application_protocols, supported_protocols);
 
@@ -563,3 +573,36 @@ fn test_tcp_transport_rejects_unauthed_dialer() {
         expect_ip4_tcp_noise_addr,
     );
 }
+
+//////////////////////////////////////
+// AptosNetTransport<QuicTransport> //
+//////////////////////////////////////
+
+#[test]
+fn test_quic_transport_mutual_auth() {
+    test_transport_success(
+        QuicTransport::new().unwrap(),
+        Auth::Mutual,
+        "/ip4/127.0.0.1/quic/0",
+        expect_ip4_quic_noise_addr,
+    );
+}
+
+#[test]
+fn test_quic_transport_server_only_auth() {
+    test_transport_success(
+        QuicTransport::new().unwrap(),
+        Auth::ServerOnly,
+        "/ip4/127.0.0.1/quic/0",
+        expect_ip4_quic_noise_addr,
+    );
+}
+
+#[test]
+fn test_quic_transport_rejects_unauthed_dialer() {
+    test_transport_rejects_unauthed_dialer(
+        QuicTransport::new().unwrap(),
+        "/ip4/127.0.0.1/quic/0",
+        expect_ip4_quic_noise_addr,
+    );
+}
@@ -52,6 +52,7 @@ pub enum LogEntry {
 #[serde(rename_all = "snake_case")]
 pub enum LogEvent {
     AggregateSummary,
+    HedgeRequest,
     NoPeersToPoll,
     PeerIgnored,
     PeerNoLongerIgnored,
@@ -39,6 +39,16 @@ pub static ERROR_RESPONSES: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter for tracking hedged requests (i.e., requests also sent to a second peer)
+pub static HEDGED_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_data_client_hedged_requests",
+        "Counters related to hedged requests",
+        &["request_types"]
+    )
+    .unwrap()
+});
+
 /// Counter for tracking request latencies
 pub static REQUEST_LATENCIES: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
@@ -6,6 +6,7 @@ use crate::{
         logging::{LogEntry, LogEvent, LogSchema},
         metrics::{increment_counter, start_timer},
         state::{ErrorType, PeerStates},
+        validation::validate_response,
     },
     AptosDataClient, Error, GlobalDataSummary, Response, ResponseCallback, ResponseContext,
     ResponseError, ResponseId, Result,
@@ -23,18 +24,23 @@ use aptos_types::{
     ledger_info::LedgerInfoWithSignatures,
     state_store::state_value::StateValueChunkWithProof,
     transaction::{TransactionListWithProof, TransactionOutputListWithProof, Version},
+    PeerId,
 };
 use async_trait::async_trait;
-use futures::StreamExt;
+use futures::{
+    future::{self, Either},
+    StreamExt,
+};
 use network::{
-    application::interface::NetworkInterface,
+    application::{interface::NetworkInterface, reputation::Severity},
     protocols::{rpc::error::RpcError, wire::handshake::v1::ProtocolId},
 };
 use rand::seq::SliceRandom;
-use std::{convert::TryFrom, fmt, sync::Arc, time::Duration};
+use std::{cmp::Ordering, collections::HashSet, convert::TryFrom, fmt, sync::Arc, time::Duration};
 use storage_service_client::StorageServiceClient;
 use storage_service_types::{
-    AccountStatesChunkWithProofRequest, Epoch, EpochEndingLedgerInfoRequest, StorageServerSummary,
+    AccountStatesChunkWithProofRequest, Epoch, EpochEndingLedgerInfoRequest,
+    NewTransactionOutputsWithProofRequest, NewTransactionsWithProofRequest, StorageServerSummary,
     StorageServiceRequest, StorageServiceResponse, TransactionOutputsWithProofRequest,
     TransactionsWithProofRequest,
 };
@@ -44,6 +50,7 @@ mod metrics;
 mod state;
 #[cfg(test)]
 mod tests;
+mod validation;
 
 // Useful constants for the Aptos Data Client
 const GLOBAL_DATA_LOG_FREQ_SECS: u64 = 5;
@@ -59,8 +66,12 @@ const POLLER_ERROR_LOG_FREQ_SECS: u64 = 1;
 /// 3. Routes requests to peers that advertise availability for that data.
 /// 4. Maintains peer scores based on each peer's observed quality of service
 ///    and upper client reports of invalid or malicious data.
-/// 5. Selects high quality peers to send each request to.
+/// 5. Selects high quality peers to send each request to (weighted by their
+///    estimated bandwidth), and hedges slow requests by also sending them to
+///    a second peer.
 /// 6. Exposes a condensed data summary of our peers' data advertisements.
+/// 7. Optionally restricts all requests to a set of trusted peers (e.g., for
+///    nodes that sync from a trusted source).
 ///
 /// The client currently assumes 1-request => 1-response. Streaming responses
 /// are handled at an upper layer.
@@ -79,6 +90,10 @@ pub struct AptosNetDataClient {
     global_summary_cache: Arc<RwLock<GlobalDataSummary>>,
     /// Used for generating the next request/response id.
     response_id_generator: Arc<U64IdGenerator>,
+    /// Used to measure response times and to delay hedged requests.
+    time_service: TimeService,
+    /// The peers to exclusively send requests to (if empty, all peers are used).
+    trusted_peers: Arc<HashSet<PeerId>>,
 }
 
 impl AptosNetDataClient {
@@ -87,6 +102,7 @@ impl AptosNetDataClient {
         storage_service_config: StorageServiceConfig,
         time_service: TimeService,
         network_client: StorageServiceClient,
+        trusted_peers: Vec<PeerId>,
     ) -> (Self, DataSummaryPoller) {
         let client = Self {
             data_client_config,
@@ -94,6 +110,8 @@ impl AptosNetDataClient {
             peer_states: Arc::new(RwLock::new(PeerStates::new(storage_service_config))),
             global_summary_cache: Arc::new(RwLock::new(GlobalDataSummary::empty())),
             response_id_generator: Arc::new(U64IdGenerator::new()),
+            time_service: time_service.clone(),
+            trusted_peers: Arc::new(trusted_peers.into_iter().collect()),
         };
         let poller = DataSummaryPoller::new(
             time_service,
@@ -119,30 +137,58 @@ impl AptosNetDataClient {
         *self.global_summary_cache.write() = aggregate;
     }
 
-    /// Choose a connected peer that can service the given request. Returns an
-    /// error if no such peer can be found.
+    /// Choose a connected peer (that isn't excluded) that can service the given
+    /// request. Returns an error if no such peer can be found.
     fn choose_peer_for_request(
         &self,
         request: &StorageServiceRequest,
+        excluded_peers: &[PeerNetworkId],
     ) -> Result<PeerNetworkId, Error> {
         let all_connected_peers = self.get_all_connected_peers()?;
 
         // Identify the peers that can service this request
         let internal_peer_states = self.peer_states.read();
-        let serviceable_peers = all_connected_peers
+        let network_peer_metadata = self.network_client.peer_metadata_storage();
+        let mut serviceable_peers = all_connected_peers
             .into_iter()
+            .filter(|peer| !excluded_peers.contains(peer))
             .filter(|peer| internal_peer_states.can_service_request(peer, request))
+            .map(|peer| {
+                let monitoring_metadata = network_peer_metadata
+                    .read(peer)
+                    .map(|peer_info| peer_info.monitoring_metadata)
+                    .unwrap_or_default();
+                (peer, monitoring_metadata)
+            })
             .collect::<Vec<_>>();
+        if serviceable_peers.is_empty() {
+            return Err(Error::DataIsUnavailable(
+                "No connected peers are advertising that they can serve this data!".to_owned(),
+            ));
+        }
 
-        // Choose a random peer from those that can service the request
+        // Only consider the nearest half of the peers (i.e., the closest to the validators and
+        // with the lowest latency), to avoid sending requests to far away or slow peers.
         serviceable_peers
-            .choose(&mut rand::thread_rng())
-            .copied()
-            .ok_or_else(|| {
-                Error::DataIsUnavailable(
-                    "No connected peers are advertising that they can serve this data!".to_owned(),
-                )
-            })
+            .sort_by(|(_, metadata_a), (_, metadata_b)| metadata_a.cmp_proximity(metadata_b));
+        let median_metadata = serviceable_peers[(serviceable_peers.len() - 1) / 2]
+            .1
+            .clone();
+        let nearest_peers = serviceable_peers
+            .into_iter()
+            .filter(|(_, metadata)| metadata.cmp_proximity(&median_metadata) != Ordering::Greater)
+            .map(|(peer, _)| peer)
+            .collect::<Vec<_>>();
+
+        // Choose a peer from the nearest peers, weighted by their bandwidth estimates
+        let bandwidth_estimates = nearest_peers
+            .iter()
+            .map(|peer| internal_peer_states.bandwidth_estimate(peer))
+            .collect::<Vec<_>>();
+        Ok(choose_peer_by_bandwidth(
+            &nearest_peers,
+            &bandwidth_estimates,
+        ))
     }
 
     /// Fetches the next group of peers to poll. The group will contain: (i) the peer who was last
@@ -176,9 +222,10 @@ impl AptosNetDataClient {
             .networks()
             .flat_map(|network_id| {
                 network_peer_metadata
-                    .read_filtered(network_id, |(_, peer_metadata)| {
+                    .read_filtered(network_id, |(peer_id, peer_metadata)| {
                         peer_metadata.is_connected()
                             && peer_metadata.supports_protocol(ProtocolId::StorageServiceRpc)
+                            && self.is_trusted_peer(peer_id)
                     })
                     .into_keys()
             })
@@ -193,6 +240,12 @@ impl AptosNetDataClient {
         Ok(connected_peers)
     }
 
+    /// Returns true iff the given peer is trusted (i.e., no trusted peers
+    /// have been specified, or the peer is one of them).
+    fn is_trusted_peer(&self, peer_id: &PeerId) -> bool {
+        self.trusted_peers.is_empty() || self.trusted_peers.contains(peer_id)
+    }
+
     /// Sends a request (to an undecided peer) and decodes the response
     async fn send_request_and_decode<T, E>(
         &self,
@@ -202,17 +255,77 @@ impl AptosNetDataClient {
         T: TryFrom<StorageServiceResponse, Error = E>,
         E: Into<Error>,
     {
-        let peer = self.choose_peer_for_request(&request).map_err(|error| {
-            error!(
-                (LogSchema::new(LogEntry::StorageServiceRequest)
-                    .event(LogEvent::PeerSelectionError)
-                    .message("Unable to select next peer")
-                    .error(&error))
-            );
-            error
-        })?;
+        let peer = self
+            .choose_peer_for_request(&request, &[])
+            .map_err(|error| {
+                error!(
+                    (LogSchema::new(LogEntry::StorageServiceRequest)
+                        .event(LogEvent::PeerSelectionError)
+                        .message("Unable to select next peer")
+                        .error(&error))
+                );
+                error
+            })?;
         let _timer = start_timer(&metrics::REQUEST_LATENCIES, request.get_label().into());
-        self.send_request_to_peer_and_decode(peer, request).await
+
+        // Optimistic fetches are expected to take a long time, so we never hedge them
+        if self.data_client_config.enable_request_hedging && !request.is_optimistic_fetch_request()
+        {
+            self.send_hedged_request_and_decode(peer, request).await
+        } else {
+            self.send_request_to_peer_and_decode(peer, request).await
+        }
+    }
+
+    /// Sends a request to the given peer and decodes the response. If the peer
+    /// doesn't respond within the hedging delay, the request is also sent to a
+    /// second peer and the first successful response is returned.
+    async fn send_hedged_request_and_decode<T, E>(
+        &self,
+        peer: PeerNetworkId,
+        request: StorageServiceRequest,
+    ) -> Result<Response<T>>
+    where
+        T: TryFrom<StorageServiceResponse, Error = E>,
+        E: Into<Error>,
+    {
+        let primary_request = self.send_request_to_peer_and_decode(peer, request.clone());
+        futures::pin_mut!(primary_request);
+
+        // Wait for the primary peer to respond (up to the hedging delay)
+        let hedging_delay = self.time_service.sleep(Duration::from_millis(
+            self.data_client_config.hedged_request_delay_ms,
+        ));
+        futures::pin_mut!(hedging_delay);
+        if let Either::Left((result, _)) =
+            future::select(primary_request.as_mut(), hedging_delay).await
+        {
+            return result;
+        }
+
+        // The primary peer is slow, so also send the request to another peer (if any)
+        let hedged_peer = match self.choose_peer_for_request(&request, &[peer]) {
+            Ok(hedged_peer) => hedged_peer,
+            Err(_) => return primary_request.await,
+        };
+        debug!(
+            (LogSchema::new(LogEntry::StorageServiceRequest)
+                .event(LogEvent::HedgeRequest)
+                .request_type(request.get_label())
+                .message(&format!("Primary peer {} is slow to respond", peer))
+                .peer(&hedged_peer))
+        );
+        increment_counter(&metrics::HEDGED_REQUESTS, request.get_label().into());
+        let hedged_request = self.send_request_to_peer_and_decode(hedged_peer, request);
+        futures::pin_mut!(hedged_request);
+
+        // Return the first successful response (or the error of the last one)
+        match future::select(primary_request, hedged_request).await {
+            Either::Left((Ok(response), _)) | Either::Right((Ok(response), _)) => Ok(response),
+            Either::Left((Err(_), pending_request)) | Either::Right((Err(_), pending_request)) => {
+                pending_request.await
+            }
+        }
     }
 
     /// Sends a request to a specific peer and decodes the response
@@ -261,17 +374,26 @@ impl AptosNetDataClient {
 
         increment_counter(&metrics::SENT_REQUESTS, request.get_label().into());
 
+        // Optimistic fetches are held by the peer until new data is available,
+        // so we need to wait longer for a response.
+        let timeout_ms = if request.is_optimistic_fetch_request() {
+            self.data_client_config.optimistic_fetch_timeout_ms
+        } else {
+            self.data_client_config.response_timeout_ms
+        };
+        let request_start_time = self.time_service.now();
         let result = self
             .network_client
-            .send_request(
-                peer,
-                request.clone(),
-                Duration::from_millis(self.data_client_config.response_timeout_ms),
-            )
-            .await;
+            .send_request(peer, request.clone(), Duration::from_millis(timeout_ms))
+            .await
+            .map_err(convert_network_error)
+            .and_then(|(response, num_bytes)| {
+                // Reject responses with an invalid shape or size early
+                validate_response(&request, &response).map(|()| (response, num_bytes))
+            });
 
         match result {
-            Ok(response) => {
+            Ok((response, num_bytes)) => {
                 debug!(
                     (LogSchema::new(LogEntry::StorageServiceResponse)
                         .event(LogEvent::ResponseSuccess)
@@ -282,6 +404,17 @@ impl AptosNetDataClient {
 
                 increment_counter(&metrics::SUCCESS_RESPONSES, request.get_label().into());
 
+                // Update the bandwidth estimate of the peer. Optimistic fetches
+                // are held by the peer, so their response times are meaningless.
+                if !request.is_optimistic_fetch_request() {
+                    let response_time = self.time_service.now().duration_since(request_start_time);
+                    self.peer_states.write().update_bandwidth_estimate(
+                        peer,
+                        num_bytes as u64,
+                        response_time,
+                    );
+                }
+
                 // For now, record all responses that at least pass the data
                 // client layer successfully. An alternative might also have the
                 // consumer notify both success and failure via the callback.
@@ -304,21 +437,7 @@ impl AptosNetDataClient {
                 };
                 Ok(Response::new(context, response))
             }
-            Err(err) => {
-                // Convert network error and storage service error types into
-                // data client errors. Also categorize the error type for scoring
-                // purposes.
-                let client_err = match err {
-                    storage_service_client::Error::RpcError(err) => match err {
-                        RpcError::NotConnected(_) => Error::DataIsUnavailable(err.to_string()),
-                        RpcError::TimedOut => Error::TimeoutWaitingForResponse(err.to_string()),
-                        _ => Error::UnexpectedErrorEncountered(err.to_string()),
-                    },
-                    storage_service_client::Error::StorageServiceError(err) => {
-                        Error::UnexpectedErrorEncountered(err.to_string())
-                    }
-                };
-
+            Err(client_err) => {
                 error!(
                     (LogSchema::new(LogEntry::StorageServiceResponse)
                         .event(LogEvent::ResponseError)
@@ -330,12 +449,43 @@ impl AptosNetDataClient {
 
                 increment_counter(&metrics::ERROR_RESPONSES, request.get_label().into());
 
-                self.notify_bad_response(id, peer, &request, ErrorType::NotUseful);
+                // Invalid responses are always penalized. Optimistic fetches are
+                // otherwise expected to fail if the peer doesn't receive new data
+                // before the fetch expires, so we don't penalize the peer for these.
+                if matches!(client_err, Error::InvalidResponse(_)) {
+                    self.report_misbehavior(
+                        peer,
+                        Severity::High,
+                        "invalid storage service response",
+                    );
+                    self.notify_bad_response(id, peer, &request, ErrorType::NotUseful);
+                } else if !request.is_optimistic_fetch_request() {
+                    // Timeouts and disconnects can be caused by the local node (e.g., a slow
+                    // or flaky connection), so they only affect the peer selection.
+                    if !matches!(
+                        client_err,
+                        Error::TimeoutWaitingForResponse(_) | Error::DataIsUnavailable(_)
+                    ) {
+                        self.report_misbehavior(
+                            peer,
+                            Severity::Low,
+                            "failed storage service request",
+                        );
+                    }
+                    self.notify_bad_response(id, peer, &request, ErrorType::NotUseful);
+                }
                 Err(client_err)
             }
         }
     }
 
+    /// Reports the misbehavior of a peer to the network, which bans the misbehaving peers
+    fn report_misbehavior(&self, peer: PeerNetworkId, severity: Severity, reason: &str) {
+        self.network_client
+            .peer_metadata_storage()
+            .report_misbehavior(peer, severity, reason);
+    }
+
     /// Updates the score of the peer who sent the response with the specified id
     fn notify_bad_response(
         &self,
@@ -386,6 +536,35 @@ impl AptosDataClient for AptosNetDataClient {
         Ok(response.map(|epoch_change| epoch_change.ledger_info_with_sigs))
     }
 
+    async fn get_new_transaction_outputs_with_proof(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+    ) -> Result<Response<(TransactionOutputListWithProof, LedgerInfoWithSignatures)>> {
+        let request = StorageServiceRequest::GetNewTransactionOutputsWithProof(
+            NewTransactionOutputsWithProofRequest {
+                known_version,
+                known_epoch,
+            },
+        );
+        self.send_request_and_decode(request).await
+    }
+
+    async fn get_new_transactions_with_proof(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+        include_events: bool,
+    ) -> Result<Response<(TransactionListWithProof, LedgerInfoWithSignatures)>> {
+        let request =
+            StorageServiceRequest::GetNewTransactionsWithProof(NewTransactionsWithProofRequest {
+                known_version,
+                known_epoch,
+                include_events,
+            });
+        self.send_request_and_decode(request).await
+    }
+
     async fn get_number_of_account_states(&self, version: Version) -> Result<Response<u64>> {
         let request = StorageServiceRequest::GetNumberOfAccountsAtVersion(version);
         self.send_request_and_decode(request).await
@@ -425,6 +604,47 @@ impl AptosDataClient for AptosNetDataClient {
     }
 }
 
+/// Converts network and storage service errors into data client errors
+fn convert_network_error(error: storage_service_client::Error) -> Error {
+    match error {
+        storage_service_client::Error::RpcError(error) => match error {
+            RpcError::NotConnected(_) => Error::DataIsUnavailable(error.to_string()),
+            RpcError::TimedOut => Error::TimeoutWaitingForResponse(error.to_string()),
+            _ => Error::UnexpectedErrorEncountered(error.to_string()),
+        },
+        storage_service_client::Error::StorageServiceError(error) => {
+            Error::UnexpectedErrorEncountered(error.to_string())
+        }
+    }
+}
+
+/// Chooses a peer at random, weighted by the bandwidth estimates of the peers.
+/// Peers without an estimate are given the average estimate (so that they're
+/// still explored). If no estimates exist, the peer is chosen uniformly.
+fn choose_peer_by_bandwidth(
+    peers: &[PeerNetworkId],
+    bandwidth_estimates: &[Option<f64>],
+) -> PeerNetworkId {
+    let known_estimates = bandwidth_estimates.iter().flatten().collect::<Vec<_>>();
+    let default_estimate = if known_estimates.is_empty() {
+        1.0
+    } else {
+        known_estimates.iter().copied().sum::<f64>() / known_estimates.len() as f64
+    };
+
+    let weighted_peers = peers
+        .iter()
+        .zip(bandwidth_estimates)
+        .map(|(peer, estimate)| (*peer, estimate.unwrap_or(default_estimate)))
+        .collect::<Vec<_>>();
+    match weighted_peers.choose_weighted(&mut rand::thread_rng(), |(_, weight)| *weight) {
+        Ok((peer, _)) => *peer,
+        Err(_) => *peers
+            .choose(&mut rand::thread_rng())
+            .expect("There should be at least one peer to choose from"),
+    }
+}
+
 /// The AptosNet-specific request context needed to update a peer's scoring.
 struct AptosNetResponseCallback {
     data_client: AptosNetDataClient,
@@ -435,6 +655,19 @@ struct AptosNetResponseCallback {
 
 impl ResponseCallback for AptosNetResponseCallback {
     fn notify_bad_response(&self, error: ResponseError) {
+        let (severity, reason) = match error {
+            ResponseError::InvalidData => (Severity::High, "invalid storage service data"),
+            ResponseError::InvalidPayloadDataType => {
+                (Severity::High, "unexpected storage service payload")
+            }
+            // Honest peers can serve proofs we fail to verify, e.g., across an epoch change
+            ResponseError::ProofVerificationError => {
+                (Severity::Low, "invalid storage service proof")
+            }
+        };
+        self.data_client
+            .report_misbehavior(self.peer, severity, reason);
+
         let error_type = ErrorType::from(error);
         self.data_client
             .notify_bad_response(self.id, self.peer, &self.request, error_type);
@@ -7,7 +7,10 @@ use crate::{
 };
 use aptos_config::{config::StorageServiceConfig, network_id::PeerNetworkId};
 use aptos_logger::debug;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
 use storage_service_types::{StorageServerSummary, StorageServiceRequest};
 
 /// Scores for peer rankings based on preferences and behavior.
@@ -22,6 +25,8 @@ const NOT_USEFUL_MULTIPLIER: f64 = 0.95;
 const MALICIOUS_MULTIPLIER: f64 = 0.8;
 /// Ignore a peer when their score dips below this threshold.
 const IGNORE_PEER_THRESHOLD: f64 = 25.0;
+/// The weight of each new sample in a peer's (moving average) bandwidth estimate.
+const BANDWIDTH_SAMPLE_WEIGHT: f64 = 0.2;
 
 pub(crate) enum ErrorType {
     /// A response or error that's not actively malicious but also doesn't help
@@ -50,6 +55,9 @@ struct PeerState {
     storage_summary: Option<StorageServerSummary>,
     /// For now, a simplified port of the original state-sync v1 scoring system.
     score: f64,
+    /// The estimated bandwidth (in bytes per second) of the peer, or `None`
+    /// if we haven't received any data from them yet.
+    bandwidth_estimate: Option<f64>,
 }
 
 impl Default for PeerState {
@@ -57,6 +65,7 @@ impl Default for PeerState {
         Self {
             storage_summary: None,
             score: STARTING_SCORE,
+            bandwidth_estimate: None,
         }
     }
 }
@@ -89,6 +98,19 @@ impl PeerState {
         };
         self.score = f64::max(self.score * multiplier, MIN_SCORE);
     }
+
+    /// Updates the bandwidth estimate of the peer using the given sample
+    fn update_bandwidth_estimate(&mut self, num_bytes: u64, duration: Duration) {
+        // Avoid infinite estimates for responses that arrive instantly
+        let duration_secs = f64::max(duration.as_secs_f64(), 0.001);
+        let sample = num_bytes as f64 / duration_secs;
+        self.bandwidth_estimate = Some(match self.bandwidth_estimate {
+            Some(estimate) => {
+                (BANDWIDTH_SAMPLE_WEIGHT * sample) + ((1.0 - BANDWIDTH_SAMPLE_WEIGHT) * estimate)
+            }
+            None => sample,
+        });
+    }
 }
 
 /// Contains all of the unbanned peers' most recent [`StorageServerSummary`] data
@@ -169,6 +191,27 @@ impl PeerStates {
         }
     }
 
+    /// Updates the bandwidth estimate of the peer using the number of bytes
+    /// received in a response and the time taken to receive it.
+    pub fn update_bandwidth_estimate(
+        &mut self,
+        peer: PeerNetworkId,
+        num_bytes: u64,
+        duration: Duration,
+    ) {
+        self.peer_to_state
+            .entry(peer)
+            .or_default()
+            .update_bandwidth_estimate(num_bytes, duration);
+    }
+
+    /// Returns the bandwidth estimate (in bytes per second) of the given peer
+    pub fn bandwidth_estimate(&self, peer: &PeerNetworkId) -> Option<f64> {
+        self.peer_to_state
+            .get(peer)
+            .and_then(|peer_state| peer_state.bandwidth_estimate)
+    }
+
     /// Marks the given peer as polled
     pub fn add_polled_peer(&mut self, peer: PeerNetworkId) {
         self.polled_peer_queue.push_front(peer);
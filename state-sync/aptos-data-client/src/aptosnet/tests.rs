@@ -11,7 +11,8 @@ use aptos_time_service::{MockTimeService, TimeService};
 use aptos_types::{
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-    transaction::{TransactionListWithProof, Version},
+    proof::TransactionInfoListWithProof,
+    transaction::{Transaction, TransactionListWithProof, Version},
     PeerId,
 };
 use channel::{aptos_channel, message_queues::QueueStyle};
@@ -21,16 +22,16 @@ use maplit::hashmap;
 use network::{
     application::{interface::MultiNetworkSender, storage::PeerMetadataStorage},
     peer_manager::{ConnectionRequestSender, PeerManagerRequest, PeerManagerRequestSender},
-    protocols::{network::NewNetworkSender, wire::handshake::v1::ProtocolId},
+    protocols::{network::NewNetworkSender, rpc::error::RpcError, wire::handshake::v1::ProtocolId},
     transport::ConnectionMetadata,
 };
 use std::{collections::BTreeMap, sync::Arc, time::Duration};
 use storage_service_client::{StorageServiceClient, StorageServiceNetworkSender};
 use storage_service_server::network::{NetworkRequest, ResponseSender};
 use storage_service_types::{
-    CompleteDataRange, DataSummary, ProtocolMetadata, StorageServerSummary, StorageServiceError,
-    StorageServiceMessage, StorageServiceRequest, StorageServiceResponse,
-    TransactionsWithProofRequest,
+    CompleteDataRange, DataSummary, NewTransactionsWithProofRequest, ProtocolMetadata,
+    StorageServerSummary, StorageServiceError, StorageServiceMessage, StorageServiceRequest,
+    StorageServiceResponse, TransactionsWithProofRequest,
 };
 
 fn mock_ledger_info(version: Version) -> LedgerInfoWithSignatures {
@@ -68,6 +69,12 @@ struct MockNetwork {
 
 impl MockNetwork {
     fn new() -> (Self, MockTimeService, AptosNetDataClient, DataSummaryPoller) {
+        Self::new_with_trusted_peers(vec![])
+    }
+
+    fn new_with_trusted_peers(
+        trusted_peers: Vec<PeerId>,
+    ) -> (Self, MockTimeService, AptosNetDataClient, DataSummaryPoller) {
         let queue_cfg = aptos_channel::Config::new(10).queue_style(QueueStyle::FIFO);
         let (peer_mgr_reqs_tx, peer_mgr_reqs_rx) = queue_cfg.build();
         let (connection_reqs_tx, _connection_reqs_rx) = queue_cfg.build();
@@ -79,15 +86,16 @@ impl MockNetwork {
             )
         });
 
-        let peer_infos = PeerMetadataStorage::new(&[NetworkId::Validator]);
+        let mock_time = TimeService::mock();
+        let peer_infos = PeerMetadataStorage::new(&[NetworkId::Validator], mock_time.clone());
         let network_client = StorageServiceClient::new(network_sender, peer_infos.clone());
 
-        let mock_time = TimeService::mock();
         let (client, poller) = AptosNetDataClient::new(
             AptosDataClientConfig::default(),
             StorageServiceConfig::default(),
             mock_time.clone(),
             network_client,
+            trusted_peers,
         );
 
         let mock_network = Self {
@@ -99,8 +107,12 @@ impl MockNetwork {
 
     /// Add a new random connected peer to the network peer DB
     fn add_connected_peer(&mut self) -> PeerNetworkId {
+        self.add_connected_peer_with_id(PeerId::random())
+    }
+
+    /// Add a new connected peer (with the given peer id) to the network peer DB
+    fn add_connected_peer_with_id(&mut self, peer_id: PeerId) -> PeerNetworkId {
         let network_id = NetworkId::Validator;
-        let peer_id = PeerId::random();
         let mut connection_metadata = ConnectionMetadata::mock(peer_id);
         connection_metadata
             .application_protocols
@@ -124,7 +136,7 @@ impl MockNetwork {
                     StorageServiceMessage::Request(request) => request,
                     _ => panic!("unexpected: {:?}", message),
                 };
-                let response_sender = ResponseSender::new(res_tx);
+                let response_sender = ResponseSender::new(protocol, res_tx);
 
                 Some((peer_id, protocol, request, response_sender))
             }
@@ -132,6 +144,16 @@ impl MockNetwork {
             None => None,
         }
     }
+
+    /// Fail the next request sent from the client with the given network error.
+    async fn fail_next_request(&mut self, error: RpcError) {
+        match self.peer_mgr_reqs_rx.next().await {
+            Some(PeerManagerRequest::SendRpc(_, network_request)) => {
+                let _ = network_request.res_tx.send(Err(error));
+            }
+            _ => panic!("Expected an rpc request"),
+        }
+    }
 }
 
 #[tokio::test]
@@ -279,6 +301,107 @@ async fn fetch_peers_to_poll() {
 //    bad peer) should lower bad peer's score
 // 4. eventually bad peer score should hit threshold and we err with no available
 
+#[tokio::test]
+async fn failed_optimistic_fetches_are_not_penalized() {
+    ::aptos_logger::Logger::init_for_testing();
+    let (mut mock_network, _, client, _) = MockNetwork::new();
+
+    let peer = mock_network.add_connected_peer();
+
+    // Bypass poller and just add the storage summary directly.
+    client.update_summary(peer, mock_storage_summary(200));
+    client.update_global_summary_cache();
+
+    // Spawn a handler for the peer that fails the first optimistic fetches
+    // (e.g., because they expired) before responding with new data.
+    let num_failed_fetches = 20;
+    tokio::spawn(async move {
+        let mut num_received_fetches = 0;
+        while let Some((_, _, request, response_sender)) = mock_network.next_request().await {
+            assert_matches!(
+                request,
+                StorageServiceRequest::GetNewTransactionsWithProof(
+                    NewTransactionsWithProofRequest {
+                        known_version: 200,
+                        known_epoch: 0,
+                        include_events: false,
+                    }
+                )
+            );
+
+            num_received_fetches += 1;
+            if num_received_fetches <= num_failed_fetches {
+                response_sender.send(Err(StorageServiceError::InternalError("".to_string())));
+            } else {
+                response_sender.send(Ok(StorageServiceResponse::NewTransactionsWithProof((
+                    TransactionListWithProof::new_empty(),
+                    mock_ledger_info(250),
+                ))));
+            }
+        }
+    });
+
+    // The failed optimistic fetches should not cause the peer to be ignored
+    for _ in 0..num_failed_fetches {
+        let result = client.get_new_transactions_with_proof(200, 0, false).await;
+        assert_matches!(result, Err(Error::UnexpectedErrorEncountered(_)));
+    }
+
+    // The peer should still be selected to service the next optimistic fetch
+    let response = client
+        .get_new_transactions_with_proof(200, 0, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        response.payload,
+        (TransactionListWithProof::new_empty(), mock_ledger_info(250))
+    );
+}
+
+#[tokio::test]
+async fn timeouts_and_disconnects_are_not_misbehavior() {
+    ::aptos_logger::Logger::init_for_testing();
+    let (mut mock_network, _, client, _) = MockNetwork::new();
+
+    let peer = mock_network.add_connected_peer();
+    let peer_infos = mock_network.peer_infos.clone();
+    client.update_summary(peer, mock_storage_summary(200));
+    client.update_global_summary_cache();
+
+    // Fail a request with each network error and verify the error seen by the client
+    let network_errors = vec![
+        RpcError::TimedOut,
+        RpcError::NotConnected(peer.peer_id()),
+        RpcError::UnexpectedResponseChannelCancel,
+    ];
+    for network_error in network_errors {
+        let request_client = client.clone();
+        let request_handle = tokio::spawn(async move {
+            request_client
+                .get_transactions_with_proof(100, 50, 100, false)
+                .await
+        });
+        let is_local_failure = matches!(
+            network_error,
+            RpcError::TimedOut | RpcError::NotConnected(_)
+        );
+        mock_network.fail_next_request(network_error).await;
+        let result = request_handle.await.unwrap();
+
+        // Only the failures that aren't caused by the local node are scored
+        if is_local_failure {
+            assert!(matches!(
+                result,
+                Err(Error::TimeoutWaitingForResponse(_)) | Err(Error::DataIsUnavailable(_))
+            ));
+            assert_eq!(peer_infos.reputation().score(peer), 0.0);
+        } else {
+            assert_matches!(result, Err(Error::UnexpectedErrorEncountered(_)));
+            assert!(peer_infos.reputation().score(peer) > 0.0);
+        }
+    }
+}
+
 #[tokio::test]
 async fn bad_peer_is_eventually_banned_internal() {
     ::aptos_logger::Logger::init_for_testing();
@@ -421,6 +544,46 @@ async fn bad_peer_is_eventually_banned_callback() {
         .contains(&CompleteDataRange::new(0, 200).unwrap()));
 }
 
+#[tokio::test]
+async fn proof_verification_failures_are_low_severity() {
+    ::aptos_logger::Logger::init_for_testing();
+    let (mut mock_network, _, client, _) = MockNetwork::new();
+
+    let peer = mock_network.add_connected_peer();
+    let peer_infos = mock_network.peer_infos.clone();
+    client.update_summary(peer, mock_storage_summary(200));
+    client.update_global_summary_cache();
+
+    tokio::spawn(async move {
+        while let Some((_, _, _, response_sender)) = mock_network.next_request().await {
+            response_sender.send(Ok(StorageServiceResponse::TransactionsWithProof(
+                TransactionListWithProof::new_empty(),
+            )));
+        }
+    });
+
+    // Honest peers can serve proofs we fail to verify, so they are penalized less than
+    // invalid data.
+    let mut penalties = vec![];
+    for error in [
+        crate::ResponseError::ProofVerificationError,
+        crate::ResponseError::InvalidData,
+    ] {
+        let response = client
+            .get_transactions_with_proof(200, 0, 200, false)
+            .await
+            .unwrap();
+        let score = peer_infos.reputation().score(peer);
+        response
+            .context
+            .response_callback
+            .notify_bad_response(error);
+        penalties.push(peer_infos.reputation().score(peer) - score);
+    }
+    assert!(penalties[0] > 0.0);
+    assert!(penalties[0] < penalties[1]);
+}
+
 #[tokio::test]
 async fn bad_peer_is_eventually_added_back() {
     ::aptos_logger::Logger::init_for_testing();
@@ -491,3 +654,113 @@ async fn bad_peer_is_eventually_added_back() {
         .transactions
         .contains(&CompleteDataRange::new(0, 200).unwrap()));
 }
+
+#[tokio::test]
+async fn slow_requests_are_hedged() {
+    ::aptos_logger::Logger::init_for_testing();
+    let (mut mock_network, mock_time, client, _) = MockNetwork::new();
+
+    // Add two connected peers that both advertise the same data
+    let peer_1 = mock_network.add_connected_peer();
+    let peer_2 = mock_network.add_connected_peer();
+    client.update_summary(peer_1, mock_storage_summary(200));
+    client.update_summary(peer_2, mock_storage_summary(200));
+    client.update_global_summary_cache();
+
+    // Send a transactions request
+    let request_client = client.clone();
+    let request_handle = tokio::spawn(async move {
+        request_client
+            .get_transactions_with_proof(100, 50, 100, false)
+            .await
+    });
+
+    // Receive the request but don't respond (the primary peer is slow)
+    let (primary_peer, _, _, _primary_response_sender) = mock_network.next_request().await.unwrap();
+
+    // Elapse the hedging delay and verify the request is sent to the other peer
+    let hedged_request_delay_ms = AptosDataClientConfig::default().hedged_request_delay_ms;
+    mock_time
+        .advance_async(Duration::from_millis(hedged_request_delay_ms))
+        .await;
+    let (hedged_peer, _, request, response_sender) = mock_network.next_request().await.unwrap();
+    assert_ne!(hedged_peer, primary_peer);
+    assert_matches!(request, StorageServiceRequest::GetTransactionsWithProof(_));
+
+    // Respond from the hedged peer and verify the client receives the response
+    response_sender.send(Ok(StorageServiceResponse::TransactionsWithProof(
+        TransactionListWithProof::new_empty(),
+    )));
+    let response = request_handle.await.unwrap().unwrap();
+    assert_eq!(response.payload, TransactionListWithProof::new_empty());
+}
+
+#[tokio::test]
+async fn invalid_responses_are_rejected() {
+    ::aptos_logger::Logger::init_for_testing();
+    let (mut mock_network, _, client, _) = MockNetwork::new();
+
+    let peer = mock_network.add_connected_peer();
+    client.update_summary(peer, mock_storage_summary(200));
+    client.update_global_summary_cache();
+
+    // Spawn a handler for the peer that responds with transactions that
+    // don't start at the requested version.
+    tokio::spawn(async move {
+        while let Some((_, _, _, response_sender)) = mock_network.next_request().await {
+            let transaction_list_with_proof = TransactionListWithProof::new(
+                vec![Transaction::StateCheckpoint],
+                None,
+                Some(10),
+                TransactionInfoListWithProof::new_empty(),
+            );
+            response_sender.send(Ok(StorageServiceResponse::TransactionsWithProof(
+                transaction_list_with_proof,
+            )));
+        }
+    });
+
+    // The invalid response should be rejected by the client
+    let result = client
+        .get_transactions_with_proof(100, 50, 100, false)
+        .await;
+    assert_matches!(result, Err(Error::InvalidResponse(_)));
+}
+
+#[tokio::test]
+async fn only_trusted_peers_are_used() {
+    ::aptos_logger::Logger::init_for_testing();
+    let trusted_peer_id = PeerId::random();
+    let (mut mock_network, _, client, _) =
+        MockNetwork::new_with_trusted_peers(vec![trusted_peer_id]);
+
+    // Add an untrusted peer that can service the request
+    let untrusted_peer = mock_network.add_connected_peer();
+    client.update_summary(untrusted_peer, mock_storage_summary(200));
+    client.update_global_summary_cache();
+
+    // The untrusted peer should never be chosen
+    let request = StorageServiceRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+        start_version: 50,
+        end_version: 100,
+        proof_version: 100,
+        include_events: false,
+    });
+    assert_matches!(
+        client.choose_peer_for_request(&request, &[]),
+        Err(Error::DataIsUnavailable(_))
+    );
+
+    // Add the trusted peer and verify it's the only one polled and chosen
+    let trusted_peer = mock_network.add_connected_peer_with_id(trusted_peer_id);
+    client.update_summary(trusted_peer, mock_storage_summary(200));
+    client.update_global_summary_cache();
+    assert_eq!(
+        client.get_all_connected_peers().unwrap(),
+        vec![trusted_peer]
+    );
+    assert_eq!(
+        client.choose_peer_for_request(&request, &[]),
+        Ok(trusted_peer)
+    );
+}
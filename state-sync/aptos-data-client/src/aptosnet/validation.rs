@@ -0,0 +1,244 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Error;
+use aptos_types::{
+    state_store::state_value::StateValueChunkWithProof,
+    transaction::{TransactionListWithProof, TransactionOutputListWithProof, Version},
+};
+use storage_service_types::{StorageServiceRequest, StorageServiceResponse};
+
+/// Performs cheap sanity checks on the shape and size of a response (e.g., the
+/// response type matches the request and the data starts at the requested
+/// index and doesn't exceed the requested range). This allows obviously invalid
+/// responses to be rejected before they are handed to the upper layers. Note:
+/// empty data lists are not rejected here, and no proofs are verified.
+pub(crate) fn validate_response(
+    request: &StorageServiceRequest,
+    response: &StorageServiceResponse,
+) -> Result<(), Error> {
+    match (request, response) {
+        (
+            StorageServiceRequest::GetAccountStatesChunkWithProof(request),
+            StorageServiceResponse::AccountStatesChunkWithProof(account_states_chunk),
+        ) => validate_account_states_chunk(
+            request.start_account_index,
+            request.end_account_index,
+            account_states_chunk,
+        ),
+        (
+            StorageServiceRequest::GetEpochEndingLedgerInfos(request),
+            StorageServiceResponse::EpochEndingLedgerInfos(epoch_change_proof),
+        ) => {
+            let max_num_ledger_infos =
+                get_max_num_items(request.start_epoch, request.expected_end_epoch)?;
+            let ledger_infos = &epoch_change_proof.ledger_info_with_sigs;
+            verify_num_items(ledger_infos.len(), max_num_ledger_infos)?;
+            for (expected_epoch, ledger_info) in (request.start_epoch..).zip(ledger_infos) {
+                let epoch = ledger_info.ledger_info().epoch();
+                if epoch != expected_epoch {
+                    return Err(Error::InvalidResponse(format!(
+                        "Epoch ending ledger infos are not contiguous! Expected epoch: {:?}, \
+                        found epoch: {:?}",
+                        expected_epoch, epoch
+                    )));
+                }
+            }
+            Ok(())
+        }
+        (
+            StorageServiceRequest::GetNewTransactionOutputsWithProof(request),
+            StorageServiceResponse::NewTransactionOutputsWithProof((
+                output_list_with_proof,
+                ledger_info,
+            )),
+        ) => {
+            let (start_version, end_version) =
+                get_new_data_range(request.known_version, ledger_info.ledger_info().version())?;
+            validate_output_list(start_version, end_version, output_list_with_proof)
+        }
+        (
+            StorageServiceRequest::GetNewTransactionsWithProof(request),
+            StorageServiceResponse::NewTransactionsWithProof((
+                transaction_list_with_proof,
+                ledger_info,
+            )),
+        ) => {
+            let (start_version, end_version) =
+                get_new_data_range(request.known_version, ledger_info.ledger_info().version())?;
+            validate_transaction_list(
+                start_version,
+                end_version,
+                request.include_events,
+                transaction_list_with_proof,
+            )
+        }
+        (
+            StorageServiceRequest::GetNumberOfAccountsAtVersion(_),
+            StorageServiceResponse::NumberOfAccountsAtVersion(_),
+        )
+        | (
+            StorageServiceRequest::GetServerProtocolVersion,
+            StorageServiceResponse::ServerProtocolVersion(_),
+        )
+        | (
+            StorageServiceRequest::GetStorageServerSummary,
+            StorageServiceResponse::StorageServerSummary(_),
+        ) => Ok(()),
+        (
+            StorageServiceRequest::GetTransactionOutputsWithProof(request),
+            StorageServiceResponse::TransactionOutputsWithProof(output_list_with_proof),
+        ) => validate_output_list(
+            request.start_version,
+            request.end_version,
+            output_list_with_proof,
+        ),
+        (
+            StorageServiceRequest::GetTransactionsWithProof(request),
+            StorageServiceResponse::TransactionsWithProof(transaction_list_with_proof),
+        ) => validate_transaction_list(
+            request.start_version,
+            request.end_version,
+            request.include_events,
+            transaction_list_with_proof,
+        ),
+        (request, response) => Err(Error::InvalidResponse(format!(
+            "The response type does not match the request! Request: {:?}, response type: {:?}",
+            request.get_label(),
+            response.get_label()
+        ))),
+    }
+}
+
+/// Validates an account states chunk against the requested index range
+fn validate_account_states_chunk(
+    start_index: u64,
+    end_index: u64,
+    account_states_chunk: &StateValueChunkWithProof,
+) -> Result<(), Error> {
+    let first_index = account_states_chunk.first_index;
+    let last_index = account_states_chunk.last_index;
+    if first_index != start_index || last_index > end_index {
+        return Err(Error::InvalidResponse(format!(
+            "The account states chunk is outside the requested range! Requested: [{:?}, {:?}], \
+            received: [{:?}, {:?}]",
+            start_index, end_index, first_index, last_index
+        )));
+    }
+
+    let num_account_states = account_states_chunk.raw_values.len();
+    let expected_num_account_states = get_max_num_items(first_index, last_index)?;
+    if num_account_states as u64 != expected_num_account_states {
+        return Err(Error::InvalidResponse(format!(
+            "The number of account states doesn't match the chunk indices! Expected: {:?}, \
+            found: {:?}",
+            expected_num_account_states, num_account_states
+        )));
+    }
+    Ok(())
+}
+
+/// Validates a transaction output list against the requested version range
+fn validate_output_list(
+    start_version: Version,
+    end_version: Version,
+    output_list_with_proof: &TransactionOutputListWithProof,
+) -> Result<(), Error> {
+    let num_outputs = output_list_with_proof.transactions_and_outputs.len();
+    verify_num_items(num_outputs, get_max_num_items(start_version, end_version)?)?;
+    verify_first_version(
+        start_version,
+        num_outputs,
+        output_list_with_proof.first_transaction_output_version,
+    )
+}
+
+/// Validates a transaction list against the requested version range (and
+/// the requested events).
+fn validate_transaction_list(
+    start_version: Version,
+    end_version: Version,
+    include_events: bool,
+    transaction_list_with_proof: &TransactionListWithProof,
+) -> Result<(), Error> {
+    let num_transactions = transaction_list_with_proof.transactions.len();
+    verify_num_items(
+        num_transactions,
+        get_max_num_items(start_version, end_version)?,
+    )?;
+    verify_first_version(
+        start_version,
+        num_transactions,
+        transaction_list_with_proof.first_transaction_version,
+    )?;
+
+    let events_match_request = match &transaction_list_with_proof.events {
+        Some(events) => include_events && events.len() == num_transactions,
+        None => !include_events,
+    };
+    if !events_match_request {
+        return Err(Error::InvalidResponse(format!(
+            "The transaction events don't match the request! Include events: {:?}",
+            include_events
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the range of new versions that can be sent in response to an
+/// optimistic fetch, i.e., (known_version + 1) to the ledger info version.
+fn get_new_data_range(
+    known_version: Version,
+    ledger_info_version: Version,
+) -> Result<(Version, Version), Error> {
+    let start_version = known_version
+        .checked_add(1)
+        .ok_or_else(|| Error::InvalidResponse("The start version has overflown!".into()))?;
+    if ledger_info_version < start_version {
+        return Err(Error::InvalidResponse(format!(
+            "The ledger info is not newer than the known version! Known version: {:?}, \
+            ledger info version: {:?}",
+            known_version, ledger_info_version
+        )));
+    }
+    Ok((start_version, ledger_info_version))
+}
+
+/// Returns the maximum number of items that can be found in the given range
+fn get_max_num_items(start_index: u64, end_index: u64) -> Result<u64, Error> {
+    end_index
+        .checked_sub(start_index)
+        .and_then(|num_items| num_items.checked_add(1))
+        .ok_or_else(|| {
+            Error::InvalidResponse(format!(
+                "Invalid range! Start: {:?}, end: {:?}",
+                start_index, end_index
+            ))
+        })
+}
+
+/// Verifies that the number of items doesn't exceed the maximum
+fn verify_num_items(num_items: usize, max_num_items: u64) -> Result<(), Error> {
+    if num_items as u64 > max_num_items {
+        return Err(Error::InvalidResponse(format!(
+            "The response contains more items than requested! Max: {:?}, found: {:?}",
+            max_num_items, num_items
+        )));
+    }
+    Ok(())
+}
+
+/// Verifies that a non-empty list of data starts at the expected version
+fn verify_first_version(
+    expected_first_version: Version,
+    num_items: usize,
+    first_version: Option<Version>,
+) -> Result<(), Error> {
+    if num_items > 0 && first_version != Some(expected_first_version) {
+        return Err(Error::InvalidResponse(format!(
+            "The first version doesn't match the request! Expected: {:?}, found: {:?}",
+            expected_first_version, first_version
+        )));
+    }
+    Ok(())
+}
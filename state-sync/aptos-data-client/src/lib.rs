@@ -89,6 +89,30 @@ pub trait AptosDataClient {
         expected_end_epoch: Epoch,
     ) -> Result<Response<Vec<LedgerInfoWithSignatures>>>;
 
+    /// Returns a transaction output list with proof object, with transaction
+    /// outputs starting at `known_version + 1`, and the ledger info that the
+    /// proof is relative to. This is an optimistic fetch: the request is held
+    /// by the peer until new data is available (i.e., beyond the `known_version`
+    /// and `known_epoch`) or the request expires (resulting in an error).
+    async fn get_new_transaction_outputs_with_proof(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+    ) -> Result<Response<(TransactionOutputListWithProof, LedgerInfoWithSignatures)>>;
+
+    /// Returns a transaction list with proof object, with transactions starting
+    /// at `known_version + 1`, and the ledger info that the proof is relative
+    /// to. If `include_events` is true, events are included in the proof. This
+    /// is an optimistic fetch: the request is held by the peer until new data
+    /// is available (i.e., beyond the `known_version` and `known_epoch`) or the
+    /// request expires (resulting in an error).
+    async fn get_new_transactions_with_proof(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+        include_events: bool,
+    ) -> Result<Response<(TransactionListWithProof, LedgerInfoWithSignatures)>>;
+
     /// Returns the number of account states at the specified version.
     async fn get_number_of_account_states(&self, version: Version) -> Result<Response<u64>>;
 
@@ -190,6 +214,8 @@ impl<T> Response<T> {
 pub enum ResponsePayload {
     AccountStatesWithProof(StateValueChunkWithProof),
     EpochEndingLedgerInfos(Vec<LedgerInfoWithSignatures>),
+    NewTransactionOutputsWithProof((TransactionOutputListWithProof, LedgerInfoWithSignatures)),
+    NewTransactionsWithProof((TransactionListWithProof, LedgerInfoWithSignatures)),
     NumberOfAccountStates(u64),
     TransactionOutputsWithProof(TransactionOutputListWithProof),
     TransactionsWithProof(TransactionListWithProof),
@@ -200,6 +226,8 @@ impl ResponsePayload {
         match self {
             Self::AccountStatesWithProof(_) => "account_states_with_proof",
             Self::EpochEndingLedgerInfos(_) => "epoch_ending_ledger_infos",
+            Self::NewTransactionOutputsWithProof(_) => "new_transaction_outputs_with_proof",
+            Self::NewTransactionsWithProof(_) => "new_transactions_with_proof",
             Self::NumberOfAccountStates(_) => "number_of_account_states",
             Self::TransactionOutputsWithProof(_) => "transaction_outputs_with_proof",
             Self::TransactionsWithProof(_) => "transactions_with_proof",
@@ -219,6 +247,16 @@ impl From<Vec<LedgerInfoWithSignatures>> for ResponsePayload {
         Self::EpochEndingLedgerInfos(inner)
     }
 }
+impl From<(TransactionOutputListWithProof, LedgerInfoWithSignatures)> for ResponsePayload {
+    fn from(inner: (TransactionOutputListWithProof, LedgerInfoWithSignatures)) -> Self {
+        Self::NewTransactionOutputsWithProof(inner)
+    }
+}
+impl From<(TransactionListWithProof, LedgerInfoWithSignatures)> for ResponsePayload {
+    fn from(inner: (TransactionListWithProof, LedgerInfoWithSignatures)) -> Self {
+        Self::NewTransactionsWithProof(inner)
+    }
+}
 impl From<u64> for ResponsePayload {
     fn from(inner: u64) -> Self {
         Self::NumberOfAccountStates(inner)
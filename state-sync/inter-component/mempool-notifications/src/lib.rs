@@ -392,6 +392,7 @@ mod tests {
             300000001,
             vec![],
             AccountAddress::random(),
+            vec![],
         ))
     }
 
@@ -0,0 +1,155 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use aptos_types::transaction::Version;
+use channel::{aptos_channel, message_queues::QueueStyle};
+use futures::{stream::FusedStream, Stream};
+use serde::{Deserialize, Serialize};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use thiserror::Error;
+
+// Only the latest commit notification is relevant to the storage service
+const STORAGE_SERVICE_NOTIFICATION_CHANNEL_SIZE: usize = 1;
+
+#[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
+pub enum Error {
+    #[error("Commit notification failed: {0}")]
+    CommitNotificationError(String),
+}
+
+/// This method returns a (StorageServiceNotifier, StorageServiceNotificationListener)
+/// pair that can be used to allow state sync and the storage service to communicate.
+///
+/// Note: state sync should take the notifier and the storage service should take
+/// the listener.
+pub fn new_storage_service_notifier_listener_pair(
+) -> (StorageServiceNotifier, StorageServiceNotificationListener) {
+    let (notification_sender, notification_receiver) = aptos_channel::new(
+        QueueStyle::KLAST,
+        STORAGE_SERVICE_NOTIFICATION_CHANNEL_SIZE,
+        None,
+    );
+
+    let storage_service_notifier = StorageServiceNotifier::new(notification_sender);
+    let storage_service_listener = StorageServiceNotificationListener::new(notification_receiver);
+
+    (storage_service_notifier, storage_service_listener)
+}
+
+/// The state sync component responsible for notifying the storage service.
+#[derive(Clone, Debug)]
+pub struct StorageServiceNotifier {
+    notification_sender: aptos_channel::Sender<(), StorageServiceCommitNotification>,
+}
+
+impl StorageServiceNotifier {
+    fn new(
+        notification_sender: aptos_channel::Sender<(), StorageServiceCommitNotification>,
+    ) -> Self {
+        Self {
+            notification_sender,
+        }
+    }
+
+    /// Notifies the storage service that new data has been committed, up to
+    /// the specified version. This never blocks: older notifications that
+    /// haven't been handled yet are replaced by the new one.
+    pub fn notify_new_commit(&self, highest_synced_version: Version) -> Result<(), Error> {
+        let commit_notification = StorageServiceCommitNotification {
+            highest_synced_version,
+        };
+        self.notification_sender
+            .push((), commit_notification)
+            .map_err(|error| {
+                Error::CommitNotificationError(format!(
+                    "Failed to notify the storage service of committed data! Error: {:?}",
+                    error
+                ))
+            })
+    }
+}
+
+/// The storage service component responsible for handling state sync notifications.
+#[derive(Debug)]
+pub struct StorageServiceNotificationListener {
+    notification_receiver: aptos_channel::Receiver<(), StorageServiceCommitNotification>,
+}
+
+impl StorageServiceNotificationListener {
+    fn new(
+        notification_receiver: aptos_channel::Receiver<(), StorageServiceCommitNotification>,
+    ) -> Self {
+        Self {
+            notification_receiver,
+        }
+    }
+}
+
+impl Stream for StorageServiceNotificationListener {
+    type Item = StorageServiceCommitNotification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().notification_receiver).poll_next(cx)
+    }
+}
+
+impl FusedStream for StorageServiceNotificationListener {
+    fn is_terminated(&self) -> bool {
+        self.notification_receiver.is_terminated()
+    }
+}
+
+/// A notification for newly committed data sent by state sync to the storage service.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StorageServiceCommitNotification {
+    pub highest_synced_version: Version,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, StorageServiceCommitNotification};
+    use claim::{assert_matches, assert_none, assert_ok};
+    use futures::{FutureExt, StreamExt};
+
+    #[test]
+    fn test_storage_service_only_latest_notification() {
+        // Create the storage service notifier and listener
+        let (storage_service_notifier, mut storage_service_listener) =
+            crate::new_storage_service_notifier_listener_pair();
+
+        // Send several notifications without handling them
+        for highest_synced_version in 0..10 {
+            assert_ok!(storage_service_notifier.notify_new_commit(highest_synced_version));
+        }
+
+        // Verify only the latest notification is received
+        let commit_notification = storage_service_listener
+            .select_next_some()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(
+            commit_notification,
+            StorageServiceCommitNotification {
+                highest_synced_version: 9
+            }
+        );
+        assert_none!(storage_service_listener.select_next_some().now_or_never());
+    }
+
+    #[test]
+    fn test_storage_service_listener_dropped() {
+        // Create the storage service notifier and drop the listener
+        let (storage_service_notifier, storage_service_listener) =
+            crate::new_storage_service_notifier_listener_pair();
+        drop(storage_service_listener);
+
+        // Verify the notification fails
+        let notify_result = storage_service_notifier.notify_new_commit(101);
+        assert_matches!(notify_result, Err(Error::CommitNotificationError(_)));
+    }
+}
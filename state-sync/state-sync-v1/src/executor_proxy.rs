@@ -715,6 +715,7 @@ mod tests {
             (index as u64 + 1) * 100000010,
             vec![],
             validator_account,
+            vec![],
         ))
     }
 
@@ -75,12 +75,24 @@ impl ApplicationNetworkSender<StateSyncMessage> for StateSyncSender {
     ) -> Result<StateSyncMessage, RpcError> {
         unimplemented!()
     }
+
+    async fn send_rpc_with_size(
+        &self,
+        _recipient: PeerId,
+        _req_msg: StateSyncMessage,
+        _timeout: Duration,
+    ) -> Result<(StateSyncMessage, usize), RpcError> {
+        unimplemented!()
+    }
 }
 
 /// Configuration for the network endpoints to support state sync.
 pub fn network_endpoint_config() -> AppConfig {
     AppConfig::p2p(
-        [ProtocolId::StateSyncDirectSend],
+        [
+            ProtocolId::StateSyncDirectSend,
+            ProtocolId::StateSyncDirectSendCompressed,
+        ],
         aptos_channel::Config::new(STATE_SYNC_MAX_BUFFER_SIZE)
             .queue_style(QueueStyle::LIFO)
             .counters(&counters::PENDING_STATE_SYNC_NETWORK_EVENTS),
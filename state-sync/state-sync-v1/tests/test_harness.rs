@@ -401,7 +401,7 @@ impl StateSyncEnvironment {
                 TimeService::real(),
                 base_addr,
                 auth_mode,
-                PeerMetadataStorage::new(&[NetworkId::Validator]),
+                PeerMetadataStorage::new(&[NetworkId::Validator], TimeService::real()),
             );
 
             let (sender, events) =
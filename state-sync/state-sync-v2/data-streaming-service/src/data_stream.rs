@@ -5,7 +5,8 @@ use crate::{
     data_notification,
     data_notification::{
         AccountsWithProofRequest, DataClientRequest, DataNotification, DataPayload,
-        EpochEndingLedgerInfosRequest, NotificationId, NumberOfAccountsRequest,
+        EpochEndingLedgerInfosRequest, NewTransactionOutputsWithProofRequest,
+        NewTransactionsWithProofRequest, NotificationId, NumberOfAccountsRequest,
         TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
     },
     error::Error,
@@ -358,6 +359,7 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
             .message("Encountered a client response that failed the sanity checks!"));
 
         self.notify_bad_response(response_context, ResponseError::InvalidPayloadDataType);
+        self.request_failure_count += 1;
         self.resend_data_client_request(data_client_request)
     }
 
@@ -373,6 +375,18 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
             .error(&data_client_error.clone().into())
             .message("Encountered a data client error!"));
 
+        // Optimistic fetches are expected to fail (e.g., expire) if no new
+        // data is committed in time, so these don't count as failures (unless
+        // no peers can service the request).
+        let optimistic_fetch_expired = data_client_request.is_optimistic_fetch_request()
+            && !matches!(
+                data_client_error,
+                aptos_data_client::Error::DataIsUnavailable(_)
+            );
+        if !optimistic_fetch_expired {
+            self.request_failure_count += 1;
+        }
+
         // TODO(joshlind): can we identify the best way to react to the error?
         self.resend_data_client_request(data_client_request)
     }
@@ -383,9 +397,6 @@ impl<T: AptosDataClient + Send + Clone + 'static> DataStream<T> {
         &mut self,
         data_client_request: &DataClientRequest,
     ) -> Result<(), Error> {
-        // Increment the number of client failures for this request
-        self.request_failure_count += 1;
-
         // Resend the client request
         let pending_client_response = self.send_client_request(data_client_request.clone());
 
@@ -611,6 +622,18 @@ fn sanity_check_client_response(
                 ResponsePayload::EpochEndingLedgerInfos(_)
             )
         }
+        DataClientRequest::NewTransactionOutputsWithProof(_) => {
+            matches!(
+                data_client_response.payload,
+                ResponsePayload::NewTransactionOutputsWithProof(_)
+            )
+        }
+        DataClientRequest::NewTransactionsWithProof(_) => {
+            matches!(
+                data_client_response.payload,
+                ResponsePayload::NewTransactionsWithProof(_)
+            )
+        }
         DataClientRequest::NumberOfAccounts(_) => {
             matches!(
                 data_client_response.payload,
@@ -675,6 +698,12 @@ fn spawn_request_task<T: AptosDataClient + Send + Clone + 'static>(
             DataClientRequest::EpochEndingLedgerInfos(request) => {
                 get_epoch_ending_ledger_infos(aptos_data_client, request).await
             }
+            DataClientRequest::NewTransactionOutputsWithProof(request) => {
+                get_new_transaction_outputs_with_proof(aptos_data_client, request).await
+            }
+            DataClientRequest::NewTransactionsWithProof(request) => {
+                get_new_transactions_with_proof(aptos_data_client, request).await
+            }
             DataClientRequest::NumberOfAccounts(request) => {
                 get_number_of_account_states(aptos_data_client, request).await
             }
@@ -729,6 +758,31 @@ async fn get_epoch_ending_ledger_infos<T: AptosDataClient + Send + Clone + 'stat
         .map(|response| response.map(ResponsePayload::from))
 }
 
+async fn get_new_transaction_outputs_with_proof<T: AptosDataClient + Send + Clone + 'static>(
+    aptos_data_client: T,
+    request: NewTransactionOutputsWithProofRequest,
+) -> Result<Response<ResponsePayload>, aptos_data_client::Error> {
+    let client_response = aptos_data_client
+        .get_new_transaction_outputs_with_proof(request.known_version, request.known_epoch);
+    client_response
+        .await
+        .map(|response| response.map(ResponsePayload::from))
+}
+
+async fn get_new_transactions_with_proof<T: AptosDataClient + Send + Clone + 'static>(
+    aptos_data_client: T,
+    request: NewTransactionsWithProofRequest,
+) -> Result<Response<ResponsePayload>, aptos_data_client::Error> {
+    let client_response = aptos_data_client.get_new_transactions_with_proof(
+        request.known_version,
+        request.known_epoch,
+        request.include_events,
+    );
+    client_response
+        .await
+        .map(|response| response.map(ResponsePayload::from))
+}
+
 async fn get_number_of_account_states<T: AptosDataClient + Send + Clone + 'static>(
     aptos_data_client: T,
     request: NumberOfAccountsRequest,
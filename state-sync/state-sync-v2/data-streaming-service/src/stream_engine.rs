@@ -5,11 +5,13 @@ use crate::{
     data_notification::{
         AccountsWithProofRequest, DataClientRequest,
         DataClientRequest::{
-            AccountsWithProof, EpochEndingLedgerInfos, NumberOfAccounts,
-            TransactionOutputsWithProof, TransactionsWithProof,
+            AccountsWithProof, EpochEndingLedgerInfos, NewTransactionOutputsWithProof,
+            NewTransactionsWithProof, NumberOfAccounts, TransactionOutputsWithProof,
+            TransactionsWithProof,
         },
-        DataNotification, DataPayload, EpochEndingLedgerInfosRequest, NumberOfAccountsRequest,
-        TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
+        DataNotification, DataPayload, EpochEndingLedgerInfosRequest,
+        NewTransactionOutputsWithProofRequest, NewTransactionsWithProofRequest,
+        NumberOfAccountsRequest, TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
     },
     error::Error,
     logging::{LogEntry, LogEvent, LogSchema},
@@ -329,6 +331,10 @@ pub struct ContinuousTransactionStreamEngine {
     // the network. All versions before this have been requested.
     pub next_request_version_and_epoch: (Version, Epoch),
 
+    // True iff an optimistic fetch has been requested for new data (i.e.,
+    // because we're already at the highest advertised version).
+    pub optimistic_fetch_requested: bool,
+
     // True iff all data has been sent across the stream. This will only be
     // possible if there is a target ledger info specified.
     pub stream_is_complete: bool,
@@ -344,6 +350,7 @@ impl ContinuousTransactionStreamEngine {
                     end_of_epoch_requested: false,
                     next_stream_version_and_epoch: (request.start_version, request.start_epoch),
                     next_request_version_and_epoch: (request.start_version, request.start_epoch),
+                    optimistic_fetch_requested: false,
                     stream_is_complete: false,
                 })
             }
@@ -354,6 +361,7 @@ impl ContinuousTransactionStreamEngine {
                     end_of_epoch_requested: false,
                     next_stream_version_and_epoch: (request.start_version, request.start_epoch),
                     next_request_version_and_epoch: (request.start_version, request.start_epoch),
+                    optimistic_fetch_requested: false,
                     stream_is_complete: false,
                 })
             }
@@ -361,34 +369,34 @@ impl ContinuousTransactionStreamEngine {
         }
     }
 
+    /// Returns the final target ledger info of the stream (if one exists)
+    fn get_final_target_ledger_info(&self) -> Option<LedgerInfoWithSignatures> {
+        match &self.request {
+            StreamRequest::ContinuouslyStreamTransactions(request) => request.target.clone(),
+            StreamRequest::ContinuouslyStreamTransactionOutputs(request) => request.target.clone(),
+            request => invalid_stream_request!(request),
+        }
+    }
+
+    /// Selects the next target ledger info to sync to. Returns `None` if
+    /// there's no final target and we're already at the highest advertised
+    /// ledger info (i.e., new data must be fetched optimistically).
     fn select_target_ledger_info(
         &self,
         advertised_data: &AdvertisedData,
-    ) -> Result<LedgerInfoWithSignatures, Error> {
+    ) -> Result<Option<LedgerInfoWithSignatures>, Error> {
         // Check if the stream has a final target ledger info
-        match &self.request {
-            StreamRequest::ContinuouslyStreamTransactions(request) => {
-                if let Some(target) = &request.target {
-                    return Ok(target.clone());
-                }
-            }
-            StreamRequest::ContinuouslyStreamTransactionOutputs(request) => {
-                if let Some(target) = &request.target {
-                    return Ok(target.clone());
-                }
-            }
-            request => invalid_stream_request!(request),
-        };
+        if let Some(target) = self.get_final_target_ledger_info() {
+            return Ok(Some(target));
+        }
 
         // We don't have a final target, select the highest to make progress
         if let Some(highest_synced_ledger_info) = advertised_data.highest_synced_ledger_info() {
             let (next_request_version, _) = self.next_request_version_and_epoch;
             if next_request_version > highest_synced_ledger_info.ledger_info().version() {
-                Err(Error::NoDataToFetch(
-                    "We're already at the highest synced ledger info version!".into(),
-                ))
+                Ok(None) // We're already at the highest synced ledger info
             } else {
-                Ok(highest_synced_ledger_info)
+                Ok(Some(highest_synced_ledger_info))
             }
         } else {
             Err(Error::DataIsUnavailable(
@@ -397,6 +405,35 @@ impl ContinuousTransactionStreamEngine {
         }
     }
 
+    /// Creates an optimistic fetch request for new data beyond the highest
+    /// version and epoch that have been requested so far.
+    fn create_optimistic_fetch_request(&self) -> Result<DataClientRequest, Error> {
+        let (next_request_version, known_epoch) = self.next_request_version_and_epoch;
+        let known_version = next_request_version
+            .checked_sub(1)
+            .ok_or_else(|| Error::IntegerOverflow("Known version has overflown!".into()))?;
+
+        let client_request = match &self.request {
+            StreamRequest::ContinuouslyStreamTransactions(request) => {
+                DataClientRequest::NewTransactionsWithProof(NewTransactionsWithProofRequest {
+                    known_version,
+                    known_epoch,
+                    include_events: request.include_events,
+                })
+            }
+            StreamRequest::ContinuouslyStreamTransactionOutputs(_) => {
+                DataClientRequest::NewTransactionOutputsWithProof(
+                    NewTransactionOutputsWithProofRequest {
+                        known_version,
+                        known_epoch,
+                    },
+                )
+            }
+            request => invalid_stream_request!(request),
+        };
+        Ok(client_request)
+    }
+
     fn get_target_ledger_info(&self) -> &LedgerInfoWithSignatures {
         self.current_target_ledger_info
             .as_ref()
@@ -494,6 +531,61 @@ impl ContinuousTransactionStreamEngine {
         Ok(())
     }
 
+    /// Handles the response to an optimistic fetch by updating the target
+    /// ledger info and the stream progress, and creating a data notification.
+    fn handle_optimistic_fetch_response(
+        &mut self,
+        known_version: Version,
+        known_epoch: Epoch,
+        client_response_payload: ResponsePayload,
+        notification_id_generator: Arc<U64IdGenerator>,
+    ) -> Result<DataNotification, Error> {
+        // The optimistic fetch is no longer pending
+        self.optimistic_fetch_requested = false;
+
+        // Extract the target ledger info and the number of new versions
+        let (target_ledger_info, num_versions) = match &client_response_payload {
+            ResponsePayload::NewTransactionsWithProof((transactions_with_proof, ledger_info)) => (
+                ledger_info.clone(),
+                transactions_with_proof.transactions.len() as u64,
+            ),
+            ResponsePayload::NewTransactionOutputsWithProof((outputs_with_proof, ledger_info)) => (
+                ledger_info.clone(),
+                outputs_with_proof.transactions_and_outputs.len() as u64,
+            ),
+            response_payload => invalid_response_type!(response_payload),
+        };
+
+        // Verify the new data is valid for the known version and epoch
+        let start_version = known_version
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("Start version has overflown!".into()))?;
+        let end_version = known_version
+            .checked_add(num_versions)
+            .ok_or_else(|| Error::IntegerOverflow("End version has overflown!".into()))?;
+        let target_version = target_ledger_info.ledger_info().version();
+        let target_epoch = target_ledger_info.ledger_info().epoch();
+        if num_versions == 0 || end_version > target_version || target_epoch != known_epoch {
+            return Err(Error::AptosDataClientResponseIsInvalid(format!(
+                "Received an invalid optimistic fetch response! Known version: {:?}, \
+                known epoch: {:?}, number of versions: {:?}, target ledger info: {:?}",
+                known_version, known_epoch, num_versions, target_ledger_info
+            )));
+        }
+
+        // Update the target ledger info and the stream progress
+        self.current_target_ledger_info = Some(target_ledger_info);
+        self.update_request_version_and_epoch(end_version)?;
+        self.update_stream_version_and_epoch(start_version, end_version)?;
+
+        // Create the data notification
+        self.create_data_notification(
+            end_version,
+            client_response_payload,
+            notification_id_generator,
+        )
+    }
+
     fn update_request_tracking(
         &mut self,
         client_requests: &[DataClientRequest],
@@ -535,13 +627,31 @@ impl DataStreamEngine for ContinuousTransactionStreamEngine {
         if self.current_target_ledger_info.is_none() && self.end_of_epoch_requested {
             return Ok(vec![]); // We are waiting for the epoch ending ledger info
         }
+        if self.optimistic_fetch_requested {
+            return Ok(vec![]); // We are waiting for the optimistic fetch response
+        }
 
         // If we don't have a syncing target, select one.
         let (next_request_version, next_request_epoch) = self.next_request_version_and_epoch;
         if self.current_target_ledger_info.is_none() {
-            // Select a new ledger info from the advertised data
+            // Select a new ledger info from the advertised data. If we're
+            // already at the highest advertised data, optimistically fetch.
             let target_ledger_info =
-                self.select_target_ledger_info(&global_data_summary.advertised_data)?;
+                match self.select_target_ledger_info(&global_data_summary.advertised_data)? {
+                    Some(target_ledger_info) => target_ledger_info,
+                    None => {
+                        debug!(
+                            (LogSchema::new(LogEntry::AptosDataClient)
+                                .event(LogEvent::Pending)
+                                .message(&format!(
+                                    "Requested an optimistic fetch for data after version: {:?}",
+                                    next_request_version
+                                )))
+                        );
+                        self.optimistic_fetch_requested = true;
+                        return Ok(vec![self.create_optimistic_fetch_request()?]);
+                    }
+                };
             if target_ledger_info.ledger_info().epoch() > next_request_epoch {
                 // There was an epoch change. Request an epoch ending ledger info.
                 info!(
@@ -616,11 +726,26 @@ impl DataStreamEngine for ContinuousTransactionStreamEngine {
 
         // Verify we can satisfy the next version
         let (next_request_version, _) = self.next_request_version_and_epoch;
-        AdvertisedData::contains_range(
+        if AdvertisedData::contains_range(
             next_request_version,
             next_request_version,
             advertised_ranges,
-        )
+        ) {
+            return true;
+        }
+
+        // Otherwise, if there's no final target, verify we can optimistically
+        // fetch the data (i.e., peers have synced to the last version we hold).
+        if self.get_final_target_ledger_info().is_none() {
+            if let Some(known_version) = next_request_version.checked_sub(1) {
+                return AdvertisedData::contains_range(
+                    known_version,
+                    known_version,
+                    advertised_ranges,
+                );
+            }
+        }
+        false
     }
 
     fn is_stream_complete(&self) -> bool {
@@ -705,6 +830,30 @@ impl DataStreamEngine for ContinuousTransactionStreamEngine {
                 }
                 request => invalid_stream_request!(request),
             },
+            NewTransactionsWithProof(request) => match &self.request {
+                StreamRequest::ContinuouslyStreamTransactions(_) => {
+                    let data_notification = self.handle_optimistic_fetch_response(
+                        request.known_version,
+                        request.known_epoch,
+                        client_response_payload,
+                        notification_id_generator,
+                    )?;
+                    Ok(Some(data_notification))
+                }
+                request => invalid_stream_request!(request),
+            },
+            NewTransactionOutputsWithProof(request) => match &self.request {
+                StreamRequest::ContinuouslyStreamTransactionOutputs(_) => {
+                    let data_notification = self.handle_optimistic_fetch_response(
+                        request.known_version,
+                        request.known_epoch,
+                        client_response_payload,
+                        notification_id_generator,
+                    )?;
+                    Ok(Some(data_notification))
+                }
+                request => invalid_stream_request!(request),
+            },
             request => invalid_client_request!(request, self),
         }
     }
@@ -1211,6 +1360,29 @@ fn create_data_notification(
         ResponsePayload::EpochEndingLedgerInfos(ledger_infos) => {
             DataPayload::EpochEndingLedgerInfos(ledger_infos)
         }
+        ResponsePayload::NewTransactionsWithProof((transactions_chunk, target_ledger_info)) => {
+            match stream_engine {
+                StreamEngine::ContinuousTransactionStreamEngine(_) => {
+                    DataPayload::ContinuousTransactionsWithProof(
+                        target_ledger_info,
+                        transactions_chunk,
+                    )
+                }
+                _ => invalid_response_type!(client_response_type),
+            }
+        }
+        ResponsePayload::NewTransactionOutputsWithProof((
+            transactions_output_chunk,
+            target_ledger_info,
+        )) => match stream_engine {
+            StreamEngine::ContinuousTransactionStreamEngine(_) => {
+                DataPayload::ContinuousTransactionOutputsWithProof(
+                    target_ledger_info,
+                    transactions_output_chunk,
+                )
+            }
+            _ => invalid_response_type!(client_response_type),
+        },
         ResponsePayload::TransactionsWithProof(transactions_chunk) => match stream_engine {
             StreamEngine::ContinuousTransactionStreamEngine(stream_engine) => {
                 DataPayload::ContinuousTransactionsWithProof(
@@ -2,15 +2,26 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    data_notification::{DataClientRequest, EpochEndingLedgerInfosRequest},
+    data_notification::{
+        DataClientRequest, DataPayload, EpochEndingLedgerInfosRequest,
+        NewTransactionOutputsWithProofRequest,
+    },
     error::Error,
-    stream_engine::{DataStreamEngine, EpochEndingStreamEngine, StreamEngine},
-    streaming_client::{GetAllEpochEndingLedgerInfosRequest, StreamRequest},
-    tests::utils::initialize_logger,
+    stream_engine::{
+        ContinuousTransactionStreamEngine, DataStreamEngine, EpochEndingStreamEngine, StreamEngine,
+    },
+    streaming_client::{
+        ContinuouslyStreamTransactionOutputsRequest, GetAllEpochEndingLedgerInfosRequest,
+        StreamRequest,
+    },
+    tests::utils::{
+        create_ledger_info, create_transaction, create_transaction_output, initialize_logger,
+    },
 };
 use aptos_data_client::{GlobalDataSummary, OptimalChunkSizes, ResponsePayload};
 use aptos_id_generator::U64IdGenerator;
-use claim::{assert_matches, assert_ok};
+use aptos_types::transaction::{TransactionOutputListWithProof, Version};
+use claim::{assert_err, assert_matches, assert_ok};
 use std::{cmp, sync::Arc};
 use storage_service_types::CompleteDataRange;
 
@@ -238,6 +249,150 @@ fn test_update_epoch_ending_stream_panic() {
         .unwrap();
 }
 
+#[test]
+fn test_create_optimistic_fetch_request() {
+    // Create a continuous outputs stream engine that is already caught up
+    let highest_synced_version = 1000;
+    let mut stream_engine = create_continuous_outputs_stream_engine(highest_synced_version + 1, 5);
+    let global_data_summary = create_continuous_outputs_data_summary(highest_synced_version, 5);
+
+    // Verify that an optimistic fetch request is created
+    let client_requests = stream_engine
+        .create_data_client_requests(5, &global_data_summary)
+        .unwrap();
+    let expected_requests = vec![DataClientRequest::NewTransactionOutputsWithProof(
+        NewTransactionOutputsWithProofRequest {
+            known_version: highest_synced_version,
+            known_epoch: 5,
+        },
+    )];
+    assert_eq!(client_requests, expected_requests);
+    assert!(stream_engine.optimistic_fetch_requested);
+
+    // Verify no more requests are created while the fetch is pending
+    let client_requests = stream_engine
+        .create_data_client_requests(5, &global_data_summary)
+        .unwrap();
+    assert!(client_requests.is_empty());
+}
+
+#[test]
+fn test_update_optimistic_fetch_stream_progress() {
+    // Create a continuous outputs stream engine with a pending optimistic fetch
+    let known_version = 1000;
+    let mut stream_engine = create_continuous_outputs_stream_engine(known_version + 1, 5);
+    stream_engine.optimistic_fetch_requested = true;
+
+    // Handle an optimistic fetch response and verify the notification
+    let client_request =
+        DataClientRequest::NewTransactionOutputsWithProof(NewTransactionOutputsWithProofRequest {
+            known_version,
+            known_epoch: 5,
+        });
+    let num_new_versions = 10;
+    let data_notification = stream_engine
+        .transform_client_response_into_notification(
+            &client_request,
+            create_new_outputs_response_payload(known_version, num_new_versions, 5),
+            create_notification_id_generator(),
+        )
+        .unwrap()
+        .unwrap();
+    assert_matches!(
+        data_notification.data_payload,
+        DataPayload::ContinuousTransactionOutputsWithProof(..)
+    );
+
+    // Verify the internal state of the engine
+    let expected_next_version_and_epoch = (known_version + num_new_versions + 1, 5);
+    assert!(!stream_engine.optimistic_fetch_requested);
+    assert!(stream_engine.current_target_ledger_info.is_none());
+    assert_eq!(
+        stream_engine.next_request_version_and_epoch,
+        expected_next_version_and_epoch
+    );
+    assert_eq!(
+        stream_engine.next_stream_version_and_epoch,
+        expected_next_version_and_epoch
+    );
+
+    // Verify that a response for a different epoch is rejected
+    let mut stream_engine = create_continuous_outputs_stream_engine(known_version + 1, 5);
+    stream_engine.optimistic_fetch_requested = true;
+    let result = stream_engine.transform_client_response_into_notification(
+        &client_request,
+        create_new_outputs_response_payload(known_version, num_new_versions, 6),
+        create_notification_id_generator(),
+    );
+    assert_matches!(
+        assert_err!(result),
+        Error::AptosDataClientResponseIsInvalid(_)
+    );
+}
+
+fn create_continuous_outputs_stream_engine(
+    start_version: Version,
+    start_epoch: u64,
+) -> ContinuousTransactionStreamEngine {
+    initialize_logger();
+
+    // Create a continuous transaction outputs stream request
+    let stream_request = StreamRequest::ContinuouslyStreamTransactionOutputs(
+        ContinuouslyStreamTransactionOutputsRequest {
+            start_version,
+            start_epoch,
+            target: None,
+        },
+    );
+
+    // Create a new continuous transaction stream engine
+    let global_data_summary = create_continuous_outputs_data_summary(start_version, start_epoch);
+    match StreamEngine::new(&stream_request, &global_data_summary.advertised_data).unwrap() {
+        StreamEngine::ContinuousTransactionStreamEngine(stream_engine) => stream_engine,
+        unexpected_engine => {
+            panic!(
+                "Expected continuous transaction stream engine but got {:?}",
+                unexpected_engine
+            );
+        }
+    }
+}
+
+fn create_continuous_outputs_data_summary(
+    highest_synced_version: Version,
+    highest_synced_epoch: u64,
+) -> GlobalDataSummary {
+    let mut global_data_summary = GlobalDataSummary::empty();
+    global_data_summary.advertised_data.synced_ledger_infos = vec![create_ledger_info(
+        highest_synced_version,
+        highest_synced_epoch,
+        false,
+    )];
+    global_data_summary.advertised_data.transaction_outputs =
+        vec![CompleteDataRange::new(0, highest_synced_version).unwrap()];
+    global_data_summary
+        .optimal_chunk_sizes
+        .transaction_output_chunk_size = 100;
+
+    global_data_summary
+}
+
+fn create_new_outputs_response_payload(
+    known_version: Version,
+    num_new_versions: u64,
+    epoch: u64,
+) -> ResponsePayload {
+    let mut output_list_with_proof = TransactionOutputListWithProof::new_empty();
+    output_list_with_proof.first_transaction_output_version = Some(known_version + 1);
+    for _ in 0..num_new_versions {
+        output_list_with_proof
+            .transactions_and_outputs
+            .push((create_transaction(), create_transaction_output()));
+    }
+    let ledger_info = create_ledger_info(known_version + num_new_versions, epoch, false);
+    ResponsePayload::NewTransactionOutputsWithProof((output_list_with_proof, ledger_info))
+}
+
 fn create_epoch_ending_stream_engine(start_epoch: u64, end_epoch: u64) -> EpochEndingStreamEngine {
     initialize_logger();
 
@@ -159,6 +159,53 @@ impl AptosDataClient for MockAptosDataClient {
         Ok(create_data_client_response(epoch_ending_ledger_infos))
     }
 
+    async fn get_new_transaction_outputs_with_proof(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+    ) -> Result<
+        Response<(TransactionOutputListWithProof, LedgerInfoWithSignatures)>,
+        aptos_data_client::Error,
+    > {
+        // Create a small chunk of new transaction outputs
+        let (start_version, end_version) = create_new_data_range(known_version);
+        let output_list_with_proof = self
+            .get_transaction_outputs_with_proof(end_version, start_version, end_version)
+            .await?
+            .payload;
+
+        // Return the new transaction outputs and the ledger info
+        let ledger_info = create_ledger_info(end_version, known_epoch, false);
+        Ok(create_data_client_response((
+            output_list_with_proof,
+            ledger_info,
+        )))
+    }
+
+    async fn get_new_transactions_with_proof(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+        include_events: bool,
+    ) -> Result<
+        Response<(TransactionListWithProof, LedgerInfoWithSignatures)>,
+        aptos_data_client::Error,
+    > {
+        // Create a small chunk of new transactions
+        let (start_version, end_version) = create_new_data_range(known_version);
+        let transaction_list_with_proof = self
+            .get_transactions_with_proof(end_version, start_version, end_version, include_events)
+            .await?
+            .payload;
+
+        // Return the new transactions and the ledger info
+        let ledger_info = create_ledger_info(end_version, known_epoch, false);
+        Ok(create_data_client_response((
+            transaction_list_with_proof,
+            ledger_info,
+        )))
+    }
+
     async fn get_number_of_account_states(
         &self,
         _version: Version,
@@ -213,6 +260,13 @@ impl ResponseCallback for NoopResponseCallback {
     }
 }
 
+/// Returns a random (non-empty) range of new versions after the known version
+fn create_new_data_range(known_version: Version) -> (Version, Version) {
+    let start_version = known_version + 1;
+    let end_version = known_version + create_non_zero_random_u64(10);
+    (start_version, end_version)
+}
+
 /// Creates a data client response using a specified payload and random id
 pub fn create_data_client_response<T>(payload: T) -> Response<T> {
     let id = create_random_u64(MAX_RESPONSE_ID);
@@ -324,7 +378,7 @@ fn create_synced_ledger_infos(
 }
 
 /// Creates a simple test transaction
-fn create_transaction() -> Transaction {
+pub fn create_transaction() -> Transaction {
     let private_key = Ed25519PrivateKey::generate_for_testing();
     let public_key = private_key.public_key();
 
@@ -346,7 +400,7 @@ fn create_transaction() -> Transaction {
 }
 
 /// Creates an empty transaction output
-fn create_transaction_output() -> TransactionOutput {
+pub fn create_transaction_output() -> TransactionOutput {
     TransactionOutput::new(WriteSet::default(), vec![], 0, TransactionStatus::Retry)
 }
 
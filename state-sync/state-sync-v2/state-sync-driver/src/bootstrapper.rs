@@ -6,7 +6,7 @@ use crate::{
     storage_synchronizer::StorageSynchronizerInterface, utils, utils::SpeculativeStreamState,
 };
 use aptos_config::config::BootstrappingMode;
-use aptos_data_client::GlobalDataSummary;
+use aptos_data_client::{AdvertisedData, GlobalDataSummary};
 use aptos_logger::*;
 use aptos_types::{
     epoch_change::Verifier,
@@ -219,8 +219,9 @@ impl VerifiedEpochStates {
     }
 }
 
-// TODO(joshlind): persist the index (e.g., in case we crash mid-download)?
-/// A simple container to manage state related to account state snapshot syncing
+/// A simple container to manage state related to account state snapshot syncing.
+/// The targets are persisted in storage (along with the committed accounts), so
+/// that an interrupted download resumes after a crash or restart.
 struct AccountStateSyncer {
     // Whether or not a state snapshot receiver has been initialized
     initialized_state_snapshot_receiver: bool,
@@ -255,6 +256,44 @@ impl AccountStateSyncer {
         }
     }
 
+    /// Resumes the account states download interrupted by a crash or restart
+    /// (if any), using the targets and accounts already committed to storage.
+    pub fn resume_from_storage(&mut self, storage: Arc<dyn DbReader>) -> Result<(), Error> {
+        let progress = match utils::fetch_state_snapshot_progress(storage.clone())? {
+            Some(progress) => progress,
+            None => return Ok(()),
+        };
+
+        // If the snapshot was already finalized, there's nothing to resume
+        let version = progress.version();
+        if utils::fetch_latest_synced_version(storage.clone())? >= version {
+            return Ok(());
+        }
+
+        // Fetch the number of accounts already committed for the snapshot
+        let expected_root_hash = progress
+            .target_output_with_proof
+            .proof
+            .transaction_infos
+            .first()
+            .ok_or_else(|| {
+                Error::StorageError("The persisted target transaction info is missing!".into())
+            })?
+            .state_change_hash();
+        let num_restored_accounts =
+            utils::fetch_num_restored_account_states(storage, version, expected_root_hash)?;
+        info!(
+            "Resuming the account states download at version: {:?}, next account index: {:?}",
+            version, num_restored_accounts
+        );
+
+        self.ledger_info_to_sync = Some(progress.target_ledger_info);
+        self.transaction_output_to_sync = Some(progress.target_output_with_proof);
+        self.next_account_index_to_commit = num_restored_accounts;
+        self.next_account_index_to_process = num_restored_accounts;
+        Ok(())
+    }
+
     /// Resets all speculative state related to account state syncing (i.e., all
     /// speculative data that as not been successfully committed to storage)
     pub fn reset_speculative_state(&mut self) {
@@ -305,7 +344,15 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> Bootstrapper<StorageSy
         // Load the latest epoch state from storage
         let latest_epoch_state = utils::fetch_latest_epoch_state(storage.clone())
             .expect("Unable to fetch latest epoch state!");
-        let verified_epoch_states = VerifiedEpochStates::new(latest_epoch_state);
+        let mut verified_epoch_states = VerifiedEpochStates::new(latest_epoch_state);
+
+        // When syncing from a trusted source, the waypoint doesn't need to be verified
+        if matches!(
+            driver_configuration.config.bootstrapping_mode,
+            BootstrappingMode::SyncFromTrustedSource
+        ) {
+            verified_epoch_states.set_verified_waypoint();
+        }
 
         Self {
             account_state_syncer: AccountStateSyncer::new(),
@@ -422,7 +469,7 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> Bootstrapper<StorageSy
                 {
                     return self.bootstrapping_complete();
                 }
-                self.fetch_all_account_states(highest_known_ledger_info)
+                self.fetch_all_account_states(global_data_summary, highest_known_ledger_info)
                     .await
             }
             _ => {
@@ -496,38 +543,45 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> Bootstrapper<StorageSy
     /// Fetches all account states (as required to bootstrap the node)
     async fn fetch_all_account_states(
         &mut self,
+        global_data_summary: &GlobalDataSummary,
         highest_known_ledger_info: LedgerInfoWithSignatures,
     ) -> Result<(), Error> {
-        // Verify we're trying to sync to an unchanging ledger info
-        if let Some(ledger_info_to_sync) = &self.account_state_syncer.ledger_info_to_sync {
-            if ledger_info_to_sync != &highest_known_ledger_info {
-                panic!(
-                    "Mismatch in ledger info to sync! Highest: {:?}, target: {:?}",
-                    highest_known_ledger_info, ledger_info_to_sync
-                );
-            }
-        } else {
-            self.account_state_syncer.ledger_info_to_sync = Some(highest_known_ledger_info.clone());
+        // Resume any interrupted account states download (if we haven't picked a target yet)
+        if self.account_state_syncer.ledger_info_to_sync.is_none() {
+            self.account_state_syncer
+                .resume_from_storage(self.storage.clone())?;
         }
 
+        // Verify the target of a resumed download hasn't been pruned by our peers
+        if !self
+            .account_state_syncer
+            .initialized_state_snapshot_receiver
+        {
+            self.verify_account_states_target_is_available(global_data_summary)?;
+        }
+
+        // Sync to the target of the resumed download or the highest known ledger info.
+        // Note: we sync to an unchanging ledger info (once the download has started).
+        let ledger_info_to_sync = self
+            .account_state_syncer
+            .ledger_info_to_sync
+            .get_or_insert(highest_known_ledger_info)
+            .clone();
+
         // Fetch the transaction info first, before the account states
-        let highest_known_ledger_version = highest_known_ledger_info.ledger_info().version();
+        let version_to_sync = ledger_info_to_sync.ledger_info().version();
         let data_stream = if self
             .account_state_syncer
             .transaction_output_to_sync
             .is_none()
         {
             self.streaming_service_client
-                .get_all_transaction_outputs(
-                    highest_known_ledger_version,
-                    highest_known_ledger_version,
-                    highest_known_ledger_version,
-                )
+                .get_all_transaction_outputs(version_to_sync, version_to_sync, version_to_sync)
                 .await?
         } else {
             let start_account_index = Some(self.account_state_syncer.next_account_index_to_commit);
             self.streaming_service_client
-                .get_all_accounts(highest_known_ledger_version, start_account_index)
+                .get_all_accounts(version_to_sync, start_account_index)
                 .await?
         };
         self.active_data_stream = Some(data_stream);
@@ -550,7 +604,8 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> Bootstrapper<StorageSy
             .next_epoch_ending_version(highest_synced_version)
             .expect("No higher epoch ending version known!");
         let data_stream = match self.driver_configuration.config.bootstrapping_mode {
-            BootstrappingMode::ApplyTransactionOutputsFromGenesis => {
+            BootstrappingMode::ApplyTransactionOutputsFromGenesis
+            | BootstrappingMode::RestoreFromBackupStorage => {
                 self.streaming_service_client
                     .get_all_transaction_outputs(
                         next_version,
@@ -569,6 +624,16 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> Bootstrapper<StorageSy
                     )
                     .await?
             }
+            BootstrappingMode::SyncFromTrustedSource => {
+                self.streaming_service_client
+                    .get_all_transactions(
+                        next_version,
+                        end_version,
+                        highest_known_ledger_version,
+                        true,
+                    )
+                    .await?
+            }
             bootstrapping_mode => {
                 unreachable!("Bootstrapping mode not supported: {:?}", bootstrapping_mode)
             }
@@ -589,8 +654,10 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> Bootstrapper<StorageSy
         &mut self,
         global_data_summary: &GlobalDataSummary,
     ) -> Result<(), Error> {
-        // Verify the waypoint can be satisfied
-        self.verify_waypoint_is_satisfiable(global_data_summary)?;
+        // Verify the waypoint can be satisfied (if it hasn't already been verified)
+        if !self.verified_epoch_states.verified_waypoint() {
+            self.verify_waypoint_is_satisfiable(global_data_summary)?;
+        }
 
         // Get the highest advertised epoch that has ended
         let highest_advertised_epoch_end = global_data_summary
@@ -686,6 +753,52 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> Bootstrapper<StorageSy
         }
     }
 
+    /// Verifies that the account states at the version we're syncing to (if
+    /// any) are still advertised by our peers. If the version has been pruned
+    /// (i.e., peers only advertise newer versions), the download is abandoned
+    /// and restarts at the highest known ledger info.
+    fn verify_account_states_target_is_available(
+        &mut self,
+        global_data_summary: &GlobalDataSummary,
+    ) -> Result<(), Error> {
+        let version_to_sync = match &self.account_state_syncer.ledger_info_to_sync {
+            Some(ledger_info_to_sync) => ledger_info_to_sync.ledger_info().version(),
+            None => return Ok(()),
+        };
+
+        let advertised_account_states = &global_data_summary.advertised_data.account_states;
+        if AdvertisedData::contains_range(
+            version_to_sync,
+            version_to_sync,
+            advertised_account_states,
+        ) {
+            Ok(())
+        } else if !advertised_account_states.is_empty()
+            && advertised_account_states
+                .iter()
+                .all(|range| range.lowest() > version_to_sync)
+        {
+            warn!(
+                "The account states at version {:?} have been pruned by all peers! Restarting the download.",
+                version_to_sync
+            );
+            if self.storage_synchronizer.pending_storage_data() {
+                return Err(Error::UnexpectedError(
+                    "Unable to abandon the account states download with pending storage data!"
+                        .into(),
+                ));
+            }
+            self.storage_synchronizer.reset_account_synchronizer()?;
+            self.account_state_syncer = AccountStateSyncer::new();
+            Ok(())
+        } else {
+            Err(Error::AdvertisedDataError(format!(
+                "The account states at version {:?} are not advertised by any peer!",
+                version_to_sync
+            )))
+        }
+    }
+
     /// Verifies the start and end indices in the given account states chunk
     async fn verify_account_states_indices(
         &mut self,
@@ -935,7 +1048,8 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> Bootstrapper<StorageSy
 
         // Execute/apply and commit the transactions/outputs
         let num_transactions_or_outputs = match bootstrapping_mode {
-            BootstrappingMode::ApplyTransactionOutputsFromGenesis => {
+            BootstrappingMode::ApplyTransactionOutputsFromGenesis
+            | BootstrappingMode::RestoreFromBackupStorage => {
                 if let Some(transaction_outputs_with_proof) = transaction_outputs_with_proof {
                     let num_transaction_outputs = transaction_outputs_with_proof
                         .transactions_and_outputs
@@ -979,6 +1093,27 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> Bootstrapper<StorageSy
                     ));
                 }
             }
+            BootstrappingMode::SyncFromTrustedSource => {
+                if let Some(transaction_list_with_proof) = transaction_list_with_proof {
+                    let num_transactions = transaction_list_with_proof.transactions.len();
+                    self.storage_synchronizer.save_transaction_history(
+                        notification_id,
+                        transaction_list_with_proof,
+                        proof_ledger_info,
+                        end_of_epoch_ledger_info,
+                    )?;
+                    num_transactions
+                } else {
+                    self.terminate_active_stream(
+                        notification_id,
+                        NotificationFeedback::PayloadTypeIsIncorrect,
+                    )
+                    .await?;
+                    return Err(Error::InvalidPayload(
+                        "Did not receive transactions with proof!".into(),
+                    ));
+                }
+            }
             bootstrapping_mode => {
                 unreachable!("Bootstrapping mode not supported: {:?}", bootstrapping_mode)
             }
@@ -1092,7 +1227,8 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> Bootstrapper<StorageSy
     ) -> Result<Option<LedgerInfoWithSignatures>, Error> {
         // Calculate the payload end version
         let num_versions = match self.driver_configuration.config.bootstrapping_mode {
-            BootstrappingMode::ApplyTransactionOutputsFromGenesis => {
+            BootstrappingMode::ApplyTransactionOutputsFromGenesis
+            | BootstrappingMode::RestoreFromBackupStorage => {
                 if let Some(transaction_outputs_with_proof) = transaction_outputs_with_proof {
                     transaction_outputs_with_proof
                         .transactions_and_outputs
@@ -1108,7 +1244,8 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> Bootstrapper<StorageSy
                     ));
                 }
             }
-            BootstrappingMode::ExecuteTransactionsFromGenesis => {
+            BootstrappingMode::ExecuteTransactionsFromGenesis
+            | BootstrappingMode::SyncFromTrustedSource => {
                 if let Some(transaction_list_with_proof) = transaction_list_with_proof {
                     transaction_list_with_proof.transactions.len()
                 } else {
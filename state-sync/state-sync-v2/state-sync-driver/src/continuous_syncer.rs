@@ -118,6 +118,16 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> ContinuousSyncer<Stora
                     )
                     .await?
             }
+            ContinuousSyncingMode::SyncFromTrustedSource => {
+                self.streaming_service_client
+                    .continuously_stream_transactions(
+                        next_version,
+                        highest_synced_epoch,
+                        true,
+                        sync_request_target,
+                    )
+                    .await?
+            }
         };
         self.speculative_stream_state = Some(SpeculativeStreamState::new(
             highest_epoch_state,
@@ -261,6 +271,27 @@ impl<StorageSyncer: StorageSynchronizerInterface + Clone> ContinuousSyncer<Stora
                         ));
                     }
                 }
+                ContinuousSyncingMode::SyncFromTrustedSource => {
+                    if let Some(transaction_list_with_proof) = transaction_list_with_proof {
+                        let num_transactions = transaction_list_with_proof.transactions.len();
+                        self.storage_synchronizer.save_transaction_history(
+                            notification_id,
+                            transaction_list_with_proof,
+                            ledger_info_with_signatures,
+                            None,
+                        )?;
+                        num_transactions
+                    } else {
+                        self.terminate_active_stream(
+                            notification_id,
+                            NotificationFeedback::PayloadTypeIsIncorrect,
+                        )
+                        .await?;
+                        return Err(Error::InvalidPayload(
+                            "Did not receive transactions with proof!".into(),
+                        ));
+                    }
+                }
             };
         let synced_version = payload_start_version
             .checked_add(num_transactions_or_outputs as u64)
@@ -9,7 +9,7 @@ use crate::{
     notification_handlers::{
         CommitNotification, CommitNotificationListener, CommittedAccounts, CommittedTransactions,
         ConsensusNotificationHandler, ErrorNotification, ErrorNotificationListener,
-        MempoolNotificationHandler,
+        MempoolNotificationHandler, StorageServiceNotificationHandler,
     },
     storage_synchronizer::StorageSynchronizerInterface,
     utils,
@@ -93,6 +93,9 @@ pub struct StateSyncDriver<DataClient, MempoolNotifier, StorageSyncer> {
 
     // The interface to read from storage
     storage: Arc<dyn DbReader>,
+
+    // The handler for notifications to the storage service
+    storage_service_notification_handler: StorageServiceNotificationHandler,
 }
 
 impl<
@@ -113,6 +116,7 @@ impl<
         aptos_data_client: DataClient,
         streaming_service_client: StreamingServiceClient,
         storage: Arc<dyn DbReader>,
+        storage_service_notification_handler: StorageServiceNotificationHandler,
     ) -> Self {
         let event_subscription_service = Arc::new(Mutex::new(event_subscription_service));
         let bootstrapper = Bootstrapper::new(
@@ -141,6 +145,7 @@ impl<
             mempool_notification_handler,
             start_time: None,
             storage,
+            storage_service_notification_handler,
         }
     }
 
@@ -261,6 +266,7 @@ impl<
             latest_synced_ledger_info,
             self.mempool_notification_handler.clone(),
             self.event_subscription_service.clone(),
+            self.storage_service_notification_handler.clone(),
         )
         .await?;
 
@@ -370,6 +376,7 @@ impl<
             latest_synced_ledger_info,
             self.mempool_notification_handler.clone(),
             self.event_subscription_service.clone(),
+            self.storage_service_notification_handler.clone(),
         )
         .await
         {
@@ -6,7 +6,7 @@ use crate::{
     driver_client::{ClientNotificationListener, DriverClient, DriverNotification},
     notification_handlers::{
         CommitNotificationListener, ConsensusNotificationHandler, ErrorNotificationListener,
-        MempoolNotificationHandler,
+        MempoolNotificationHandler, StorageServiceNotificationHandler,
     },
     storage_synchronizer::StorageSynchronizer,
 };
@@ -21,6 +21,7 @@ use futures::channel::mpsc;
 use mempool_notifications::MempoolNotificationSender;
 use std::sync::Arc;
 use storage_interface::DbReaderWriter;
+use storage_service_notifications::StorageServiceNotifier;
 use tokio::runtime::{Builder, Runtime};
 
 /// Creates a new state sync driver and client
@@ -41,6 +42,7 @@ impl DriverFactory {
         storage: DbReaderWriter,
         chunk_executor: Arc<ChunkExecutor>,
         mempool_notification_sender: MempoolNotifier,
+        storage_service_notifier: StorageServiceNotifier,
         consensus_listener: ConsensusNotificationListener,
         event_subscription_service: EventSubscriptionService,
         aptos_data_client: AptosNetDataClient,
@@ -57,6 +59,8 @@ impl DriverFactory {
             ErrorNotificationListener::new();
         let mempool_notification_handler =
             MempoolNotificationHandler::new(mempool_notification_sender);
+        let storage_service_notification_handler =
+            StorageServiceNotificationHandler::new(storage_service_notifier);
 
         // Create a new runtime (if required)
         let driver_runtime = if create_runtime {
@@ -101,6 +105,7 @@ impl DriverFactory {
             aptos_data_client,
             streaming_service_client,
             storage.reader,
+            storage_service_notification_handler,
         );
 
         // Spawn the driver
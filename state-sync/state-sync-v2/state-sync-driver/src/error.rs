@@ -28,6 +28,8 @@ pub enum Error {
     InvalidPayload(String),
     #[error("Failed to notify mempool of the new commit: {0}")]
     NotifyMempoolError(String),
+    #[error("Failed to notify the storage service of the new commit: {0}")]
+    NotifyStorageServiceError(String),
     #[error("Received an old sync request for version {0}, but our committed version is: {1}")]
     OldSyncRequest(Version, Version),
     #[error("Received oneshot::canceled. The sender of a channel was dropped: {0}")]
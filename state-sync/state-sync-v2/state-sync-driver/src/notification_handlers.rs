@@ -23,6 +23,7 @@ use std::{
     task::{Context, Poll},
     time::{Duration, SystemTime},
 };
+use storage_service_notifications::StorageServiceNotifier;
 
 // TODO(joshlind): make these configurable!
 const CONSENSUS_SYNC_REQUEST_TIMEOUT_MS: u64 = 60000; // 1 minute
@@ -72,8 +73,8 @@ impl CommitNotification {
         CommitNotification::CommittedTransactions(committed_transactions)
     }
 
-    /// Handles the commit notification by notifying mempool and the event
-    /// subscription service.
+    /// Handles the commit notification by notifying the storage service,
+    /// mempool and the event subscription service.
     pub async fn handle_transaction_notification<M: MempoolNotificationSender>(
         events: Vec<ContractEvent>,
         transactions: Vec<Transaction>,
@@ -81,7 +82,16 @@ impl CommitNotification {
         latest_synced_ledger_info: LedgerInfoWithSignatures,
         mut mempool_notification_handler: MempoolNotificationHandler<M>,
         event_subscription_service: Arc<Mutex<EventSubscriptionService>>,
+        storage_service_notification_handler: StorageServiceNotificationHandler,
     ) -> Result<(), Error> {
+        // Notify the storage service of the committed transactions
+        debug!(
+            "Notifying the storage service of transactions at version: {:?}",
+            latest_synced_version
+        );
+        storage_service_notification_handler
+            .notify_storage_service_of_committed_transactions(latest_synced_version)?;
+
         // Notify mempool of the committed transactions
         debug!(
             "Notifying mempool of transactions at version: {:?}",
@@ -451,3 +461,28 @@ impl<M: MempoolNotificationSender> MempoolNotificationHandler<M> {
         }
     }
 }
+
+/// A simple handler for sending notifications to the storage service
+#[derive(Clone)]
+pub struct StorageServiceNotificationHandler {
+    storage_service_notifier: StorageServiceNotifier,
+}
+
+impl StorageServiceNotificationHandler {
+    pub fn new(storage_service_notifier: StorageServiceNotifier) -> Self {
+        Self {
+            storage_service_notifier,
+        }
+    }
+
+    /// Notifies the storage service that transactions have been committed
+    /// (e.g., so that it can service any pending optimistic fetches).
+    pub fn notify_storage_service_of_committed_transactions(
+        &self,
+        highest_synced_version: Version,
+    ) -> Result<(), Error> {
+        self.storage_service_notifier
+            .notify_new_commit(highest_synced_version)
+            .map_err(|error| Error::NotifyStorageServiceError(format!("{:?}", error)))
+    }
+}
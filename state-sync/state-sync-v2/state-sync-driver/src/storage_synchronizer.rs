@@ -8,9 +8,10 @@ use crate::{
 use aptos_config::config::StateSyncDriverConfig;
 use aptos_logger::prelude::*;
 use aptos_types::{
+    contract_event::ContractEvent,
     ledger_info::LedgerInfoWithSignatures,
     state_store::state_value::StateValueChunkWithProof,
-    transaction::{TransactionListWithProof, TransactionOutputListWithProof},
+    transaction::{Transaction, TransactionListWithProof, TransactionOutputListWithProof},
 };
 use data_streaming_service::data_notification::NotificationId;
 use executor_types::ChunkExecutorTrait;
@@ -22,7 +23,7 @@ use std::{
         Arc,
     },
 };
-use storage_interface::DbWriter;
+use storage_interface::{DbWriter, StateSnapshotProgress};
 use tokio::runtime::{Handle, Runtime};
 
 // TODO(joshlind): add structured logging support!
@@ -55,6 +56,8 @@ pub trait StorageSynchronizerInterface {
     /// Initializes an account synchronizer with the specified
     /// `target_ledger_info` and `target_output_with_proof` at the target
     /// syncing version. Also, writes all `epoch_change_proofs` to storage.
+    /// The targets are persisted, and the account states already committed
+    /// for the same targets (e.g., before a crash) are kept.
     ///
     /// Note: this assumes that `epoch_change_proofs`, `target_ledger_info`,
     /// and `target_output_with_proof` have already been verified.
@@ -69,6 +72,13 @@ pub trait StorageSynchronizerInterface {
     /// to be executed/applied or committed.
     fn pending_storage_data(&self) -> bool;
 
+    /// Abandons the account states synchronization (e.g., because the
+    /// target has been pruned by our peers) and deletes its progress and
+    /// the account states already committed from storage.
+    ///
+    /// Note: this requires that there is no pending storage data.
+    fn reset_account_synchronizer(&mut self) -> Result<(), Error>;
+
     /// Saves the given account states to storage.
     ///
     /// Note: this requires that `initialize_account_synchronizer` has been
@@ -78,6 +88,19 @@ pub trait StorageSynchronizerInterface {
         notification_id: NotificationId,
         account_states_with_proof: StateValueChunkWithProof,
     ) -> Result<(), Error>;
+
+    /// Verifies a batch of transactions against the transaction accumulator
+    /// and saves the transaction history to storage (without executing the
+    /// transactions or updating the state).
+    ///
+    /// Note: this assumes that the ledger infos have already been verified.
+    fn save_transaction_history(
+        &mut self,
+        notification_id: NotificationId,
+        transaction_list_with_proof: TransactionListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error>;
 }
 
 /// The implementation of the `StorageSynchronizerInterface` used by state sync
@@ -151,10 +174,12 @@ impl<ChunkExecutor: ChunkExecutorTrait + 'static> StorageSynchronizer<ChunkExecu
         let runtime = runtime.map(|runtime| runtime.handle().clone());
         spawn_executor(
             chunk_executor.clone(),
+            commit_notification_sender.clone(),
             error_notification_sender.clone(),
             executor_listener,
             committer_notifier,
             pending_transaction_chunks.clone(),
+            storage.clone(),
             runtime.clone(),
         );
 
@@ -236,6 +261,20 @@ impl<ChunkExecutor: ChunkExecutorTrait + 'static> StorageSynchronizerInterface
         target_ledger_info: LedgerInfoWithSignatures,
         target_output_with_proof: TransactionOutputListWithProof,
     ) -> Result<(), Error> {
+        // Persist the target of the snapshot so that we can resume after a crash
+        let state_snapshot_progress = StateSnapshotProgress::new(
+            target_ledger_info.clone(),
+            target_output_with_proof.clone(),
+        );
+        self.storage
+            .save_state_snapshot_progress(&state_snapshot_progress)
+            .map_err(|error| {
+                Error::StorageError(format!(
+                    "Failed to save the state snapshot progress: {:?}",
+                    error
+                ))
+            })?;
+
         // Create a channel to notify the state snapshot receiver when data chunks are ready
         let max_pending_data_chunks = self.driver_config.max_pending_data_chunks as usize;
         let (state_snapshot_notifier, state_snapshot_listener) =
@@ -263,6 +302,21 @@ impl<ChunkExecutor: ChunkExecutorTrait + 'static> StorageSynchronizerInterface
         self.pending_data_chunks.load(Ordering::Relaxed) > 0
     }
 
+    fn reset_account_synchronizer(&mut self) -> Result<(), Error> {
+        // Stop the state snapshot receiver (if any) by dropping its channel
+        self.state_snapshot_notifier = None;
+
+        // Delete the progress and the partially restored snapshot
+        self.storage
+            .delete_state_snapshot_progress()
+            .map_err(|error| {
+                Error::StorageError(format!(
+                    "Failed to delete the state snapshot progress: {:?}",
+                    error
+                ))
+            })
+    }
+
     fn save_account_states(
         &mut self,
         notification_id: NotificationId,
@@ -284,6 +338,22 @@ impl<ChunkExecutor: ChunkExecutorTrait + 'static> StorageSynchronizerInterface
             Ok(())
         }
     }
+
+    fn save_transaction_history(
+        &mut self,
+        notification_id: NotificationId,
+        transaction_list_with_proof: TransactionListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        let storage_data_chunk = StorageDataChunk::TransactionHistory(
+            notification_id,
+            transaction_list_with_proof,
+            target_ledger_info,
+            end_of_epoch_ledger_info,
+        );
+        self.notify_executor(storage_data_chunk)
+    }
 }
 
 /// A chunk of data to be executed and/or committed to storage (i.e., accounts,
@@ -298,6 +368,12 @@ enum StorageDataChunk {
         LedgerInfoWithSignatures,
         Option<LedgerInfoWithSignatures>,
     ),
+    TransactionHistory(
+        NotificationId,
+        TransactionListWithProof,
+        LedgerInfoWithSignatures,
+        Option<LedgerInfoWithSignatures>,
+    ),
     TransactionOutputs(
         NotificationId,
         TransactionOutputListWithProof,
@@ -309,10 +385,12 @@ enum StorageDataChunk {
 /// Spawns a dedicated executor that executes/applies storage data chunks
 fn spawn_executor<ChunkExecutor: ChunkExecutorTrait + 'static>(
     chunk_executor: Arc<ChunkExecutor>,
+    mut commit_notification_sender: mpsc::UnboundedSender<CommitNotification>,
     error_notification_sender: mpsc::UnboundedSender<ErrorNotification>,
     mut executor_listener: mpsc::Receiver<StorageDataChunk>,
     mut committer_notifier: mpsc::Sender<NotificationId>,
     pending_transaction_chunks: Arc<AtomicU64>,
+    storage: Arc<dyn DbWriter>,
     runtime: Option<Handle>,
 ) {
     // Create an executor
@@ -340,6 +418,30 @@ fn spawn_executor<ChunkExecutor: ChunkExecutorTrait + 'static>(
                                 );
                              (notification_id, result)
                         }
+                        StorageDataChunk::TransactionHistory(notification_id, transactions_with_proof, target_ledger_info, end_of_epoch_ledger_info) => {
+                            // Transaction history is saved directly (there's nothing to execute or commit)
+                            match save_transaction_history(
+                                storage.clone(),
+                                transactions_with_proof,
+                                target_ledger_info,
+                                end_of_epoch_ledger_info,
+                            ) {
+                                Ok((events, transactions)) => {
+                                    // Send a commit notification to the commit listener
+                                    let commit_notification = CommitNotification::new_committed_transactions(events, transactions);
+                                    if let Err(error) = commit_notification_sender.send(commit_notification).await {
+                                        let error = format!("Failed to send transaction commit notification! Error: {:?}", error);
+                                        send_storage_synchronizer_error(error_notification_sender.clone(), notification_id, error).await;
+                                    }
+                                }
+                                Err(error) => {
+                                    let error = format!("Failed to save the transaction history! Error: {:?}", error);
+                                    send_storage_synchronizer_error(error_notification_sender.clone(), notification_id, error).await;
+                                }
+                            }
+                            decrement_atomic(pending_transaction_chunks.clone());
+                            continue;
+                        }
                         storage_data_chunk => {
                             panic!("Invalid storage data chunk sent to executor: {:?}", storage_data_chunk);
                         }
@@ -369,6 +471,58 @@ fn spawn_executor<ChunkExecutor: ChunkExecutorTrait + 'static>(
     spawn(runtime, executor);
 }
 
+/// Verifies the given transaction list against the target ledger info and
+/// saves the transaction history to storage. The ledger info is persisted
+/// with the transactions if the chunk ends the epoch or reaches the target.
+/// Returns the saved events and transactions (for the commit notification).
+fn save_transaction_history(
+    storage: Arc<dyn DbWriter>,
+    transaction_list_with_proof: TransactionListWithProof,
+    target_ledger_info: LedgerInfoWithSignatures,
+    end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+) -> Result<(Vec<ContractEvent>, Vec<Transaction>), Error> {
+    // Verify the transactions against the transaction accumulator
+    let first_transaction_version = transaction_list_with_proof.first_transaction_version;
+    transaction_list_with_proof
+        .verify(target_ledger_info.ledger_info(), first_transaction_version)
+        .map_err(|error| {
+            Error::VerificationError(format!(
+                "The transaction list with proof failed verification! Error: {:?}",
+                error
+            ))
+        })?;
+
+    // Identify the ledger info to persist (if any)
+    let num_transactions = transaction_list_with_proof.transactions.len() as u64;
+    let last_transaction_version = first_transaction_version
+        .and_then(|version| version.checked_add(num_transactions))
+        .and_then(|version| version.checked_sub(1));
+    let ledger_info_to_save = if end_of_epoch_ledger_info.is_some() {
+        end_of_epoch_ledger_info
+    } else if last_transaction_version == Some(target_ledger_info.ledger_info().version()) {
+        Some(target_ledger_info)
+    } else {
+        None
+    };
+
+    // Identify the committed events and transactions
+    let events = match &transaction_list_with_proof.events {
+        Some(events) => events.iter().flatten().cloned().collect(),
+        None => {
+            return Err(Error::UnexpectedError(
+                "The transaction history is missing the transaction events!".into(),
+            ))
+        }
+    };
+    let transactions = transaction_list_with_proof.transactions.clone();
+
+    // Save the transaction history
+    storage
+        .save_transaction_history(transaction_list_with_proof, ledger_info_to_save.as_ref())
+        .map_err(|error| Error::StorageError(format!("{:?}", error)))?;
+    Ok((events, transactions))
+}
+
 /// Spawns a dedicated committer that commits executed (but pending) chunks
 fn spawn_committer<ChunkExecutor: ChunkExecutorTrait + 'static>(
     chunk_executor: Arc<ChunkExecutor>,
@@ -432,7 +586,7 @@ fn spawn_state_snapshot_receiver<ChunkExecutor: ChunkExecutorTrait + 'static>(
             .expect("Target transaction info should exist!")
             .state_change_hash();
 
-        // Create the snapshot receiver
+        // Create the snapshot receiver (this resumes any interrupted restore of the snapshot)
         let mut state_snapshot_receiver = storage
             .get_state_snapshot_receiver(version, expected_root_hash)
             .expect("Failed to initialize the state snapshot receiver!");
@@ -494,6 +648,10 @@ fn spawn_state_snapshot_receiver<ChunkExecutor: ChunkExecutorTrait + 'static>(
                         }
                     }
                 }
+                complete => {
+                    // The account states synchronization was reset
+                    return;
+                }
             }
         }
     };
@@ -0,0 +1,146 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bootstrapper::Bootstrapper,
+    driver::DriverConfiguration,
+    error::Error,
+    tests::{
+        mocks::{MockStorageSynchronizer, SavedTransactionHistory},
+        utils::{
+            create_database_with_genesis, create_signed_ledger_info,
+            create_transaction_list_with_proof, expect_stream_request,
+        },
+    },
+    utils::fetch_latest_epoch_state,
+};
+use aptos_config::config::{
+    BootstrappingMode, ContinuousSyncingMode, RoleType, StateSyncDriverConfig,
+};
+use aptos_data_client::GlobalDataSummary;
+use aptos_types::{epoch_state::EpochState, waypoint::Waypoint};
+use claim::{assert_matches, assert_ok};
+use data_streaming_service::{
+    data_notification::{DataNotification, DataPayload},
+    streaming_client::{
+        new_streaming_service_client_listener_pair, GetAllEpochEndingLedgerInfosRequest,
+        GetAllTransactionsRequest, NotificationFeedback, StreamRequest, TerminateStreamRequest,
+    },
+};
+use futures::StreamExt;
+use storage_service_types::CompleteDataRange;
+
+#[tokio::test]
+async fn test_bootstrap_from_trusted_source() {
+    // Create a bootstrapper that syncs from a trusted source
+    let mut driver_config = StateSyncDriverConfig::default();
+    driver_config.bootstrapping_mode = BootstrappingMode::SyncFromTrustedSource;
+    driver_config.continuous_syncing_mode = ContinuousSyncingMode::SyncFromTrustedSource;
+    driver_config.max_stream_wait_time_ms = 100;
+    let driver_configuration =
+        DriverConfiguration::new(driver_config, RoleType::FullNode, Waypoint::default());
+    let (storage, _, validator_signer) = create_database_with_genesis();
+    let (streaming_service_client, mut streaming_service_listener) =
+        new_streaming_service_client_listener_pair();
+    let storage_synchronizer = MockStorageSynchronizer::default();
+    let mut bootstrapper = Bootstrapper::new(
+        driver_configuration,
+        streaming_service_client,
+        storage.clone(),
+        storage_synchronizer.clone(),
+    );
+
+    // Advertise the end of epoch 1 (at version 5)
+    let mut global_data_summary = GlobalDataSummary::empty();
+    global_data_summary
+        .advertised_data
+        .epoch_ending_ledger_infos = vec![CompleteDataRange::new(0, 1).unwrap()];
+    let genesis_epoch_state = fetch_latest_epoch_state(storage).unwrap();
+    let next_epoch_state = EpochState {
+        epoch: 2,
+        verifier: genesis_epoch_state.verifier,
+    };
+    let epoch_ending_ledger_info =
+        create_signed_ledger_info(&validator_signer, 1, 5, Some(next_epoch_state));
+
+    // Verify the epoch ending ledger infos are fetched first (without a waypoint)
+    let (result, notification_sender) = futures::join!(
+        bootstrapper.drive_progress(&global_data_summary),
+        expect_stream_request(
+            &mut streaming_service_listener,
+            StreamRequest::GetAllEpochEndingLedgerInfos(GetAllEpochEndingLedgerInfosRequest {
+                start_epoch: 1
+            }),
+        )
+    );
+    assert_ok!(result);
+    for (notification_id, data_payload) in vec![
+        DataPayload::EpochEndingLedgerInfos(vec![epoch_ending_ledger_info.clone()]),
+        DataPayload::EndOfStream,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        notification_sender
+            .push(
+                (),
+                DataNotification {
+                    notification_id: notification_id as u64,
+                    data_payload,
+                },
+            )
+            .unwrap();
+    }
+    assert_ok!(bootstrapper.drive_progress(&global_data_summary).await);
+    let request_message = streaming_service_listener.select_next_some().await;
+    assert_eq!(
+        request_message.stream_request,
+        StreamRequest::TerminateStream(TerminateStreamRequest {
+            notification_id: 1,
+            notification_feedback: NotificationFeedback::EndOfStream,
+        })
+    );
+    assert_ok!(bootstrapper.drive_progress(&global_data_summary).await);
+
+    // Verify all transactions (with events) are requested up to the epoch end
+    let (result, notification_sender) = futures::join!(
+        bootstrapper.drive_progress(&global_data_summary),
+        expect_stream_request(
+            &mut streaming_service_listener,
+            StreamRequest::GetAllTransactions(GetAllTransactionsRequest {
+                start_version: 1,
+                end_version: 5,
+                proof_version: 5,
+                include_events: true,
+            }),
+        )
+    );
+    assert_ok!(result);
+
+    // Verify the transactions are saved as transaction history (without execution)
+    let transaction_list_with_proof = create_transaction_list_with_proof(1, 5);
+    notification_sender
+        .push(
+            (),
+            DataNotification {
+                notification_id: 2,
+                data_payload: DataPayload::TransactionsWithProof(
+                    transaction_list_with_proof.clone(),
+                ),
+            },
+        )
+        .unwrap();
+    assert_matches!(
+        bootstrapper.drive_progress(&global_data_summary).await,
+        Err(Error::DataStreamNotificationTimeout(_))
+    );
+    assert_eq!(
+        storage_synchronizer.get_saved_transaction_history(),
+        vec![SavedTransactionHistory {
+            notification_id: 2,
+            transaction_list_with_proof,
+            target_ledger_info: epoch_ending_ledger_info.clone(),
+            end_of_epoch_ledger_info: Some(epoch_ending_ledger_info),
+        }]
+    );
+}
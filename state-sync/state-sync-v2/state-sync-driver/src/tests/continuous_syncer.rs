@@ -0,0 +1,97 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    continuous_syncer::ContinuousSyncer,
+    driver::DriverConfiguration,
+    error::Error,
+    tests::{
+        mocks::{MockStorageSynchronizer, SavedTransactionHistory},
+        utils::{
+            create_database_with_genesis, create_signed_ledger_info,
+            create_transaction_list_with_proof, expect_stream_request,
+        },
+    },
+};
+use aptos_config::config::{
+    BootstrappingMode, ContinuousSyncingMode, RoleType, StateSyncDriverConfig,
+};
+use aptos_infallible::Mutex;
+use aptos_types::waypoint::Waypoint;
+use claim::{assert_matches, assert_ok};
+use data_streaming_service::{
+    data_notification::{DataNotification, DataPayload},
+    streaming_client::{
+        new_streaming_service_client_listener_pair, ContinuouslyStreamTransactionsRequest,
+        StreamRequest,
+    },
+};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_continuously_sync_from_trusted_source() {
+    // Create a continuous syncer that syncs from a trusted source
+    let mut driver_config = StateSyncDriverConfig::default();
+    driver_config.bootstrapping_mode = BootstrappingMode::SyncFromTrustedSource;
+    driver_config.continuous_syncing_mode = ContinuousSyncingMode::SyncFromTrustedSource;
+    driver_config.max_stream_wait_time_ms = 100;
+    let driver_configuration =
+        DriverConfiguration::new(driver_config, RoleType::FullNode, Waypoint::default());
+    let (storage, _, validator_signer) = create_database_with_genesis();
+    let (streaming_service_client, mut streaming_service_listener) =
+        new_streaming_service_client_listener_pair();
+    let storage_synchronizer = MockStorageSynchronizer::default();
+    let mut continuous_syncer = ContinuousSyncer::new(
+        driver_configuration,
+        streaming_service_client,
+        storage,
+        storage_synchronizer.clone(),
+    );
+
+    // Verify transactions (with events) are continuously streamed after genesis
+    let consensus_sync_request = Arc::new(Mutex::new(None));
+    let (result, notification_sender) = futures::join!(
+        continuous_syncer.drive_progress(consensus_sync_request.clone()),
+        expect_stream_request(
+            &mut streaming_service_listener,
+            StreamRequest::ContinuouslyStreamTransactions(ContinuouslyStreamTransactionsRequest {
+                start_version: 1,
+                start_epoch: 1,
+                include_events: true,
+                target: None,
+            }),
+        )
+    );
+    assert_ok!(result);
+
+    // Verify the transactions are saved as transaction history (without execution)
+    let ledger_info = create_signed_ledger_info(&validator_signer, 1, 2, None);
+    let transaction_list_with_proof = create_transaction_list_with_proof(1, 2);
+    notification_sender
+        .push(
+            (),
+            DataNotification {
+                notification_id: 0,
+                data_payload: DataPayload::ContinuousTransactionsWithProof(
+                    ledger_info.clone(),
+                    transaction_list_with_proof.clone(),
+                ),
+            },
+        )
+        .unwrap();
+    assert_matches!(
+        continuous_syncer
+            .drive_progress(consensus_sync_request)
+            .await,
+        Err(Error::DataStreamNotificationTimeout(_))
+    );
+    assert_eq!(
+        storage_synchronizer.get_saved_transaction_history(),
+        vec![SavedTransactionHistory {
+            notification_id: 0,
+            transaction_list_with_proof,
+            target_ledger_info: ledger_info,
+            end_of_epoch_ledger_info: None,
+        }]
+    );
+}
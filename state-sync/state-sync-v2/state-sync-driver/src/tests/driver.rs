@@ -13,7 +13,7 @@ use consensus_notifications::ConsensusNotificationSender;
 #[tokio::test]
 async fn test_consensus_commit_notification() {
     // Create a driver for a full node
-    let (_full_node_driver, consensus_notifier, _, _) = utils::create_full_node_driver();
+    let (_full_node_driver, consensus_notifier, _, _, _) = utils::create_full_node_driver();
 
     // Verify that full nodes can't process commit notifications
     let result = consensus_notifier
@@ -22,7 +22,7 @@ async fn test_consensus_commit_notification() {
     assert_err!(result);
 
     // Create a driver for a validator with a waypoint at version 0
-    let (_validator_driver, consensus_notifier, _, _) = utils::create_validator_driver();
+    let (_validator_driver, consensus_notifier, _, _, _) = utils::create_validator_driver();
 
     // Send a new commit notification and verify the node isn't bootstrapped
     let result = consensus_notifier
@@ -34,7 +34,7 @@ async fn test_consensus_commit_notification() {
 #[tokio::test]
 async fn test_consensus_sync_request() {
     // Create a driver for a full node
-    let (_full_node_driver, consensus_notifier, _, _) = utils::create_full_node_driver();
+    let (_full_node_driver, consensus_notifier, _, _, _) = utils::create_full_node_driver();
 
     // Verify that full nodes can't process sync requests
     let result = consensus_notifier
@@ -43,7 +43,7 @@ async fn test_consensus_sync_request() {
     assert_err!(result);
 
     // Create a driver for a validator with a waypoint at version 0
-    let (_validator_driver, consensus_notifier, _, _) = utils::create_validator_driver();
+    let (_validator_driver, consensus_notifier, _, _, _) = utils::create_validator_driver();
 
     // Send a new sync request and verify the node isn't bootstrapped
     let result = consensus_notifier
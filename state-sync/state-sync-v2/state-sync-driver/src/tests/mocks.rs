@@ -0,0 +1,100 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{error::Error, storage_synchronizer::StorageSynchronizerInterface};
+use aptos_infallible::Mutex;
+use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    state_store::state_value::StateValueChunkWithProof,
+    transaction::{TransactionListWithProof, TransactionOutputListWithProof},
+};
+use data_streaming_service::data_notification::NotificationId;
+use std::sync::Arc;
+
+/// A transaction history chunk saved by the mock storage synchronizer
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedTransactionHistory {
+    pub notification_id: NotificationId,
+    pub transaction_list_with_proof: TransactionListWithProof,
+    pub target_ledger_info: LedgerInfoWithSignatures,
+    pub end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+}
+
+/// A mock storage synchronizer that records the transaction history it is
+/// asked to save. All other data is unexpected.
+#[derive(Clone, Default)]
+pub struct MockStorageSynchronizer {
+    saved_transaction_history: Arc<Mutex<Vec<SavedTransactionHistory>>>,
+}
+
+impl MockStorageSynchronizer {
+    /// Returns all transaction history chunks saved thus far
+    pub fn get_saved_transaction_history(&self) -> Vec<SavedTransactionHistory> {
+        self.saved_transaction_history.lock().clone()
+    }
+}
+
+impl StorageSynchronizerInterface for MockStorageSynchronizer {
+    fn apply_transaction_outputs(
+        &mut self,
+        _notification_id: NotificationId,
+        _output_list_with_proof: TransactionOutputListWithProof,
+        _target_ledger_info: LedgerInfoWithSignatures,
+        _end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        unimplemented!("Transaction outputs should not be applied!")
+    }
+
+    fn execute_transactions(
+        &mut self,
+        _notification_id: NotificationId,
+        _transaction_list_with_proof: TransactionListWithProof,
+        _target_ledger_info: LedgerInfoWithSignatures,
+        _end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        unimplemented!("Transactions should not be executed!")
+    }
+
+    fn initialize_account_synchronizer(
+        &mut self,
+        _epoch_change_proofs: Vec<LedgerInfoWithSignatures>,
+        _target_ledger_info: LedgerInfoWithSignatures,
+        _target_output_with_proof: TransactionOutputListWithProof,
+    ) -> Result<(), Error> {
+        unimplemented!("The account synchronizer should not be initialized!")
+    }
+
+    fn pending_storage_data(&self) -> bool {
+        false
+    }
+
+    fn reset_account_synchronizer(&mut self) -> Result<(), Error> {
+        unimplemented!("The account synchronizer should not be reset!")
+    }
+
+    fn save_account_states(
+        &mut self,
+        _notification_id: NotificationId,
+        _account_states_with_proof: StateValueChunkWithProof,
+    ) -> Result<(), Error> {
+        unimplemented!("Account states should not be saved!")
+    }
+
+    fn save_transaction_history(
+        &mut self,
+        notification_id: NotificationId,
+        transaction_list_with_proof: TransactionListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        self.saved_transaction_history
+            .lock()
+            .push(SavedTransactionHistory {
+                notification_id,
+                transaction_list_with_proof,
+                target_ledger_info,
+                end_of_epoch_ledger_info,
+            });
+        Ok(())
+    }
+}
@@ -1,5 +1,9 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+mod bootstrapper;
+mod continuous_syncer;
 mod driver;
+mod mocks;
+mod storage_synchronizer;
 mod utils;
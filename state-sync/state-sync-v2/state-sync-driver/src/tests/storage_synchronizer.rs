@@ -0,0 +1,76 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    notification_handlers::CommitNotification,
+    storage_synchronizer::{StorageSynchronizer, StorageSynchronizerInterface},
+    tests::utils::create_database_with_genesis,
+};
+use aptos_config::config::StateSyncDriverConfig;
+use aptos_vm::AptosVM;
+use executor::chunk_executor::ChunkExecutor;
+use executor_test_helpers::integration_test_impl::test_execution_with_storage_impl;
+use futures::{channel::mpsc, StreamExt};
+use std::sync::Arc;
+use storage_interface::DbReader;
+
+#[tokio::test]
+async fn test_save_transaction_history_commit_notification() {
+    // Fetch the transaction history (after genesis) of a test database
+    let source_db = test_execution_with_storage_impl();
+    let latest_version = source_db.get_latest_version().unwrap();
+    let target_ledger_info = source_db.get_latest_ledger_info().unwrap();
+    let transaction_list_with_proof = source_db
+        .get_transactions(1, latest_version, latest_version, true)
+        .unwrap();
+
+    // Create a storage synchronizer for a database with the same genesis
+    let (storage, db_rw, _) = create_database_with_genesis();
+    let chunk_executor = Arc::new(ChunkExecutor::<AptosVM>::new(db_rw.clone()).unwrap());
+    let (commit_notification_sender, mut commit_notification_listener) = mpsc::unbounded();
+    let (error_notification_sender, _error_notification_listener) = mpsc::unbounded();
+    let mut storage_synchronizer = StorageSynchronizer::new(
+        StateSyncDriverConfig::default(),
+        chunk_executor,
+        commit_notification_sender,
+        error_notification_sender,
+        db_rw.writer,
+        None,
+    );
+
+    // Save the transaction history and verify a commit notification is sent
+    storage_synchronizer
+        .save_transaction_history(
+            0,
+            transaction_list_with_proof.clone(),
+            target_ledger_info.clone(),
+            None,
+        )
+        .unwrap();
+    match commit_notification_listener.select_next_some().await {
+        CommitNotification::CommittedTransactions(committed_transactions) => {
+            let expected_events: Vec<_> = transaction_list_with_proof
+                .events
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .collect();
+            assert_eq!(committed_transactions.events, expected_events);
+            assert_eq!(
+                committed_transactions.transactions,
+                transaction_list_with_proof.transactions
+            );
+        }
+        commit_notification => panic!(
+            "Unexpected commit notification found: {:?}",
+            commit_notification
+        ),
+    }
+
+    // Verify the transaction history was saved
+    assert_eq!(
+        storage.get_latest_ledger_info().unwrap(),
+        target_ledger_info
+    );
+    assert_eq!(storage.get_latest_version().unwrap(), latest_version);
+}
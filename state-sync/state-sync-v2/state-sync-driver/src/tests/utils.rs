@@ -14,24 +14,35 @@ use aptos_types::{
     account_address::AccountAddress,
     block_info::BlockInfo,
     chain_id::ChainId,
+    epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     move_resource::MoveStorage,
     on_chain_config::ON_CHAIN_CONFIG_REGISTRY,
+    proof::TransactionInfoListWithProof,
     transaction::{
-        RawTransaction, Script, SignedTransaction, Transaction, TransactionPayload, Version,
-        WriteSetPayload,
+        RawTransaction, Script, SignedTransaction, Transaction, TransactionListWithProof,
+        TransactionPayload, Version, WriteSetPayload,
     },
+    validator_signer::ValidatorSigner,
     waypoint::Waypoint,
 };
 use aptos_vm::AptosVM;
 use aptosdb::AptosDB;
+use channel::{aptos_channel, message_queues::QueueStyle};
 use consensus_notifications::ConsensusNotifier;
-use data_streaming_service::streaming_client::new_streaming_service_client_listener_pair;
+use data_streaming_service::{
+    data_notification::DataNotification,
+    data_stream::DataStreamListener,
+    streaming_client::{
+        new_streaming_service_client_listener_pair, StreamRequest, StreamingServiceListener,
+    },
+};
 use event_notifications::{
     EventNotificationSender, EventSubscriptionService, ReconfigNotificationListener,
 };
 use executor::chunk_executor::ChunkExecutor;
 use executor_test_helpers::bootstrap_genesis;
+use futures::StreamExt;
 use mempool_notifications::MempoolNotificationListener;
 use network::application::{interface::MultiNetworkSender, storage::PeerMetadataStorage};
 use std::{
@@ -40,6 +51,10 @@ use std::{
 };
 use storage_interface::{DbReader, DbReaderWriter};
 use storage_service_client::StorageServiceClient;
+use storage_service_notifications::StorageServiceNotificationListener;
+
+// The max number of pending data notifications on test data streams
+const MAX_NOTIFICATION_CHANNEL_SIZE: usize = 100;
 
 /// Creates a state sync driver with the given config and waypoint
 #[allow(dead_code)]
@@ -51,6 +66,7 @@ pub fn create_driver_with_config_and_waypoint(
     ConsensusNotifier,
     MempoolNotificationListener,
     ReconfigNotificationListener,
+    StorageServiceNotificationListener,
 ) {
     create_driver_for_tests(node_config, waypoint)
 }
@@ -61,6 +77,7 @@ pub fn create_validator_driver() -> (
     ConsensusNotifier,
     MempoolNotificationListener,
     ReconfigNotificationListener,
+    StorageServiceNotificationListener,
 ) {
     let mut node_config = NodeConfig::default();
     node_config.base.role = RoleType::Validator;
@@ -74,6 +91,7 @@ pub fn create_full_node_driver() -> (
     ConsensusNotifier,
     MempoolNotificationListener,
     ReconfigNotificationListener,
+    StorageServiceNotificationListener,
 ) {
     let mut node_config = NodeConfig::default();
     node_config.base.role = RoleType::FullNode;
@@ -90,19 +108,12 @@ fn create_driver_for_tests(
     ConsensusNotifier,
     MempoolNotificationListener,
     ReconfigNotificationListener,
+    StorageServiceNotificationListener,
 ) {
     // Create test aptos database
-    let db_path = aptos_temppath::TempPath::new();
-    db_path.create_as_dir().unwrap();
-    let (db, db_rw) = DbReaderWriter::wrap(AptosDB::new_for_test(db_path.path()));
-
-    // Bootstrap the genesis transaction
-    let (genesis, _) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
-    let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
-    bootstrap_genesis::<AptosVM>(&db_rw, &genesis_txn).unwrap();
+    let (storage, db_rw, _) = create_database_with_genesis();
 
     // Create the event subscription service and notify initial configs
-    let storage: Arc<dyn DbReader> = db;
     let synced_version = (&*storage).fetch_synced_version().unwrap();
     let mut event_subscription_service = EventSubscriptionService::new(
         ON_CHAIN_CONFIG_REGISTRY,
@@ -115,11 +126,13 @@ fn create_driver_for_tests(
         .notify_initial_configs(synced_version)
         .unwrap();
 
-    // Create consensus and mempool notifiers and listeners
+    // Create consensus, mempool and storage service notifiers and listeners
     let (consensus_notifier, consensus_listener) =
         consensus_notifications::new_consensus_notifier_listener_pair(1000);
     let (mempool_notifier, mempool_listener) =
         mempool_notifications::new_mempool_notifier_listener_pair();
+    let (storage_service_notifier, storage_service_listener) =
+        storage_service_notifications::new_storage_service_notifier_listener_pair();
 
     // Create the chunk executor
     let chunk_executor = Arc::new(ChunkExecutor::<AptosVM>::new(db_rw.clone()).unwrap());
@@ -130,13 +143,14 @@ fn create_driver_for_tests(
     // Create a test aptos data client
     let network_client = StorageServiceClient::new(
         MultiNetworkSender::new(HashMap::new()),
-        PeerMetadataStorage::new(&[]),
+        PeerMetadataStorage::new(&[], TimeService::mock()),
     );
     let (aptos_data_client, _) = AptosNetDataClient::new(
         node_config.state_sync.aptos_data_client,
         node_config.state_sync.storage_service,
         TimeService::mock(),
         network_client,
+        vec![],
     );
 
     // Create and spawn the driver
@@ -147,6 +161,7 @@ fn create_driver_for_tests(
         db_rw,
         chunk_executor,
         mempool_notifier,
+        storage_service_notifier,
         consensus_listener,
         event_subscription_service,
         aptos_data_client,
@@ -158,9 +173,91 @@ fn create_driver_for_tests(
         consensus_notifier,
         mempool_listener,
         reconfiguration_subscriber,
+        storage_service_listener,
+    )
+}
+
+/// Creates a test aptos database bootstrapped with the test genesis. Returns
+/// the database (reader and writer) and the signer of the genesis validator.
+pub fn create_database_with_genesis() -> (Arc<dyn DbReader>, DbReaderWriter, ValidatorSigner) {
+    // Create test aptos database
+    let db_path = aptos_temppath::TempPath::new();
+    db_path.create_as_dir().unwrap();
+    let (db, db_rw) = DbReaderWriter::wrap(AptosDB::new_for_test(db_path.path()));
+
+    // Bootstrap the genesis transaction
+    let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+    let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+    bootstrap_genesis::<AptosVM>(&db_rw, &genesis_txn).unwrap();
+    let validator_signer =
+        ValidatorSigner::new(validators[0].data.address, validators[0].key.clone());
+
+    let storage: Arc<dyn DbReader> = db;
+    (storage, db_rw, validator_signer)
+}
+
+/// Creates a ledger info at the specified epoch and version, signed by the
+/// given validator signer
+pub fn create_signed_ledger_info(
+    validator_signer: &ValidatorSigner,
+    epoch: u64,
+    version: Version,
+    next_epoch_state: Option<EpochState>,
+) -> LedgerInfoWithSignatures {
+    let block_info = BlockInfo::new(
+        epoch,
+        0,
+        HashValue::zero(),
+        HashValue::random(),
+        version,
+        0,
+        next_epoch_state,
+    );
+    let ledger_info = LedgerInfo::new(block_info, HashValue::zero());
+    let mut signatures = BTreeMap::new();
+    signatures.insert(
+        validator_signer.author(),
+        validator_signer.sign(&ledger_info),
+    );
+    LedgerInfoWithSignatures::new(ledger_info, signatures)
+}
+
+/// Creates a list of test transactions (with events) starting at the given version
+pub fn create_transaction_list_with_proof(
+    first_version: Version,
+    num_transactions: usize,
+) -> TransactionListWithProof {
+    let transactions = (0..num_transactions)
+        .map(|_| create_test_transaction())
+        .collect();
+    TransactionListWithProof::new(
+        transactions,
+        Some(vec![vec![]; num_transactions]),
+        Some(first_version),
+        TransactionInfoListWithProof::new_empty(),
     )
 }
 
+/// Waits for the given stream request and responds with a new data stream.
+/// Returns the sender for the data notifications of the stream.
+pub async fn expect_stream_request(
+    streaming_service_listener: &mut StreamingServiceListener,
+    expected_stream_request: StreamRequest,
+) -> aptos_channel::Sender<(), DataNotification> {
+    let request_message = streaming_service_listener.select_next_some().await;
+    assert_eq!(request_message.stream_request, expected_stream_request);
+
+    let (notification_sender, notification_receiver) =
+        aptos_channel::new(QueueStyle::KLAST, MAX_NOTIFICATION_CHANNEL_SIZE, None);
+    let data_stream_listener = DataStreamListener::new(notification_receiver);
+    assert!(request_message
+        .response_sender
+        .send(Ok(data_stream_listener))
+        .is_ok());
+
+    notification_sender
+}
+
 /// Creates a single test transaction
 pub fn create_test_transaction() -> Transaction {
     let private_key = Ed25519PrivateKey::generate_for_testing();
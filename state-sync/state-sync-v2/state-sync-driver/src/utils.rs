@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error::Error;
+use aptos_crypto::HashValue;
 use aptos_logger::prelude::*;
 use aptos_types::{
     epoch_change::Verifier, epoch_state::EpochState, ledger_info::LedgerInfoWithSignatures,
@@ -14,7 +15,7 @@ use data_streaming_service::{
 };
 use futures::StreamExt;
 use std::{sync::Arc, time::Duration};
-use storage_interface::{DbReader, StartupInfo};
+use storage_interface::{DbReader, StartupInfo, StateSnapshotProgress};
 use tokio::time::timeout;
 
 /// The speculative state that tracks a data stream of transactions or outputs.
@@ -172,6 +173,36 @@ pub fn fetch_latest_synced_version(storage: Arc<dyn DbReader>) -> Result<Version
         .map(|(latest_synced_version, _)| latest_synced_version)
 }
 
+/// Fetches the progress of an interrupted state snapshot restore (if any)
+/// from the specified storage.
+pub fn fetch_state_snapshot_progress(
+    storage: Arc<dyn DbReader>,
+) -> Result<Option<StateSnapshotProgress>, Error> {
+    storage.get_state_snapshot_progress().map_err(|error| {
+        Error::StorageError(format!(
+            "Failed to get the state snapshot progress from storage: {:?}",
+            error
+        ))
+    })
+}
+
+/// Fetches the number of account states already restored in the specified
+/// storage for the state snapshot at the given version and root hash.
+pub fn fetch_num_restored_account_states(
+    storage: Arc<dyn DbReader>,
+    version: Version,
+    expected_root_hash: HashValue,
+) -> Result<u64, Error> {
+    storage
+        .get_num_restored_state_values(version, expected_root_hash)
+        .map_err(|error| {
+            Error::StorageError(format!(
+                "Failed to get the number of restored account states from storage: {:?}",
+                error
+            ))
+        })
+}
+
 /// Fetches the startup info from the specified storage
 fn fetch_startup_info(storage: Arc<dyn DbReader>) -> Result<StartupInfo, Error> {
     let startup_info = storage.get_startup_info().map_err(|error| {
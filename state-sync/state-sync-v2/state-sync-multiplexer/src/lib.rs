@@ -20,6 +20,7 @@ use state_sync_v1::{
 };
 use std::sync::Arc;
 use storage_interface::DbReaderWriter;
+use storage_service_notifications::StorageServiceNotifier;
 use tokio::runtime::Runtime;
 
 /// A struct for holding the various runtimes required by state sync v2.
@@ -67,6 +68,7 @@ impl StateSyncMultiplexer {
     >(
         network: Vec<(NetworkId, StateSyncSender, StateSyncEvents)>,
         mempool_notifier: MempoolNotifier,
+        storage_service_notifier: StorageServiceNotifier,
         consensus_listener: ConsensusNotificationListener,
         storage: DbReaderWriter,
         chunk_executor: Arc<ChunkExecutor>,
@@ -108,6 +110,7 @@ impl StateSyncMultiplexer {
                 storage,
                 chunk_executor,
                 mempool_notifier,
+                storage_service_notifier,
                 consensus_listener,
                 event_subscription_service,
                 aptos_data_client,
@@ -191,6 +194,7 @@ mod tests {
     use std::{collections::HashMap, sync::Arc};
     use storage_interface::DbReaderWriter;
     use storage_service_client::StorageServiceClient;
+    use storage_service_notifications::new_storage_service_notifier_listener_pair;
 
     #[test]
     fn test_new_initialized_configs() {
@@ -209,8 +213,9 @@ mod tests {
         let (node_config, _) = test_config();
         bootstrap_genesis::<AptosVM>(&db_rw, get_genesis_txn(&node_config).unwrap()).unwrap();
 
-        // Create mempool and consensus notifiers
+        // Create mempool, storage service and consensus notifiers
         let (mempool_notifier, _) = new_mempool_notifier_listener_pair();
+        let (storage_service_notifier, _) = new_storage_service_notifier_listener_pair();
         let (_, consensus_listener) = new_consensus_notifier_listener_pair(0);
 
         // Create the event subscription service and a reconfig subscriber
@@ -228,19 +233,21 @@ mod tests {
         // Create a test aptos data client
         let network_client = StorageServiceClient::new(
             MultiNetworkSender::new(HashMap::new()),
-            PeerMetadataStorage::new(&[]),
+            PeerMetadataStorage::new(&[], TimeService::mock()),
         );
         let (aptos_data_client, _) = AptosNetDataClient::new(
             node_config.state_sync.aptos_data_client,
             node_config.state_sync.storage_service,
             TimeService::mock(),
             network_client,
+            vec![],
         );
 
         // Create the multiplexer
         let _ = StateSyncMultiplexer::new(
             vec![],
             mempool_notifier,
+            storage_service_notifier,
             consensus_listener,
             db_rw.clone(),
             Arc::new(ChunkExecutor::<AptosVM>::new(db_rw).unwrap()),
@@ -54,18 +54,20 @@ impl StorageServiceClient {
         }
     }
 
+    /// Sends the request to the given peer, returning the response along with
+    /// the number of bytes received for it.
     pub async fn send_request(
         &self,
         recipient: PeerNetworkId,
         request: StorageServiceRequest,
         timeout: Duration,
-    ) -> Result<StorageServiceResponse, Error> {
-        let message = self
+    ) -> Result<(StorageServiceResponse, usize), Error> {
+        let (message, num_bytes) = self
             .network_sender
-            .send_rpc(recipient, StorageServiceMessage::Request(request), timeout)
+            .send_rpc_with_size(recipient, StorageServiceMessage::Request(request), timeout)
             .await?;
         match message {
-            StorageServiceMessage::Response(Ok(response)) => Ok(response),
+            StorageServiceMessage::Response(Ok(response)) => Ok((response, num_bytes)),
             StorageServiceMessage::Response(Err(err)) => Err(Error::StorageServiceError(err)),
             StorageServiceMessage::Request(_) => Err(Error::RpcError(RpcError::InvalidRpcResponse)),
         }
@@ -99,7 +101,10 @@ pub type StorageServiceMultiSender =
     MultiNetworkSender<StorageServiceMessage, StorageServiceNetworkSender>;
 
 pub fn network_endpoint_config() -> AppConfig {
-    AppConfig::client([ProtocolId::StorageServiceRpc])
+    AppConfig::client([
+        ProtocolId::StorageServiceRpc,
+        ProtocolId::StorageServiceRpcCompressed,
+    ])
 }
 
 // TODO(philiphayes): this is a lot of boilerplate for what is effectively a
@@ -144,4 +149,15 @@ impl ApplicationNetworkSender<StorageServiceMessage> for StorageServiceNetworkSe
             .send_rpc(recipient, ProtocolId::StorageServiceRpc, message, timeout)
             .await
     }
+
+    async fn send_rpc_with_size(
+        &self,
+        recipient: PeerId,
+        message: StorageServiceMessage,
+        timeout: Duration,
+    ) -> Result<(StorageServiceMessage, usize), RpcError> {
+        self.inner
+            .send_rpc_with_size(recipient, ProtocolId::StorageServiceRpc, message, timeout)
+            .await
+    }
 }
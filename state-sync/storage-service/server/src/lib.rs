@@ -6,11 +6,16 @@
 use crate::{
     logging::{LogEntry, LogSchema},
     metrics::{increment_counter, start_timer},
-    network::StorageServiceNetworkEvents,
+    network::{PeerNetworkRequest, ResponseSender, StorageServiceNetworkEvents},
+    optimistic_fetch::{handle_active_optimistic_fetches, OptimisticFetchRequest},
+    response_cache::{ResponseCache, SerializedResponse},
 };
 use ::network::ProtocolId;
-use aptos_config::config::StorageServiceConfig;
-use aptos_infallible::RwLock;
+use aptos_config::{
+    config::StorageServiceConfig,
+    network_id::{NetworkId, PeerNetworkId},
+};
+use aptos_infallible::{Mutex, RwLock};
 use aptos_logger::prelude::*;
 use aptos_time_service::{TimeService, TimeServiceTrait};
 use aptos_types::{
@@ -19,10 +24,11 @@ use aptos_types::{
     transaction::{TransactionListWithProof, TransactionOutputListWithProof, Version},
 };
 use bounded_executor::BoundedExecutor;
-use futures::stream::StreamExt;
+use futures::stream::{BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::{sync::Arc, time::Duration};
+use std::{cmp::min, collections::HashMap, sync::Arc, time::Duration};
 use storage_interface::DbReader;
+use storage_service_notifications::StorageServiceNotificationListener;
 use storage_service_types::{
     AccountStatesChunkWithProofRequest, CompleteDataRange, DataSummary,
     EpochEndingLedgerInfoRequest, ProtocolMetadata, Result, ServerProtocolVersion,
@@ -35,6 +41,8 @@ use tokio::runtime::Handle;
 mod logging;
 mod metrics;
 pub mod network;
+mod optimistic_fetch;
+pub mod response_cache;
 
 #[cfg(test)]
 mod tests;
@@ -43,6 +51,11 @@ mod tests;
 pub const STORAGE_SERVER_VERSION: u64 = 1;
 const SUMMARY_LOG_FREQUENCY_SECS: u64 = 5;
 
+// Precomputation only uses spare capacity: it never takes request slots and is
+// skipped (rather than queued) while the previous precomputation is running.
+const MAX_CONCURRENT_PRECOMPUTATIONS: usize = 1;
+const MAX_NUM_PRECOMPUTED_ACCOUNT_STATES_CHUNKS: u64 = 10;
+
 #[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
 pub enum Error {
     #[error("Invalid request received: {0}")]
@@ -69,15 +82,28 @@ impl Error {
 pub struct StorageServiceServer<T> {
     config: StorageServiceConfig,
     bounded_executor: BoundedExecutor,
+    // A separate executor for precomputing responses (at low priority)
+    precompute_executor: BoundedExecutor,
     storage: T,
-    // TODO(philiphayes): would like a "multi-network" stream here, so we only
-    // need one service for all networks.
-    network_requests: StorageServiceNetworkEvents,
+    // The requests from all networks (a single service handles all networks)
+    network_requests: BoxStream<'static, PeerNetworkRequest>,
     time_service: TimeService,
 
+    // The listener for notifications of newly committed data (sent by state
+    // sync). Taken when the storage summary refresher is spawned.
+    storage_service_listener: Option<StorageServiceNotificationListener>,
+
     // We maintain a cached storage server summary to avoid hitting the DB for
-    // every request. This is refreshed periodically.
+    // every request. This is refreshed periodically (and on new commits).
     cached_storage_server_summary: Arc<RwLock<StorageServerSummary>>,
+
+    // The optimistic fetches (i.e., long-poll requests for new data) held by
+    // the server. These are serviced when new data is committed to storage.
+    optimistic_fetches: Arc<Mutex<HashMap<PeerNetworkId, OptimisticFetchRequest>>>,
+
+    // An LRU cache for commonly requested data items. This avoids hitting the
+    // DB (and rebuilding proofs) when many peers request the same data.
+    lru_response_cache: Arc<Mutex<ResponseCache>>,
 }
 
 impl<T: StorageReaderInterface> StorageServiceServer<T> {
@@ -86,52 +112,99 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         executor: Handle,
         storage: T,
         time_service: TimeService,
-        network_requests: StorageServiceNetworkEvents,
+        network_handles: Vec<(NetworkId, StorageServiceNetworkEvents)>,
+        storage_service_listener: StorageServiceNotificationListener,
     ) -> Self {
         let bounded_executor =
-            BoundedExecutor::new(config.max_concurrent_requests as usize, executor);
+            BoundedExecutor::new(config.max_concurrent_requests as usize, executor.clone());
+        let precompute_executor = BoundedExecutor::new(MAX_CONCURRENT_PRECOMPUTATIONS, executor);
+        let network_requests = network::merge_network_requests(network_handles);
         let cached_storage_server_summary = Arc::new(RwLock::new(StorageServerSummary::default()));
+        let optimistic_fetches = Arc::new(Mutex::new(HashMap::new()));
+        let lru_response_cache = Arc::new(Mutex::new(ResponseCache::new(
+            config.max_lru_cache_size_bytes as usize,
+        )));
 
         Self {
             config,
             bounded_executor,
+            precompute_executor,
             storage,
             network_requests,
             time_service,
+            storage_service_listener: Some(storage_service_listener),
             cached_storage_server_summary,
+            optimistic_fetches,
+            lru_response_cache,
         }
     }
 
-    /// Spawns a non-terminating task that refreshes the cached storage server summary
+    /// Spawns a non-terminating task that refreshes the cached storage server
+    /// summary and services any optimistic fetches that are now ready. This
+    /// happens every time new data is committed (and periodically).
     async fn spawn_storage_summary_refresher(&mut self) {
+        let bounded_executor = self.bounded_executor.clone();
         let config = self.config;
         let storage = self.storage.clone();
         let time_service = self.time_service.clone();
         let cached_storage_server_summary = self.cached_storage_server_summary.clone();
+        let optimistic_fetches = self.optimistic_fetches.clone();
+        let mut storage_service_listener = self
+            .storage_service_listener
+            .take()
+            .expect("The storage summary refresher has already been spawned!");
 
         // Spawn the task
         self.bounded_executor
             .spawn(async move {
                 // Create a ticker for the refresh interval
                 let duration = Duration::from_millis(config.storage_summary_refresh_interval_ms);
-                let ticker = time_service.interval(duration);
+                let ticker = time_service.interval(duration).fuse();
                 futures::pin_mut!(ticker);
 
-                // Periodically refresh the cache
+                // Refresh the cache periodically and whenever new data is committed
                 loop {
-                    ticker.next().await;
+                    futures::select! {
+                        _ = ticker.select_next_some() => {},
+                        _ = storage_service_listener.select_next_some() => {},
+                    }
 
-                    if let Err(error) = refresh_cached_storage_summary(
-                        config,
-                        storage.clone(),
-                        cached_storage_server_summary.clone(),
-                    ) {
+                    // Refresh the cache (this reads from storage, so avoid blocking the runtime)
+                    let refresh_result = tokio::task::spawn_blocking({
+                        let storage = storage.clone();
+                        let cached_storage_server_summary = cached_storage_server_summary.clone();
+                        move || {
+                            refresh_cached_storage_summary(
+                                config,
+                                storage,
+                                cached_storage_server_summary,
+                            )
+                        }
+                    })
+                    .await
+                    .map_err(|error| StorageServiceError::InternalError(error.to_string()))
+                    .and_then(|result| result);
+                    if let Err(error) = refresh_result {
                         let error = format!(
                             "Failed to refresh the cached storage summary! Error: {:?}",
                             error
                         );
                         error!(LogSchema::new(LogEntry::StorageServiceError).message(&error));
                     }
+
+                    // Service any optimistic fetches that are now ready
+                    if let Err(error) = handle_active_optimistic_fetches(
+                        bounded_executor.clone(),
+                        config,
+                        storage.clone(),
+                        cached_storage_server_summary.clone(),
+                        optimistic_fetches.clone(),
+                        time_service.clone(),
+                    )
+                    .await
+                    {
+                        error!(LogSchema::new(LogEntry::OptimisticFetchError).error(&error));
+                    }
                 }
             })
             .await;
@@ -145,26 +218,58 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
         // Handle the storage requests
         while let Some(request) = self.network_requests.next().await {
             // Log the request
-            let (peer, protocol, request, response_sender) = request;
+            let (peer_network_id, protocol, request, response_sender) = request;
             debug!(LogSchema::new(LogEntry::ReceivedStorageRequest)
                 .request(&request)
                 .message(&format!(
                     "Received storage request. Peer: {:?}, protocol: {:?}.",
-                    peer, protocol,
+                    peer_network_id, protocol,
                 )));
 
+            let handler = Handler::new(
+                self.config,
+                self.storage.clone(),
+                self.cached_storage_server_summary.clone(),
+                self.optimistic_fetches.clone(),
+                self.lru_response_cache.clone(),
+                self.time_service.clone(),
+            );
+
+            // Optimistic fetches are only stored (to be serviced when new data
+            // arrives), so we can handle them directly.
+            if request.is_optimistic_fetch_request() {
+                handler.handle_optimistic_fetch_request(
+                    peer_network_id,
+                    protocol,
+                    request,
+                    response_sender,
+                );
+                continue;
+            }
+
             // All handler methods are currently CPU-bound and synchronous
             // I/O-bound, so we want to spawn on the blocking thread pool to
             // avoid starving other async tasks on the same runtime.
-            let config = self.config;
-            let storage = self.storage.clone();
-            let cached_storage_server_summary = self.cached_storage_server_summary.clone();
+            let precompute_executor = self.precompute_executor.clone();
             self.bounded_executor
                 .spawn_blocking(move || {
-                    let response = Handler::new(config, storage, cached_storage_server_summary)
-                        .call(protocol, request);
-                    log_storage_response(&response);
-                    response_sender.send(response);
+                    let response = handler.call(protocol, request.clone());
+                    let precompute_chunks =
+                        response.is_ok() && handler.should_precompute_chunks(&request);
+                    log_serialized_storage_response(&request, &response);
+                    response_sender.send_serialized(response.map(SerializedResponse::into_bytes));
+
+                    // Now that the client has its response, precompute the
+                    // chunks it (and other peers) are likely to request next.
+                    // This is skipped if a precomputation is already running.
+                    if precompute_chunks {
+                        let _ = precompute_executor.try_spawn(async move {
+                            let _ = tokio::task::spawn_blocking(move || {
+                                handler.precompute_account_states_chunks(protocol, &request)
+                            })
+                            .await;
+                        });
+                    }
                 })
                 .await;
         }
@@ -208,6 +313,9 @@ pub struct Handler<T> {
     config: StorageServiceConfig,
     storage: T,
     cached_storage_server_summary: Arc<RwLock<StorageServerSummary>>,
+    optimistic_fetches: Arc<Mutex<HashMap<PeerNetworkId, OptimisticFetchRequest>>>,
+    lru_response_cache: Arc<Mutex<ResponseCache>>,
+    time_service: TimeService,
 }
 
 impl<T: StorageReaderInterface> Handler<T> {
@@ -215,19 +323,53 @@ impl<T: StorageReaderInterface> Handler<T> {
         config: StorageServiceConfig,
         storage: T,
         cached_storage_server_summary: Arc<RwLock<StorageServerSummary>>,
+        optimistic_fetches: Arc<Mutex<HashMap<PeerNetworkId, OptimisticFetchRequest>>>,
+        lru_response_cache: Arc<Mutex<ResponseCache>>,
+        time_service: TimeService,
     ) -> Self {
         Self {
             config,
             storage,
             cached_storage_server_summary,
+            optimistic_fetches,
+            lru_response_cache,
+            time_service,
         }
     }
 
+    /// Stores the optimistic fetch request so that it can be serviced once
+    /// new data arrives (or dropped once it expires). Each peer may only have
+    /// a single optimistic fetch (per network), so any existing fetch for the
+    /// peer is replaced.
+    pub fn handle_optimistic_fetch_request(
+        &self,
+        peer_network_id: PeerNetworkId,
+        protocol: ProtocolId,
+        request: StorageServiceRequest,
+        response_sender: ResponseSender,
+    ) {
+        increment_counter(
+            &metrics::STORAGE_REQUESTS_RECEIVED,
+            protocol,
+            request.get_label().into(),
+        );
+
+        let optimistic_fetch = OptimisticFetchRequest::new(
+            protocol,
+            request,
+            response_sender,
+            self.time_service.clone(),
+        );
+        self.optimistic_fetches
+            .lock()
+            .insert(peer_network_id, optimistic_fetch);
+    }
+
     pub fn call(
         &self,
         protocol: ProtocolId,
         request: StorageServiceRequest,
-    ) -> Result<StorageServiceResponse> {
+    ) -> Result<SerializedResponse> {
         // Update the request count
         increment_counter(
             &metrics::STORAGE_REQUESTS_RECEIVED,
@@ -244,23 +386,19 @@ impl<T: StorageReaderInterface> Handler<T> {
 
         // Process the request
         let response = match &request {
-            StorageServiceRequest::GetAccountStatesChunkWithProof(request) => {
-                self.get_account_states_chunk_with_proof(request)
-            }
-            StorageServiceRequest::GetEpochEndingLedgerInfos(request) => {
-                self.get_epoch_ending_ledger_infos(request)
-            }
-            StorageServiceRequest::GetNumberOfAccountsAtVersion(version) => {
-                self.get_number_of_accounts_at_version(*version)
-            }
-            StorageServiceRequest::GetServerProtocolVersion => self.get_server_protocol_version(),
-            StorageServiceRequest::GetStorageServerSummary => self.get_storage_server_summary(),
-            StorageServiceRequest::GetTransactionOutputsWithProof(request) => {
-                self.get_transaction_outputs_with_proof(request)
-            }
-            StorageServiceRequest::GetTransactionsWithProof(request) => {
-                self.get_transactions_with_proof(request)
+            StorageServiceRequest::GetServerProtocolVersion => self
+                .get_server_protocol_version()
+                .and_then(|response| SerializedResponse::new(protocol, response)),
+            StorageServiceRequest::GetStorageServerSummary => self
+                .get_storage_server_summary()
+                .and_then(|response| SerializedResponse::new(protocol, response)),
+            StorageServiceRequest::GetNewTransactionOutputsWithProof(_)
+            | StorageServiceRequest::GetNewTransactionsWithProof(_) => {
+                Err(Error::UnexpectedErrorEncountered(
+                    "Optimistic fetches must be held until new data is available!".into(),
+                ))
             }
+            _ => self.process_cachable_request(protocol, &request),
         };
 
         // Process the response and handle any errors
@@ -294,6 +432,155 @@ impl<T: StorageReaderInterface> Handler<T> {
         }
     }
 
+    /// Processes a request whose response only depends on the data in storage
+    /// (and not on the state of the server). As such, the serialized response
+    /// can be served from (and stored in) the LRU response cache.
+    fn process_cachable_request(
+        &self,
+        protocol: ProtocolId,
+        request: &StorageServiceRequest,
+    ) -> Result<SerializedResponse, Error> {
+        increment_counter(&metrics::LRU_CACHE_EVENT, protocol, "probe".into());
+
+        // Check if the response is already in the cache
+        if let Some(response) = self.lru_response_cache.lock().get(protocol, request) {
+            increment_counter(&metrics::LRU_CACHE_EVENT, protocol, "cache_hit".into());
+            return Ok(response);
+        }
+
+        // Fetch the data response from storage, serialize it and cache it
+        let response = SerializedResponse::new(protocol, self.fetch_data_response(request)?)?;
+        self.lru_response_cache
+            .lock()
+            .put(protocol, request.clone(), response.clone());
+        Ok(response)
+    }
+
+    /// Fetches the response for the given data request from storage
+    fn fetch_data_response(
+        &self,
+        request: &StorageServiceRequest,
+    ) -> Result<StorageServiceResponse, Error> {
+        match request {
+            StorageServiceRequest::GetAccountStatesChunkWithProof(request) => {
+                self.get_account_states_chunk_with_proof(request)
+            }
+            StorageServiceRequest::GetEpochEndingLedgerInfos(request) => {
+                self.get_epoch_ending_ledger_infos(request)
+            }
+            StorageServiceRequest::GetNumberOfAccountsAtVersion(version) => {
+                self.get_number_of_accounts_at_version(*version)
+            }
+            StorageServiceRequest::GetTransactionOutputsWithProof(request) => {
+                self.get_transaction_outputs_with_proof(request)
+            }
+            StorageServiceRequest::GetTransactionsWithProof(request) => {
+                self.get_transactions_with_proof(request)
+            }
+            request => Err(Error::UnexpectedErrorEncountered(format!(
+                "Unexpected data request: {:?}",
+                request
+            ))),
+        }
+    }
+
+    /// Returns true iff chunks should be precomputed after responding to the
+    /// given request, i.e., precomputation is enabled and the request is for
+    /// an account states chunk.
+    pub fn should_precompute_chunks(&self, request: &StorageServiceRequest) -> bool {
+        self.config.num_precomputed_account_states_chunks > 0
+            && matches!(
+                request,
+                StorageServiceRequest::GetAccountStatesChunkWithProof(_)
+            )
+    }
+
+    /// Precomputes the account states chunks that follow the chunk requested
+    /// by the given request (at the same version and chunk size), and stores
+    /// them in the LRU response cache. This allows the server to quickly
+    /// respond to peers syncing the same account states snapshot. This is a
+    /// no-op if precomputation is disabled or the request isn't for an account
+    /// states chunk.
+    pub fn precompute_account_states_chunks(
+        &self,
+        protocol: ProtocolId,
+        request: &StorageServiceRequest,
+    ) {
+        let num_chunks_to_precompute = min(
+            self.config.num_precomputed_account_states_chunks,
+            MAX_NUM_PRECOMPUTED_ACCOUNT_STATES_CHUNKS,
+        );
+        let request = match request {
+            StorageServiceRequest::GetAccountStatesChunkWithProof(request)
+                if num_chunks_to_precompute > 0 =>
+            {
+                request
+            }
+            _ => return,
+        };
+
+        if let Err(error) =
+            self.precompute_next_account_states_chunks(protocol, request, num_chunks_to_precompute)
+        {
+            warn!(LogSchema::new(LogEntry::StorageServiceError)
+                .error(&error)
+                .message("Failed to precompute the next account states chunks!"));
+        }
+    }
+
+    fn precompute_next_account_states_chunks(
+        &self,
+        protocol: ProtocolId,
+        request: &AccountStatesChunkWithProofRequest,
+        num_chunks_to_precompute: u64,
+    ) -> Result<(), Error> {
+        let number_of_accounts = self.storage.get_number_of_accounts(request.version)?;
+        let chunk_size = request
+            .end_account_index
+            .checked_sub(request.start_account_index)
+            .and_then(|chunk_size| chunk_size.checked_add(1))
+            .ok_or_else(|| Error::InvalidRequest("Invalid account states chunk!".into()))?;
+
+        let mut last_account_index = request.end_account_index;
+        for _ in 0..num_chunks_to_precompute {
+            // Identify the next chunk (if there are still account states left)
+            let start_account_index = match last_account_index.checked_add(1) {
+                Some(start_account_index) if start_account_index < number_of_accounts => {
+                    start_account_index
+                }
+                _ => break,
+            };
+            let end_account_index = min(
+                start_account_index.saturating_add(chunk_size - 1),
+                number_of_accounts - 1,
+            );
+
+            // Fetch and cache the chunk (if it hasn't already been cached)
+            let chunk_request = StorageServiceRequest::GetAccountStatesChunkWithProof(
+                AccountStatesChunkWithProofRequest {
+                    version: request.version,
+                    start_account_index,
+                    end_account_index,
+                },
+            );
+            if !self
+                .lru_response_cache
+                .lock()
+                .contains(protocol, &chunk_request)
+            {
+                let response =
+                    SerializedResponse::new(protocol, self.fetch_data_response(&chunk_request)?)?;
+                self.lru_response_cache
+                    .lock()
+                    .put(protocol, chunk_request, response);
+                increment_counter(&metrics::LRU_CACHE_EVENT, protocol, "precompute".into());
+            }
+            last_account_index = end_account_index;
+        }
+
+        Ok(())
+    }
+
     fn get_account_states_chunk_with_proof(
         &self,
         request: &AccountStatesChunkWithProofRequest,
@@ -695,6 +982,38 @@ fn inclusive_range_len(start: u64, end: u64) -> Result<u64, Error> {
     Ok(len)
 }
 
+/// Logs the serialized response sent by storage for the given peer request
+fn log_serialized_storage_response(
+    request: &StorageServiceRequest,
+    storage_response: &Result<SerializedResponse, StorageServiceError>,
+) {
+    match storage_response {
+        Ok(storage_response) => {
+            let response = format!(
+                "{} ({} bytes)",
+                storage_response.get_label(),
+                storage_response.bytes().len()
+            );
+            if matches!(request, StorageServiceRequest::GetStorageServerSummary) {
+                // We expect peers to be polling our storage server summary frequently,
+                // so only log this response periodically.
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(SUMMARY_LOG_FREQUENCY_SECS)),
+                    {
+                        debug!(LogSchema::new(LogEntry::SentStorageResponse).response(&response));
+                    }
+                );
+            } else {
+                debug!(LogSchema::new(LogEntry::SentStorageResponse).response(&response));
+            }
+        }
+        Err(storage_error) => {
+            let storage_error = format!("{:?}", storage_error); // Use debug formatting
+            debug!(LogSchema::new(LogEntry::SentStorageResponse).response(&storage_error));
+        }
+    };
+}
+
 /// Logs the response sent by storage for a peer request
 fn log_storage_response(storage_response: &Result<StorageServiceResponse, StorageServiceError>) {
     match storage_response {
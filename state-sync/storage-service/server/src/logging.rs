@@ -30,6 +30,7 @@ impl<'a> LogSchema<'a> {
 #[derive(Clone, Copy, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum LogEntry {
+    OptimisticFetchError,
     ReceivedStorageRequest,
     SentStorageResponse,
     StorageServiceError,
@@ -2,16 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::metrics;
-use aptos_config::config::StorageServiceConfig;
+use aptos_config::{
+    config::StorageServiceConfig,
+    network_id::{NetworkId, PeerNetworkId},
+};
 use aptos_types::PeerId;
 use bytes::Bytes;
 use channel::{aptos_channel, message_queues::QueueStyle};
 use futures::{
     channel::oneshot,
     future,
-    stream::{BoxStream, Stream, StreamExt},
+    stream::{select_all, BoxStream, Stream, StreamExt},
 };
 use network::{
+    application::storage::MisbehaviorReporter,
     peer_manager::{ConnectionNotification, PeerManagerNotification},
     protocols::network::{AppConfig, Event, NetworkEvents, NewNetworkEvents, RpcError},
     ProtocolId,
@@ -27,7 +31,10 @@ use storage_service_types::{
 pub fn network_endpoint_config(storage_config: StorageServiceConfig) -> AppConfig {
     let max_network_channel_size = storage_config.max_network_channel_size as usize;
     AppConfig::service(
-        [ProtocolId::StorageServiceRpc],
+        [
+            ProtocolId::StorageServiceRpc,
+            ProtocolId::StorageServiceRpcCompressed,
+        ],
         aptos_channel::Config::new(max_network_channel_size)
             .queue_style(QueueStyle::FIFO)
             .counters(&metrics::PENDING_STORAGE_SERVER_NETWORK_EVENTS),
@@ -36,18 +43,48 @@ pub fn network_endpoint_config(storage_config: StorageServiceConfig) -> AppConfi
 
 pub type NetworkRequest = (PeerId, ProtocolId, StorageServiceRequest, ResponseSender);
 
+/// A request from a peer on any of the networks served by the storage service.
+pub type PeerNetworkRequest = (
+    PeerNetworkId,
+    ProtocolId,
+    StorageServiceRequest,
+    ResponseSender,
+);
+
+/// Merges the requests from all networks into a single stream, identifying
+/// the peer of each request by its network (the same peer may be connected
+/// on several networks).
+pub fn merge_network_requests(
+    network_handles: Vec<(NetworkId, StorageServiceNetworkEvents)>,
+) -> BoxStream<'static, PeerNetworkRequest> {
+    let network_requests = network_handles
+        .into_iter()
+        .map(|(network_id, network_events)| {
+            network_events.map(move |(peer_id, protocol, request, response_sender)| {
+                let peer_network_id = PeerNetworkId::new(network_id, peer_id);
+                (peer_network_id, protocol, request, response_sender)
+            })
+        });
+    select_all(network_requests).boxed()
+}
+
 /// A stream of requests from network. Each request also comes with a callback to
 /// send the response.
 pub struct StorageServiceNetworkEvents(BoxStream<'static, NetworkRequest>);
 
 impl NewNetworkEvents for StorageServiceNetworkEvents {
-    fn new(
+    fn new_with_reporter(
         peer_mgr_notifs_rx: aptos_channel::Receiver<(PeerId, ProtocolId), PeerManagerNotification>,
         connection_notifs_rx: aptos_channel::Receiver<PeerId, ConnectionNotification>,
+        misbehavior_reporter: Option<MisbehaviorReporter>,
     ) -> Self {
-        let events = NetworkEvents::new(peer_mgr_notifs_rx, connection_notifs_rx)
-            .filter_map(|event| future::ready(Self::event_to_request(event)))
-            .boxed();
+        let events = NetworkEvents::new_with_reporter(
+            peer_mgr_notifs_rx,
+            connection_notifs_rx,
+            misbehavior_reporter,
+        )
+        .filter_map(|event| future::ready(Self::event_to_request(event)))
+        .boxed();
 
         Self(events)
     }
@@ -64,7 +101,7 @@ impl StorageServiceNetworkEvents {
                 protocol_id,
                 response_tx,
             ) => {
-                let response_tx = ResponseSender::new(response_tx);
+                let response_tx = ResponseSender::new(protocol_id, response_tx);
                 Some((peer_id, protocol_id, request, response_tx))
             }
             // We don't use DirectSend and don't care about connection events.
@@ -84,19 +121,40 @@ impl Stream for StorageServiceNetworkEvents {
 /// A channel for fulfilling a pending StorageService RPC request.
 /// Provides a more strongly typed interface around the raw RPC response channel.
 pub struct ResponseSender {
+    /// The protocol of the request, the response is encoded the same way.
+    protocol_id: ProtocolId,
     response_tx: oneshot::Sender<Result<Bytes, RpcError>>,
 }
 
 impl ResponseSender {
-    pub fn new(response_tx: oneshot::Sender<Result<Bytes, RpcError>>) -> Self {
-        Self { response_tx }
+    pub fn new(
+        protocol_id: ProtocolId,
+        response_tx: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) -> Self {
+        Self {
+            protocol_id,
+            response_tx,
+        }
     }
 
     pub fn send(self, response: Result<StorageServiceResponse>) {
         let msg = StorageServiceMessage::Response(response);
-        let result = bcs::to_bytes(&msg)
+        let result = self
+            .protocol_id
+            .to_bytes(&msg)
             .map(Bytes::from)
-            .map_err(RpcError::BcsError);
+            .map_err(RpcError::Error);
         let _ = self.response_tx.send(result);
     }
+
+    /// Sends the given response, which is already serialized for the protocol
+    /// of the request (e.g., cached), as is.
+    pub fn send_serialized(self, response: Result<Bytes>) {
+        match response {
+            Ok(response) => {
+                let _ = self.response_tx.send(Ok(response));
+            }
+            Err(error) => self.send(Err(error)),
+        }
+    }
 }
@@ -0,0 +1,282 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    log_storage_response,
+    logging::{LogEntry, LogSchema},
+    metrics,
+    metrics::increment_counter,
+    network::ResponseSender,
+    Error, StorageReaderInterface,
+};
+use aptos_config::{config::StorageServiceConfig, network_id::PeerNetworkId};
+use aptos_infallible::{Mutex, RwLock};
+use aptos_logger::prelude::*;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use aptos_types::{ledger_info::LedgerInfoWithSignatures, transaction::Version};
+use bounded_executor::BoundedExecutor;
+use network::ProtocolId;
+use std::{cmp::min, collections::HashMap, sync::Arc, time::Instant};
+use storage_service_types::{
+    Epoch, StorageServerSummary, StorageServiceError, StorageServiceRequest, StorageServiceResponse,
+};
+
+/// An optimistic fetch request that is held by the server until new data is
+/// available to service it (i.e., data beyond the client's known version and
+/// epoch), or the request expires.
+pub struct OptimisticFetchRequest {
+    protocol: ProtocolId,
+    request: StorageServiceRequest,
+    response_sender: ResponseSender,
+    fetch_start_time: Instant,
+}
+
+impl OptimisticFetchRequest {
+    pub fn new(
+        protocol: ProtocolId,
+        request: StorageServiceRequest,
+        response_sender: ResponseSender,
+        time_service: TimeService,
+    ) -> Self {
+        Self {
+            protocol,
+            request,
+            response_sender,
+            fetch_start_time: time_service.now(),
+        }
+    }
+
+    /// Returns the highest version and epoch known by the client
+    fn highest_known_version_and_epoch(&self) -> (Version, Epoch) {
+        match &self.request {
+            StorageServiceRequest::GetNewTransactionOutputsWithProof(request) => {
+                (request.known_version, request.known_epoch)
+            }
+            StorageServiceRequest::GetNewTransactionsWithProof(request) => {
+                (request.known_version, request.known_epoch)
+            }
+            request => unreachable!("Unexpected optimistic fetch request: {:?}", request),
+        }
+    }
+
+    /// Returns true iff the optimistic fetch has been held for longer than
+    /// the maximum optimistic fetch period.
+    fn is_expired(&self, max_optimistic_fetch_period_ms: u64, time_service: &TimeService) -> bool {
+        let elapsed_time = time_service
+            .now()
+            .duration_since(self.fetch_start_time)
+            .as_millis();
+        elapsed_time >= max_optimistic_fetch_period_ms as u128
+    }
+
+    /// Sends the given response to the client
+    fn send_response(self, response: Result<StorageServiceResponse, StorageServiceError>) {
+        log_storage_response(&response);
+        self.response_sender.send(response);
+    }
+}
+
+/// Handles the active optimistic fetches: expired fetches are removed and
+/// fetches that can now be serviced (i.e., new data has been committed since
+/// the client's known version) are responded to. This is expected to be called
+/// every time new data is committed to storage (and periodically, to remove
+/// expired fetches).
+pub(crate) async fn handle_active_optimistic_fetches<T: StorageReaderInterface>(
+    bounded_executor: BoundedExecutor,
+    config: StorageServiceConfig,
+    storage: T,
+    cached_storage_server_summary: Arc<RwLock<StorageServerSummary>>,
+    optimistic_fetches: Arc<Mutex<HashMap<PeerNetworkId, OptimisticFetchRequest>>>,
+    time_service: TimeService,
+) -> Result<(), Error> {
+    // Fetch the latest synced ledger info
+    let target_ledger_info = match cached_storage_server_summary
+        .read()
+        .data_summary
+        .synced_ledger_info
+        .clone()
+    {
+        Some(target_ledger_info) => target_ledger_info,
+        None => return Ok(()), // We haven't synced any data yet
+    };
+    let target_version = target_ledger_info.ledger_info().version();
+
+    // Identify the optimistic fetches that have expired or are ready
+    let mut expired_fetches = vec![];
+    let mut ready_fetches = vec![];
+    for (peer_network_id, optimistic_fetch) in optimistic_fetches.lock().iter() {
+        if optimistic_fetch.is_expired(config.max_optimistic_fetch_period_ms, &time_service) {
+            expired_fetches.push(*peer_network_id);
+        } else {
+            let (known_version, _) = optimistic_fetch.highest_known_version_and_epoch();
+            if known_version < target_version {
+                ready_fetches.push(*peer_network_id);
+            }
+        }
+    }
+
+    // Remove the expired fetches. Dropping the response sender notifies
+    // the client that the request has failed.
+    for peer_network_id in expired_fetches {
+        optimistic_fetches.lock().remove(&peer_network_id);
+    }
+
+    // Service the ready fetches. Fetching the new data requires synchronous
+    // storage reads, so each fetch is serviced on the blocking thread pool.
+    for peer_network_id in ready_fetches {
+        let optimistic_fetch = match optimistic_fetches.lock().remove(&peer_network_id) {
+            Some(optimistic_fetch) => optimistic_fetch,
+            None => continue, // The fetch was replaced and serviced elsewhere
+        };
+
+        let storage = storage.clone();
+        let target_ledger_info = target_ledger_info.clone();
+        bounded_executor
+            .spawn_blocking(move || {
+                service_optimistic_fetch(config, storage, optimistic_fetch, target_ledger_info)
+            })
+            .await;
+    }
+
+    Ok(())
+}
+
+/// Services the given optimistic fetch by sending the new data (and proof)
+/// to the client.
+fn service_optimistic_fetch<T: StorageReaderInterface>(
+    config: StorageServiceConfig,
+    storage: T,
+    optimistic_fetch: OptimisticFetchRequest,
+    target_ledger_info: LedgerInfoWithSignatures,
+) {
+    let protocol = optimistic_fetch.protocol;
+    let response =
+        get_new_data_with_proof(config, &storage, &optimistic_fetch, &target_ledger_info);
+    match response {
+        Ok(response) => {
+            increment_counter(
+                &metrics::STORAGE_RESPONSES_SENT,
+                protocol,
+                response.get_label().into(),
+            );
+            optimistic_fetch.send_response(Ok(response));
+        }
+        Err(error) => {
+            increment_counter(
+                &metrics::STORAGE_ERRORS_ENCOUNTERED,
+                protocol,
+                error.get_label().into(),
+            );
+            error!(LogSchema::new(LogEntry::OptimisticFetchError)
+                .error(&error)
+                .request(&optimistic_fetch.request));
+
+            let error = match error {
+                Error::InvalidRequest(error) => StorageServiceError::InvalidRequest(error),
+                error => StorageServiceError::InternalError(error.to_string()),
+            };
+            optimistic_fetch.send_response(Err(error));
+        }
+    }
+}
+
+/// Fetches the new data (and proof) required to service the given optimistic
+/// fetch. If the client is in an older epoch, the data is proven relative to
+/// the epoch ending ledger info of that epoch. Otherwise, the data is proven
+/// relative to the given target ledger info.
+fn get_new_data_with_proof<T: StorageReaderInterface>(
+    config: StorageServiceConfig,
+    storage: &T,
+    optimistic_fetch: &OptimisticFetchRequest,
+    target_ledger_info: &LedgerInfoWithSignatures,
+) -> Result<StorageServiceResponse, Error> {
+    // Identify the ledger info to prove the new data against
+    let (known_version, known_epoch) = optimistic_fetch.highest_known_version_and_epoch();
+    let target_epoch = target_ledger_info.ledger_info().epoch();
+    let proof_ledger_info = if known_epoch < target_epoch {
+        let epoch_change_proof = storage.get_epoch_ending_ledger_infos(known_epoch, known_epoch)?;
+        epoch_change_proof
+            .ledger_info_with_sigs
+            .first()
+            .cloned()
+            .ok_or_else(|| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "Missing the epoch ending ledger info for epoch: {:?}",
+                    known_epoch
+                ))
+            })?
+    } else if known_epoch == target_epoch {
+        target_ledger_info.clone()
+    } else {
+        return Err(Error::InvalidRequest(format!(
+            "The known epoch is higher than the synced epoch! Known epoch: {:?}, synced epoch: {:?}",
+            known_epoch, target_epoch
+        )));
+    };
+    let proof_version = proof_ledger_info.ledger_info().version();
+    if known_version >= proof_version {
+        return Err(Error::InvalidRequest(format!(
+            "The known version is not lower than the version of the epoch ending ledger info! \
+             Known version: {:?}, ledger info version: {:?}",
+            known_version, proof_version
+        )));
+    }
+
+    // Fetch the new data, up to the maximum chunk size
+    let start_version = known_version
+        .checked_add(1)
+        .ok_or_else(|| Error::InvalidRequest("Start version has overflown!".into()))?;
+    match &optimistic_fetch.request {
+        StorageServiceRequest::GetNewTransactionOutputsWithProof(_) => {
+            let end_version = calculate_end_version(
+                start_version,
+                proof_version,
+                config.max_transaction_output_chunk_size,
+            )?;
+            let transaction_output_list_with_proof = storage.get_transaction_outputs_with_proof(
+                proof_version,
+                start_version,
+                end_version,
+            )?;
+            Ok(StorageServiceResponse::NewTransactionOutputsWithProof((
+                transaction_output_list_with_proof,
+                proof_ledger_info,
+            )))
+        }
+        StorageServiceRequest::GetNewTransactionsWithProof(request) => {
+            let end_version = calculate_end_version(
+                start_version,
+                proof_version,
+                config.max_transaction_chunk_size,
+            )?;
+            let transaction_list_with_proof = storage.get_transactions_with_proof(
+                proof_version,
+                start_version,
+                end_version,
+                request.include_events,
+            )?;
+            Ok(StorageServiceResponse::NewTransactionsWithProof((
+                transaction_list_with_proof,
+                proof_ledger_info,
+            )))
+        }
+        request => Err(Error::UnexpectedErrorEncountered(format!(
+            "Unexpected optimistic fetch request: {:?}",
+            request
+        ))),
+    }
+}
+
+/// Returns the end version for a chunk starting at `start_version` that
+/// doesn't exceed the `target_version` or the `max_chunk_size`.
+fn calculate_end_version(
+    start_version: Version,
+    target_version: Version,
+    max_chunk_size: u64,
+) -> Result<Version, Error> {
+    let max_end_version = max_chunk_size
+        .checked_sub(1)
+        .and_then(|chunk_size| start_version.checked_add(chunk_size))
+        .ok_or_else(|| Error::UnexpectedErrorEncountered("End version has overflown!".into()))?;
+    Ok(min(max_end_version, target_version))
+}
@@ -0,0 +1,128 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Error;
+use bytes::Bytes;
+use lru::LruCache;
+use network::ProtocolId;
+use storage_service_types::{StorageServiceMessage, StorageServiceRequest, StorageServiceResponse};
+
+/// A response serialized for the protocol of its request (i.e., the bytes of
+/// the `StorageServiceMessage` sent to the client). This allows the server to
+/// send cached responses without cloning or re-serializing them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SerializedResponse {
+    label: &'static str, // The label of the response (for metrics)
+    bytes: Bytes,
+}
+
+impl SerializedResponse {
+    pub fn new(protocol: ProtocolId, response: StorageServiceResponse) -> Result<Self, Error> {
+        let label = response.get_label();
+        let bytes = protocol
+            .to_bytes(&StorageServiceMessage::Response(Ok(response)))
+            .map_err(|error| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "Failed to serialize the response! Error: {:?}",
+                    error
+                ))
+            })?;
+
+        Ok(Self {
+            label,
+            bytes: bytes.into(),
+        })
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        self.label
+    }
+
+    pub fn bytes(&self) -> &Bytes {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Bytes {
+        self.bytes
+    }
+}
+
+/// An LRU cache of serialized responses (keyed by request and protocol),
+/// bounded by the total size of the cached responses.
+pub struct ResponseCache {
+    cache: LruCache<(ProtocolId, StorageServiceRequest), SerializedResponse>,
+    max_size_bytes: usize,
+    size_bytes: usize,
+}
+
+impl ResponseCache {
+    pub fn new(max_size_bytes: usize) -> Self {
+        Self {
+            cache: LruCache::unbounded(),
+            max_size_bytes,
+            size_bytes: 0,
+        }
+    }
+
+    /// Returns the cached response (if any) and marks it as recently used
+    pub fn get(
+        &mut self,
+        protocol: ProtocolId,
+        request: &StorageServiceRequest,
+    ) -> Option<SerializedResponse> {
+        self.cache.get(&(protocol, request.clone())).cloned()
+    }
+
+    /// Returns the cached response (if any) without marking it as recently used
+    pub fn peek(
+        &self,
+        protocol: ProtocolId,
+        request: &StorageServiceRequest,
+    ) -> Option<&SerializedResponse> {
+        self.cache.peek(&(protocol, request.clone()))
+    }
+
+    pub fn contains(&self, protocol: ProtocolId, request: &StorageServiceRequest) -> bool {
+        self.cache.contains(&(protocol, request.clone()))
+    }
+
+    /// Caches the response, evicting the least recently used responses until
+    /// the cache fits in its maximum size. Responses larger than the maximum
+    /// size are never cached.
+    pub fn put(
+        &mut self,
+        protocol: ProtocolId,
+        request: StorageServiceRequest,
+        response: SerializedResponse,
+    ) {
+        let response_size = response.bytes.len();
+        if response_size > self.max_size_bytes {
+            return;
+        }
+
+        if let Some(replaced_response) = self.cache.put((protocol, request), response) {
+            self.size_bytes -= replaced_response.bytes.len();
+        }
+        self.size_bytes += response_size;
+        while self.size_bytes > self.max_size_bytes {
+            match self.cache.pop_lru() {
+                Some((_, evicted_response)) => self.size_bytes -= evicted_response.bytes.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Returns the number of cached responses
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// Returns the total size (in bytes) of the cached responses
+    pub fn size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+}
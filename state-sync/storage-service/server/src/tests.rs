@@ -3,10 +3,15 @@
 
 #![forbid(unsafe_code)]
 
-use crate::{network::StorageServiceNetworkEvents, StorageReader, StorageServiceServer};
+use crate::{
+    network::StorageServiceNetworkEvents,
+    response_cache::{ResponseCache, SerializedResponse},
+    Handler, StorageReader, StorageServiceServer,
+};
 use anyhow::Result;
-use aptos_config::config::StorageServiceConfig;
+use aptos_config::{config::StorageServiceConfig, network_id::NetworkId};
 use aptos_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform};
+use aptos_infallible::{Mutex, RwLock};
 use aptos_logger::Level;
 use aptos_time_service::{MockTimeService, TimeService};
 use aptos_types::{
@@ -27,6 +32,7 @@ use aptos_types::{
     write_set::WriteSet,
     PeerId,
 };
+use bytes::Bytes;
 use channel::aptos_channel;
 use claim::{assert_matches, assert_none, assert_some};
 use futures::channel::oneshot;
@@ -34,14 +40,21 @@ use move_core_types::language_storage::TypeTag;
 use network::{
     peer_manager::PeerManagerNotification,
     protocols::{
-        network::NewNetworkEvents, rpc::InboundRpcRequest, wire::handshake::v1::ProtocolId,
+        network::NewNetworkEvents,
+        rpc::{error::RpcError, InboundRpcRequest},
+        wire::handshake::v1::ProtocolId,
     },
 };
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 use storage_interface::DbReader;
+use storage_service_notifications::StorageServiceNotifier;
 use storage_service_types::{
     AccountStatesChunkWithProofRequest, CompleteDataRange, DataSummary,
-    EpochEndingLedgerInfoRequest, ProtocolMetadata, ServerProtocolVersion, StorageServerSummary,
+    EpochEndingLedgerInfoRequest, NewTransactionOutputsWithProofRequest,
+    NewTransactionsWithProofRequest, ProtocolMetadata, ServerProtocolVersion, StorageServerSummary,
     StorageServiceError, StorageServiceMessage, StorageServiceRequest, StorageServiceResponse,
     TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
 };
@@ -515,10 +528,313 @@ async fn test_get_invalid_epoch_ledger_infos() {
     assert_matches!(response, StorageServiceError::InvalidRequest(_));
 }
 
-/// A wrapper around the inbound network interface/channel for easily sending
-/// mock client requests to a [`StorageServiceServer`].
+#[tokio::test]
+async fn test_get_new_transaction_outputs_with_proof() {
+    let (mut mock_client, service, mock_time) = MockClient::new();
+    tokio::spawn(service.start());
+
+    // Send an optimistic fetch for new transaction outputs
+    let known_version = LAST_TXN_VERSION - 10;
+    let request = StorageServiceRequest::GetNewTransactionOutputsWithProof(
+        NewTransactionOutputsWithProofRequest {
+            known_version,
+            known_epoch: LAST_EPOCH,
+        },
+    );
+    let response_receiver = mock_client.send_request_without_waiting(request);
+
+    // Process another request to ensure the optimistic fetch has been stored
+    let _ = mock_client
+        .send_request(StorageServiceRequest::GetServerProtocolVersion)
+        .await
+        .unwrap();
+
+    // Elapse enough time to force a cache update
+    let cache_update_freq_ms = StorageServiceConfig::default().storage_summary_refresh_interval_ms;
+    mock_time.advance_ms_async(cache_update_freq_ms).await;
+
+    // Verify the response contains the new outputs and the latest ledger info
+    let response = mock_client
+        .wait_for_response(response_receiver)
+        .await
+        .unwrap();
+    match response {
+        StorageServiceResponse::NewTransactionOutputsWithProof((
+            outputs_with_proof,
+            ledger_info,
+        )) => {
+            assert_eq!(
+                outputs_with_proof.transactions_and_outputs.len() as u64,
+                LAST_TXN_VERSION - known_version
+            );
+            assert_eq!(
+                outputs_with_proof.first_transaction_output_version,
+                Some(known_version + 1)
+            );
+            assert_eq!(
+                ledger_info,
+                create_test_ledger_info_with_sigs(LAST_EPOCH, LAST_TXN_VERSION)
+            );
+        }
+        _ => panic!("Expected new outputs with proof but got: {:?}", response),
+    };
+}
+
+#[tokio::test]
+async fn test_get_new_transactions_with_proof_expiry() {
+    let (mut mock_client, service, mock_time) = MockClient::new();
+    tokio::spawn(service.start());
+
+    // Send an optimistic fetch for new transactions (when we're already up-to-date)
+    let request =
+        StorageServiceRequest::GetNewTransactionsWithProof(NewTransactionsWithProofRequest {
+            known_version: LAST_TXN_VERSION,
+            known_epoch: LAST_EPOCH,
+            include_events: false,
+        });
+    let mut response_receiver = mock_client.send_request_without_waiting(request);
+
+    // Process another request to ensure the optimistic fetch has been stored
+    let _ = mock_client
+        .send_request(StorageServiceRequest::GetServerProtocolVersion)
+        .await
+        .unwrap();
+
+    // Elapse enough time to force a cache update and verify no response is sent
+    let storage_config = StorageServiceConfig::default();
+    let cache_update_freq_ms = storage_config.storage_summary_refresh_interval_ms;
+    mock_time.advance_ms_async(cache_update_freq_ms).await;
+    assert_none!(response_receiver.try_recv().unwrap());
+
+    // Elapse enough time for the optimistic fetch to expire
+    let num_cache_updates = storage_config.max_optimistic_fetch_period_ms / cache_update_freq_ms;
+    for _ in 0..num_cache_updates {
+        mock_time.advance_ms_async(cache_update_freq_ms).await;
+    }
+
+    // Verify the optimistic fetch was dropped
+    assert_matches!(response_receiver.await, Err(oneshot::Canceled));
+}
+
+#[tokio::test]
+async fn test_get_new_transaction_outputs_with_proof_on_commit() {
+    let (mut mock_client, service, _) = MockClient::new();
+    tokio::spawn(service.start());
+
+    // Send an optimistic fetch for new transaction outputs
+    let known_version = LAST_TXN_VERSION - 10;
+    let request = StorageServiceRequest::GetNewTransactionOutputsWithProof(
+        NewTransactionOutputsWithProofRequest {
+            known_version,
+            known_epoch: LAST_EPOCH,
+        },
+    );
+    let response_receiver = mock_client.send_request_without_waiting(request);
+
+    // Process another request to ensure the optimistic fetch has been stored
+    let _ = mock_client
+        .send_request(StorageServiceRequest::GetServerProtocolVersion)
+        .await
+        .unwrap();
+
+    // Notify the server of a new commit (without elapsing any time)
+    mock_client.notify_new_commit(LAST_TXN_VERSION);
+
+    // Verify the optimistic fetch is serviced
+    let response = mock_client
+        .wait_for_response(response_receiver)
+        .await
+        .unwrap();
+    assert_matches!(
+        response,
+        StorageServiceResponse::NewTransactionOutputsWithProof(_)
+    );
+}
+
+#[tokio::test]
+async fn test_get_new_transactions_with_proof_multiple_networks() {
+    let (mut mock_client, service, _) = MockClient::new();
+    tokio::spawn(service.start());
+
+    // Send an optimistic fetch from the same peer on different networks
+    let known_version = LAST_TXN_VERSION - 10;
+    let request =
+        StorageServiceRequest::GetNewTransactionsWithProof(NewTransactionsWithProofRequest {
+            known_version,
+            known_epoch: LAST_EPOCH,
+            include_events: false,
+        });
+    let mut response_receivers = vec![];
+    for network_id in [NetworkId::Validator, NetworkId::Public] {
+        let response_receiver =
+            mock_client.send_network_request_without_waiting(network_id, request.clone());
+        response_receivers.push(response_receiver);
+    }
+
+    // Process another request to ensure the optimistic fetches have been stored
+    let _ = mock_client
+        .send_request(StorageServiceRequest::GetServerProtocolVersion)
+        .await
+        .unwrap();
+
+    // Notify the server of a new commit and verify both optimistic fetches are serviced
+    mock_client.notify_new_commit(LAST_TXN_VERSION);
+    for response_receiver in response_receivers {
+        let response = mock_client
+            .wait_for_response(response_receiver)
+            .await
+            .unwrap();
+        assert_matches!(
+            response,
+            StorageServiceResponse::NewTransactionsWithProof(_)
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_cached_responses() {
+    let (mut mock_client, service, _) = MockClient::new();
+    let lru_response_cache = service.lru_response_cache.clone();
+    tokio::spawn(service.start());
+
+    // Process a request to fetch transaction outputs with a proof
+    let request =
+        StorageServiceRequest::GetTransactionOutputsWithProof(TransactionOutputsWithProofRequest {
+            proof_version: LAST_TXN_VERSION,
+            start_version: FIRST_TXN_OUTPUT_VERSION,
+            end_version: FIRST_TXN_OUTPUT_VERSION + 10,
+        });
+    let response = mock_client.send_request(request.clone()).await.unwrap();
+
+    // Verify the serialized response was cached
+    let protocol = ProtocolId::StorageServiceRpc;
+    assert_eq!(
+        lru_response_cache.lock().peek(protocol, &request).cloned(),
+        Some(SerializedResponse::new(protocol, response.clone()).unwrap())
+    );
+
+    // Process the same request again and verify the cached response is returned
+    let cached_response = mock_client.send_request(request.clone()).await.unwrap();
+    assert_eq!(cached_response, response);
+
+    // Verify the response isn't cached for other protocols (the serialization differs)
+    assert!(!lru_response_cache
+        .lock()
+        .contains(ProtocolId::StorageServiceRpcCompressed, &request));
+
+    // Verify that responses for server state (e.g., the protocol version) aren't cached
+    let request = StorageServiceRequest::GetServerProtocolVersion;
+    let _ = mock_client.send_request(request.clone()).await.unwrap();
+    assert!(!lru_response_cache.lock().contains(protocol, &request));
+}
+
+#[test]
+fn test_response_cache_bounded_by_size() {
+    // Create serialized responses for several epoch ending ledger info requests
+    let protocol = ProtocolId::StorageServiceRpc;
+    let requests_and_responses: Vec<_> = (0..10)
+        .map(|epoch| {
+            let request =
+                StorageServiceRequest::GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest {
+                    start_epoch: epoch,
+                    expected_end_epoch: epoch,
+                });
+            let response = StorageServiceResponse::EpochEndingLedgerInfos(EpochChangeProof::new(
+                vec![create_test_ledger_info_with_sigs(epoch, epoch)],
+                false,
+            ));
+            (
+                request,
+                SerializedResponse::new(protocol, response).unwrap(),
+            )
+        })
+        .collect();
+    let response_size = requests_and_responses[0].1.bytes().len();
+
+    // Create a cache that only fits three responses and cache all the responses
+    let mut response_cache = ResponseCache::new(3 * response_size);
+    for (request, response) in requests_and_responses.clone() {
+        response_cache.put(protocol, request, response);
+        assert!(response_cache.size_bytes() <= 3 * response_size);
+    }
+
+    // Verify only the three most recently used responses are still cached
+    assert_eq!(response_cache.len(), 3);
+    for (index, (request, response)) in requests_and_responses.iter().enumerate() {
+        if index < 7 {
+            assert!(!response_cache.contains(protocol, request));
+        } else {
+            assert_eq!(
+                response_cache.get(protocol, request).as_ref(),
+                Some(response)
+            );
+        }
+    }
+
+    // Verify responses larger than the cache are never cached
+    let mut response_cache = ResponseCache::new(response_size - 1);
+    let (request, response) = requests_and_responses[0].clone();
+    response_cache.put(protocol, request.clone(), response);
+    assert!(response_cache.is_empty());
+    assert_eq!(response_cache.size_bytes(), 0);
+}
+
+#[test]
+fn test_precompute_account_states_chunks() {
+    initialize_logger();
+
+    // Create a handler that precomputes the next account states chunks
+    let num_precomputed_account_states_chunks = 3;
+    let storage_config = StorageServiceConfig {
+        num_precomputed_account_states_chunks,
+        ..Default::default()
+    };
+    let lru_response_cache = Arc::new(Mutex::new(ResponseCache::new(
+        storage_config.max_lru_cache_size_bytes as usize,
+    )));
+    let handler = Handler::new(
+        storage_config,
+        StorageReader::new(storage_config, Arc::new(MockDbReader)),
+        Arc::new(RwLock::new(StorageServerSummary::default())),
+        Arc::new(Mutex::new(HashMap::new())),
+        lru_response_cache.clone(),
+        TimeService::mock(),
+    );
+
+    // Precompute the chunks that follow the chunk ending two chunks before the last account
+    let chunk_size = 300;
+    let start_account_index = NUM_ACCOUNTS_AT_VERSION - (3 * chunk_size);
+    let request =
+        StorageServiceRequest::GetAccountStatesChunkWithProof(AccountStatesChunkWithProofRequest {
+            version: 0,
+            start_account_index,
+            end_account_index: start_account_index + chunk_size - 1,
+        });
+    handler.precompute_account_states_chunks(ProtocolId::StorageServiceRpc, &request);
+
+    // Verify the next two chunks were cached (there are no more accounts after them)
+    assert_eq!(lru_response_cache.lock().len(), 2);
+    for chunk_index in 1..3 {
+        let chunk_start_index = start_account_index + (chunk_index * chunk_size);
+        let chunk_request = StorageServiceRequest::GetAccountStatesChunkWithProof(
+            AccountStatesChunkWithProofRequest {
+                version: 0,
+                start_account_index: chunk_start_index,
+                end_account_index: chunk_start_index + chunk_size - 1,
+            },
+        );
+        assert_some!(lru_response_cache
+            .lock()
+            .peek(ProtocolId::StorageServiceRpc, &chunk_request));
+    }
+}
+
+/// A wrapper around the inbound network interfaces/channels for easily sending
+/// mock client requests (on any network) to a [`StorageServiceServer`].
 struct MockClient {
-    peer_mgr_notifs_tx: aptos_channel::Sender<(PeerId, ProtocolId), PeerManagerNotification>,
+    peer_mgr_notifs_txs:
+        HashMap<NetworkId, aptos_channel::Sender<(PeerId, ProtocolId), PeerManagerNotification>>,
+    storage_service_notifier: StorageServiceNotifier,
 }
 
 impl MockClient {
@@ -527,13 +843,21 @@ impl MockClient {
         let storage_config = StorageServiceConfig::default();
         let storage = StorageReader::new(storage_config, Arc::new(MockDbReader));
 
-        let queue_cfg = crate::network::network_endpoint_config(storage_config)
-            .inbound_queue
-            .unwrap();
-        let (peer_mgr_notifs_tx, peer_mgr_notifs_rx) = queue_cfg.build();
-        let (_connection_notifs_tx, connection_notifs_rx) = queue_cfg.build();
-        let network_requests =
-            StorageServiceNetworkEvents::new(peer_mgr_notifs_rx, connection_notifs_rx);
+        let mut network_handles = vec![];
+        let mut peer_mgr_notifs_txs = HashMap::new();
+        for network_id in [NetworkId::Validator, NetworkId::Public] {
+            let queue_cfg = crate::network::network_endpoint_config(storage_config)
+                .inbound_queue
+                .unwrap();
+            let (peer_mgr_notifs_tx, peer_mgr_notifs_rx) = queue_cfg.build();
+            let (_connection_notifs_tx, connection_notifs_rx) = queue_cfg.build();
+            let network_requests =
+                StorageServiceNetworkEvents::new(peer_mgr_notifs_rx, connection_notifs_rx);
+            network_handles.push((network_id, network_requests));
+            peer_mgr_notifs_txs.insert(network_id, peer_mgr_notifs_tx);
+        }
+        let (storage_service_notifier, storage_service_listener) =
+            storage_service_notifications::new_storage_service_notifier_listener_pair();
 
         let executor = tokio::runtime::Handle::current();
         let mock_time_service = TimeService::mock();
@@ -542,10 +866,14 @@ impl MockClient {
             executor,
             storage,
             mock_time_service.clone(),
-            network_requests,
+            network_handles,
+            storage_service_listener,
         );
 
-        let mock_client = Self { peer_mgr_notifs_tx };
+        let mock_client = Self {
+            peer_mgr_notifs_txs,
+            storage_service_notifier,
+        };
         (mock_client, storage_server, mock_time_service.into_mock())
     }
 
@@ -553,6 +881,26 @@ impl MockClient {
         &mut self,
         request: StorageServiceRequest,
     ) -> Result<StorageServiceResponse, StorageServiceError> {
+        let response_receiver = self.send_request_without_waiting(request);
+        self.wait_for_response(response_receiver).await
+    }
+
+    /// Sends the request to the storage service (on the validator network)
+    /// and returns the receiver on which the response will be delivered.
+    fn send_request_without_waiting(
+        &mut self,
+        request: StorageServiceRequest,
+    ) -> oneshot::Receiver<Result<Bytes, RpcError>> {
+        self.send_network_request_without_waiting(NetworkId::Validator, request)
+    }
+
+    /// Sends the request to the storage service on the specified network and
+    /// returns the receiver on which the response will be delivered.
+    fn send_network_request_without_waiting(
+        &mut self,
+        network_id: NetworkId,
+        request: StorageServiceRequest,
+    ) -> oneshot::Receiver<Result<Bytes, RpcError>> {
         // craft the inbound Rpc notification
         let peer_id = PeerId::ZERO;
         let protocol_id = ProtocolId::StorageServiceRpc;
@@ -568,12 +916,30 @@ impl MockClient {
         let notif = PeerManagerNotification::RecvRpc(peer_id, inbound_rpc);
 
         // push it up to the storage service
-        self.peer_mgr_notifs_tx
+        self.peer_mgr_notifs_txs
+            .get(&network_id)
+            .unwrap()
             .push((peer_id, protocol_id), notif)
             .unwrap();
 
+        res_rx
+    }
+
+    /// Notifies the storage service that new data has been committed
+    fn notify_new_commit(&self, highest_synced_version: Version) {
+        self.storage_service_notifier
+            .notify_new_commit(highest_synced_version)
+            .unwrap();
+    }
+
+    /// Waits for the response on the given receiver and deserializes it
+    async fn wait_for_response(
+        &mut self,
+        response_receiver: oneshot::Receiver<Result<Bytes, RpcError>>,
+    ) -> Result<StorageServiceResponse, StorageServiceError> {
         // wait for the response and deserialize
-        let response = res_rx.await.unwrap().unwrap();
+        let protocol_id = ProtocolId::StorageServiceRpc;
+        let response = response_receiver.await.unwrap().unwrap();
         let response = protocol_id
             .from_bytes::<StorageServiceMessage>(&response)
             .unwrap();
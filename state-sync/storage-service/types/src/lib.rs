@@ -51,7 +51,7 @@ pub enum StorageServiceMessage {
 }
 
 /// A storage service request.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub enum StorageServiceRequest {
     GetAccountStatesChunkWithProof(AccountStatesChunkWithProofRequest), // Fetches a list of account states with a proof
     GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest), // Fetches a list of epoch ending ledger infos
@@ -60,6 +60,8 @@ pub enum StorageServiceRequest {
     GetStorageServerSummary,               // Fetches a summary of the storage server state
     GetTransactionOutputsWithProof(TransactionOutputsWithProofRequest), // Fetches a list of transaction outputs with a proof
     GetTransactionsWithProof(TransactionsWithProofRequest), // Fetches a list of transactions with a proof
+    GetNewTransactionOutputsWithProof(NewTransactionOutputsWithProofRequest), // Optimistically fetches new transaction outputs
+    GetNewTransactionsWithProof(NewTransactionsWithProofRequest), // Optimistically fetches new transactions
 }
 
 impl StorageServiceRequest {
@@ -73,12 +75,23 @@ impl StorageServiceRequest {
             Self::GetStorageServerSummary => "get_storage_server_summary",
             Self::GetTransactionOutputsWithProof(_) => "get_transaction_outputs_with_proof",
             Self::GetTransactionsWithProof(_) => "get_transactions_with_proof",
+            Self::GetNewTransactionOutputsWithProof(_) => "get_new_transaction_outputs_with_proof",
+            Self::GetNewTransactionsWithProof(_) => "get_new_transactions_with_proof",
         }
     }
 
     pub fn is_get_storage_server_summary(&self) -> bool {
         matches!(self, &Self::GetStorageServerSummary)
     }
+
+    /// Returns true iff the request is an optimistic fetch, i.e., a request
+    /// that the server holds until new data is available (or it expires).
+    pub fn is_optimistic_fetch_request(&self) -> bool {
+        matches!(
+            self,
+            &Self::GetNewTransactionOutputsWithProof(_) | &Self::GetNewTransactionsWithProof(_)
+        )
+    }
 }
 
 /// A storage service response.
@@ -93,6 +106,8 @@ pub enum StorageServiceResponse {
     StorageServerSummary(StorageServerSummary),
     TransactionOutputsWithProof(TransactionOutputListWithProof),
     TransactionsWithProof(TransactionListWithProof),
+    NewTransactionOutputsWithProof((TransactionOutputListWithProof, LedgerInfoWithSignatures)),
+    NewTransactionsWithProof((TransactionListWithProof, LedgerInfoWithSignatures)),
 }
 
 // TODO(philiphayes): is there a proc-macro for this?
@@ -107,6 +122,8 @@ impl StorageServiceResponse {
             Self::StorageServerSummary(_) => "storage_server_summary",
             Self::TransactionOutputsWithProof(_) => "transaction_outputs_with_proof",
             Self::TransactionsWithProof(_) => "transactions_with_proof",
+            Self::NewTransactionOutputsWithProof(_) => "new_transaction_outputs_with_proof",
+            Self::NewTransactionsWithProof(_) => "new_transactions_with_proof",
         }
     }
 }
@@ -227,9 +244,37 @@ impl TryFrom<StorageServiceResponse> for TransactionListWithProof {
     }
 }
 
+impl TryFrom<StorageServiceResponse>
+    for (TransactionOutputListWithProof, LedgerInfoWithSignatures)
+{
+    type Error = UnexpectedResponseError;
+    fn try_from(response: StorageServiceResponse) -> Result<Self, Self::Error> {
+        match response {
+            StorageServiceResponse::NewTransactionOutputsWithProof(inner) => Ok(inner),
+            _ => Err(UnexpectedResponseError(format!(
+                "expected new_transaction_outputs_with_proof, found {}",
+                response.get_label()
+            ))),
+        }
+    }
+}
+
+impl TryFrom<StorageServiceResponse> for (TransactionListWithProof, LedgerInfoWithSignatures) {
+    type Error = UnexpectedResponseError;
+    fn try_from(response: StorageServiceResponse) -> Result<Self, Self::Error> {
+        match response {
+            StorageServiceResponse::NewTransactionsWithProof(inner) => Ok(inner),
+            _ => Err(UnexpectedResponseError(format!(
+                "expected new_transactions_with_proof, found {}",
+                response.get_label()
+            ))),
+        }
+    }
+}
+
 /// A storage service request for fetching a list of account states at a
 /// specified version.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct AccountStatesChunkWithProofRequest {
     pub version: u64,             // The version to fetch the account states at
     pub start_account_index: u64, // The account index to start fetching account states
@@ -238,7 +283,7 @@ pub struct AccountStatesChunkWithProofRequest {
 
 /// A storage service request for fetching a transaction output list with a
 /// corresponding proof.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct TransactionOutputsWithProofRequest {
     pub proof_version: u64, // The version the proof should be relative to
     pub start_version: u64, // The starting version of the transaction output list
@@ -247,7 +292,7 @@ pub struct TransactionOutputsWithProofRequest {
 
 /// A storage service request for fetching a transaction list with a
 /// corresponding proof.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct TransactionsWithProofRequest {
     pub proof_version: u64,   // The version the proof should be relative to
     pub start_version: u64,   // The starting version of the transaction list
@@ -255,8 +300,27 @@ pub struct TransactionsWithProofRequest {
     pub include_events: bool, // Whether or not to include events in the response
 }
 
+/// An optimistic fetch request for transaction outputs (with a proof) that
+/// are newer than the highest version and epoch known by the client. The
+/// server holds the request until new data is available or it expires.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct NewTransactionOutputsWithProofRequest {
+    pub known_version: u64, // The highest version known by the client
+    pub known_epoch: u64,   // The highest epoch known by the client
+}
+
+/// An optimistic fetch request for transactions (with a proof) that are
+/// newer than the highest version and epoch known by the client. The server
+/// holds the request until new data is available or it expires.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct NewTransactionsWithProofRequest {
+    pub known_version: u64,   // The highest version known by the client
+    pub known_epoch: u64,     // The highest epoch known by the client
+    pub include_events: bool, // Whether or not to include events in the response
+}
+
 /// A storage service request for fetching a list of epoch ending ledger infos.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct EpochEndingLedgerInfoRequest {
     pub start_epoch: u64,
     pub expected_end_epoch: u64,
@@ -300,7 +364,9 @@ impl ProtocolMetadata {
         match request {
             GetServerProtocolVersion
             | GetStorageServerSummary
-            | GetNumberOfAccountsAtVersion(_) => true,
+            | GetNumberOfAccountsAtVersion(_)
+            | GetNewTransactionOutputsWithProof(_)
+            | GetNewTransactionsWithProof(_) => true,
             GetAccountStatesChunkWithProof(request) => {
                 CompleteDataRange::new(request.start_account_index, request.end_account_index)
                     .map_or(false, |range| {
@@ -452,8 +518,34 @@ impl DataSummary {
 
                 can_serve_txns && can_create_proof
             }
+            GetNewTransactionOutputsWithProof(request) => {
+                self.can_service_optimistic_fetch(request.known_version, self.transaction_outputs)
+            }
+            GetNewTransactionsWithProof(request) => {
+                self.can_service_optimistic_fetch(request.known_version, self.transactions)
+            }
         }
     }
+
+    /// Returns true iff an optimistic fetch for data newer than the
+    /// `known_version` can be serviced. This requires that the server is
+    /// synced to at least the known version and holds the relevant data.
+    fn can_service_optimistic_fetch(
+        &self,
+        known_version: Version,
+        data_range: Option<CompleteDataRange<Version>>,
+    ) -> bool {
+        let synced_to_known_version = self
+            .synced_ledger_info
+            .as_ref()
+            .map(|li| li.ledger_info().version() >= known_version)
+            .unwrap_or(false);
+        let can_serve_data = data_range
+            .map(|range| range.contains(known_version))
+            .unwrap_or(false);
+
+        synced_to_known_version && can_serve_data
+    }
 }
 
 #[derive(Clone, Debug, Error)]
@@ -650,6 +742,23 @@ mod tests {
         get_account_state_chunks_request(version, 0, 1000)
     }
 
+    fn get_new_txns_request(known_version: Version) -> StorageServiceRequest {
+        StorageServiceRequest::GetNewTransactionsWithProof(NewTransactionsWithProofRequest {
+            known_version,
+            known_epoch: 0,
+            include_events: true,
+        })
+    }
+
+    fn get_new_txn_outputs_request(known_version: Version) -> StorageServiceRequest {
+        StorageServiceRequest::GetNewTransactionOutputsWithProof(
+            NewTransactionOutputsWithProofRequest {
+                known_version,
+                known_epoch: 0,
+            },
+        )
+    }
+
     #[test]
     fn test_complete_data_range() {
         // good ranges
@@ -788,6 +897,37 @@ mod tests {
         assert!(!summary.can_service(&get_account_states_request(99)));
     }
 
+    #[test]
+    fn test_data_summary_can_service_optimistic_fetch_request() {
+        let summary = DataSummary {
+            synced_ledger_info: Some(mock_ledger_info(250)),
+            transactions: Some(range(100, 250)),
+            transaction_outputs: Some(range(150, 250)),
+            ..Default::default()
+        };
+
+        // synced to the known version and holds the data => can service
+        assert!(summary.can_service(&get_new_txns_request(100)));
+        assert!(summary.can_service(&get_new_txns_request(250)));
+        assert!(summary.can_service(&get_new_txn_outputs_request(150)));
+        assert!(summary.can_service(&get_new_txn_outputs_request(250)));
+
+        // not synced to the known version => cannot service
+        assert!(!summary.can_service(&get_new_txns_request(251)));
+        assert!(!summary.can_service(&get_new_txn_outputs_request(300)));
+
+        // synced, but the data has been pruned => cannot service
+        assert!(!summary.can_service(&get_new_txns_request(99)));
+        assert!(!summary.can_service(&get_new_txn_outputs_request(149)));
+
+        // no synced ledger info => cannot service
+        let summary = DataSummary {
+            transactions: Some(range(100, 250)),
+            ..Default::default()
+        };
+        assert!(!summary.can_service(&get_new_txns_request(200)));
+    }
+
     #[test]
     fn test_protocol_metadata_can_service() {
         let metadata = ProtocolMetadata {
@@ -176,6 +176,214 @@ fn test_sync_transactions_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoW
     }
 }
 
+fn test_save_transaction_history_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let src_tmp_dir = TempPath::new();
+    let src_db = AptosDB::new_for_test(&src_tmp_dir);
+    let tgt_tmp_dir = TempPath::new();
+    let tgt_db = AptosDB::new_for_test(&tgt_tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        src_db
+            .save_transactions(
+                txns_to_commit,
+                cur_ver, /* first_version */
+                Some(ledger_info_with_sigs),
+            )
+            .unwrap();
+        let ledger_version = ledger_info_with_sigs.ledger_info().version();
+        let num_txns = txns_to_commit.len() as u64;
+        let txn_list_with_proof = src_db
+            .get_transactions(
+                cur_ver,
+                num_txns,
+                ledger_version,
+                true, /* fetch_events */
+            )
+            .unwrap();
+
+        // The transaction events are required
+        let mut txn_list_without_events = txn_list_with_proof.clone();
+        txn_list_without_events.events = None;
+        assert!(tgt_db
+            .save_transaction_history(txn_list_without_events, Some(ledger_info_with_sigs))
+            .is_err());
+
+        // The transactions must start at the next version of the DB
+        let mut txn_list_with_gap = txn_list_with_proof.clone();
+        txn_list_with_gap.first_transaction_version = Some(cur_ver + 1);
+        assert!(tgt_db
+            .save_transaction_history(txn_list_with_gap, Some(ledger_info_with_sigs))
+            .is_err());
+
+        tgt_db
+            .save_transaction_history(txn_list_with_proof.clone(), Some(ledger_info_with_sigs))
+            .unwrap();
+        assert_eq!(
+            tgt_db.ledger_store.get_latest_ledger_info().unwrap(),
+            *ledger_info_with_sigs
+        );
+        assert_eq!(
+            tgt_db
+                .get_transactions(
+                    cur_ver,
+                    num_txns,
+                    ledger_version,
+                    true /* fetch_events */
+                )
+                .unwrap(),
+            txn_list_with_proof
+        );
+
+        // Saved transactions can't be saved again
+        assert!(tgt_db
+            .save_transaction_history(txn_list_with_proof, Some(ledger_info_with_sigs))
+            .is_err());
+
+        cur_ver += num_txns;
+    }
+}
+
+fn test_backfill_transaction_history_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let src_tmp_dir = TempPath::new();
+    let src_db = AptosDB::new_for_test(&src_tmp_dir);
+    let tgt_tmp_dir = TempPath::new();
+    let tgt_db = AptosDB::new_for_test(&tgt_tmp_dir);
+
+    let mut cur_ver = 0;
+    let mut batch_ranges = Vec::new();
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        src_db
+            .save_transactions(
+                txns_to_commit,
+                cur_ver, /* first_version */
+                Some(ledger_info_with_sigs),
+            )
+            .unwrap();
+        batch_ranges.push((cur_ver, txns_to_commit.len() as u64));
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_ledger_info = input.last().unwrap().1.clone();
+    let latest_version = latest_ledger_info.ledger_info().version();
+
+    // Without any history, there is nothing to backfill
+    let txn_list_with_proof = src_db
+        .get_transactions(0, 1, latest_version, true /* fetch_events */)
+        .unwrap();
+    assert!(tgt_db
+        .backfill_transaction_history(txn_list_with_proof, &latest_ledger_info)
+        .is_err());
+
+    // The target only has the latest version, as if it bootstrapped from a state snapshot
+    let output_with_proof = src_db
+        .get_transaction_outputs(latest_version, 1, latest_version)
+        .unwrap();
+    tgt_db
+        .finalize_state_snapshot(latest_version, output_with_proof)
+        .unwrap();
+    tgt_db
+        .save_ledger_infos(&[latest_ledger_info.clone()])
+        .unwrap();
+    assert_eq!(
+        tgt_db.get_transaction_history_backfill_progress().unwrap(),
+        None
+    );
+
+    for (first_version, num_txns) in batch_ranges {
+        // The stored history isn't backfilled again
+        let num_txns = std::cmp::min(num_txns, latest_version - first_version);
+        if num_txns == 0 {
+            let txn_list_with_proof = src_db
+                .get_transactions(
+                    first_version,
+                    1,
+                    latest_version,
+                    true, /* fetch_events */
+                )
+                .unwrap();
+            assert!(tgt_db
+                .backfill_transaction_history(txn_list_with_proof, &latest_ledger_info)
+                .is_err());
+            continue;
+        }
+        let txn_list_with_proof = src_db
+            .get_transactions(
+                first_version,
+                num_txns,
+                latest_version,
+                true, /* fetch_events */
+            )
+            .unwrap();
+
+        // The transaction events are required
+        let mut txn_list_without_events = txn_list_with_proof.clone();
+        txn_list_without_events.events = None;
+        assert!(tgt_db
+            .backfill_transaction_history(txn_list_without_events, &latest_ledger_info)
+            .is_err());
+
+        // The transactions must start at the next backfill version
+        let mut txn_list_with_gap = txn_list_with_proof.clone();
+        txn_list_with_gap.first_transaction_version = Some(first_version + 1);
+        assert!(tgt_db
+            .backfill_transaction_history(txn_list_with_gap, &latest_ledger_info)
+            .is_err());
+
+        // The transactions must be proven by the stored transaction accumulator
+        let mut tampered_txn_list = txn_list_with_proof.clone();
+        tampered_txn_list.proof.transaction_infos.reverse();
+        tampered_txn_list.transactions.reverse();
+        tampered_txn_list.events.as_mut().unwrap().reverse();
+        if num_txns > 1 {
+            assert!(tgt_db
+                .backfill_transaction_history(tampered_txn_list, &latest_ledger_info)
+                .is_err());
+        }
+
+        tgt_db
+            .backfill_transaction_history(txn_list_with_proof.clone(), &latest_ledger_info)
+            .unwrap();
+        let next_version = first_version + num_txns;
+        let expected_progress = if next_version == latest_version {
+            None
+        } else {
+            Some(TransactionHistoryBackfillProgress::new(
+                next_version,
+                latest_version,
+            ))
+        };
+        assert_eq!(
+            tgt_db.get_transaction_history_backfill_progress().unwrap(),
+            expected_progress
+        );
+
+        // Backfilled transactions can't be backfilled again
+        assert!(tgt_db
+            .backfill_transaction_history(txn_list_with_proof, &latest_ledger_info)
+            .is_err());
+    }
+
+    // The whole history is stored and proven by the rebuilt accumulator
+    assert_eq!(tgt_db.get_first_txn_version().unwrap(), Some(0));
+    let mut version = 0;
+    while version <= latest_version {
+        let limit = std::cmp::min(MAX_LIMIT, latest_version - version + 1);
+        assert_eq!(
+            tgt_db
+                .get_transactions(version, limit, latest_version, true /* fetch_events */)
+                .unwrap(),
+            src_db
+                .get_transactions(version, limit, latest_version, true /* fetch_events */)
+                .unwrap()
+        );
+        version += limit;
+    }
+}
+
 fn get_events_by_event_key(
     db: &AptosDB,
     ledger_info: &LedgerInfo,
@@ -577,6 +785,16 @@ proptest! {
     fn test_sync_transactions(input in arb_blocks_to_commit()) {
         test_sync_transactions_impl(input);
     }
+
+    #[test]
+    fn test_save_transaction_history(input in arb_blocks_to_commit()) {
+        test_save_transaction_history_impl(input);
+    }
+
+    #[test]
+    fn test_backfill_transaction_history(input in arb_blocks_to_commit()) {
+        test_backfill_transaction_history_impl(input);
+    }
 }
 
 #[test]
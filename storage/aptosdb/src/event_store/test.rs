@@ -287,6 +287,7 @@ prop_compose! {
                 address, // proposer
                 Vec::new(), // prev block voters
                 timestamp,
+                Vec::new(), // failed proposers
             );
             let event = ContractEvent::new(
                 new_block_event_key(),
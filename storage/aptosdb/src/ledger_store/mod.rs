@@ -10,6 +10,7 @@ use crate::{
     schema::{
         epoch_by_version::EpochByVersionSchema, ledger_counters::LedgerCountersSchema,
         ledger_info::LedgerInfoSchema, transaction_accumulator::TransactionAccumulatorSchema,
+        transaction_history_backfill_progress::TransactionHistoryBackfillProgressSchema,
         transaction_info::TransactionInfoSchema,
     },
 };
@@ -32,7 +33,7 @@ use arc_swap::ArcSwap;
 use itertools::Itertools;
 use schemadb::{ReadOptions, SchemaBatch, SchemaIterator, DB};
 use std::{ops::Deref, sync::Arc};
-use storage_interface::{StartupInfo, TreeState};
+use storage_interface::{StartupInfo, TransactionHistoryBackfillProgress, TreeState};
 
 #[derive(Debug)]
 pub struct LedgerStore {
@@ -348,6 +349,29 @@ impl LedgerStore {
         Ok(root_hash)
     }
 
+    /// Returns the progress of the unfinished transaction history backfill, if any.
+    pub fn get_transaction_history_backfill_progress(
+        &self,
+    ) -> Result<Option<TransactionHistoryBackfillProgress>> {
+        self.db.get::<TransactionHistoryBackfillProgressSchema>(&())
+    }
+
+    /// Write the progress of the transaction history backfill to `cs`, deleting it once the
+    /// backfill is complete.
+    pub fn put_transaction_history_backfill_progress(
+        &self,
+        progress: &TransactionHistoryBackfillProgress,
+        cs: &mut ChangeSet,
+    ) -> Result<()> {
+        if progress.is_complete() {
+            cs.batch
+                .delete::<TransactionHistoryBackfillProgressSchema>(&())
+        } else {
+            cs.batch
+                .put::<TransactionHistoryBackfillProgressSchema>(&(), progress)
+        }
+    }
+
     /// Write `ledger_info` to `cs`.
     pub fn put_ledger_info(
         &self,
@@ -98,7 +98,8 @@ use std::{
     time::{Duration, Instant},
 };
 use storage_interface::{
-    DbReader, DbWriter, MoveDbReader, Order, StartupInfo, StateSnapshotReceiver, TreeState,
+    DbReader, DbWriter, MoveDbReader, Order, StartupInfo, StateSnapshotProgress,
+    StateSnapshotReceiver, TransactionHistoryBackfillProgress, TreeState,
 };
 
 const MAX_LIMIT: u64 = 5000;
@@ -243,10 +244,12 @@ impl AptosDB {
             JELLYFISH_MERKLE_NODE_CF_NAME,
             LEDGER_COUNTERS_CF_NAME,
             STALE_NODE_INDEX_CF_NAME,
+            STATE_SNAPSHOT_PROGRESS_CF_NAME,
             TRANSACTION_CF_NAME,
             TRANSACTION_ACCUMULATOR_CF_NAME,
             TRANSACTION_BY_ACCOUNT_CF_NAME,
             TRANSACTION_BY_HASH_CF_NAME,
+            TRANSACTION_HISTORY_BACKFILL_PROGRESS_CF_NAME,
             TRANSACTION_INFO_CF_NAME,
             WRITE_SET_CF_NAME,
         ]
@@ -1203,6 +1206,32 @@ impl DbReader for AptosDB {
             .as_ref()
             .map(|x| x.get_state_store_pruner_window() as usize)
     }
+
+    fn get_state_snapshot_progress(&self) -> Result<Option<StateSnapshotProgress>> {
+        gauged_api("get_state_snapshot_progress", || {
+            self.state_store.get_snapshot_progress()
+        })
+    }
+
+    fn get_transaction_history_backfill_progress(
+        &self,
+    ) -> Result<Option<TransactionHistoryBackfillProgress>> {
+        gauged_api("get_transaction_history_backfill_progress", || {
+            self.ledger_store
+                .get_transaction_history_backfill_progress()
+        })
+    }
+
+    fn get_num_restored_state_values(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<u64> {
+        gauged_api("get_num_restored_state_values", || {
+            self.state_store
+                .get_num_restored_values(version, expected_root_hash)
+        })
+    }
 }
 
 impl ModuleResolver for AptosDB {
@@ -1252,6 +1281,167 @@ impl DbWriter for AptosDB {
         })
     }
 
+    fn save_transaction_history(
+        &self,
+        transaction_list_with_proof: TransactionListWithProof,
+        ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
+    ) -> Result<()> {
+        gauged_api("save_transaction_history", || {
+            let first_version = transaction_list_with_proof
+                .first_transaction_version
+                .ok_or_else(|| format_err!("The first transaction version is missing!"))?;
+            let next_version = self
+                .ledger_store
+                .get_latest_transaction_info_option()?
+                .map_or(0, |(version, _)| version + 1);
+            ensure!(
+                first_version == next_version,
+                "The transaction history isn't applicable: first version {}, next version {}",
+                first_version,
+                next_version,
+            );
+            let transactions = transaction_list_with_proof.transactions;
+            let transaction_infos = transaction_list_with_proof.proof.transaction_infos;
+            let events = transaction_list_with_proof
+                .events
+                .ok_or_else(|| format_err!("The transaction events are missing!"))?;
+            ensure!(
+                transactions.len() == transaction_infos.len() && transactions.len() == events.len(),
+                "The number of transactions, infos and events don't match! {}, {}, {}",
+                transactions.len(),
+                transaction_infos.len(),
+                events.len(),
+            );
+
+            // Gather the transactions, infos, events and ledger info
+            let mut cs = ChangeSet::new();
+            for (version, transaction) in (first_version..).zip(transactions.iter()) {
+                self.transaction_store
+                    .put_transaction(version, transaction, &mut cs)?;
+            }
+            let new_root_hash = self.ledger_store.put_transaction_infos(
+                first_version,
+                &transaction_infos,
+                &mut cs,
+            )?;
+            self.event_store
+                .put_events_multiple_versions(first_version, &events, &mut cs)?;
+            if let Some(ledger_info_with_sigs) = ledger_info_with_sigs {
+                let expected_root_hash = ledger_info_with_sigs
+                    .ledger_info()
+                    .transaction_accumulator_hash();
+                ensure!(
+                    new_root_hash == expected_root_hash,
+                    "Root hash calculated doesn't match expected. {:?} vs {:?}",
+                    new_root_hash,
+                    expected_root_hash,
+                );
+                self.ledger_store
+                    .put_ledger_info(ledger_info_with_sigs, &mut cs)?;
+            }
+
+            // Persist everything atomically
+            self.db.write_schemas(cs.batch)?;
+            if let Some(ledger_info_with_sigs) = ledger_info_with_sigs {
+                self.ledger_store
+                    .set_latest_ledger_info(ledger_info_with_sigs.clone());
+            }
+            Ok(())
+        })
+    }
+
+    fn backfill_transaction_history(
+        &self,
+        transaction_list_with_proof: TransactionListWithProof,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+    ) -> Result<()> {
+        gauged_api("backfill_transaction_history", || {
+            // The backfill ends at the first version stored when it started
+            let progress = match self
+                .ledger_store
+                .get_transaction_history_backfill_progress()?
+            {
+                Some(progress) => progress,
+                None => {
+                    let end_version =
+                        self.transaction_store
+                            .get_first_txn_version()?
+                            .ok_or_else(|| {
+                                format_err!("There is no transaction history to backfill!")
+                            })?;
+                    TransactionHistoryBackfillProgress::new(0, end_version)
+                }
+            };
+            let first_version = transaction_list_with_proof
+                .first_transaction_version
+                .ok_or_else(|| format_err!("The first transaction version is missing!"))?;
+            let num_transactions = transaction_list_with_proof.transactions.len() as u64;
+            ensure!(
+                first_version == progress.next_version,
+                "The transaction history isn't applicable: first version {}, next backfill \
+                 version {}",
+                first_version,
+                progress.next_version,
+            );
+            ensure!(
+                num_transactions > 0 && first_version + num_transactions <= progress.end_version,
+                "The transaction history doesn't fit below the stored history: first version {}, \
+                 {} transactions, end version {}",
+                first_version,
+                num_transactions,
+                progress.end_version,
+            );
+
+            // Verify the transaction list against the stored transaction accumulator
+            let ledger_info = ledger_info_with_sigs.ledger_info();
+            let latest_version = self.ledger_store.get_latest_transaction_info()?.0;
+            ensure!(
+                ledger_info.version() <= latest_version,
+                "The ledger info version {} is newer than the latest version {}",
+                ledger_info.version(),
+                latest_version,
+            );
+            let root_hash = self.ledger_store.get_root_hash(ledger_info.version())?;
+            ensure!(
+                root_hash == ledger_info.transaction_accumulator_hash(),
+                "The ledger info doesn't match the stored transaction accumulator. {:?} vs {:?}",
+                ledger_info.transaction_accumulator_hash(),
+                root_hash,
+            );
+            ensure!(
+                transaction_list_with_proof.events.is_some(),
+                "The transaction events are missing!"
+            );
+            transaction_list_with_proof.verify(ledger_info, Some(first_version))?;
+
+            // Gather the transactions, infos, events and progress. The accumulator nodes of the
+            // backfilled versions are rebuilt on top of the ones written by the previous chunks.
+            let transactions = transaction_list_with_proof.transactions;
+            let transaction_infos = transaction_list_with_proof.proof.transaction_infos;
+            let events = transaction_list_with_proof
+                .events
+                .expect("The transaction events were checked above");
+            let mut cs = ChangeSet::new();
+            for (version, transaction) in (first_version..).zip(transactions.iter()) {
+                self.transaction_store
+                    .put_transaction(version, transaction, &mut cs)?;
+            }
+            self.ledger_store
+                .put_transaction_infos(first_version, &transaction_infos, &mut cs)?;
+            self.event_store
+                .put_events_multiple_versions(first_version, &events, &mut cs)?;
+            let progress = TransactionHistoryBackfillProgress::new(
+                first_version + num_transactions,
+                progress.end_version,
+            );
+            self.ledger_store
+                .put_transaction_history_backfill_progress(&progress, &mut cs)?;
+
+            // Persist everything atomically
+            self.db.write_schemas(cs.batch)
+        })
+    }
+
     /// `first_version` is the version of the first transaction in `txns_to_commit`.
     /// When `ledger_info_with_sigs` is provided, verify that the transaction accumulator root hash
     /// it carries is generated after the `txns_to_commit` are applied.
@@ -1353,6 +1543,21 @@ impl DbWriter for AptosDB {
         })
     }
 
+    fn save_state_snapshot_progress(&self, progress: &StateSnapshotProgress) -> Result<()> {
+        gauged_api("save_state_snapshot_progress", || {
+            self.state_store.put_snapshot_progress(progress)
+        })
+    }
+
+    fn delete_state_snapshot_progress(&self) -> Result<()> {
+        gauged_api("delete_state_snapshot_progress", || {
+            match self.state_store.get_snapshot_progress()? {
+                Some(progress) => self.state_store.abandon_snapshot(progress.version()),
+                None => Ok(()),
+            }
+        })
+    }
+
     fn finalize_state_snapshot(
         &self,
         version: Version,
@@ -1404,7 +1609,10 @@ impl DbWriter for AptosDB {
                 &transactions,
                 &transaction_infos,
                 &events,
-            )
+            )?;
+
+            // The restore is complete, so there is no progress to resume from anymore
+            self.state_store.delete_snapshot_progress()
         })
     }
 }
@@ -0,0 +1,51 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the progress of an unfinished state
+//! snapshot restore.
+//!
+//! There is at most one restore in progress, so the only key is the empty key.
+//! ```text
+//! |<--key-->|<--------value-------->|
+//! |   ()    | state snapshot progress |
+//! ```
+
+use super::STATE_SNAPSHOT_PROGRESS_CF_NAME;
+use crate::schema::ensure_slice_len_eq;
+use anyhow::Result;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use storage_interface::StateSnapshotProgress;
+
+define_schema!(
+    StateSnapshotProgressSchema,
+    (),
+    StateSnapshotProgress,
+    STATE_SNAPSHOT_PROGRESS_CF_NAME
+);
+
+impl KeyCodec<StateSnapshotProgressSchema> for () {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(vec![])
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 0)?;
+        Ok(())
+    }
+}
+
+impl ValueCodec<StateSnapshotProgressSchema> for StateSnapshotProgress {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        bcs::to_bytes(self).map_err(Into::into)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        bcs::from_bytes(data).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test;
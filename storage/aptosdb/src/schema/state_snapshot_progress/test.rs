@@ -0,0 +1,22 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures, transaction::TransactionOutputListWithProof,
+};
+use proptest::prelude::*;
+use schemadb::{schema::fuzzing::assert_encode_decode, test_no_panic_decoding};
+
+proptest! {
+    #[test]
+    fn test_encode_decode(ledger_info in any::<LedgerInfoWithSignatures>()) {
+        let progress = StateSnapshotProgress::new(
+            ledger_info,
+            TransactionOutputListWithProof::new_empty(),
+        );
+        assert_encode_decode::<StateSnapshotProgressSchema>(&(), &progress);
+    }
+}
+
+test_no_panic_decoding!(StateSnapshotProgressSchema);
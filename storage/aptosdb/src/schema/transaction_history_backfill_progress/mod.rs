@@ -0,0 +1,51 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the progress of an unfinished transaction
+//! history backfill.
+//!
+//! There is at most one backfill in progress, so the only key is the empty key.
+//! ```text
+//! |<--key-->|<-----------value----------->|
+//! |   ()    | transaction history progress |
+//! ```
+
+use super::TRANSACTION_HISTORY_BACKFILL_PROGRESS_CF_NAME;
+use crate::schema::ensure_slice_len_eq;
+use anyhow::Result;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use storage_interface::TransactionHistoryBackfillProgress;
+
+define_schema!(
+    TransactionHistoryBackfillProgressSchema,
+    (),
+    TransactionHistoryBackfillProgress,
+    TRANSACTION_HISTORY_BACKFILL_PROGRESS_CF_NAME
+);
+
+impl KeyCodec<TransactionHistoryBackfillProgressSchema> for () {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(vec![])
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 0)?;
+        Ok(())
+    }
+}
+
+impl ValueCodec<TransactionHistoryBackfillProgressSchema> for TransactionHistoryBackfillProgress {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        bcs::to_bytes(self).map_err(Into::into)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        bcs::from_bytes(data).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,17 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use aptos_types::transaction::Version;
+use proptest::prelude::*;
+use schemadb::{schema::fuzzing::assert_encode_decode, test_no_panic_decoding};
+
+proptest! {
+    #[test]
+    fn test_encode_decode(next_version in any::<Version>(), end_version in any::<Version>()) {
+        let progress = TransactionHistoryBackfillProgress::new(next_version, end_version);
+        assert_encode_decode::<TransactionHistoryBackfillProgressSchema>(&(), &progress);
+    }
+}
+
+test_no_panic_decoding!(TransactionHistoryBackfillProgressSchema);
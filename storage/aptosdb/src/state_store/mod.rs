@@ -11,6 +11,7 @@ use crate::{
     ledger_counters::LedgerCounter,
     schema::{
         jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        state_snapshot_progress::StateSnapshotProgressSchema,
     },
     AptosDbError,
 };
@@ -32,12 +33,15 @@ use aptos_types::{
 use itertools::process_results;
 use schemadb::{SchemaBatch, DB};
 use std::{collections::HashMap, sync::Arc};
-use storage_interface::StateSnapshotReceiver;
+use storage_interface::{StateSnapshotProgress, StateSnapshotReceiver};
 
 type LeafNode = aptos_jellyfish_merkle::node_type::LeafNode<StateValue>;
 type Node = aptos_jellyfish_merkle::node_type::Node<StateValue>;
 type NodeBatch = aptos_jellyfish_merkle::NodeBatch<StateValue>;
 
+/// The maximum number of nodes deleted in a single write when abandoning a snapshot restore.
+const MAX_NODES_TO_DELETE_PER_BATCH: usize = 10_000;
+
 #[derive(Debug)]
 pub(crate) struct StateStore {
     db: Arc<DB>,
@@ -121,7 +125,7 @@ impl StateStore {
         JellyfishMerkleTree::new(self).get_root_hash_option(version)
     }
 
-    /// Finds the rightmost leaf by scanning the entire DB.
+    /// Finds the rightmost leaf of the latest version by scanning the entire DB.
     #[cfg(test)]
     pub fn get_rightmost_leaf_naive(&self) -> Result<Option<(NodeKey, LeafNode)>> {
         let mut ret = None;
@@ -129,9 +133,17 @@ impl StateStore {
         let mut iter = self
             .db
             .iter::<JellyfishMerkleNodeSchema>(Default::default())?;
+        iter.seek_to_last();
+        let version = match iter.next().transpose()? {
+            Some((node_key, _node)) => node_key.version(),
+            None => return Ok(None),
+        };
         iter.seek_to_first();
 
         while let Some((node_key, node)) = iter.next().transpose()? {
+            if node_key.version() != version {
+                continue;
+            }
             if let Node::Leaf(leaf_node) = node {
                 match ret {
                     None => ret = Some((node_key, leaf_node)),
@@ -183,17 +195,78 @@ impl StateStore {
         })
     }
 
+    /// Returns a receiver restoring the snapshot at the given version, which resumes the
+    /// interrupted restore of the same snapshot, if any.
     pub fn get_snapshot_receiver(
         self: &Arc<Self>,
         version: Version,
         expected_root_hash: HashValue,
     ) -> Result<Box<dyn StateSnapshotReceiver<StateValue>>> {
-        Ok(Box::new(JellyfishMerkleRestore::new_overwrite(
+        Ok(Box::new(JellyfishMerkleRestore::new(
             Arc::clone(self),
             version,
             expected_root_hash,
         )?))
     }
+
+    /// Returns the progress of the unfinished snapshot restore, if any.
+    pub fn get_snapshot_progress(&self) -> Result<Option<StateSnapshotProgress>> {
+        self.db.get::<StateSnapshotProgressSchema>(&())
+    }
+
+    /// Persists the progress of a snapshot restore, replacing any previous progress.
+    pub fn put_snapshot_progress(&self, progress: &StateSnapshotProgress) -> Result<()> {
+        self.db.put::<StateSnapshotProgressSchema>(&(), progress)
+    }
+
+    /// Deletes the progress of a finished or abandoned snapshot restore.
+    pub fn delete_snapshot_progress(&self) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        batch.delete::<StateSnapshotProgressSchema>(&())?;
+        self.db.write_schemas(batch)
+    }
+
+    /// Deletes an abandoned restore of the snapshot at the given version: the nodes it already
+    /// restored, which don't belong to any committed tree, and its progress.
+    pub fn abandon_snapshot(&self, version: Version) -> Result<()> {
+        let mut iter = self
+            .db
+            .iter::<JellyfishMerkleNodeSchema>(Default::default())?;
+        iter.seek(&(version, 0u8))?;
+
+        // Everything restored has the same version, so the nodes are deleted in batches until
+        // the iterator reaches a newer version.
+        let mut batch = SchemaBatch::new();
+        let mut num_nodes_in_batch = 0;
+        for item in iter {
+            let (node_key, _node) = item?;
+            if node_key.version() != version {
+                break;
+            }
+            batch.delete::<JellyfishMerkleNodeSchema>(&node_key)?;
+            num_nodes_in_batch += 1;
+            if num_nodes_in_batch == MAX_NODES_TO_DELETE_PER_BATCH {
+                self.db
+                    .write_schemas(std::mem::replace(&mut batch, SchemaBatch::new()))?;
+                num_nodes_in_batch = 0;
+            }
+        }
+        batch.delete::<StateSnapshotProgressSchema>(&())?;
+        self.db.write_schemas(batch)
+    }
+
+    /// Returns the number of values durably restored by an interrupted restore of the snapshot
+    /// at the given version.
+    pub fn get_num_restored_values(
+        self: &Arc<Self>,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<u64> {
+        Ok(
+            JellyfishMerkleRestore::new(Arc::clone(self), version, expected_root_hash)?
+                .num_keys_restored(),
+        )
+    }
 }
 
 impl TreeReader<StateValue> for StateStore {
@@ -202,12 +275,12 @@ impl TreeReader<StateValue> for StateStore {
     }
 
     fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
-        // Since everything has the same version during restore, we seek to the first node and get
-        // its version.
+        // Since everything restored has the same version, newer than any existing tree (e.g., the
+        // genesis of a bootstrapping node), we seek to the last node and get its version.
         let mut iter = self
             .db
             .iter::<JellyfishMerkleNodeSchema>(Default::default())?;
-        iter.seek_to_first();
+        iter.seek_to_last();
         let version = match iter.next().transpose()? {
             Some((node_key, _node)) => node_key.version(),
             None => return Ok(None),
@@ -243,7 +316,10 @@ impl TreeReader<StateValue> for StateStore {
             iter.seek_for_prev(&seek_key)?;
 
             if let Some((node_key, node)) = iter.next().transpose()? {
-                debug_assert_eq!(node_key.version(), version);
+                // The range is empty at this version, so we ended up in an older version.
+                if node_key.version() != version {
+                    continue;
+                }
                 debug_assert!(node_key.nibble_path().num_nibbles() < num_nibbles);
 
                 if let Node::Leaf(leaf_node) = node {
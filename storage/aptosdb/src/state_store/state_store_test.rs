@@ -393,9 +393,11 @@ proptest! {
         let version = (input.len() - 1) as Version;
         let expected_root_hash = store1.get_root_hash(version).unwrap();
 
+        // The restore happens on top of an older tree, e.g., the genesis of a bootstrapping node.
         let tmp_dir2 = TempPath::new();
         let db2 = AptosDB::new_for_test(&tmp_dir2);
         let store2 = &db2.state_store;
+        init_store(store2, input.clone().into_iter().take(1));
 
         let mut restore =
             JellyfishMerkleRestore::new(Arc::clone(store2), version, expected_root_hash).unwrap();
@@ -407,7 +409,8 @@ proptest! {
         ordered_input.sort_unstable_by_key(|(key, _value)| *key);
 
         let batch1: Vec<_> = ordered_input
-            .into_iter()
+            .iter()
+            .cloned()
             .take(batch1_size)
             .collect();
         let rightmost_of_batch1 = batch1.last().map(|(key, _value)| *key).unwrap();
@@ -419,7 +422,69 @@ proptest! {
 
         let expected = store2.get_rightmost_leaf_naive().unwrap();
         let actual = store2.get_rightmost_leaf().unwrap();
-        prop_assert_eq!(actual, expected);
+        prop_assert_eq!(&actual, &expected);
+
+        // A new receiver resumes after the durably restored values.
+        let num_restored_values = match actual {
+            Some((node_key, leaf_node)) if node_key.version() == version => ordered_input
+                .iter()
+                .take_while(|(key, _value)| *key <= leaf_node.account_key())
+                .count() as u64,
+            _ => 0,
+        };
+        prop_assert_eq!(
+            store2.get_num_restored_values(version, expected_root_hash).unwrap(),
+            num_restored_values
+        );
+    }
+
+    #[test]
+    fn test_abandon_snapshot(
+        (input, batch1_size) in hash_map(any::<StateKey>(), any::<StateValue>(), 2..1000)
+            .prop_flat_map(|input| {
+                let len = input.len();
+                (Just(input), 1..len)
+            })
+    ) {
+        let tmp_dir1 = TempPath::new();
+        let db1 = AptosDB::new_for_test(&tmp_dir1);
+        let store1 = &db1.state_store;
+        init_store(store1, input.clone().into_iter());
+
+        let version = (input.len() - 1) as Version;
+        let expected_root_hash = store1.get_root_hash(version).unwrap();
+
+        // Partially restore the snapshot on top of an older tree.
+        let tmp_dir2 = TempPath::new();
+        let db2 = AptosDB::new_for_test(&tmp_dir2);
+        let store2 = &db2.state_store;
+        init_store(store2, input.clone().into_iter().take(1));
+        let genesis_root_hash = store2.get_root_hash(0).unwrap();
+
+        let mut restore =
+            JellyfishMerkleRestore::new(Arc::clone(store2), version, expected_root_hash).unwrap();
+        let mut ordered_input: Vec<_> = input
+            .into_iter()
+            .map(|(addr, value)| (addr.hash(), value))
+            .collect();
+        ordered_input.sort_unstable_by_key(|(key, _value)| *key);
+        let batch1: Vec<_> = ordered_input.into_iter().take(batch1_size).collect();
+        let rightmost_of_batch1 = batch1.last().map(|(key, _value)| *key).unwrap();
+        let proof_of_batch1 = store1
+            .get_value_range_proof(rightmost_of_batch1, version)
+            .unwrap();
+        restore.add_chunk(batch1, proof_of_batch1).unwrap();
+
+        // Nothing is left of the abandoned restore, and the older tree is untouched.
+        store2.abandon_snapshot(version).unwrap();
+        prop_assert!(store2.get_snapshot_progress().unwrap().is_none());
+        prop_assert_eq!(
+            store2.get_num_restored_values(version, expected_root_hash).unwrap(),
+            0
+        );
+        let rightmost_leaf = store2.get_rightmost_leaf().unwrap().unwrap();
+        prop_assert_eq!(rightmost_leaf.0.version(), 0);
+        prop_assert_eq!(store2.get_root_hash(0).unwrap(), genesis_root_hash);
     }
 
     #[test]
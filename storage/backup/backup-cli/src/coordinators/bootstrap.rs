@@ -0,0 +1,308 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        epoch_ending::restore::EpochHistoryRestoreController,
+        state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        transaction::restore::TransactionRestoreBatchController,
+    },
+    metadata,
+    metadata::cache::MetadataCacheOpt,
+    metrics::restore::{
+        COORDINATOR_FAIL_TS, COORDINATOR_START_TS, COORDINATOR_SUCC_TS, COORDINATOR_TARGET_VERSION,
+    },
+    storage::{
+        command_adapter::{CommandAdapter, CommandAdapterOpt},
+        local_fs::LocalFs,
+        BackupStorage,
+    },
+    utils::{error_notes::ErrorNotes, unix_timestamp_sec, GlobalRestoreOptions, RestoreRunMode},
+};
+use anyhow::{anyhow, bail, ensure, Result};
+use aptos_config::config::{BackupRestoreConfig, BackupStorageLocation};
+use aptos_logger::prelude::*;
+use aptos_types::{transaction::Version, waypoint::Waypoint};
+use aptosdb::backup::restore_handler::RestoreHandler;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::max,
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{fs::File, io::AsyncWriteExt};
+
+/// The progress of bootstrapping from a backup storage, persisted in a file so
+/// that an interrupted bootstrap resumes (following the same plan) on restart.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BootstrapProgress {
+    /// The version to bootstrap the DB to
+    pub target_version: Version,
+    /// The version of the state snapshot to restore (if any)
+    pub state_snapshot_version: Option<Version>,
+    /// Whether the state snapshot (if any) is fully restored
+    pub state_snapshot_restored: bool,
+    /// Whether the bootstrap has completed
+    pub done: bool,
+}
+
+impl BootstrapProgress {
+    fn new(target_version: Version, state_snapshot_version: Option<Version>) -> Self {
+        Self {
+            target_version,
+            state_snapshot_version,
+            state_snapshot_restored: false,
+            done: false,
+        }
+    }
+
+    /// Loads the progress from the given file, or returns `None` if it doesn't exist.
+    pub async fn load(path: &Path) -> Result<Option<Self>> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes).err_notes(path)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).err_notes(path).map_err(Into::into),
+        }
+    }
+
+    /// Atomically overwrites the given file with the progress.
+    async fn persist(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        let mut file = File::create(&tmp_path).await.err_notes(&tmp_path)?;
+        file.write_all(&serde_json::to_vec(self)?).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&tmp_path, path).await.err_notes(path)?;
+        Ok(())
+    }
+}
+
+/// Bootstraps a DB directly from a backup storage, i.e., restores the epoch
+/// history, the latest state snapshot and all transactions after it. The
+/// restored epoch history must contain (and match) the given waypoint.
+///
+/// The progress is persisted in the given file, so running the coordinator
+/// again resumes an interrupted bootstrap (and is a no-op once it is done).
+/// A DB that already has transactions but no progress file wasn't bootstrapped
+/// from backup storage, and is left untouched.
+pub struct BootstrapCoordinator {
+    config: BackupRestoreConfig,
+    restore_handler: RestoreHandler,
+    waypoint: Waypoint,
+    progress_file: PathBuf,
+}
+
+impl BootstrapCoordinator {
+    pub fn new(
+        config: BackupRestoreConfig,
+        restore_handler: RestoreHandler,
+        waypoint: Waypoint,
+        progress_file: PathBuf,
+    ) -> Self {
+        Self {
+            config,
+            restore_handler,
+            waypoint,
+            progress_file,
+        }
+    }
+
+    pub async fn run(self) -> Result<()> {
+        info!("Bootstrap coordinator started.");
+        COORDINATOR_START_TS.set(unix_timestamp_sec());
+
+        let ret = self.run_impl().await;
+
+        if let Err(e) = &ret {
+            error!(
+                error = ?e,
+                "Bootstrap coordinator failed."
+            );
+            COORDINATOR_FAIL_TS.set(unix_timestamp_sec());
+        } else {
+            info!("Bootstrap coordinator exiting with success.");
+            COORDINATOR_SUCC_TS.set(unix_timestamp_sec());
+        }
+
+        ret
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        let progress = BootstrapProgress::load(&self.progress_file).await?;
+        if progress.as_ref().map_or(false, |p| p.done) {
+            info!("DB already bootstrapped from backup storage.");
+            return Ok(());
+        }
+        let next_version = self
+            .restore_handler
+            .get_next_expected_transaction_version()?;
+        if progress.is_none() && next_version > 0 {
+            info!(
+                "DB already has transactions till {}, skip bootstrapping from backup storage.",
+                next_version
+            );
+            return Ok(());
+        }
+
+        let storage = self.init_storage().await?;
+        let metadata_view = metadata::cache::sync_and_load(
+            &MetadataCacheOpt::new(self.config.metadata_cache_dir.clone()),
+            Arc::clone(&storage),
+            self.config.concurrent_downloads,
+        )
+        .await?;
+
+        // Stick to the persisted plan when resuming, newer backups might be available by now.
+        let mut progress = match progress {
+            Some(progress) => {
+                info!(
+                    "Resuming to bootstrap from backup storage, DB has transactions till {}.",
+                    next_version
+                );
+                progress
+            }
+            None => {
+                let transactions =
+                    metadata_view.select_transaction_backups(0, Version::max_value())?;
+                let target_version = match transactions.last() {
+                    Some(backup) => backup.last_version,
+                    None => bail!("No transaction backup found."),
+                };
+                let progress = BootstrapProgress::new(
+                    target_version,
+                    metadata_view
+                        .select_state_snapshot(target_version)?
+                        .map(|b| b.version),
+                );
+                progress.persist(&self.progress_file).await?;
+                progress
+            }
+        };
+        let target_version = progress.target_version;
+        let transactions = metadata_view.select_transaction_backups(0, target_version)?;
+        ensure!(
+            transactions
+                .last()
+                .map_or(false, |b| b.last_version >= target_version),
+            "No transaction backup found till the target version {}.",
+            target_version,
+        );
+        let epoch_endings = metadata_view.select_epoch_ending_backups(target_version)?;
+        let state_snapshot = match progress.state_snapshot_version {
+            Some(version) => Some(
+                metadata_view
+                    .select_state_snapshot(version)?
+                    .filter(|b| b.version == version)
+                    .ok_or_else(|| {
+                        anyhow!("No state snapshot backup found at version {}.", version)
+                    })?,
+            ),
+            None => None,
+        };
+        ensure!(
+            state_snapshot.is_none() || progress.state_snapshot_restored || next_version == 0,
+            "DB has transactions till {} before the state snapshot is restored.",
+            next_version,
+        );
+        // Transactions are only saved after the state snapshot is restored, so the DB is
+        // complete till its next version, resume from there.
+        let replay_transactions_from_version = match &state_snapshot {
+            Some(b) => max(b.version + 1, next_version),
+            None => next_version,
+        };
+        ensure!(
+            epoch_endings
+                .iter()
+                .any(|b| b.first_version <= self.waypoint.version()
+                    && self.waypoint.version() <= b.last_version),
+            "No epoch ending backup contains the waypoint version {}.",
+            self.waypoint.version(),
+        );
+        COORDINATOR_TARGET_VERSION.set(target_version as i64);
+        info!(
+            "Planned to bootstrap from backup to version {}, state snapshot: {:?}.",
+            target_version, progress.state_snapshot_version,
+        );
+
+        let mut trusted_waypoints = HashMap::new();
+        trusted_waypoints.insert(self.waypoint.version(), self.waypoint);
+        let global_opt = GlobalRestoreOptions {
+            target_version,
+            trusted_waypoints: Arc::new(trusted_waypoints),
+            run_mode: Arc::new(RestoreRunMode::Restore {
+                restore_handler: self.restore_handler,
+            }),
+            concurrent_downloads: self.config.concurrent_downloads,
+        };
+
+        let epoch_history = Arc::new(
+            EpochHistoryRestoreController::new(
+                epoch_endings
+                    .into_iter()
+                    .map(|backup| backup.manifest)
+                    .collect(),
+                global_opt.clone(),
+                Arc::clone(&storage),
+            )
+            .run()
+            .await?,
+        );
+        // The epoch history restore only checks trusted waypoints at epoch ending versions,
+        // make sure the waypoint actually matches one of them.
+        ensure!(
+            epoch_history.epoch_endings.iter().any(|li| {
+                li.version() == self.waypoint.version() && self.waypoint.verify(li).is_ok()
+            }),
+            "Waypoint {} doesn't match any epoch ending LedgerInfo in the backup.",
+            self.waypoint,
+        );
+
+        if let Some(backup) = state_snapshot {
+            // A partially restored state snapshot is overwritten from scratch.
+            if !progress.state_snapshot_restored {
+                StateSnapshotRestoreController::new(
+                    StateSnapshotRestoreOpt {
+                        manifest_handle: backup.manifest,
+                        version: backup.version,
+                    },
+                    global_opt.clone(),
+                    Arc::clone(&storage),
+                    Some(Arc::clone(&epoch_history)),
+                )
+                .run()
+                .await?;
+                progress.state_snapshot_restored = true;
+                progress.persist(&self.progress_file).await?;
+            }
+        }
+
+        let txn_manifests = transactions
+            .into_iter()
+            .skip_while(|b| b.last_version < replay_transactions_from_version)
+            .map(|b| b.manifest)
+            .collect();
+        TransactionRestoreBatchController::new(
+            global_opt,
+            storage,
+            txn_manifests,
+            Some(replay_transactions_from_version),
+            Some(epoch_history),
+        )
+        .run()
+        .await?;
+
+        progress.done = true;
+        progress.persist(&self.progress_file).await
+    }
+
+    async fn init_storage(&self) -> Result<Arc<dyn BackupStorage>> {
+        Ok(match &self.config.backup_storage {
+            Some(BackupStorageLocation::LocalFs(dir)) => Arc::new(LocalFs::new(dir.clone())),
+            Some(BackupStorageLocation::CommandAdapter(config)) => Arc::new(
+                CommandAdapter::new_with_opt(CommandAdapterOpt::new(config.clone())).await?,
+            ),
+            None => bail!("No backup storage configured to bootstrap from."),
+        })
+    }
+}
@@ -2,6 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod backup;
+pub mod bootstrap;
 pub mod replay_verify;
 pub mod restore;
 pub mod verify;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,282 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        epoch_ending::backup::{EpochEndingBackupController, EpochEndingBackupOpt},
+        state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+        transaction::backup::{TransactionBackupController, TransactionBackupOpt},
+    },
+    coordinators::bootstrap::{BootstrapCoordinator, BootstrapProgress},
+    storage::{local_fs::LocalFs, BackupStorage, FileHandle},
+    utils::{
+        backup_service_client::BackupServiceClient, test_utils::start_local_backup_service,
+        GlobalBackupOpt,
+    },
+};
+use aptos_config::config::{BackupRestoreConfig, BackupStorageLocation};
+use aptos_temppath::TempPath;
+use aptos_types::{transaction::Version, waypoint::Waypoint};
+use aptosdb::{AptosDB, GetRestoreHandler};
+use executor_test_helpers::integration_test_impl::test_execution_with_storage_impl;
+use std::{path::PathBuf, sync::Arc};
+use storage_interface::DbReader;
+use tokio::{runtime::Runtime, time::Duration};
+
+struct TestBackup {
+    rt: Runtime,
+    backup_dir: TempPath,
+    state_snapshot_ver: Version,
+    txn_manifests: Vec<FileHandle>,
+    latest_ver: Version,
+    waypoint: Waypoint,
+}
+
+/// Backs up the epoch endings, a state snapshot and the transactions (in two
+/// separate backups) of the given DB.
+fn backup_db(db: &Arc<AptosDB>) -> TestBackup {
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+    let (rt, port) = start_local_backup_service(Arc::clone(db));
+    let client = Arc::new(BackupServiceClient::new(format!(
+        "http://localhost:{}",
+        port
+    )));
+    let global_backup_opt = GlobalBackupOpt {
+        max_chunk_size: 2048,
+    };
+
+    let latest_ver = db.get_latest_version().unwrap();
+    let state_snapshot_ver = latest_ver / 3;
+    let mid_ver = latest_ver * 2 / 3;
+    let end_epoch = db
+        .get_latest_ledger_info()
+        .unwrap()
+        .ledger_info()
+        .next_block_epoch();
+    rt.block_on(
+        EpochEndingBackupController::new(
+            EpochEndingBackupOpt {
+                start_epoch: 0,
+                end_epoch,
+            },
+            global_backup_opt.clone(),
+            Arc::clone(&client),
+            Arc::clone(&store),
+        )
+        .run(),
+    )
+    .unwrap();
+    rt.block_on(
+        StateSnapshotBackupController::new(
+            StateSnapshotBackupOpt {
+                version: state_snapshot_ver,
+            },
+            global_backup_opt.clone(),
+            Arc::clone(&client),
+            Arc::clone(&store),
+        )
+        .run(),
+    )
+    .unwrap();
+    let txn_manifests = [(0, mid_ver), (mid_ver + 1, latest_ver)]
+        .iter()
+        .map(|(start_version, last_version)| {
+            rt.block_on(
+                TransactionBackupController::new(
+                    TransactionBackupOpt {
+                        start_version: *start_version,
+                        num_transactions: (last_version - start_version + 1) as usize,
+                    },
+                    global_backup_opt.clone(),
+                    Arc::clone(&client),
+                    Arc::clone(&store),
+                )
+                .run(),
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let genesis_li = db.get_epoch_ending_ledger_infos(0, 1).unwrap();
+    let waypoint =
+        Waypoint::new_epoch_boundary(genesis_li.ledger_info_with_sigs[0].ledger_info()).unwrap();
+
+    TestBackup {
+        rt,
+        backup_dir,
+        state_snapshot_ver,
+        txn_manifests,
+        latest_ver,
+        waypoint,
+    }
+}
+
+fn bootstrap_coordinator(
+    backup: &TestBackup,
+    tgt_db: &Arc<AptosDB>,
+    metadata_cache_dir: &TempPath,
+    progress_file: PathBuf,
+) -> BootstrapCoordinator {
+    BootstrapCoordinator::new(
+        BackupRestoreConfig {
+            backup_storage: Some(BackupStorageLocation::LocalFs(
+                backup.backup_dir.path().to_path_buf(),
+            )),
+            concurrent_downloads: 4,
+            metadata_cache_dir: Some(metadata_cache_dir.path().to_path_buf()),
+        },
+        Arc::clone(tgt_db).get_restore_handler(),
+        backup.waypoint,
+        progress_file,
+    )
+}
+
+fn assert_bootstrapped(src_db: &Arc<AptosDB>, tgt_db: &Arc<AptosDB>, latest_ver: Version) {
+    assert_eq!(
+        tgt_db.get_latest_transaction_info_option().unwrap(),
+        src_db.get_latest_transaction_info_option().unwrap(),
+    );
+    assert_eq!(
+        tgt_db
+            .get_transactions(0, latest_ver + 1, latest_ver, true /* fetch_events */)
+            .unwrap(),
+        src_db
+            .get_transactions(0, latest_ver + 1, latest_ver, true /* fetch_events */)
+            .unwrap(),
+    );
+}
+
+#[test]
+fn test_bootstrap_from_backup() {
+    let src_db = test_execution_with_storage_impl();
+    let backup = backup_db(&src_db);
+    let tgt_db_dir = TempPath::new();
+    let tgt_db = Arc::new(AptosDB::new_for_test(&tgt_db_dir));
+    let metadata_cache_dir = TempPath::new();
+    let progress_dir = TempPath::new();
+    progress_dir.create_as_dir().unwrap();
+    let progress_file = progress_dir.path().join("progress.json");
+
+    // Bootstrap the empty DB
+    backup
+        .rt
+        .block_on(
+            bootstrap_coordinator(&backup, &tgt_db, &metadata_cache_dir, progress_file.clone())
+                .run(),
+        )
+        .unwrap();
+    assert_bootstrapped(&src_db, &tgt_db, backup.latest_ver);
+    let progress = backup
+        .rt
+        .block_on(BootstrapProgress::load(&progress_file))
+        .unwrap()
+        .unwrap();
+    assert_eq!(progress.target_version, backup.latest_ver);
+    assert_eq!(
+        progress.state_snapshot_version,
+        Some(backup.state_snapshot_ver)
+    );
+    assert!(progress.state_snapshot_restored);
+    assert!(progress.done);
+
+    // Bootstrapping again is a no-op, even if the backup storage is gone
+    std::fs::remove_dir_all(backup.backup_dir.path()).unwrap();
+    backup
+        .rt
+        .block_on(bootstrap_coordinator(&backup, &tgt_db, &metadata_cache_dir, progress_file).run())
+        .unwrap();
+    assert_bootstrapped(&src_db, &tgt_db, backup.latest_ver);
+
+    backup.rt.shutdown_timeout(Duration::from_secs(1));
+}
+
+#[test]
+fn test_bootstrap_from_backup_resumes() {
+    let src_db = test_execution_with_storage_impl();
+    let backup = backup_db(&src_db);
+    let tgt_db_dir = TempPath::new();
+    let tgt_db = Arc::new(AptosDB::new_for_test(&tgt_db_dir));
+    let metadata_cache_dir = TempPath::new();
+    let progress_dir = TempPath::new();
+    progress_dir.create_as_dir().unwrap();
+    let progress_file = progress_dir.path().join("progress.json");
+
+    // Make the last transaction backup unavailable, so the bootstrap fails midway
+    let last_txn_backup = backup
+        .backup_dir
+        .path()
+        .join(&backup.txn_manifests[1])
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let moved_txn_backup = backup.backup_dir.path().join("unavailable");
+    std::fs::rename(&last_txn_backup, &moved_txn_backup).unwrap();
+    assert!(backup
+        .rt
+        .block_on(
+            bootstrap_coordinator(&backup, &tgt_db, &metadata_cache_dir, progress_file.clone())
+                .run(),
+        )
+        .is_err());
+    let progress = backup
+        .rt
+        .block_on(BootstrapProgress::load(&progress_file))
+        .unwrap()
+        .unwrap();
+    assert!(progress.state_snapshot_restored);
+    assert!(!progress.done);
+    assert!(
+        tgt_db
+            .get_latest_transaction_info_option()
+            .unwrap()
+            .map(|(version, _)| version)
+            < Some(backup.latest_ver)
+    );
+
+    // Bootstrapping again resumes once the backup is available
+    std::fs::rename(&moved_txn_backup, &last_txn_backup).unwrap();
+    backup
+        .rt
+        .block_on(
+            bootstrap_coordinator(&backup, &tgt_db, &metadata_cache_dir, progress_file.clone())
+                .run(),
+        )
+        .unwrap();
+    assert_bootstrapped(&src_db, &tgt_db, backup.latest_ver);
+    let progress = backup
+        .rt
+        .block_on(BootstrapProgress::load(&progress_file))
+        .unwrap()
+        .unwrap();
+    assert!(progress.done);
+
+    backup.rt.shutdown_timeout(Duration::from_secs(1));
+}
+
+#[test]
+fn test_bootstrap_skips_non_empty_db() {
+    let src_db = test_execution_with_storage_impl();
+    let backup = backup_db(&src_db);
+    let metadata_cache_dir = TempPath::new();
+    let progress_dir = TempPath::new();
+    progress_dir.create_as_dir().unwrap();
+    let progress_file = progress_dir.path().join("progress.json");
+
+    // A DB that wasn't bootstrapped from backup storage is left untouched
+    backup
+        .rt
+        .block_on(
+            bootstrap_coordinator(&backup, &src_db, &metadata_cache_dir, progress_file.clone())
+                .run(),
+        )
+        .unwrap();
+    assert!(backup
+        .rt
+        .block_on(BootstrapProgress::load(&progress_file))
+        .unwrap()
+        .is_none());
+
+    backup.rt.shutdown_timeout(Duration::from_secs(1));
+}
@@ -33,6 +33,12 @@ pub struct CommandAdapterOpt {
     config: PathBuf,
 }
 
+impl CommandAdapterOpt {
+    pub fn new(config: PathBuf) -> Self {
+        Self { config }
+    }
+}
+
 /// A BackupStorage that delegates required APIs to configured command lines.
 /// see `CommandAdapterConfig`.
 pub struct CommandAdapter {
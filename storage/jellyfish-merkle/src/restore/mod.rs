@@ -171,21 +171,19 @@ where
         expected_root_hash: HashValue,
     ) -> Result<Self> {
         let tree_reader = Arc::clone(&store);
-        let (partial_nodes, previous_leaf) =
-            if let Some((node_key, leaf_node)) = tree_reader.get_rightmost_leaf()? {
-                // TODO: confirm rightmost leaf is at the desired version
-                // If the system crashed in the middle of the previous restoration attempt, we need
-                // to recover the partial nodes to the state right before the crash.
-                (
-                    Self::recover_partial_nodes(tree_reader.as_ref(), version, node_key)?,
-                    Some(leaf_node),
-                )
-            } else {
-                (
-                    vec![InternalInfo::new_empty(NodeKey::new_empty_path(version))],
-                    None,
-                )
-            };
+        let (partial_nodes, previous_leaf) = match tree_reader.get_rightmost_leaf()? {
+            // If the system crashed in the middle of the previous restoration attempt, we need
+            // to recover the partial nodes to the state right before the crash. Nodes at
+            // another version belong to another tree, so we start from scratch.
+            Some((node_key, leaf_node)) if node_key.version() == version => (
+                Self::recover_partial_nodes(tree_reader.as_ref(), version, node_key)?,
+                Some(leaf_node),
+            ),
+            _ => (
+                vec![InternalInfo::new_empty(NodeKey::new_empty_path(version))],
+                None,
+            ),
+        };
 
         Ok(Self {
             store,
@@ -288,6 +286,20 @@ where
         Ok(partial_nodes)
     }
 
+    /// Returns the number of keys restored so far, i.e., the index of the next key to add. Right
+    /// after recovering from a crash, this is the number of keys durably written to storage.
+    pub fn num_keys_restored(&self) -> u64 {
+        self.partial_nodes
+            .iter()
+            .flat_map(|partial_node| partial_node.children.iter().flatten())
+            .map(|child_info| match child_info {
+                // Partial children are counted by the next partial node.
+                ChildInfo::Internal { leaf_count, .. } => leaf_count.unwrap_or(0) as u64,
+                ChildInfo::Leaf { .. } => 1,
+            })
+            .sum()
+    }
+
     /// Restores a chunk of accounts. This function will verify that the given chunk is correct
     /// using the proof and root hash, then write things to storage. If the chunk is invalid, an
     /// error will be returned and nothing will be written to storage.
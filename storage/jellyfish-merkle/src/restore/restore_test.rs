@@ -66,6 +66,10 @@ proptest! {
 
             let mut restore =
                 JellyfishMerkleRestore::new(Arc::clone(&restore_db), version, expected_root_hash).unwrap();
+            prop_assert_eq!(
+                restore.num_keys_restored() as usize,
+                all.len() - remaining_accounts.len()
+            );
             let proof = tree
                 .get_range_proof(
                     remaining_accounts.last().map(|(key, _value)| *key).unwrap(),
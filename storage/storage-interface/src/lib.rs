@@ -38,6 +38,58 @@ use thiserror::Error;
 pub mod mock;
 pub mod state_view;
 
+/// The progress of a state snapshot restore (e.g., when bootstrapping by downloading the
+/// latest account states), persisted so that the restore can resume after a crash.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StateSnapshotProgress {
+    /// The ledger info of the version being restored.
+    pub target_ledger_info: LedgerInfoWithSignatures,
+    /// The transaction output (inc. info and proof) at the version being restored.
+    pub target_output_with_proof: TransactionOutputListWithProof,
+}
+
+impl StateSnapshotProgress {
+    pub fn new(
+        target_ledger_info: LedgerInfoWithSignatures,
+        target_output_with_proof: TransactionOutputListWithProof,
+    ) -> Self {
+        Self {
+            target_ledger_info,
+            target_output_with_proof,
+        }
+    }
+
+    /// The version being restored.
+    pub fn version(&self) -> Version {
+        self.target_ledger_info.ledger_info().version()
+    }
+}
+
+/// The progress of a transaction history backfill (i.e., filling in the transaction history
+/// below the first version stored, e.g., after bootstrapping from a state snapshot), persisted
+/// so that the backfill can resume after a crash.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TransactionHistoryBackfillProgress {
+    /// The next version to backfill.
+    pub next_version: Version,
+    /// The first version stored when the backfill started, where it ends.
+    pub end_version: Version,
+}
+
+impl TransactionHistoryBackfillProgress {
+    pub fn new(next_version: Version, end_version: Version) -> Self {
+        Self {
+            next_version,
+            end_version,
+        }
+    }
+
+    /// Returns true iff all the versions have been backfilled.
+    pub fn is_complete(&self) -> bool {
+        self.next_version >= self.end_version
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct StartupInfo {
     /// The latest ledger info.
@@ -498,6 +550,29 @@ pub trait DbReader: Send + Sync {
     fn get_state_prune_window(&self) -> Option<usize> {
         unimplemented!()
     }
+
+    /// Returns the progress of the unfinished state snapshot restore, if any.
+    fn get_state_snapshot_progress(&self) -> Result<Option<StateSnapshotProgress>> {
+        unimplemented!()
+    }
+
+    /// Returns the progress of the unfinished transaction history backfill, if any.
+    fn get_transaction_history_backfill_progress(
+        &self,
+    ) -> Result<Option<TransactionHistoryBackfillProgress>> {
+        unimplemented!()
+    }
+
+    /// Returns the number of state values already restored (and durably written) by an
+    /// unfinished state snapshot restore at the given version. A state snapshot receiver
+    /// created for the same version and root hash resumes after these values.
+    fn get_num_restored_state_values(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<u64> {
+        unimplemented!()
+    }
 }
 
 impl MoveStorage for &dyn DbReader {
@@ -574,7 +649,9 @@ impl MoveStorage for &dyn DbReader {
 pub trait DbWriter: Send + Sync {
     /// Get a (stateful) state snapshot receiver.
     ///
-    /// Chunk of accounts need to be added via `add_chunk()` before finishing up with `finish_box()`
+    /// Chunk of accounts need to be added via `add_chunk()` before finishing up with `finish_box()`.
+    /// If a previous restore of the same snapshot was interrupted, the receiver resumes it: see
+    /// `get_num_restored_state_values()`.
     fn get_state_snapshot_receiver(
         &self,
         version: Version,
@@ -583,9 +660,20 @@ pub trait DbWriter: Send + Sync {
         unimplemented!()
     }
 
+    /// Persists the progress of a state snapshot restore, replacing any previous progress.
+    fn save_state_snapshot_progress(&self, progress: &StateSnapshotProgress) -> Result<()> {
+        unimplemented!()
+    }
+
+    /// Deletes the progress of an abandoned state snapshot restore, along with the state
+    /// values it already restored.
+    fn delete_state_snapshot_progress(&self) -> Result<()> {
+        unimplemented!()
+    }
+
     /// Finalizes a state snapshot that has already been restored to the database through
     /// a state snapshot receiver. This is required to bootstrap the transaction accumulator
-    /// and populate transaction and event information.
+    /// and populate transaction and event information. Also deletes the restore progress.
     ///
     /// Note: this assumes that the output with proof has already been verified and that the
     /// state snapshot was restored at the same version.
@@ -604,6 +692,39 @@ pub trait DbWriter: Send + Sync {
         unimplemented!()
     }
 
+    /// Persists the transaction history in the given transaction list (i.e., the
+    /// transactions, transaction infos and events), without executing the transactions
+    /// or updating the state. This is used by nodes that only store the transaction
+    /// history (e.g., archival nodes syncing from a trusted source). If a ledger info is
+    /// specified, it is persisted atomically with the transactions.
+    ///
+    /// Note: this assumes that the transaction list has already been verified against
+    /// the transaction accumulator, and that the ledger info has already been verified.
+    fn save_transaction_history(
+        &self,
+        transaction_list_with_proof: TransactionListWithProof,
+        ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+
+    /// Persists the transaction history in the given transaction list below the first version
+    /// stored (e.g., after bootstrapping from a state snapshot), in order, starting from
+    /// version 0. The backfill progress is persisted atomically with the transactions, and
+    /// deleted once the history is complete.
+    ///
+    /// The ledger info must be at or below the latest version stored. Unlike
+    /// `save_transaction_history`, the transaction list is verified here: the ledger info
+    /// must match the transaction accumulator stored at its version and must prove the
+    /// transaction list.
+    fn backfill_transaction_history(
+        &self,
+        transaction_list_with_proof: TransactionListWithProof,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+
     /// Persist transactions. Called by the executor module when either syncing nodes or committing
     /// blocks during normal operation.
     /// See [`AptosDB::save_transactions`].
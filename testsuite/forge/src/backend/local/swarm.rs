@@ -6,7 +6,7 @@ use crate::{
     Validator, Version,
 };
 use anyhow::{anyhow, bail, Result};
-use aptos_config::config::NodeConfig;
+use aptos_config::config::{FaultInjectionConfig, NetworkFaults, NodeConfig, PersistableConfig};
 use aptos_genesis_tool::{fullnode_builder::FullnodeConfig, validator_builder::ValidatorBuilder};
 use aptos_sdk::{
     crypto::ed25519::Ed25519PrivateKey,
@@ -27,6 +27,11 @@ use std::{
 };
 use tempfile::TempDir;
 
+/// The file of the network faults reloaded by the validators, in the swarm directory.
+const NETWORK_FAULTS_FILE: &str = "network_faults.yaml";
+/// The interval for the validators to reload the network faults.
+const NETWORK_FAULTS_RELOAD_INTERVAL_MS: u64 = 1000;
+
 #[derive(Debug)]
 pub enum SwarmDirectory {
     Persistent(PathBuf),
@@ -362,6 +367,34 @@ impl LocalSwarm {
         Ok(peer_id)
     }
 
+    /// Injects network faults in the connections between the validators. The validators are
+    /// restarted to enable fault injection the first time, later changes are reloaded live.
+    pub async fn set_network_faults(&mut self, faults: &NetworkFaults) -> Result<()> {
+        let faults_path = self.dir.join(NETWORK_FAULTS_FILE);
+        faults.save_config(&faults_path)?;
+
+        for validator in self.validators.values_mut() {
+            let config_path = validator.config_path();
+            let mut config = validator.config().clone();
+            let validator_network = config
+                .validator_network
+                .as_mut()
+                .ok_or_else(|| anyhow!("validator {} has no network", validator.name()))?;
+            if validator_network.fault_injection.is_some() {
+                continue;
+            }
+            validator_network.fault_injection = Some(FaultInjectionConfig {
+                faults_path: faults_path.clone(),
+                reload_interval_ms: NETWORK_FAULTS_RELOAD_INTERVAL_MS,
+            });
+
+            config.save(config_path)?;
+            *validator.config_mut() = config;
+            validator.restart().await?;
+        }
+        Ok(())
+    }
+
     pub fn chain_id(&self) -> ChainId {
         self.chain_id
     }
@@ -1,17 +1,22 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::smoke_test_environment::new_local_swarm_with_aptos;
+use crate::{
+    smoke_test_environment::new_local_swarm_with_aptos, test_utils::create_and_fund_account,
+};
 use aptos::op::key::GenerateKey;
 use aptos_config::{
-    config::{DiscoveryMethod, Identity, NetworkConfig, NodeConfig, PeerSet, PersistableConfig},
+    config::{
+        DiscoveryMethod, Identity, LinkFaults, NetworkConfig, NetworkFaults, NodeConfig,
+        PeerLinkFaults, PeerSet, PersistableConfig,
+    },
     network_id::NetworkId,
 };
 use aptos_crypto::{x25519, x25519::PrivateKey};
 use aptos_operational_tool::{keys::EncodingType, test_helper::OperationalTool};
 use aptos_temppath::TempPath;
 use aptos_types::network_address::{NetworkAddress, Protocol};
-use forge::{FullNode, LocalNode, NodeExt, Swarm};
+use forge::{FullNode, LocalNode, NodeExt, Swarm, SwarmExt};
 use std::{
     collections::HashMap,
     path::Path,
@@ -194,6 +199,52 @@ async fn test_file_discovery() {
     );
 }
 
+#[tokio::test]
+async fn test_network_fault_injection() {
+    let mut swarm = new_local_swarm_with_aptos(4).await;
+    let validator_peer_ids: Vec<_> = swarm.validators().map(|v| v.peer_id()).collect();
+
+    // Slow down all the links, and make one of them lossy
+    let faults = NetworkFaults {
+        default_faults: LinkFaults {
+            latency_ms: 100,
+            jitter_ms: 50,
+            bandwidth_bytes_per_sec: Some(10 * 1024 * 1024),
+            ..LinkFaults::default()
+        },
+        link_faults: vec![PeerLinkFaults {
+            peer_a: validator_peer_ids[0],
+            peer_b: validator_peer_ids[1],
+            faults: LinkFaults {
+                latency_ms: 200,
+                stall_probability: 0.01,
+                stall_duration_ms: 500,
+                reset_probability: 0.001,
+                ..LinkFaults::default()
+            },
+        }],
+    };
+    swarm.set_network_faults(&faults).await.unwrap();
+
+    // The validators should keep committing transactions
+    swarm
+        .liveness_check(Instant::now() + Duration::from_secs(120))
+        .await
+        .unwrap();
+    create_and_fund_account(&mut swarm, 100).await;
+
+    // The faults can be lifted at runtime
+    swarm
+        .set_network_faults(&NetworkFaults::default())
+        .await
+        .unwrap();
+    swarm
+        .liveness_check(Instant::now() + Duration::from_secs(60))
+        .await
+        .unwrap();
+    create_and_fund_account(&mut swarm, 100).await;
+}
+
 /// Creates a discovery file with the given `PeerSet`
 fn create_discovery_file(peer_set: PeerSet) -> TempPath {
     let discovery_file = TempPath::new();
@@ -13,6 +13,7 @@ pub struct NewBlockEvent {
     proposer: AccountAddress,
     previous_block_votes: Vec<AccountAddress>,
     time_micro_seconds: u64,
+    failed_proposers: Vec<AccountAddress>,
 }
 
 impl NewBlockEvent {
@@ -28,6 +29,10 @@ impl NewBlockEvent {
         self.time_micro_seconds
     }
 
+    pub fn failed_proposers(&self) -> &[AccountAddress] {
+        &self.failed_proposers
+    }
+
     pub fn try_from_bytes(bytes: &[u8]) -> Result<Self> {
         bcs::from_bytes(bytes).map_err(Into::into)
     }
@@ -38,12 +43,14 @@ impl NewBlockEvent {
         proposer: AccountAddress,
         previous_block_votes: Vec<AccountAddress>,
         time_micro_seconds: u64,
+        failed_proposers: Vec<AccountAddress>,
     ) -> Self {
         Self {
             round,
             proposer,
             previous_block_votes,
             time_micro_seconds,
+            failed_proposers,
         }
     }
 }
@@ -34,6 +34,9 @@ pub struct BlockMetadata {
     // The vector has to be sorted to ensure consistent result among all nodes
     previous_block_votes: Vec<AccountAddress>,
     proposer: AccountAddress,
+    // The proposers elected for the rounds between the parent block and this one, that failed
+    // to get their block certified, by increasing round
+    failed_proposers: Vec<AccountAddress>,
 }
 
 impl BlockMetadata {
@@ -43,6 +46,7 @@ impl BlockMetadata {
         timestamp_usecs: u64,
         previous_block_votes: Vec<AccountAddress>,
         proposer: AccountAddress,
+        failed_proposers: Vec<AccountAddress>,
     ) -> Self {
         Self {
             id,
@@ -50,6 +54,7 @@ impl BlockMetadata {
             timestamp_usecs,
             previous_block_votes,
             proposer,
+            failed_proposers,
         }
     }
 
@@ -57,12 +62,21 @@ impl BlockMetadata {
         self.id
     }
 
-    pub fn into_inner(self) -> (u64, u64, Vec<AccountAddress>, AccountAddress) {
+    pub fn into_inner(
+        self,
+    ) -> (
+        u64,
+        u64,
+        Vec<AccountAddress>,
+        AccountAddress,
+        Vec<AccountAddress>,
+    ) {
         (
             self.round,
             self.timestamp_usecs,
             self.previous_block_votes.clone(),
             self.proposer,
+            self.failed_proposers.clone(),
         )
     }
 
@@ -78,6 +92,10 @@ impl BlockMetadata {
         &self.previous_block_votes
     }
 
+    pub fn failed_proposers(&self) -> &Vec<AccountAddress> {
+        &self.failed_proposers
+    }
+
     pub fn round(&self) -> u64 {
         self.round
     }
@@ -124,6 +142,7 @@ pub struct NewBlockEvent {
     proposer: AccountAddress,
     votes: Vec<AccountAddress>,
     timestamp: u64,
+    failed_proposers: Vec<AccountAddress>,
 }
 
 impl NewBlockEvent {
@@ -132,12 +151,14 @@ impl NewBlockEvent {
         proposer: AccountAddress,
         votes: Vec<AccountAddress>,
         timestamp: u64,
+        failed_proposers: Vec<AccountAddress>,
     ) -> Self {
         Self {
             round,
             proposer,
             votes,
             timestamp,
+            failed_proposers,
         }
     }
     pub fn round(&self) -> u64 {
@@ -151,4 +172,9 @@ impl NewBlockEvent {
     pub fn votes(&self) -> Vec<AccountAddress> {
         self.votes.clone()
     }
+
+    /// The proposers elected for the rounds that failed right before this block.
+    pub fn failed_proposers(&self) -> &[AccountAddress] {
+        &self.failed_proposers
+    }
 }
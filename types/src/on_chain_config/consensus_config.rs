@@ -10,6 +10,8 @@ use serde::{Deserialize, Serialize};
 pub enum OnChainConsensusConfig {
     V1(ConsensusConfigV1),
     V2(ConsensusConfigV2),
+    V3(ConsensusConfigV3),
+    V4(ConsensusConfigV4),
 }
 
 /// The public interface that exposes all values with safe fallback.
@@ -19,6 +21,8 @@ impl OnChainConsensusConfig {
         match &self {
             OnChainConsensusConfig::V1(config) => config.two_chain,
             OnChainConsensusConfig::V2(config) => config.two_chain,
+            OnChainConsensusConfig::V3(config) => config.two_chain,
+            OnChainConsensusConfig::V4(config) => config.two_chain,
         }
     }
 
@@ -26,6 +30,8 @@ impl OnChainConsensusConfig {
     pub fn leader_reputation_exclude_round(&self) -> u64 {
         match &self {
             OnChainConsensusConfig::V2(config) => config.exclude_round,
+            OnChainConsensusConfig::V3(config) => config.exclude_round,
+            OnChainConsensusConfig::V4(config) => config.exclude_round,
             // default value before onchain config
             _ => 4,
         }
@@ -35,6 +41,8 @@ impl OnChainConsensusConfig {
     pub fn decoupled_execution(&self) -> bool {
         match &self {
             OnChainConsensusConfig::V2(config) => config.decoupled_execution,
+            OnChainConsensusConfig::V3(config) => config.decoupled_execution,
+            OnChainConsensusConfig::V4(config) => config.decoupled_execution,
             _ => false,
         }
     }
@@ -48,9 +56,38 @@ impl OnChainConsensusConfig {
         }
         match &self {
             OnChainConsensusConfig::V2(config) => config.back_pressure_limit,
+            OnChainConsensusConfig::V3(config) => config.back_pressure_limit,
+            OnChainConsensusConfig::V4(config) => config.back_pressure_limit,
             _ => 10,
         }
     }
+
+    /// Disseminate transaction batches through the quorum store and let proposals carry
+    /// proofs of availability instead of the transactions.
+    pub fn quorum_store_enabled(&self) -> bool {
+        match &self {
+            OnChainConsensusConfig::V3(config) => config.quorum_store_enabled,
+            OnChainConsensusConfig::V4(config) => config.quorum_store_enabled,
+            _ => false,
+        }
+    }
+
+    /// The maximum number of failed proposers a block records, for the rounds between its parent
+    /// and itself.
+    pub fn max_failed_authors_to_store(&self) -> usize {
+        match &self {
+            OnChainConsensusConfig::V4(config) => config.max_failed_authors_to_store as usize,
+            _ => 10,
+        }
+    }
+
+    /// The leader reputation weights that override the ones of the local config.
+    pub fn leader_reputation_weights(&self) -> Option<LeaderReputationWeights> {
+        match &self {
+            OnChainConsensusConfig::V4(config) => Some(config.leader_reputation_weights),
+            _ => None,
+        }
+    }
 }
 
 /// This is used when on-chain config is not initialized.
@@ -79,6 +116,41 @@ pub struct ConsensusConfigV2 {
     pub exclude_round: u64,
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ConsensusConfigV3 {
+    pub two_chain: bool,
+    pub decoupled_execution: bool,
+    pub back_pressure_limit: u64,
+    pub exclude_round: u64,
+    pub quorum_store_enabled: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ConsensusConfigV4 {
+    pub two_chain: bool,
+    pub decoupled_execution: bool,
+    pub back_pressure_limit: u64,
+    pub exclude_round: u64,
+    pub quorum_store_enabled: bool,
+    pub leader_reputation_weights: LeaderReputationWeights,
+    pub max_failed_authors_to_store: u64,
+}
+
+/// The weights leader reputation elects the proposers with, relative to each other.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LeaderReputationWeights {
+    /// Weight of the validators that proposed or voted in the window.
+    pub active_weight: u64,
+    /// Weight of the validators that didn't.
+    pub inactive_weight: u64,
+    /// Weight of the validators that failed too many of the rounds they were elected for in the
+    /// window, takes precedence over the other two. Zero excludes them from the election.
+    pub failed_weight: u64,
+    /// Percentage of failed rounds, out of the rounds a validator was elected for, at which
+    /// the validator gets the failed weight.
+    pub failure_threshold_percent: u64,
+}
+
 impl OnChainConfig for OnChainConsensusConfig {
     const IDENTIFIER: &'static str = "ConsensusConfig";
 
@@ -552,6 +552,7 @@ fn test_transaction_list_with_proof() {
         0,
         vec![],
         AccountAddress::random(),
+        vec![],
     ))];
 
     // Create transaction list with proof
@@ -622,6 +623,7 @@ fn test_transaction_and_output_list_with_proof() {
         0,
         vec![],
         AccountAddress::random(),
+        vec![],
     ));
     let event = create_event();
     let transaction_output = TransactionOutput::new(